@@ -1,6 +1,10 @@
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, Fields};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Fields, GenericArgument, Lit, Meta, NestedMeta,
+    PathArguments, Type,
+};
 
 #[proc_macro_derive(StringWrapper)]
 pub fn string_wrapper_derive(input: TokenStream) -> TokenStream {
@@ -25,37 +29,36 @@ pub fn string_wrapper_derive(input: TokenStream) -> TokenStream {
             pub fn as_wrapper(&self) -> &#name {
                 self
             }
+
+            /// Returns the wrapped string.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
         }
 
         impl std::ops::Deref for #name {
-            type Target = String;
+            type Target = str;
 
             fn deref(&self) -> &Self::Target {
                 &self.0
             }
         }
 
-        impl std::ops::DerefMut for #name {
-            fn deref_mut(&mut self) -> &mut Self::Target {
-                &mut self.0
-            }
-        }
-
         impl AsRef<str> for #name {
             fn as_ref(&self) -> &str {
-                self.0.as_ref()
+                &self.0
             }
         }
 
         impl From<String> for #name {
             fn from(s: String) -> Self {
-                #name(s)
+                #name(s.into())
             }
         }
 
         impl From<&str> for #name {
             fn from(s: &str) -> Self {
-                #name(s.to_string())
+                #name(s.into())
             }
         }
 
@@ -85,7 +88,474 @@ pub fn string_wrapper_derive(input: TokenStream) -> TokenStream {
                 #name(self.0.clone())
             }
         }
+
+        impl crate::json::JsonValue for #name {
+            fn json_value(&self) -> Option<serde_json::Value> {
+                crate::json::JsonValue::json_value(&*self.0)
+            }
+        }
     };
 
     TokenStream::from(expanded)
 }
+
+/// Generates `validate_fields`, checking the constraints declared on the fields of a schema
+/// with `#[gtfs(...)]` attributes:
+/// - `required`: the field can never be empty.
+/// - `required_if = "<condition>"`: the field can not be empty when the condition holds.
+/// - `forbidden_if = "<condition>"`: the field must be empty when the condition holds.
+/// - `non_negative`: the field, when defined, can not be lower than its default value.
+///
+/// Conditions are boolean Rust expressions, evaluated with `self` in scope. A `reason = "..."`
+/// may follow a check, to be reported in its error instead of the default one.
+///
+/// An optional field is empty when `None`, and a string or identifier (a type named `String`
+/// or ending with `Id`) when it has no characters.
+#[proc_macro_derive(GtfsValidate, attributes(gtfs))]
+pub fn gtfs_validate_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match gtfs_validate(&input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(error) => TokenStream::from(error.to_compile_error()),
+    }
+}
+
+/// A constraint declared by a `#[gtfs(...)]` attribute.
+enum Check {
+    Required,
+    RequiredIf(TokenStream2, String),
+    ForbiddenIf(TokenStream2, String),
+    NonNegative,
+}
+
+fn gtfs_validate(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "GtfsValidate can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "GtfsValidate can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut checks = vec![];
+    for field in fields {
+        // Named fields always have an identifier.
+        let ident = field.ident.as_ref().unwrap();
+        let field_name = ident.to_string();
+        for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("gtfs")) {
+            let Some((check, reason)) = parse_check(attr)? else {
+                continue;
+            };
+            let is_empty = is_empty(ident, &field.ty);
+            let (condition, constructor, default_reason) = match check {
+                Check::Required => (
+                    is_empty,
+                    quote!(new_missing_value),
+                    "can never be empty".to_string(),
+                ),
+                Check::RequiredIf(condition, source) => (
+                    quote!({
+                        let condition: bool = #condition;
+                        condition && #is_empty
+                    }),
+                    quote!(new_missing_value),
+                    format!("required when {}", source),
+                ),
+                Check::ForbiddenIf(condition, source) => (
+                    quote!({
+                        let condition: bool = #condition;
+                        condition && !#is_empty
+                    }),
+                    quote!(new_forbidden_value),
+                    format!("forbidden when {}", source),
+                ),
+                Check::NonNegative => {
+                    let (ty, optional) = match option_inner(&field.ty) {
+                        Some(ty) => (ty, true),
+                        None => (&field.ty, false),
+                    };
+                    let condition = if optional {
+                        quote!(self.#ident.as_ref().is_some_and(|value| *value < zero))
+                    } else {
+                        quote!(self.#ident < zero)
+                    };
+                    (
+                        quote!({
+                            let zero: #ty = Default::default();
+                            #condition
+                        }),
+                        quote!(new_invalid_value),
+                        "cannot be negative".to_string(),
+                    )
+                }
+            };
+            let reason = reason.unwrap_or(default_reason);
+            checks.push(quote! {
+                if #condition {
+                    return Err(crate::error::SchemaValidationError::#constructor(
+                        #field_name.to_string(),
+                        Some(#reason.to_string()),
+                        crate::schemas::Schema::from(self.clone()),
+                    )
+                    .into());
+                }
+            });
+        }
+    }
+
+    Ok(quote! {
+        impl #name {
+            /// Checks the constraints declared with `#[gtfs(...)]` on the fields of the record.
+            pub(crate) fn validate_fields(&self) -> crate::error::Result<()> {
+                #(#checks)*
+                Ok(())
+            }
+        }
+    })
+}
+
+/// Parses a `#[gtfs(...)]` attribute into a check and its reason, if any.
+///
+/// Returns `None` for the attributes of [`GtfsTable`] declaring the columns of a field or its
+/// conversion to JSON.
+fn parse_check(attr: &syn::Attribute) -> syn::Result<Option<(Check, Option<String>)>> {
+    let list = match attr.parse_meta()? {
+        Meta::List(list) => list,
+        meta => return Err(syn::Error::new_spanned(meta, "expected #[gtfs(...)]")),
+    };
+
+    let mut check = None;
+    let mut reason = None;
+    for nested in &list.nested {
+        let NestedMeta::Meta(meta) = nested else {
+            return Err(syn::Error::new_spanned(nested, "unexpected literal"));
+        };
+        let key = meta.path().get_ident().map(|ident| ident.to_string());
+        let parsed = match (key.as_deref(), meta) {
+            (Some("required"), Meta::Path(_)) => Check::Required,
+            (Some("non_negative"), Meta::Path(_)) => Check::NonNegative,
+            (Some("columns" | "json_with"), Meta::NameValue(_)) => return Ok(None),
+            (Some("required_if" | "forbidden_if" | "reason"), Meta::NameValue(name_value)) => {
+                let Lit::Str(value) = &name_value.lit else {
+                    return Err(syn::Error::new_spanned(
+                        &name_value.lit,
+                        "expected a string",
+                    ));
+                };
+                match key.as_deref() {
+                    Some("required_if") => Check::RequiredIf(value.parse()?, value.value()),
+                    Some("forbidden_if") => Check::ForbiddenIf(value.parse()?, value.value()),
+                    _ => {
+                        reason = Some(value.value());
+                        continue;
+                    }
+                }
+            }
+            _ => return Err(syn::Error::new_spanned(meta, "unknown gtfs attribute")),
+        };
+        if check.replace(parsed).is_some() {
+            return Err(syn::Error::new_spanned(
+                meta,
+                "only one check can be declared per attribute",
+            ));
+        }
+    }
+    match check {
+        Some(check) => Ok(Some((check, reason))),
+        None => Err(syn::Error::new_spanned(attr, "missing check")),
+    }
+}
+
+/// Returns the expression telling whether the field is empty.
+fn is_empty(ident: &syn::Ident, ty: &Type) -> TokenStream2 {
+    match option_inner(ty) {
+        Some(inner) if is_string_like(inner) => {
+            quote!(self.#ident.as_ref().is_none_or(|value| value.is_empty()))
+        }
+        Some(_) => quote!(self.#ident.is_none()),
+        None => quote!(self.#ident.is_empty()),
+    }
+}
+
+/// Returns `T` for a field of type `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first()? {
+        GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
+/// Whether the type is a string or an identifier wrapping one.
+fn is_string_like(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    path.path.segments.last().is_some_and(|segment| {
+        let ident = segment.ident.to_string();
+        ident == "String" || ident.ends_with("Id")
+    })
+}
+
+/// Implements `GtfsTable` for the records of a file, declared with a
+/// `#[gtfs(file = "stops.txt", primary_key = "stop_id")]` attribute on the struct.
+///
+/// The primary key lists the fields identifying a record, separated by commas, and is omitted
+/// for files without one. The headers are the names of the fields not skipped by serde, in
+/// order, a field flattened into several columns declaring them with
+/// `#[gtfs(columns = "stop_lat, stop_lon")]`.
+///
+/// Also implements `JsonRecord`, converting each column with `JsonValue`, or `JsonColumns` for
+/// the fields with several columns. A field converted differently names its function with
+/// `#[gtfs(json_with = "...")]`.
+#[proc_macro_derive(GtfsTable, attributes(gtfs))]
+pub fn gtfs_table_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match gtfs_table(&input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(error) => TokenStream::from(error.to_compile_error()),
+    }
+}
+
+fn gtfs_table(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "GtfsTable can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "GtfsTable can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut file = None;
+    let mut primary_key = vec![];
+    for attr in input.attrs.iter().filter(|attr| attr.path.is_ident("gtfs")) {
+        let Meta::List(list) = attr.parse_meta()? else {
+            return Err(syn::Error::new_spanned(attr, "expected #[gtfs(...)]"));
+        };
+        for nested in &list.nested {
+            let NestedMeta::Meta(Meta::NameValue(name_value)) = nested else {
+                return Err(syn::Error::new_spanned(
+                    nested,
+                    "expected `key = \"value\"`",
+                ));
+            };
+            let Lit::Str(value) = &name_value.lit else {
+                return Err(syn::Error::new_spanned(
+                    &name_value.lit,
+                    "expected a string",
+                ));
+            };
+            if name_value.path.is_ident("file") {
+                file = Some(value.clone());
+            } else if name_value.path.is_ident("primary_key") {
+                for column in value.value().split(',') {
+                    let column = column.trim();
+                    let field = fields
+                        .iter()
+                        .find(|field| field.ident.as_ref().is_some_and(|ident| ident == column))
+                        .ok_or_else(|| {
+                            syn::Error::new_spanned(value, format!("unknown field `{}`", column))
+                        })?;
+                    primary_key.push(field);
+                }
+            } else {
+                return Err(syn::Error::new_spanned(
+                    &name_value.path,
+                    "unknown gtfs attribute",
+                ));
+            }
+        }
+    }
+    let Some(file) = file else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "missing #[gtfs(file = \"...\")] attribute",
+        ));
+    };
+    // The variant of `GtfsFile` is the name of the file in upper camel case.
+    let variant = file
+        .value()
+        .trim_end_matches(".txt")
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect::<String>();
+    let variant = syn::Ident::new(&variant, file.span());
+
+    let mut headers = vec![];
+    let mut json_fields = vec![];
+    for field in fields {
+        let ident = &field.ident;
+        let cfg_attrs: Vec<_> = field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("cfg"))
+            .collect();
+        let mut header = field.ident.as_ref().map(|ident| ident.to_string());
+        if let Some(columns) = field_columns(field)? {
+            json_fields.push(quote! {
+                #(#cfg_attrs)*
+                for (column, value) in [#(#columns),*]
+                    .into_iter()
+                    .zip(crate::json::JsonColumns::json_columns(&self.#ident))
+                {
+                    fields.insert(column.to_string(), value);
+                }
+            });
+            headers.extend(columns);
+            continue;
+        }
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("serde"))
+        {
+            let Ok(Meta::List(list)) = attr.parse_meta() else {
+                continue;
+            };
+            for nested in &list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => header = None,
+                    NestedMeta::Meta(Meta::NameValue(name_value))
+                        if name_value.path.is_ident("rename") =>
+                    {
+                        if let Lit::Str(value) = &name_value.lit {
+                            header = header.map(|_| value.value());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if let Some(header) = &header {
+            let json_value = match field_json_with(field)? {
+                Some(path) => quote!(#path(&self.#ident)),
+                None => quote!(crate::json::JsonValue::json_value(&self.#ident)),
+            };
+            json_fields.push(quote! {
+                #(#cfg_attrs)*
+                if let Some(value) = #json_value {
+                    fields.insert(#header.to_string(), value);
+                }
+            });
+        }
+        headers.extend(header);
+    }
+
+    let key_columns: Vec<String> = primary_key
+        .iter()
+        .filter_map(|field| field.ident.as_ref().map(|ident| ident.to_string()))
+        .collect();
+    let key_idents: Vec<_> = primary_key.iter().map(|field| &field.ident).collect();
+    let key_types: Vec<_> = primary_key.iter().map(|field| &field.ty).collect();
+    let (key_type, key) = match primary_key.len() {
+        0 => (quote!(()), quote!()),
+        1 => (quote!(#(#key_types)*), quote!(#(self.#key_idents.clone())*)),
+        _ => (
+            quote!((#(#key_types),*)),
+            quote!((#(self.#key_idents.clone()),*)),
+        ),
+    };
+
+    Ok(quote! {
+        impl crate::schemas::GtfsTable for #name {
+            type Key = #key_type;
+
+            const FILE: crate::GtfsFile = crate::GtfsFile::#variant;
+            const HEADERS: &'static [&'static str] = &[#(#headers),*];
+            const PRIMARY_KEY: &'static [&'static str] = &[#(#key_columns),*];
+
+            fn key(&self) -> Self::Key {
+                #key
+            }
+        }
+
+        impl crate::json::JsonRecord for #name {
+            fn json_fields(&self) -> serde_json::Map<String, serde_json::Value> {
+                let mut fields = serde_json::Map::new();
+                #(#json_fields)*
+                fields
+            }
+        }
+    })
+}
+
+/// Returns the function declared with `#[gtfs(json_with = "...")]` on a field, if any.
+fn field_json_with(field: &syn::Field) -> syn::Result<Option<syn::Path>> {
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("gtfs")) {
+        let Meta::List(list) = attr.parse_meta()? else {
+            continue;
+        };
+        for nested in &list.nested {
+            if let NestedMeta::Meta(Meta::NameValue(name_value)) = nested {
+                if let (true, Lit::Str(value)) =
+                    (name_value.path.is_ident("json_with"), &name_value.lit)
+                {
+                    return Ok(Some(value.parse()?));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the columns declared with `#[gtfs(columns = "...")]` on a field, if any.
+fn field_columns(field: &syn::Field) -> syn::Result<Option<Vec<String>>> {
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("gtfs")) {
+        let Meta::List(list) = attr.parse_meta()? else {
+            continue;
+        };
+        for nested in &list.nested {
+            if let NestedMeta::Meta(Meta::NameValue(name_value)) = nested {
+                if let (true, Lit::Str(value)) =
+                    (name_value.path.is_ident("columns"), &name_value.lit)
+                {
+                    let columns = value.value();
+                    return Ok(Some(
+                        columns
+                            .split(',')
+                            .map(|column| column.trim().to_string())
+                            .collect(),
+                    ));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
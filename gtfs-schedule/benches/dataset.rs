@@ -0,0 +1,131 @@
+//! Throughput of parsing, validating, indexing and querying datasets.
+//!
+//! Run with `cargo bench`, adding `--features csv-core` to compare the CSV backends. The small
+//! feed is a fixture of the tests, the medium and large ones are generated by
+//! `synth::generate_feed` and written to a temporary directory first.
+
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use gtfs_schedule::schemas::{StopId, TripId};
+use gtfs_schedule::{synth, Dataset, PlanOptions};
+#[cfg(feature = "csv-core")]
+use gtfs_schedule::{CsvBackend, ParseOptions};
+
+/// A feed to benchmark, with the directory holding its files.
+struct Feed {
+    name: &'static str,
+    dir: PathBuf,
+    dataset: Dataset,
+}
+
+impl Feed {
+    /// Number of records of the feed.
+    fn records(&self) -> u64 {
+        self.dataset.memory_stats().records as u64
+    }
+}
+
+/// Returns the small, medium and large feeds.
+fn feeds() -> Vec<Feed> {
+    let small_dir = Path::new("tests/_data/good_feed").to_path_buf();
+    let mut feeds = vec![Feed {
+        name: "small",
+        dataset: Dataset::from_csv(&small_dir).unwrap(),
+        dir: small_dir,
+    }];
+    for (name, size) in [("medium", 10), ("large", 100)] {
+        let dir = std::env::temp_dir().join(format!("gtfs-schedule-bench-{}", name));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dataset = synth::generate_feed(size);
+        dataset.to_csv(&dir).unwrap();
+        feeds.push(Feed { name, dir, dataset });
+    }
+    feeds
+}
+
+fn bench_parse(c: &mut Criterion, feeds: &[Feed]) {
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    for feed in feeds {
+        group.throughput(Throughput::Elements(feed.records()));
+        group.bench_with_input(BenchmarkId::new("csv", feed.name), feed, |b, feed| {
+            b.iter(|| Dataset::from_csv(&feed.dir).unwrap())
+        });
+        #[cfg(feature = "csv-core")]
+        {
+            let options = ParseOptions::default().csv_backend(CsvBackend::CsvCore);
+            group.bench_with_input(BenchmarkId::new("csv-core", feed.name), feed, |b, feed| {
+                b.iter(|| Dataset::from_csv_with_options(&feed.dir, &options).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_validate(c: &mut Criterion, feeds: &[Feed]) {
+    let mut group = c.benchmark_group("validate");
+    group.sample_size(10);
+    for feed in feeds {
+        group.throughput(Throughput::Elements(feed.records()));
+        group.bench_with_input(BenchmarkId::from_parameter(feed.name), feed, |b, feed| {
+            b.iter(|| feed.dataset.validate().unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_index(c: &mut Criterion, feeds: &[Feed]) {
+    let mut group = c.benchmark_group("compile_timetable");
+    group.sample_size(10);
+    for feed in feeds {
+        let date = feed.dataset.calendar.iter().next().unwrap().start_date;
+        group.throughput(Throughput::Elements(feed.dataset.stop_times.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(feed.name), feed, |b, feed| {
+            b.iter(|| feed.dataset.compile_timetable(date..=date))
+        });
+    }
+    group.finish();
+}
+
+fn bench_query(c: &mut Criterion, feeds: &[Feed]) {
+    let mut group = c.benchmark_group("query");
+    // Synthetic feeds only, as their stops and trips are known.
+    for feed in &feeds[1..] {
+        let trip_id = TripId::from("R0D0T0");
+        group.bench_with_input(
+            BenchmarkId::new("stop_times_for_trip", feed.name),
+            feed,
+            |b, feed| b.iter(|| feed.dataset.iter_stop_times_for_trip(&trip_id).count()),
+        );
+
+        let departure = NaiveDate::from_ymd_opt(2024, 6, 5)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+        let (from, to) = (StopId::from("R0S0"), StopId::from("R1S19"));
+        group.bench_with_input(BenchmarkId::new("plan", feed.name), feed, |b, feed| {
+            b.iter(|| {
+                feed.dataset
+                    .plan(&from, &to, departure, &PlanOptions::default())
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    let feeds = feeds();
+    bench_parse(c, &feeds);
+    bench_validate(c, &feeds);
+    bench_index(c, &feeds);
+    bench_query(c, &feeds);
+    for feed in &feeds[1..] {
+        std::fs::remove_dir_all(&feed.dir).unwrap();
+    }
+}
+
+criterion_group!(dataset, benches);
+criterion_main!(dataset);
@@ -0,0 +1,73 @@
+//! Turns fuzzer input into records of the specification.
+
+use gtfs_schedule::schemas::*;
+use gtfs_schedule::error::Result;
+use gtfs_schedule::{parse_record, GtfsFile};
+
+/// Splits `data` into a file of the specification, selected by the first byte, and the CSV
+/// contents of that file.
+pub fn split_file(data: &[u8]) -> Option<(GtfsFile, &[u8])> {
+    let (selector, contents) = data.split_first()?;
+    let file = GtfsFile::ALL[usize::from(*selector) % GtfsFile::ALL.len()];
+    Some((file, contents))
+}
+
+/// Parses the records of `contents` as records of `file`, stopping at the first invalid CSV
+/// line.
+pub fn parse_records(file: GtfsFile, contents: &[u8]) -> Vec<Result<Schema>> {
+    let mut reader = csv::Reader::from_reader(contents);
+    let Ok(header) = reader.headers().cloned() else {
+        return Vec::new();
+    };
+    reader
+        .records()
+        .map_while(|record| record.ok())
+        .map(|record| parse_schema(file, &header, &record))
+        .collect()
+}
+
+/// Parses `record` as a record of `file`.
+fn parse_schema(
+    file: GtfsFile,
+    header: &csv::StringRecord,
+    record: &csv::StringRecord,
+) -> Result<Schema> {
+    macro_rules! parse {
+        ($($file:ident => $schema:ident,)*) => {
+            match file {
+                $(GtfsFile::$file => parse_record::<$schema>(header, record).map(Schema::from),)*
+            }
+        };
+    }
+    parse!(
+        Agency => Agency,
+        Areas => Area,
+        Attributions => Attribution,
+        BookingRules => BookingRule,
+        Calendar => Calendar,
+        CalendarDates => CalendarDate,
+        FareAttributes => FareAttribute,
+        FareLegRules => FareLegRule,
+        FareMedia => FareMedia,
+        FareProducts => FareProduct,
+        FareRules => FareRule,
+        FareTransfers => FareTransferRule,
+        FeedInfo => FeedInfo,
+        Frequencies => Frequency,
+        Levels => Level,
+        LocationGroups => LocationGroup,
+        LocationGroupsStops => LocationGroupStop,
+        Networks => Network,
+        Pathways => Pathway,
+        Routes => Route,
+        RoutesNetworks => RouteNetwork,
+        Shapes => Shape,
+        Stops => Stop,
+        StopsAreas => StopArea,
+        StopTimes => StopTime,
+        Timeframes => Timeframe,
+        Transfers => Transfer,
+        Translations => Translation,
+        Trips => Trip,
+    )
+}
@@ -0,0 +1,13 @@
+//! Parses the records of a single file, which must never panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+mod common;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((file, contents)) = common::split_file(data) {
+        let _ = common::parse_records(file, contents);
+    }
+});
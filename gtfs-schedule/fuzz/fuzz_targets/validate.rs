@@ -0,0 +1,21 @@
+//! Builds a dataset from the records of several files and validates it, which must never panic.
+//!
+//! The input holds one file after the other, separated by NUL bytes.
+
+#![no_main]
+
+use gtfs_schedule::Dataset;
+use libfuzzer_sys::fuzz_target;
+
+mod common;
+
+fuzz_target!(|data: &[u8]| {
+    let mut dataset = Dataset::default();
+    let mut editor = dataset.begin_edit();
+    for (file, contents) in data.split(|byte| *byte == 0).filter_map(common::split_file) {
+        for record in common::parse_records(file, contents).into_iter().flatten() {
+            editor.upsert(record);
+        }
+    }
+    let _ = dataset.validate();
+});
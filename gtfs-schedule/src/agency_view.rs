@@ -0,0 +1,217 @@
+//! Views of the part of a dataset operated by a single agency.
+//!
+//! The main types are:
+//! - [`DatasetView`]: The records of a dataset belonging to an agency.
+
+use std::collections::HashSet;
+
+use dashmap::mapref::multiple::RefMulti;
+
+use crate::schemas::{
+    Agency, AgencyId, Attribution, FareAttribute, FareId, FareRule, Route, RouteId, Stop, StopId,
+    StopTime, Trip, TripId,
+};
+use crate::Dataset;
+
+/// The records of a dataset belonging to an agency, see [`Dataset::agency_view`].
+///
+/// The view borrows the dataset and only holds the ids of the records it exposes. Its items are
+/// guards holding read locks on the maps of the dataset, as those of
+/// [`Dataset::iter_trips_for_route`].
+#[derive(Clone)]
+pub struct DatasetView<'a> {
+    dataset: &'a Dataset,
+    agency_id: AgencyId,
+    route_ids: HashSet<RouteId>,
+    trip_ids: HashSet<TripId>,
+    stop_ids: HashSet<StopId>,
+    fare_ids: HashSet<FareId>,
+}
+
+impl Dataset {
+    /// Returns a view of the records belonging to an agency, `None` if there is no such agency.
+    ///
+    /// The view holds:
+    /// - the routes of the agency, including routes without `agency_id` when the dataset has a
+    ///   single agency,
+    /// - their trips and stop times,
+    /// - the stops those stop times serve, along with their parent stations,
+    /// - the fares of fare_attributes.txt of the agency, and those without `agency_id` applying
+    ///   to one of its routes in fare_rules.txt,
+    /// - the attributions of the agency, or of one of its routes or trips.
+    pub fn agency_view(&self, agency_id: &AgencyId) -> Option<DatasetView<'_>> {
+        if !self
+            .agencies
+            .iter()
+            .any(|agency| agency.agency_id.as_ref() == Some(agency_id))
+        {
+            return None;
+        }
+        let single_agency = self.agencies.len() == 1;
+        let is_agency = |id: Option<&AgencyId>| id.map_or(single_agency, |id| id == agency_id);
+
+        let route_ids: HashSet<RouteId> = self
+            .routes
+            .iter()
+            .filter(|route| is_agency(route.agency_id.as_ref()))
+            .map(|route| route.route_id.clone())
+            .collect();
+        let trip_ids: HashSet<TripId> = self
+            .trips
+            .iter()
+            .filter(|trip| route_ids.contains(&trip.route_id))
+            .map(|trip| trip.trip_id.clone())
+            .collect();
+
+        let mut pending: Vec<StopId> = self
+            .stop_times
+            .iter()
+            .filter(|stop_time| trip_ids.contains(&stop_time.trip_id))
+            .filter_map(|stop_time| stop_time.stop_id.clone())
+            .collect();
+        let mut stop_ids = HashSet::new();
+        while let Some(stop_id) = pending.pop() {
+            if stop_ids.contains(&stop_id) {
+                continue;
+            }
+            pending.extend(
+                self.stops
+                    .get(&stop_id)
+                    .and_then(|stop| stop.parent_station.clone()),
+            );
+            stop_ids.insert(stop_id);
+        }
+
+        let mut fare_ids: HashSet<FareId> = self
+            .fare_attributes
+            .iter()
+            .filter(|fare| fare.agency_id.as_ref().is_some_and(|id| id == agency_id))
+            .map(|fare| fare.fare_id.clone())
+            .collect();
+        fare_ids.extend(
+            self.fare_rules
+                .iter()
+                .filter(|rule| {
+                    rule.route_id
+                        .as_ref()
+                        .is_some_and(|route_id| route_ids.contains(route_id))
+                })
+                .filter(|rule| {
+                    self.fare_attributes
+                        .get(&rule.fare_id)
+                        .is_some_and(|fare| fare.agency_id.is_none())
+                })
+                .map(|rule| rule.fare_id.clone()),
+        );
+
+        Some(DatasetView {
+            dataset: self,
+            agency_id: agency_id.clone(),
+            route_ids,
+            trip_ids,
+            stop_ids,
+            fare_ids,
+        })
+    }
+}
+
+impl<'a> DatasetView<'a> {
+    /// The dataset the view is taken from.
+    pub fn dataset(&self) -> &'a Dataset {
+        self.dataset
+    }
+
+    /// The agency of the view.
+    pub fn agency(&self) -> Option<&'a Agency> {
+        self.dataset
+            .agencies
+            .iter()
+            .find(|agency| agency.agency_id.as_ref() == Some(&self.agency_id))
+    }
+
+    /// Whether a route belongs to the agency.
+    pub fn contains_route(&self, route_id: &RouteId) -> bool {
+        self.route_ids.contains(route_id)
+    }
+
+    /// Whether a trip belongs to the agency.
+    pub fn contains_trip(&self, trip_id: &TripId) -> bool {
+        self.trip_ids.contains(trip_id)
+    }
+
+    /// Whether a stop is served by the agency, or is the parent station of such a stop.
+    pub fn contains_stop(&self, stop_id: &StopId) -> bool {
+        self.stop_ids.contains(stop_id)
+    }
+
+    /// Iterates over the routes of the agency, in no particular order.
+    pub fn routes(&self) -> impl Iterator<Item = RefMulti<'a, RouteId, Route>> + '_ {
+        self.dataset
+            .routes
+            .iter()
+            .filter(|route| self.route_ids.contains(&route.route_id))
+    }
+
+    /// Iterates over the trips of the agency, in no particular order.
+    pub fn trips(&self) -> impl Iterator<Item = RefMulti<'a, TripId, Trip>> + '_ {
+        self.dataset
+            .trips
+            .iter()
+            .filter(|trip| self.trip_ids.contains(&trip.trip_id))
+    }
+
+    /// Iterates over the stop times of the trips of the agency, in no particular order.
+    pub fn stop_times(&self) -> impl Iterator<Item = RefMulti<'a, (TripId, u32), StopTime>> + '_ {
+        self.dataset
+            .stop_times
+            .iter()
+            .filter(|stop_time| self.trip_ids.contains(&stop_time.trip_id))
+    }
+
+    /// Iterates over the stops served by the agency and their parent stations, in no particular
+    /// order.
+    pub fn stops(&self) -> impl Iterator<Item = RefMulti<'a, StopId, Stop>> + '_ {
+        self.dataset
+            .stops
+            .iter()
+            .filter(|stop| self.stop_ids.contains(&stop.stop_id))
+    }
+
+    /// Iterates over the fares of the agency, in no particular order.
+    pub fn fare_attributes(
+        &self,
+    ) -> impl Iterator<Item = RefMulti<'a, FareId, FareAttribute>> + '_ {
+        self.dataset
+            .fare_attributes
+            .iter()
+            .filter(|fare| self.fare_ids.contains(&fare.fare_id))
+    }
+
+    /// Iterates over the rules of the fares of the agency.
+    ///
+    /// Rules restricted to a route only belong to the view when the route does.
+    pub fn fare_rules(&self) -> impl Iterator<Item = &'a FareRule> + '_ {
+        self.dataset.fare_rules.iter().filter(|rule| {
+            self.fare_ids.contains(&rule.fare_id)
+                && rule
+                    .route_id
+                    .as_ref()
+                    .is_none_or(|route_id| self.route_ids.contains(route_id))
+        })
+    }
+
+    /// Iterates over the attributions of the agency, and of its routes and trips.
+    pub fn attributions(&self) -> impl Iterator<Item = &'a Attribution> + '_ {
+        self.dataset.attributions.iter().filter(|attribution| {
+            attribution.agency_id.as_ref() == Some(&self.agency_id)
+                || attribution
+                    .route_id
+                    .as_ref()
+                    .is_some_and(|route_id| self.route_ids.contains(route_id))
+                || attribution
+                    .trip_id
+                    .as_ref()
+                    .is_some_and(|trip_id| self.trip_ids.contains(trip_id))
+        })
+    }
+}
@@ -0,0 +1,145 @@
+//! Resolution of the fare areas and zones of stops.
+//!
+//! The main types are:
+//! - [`AreaIndex`]: The areas and zones of the stops of a dataset, for repeated lookups.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::schemas::{AreaId, LocationType, StopId, ZoneId};
+use crate::Dataset;
+
+/// The areas and zones of the stops of a dataset, see [`Dataset::area_index`].
+///
+/// The index is a snapshot: it does not reflect the changes made to the dataset after it was
+/// built.
+#[derive(Debug, Clone, Default)]
+pub struct AreaIndex {
+    areas_by_stop: HashMap<StopId, Vec<AreaId>>,
+    stops_by_area: HashMap<AreaId, Vec<StopId>>,
+    zones: HashMap<StopId, ZoneId>,
+}
+
+impl AreaIndex {
+    /// Returns the areas a stop belongs to, see [`Dataset::areas_for_stop`].
+    pub fn areas_for_stop(&self, stop_id: &StopId) -> &[AreaId] {
+        self.areas_by_stop.get(stop_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the stops of an area, see [`Dataset::stops_in_area`].
+    pub fn stops_in_area(&self, area_id: &AreaId) -> &[StopId] {
+        self.stops_by_area.get(area_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the fare zone of a stop, see [`Dataset::zone_for_stop`].
+    pub fn zone_for_stop(&self, stop_id: &StopId) -> Option<&ZoneId> {
+        self.zones.get(stop_id)
+    }
+}
+
+impl Dataset {
+    /// Builds an index of the areas and zones of the stops of the dataset, for repeated lookups.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn area_index(&self) -> AreaIndex {
+        let mut children: HashMap<StopId, Vec<StopId>> = HashMap::new();
+        for stop in self.stops.iter() {
+            if let Some(parent_station) = &stop.parent_station {
+                children
+                    .entry(parent_station.clone())
+                    .or_default()
+                    .push(stop.stop_id.clone());
+            }
+        }
+
+        let mut index = AreaIndex::default();
+        for stop_area in &self.stops_areas {
+            // A station stands for all of its locations.
+            let mut pending = vec![stop_area.stop_id.clone()];
+            let mut visited = HashSet::new();
+            while let Some(stop_id) = pending.pop() {
+                if !visited.insert(stop_id.clone()) {
+                    continue;
+                }
+                pending.extend(children.get(&stop_id).into_iter().flatten().cloned());
+
+                let areas = index.areas_by_stop.entry(stop_id.clone()).or_default();
+                if !areas.contains(&stop_area.area_id) {
+                    areas.push(stop_area.area_id.clone());
+                }
+                let stops = index
+                    .stops_by_area
+                    .entry(stop_area.area_id.clone())
+                    .or_default();
+                if !stops.contains(&stop_id) {
+                    stops.push(stop_id);
+                }
+            }
+        }
+        for areas in index.areas_by_stop.values_mut() {
+            areas.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        }
+        for stops in index.stops_by_area.values_mut() {
+            stops.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        }
+
+        for stop in self.stops.iter() {
+            if let Some(zone_id) = self.zone_for_stop(&stop.stop_id) {
+                index.zones.insert(stop.stop_id.clone(), zone_id);
+            }
+        }
+
+        index
+    }
+
+    /// Returns the areas of areas.txt a stop belongs to, sorted by id.
+    ///
+    /// A stop belongs to the areas listing it in stops_areas.txt, and to those listing its
+    /// parent station, as a station stands for all of its locations. Use [`Dataset::area_index`]
+    /// for repeated lookups.
+    pub fn areas_for_stop(&self, stop_id: &StopId) -> Vec<AreaId> {
+        let mut ancestors = vec![stop_id.clone()];
+        while let Some(parent_station) = ancestors
+            .last()
+            .and_then(|stop_id| self.stops.get(stop_id))
+            .and_then(|stop| stop.parent_station.clone())
+        {
+            if ancestors.contains(&parent_station) {
+                break;
+            }
+            ancestors.push(parent_station);
+        }
+
+        let mut areas: Vec<AreaId> = vec![];
+        for stop_area in &self.stops_areas {
+            if ancestors.contains(&stop_area.stop_id) && !areas.contains(&stop_area.area_id) {
+                areas.push(stop_area.area_id.clone());
+            }
+        }
+        areas.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        areas
+    }
+
+    /// Returns the stops belonging to an area, sorted by id, see [`Dataset::areas_for_stop`].
+    pub fn stops_in_area(&self, area_id: &AreaId) -> Vec<StopId> {
+        self.area_index().stops_in_area(area_id).to_vec()
+    }
+
+    /// Returns the fare zone of a stop.
+    ///
+    /// A boarding area without [`crate::schemas::Stop::zone_id`] is in the zone of its
+    /// platform.
+    pub fn zone_for_stop(&self, stop_id: &StopId) -> Option<ZoneId> {
+        let stop = self.stops.get(stop_id)?;
+        stop.zone_id.clone().or_else(|| {
+            let parent_station = stop.parent_station.as_ref()?;
+            let parent = self.stops.get(parent_station)?;
+            if matches!(
+                parent.location_type,
+                None | Some(LocationType::StopOrPlatform)
+            ) {
+                parent.zone_id.clone()
+            } else {
+                None
+            }
+        })
+    }
+}
@@ -0,0 +1,470 @@
+//! Analysis of vehicle blocks.
+//!
+//! The main types are:
+//! - [`VehicleRequirement`]: The number of vehicles needed to run the service of a day.
+//! - [`BlockConflict`]: Trips of a block that a vehicle can not run one after the other.
+//! - [`Rotation`]: The trips run by the vehicle of a block during a service day.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+
+use crate::schemas::{
+    BlockId, CalendarServiceId, Frequency, NaiveServiceTime, RouteId, StopId, TripId,
+};
+use crate::Dataset;
+
+/// The number of vehicles needed to run the service of a day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VehicleRequirement {
+    /// The service day.
+    pub date: NaiveDate,
+    /// The maximum number of vehicles in service at the same time, across all routes.
+    pub peak_vehicles: usize,
+    /// The maximum number of vehicles in service at the same time, for each route.
+    pub by_route: HashMap<RouteId, usize>,
+}
+
+/// Trips of a block running at the same time on a service day, see
+/// [`Dataset::block_conflicts`].
+///
+/// The vehicle of the block can not run both, which usually comes from an export bug.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockConflict {
+    /// Identifies the block.
+    pub block_id: BlockId,
+    /// The trip starting first, or the first by [`TripId`] if both start at the same time.
+    pub trip_id: TripId,
+    /// The trip starting while [`BlockConflict::trip_id`] is still running.
+    pub other_trip_id: TripId,
+    /// The first service day both trips run on.
+    pub date: NaiveDate,
+    /// When both trips start running at the same time.
+    pub overlap_start: NaiveServiceTime,
+    /// When either trip stops running.
+    pub overlap_end: NaiveServiceTime,
+}
+
+/// The trips run by the vehicle of a block during a service day, see
+/// [`Dataset::vehicle_rotations`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rotation {
+    /// Identifies the block.
+    pub block_id: BlockId,
+    /// The service day.
+    pub date: NaiveDate,
+    /// When the vehicle leaves the first stop of its first trip.
+    pub pull_out: NaiveServiceTime,
+    /// When the vehicle reaches the last stop of its last trip.
+    pub pull_in: NaiveServiceTime,
+    /// The trips of the block, in the order they are run.
+    pub trips: Vec<RotationTrip>,
+    /// What the vehicle does between two trips, the `i`-th gap following the `i`-th trip.
+    pub gaps: Vec<RotationGap>,
+}
+
+/// A trip of a [`Rotation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotationTrip {
+    /// Identifies the trip.
+    pub trip_id: TripId,
+    /// The route of the trip.
+    pub route_id: RouteId,
+    /// When the trip leaves its first stop.
+    pub start: NaiveServiceTime,
+    /// When the trip reaches its last stop.
+    pub end: NaiveServiceTime,
+    /// The first stop of the trip.
+    pub first_stop_id: Option<StopId>,
+    /// The last stop of the trip.
+    pub last_stop_id: Option<StopId>,
+}
+
+/// The time between two trips of a [`Rotation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotationGap {
+    /// When the previous trip ends.
+    pub start: NaiveServiceTime,
+    /// When the next trip starts, or [`RotationGap::start`] if both trips overlap, see
+    /// [`Dataset::block_conflicts`].
+    pub end: NaiveServiceTime,
+    /// Where the previous trip ends.
+    pub from_stop_id: Option<StopId>,
+    /// Where the next trip starts.
+    pub to_stop_id: Option<StopId>,
+}
+
+impl RotationGap {
+    /// Time between both trips.
+    pub fn duration(&self) -> Duration {
+        self.end.duration_since(self.start).unwrap_or_default()
+    }
+
+    /// Whether the vehicle drives without passengers to another stop, rather than waiting at
+    /// the stop where the previous trip ended.
+    pub fn is_deadhead(&self) -> bool {
+        self.from_stop_id != self.to_stop_id
+    }
+}
+
+impl Rotation {
+    /// Time between pull-out and pull-in.
+    pub fn duration(&self) -> Duration {
+        self.pull_in
+            .duration_since(self.pull_out)
+            .unwrap_or_default()
+    }
+
+    /// Time spent running trips.
+    pub fn revenue_duration(&self) -> Duration {
+        self.trips
+            .iter()
+            .map(|trip| trip.end.duration_since(trip.start).unwrap_or_default())
+            .sum()
+    }
+
+    /// Time spent driving between the end of a trip and the start of the next one, elsewhere.
+    pub fn deadhead_duration(&self) -> Duration {
+        self.gaps
+            .iter()
+            .filter(|gap| gap.is_deadhead())
+            .map(RotationGap::duration)
+            .sum()
+    }
+}
+
+/// A trip run on a given service day.
+struct TripRun {
+    route_id: RouteId,
+    block_id: Option<BlockId>,
+    /// Seconds since the start of the service day at which the trip leaves its first stop.
+    start: u32,
+    /// Seconds since the start of the service day at which the trip reaches its last stop.
+    end: u32,
+}
+
+impl Dataset {
+    /// Estimates the number of vehicles needed to run the service of `date`.
+    ///
+    /// Trips sharing a [`crate::schemas::Trip::block_id`] are run by the same vehicle, which is
+    /// in service from the start of the first trip of the block to the end of its last trip.
+    /// Other trips are linked greedily: a vehicle becomes available for another trip as soon as
+    /// it completes its current one. Deadheads and layovers are not modeled, the figures are thus
+    /// a lower bound.
+    ///
+    /// Trips defined in frequencies.txt are expanded into one run per headway. Per-route figures
+    /// only consider the trips of the route, including for blocks spanning several routes.
+    pub fn vehicles_required(&self, date: NaiveDate) -> VehicleRequirement {
+        let runs = self.trip_runs(date);
+
+        let peak = peak_vehicles(&runs);
+        let mut routes: HashMap<RouteId, Vec<&TripRun>> = HashMap::new();
+        for run in &runs {
+            routes.entry(run.route_id.clone()).or_default().push(run);
+        }
+        let by_route = routes
+            .into_iter()
+            .map(|(route_id, runs)| (route_id, peak_vehicles(runs)))
+            .collect();
+
+        VehicleRequirement {
+            date,
+            peak_vehicles: peak,
+            by_route,
+        }
+    }
+
+    /// Finds the trips sharing a [`crate::schemas::Trip::block_id`] that run at the same time
+    /// on a service day both run on.
+    ///
+    /// A trip runs from its first to its last time, so a trip may start when the previous trip
+    /// of the block ends. Trips defined in frequencies.txt are left out, as are trips without
+    /// times.
+    ///
+    /// Conflicts are sorted by block, then by trip.
+    pub fn block_conflicts(&self) -> Vec<BlockConflict> {
+        let spans = self.trip_spans();
+        let frequency_based: HashSet<TripId> = self
+            .frequencies
+            .iter()
+            .map(|frequency| frequency.trip_id.clone())
+            .collect();
+
+        let mut blocks: HashMap<BlockId, Vec<(u32, u32, TripId, CalendarServiceId)>> =
+            HashMap::new();
+        for trip in self.trips.iter() {
+            let (Some(block_id), Some(&(start, end))) = (&trip.block_id, spans.get(&trip.trip_id))
+            else {
+                continue;
+            };
+            if !frequency_based.contains(&trip.trip_id) {
+                blocks.entry(block_id.clone()).or_default().push((
+                    start,
+                    end,
+                    trip.trip_id.clone(),
+                    trip.service_id.clone(),
+                ));
+            }
+        }
+
+        let mut service_dates: HashMap<CalendarServiceId, Vec<NaiveDate>> = HashMap::new();
+        let mut conflicts = vec![];
+        for (block_id, mut trips) in blocks {
+            trips.sort_unstable_by(|a, b| (a.0, a.2.as_str()).cmp(&(b.0, b.2.as_str())));
+            for (index, (_, end, trip_id, service_id)) in trips.iter().enumerate() {
+                for (other_start, other_end, other_trip_id, other_service_id) in &trips[index + 1..]
+                {
+                    if other_start >= end {
+                        break;
+                    }
+                    for service_id in [service_id, other_service_id] {
+                        if !service_dates.contains_key(service_id) {
+                            let dates = self.service_dates(service_id);
+                            service_dates.insert(service_id.clone(), dates);
+                        }
+                    }
+                    let other_dates = &service_dates[other_service_id];
+                    let Some(date) = service_dates[service_id]
+                        .iter()
+                        .find(|date| other_dates.binary_search(date).is_ok())
+                    else {
+                        continue;
+                    };
+                    conflicts.push(BlockConflict {
+                        block_id: block_id.clone(),
+                        trip_id: trip_id.clone(),
+                        other_trip_id: other_trip_id.clone(),
+                        date: *date,
+                        overlap_start: NaiveServiceTime::from_secs(*other_start),
+                        overlap_end: NaiveServiceTime::from_secs(*end.min(other_end)),
+                    });
+                }
+            }
+        }
+
+        conflicts.sort_unstable_by(|a, b| {
+            (
+                a.block_id.as_str(),
+                a.trip_id.as_str(),
+                a.other_trip_id.as_str(),
+            )
+                .cmp(&(
+                    b.block_id.as_str(),
+                    b.trip_id.as_str(),
+                    b.other_trip_id.as_str(),
+                ))
+        });
+        conflicts
+    }
+
+    /// Chains the trips of each block running on `date` into the rotation of its vehicle.
+    ///
+    /// Trips are run in the order they start, from their first to their last time. The gaps
+    /// between trips are layovers when the next trip starts where the previous one ended, and
+    /// deadheads otherwise, whose actual driving time is unknown. Trips defined in
+    /// frequencies.txt are left out, as are trips without block or times.
+    ///
+    /// Rotations are sorted by block.
+    pub fn vehicle_rotations(&self, date: NaiveDate) -> Vec<Rotation> {
+        let spans = self.trip_spans();
+        let frequency_based: HashSet<TripId> = self
+            .frequencies
+            .iter()
+            .map(|frequency| frequency.trip_id.clone())
+            .collect();
+
+        let mut blocks: HashMap<BlockId, Vec<(TripId, RouteId, u32, u32)>> = HashMap::new();
+        for trip in self.trips.iter() {
+            let (Some(block_id), Some(&(start, end))) = (&trip.block_id, spans.get(&trip.trip_id))
+            else {
+                continue;
+            };
+            if !frequency_based.contains(&trip.trip_id)
+                && self.is_service_active(&trip.service_id, date)
+            {
+                blocks.entry(block_id.clone()).or_default().push((
+                    trip.trip_id.clone(),
+                    trip.route_id.clone(),
+                    start,
+                    end,
+                ));
+            }
+        }
+
+        let trip_ids: HashSet<&TripId> = blocks
+            .values()
+            .flatten()
+            .map(|(trip_id, _, _, _)| trip_id)
+            .collect();
+        // The first and last stops of each trip, with their stop sequence.
+        let mut terminals: HashMap<TripId, [(u32, StopId); 2]> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            let Some(stop_id) = &stop_time.stop_id else {
+                continue;
+            };
+            if !trip_ids.contains(&stop_time.trip_id) {
+                continue;
+            }
+            let call = (stop_time.stop_sequence, stop_id.clone());
+            let [first, last] = terminals
+                .entry(stop_time.trip_id.clone())
+                .or_insert_with(|| [call.clone(), call.clone()]);
+            if call.0 < first.0 {
+                *first = call.clone();
+            }
+            if call.0 > last.0 {
+                *last = call;
+            }
+        }
+
+        let mut rotations: Vec<Rotation> = blocks
+            .into_iter()
+            .map(|(block_id, mut trips)| {
+                trips.sort_unstable_by(|a, b| (a.2, a.0.as_str()).cmp(&(b.2, b.0.as_str())));
+                let trips: Vec<RotationTrip> = trips
+                    .into_iter()
+                    .map(|(trip_id, route_id, start, end)| {
+                        let terminals = terminals.get(&trip_id);
+                        RotationTrip {
+                            first_stop_id: terminals.map(|[first, _]| first.1.clone()),
+                            last_stop_id: terminals.map(|[_, last]| last.1.clone()),
+                            trip_id,
+                            route_id,
+                            start: NaiveServiceTime::from_secs(start),
+                            end: NaiveServiceTime::from_secs(end),
+                        }
+                    })
+                    .collect();
+                let gaps = trips
+                    .windows(2)
+                    .map(|pair| RotationGap {
+                        start: pair[0].end,
+                        end: pair[1].start.max(pair[0].end),
+                        from_stop_id: pair[0].last_stop_id.clone(),
+                        to_stop_id: pair[1].first_stop_id.clone(),
+                    })
+                    .collect();
+
+                Rotation {
+                    block_id,
+                    date,
+                    pull_out: trips[0].start,
+                    pull_in: trips
+                        .iter()
+                        .map(|trip| trip.end)
+                        .max()
+                        .unwrap_or(trips[0].end),
+                    trips,
+                    gaps,
+                }
+            })
+            .collect();
+        rotations.sort_unstable_by(|a, b| a.block_id.as_str().cmp(b.block_id.as_str()));
+        rotations
+    }
+
+    /// Returns the earliest and latest times of each trip, in seconds since the start of the
+    /// service day.
+    fn trip_spans(&self) -> HashMap<TripId, (u32, u32)> {
+        let mut spans: HashMap<TripId, (u32, u32)> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            let times = [stop_time.arrival_time, stop_time.departure_time];
+            for secs in times.iter().flatten().map(|time| time.as_secs()) {
+                let span = spans
+                    .entry(stop_time.trip_id.clone())
+                    .or_insert((secs, secs));
+                span.0 = span.0.min(secs);
+                span.1 = span.1.max(secs);
+            }
+        }
+        spans
+    }
+
+    /// Lists the trips run on `date`, expanding frequency-based trips.
+    fn trip_runs(&self, date: NaiveDate) -> Vec<TripRun> {
+        let spans = self.trip_spans();
+
+        let mut frequencies: HashMap<TripId, Vec<Frequency>> = HashMap::new();
+        for frequency in self.frequencies.iter() {
+            frequencies
+                .entry(frequency.trip_id.clone())
+                .or_default()
+                .push(frequency.value().clone());
+        }
+
+        let mut runs = vec![];
+        for trip in self.trips.iter() {
+            if !self.is_service_active(&trip.service_id, date) {
+                continue;
+            }
+            let Some(&(start, end)) = spans.get(&trip.trip_id) else {
+                continue;
+            };
+
+            match frequencies.get(&trip.trip_id) {
+                Some(frequencies) => {
+                    for frequency in frequencies {
+                        let headway = frequency.headway_secs.as_secs() as u32;
+                        if headway == 0 {
+                            continue;
+                        }
+                        let mut departure = frequency.start_time.as_secs();
+                        while departure < frequency.end_time.as_secs() {
+                            runs.push(TripRun {
+                                route_id: trip.route_id.clone(),
+                                block_id: None,
+                                start: departure,
+                                end: departure + (end - start),
+                            });
+                            departure += headway;
+                        }
+                    }
+                }
+                None => runs.push(TripRun {
+                    route_id: trip.route_id.clone(),
+                    block_id: trip.block_id.clone(),
+                    start,
+                    end,
+                }),
+            }
+        }
+
+        runs
+    }
+}
+
+/// Maximum number of vehicles in service at the same time to operate `runs`.
+///
+/// The runs of a block are merged into a single span, other runs each get their own span.
+fn peak_vehicles<'a>(runs: impl IntoIterator<Item = &'a TripRun>) -> usize {
+    let mut blocks: HashMap<&BlockId, (u32, u32)> = HashMap::new();
+    let mut spans = vec![];
+    for run in runs {
+        match &run.block_id {
+            Some(block_id) => {
+                let span = blocks.entry(block_id).or_insert((run.start, run.end));
+                span.0 = span.0.min(run.start);
+                span.1 = span.1.max(run.end);
+            }
+            None => spans.push((run.start, run.end)),
+        }
+    }
+    spans.extend(blocks.into_values());
+
+    // A vehicle completing a run can start another one at the same time.
+    let mut events: Vec<(u32, i32)> = spans
+        .into_iter()
+        .flat_map(|(start, end)| [(start, 1), (end, -1)])
+        .collect();
+    events.sort_unstable();
+
+    let mut current = 0;
+    let mut peak = 0;
+    for (_, change) in events {
+        current += change;
+        peak = peak.max(current);
+    }
+
+    peak as usize
+}
@@ -0,0 +1,136 @@
+//! Zero-copy reading of records, borrowing their text from the line being read.
+//!
+//! Parsing a dataset allocates the text of most cells, which dominates the time taken by
+//! analyses that only scan a large file once, e.g. counting the stop times of each trip.
+//! The records read here borrow their identifiers and texts instead, and are only valid until
+//! the next record is read.
+//!
+//! The main types are:
+//! - [`BorrowedReader`]: Reads the records of a file one at a time, reusing a single buffer.
+//! - [`StopTimeRef`]: A stop time borrowing its identifiers and texts.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::File;
+use std::io::Read;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::{ErrorContext, ParseError, ParseErrorKind, Result};
+use crate::schemas::{
+    ContinuousDropOff, ContinuousPickup, DropOffType, NaiveServiceTime, PickupType, Timepoint,
+};
+
+/// Reads the records of a file one at a time, borrowing their text, see [`StopTimeRef`].
+///
+/// Any type deserializing `&str` fields can be read, so callers can declare the columns they
+/// need as a struct of their own.
+///
+/// ```no_run
+/// use std::collections::HashMap;
+///
+/// use gtfs_schedule::{BorrowedReader, StopTimeRef};
+///
+/// let mut reader = BorrowedReader::from_path("stop_times.txt".as_ref()).unwrap();
+/// let mut stop_times_per_trip: HashMap<String, usize> = HashMap::new();
+/// while let Some(stop_time) = reader.read::<StopTimeRef>() {
+///     let stop_time = stop_time.unwrap();
+///     match stop_times_per_trip.get_mut(stop_time.trip_id) {
+///         Some(count) => *count += 1,
+///         None => {
+///             stop_times_per_trip.insert(stop_time.trip_id.to_string(), 1);
+///         }
+///     }
+/// }
+/// ```
+pub struct BorrowedReader<R: Read> {
+    reader: csv::Reader<R>,
+    header: csv::StringRecord,
+    record: csv::StringRecord,
+}
+
+impl<R: Read> BorrowedReader<R> {
+    /// Reads records from `reader`, starting with the header.
+    ///
+    /// Column names are matched regardless of surrounding whitespace and byte order marks.
+    pub fn from_reader(reader: R) -> Result<Self> {
+        let mut reader = csv::Reader::from_reader(reader);
+        let header = reader
+            .headers()
+            .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?
+            .iter()
+            .map(|column| column.trim_start_matches('\u{feff}').trim())
+            .collect();
+
+        Ok(Self {
+            reader,
+            header,
+            record: csv::StringRecord::new(),
+        })
+    }
+
+    /// The columns of the file, in order.
+    pub fn header(&self) -> &csv::StringRecord {
+        &self.header
+    }
+
+    /// Reads the next record, or returns `None` once every record was read.
+    ///
+    /// The record borrows from the reader, so it must be dropped before the next one is read.
+    pub fn read<'r, T: Deserialize<'r>>(&'r mut self) -> Option<Result<T>> {
+        match self.reader.read_record(&mut self.record) {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(e) => return Some(Err(ParseError::from(ParseErrorKind::from(e)).into())),
+        }
+        let record = &self.record;
+        Some(record.deserialize(Some(&self.header)).map_err(|e| {
+            let context = match record.position() {
+                Some(position) => {
+                    format!("Failed to deserialize record at position: {:?}", position)
+                }
+                None => "Failed to deserialize record".to_string(),
+            };
+            ParseError::from(ParseErrorKind::from(e))
+                .with_context(ErrorContext(context))
+                .into()
+        }))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl BorrowedReader<File> {
+    /// Reads records from the file at `path`.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let file = File::open(path).map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+        Self::from_reader(file)
+    }
+}
+
+/// A stop time borrowing its identifiers and texts, see [`crate::schemas::StopTime`] for the
+/// meaning of each field.
+///
+/// Unlike [`crate::schemas::StopTime`], the record is not validated and no default value is
+/// filled in.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StopTimeRef<'a> {
+    pub trip_id: &'a str,
+    pub arrival_time: Option<NaiveServiceTime>,
+    pub departure_time: Option<NaiveServiceTime>,
+    pub stop_id: Option<&'a str>,
+    pub location_group_id: Option<&'a str>,
+    pub location_id: Option<&'a str>,
+    pub stop_sequence: u32,
+    pub stop_headsign: Option<&'a str>,
+    pub start_pickup_drop_off_window: Option<NaiveServiceTime>,
+    pub end_pickup_drop_off_window: Option<NaiveServiceTime>,
+    pub pickup_type: Option<PickupType>,
+    pub drop_off_type: Option<DropOffType>,
+    pub continuous_pickup: Option<ContinuousPickup>,
+    pub continuous_drop_off: Option<ContinuousDropOff>,
+    pub shape_dist_traveled: Option<f32>,
+    pub timepoint: Option<Timepoint>,
+    pub pickup_booking_rule_id: Option<&'a str>,
+    pub drop_off_booking_rule_id: Option<&'a str>,
+}
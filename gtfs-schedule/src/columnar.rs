@@ -0,0 +1,244 @@
+//! Export of datasets as Apache Arrow record batches and Parquet files.
+//!
+//! The main types are:
+//! - [`RecordBatches`]: An iterator over the records of a table as Arrow record batches.
+
+use std::collections::BTreeSet;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::File;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, BooleanBuilder, Date32Builder, Float64Builder, Int64Builder, StringBuilder,
+};
+use arrow::datatypes::{DataType, Field, Schema as ArrowSchema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use chrono::NaiveDate;
+#[cfg(not(target_arch = "wasm32"))]
+use parquet::arrow::ArrowWriter;
+use serde_json::{Map, Value};
+
+#[cfg(feature = "extensions")]
+use crate::schemas::Extensions;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::{ParseError, ParseErrorKind, Result};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::json::table_name;
+use crate::json::DATE_COLUMNS;
+use crate::writer::{field_value, shortest_float};
+use crate::{Dataset, GtfsFile};
+
+/// Number of records in each row group of the Parquet files written by [`Dataset::to_parquet`].
+#[cfg(not(target_arch = "wasm32"))]
+const PARQUET_BATCH_SIZE: usize = 65_536;
+
+/// An iterator over the records of a table as Arrow record batches, see
+/// [`Dataset::record_batches`].
+///
+/// It implements [`RecordBatchReader`], so it can be handed over directly to the libraries
+/// reading Arrow data.
+pub struct RecordBatches {
+    schema: SchemaRef,
+    rows: std::vec::IntoIter<Map<String, Value>>,
+    batch_size: usize,
+}
+
+impl Iterator for RecordBatches {
+    type Item = std::result::Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rows: Vec<Map<String, Value>> = self.rows.by_ref().take(self.batch_size).collect();
+        if rows.is_empty() {
+            return None;
+        }
+        Some(record_batch(&self.schema, &rows))
+    }
+}
+
+impl RecordBatchReader for RecordBatches {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl Dataset {
+    /// Returns the records of a table as Arrow record batches of at most `batch_size` rows.
+    ///
+    /// Only the columns holding at least one value are exported, in the order of
+    /// [`GtfsFile::columns`], followed by the columns that are not part of the specification when
+    /// the `extensions` feature is enabled. All columns are nullable, and their type depends on
+    /// the values of the table:
+    /// - Dates are `Date32` and booleans are `Boolean`.
+    /// - Integers and enumerations are `Int64`, decimals are `Float64`.
+    /// - Everything else is `Utf8`, service times being written as `HH:MM:SS`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is 0.
+    pub fn record_batches(&self, file: GtfsFile, batch_size: usize) -> RecordBatches {
+        assert!(batch_size > 0, "batch_size must be greater than 0");
+
+        let rows: Vec<Map<String, Value>> = self
+            .iter_table(file)
+            .map(|record| {
+                let row: Map<String, Value> = match serde_json::to_value(&record) {
+                    Ok(Value::Object(fields)) => fields
+                        .into_iter()
+                        .filter(|(_, value)| field_value(value).is_some())
+                        .collect(),
+                    _ => Map::new(),
+                };
+                #[cfg(feature = "extensions")]
+                let row = {
+                    let mut row = row;
+                    for (column, value) in record.extensions() {
+                        row.entry(column.clone())
+                            .or_insert_with(|| Value::String(value.clone()));
+                    }
+                    row
+                };
+                row
+            })
+            .collect();
+
+        let extension_columns: BTreeSet<&String> = rows
+            .iter()
+            .flat_map(|row| row.keys())
+            .filter(|column| !file.columns().contains(&column.as_str()))
+            .collect();
+        let fields: Vec<Field> = file
+            .columns()
+            .iter()
+            .copied()
+            .filter(|column| rows.iter().any(|row| row.contains_key(*column)))
+            .chain(extension_columns.into_iter().map(|column| column.as_str()))
+            .map(|column| Field::new(column, column_type(column, &rows), true))
+            .collect();
+
+        RecordBatches {
+            schema: Arc::new(ArrowSchema::new(fields)),
+            rows: rows.into_iter(),
+            batch_size,
+        }
+    }
+
+    /// Writes the dataset as Parquet files into `dir`, which must exist.
+    ///
+    /// A file named after the table (e.g. `stop_times.parquet`) is written for each table with
+    /// at least one record, with the columns described in [`Dataset::record_batches`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_parquet(&self, dir: &Path) -> Result<()> {
+        for file in GtfsFile::ALL {
+            let batches = self.record_batches(*file, PARQUET_BATCH_SIZE);
+            if batches.schema.fields().is_empty() {
+                continue;
+            }
+
+            let output = File::create(dir.join(format!("{}.parquet", table_name(*file))))
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+            let mut writer = ArrowWriter::try_new(output, batches.schema(), None)
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+            for batch in batches {
+                let batch = batch.map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+                writer
+                    .write(&batch)
+                    .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+            }
+            writer
+                .close()
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Picks the Arrow type of a column from the values it holds.
+fn column_type(column: &str, rows: &[Map<String, Value>]) -> DataType {
+    let mut values = rows.iter().filter_map(|row| row.get(column)).peekable();
+    if values.peek().is_none() {
+        return DataType::Utf8;
+    }
+
+    let values: Vec<&Value> = values.collect();
+    if DATE_COLUMNS.contains(&column) && values.iter().all(|value| date(value).is_some()) {
+        DataType::Date32
+    } else if values.iter().all(|value| value.is_boolean()) {
+        DataType::Boolean
+    } else if values.iter().all(|value| value.is_i64()) {
+        DataType::Int64
+    } else if values.iter().all(|value| value.is_number()) {
+        DataType::Float64
+    } else {
+        DataType::Utf8
+    }
+}
+
+/// Parses a date written as `YYYYMMDD`.
+fn date(value: &Value) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.as_str()?, "%Y%m%d").ok()
+}
+
+/// Builds a record batch from rows whose columns have the types of `schema`.
+fn record_batch(
+    schema: &SchemaRef,
+    rows: &[Map<String, Value>],
+) -> std::result::Result<RecordBatch, ArrowError> {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap_or_default();
+
+    let columns: Vec<ArrayRef> = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let values = rows.iter().map(|row| row.get(field.name()));
+            let array: ArrayRef = match field.data_type() {
+                DataType::Date32 => {
+                    let mut builder = Date32Builder::with_capacity(rows.len());
+                    for value in values {
+                        builder.append_option(
+                            value
+                                .and_then(date)
+                                .map(|date| (date - epoch).num_days() as i32),
+                        );
+                    }
+                    Arc::new(builder.finish())
+                }
+                DataType::Boolean => {
+                    let mut builder = BooleanBuilder::with_capacity(rows.len());
+                    for value in values {
+                        builder.append_option(value.and_then(Value::as_bool));
+                    }
+                    Arc::new(builder.finish())
+                }
+                DataType::Int64 => {
+                    let mut builder = Int64Builder::with_capacity(rows.len());
+                    for value in values {
+                        builder.append_option(value.and_then(Value::as_i64));
+                    }
+                    Arc::new(builder.finish())
+                }
+                DataType::Float64 => {
+                    let mut builder = Float64Builder::with_capacity(rows.len());
+                    for value in values {
+                        builder.append_option(value.and_then(Value::as_f64).map(shortest_float));
+                    }
+                    Arc::new(builder.finish())
+                }
+                _ => {
+                    let mut builder = StringBuilder::new();
+                    for value in values {
+                        builder.append_option(value.and_then(field_value));
+                    }
+                    Arc::new(builder.finish())
+                }
+            };
+            array
+        })
+        .collect();
+
+    RecordBatch::try_new(schema.clone(), columns)
+}
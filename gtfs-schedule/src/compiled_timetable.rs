@@ -0,0 +1,378 @@
+//! Compilation of the schedule of a dataset into the arrays used by journey planners.
+//!
+//! The main types are:
+//! - [`CompiledTimetable`]: The trips running over a range of dates, as connections for the
+//!   Connection Scan Algorithm (CSA) and as routes for RAPTOR.
+//! - [`Connection`]: A vehicle going from a stop to the next one.
+//! - [`TimetableRoute`]: Trips serving the same stops in the same order, without overtaking.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::{Range, RangeInclusive};
+
+use chrono::NaiveDate;
+
+use crate::schemas::{
+    CalendarServiceId, DropOffType, Frequency, PickupType, RouteId, StopId, StopTime, TripId,
+};
+use crate::Dataset;
+
+/// Number of seconds in a day.
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+
+/// The trips running over a range of dates, see [`Dataset::compile_timetable`].
+///
+/// Stops and trips are referred to by their index in [`CompiledTimetable::stops`] and
+/// [`CompiledTimetable::trips`]. Times are in seconds since the start of the service day of
+/// [`CompiledTimetable::start_date`], so that trips of different days can be compared.
+#[derive(Debug, Clone, Default)]
+pub struct CompiledTimetable {
+    /// The first date of the timetable, from which times are counted.
+    pub start_date: NaiveDate,
+    /// Every stop of the dataset, sorted by id.
+    pub stops: Vec<StopId>,
+    /// The trips running over the dates of the timetable.
+    pub trips: Vec<TripRun>,
+    /// Every connection of the trips, sorted by departure time, then by arrival time.
+    pub connections: Vec<Connection>,
+    /// The routes of the trips, for RAPTOR.
+    pub routes: Vec<TimetableRoute>,
+    /// The stops of the routes, see [`TimetableRoute::stops`].
+    pub route_stops: Vec<u32>,
+    /// The trips of the routes, see [`TimetableRoute::trips`].
+    pub route_trips: Vec<u32>,
+    /// The stop times of the routes, see [`CompiledTimetable::route_stop_time`].
+    pub route_stop_times: Vec<TimetableStopTime>,
+    /// The routes serving each stop, by stop index.
+    pub routes_by_stop: Vec<Vec<u32>>,
+    stop_indices: HashMap<StopId, u32>,
+}
+
+/// A trip running on a date, see [`CompiledTimetable::trips`].
+///
+/// Trips defined in frequencies.txt run several times a day, once per departure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TripRun {
+    /// Identifies the trip.
+    pub trip_id: TripId,
+    /// The service day of the run.
+    pub date: NaiveDate,
+    /// When the run leaves its first stop, see [`CompiledTimetable`] for the unit.
+    pub departure_time: u32,
+}
+
+/// A vehicle going from a stop to the next one, see [`CompiledTimetable::connections`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Connection {
+    /// Index of the stop the vehicle leaves.
+    pub departure_stop: u32,
+    /// Index of the stop the vehicle reaches.
+    pub arrival_stop: u32,
+    /// When the vehicle leaves the departure stop.
+    pub departure_time: u32,
+    /// When the vehicle reaches the arrival stop.
+    pub arrival_time: u32,
+    /// Index of the trip run.
+    pub trip: u32,
+    /// Whether passengers can board at the departure stop.
+    pub pickup: bool,
+    /// Whether passengers can alight at the arrival stop.
+    pub drop_off: bool,
+}
+
+/// Trips of a route serving the same stops in the same order, none of them overtaking another.
+///
+/// This is the route of RAPTOR: its trips are sorted by departure, and stay in that order at
+/// every stop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimetableRoute {
+    /// Identifies the route of the trips.
+    pub route_id: RouteId,
+    /// Range of [`CompiledTimetable::route_stops`] holding the stops served, in order.
+    pub stops: Range<usize>,
+    /// Range of [`CompiledTimetable::route_trips`] holding the trips, sorted by departure.
+    pub trips: Range<usize>,
+    /// Index of the first stop time of the route in [`CompiledTimetable::route_stop_times`].
+    pub stop_times: usize,
+}
+
+/// When a trip of a [`TimetableRoute`] serves a stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimetableStopTime {
+    /// When the vehicle arrives at the stop.
+    pub arrival_time: u32,
+    /// When the vehicle leaves the stop.
+    pub departure_time: u32,
+}
+
+impl CompiledTimetable {
+    /// Returns the index of a stop, `None` if the dataset has no such stop.
+    pub fn stop_index(&self, stop_id: &StopId) -> Option<u32> {
+        self.stop_indices.get(stop_id).copied()
+    }
+
+    /// Returns when the `trip`-th trip of a route serves its `stop`-th stop.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `trip` or `stop` is out of the range of the route.
+    pub fn route_stop_time(
+        &self,
+        route: &TimetableRoute,
+        trip: usize,
+        stop: usize,
+    ) -> TimetableStopTime {
+        assert!(trip < route.trips.len() && stop < route.stops.len());
+        self.route_stop_times[route.stop_times + trip * route.stops.len() + stop]
+    }
+}
+
+/// A trip run being compiled, with the stops and times it serves.
+struct CompiledRun {
+    route_id: RouteId,
+    trip: u32,
+    stops: Vec<u32>,
+    stop_times: Vec<TimetableStopTime>,
+}
+
+impl Dataset {
+    /// Compiles the trips running on `dates` into connections and routes, see
+    /// [`CompiledTimetable`].
+    ///
+    /// Trips defined in frequencies.txt are expanded into one run per departure. Stop times
+    /// without a stop (flexible services) or without a time are left out, so
+    /// [`Dataset::interpolate_all_stop_times`] should be called first for datasets relying on
+    /// interpolated times. A trip run of a service day ending after midnight keeps counting from
+    /// its own day, e.g. `25:00:00` on the first day is `90000`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(dates = ?dates)))]
+    pub fn compile_timetable(&self, dates: RangeInclusive<NaiveDate>) -> CompiledTimetable {
+        let mut timetable = CompiledTimetable {
+            start_date: *dates.start(),
+            ..CompiledTimetable::default()
+        };
+        let mut stop_ids: Vec<StopId> =
+            self.stops.iter().map(|stop| stop.stop_id.clone()).collect();
+        stop_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        for (index, stop_id) in stop_ids.iter().enumerate() {
+            timetable.stop_indices.insert(stop_id.clone(), index as u32);
+        }
+        timetable.stops = stop_ids;
+
+        let mut stop_times: HashMap<TripId, Vec<StopTime>> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            if stop_time.stop_id.is_some()
+                && (stop_time.arrival_time.is_some() || stop_time.departure_time.is_some())
+            {
+                stop_times
+                    .entry(stop_time.trip_id.clone())
+                    .or_default()
+                    .push(stop_time.value().clone());
+            }
+        }
+        let mut frequencies: HashMap<TripId, Vec<Frequency>> = HashMap::new();
+        for frequency in self.frequencies.iter() {
+            frequencies
+                .entry(frequency.trip_id.clone())
+                .or_default()
+                .push(frequency.value().clone());
+        }
+        let mut trips: Vec<(TripId, RouteId, CalendarServiceId)> = self
+            .trips
+            .iter()
+            .map(|trip| {
+                (
+                    trip.trip_id.clone(),
+                    trip.route_id.clone(),
+                    trip.service_id.clone(),
+                )
+            })
+            .collect();
+        trips.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+
+        let mut runs = vec![];
+        let mut active_services: HashMap<(CalendarServiceId, NaiveDate), bool> = HashMap::new();
+        for (day, date) in dates
+            .start()
+            .iter_days()
+            .take_while(|date| date <= dates.end())
+            .enumerate()
+        {
+            let day_start = day as u32 * SECONDS_PER_DAY;
+            for (trip_id, route_id, service_id) in &trips {
+                let active = *active_services
+                    .entry((service_id.clone(), date))
+                    .or_insert_with(|| self.is_service_active(service_id, date));
+                let Some(trip_stop_times) = stop_times.get_mut(trip_id) else {
+                    continue;
+                };
+                if !active || trip_stop_times.len() < 2 {
+                    continue;
+                }
+                trip_stop_times.sort_by_key(|stop_time| stop_time.stop_sequence);
+
+                // Times of the trip as scheduled in stop_times.txt, then its departures.
+                let times: Vec<TimetableStopTime> = trip_stop_times
+                    .iter()
+                    .map(|stop_time| {
+                        let arrival = stop_time.arrival_time.or(stop_time.departure_time);
+                        let departure = stop_time.departure_time.or(stop_time.arrival_time);
+                        TimetableStopTime {
+                            arrival_time: arrival.map_or(0, |time| time.as_secs()),
+                            departure_time: departure.map_or(0, |time| time.as_secs()),
+                        }
+                    })
+                    .collect();
+                let first_departure = times[0].departure_time;
+                let starts =
+                    trip_starts(frequencies.get(trip_id).map(Vec::as_slice), first_departure);
+
+                for start in starts {
+                    // Times are counted from the first departure, which may come after some of
+                    // them, e.g. after the arrival at the first stop.
+                    let shift = |time: u32| {
+                        let time = i64::from(day_start + start) + i64::from(time)
+                            - i64::from(first_departure);
+                        u32::try_from(time).ok()
+                    };
+                    // Runs starting before the first day, which only frequencies can lead to,
+                    // are left out.
+                    let Some(run_times) = times
+                        .iter()
+                        .map(|time| {
+                            Some(TimetableStopTime {
+                                arrival_time: shift(time.arrival_time)?,
+                                departure_time: shift(time.departure_time)?,
+                            })
+                        })
+                        .collect::<Option<Vec<_>>>()
+                    else {
+                        continue;
+                    };
+                    let trip = runs.len() as u32;
+                    timetable.trips.push(TripRun {
+                        trip_id: trip_id.clone(),
+                        date,
+                        departure_time: day_start + start,
+                    });
+                    let run = CompiledRun {
+                        route_id: route_id.clone(),
+                        trip,
+                        stops: trip_stop_times
+                            .iter()
+                            .filter_map(|stop_time| {
+                                timetable.stop_index(stop_time.stop_id.as_ref()?)
+                            })
+                            .collect(),
+                        stop_times: run_times,
+                    };
+                    // Stop times of stops missing from stops.txt are left out.
+                    if run.stops.len() != run.stop_times.len() {
+                        timetable.trips.pop();
+                        continue;
+                    }
+
+                    for (index, pair) in trip_stop_times.windows(2).enumerate() {
+                        timetable.connections.push(Connection {
+                            departure_stop: run.stops[index],
+                            arrival_stop: run.stops[index + 1],
+                            departure_time: run.stop_times[index].departure_time,
+                            arrival_time: run.stop_times[index + 1].arrival_time,
+                            trip,
+                            pickup: pair[0].pickup_type != Some(PickupType::NoPickupAvailable),
+                            drop_off: pair[1].drop_off_type
+                                != Some(DropOffType::NoDropOffAvailable),
+                        });
+                    }
+                    runs.push(run);
+                }
+            }
+        }
+        timetable.connections.sort_by_key(|connection| {
+            (
+                connection.departure_time,
+                connection.arrival_time,
+                connection.trip,
+            )
+        });
+
+        timetable.compile_routes(runs);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            trips = timetable.trips.len(),
+            connections = timetable.connections.len(),
+            routes = timetable.routes.len(),
+            "compiled timetable"
+        );
+        timetable
+    }
+}
+
+impl CompiledTimetable {
+    /// Groups trip runs into routes, see [`TimetableRoute`].
+    fn compile_routes(&mut self, runs: Vec<CompiledRun>) {
+        let mut patterns: BTreeMap<(String, Vec<u32>), Vec<CompiledRun>> = BTreeMap::new();
+        for run in runs {
+            patterns
+                .entry((run.route_id.to_string(), run.stops.clone()))
+                .or_default()
+                .push(run);
+        }
+
+        self.routes_by_stop = vec![vec![]; self.stops.len()];
+        for ((_, stops), mut pattern_runs) in patterns {
+            pattern_runs.sort_by_key(|run| (run.stop_times[0].departure_time, run.trip));
+
+            // Split the trips of the pattern so that none overtakes another in a route.
+            let mut routes: Vec<Vec<CompiledRun>> = vec![];
+            for run in pattern_runs {
+                let route = routes.iter_mut().find(|route| {
+                    route.last().is_some_and(|last| {
+                        last.stop_times.iter().zip(&run.stop_times).all(|(a, b)| {
+                            a.arrival_time <= b.arrival_time && a.departure_time <= b.departure_time
+                        })
+                    })
+                });
+                match route {
+                    Some(route) => route.push(run),
+                    None => routes.push(vec![run]),
+                }
+            }
+
+            for route_runs in routes {
+                let route_index = self.routes.len() as u32;
+                let route = TimetableRoute {
+                    route_id: route_runs[0].route_id.clone(),
+                    stops: self.route_stops.len()..self.route_stops.len() + stops.len(),
+                    trips: self.route_trips.len()..self.route_trips.len() + route_runs.len(),
+                    stop_times: self.route_stop_times.len(),
+                };
+                self.route_stops.extend(&stops);
+                for run in route_runs {
+                    self.route_trips.push(run.trip);
+                    self.route_stop_times.extend(run.stop_times);
+                }
+                for stop in &stops {
+                    let routes = &mut self.routes_by_stop[*stop as usize];
+                    if routes.last() != Some(&route_index) {
+                        routes.push(route_index);
+                    }
+                }
+                self.routes.push(route);
+            }
+        }
+    }
+}
+
+/// Returns when the runs of a trip leave its first stop, in seconds since the start of the service
+/// day.
+///
+/// A trip defined in frequencies.txt runs at each of the [`Frequency::departures`] of its
+/// frequencies. Other trips run once, at `first_departure`.
+pub(crate) fn trip_starts(frequencies: Option<&[Frequency]>, first_departure: u32) -> Vec<u32> {
+    match frequencies {
+        Some(frequencies) => frequencies
+            .iter()
+            .flat_map(|frequency| frequency.departures().map(|time| time.as_secs()))
+            .collect(),
+        None => vec![first_departure],
+    }
+}
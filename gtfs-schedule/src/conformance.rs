@@ -0,0 +1,373 @@
+//! Grading of the completeness of a dataset, beyond its validity.
+//!
+//! The main types are:
+//! - [`ConformanceReport`]: The grades of a dataset, as published by feed registries.
+//! - [`ConformanceCategory`]: An aspect of a dataset that is graded.
+//! - [`CategoryScore`]: The grade of a dataset in a category, with the findings explaining it.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::schemas::{LocationType, StopId, TripId, WheelchairAccessible, WheelchairBoarding};
+use crate::Dataset;
+
+/// The grades of a dataset in every category, see [`Dataset::conformance_report`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConformanceReport {
+    /// The grade of each category, in the order of [`ConformanceCategory`].
+    pub categories: Vec<CategoryScore>,
+}
+
+/// An aspect of a dataset that is graded, see [`ConformanceReport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConformanceCategory {
+    /// Stops and trips telling whether wheelchairs can use them.
+    Accessibility,
+    /// Stations with several locations describing how to move between them in pathways.txt.
+    Pathways,
+    /// Routes covered by a leg rule of fare_leg_rules.txt.
+    FaresV2,
+    /// Trips with a shape.
+    Shapes,
+    /// Trips with a headsign telling riders where they go.
+    Headsigns,
+}
+
+/// The grade of a dataset in a category.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CategoryScore {
+    /// The category graded.
+    pub category: ConformanceCategory,
+    /// Percentage of the records of the category meeting its expectations, between `0` and
+    /// `100`, `None` if the category does not apply to the dataset.
+    pub score: Option<f64>,
+    /// Why records fall short of the expectations of the category.
+    pub findings: Vec<String>,
+}
+
+impl ConformanceReport {
+    /// Returns the grade of a category.
+    pub fn category(&self, category: ConformanceCategory) -> Option<&CategoryScore> {
+        self.categories
+            .iter()
+            .find(|score| score.category == category)
+    }
+
+    /// Average score of the categories applying to the dataset, `100` if none does.
+    pub fn score(&self) -> f64 {
+        let scores: Vec<f64> = self
+            .categories
+            .iter()
+            .filter_map(|category| category.score)
+            .collect();
+        if scores.is_empty() {
+            100.0
+        } else {
+            scores.iter().sum::<f64>() / scores.len() as f64
+        }
+    }
+
+    /// Letter grade of [`ConformanceReport::score`], from `A` (90 or more) to `F` (less than
+    /// 60).
+    pub fn grade(&self) -> char {
+        match self.score() {
+            score if score >= 90.0 => 'A',
+            score if score >= 80.0 => 'B',
+            score if score >= 70.0 => 'C',
+            score if score >= 60.0 => 'D',
+            _ => 'F',
+        }
+    }
+}
+
+/// Percentage of `count` records out of `total`, `None` if there is no record.
+fn percentage(count: usize, total: usize) -> Option<f64> {
+    (total > 0).then(|| count as f64 * 100.0 / total as f64)
+}
+
+impl Dataset {
+    /// Grades the completeness of the dataset in every [`ConformanceCategory`].
+    ///
+    /// Unlike validation, this does not look for errors, but for the optional data riders and
+    /// trip planners benefit from. Findings are sorted, and list at most a few examples.
+    pub fn conformance_report(&self) -> ConformanceReport {
+        ConformanceReport {
+            categories: vec![
+                self.accessibility_score(),
+                self.pathways_score(),
+                self.fares_v2_score(),
+                self.shapes_score(),
+                self.headsigns_score(),
+            ],
+        }
+    }
+
+    /// Grades the share of stops and trips with wheelchair information.
+    fn accessibility_score(&self) -> CategoryScore {
+        let stops: Vec<StopId> = self
+            .stops
+            .iter()
+            .filter(|stop| {
+                matches!(
+                    stop.location_type,
+                    None | Some(LocationType::StopOrPlatform)
+                )
+            })
+            .map(|stop| stop.stop_id.clone())
+            .collect();
+        let stops_without: Vec<StopId> = stops
+            .iter()
+            .filter(|stop_id| {
+                matches!(
+                    self.stop_wheelchair_boarding(stop_id),
+                    None | Some(WheelchairBoarding::NoInformation)
+                )
+            })
+            .cloned()
+            .collect();
+        let trips_without: Vec<TripId> = self
+            .trips
+            .iter()
+            .filter(|trip| {
+                matches!(
+                    trip.wheelchair_accessible,
+                    None | Some(WheelchairAccessible::NoInformation)
+                )
+            })
+            .map(|trip| trip.trip_id.clone())
+            .collect();
+
+        let total = stops.len() + self.trips.len();
+        let mut findings = vec![];
+        if !stops_without.is_empty() {
+            findings.push(format!(
+                "{} of {} stops have no wheelchair_boarding, e.g. {}",
+                stops_without.len(),
+                stops.len(),
+                examples(stops_without.iter().map(|stop_id| stop_id.as_str()))
+            ));
+        }
+        if !trips_without.is_empty() {
+            findings.push(format!(
+                "{} of {} trips have no wheelchair_accessible, e.g. {}",
+                trips_without.len(),
+                self.trips.len(),
+                examples(trips_without.iter().map(|trip_id| trip_id.as_str()))
+            ));
+        }
+
+        CategoryScore {
+            category: ConformanceCategory::Accessibility,
+            score: percentage(total - stops_without.len() - trips_without.len(), total),
+            findings,
+        }
+    }
+
+    /// Grades the share of stations with several locations that have pathways.
+    fn pathways_score(&self) -> CategoryScore {
+        let mut locations: HashMap<StopId, usize> = HashMap::new();
+        let mut parents: HashMap<StopId, StopId> = HashMap::new();
+        for stop in self.stops.iter() {
+            if let Some(parent_station) = &stop.parent_station {
+                *locations.entry(parent_station.clone()).or_default() += 1;
+                parents.insert(stop.stop_id.clone(), parent_station.clone());
+            }
+        }
+        let station = |stop_id: &StopId| {
+            let parent = parents.get(stop_id)?;
+            // Boarding areas belong to a platform, itself in a station.
+            Some(parents.get(parent).unwrap_or(parent).clone())
+        };
+        let with_pathways: HashSet<StopId> = self
+            .pathways
+            .iter()
+            .flat_map(|pathway| [station(&pathway.from_stop_id), station(&pathway.to_stop_id)])
+            .flatten()
+            .collect();
+
+        let stations: Vec<StopId> = self
+            .stops
+            .iter()
+            .filter(|stop| matches!(stop.location_type, Some(LocationType::Station)))
+            .filter(|stop| locations.get(&stop.stop_id).is_some_and(|count| *count > 1))
+            .map(|stop| stop.stop_id.clone())
+            .collect();
+        let stations_without: Vec<&StopId> = stations
+            .iter()
+            .filter(|stop_id| !with_pathways.contains(*stop_id))
+            .collect();
+
+        let mut findings = vec![];
+        if !stations_without.is_empty() {
+            findings.push(format!(
+                "{} of {} stations with several locations have no pathway, e.g. {}",
+                stations_without.len(),
+                stations.len(),
+                examples(stations_without.iter().map(|stop_id| stop_id.as_str()))
+            ));
+        }
+
+        CategoryScore {
+            category: ConformanceCategory::Pathways,
+            score: percentage(stations.len() - stations_without.len(), stations.len()),
+            findings,
+        }
+    }
+
+    /// Grades the share of routes covered by a leg rule.
+    fn fares_v2_score(&self) -> CategoryScore {
+        let networks: HashSet<Option<String>> = self
+            .fare_leg_rules
+            .iter()
+            .map(|rule| {
+                rule.network_id
+                    .as_ref()
+                    .map(|network_id| network_id.to_string())
+            })
+            .collect();
+        let routes_without: Vec<String> = self
+            .routes
+            .iter()
+            .filter(|route| {
+                let network_id = self.network_of_route(&route.route_id);
+                !networks.contains(&None)
+                    && !networks.contains(&network_id.map(|network_id| network_id.to_string()))
+            })
+            .map(|route| route.route_id.to_string())
+            .collect();
+
+        let mut findings = vec![];
+        if self.fare_leg_rules.is_empty() {
+            findings.push("fare_leg_rules.txt has no record".to_string());
+        } else if !routes_without.is_empty() {
+            findings.push(format!(
+                "{} of {} routes are not covered by a leg rule, e.g. {}",
+                routes_without.len(),
+                self.routes.len(),
+                examples(routes_without.iter().map(String::as_str))
+            ));
+        }
+
+        CategoryScore {
+            category: ConformanceCategory::FaresV2,
+            score: percentage(self.routes.len() - routes_without.len(), self.routes.len()),
+            findings,
+        }
+    }
+
+    /// Grades the share of trips with an existing shape.
+    fn shapes_score(&self) -> CategoryScore {
+        let shape_ids: HashSet<String> = self
+            .shapes
+            .iter()
+            .map(|shape| shape.shape_id.to_string())
+            .collect();
+        let trips_without: Vec<TripId> = self
+            .trips
+            .iter()
+            .filter(|trip| {
+                trip.shape_id
+                    .as_ref()
+                    .is_none_or(|shape_id| !shape_ids.contains(shape_id.as_str()))
+            })
+            .map(|trip| trip.trip_id.clone())
+            .collect();
+
+        let mut findings = vec![];
+        if !trips_without.is_empty() {
+            findings.push(format!(
+                "{} of {} trips have no shape, e.g. {}",
+                trips_without.len(),
+                self.trips.len(),
+                examples(trips_without.iter().map(|trip_id| trip_id.as_str()))
+            ));
+        }
+
+        CategoryScore {
+            category: ConformanceCategory::Shapes,
+            score: percentage(self.trips.len() - trips_without.len(), self.trips.len()),
+            findings,
+        }
+    }
+
+    /// Grades the share of trips with a headsign that is not blank and differs from the name of
+    /// their route.
+    fn headsigns_score(&self) -> CategoryScore {
+        let stop_headsigns: HashSet<TripId> = self
+            .stop_times
+            .iter()
+            .filter(|stop_time| {
+                stop_time
+                    .stop_headsign
+                    .as_ref()
+                    .is_some_and(|headsign| !headsign.trim().is_empty())
+            })
+            .map(|stop_time| stop_time.trip_id.clone())
+            .collect();
+
+        let mut missing = vec![];
+        let mut route_names = vec![];
+        for trip in self.trips.iter() {
+            match trip
+                .trip_headsign
+                .as_deref()
+                .map(str::trim)
+                .filter(|headsign| !headsign.is_empty())
+            {
+                Some(headsign) => {
+                    let is_route_name = self.routes.get(&trip.route_id).is_some_and(|route| {
+                        [&route.route_short_name, &route.route_long_name]
+                            .into_iter()
+                            .flatten()
+                            .any(|name| name.trim().eq_ignore_ascii_case(headsign))
+                    });
+                    if is_route_name {
+                        route_names.push(trip.trip_id.clone());
+                    }
+                }
+                None if stop_headsigns.contains(&trip.trip_id) => {}
+                None => missing.push(trip.trip_id.clone()),
+            }
+        }
+
+        let mut findings = vec![];
+        if !missing.is_empty() {
+            findings.push(format!(
+                "{} of {} trips have no headsign, e.g. {}",
+                missing.len(),
+                self.trips.len(),
+                examples(missing.iter().map(|trip_id| trip_id.as_str()))
+            ));
+        }
+        if !route_names.is_empty() {
+            findings.push(format!(
+                "{} of {} trips have the name of their route as headsign, e.g. {}",
+                route_names.len(),
+                self.trips.len(),
+                examples(route_names.iter().map(|trip_id| trip_id.as_str()))
+            ));
+        }
+
+        CategoryScore {
+            category: ConformanceCategory::Headsigns,
+            score: percentage(
+                self.trips.len() - missing.len() - route_names.len(),
+                self.trips.len(),
+            ),
+            findings,
+        }
+    }
+}
+
+/// Lists the first few ids, sorted, for a finding.
+fn examples<'a>(ids: impl Iterator<Item = &'a str>) -> String {
+    let mut ids: Vec<&str> = ids.collect();
+    ids.sort_unstable();
+    let mut list = ids.iter().take(3).copied().collect::<Vec<_>>().join(", ");
+    if ids.len() > 3 {
+        list.push_str(", …");
+    }
+    list
+}
@@ -0,0 +1,64 @@
+//! Resolution of the continuous pickup and drop-off of trips, between their stop times.
+
+use crate::schemas::{ContinuousDropOff, ContinuousPickup, Route, StopTime, TripId};
+use crate::Dataset;
+
+impl Dataset {
+    /// Returns whether riders can board anywhere along the shape of a trip, between the stop
+    /// time at `stop_sequence` and the next stop time of the trip.
+    ///
+    /// The value of the stop time overrides the value of the route of the trip, and an empty
+    /// value inherits it. When neither is set, there is no continuous pickup. `None` if the
+    /// stop time is unknown, or if its value is empty and its trip unknown.
+    pub fn effective_continuous_pickup(
+        &self,
+        trip_id: &TripId,
+        stop_sequence: u32,
+    ) -> Option<ContinuousPickup> {
+        self.resolve_continuous_stopping(
+            trip_id,
+            stop_sequence,
+            |stop_time| stop_time.continuous_pickup.clone(),
+            |route| route.continuous_pickup.clone(),
+        )
+        .map(|pickup| pickup.unwrap_or(ContinuousPickup::NoContinuousStopping))
+    }
+
+    /// Returns whether riders can alight anywhere along the shape of a trip, between the stop
+    /// time at `stop_sequence` and the next stop time of the trip, resolved as
+    /// [`Dataset::effective_continuous_pickup`].
+    pub fn effective_continuous_drop_off(
+        &self,
+        trip_id: &TripId,
+        stop_sequence: u32,
+    ) -> Option<ContinuousDropOff> {
+        self.resolve_continuous_stopping(
+            trip_id,
+            stop_sequence,
+            |stop_time| stop_time.continuous_drop_off.clone(),
+            |route| route.continuous_drop_off.clone(),
+        )
+        .map(|drop_off| drop_off.unwrap_or(ContinuousDropOff::NoContinuousStopping))
+    }
+
+    /// Returns the value of the stop time if set, otherwise the value of the route of its trip.
+    fn resolve_continuous_stopping<T>(
+        &self,
+        trip_id: &TripId,
+        stop_sequence: u32,
+        stop_time_value: impl Fn(&StopTime) -> Option<T>,
+        route_value: impl Fn(&Route) -> Option<T>,
+    ) -> Option<Option<T>> {
+        let stop_time = self.stop_times.get(&(trip_id.clone(), stop_sequence))?;
+        if let Some(value) = stop_time_value(&stop_time) {
+            return Some(Some(value));
+        }
+        drop(stop_time);
+        let route_id = self.trips.get(trip_id)?.route_id.clone();
+        Some(
+            self.routes
+                .get(&route_id)
+                .and_then(|route| route_value(&route)),
+        )
+    }
+}
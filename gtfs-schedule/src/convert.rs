@@ -0,0 +1,977 @@
+//! Conversion of datasets to other transit formats, through an intermediate model.
+//!
+//! The main types are:
+//! - [`TransitModel`]: The scheduled service of a dataset, independent of any format.
+//! - [`Exporter`]: Writes a [`TransitModel`] in another format.
+//! - [`NetexExporter`]: Writes a [`TransitModel`] as a NeTEx document.
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use gtfs_schedule::convert::{Exporter, NetexExporter, TransitModel};
+//! use gtfs_schedule::Dataset;
+//!
+//! let dataset = Dataset::from_csv(Path::new("gtfs")).unwrap();
+//! let model = TransitModel::from_dataset(&dataset);
+//! let mut file = std::fs::File::create("netex.xml").unwrap();
+//! NetexExporter::new("DEMO").export(&model, &mut file).unwrap();
+//! ```
+
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use geo::Coord;
+
+use crate::compiled_timetable::trip_starts;
+use crate::error::{ParseError, ParseErrorKind, Result};
+use crate::schemas::{
+    Agency, DropOffType, Frequency, LocationType, NaiveServiceTime, PickupType, RouteType, Stop,
+    StopTime, TripId,
+};
+use crate::Dataset;
+
+/// The scheduled service of a dataset, independent of any format, see [`Exporter`].
+///
+/// Ids are those of the dataset. Records are sorted by id.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransitModel {
+    /// The timezone of the times of the model.
+    pub timezone: Option<Tz>,
+    /// The operators, from agency.txt.
+    pub operators: Vec<Operator>,
+    /// The lines, from routes.txt.
+    pub lines: Vec<Line>,
+    /// The stop places, from the stations and the platforms of stops.txt.
+    pub stop_places: Vec<StopPlace>,
+    /// The days of operation, from the services of calendar.txt and calendar_dates.txt.
+    pub day_types: Vec<DayType>,
+    /// The journeys, from trips.txt, stop_times.txt and frequencies.txt.
+    pub service_journeys: Vec<ServiceJourney>,
+}
+
+/// An operator of lines, see [`TransitModel::operators`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operator {
+    /// The `agency_id`, or the `agency_name` of a single agency without id.
+    pub id: String,
+    pub name: String,
+    pub url: Option<String>,
+    pub phone: Option<String>,
+}
+
+/// A line, see [`TransitModel::lines`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub id: String,
+    pub operator_id: Option<String>,
+    /// The long name of the route, or its short name if it has none.
+    pub name: Option<String>,
+    /// The short name of the route.
+    pub public_code: Option<String>,
+    pub mode: TransportMode,
+    /// The color of the line, as an hexadecimal `RRGGBB` value.
+    pub color: Option<String>,
+    /// The color of the text drawn over [`Line::color`], as an hexadecimal `RRGGBB` value.
+    pub text_color: Option<String>,
+}
+
+/// The mode of transport of a [`Line`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransportMode {
+    Tram,
+    Metro,
+    Rail,
+    Bus,
+    Trolleybus,
+    Water,
+    Cableway,
+    Funicular,
+    Unknown,
+}
+
+impl From<&RouteType> for TransportMode {
+    fn from(route_type: &RouteType) -> Self {
+        match route_type {
+            RouteType::LightRail | RouteType::CableTram => TransportMode::Tram,
+            RouteType::Subway | RouteType::Monorail => TransportMode::Metro,
+            RouteType::Rail => TransportMode::Rail,
+            RouteType::Bus => TransportMode::Bus,
+            RouteType::Trolleybus => TransportMode::Trolleybus,
+            RouteType::Ferry => TransportMode::Water,
+            RouteType::AerialLift => TransportMode::Cableway,
+            RouteType::Funicular => TransportMode::Funicular,
+            RouteType::Unknown(_) => TransportMode::Unknown,
+        }
+    }
+}
+
+/// A place where riders board and alight, holding its quays, see [`TransitModel::stop_places`].
+///
+/// Stations are stop places holding their platforms. Platforms without a station are stop
+/// places holding a single quay, sharing their id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StopPlace {
+    pub id: String,
+    pub name: Option<String>,
+    /// The location of the place, in degrees (`x` is the longitude, `y` the latitude).
+    pub coord: Option<Coord>,
+    pub quays: Vec<Quay>,
+}
+
+/// A platform of a [`StopPlace`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quay {
+    pub id: String,
+    pub name: Option<String>,
+    /// The location of the quay, in degrees (`x` is the longitude, `y` the latitude).
+    pub coord: Option<Coord>,
+    /// The platform code, e.g. `G` or `3`.
+    pub public_code: Option<String>,
+}
+
+/// The days a service runs, see [`TransitModel::day_types`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayType {
+    /// The `service_id`.
+    pub id: String,
+    /// The dates the service runs, sorted.
+    pub dates: Vec<NaiveDate>,
+}
+
+/// A single run of a trip, see [`TransitModel::service_journeys`].
+///
+/// Trips defined in frequencies.txt are expanded into one journey per departure, with the id of
+/// the trip followed by `_` and the number of the run, starting from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceJourney {
+    pub id: String,
+    pub line_id: String,
+    pub day_type_id: String,
+    pub headsign: Option<String>,
+    /// The stops of the journey, in order.
+    pub calls: Vec<Call>,
+}
+
+/// A stop of a [`ServiceJourney`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    pub quay_id: String,
+    pub arrival_time: Option<NaiveServiceTime>,
+    pub departure_time: Option<NaiveServiceTime>,
+    /// Whether riders may board, `false` when pickup is not available.
+    pub for_boarding: bool,
+    /// Whether riders may alight, `false` when drop off is not available.
+    pub for_alighting: bool,
+}
+
+impl TransitModel {
+    /// Builds the model of a dataset.
+    ///
+    /// Stop times without a stop, such as those of flexible services, are left out.
+    pub fn from_dataset(dataset: &Dataset) -> Self {
+        let single_agency = match dataset.agencies.as_slice() {
+            [agency] => Some(agency),
+            _ => None,
+        };
+        let operator_id = |agency: &Agency| match &agency.agency_id {
+            Some(agency_id) => agency_id.to_string(),
+            None => agency.agency_name.clone(),
+        };
+        let mut operators: Vec<Operator> = dataset
+            .agencies
+            .iter()
+            .map(|agency| Operator {
+                id: operator_id(agency),
+                name: agency.agency_name.clone(),
+                url: Some(agency.agency_url.to_string()),
+                phone: agency.agency_phone.as_ref().map(|phone| phone.to_string()),
+            })
+            .collect();
+        operators.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut lines: Vec<Line> = dataset
+            .routes
+            .iter()
+            .map(|route| Line {
+                id: route.route_id.to_string(),
+                operator_id: route
+                    .agency_id
+                    .as_ref()
+                    .map(|agency_id| agency_id.to_string())
+                    .or_else(|| single_agency.map(operator_id)),
+                name: route
+                    .route_long_name
+                    .clone()
+                    .or_else(|| route.route_short_name.clone()),
+                public_code: route.route_short_name.clone(),
+                mode: TransportMode::from(&route.route_type),
+                color: route.route_color.clone(),
+                text_color: route.route_text_color.clone(),
+            })
+            .collect();
+        lines.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut stop_places: Vec<StopPlace> = vec![];
+        let mut quays: HashMap<String, Vec<Quay>> = HashMap::new();
+        for stop in dataset.stops.iter() {
+            match stop.location_type {
+                Some(LocationType::Station) => stop_places.push(StopPlace {
+                    id: stop.stop_id.to_string(),
+                    name: stop.stop_name.clone(),
+                    coord: stop.stop_coord.as_ref().map(|coord| **coord),
+                    quays: vec![],
+                }),
+                None | Some(LocationType::StopOrPlatform) => {
+                    let station = stop.parent_station.as_ref().filter(|parent_station| {
+                        dataset.stops.get(*parent_station).is_some_and(|parent| {
+                            matches!(parent.location_type, Some(LocationType::Station))
+                        })
+                    });
+                    match station {
+                        Some(station) => quays
+                            .entry(station.to_string())
+                            .or_default()
+                            .push(quay(&stop)),
+                        None => stop_places.push(StopPlace {
+                            id: stop.stop_id.to_string(),
+                            name: stop.stop_name.clone(),
+                            coord: stop.stop_coord.as_ref().map(|coord| **coord),
+                            quays: vec![quay(&stop)],
+                        }),
+                    }
+                }
+                _ => {}
+            }
+        }
+        for stop_place in &mut stop_places {
+            if let Some(mut station_quays) = quays.remove(&stop_place.id) {
+                station_quays.sort_by(|a, b| a.id.cmp(&b.id));
+                stop_place.quays = station_quays;
+            }
+        }
+        stop_places.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let service_ids: BTreeSet<String> = dataset
+            .trips
+            .iter()
+            .map(|trip| trip.service_id.to_string())
+            .collect();
+        let day_types = service_ids
+            .into_iter()
+            .map(|service_id| DayType {
+                dates: dataset.service_dates(&service_id.as_str().into()),
+                id: service_id,
+            })
+            .collect();
+
+        let mut stop_times: HashMap<TripId, Vec<StopTime>> = HashMap::new();
+        for stop_time in dataset.stop_times.iter() {
+            if stop_time.stop_id.is_some() {
+                stop_times
+                    .entry(stop_time.trip_id.clone())
+                    .or_default()
+                    .push(stop_time.value().clone());
+            }
+        }
+        let mut frequencies: HashMap<TripId, Vec<Frequency>> = HashMap::new();
+        for frequency in dataset.frequencies.iter() {
+            frequencies
+                .entry(frequency.trip_id.clone())
+                .or_default()
+                .push(frequency.value().clone());
+        }
+        let mut service_journeys = vec![];
+        for trip in dataset.trips.iter() {
+            let Some(stop_times) = stop_times.get_mut(&trip.trip_id) else {
+                continue;
+            };
+            stop_times.sort_by_key(|stop_time| stop_time.stop_sequence);
+            let Some(first_departure) = stop_times
+                .iter()
+                .find_map(|stop_time| stop_time.departure_time.or(stop_time.arrival_time))
+            else {
+                continue;
+            };
+
+            let trip_frequencies = frequencies.get(&trip.trip_id).map(Vec::as_slice);
+            let starts = trip_starts(trip_frequencies, first_departure.as_secs());
+            for (run, start) in starts.iter().enumerate() {
+                let offset = i64::from(*start) - i64::from(first_departure.as_secs());
+                let shift = |time: Option<NaiveServiceTime>| {
+                    let secs = u32::try_from(i64::from(time?.as_secs()) + offset).ok()?;
+                    Some(NaiveServiceTime::from_secs(secs))
+                };
+                service_journeys.push(ServiceJourney {
+                    id: match trip_frequencies {
+                        Some(_) => format!("{}_{}", trip.trip_id, run + 1),
+                        None => trip.trip_id.to_string(),
+                    },
+                    line_id: trip.route_id.to_string(),
+                    day_type_id: trip.service_id.to_string(),
+                    headsign: trip.trip_headsign.clone(),
+                    calls: stop_times
+                        .iter()
+                        .filter_map(|stop_time| {
+                            Some(Call {
+                                quay_id: stop_time.stop_id.as_ref()?.to_string(),
+                                arrival_time: shift(stop_time.arrival_time),
+                                departure_time: shift(stop_time.departure_time),
+                                for_boarding: !matches!(
+                                    stop_time.pickup_type,
+                                    Some(PickupType::NoPickupAvailable)
+                                ),
+                                for_alighting: !matches!(
+                                    stop_time.drop_off_type,
+                                    Some(DropOffType::NoDropOffAvailable)
+                                ),
+                            })
+                        })
+                        .collect(),
+                });
+            }
+        }
+        service_journeys.sort_by(|a, b| a.id.cmp(&b.id));
+
+        TransitModel {
+            timezone: dataset
+                .agencies
+                .first()
+                .map(|agency| agency.agency_timezone),
+            operators,
+            lines,
+            stop_places,
+            day_types,
+            service_journeys,
+        }
+    }
+}
+
+/// Builds the quay of a platform.
+fn quay(stop: &Stop) -> Quay {
+    Quay {
+        id: stop.stop_id.to_string(),
+        name: stop.stop_name.clone(),
+        coord: stop.stop_coord.as_ref().map(|coord| **coord),
+        public_code: stop.platform_code.clone(),
+    }
+}
+
+/// Writes a [`TransitModel`] in another format.
+///
+/// Importing other formats is left to crates depending on this one: they can build a
+/// [`TransitModel`], whose fields are public.
+pub trait Exporter {
+    /// Writes the model to `writer`.
+    fn export(&self, model: &TransitModel, writer: &mut dyn Write) -> Result<()>;
+}
+
+/// Writes a [`TransitModel`] as a NeTEx document, following the European Passenger Information
+/// Profile (EPIP), the basic profile of NeTEx.
+///
+/// The document is a single composite frame holding a resource frame (operators), a site frame
+/// (stop places and quays), a service frame (lines, scheduled stop points and journey patterns),
+/// a service calendar frame (day types and operating days) and a timetable frame (service
+/// journeys). Ids are those of the model, prefixed with the codespace and the element name, e.g.
+/// `DEMO:Line:AB`. Journeys sharing a line and a sequence of stops share a journey pattern.
+#[derive(Debug, Clone)]
+pub struct NetexExporter {
+    codespace: String,
+    participant_ref: String,
+    timestamp: Option<NaiveDateTime>,
+}
+
+impl NetexExporter {
+    /// Writes documents using the given codespace, which is also the participant by default.
+    pub fn new(codespace: &str) -> Self {
+        Self {
+            codespace: codespace.to_string(),
+            participant_ref: codespace.to_string(),
+            timestamp: None,
+        }
+    }
+
+    /// Set the participant publishing the documents.
+    pub fn participant_ref(mut self, participant_ref: &str) -> Self {
+        self.participant_ref = participant_ref.to_string();
+        self
+    }
+
+    /// Set the publication timestamp, in UTC, instead of the time of the export.
+    pub fn timestamp(mut self, timestamp: NaiveDateTime) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Formats the id of an element.
+    fn id(&self, element: &str, id: &str) -> String {
+        format!("{}:{}:{}", self.codespace, element, id)
+    }
+
+    fn write_resource_frame(&self, model: &TransitModel, xml: &mut XmlWriter) -> Result<()> {
+        xml.start(
+            "ResourceFrame",
+            &[("id", &self.id("ResourceFrame", "1")), ("version", "1")],
+        )?;
+        xml.start("organisations", &[])?;
+        for operator in &model.operators {
+            xml.start(
+                "Operator",
+                &[("id", &self.id("Operator", &operator.id)), ("version", "1")],
+            )?;
+            xml.text("Name", &operator.name)?;
+            if operator.url.is_some() || operator.phone.is_some() {
+                xml.start("ContactDetails", &[])?;
+                if let Some(phone) = &operator.phone {
+                    xml.text("Phone", phone)?;
+                }
+                if let Some(url) = &operator.url {
+                    xml.text("Url", url)?;
+                }
+                xml.end("ContactDetails")?;
+            }
+            xml.end("Operator")?;
+        }
+        xml.end("organisations")?;
+        xml.end("ResourceFrame")
+    }
+
+    fn write_site_frame(&self, model: &TransitModel, xml: &mut XmlWriter) -> Result<()> {
+        xml.start(
+            "SiteFrame",
+            &[("id", &self.id("SiteFrame", "1")), ("version", "1")],
+        )?;
+        xml.start("stopPlaces", &[])?;
+        for stop_place in &model.stop_places {
+            xml.start(
+                "StopPlace",
+                &[
+                    ("id", &self.id("StopPlace", &stop_place.id)),
+                    ("version", "1"),
+                ],
+            )?;
+            if let Some(name) = &stop_place.name {
+                xml.text("Name", name)?;
+            }
+            write_centroid(stop_place.coord, xml)?;
+            xml.start("quays", &[])?;
+            for quay in &stop_place.quays {
+                xml.start(
+                    "Quay",
+                    &[("id", &self.id("Quay", &quay.id)), ("version", "1")],
+                )?;
+                if let Some(name) = &quay.name {
+                    xml.text("Name", name)?;
+                }
+                write_centroid(quay.coord, xml)?;
+                if let Some(public_code) = &quay.public_code {
+                    xml.text("PublicCode", public_code)?;
+                }
+                xml.end("Quay")?;
+            }
+            xml.end("quays")?;
+            xml.end("StopPlace")?;
+        }
+        xml.end("stopPlaces")?;
+        xml.end("SiteFrame")
+    }
+
+    fn write_service_frame(
+        &self,
+        model: &TransitModel,
+        patterns: &[JourneyPattern],
+        xml: &mut XmlWriter,
+    ) -> Result<()> {
+        xml.start(
+            "ServiceFrame",
+            &[("id", &self.id("ServiceFrame", "1")), ("version", "1")],
+        )?;
+        xml.start("lines", &[])?;
+        for line in &model.lines {
+            xml.start(
+                "Line",
+                &[("id", &self.id("Line", &line.id)), ("version", "1")],
+            )?;
+            xml.text("Name", line.name.as_deref().unwrap_or(&line.id))?;
+            xml.text("TransportMode", netex_mode(line.mode))?;
+            if let Some(public_code) = &line.public_code {
+                xml.text("PublicCode", public_code)?;
+            }
+            if let Some(operator_id) = &line.operator_id {
+                xml.empty(
+                    "OperatorRef",
+                    &[("ref", &self.id("Operator", operator_id)), ("version", "1")],
+                )?;
+            }
+            if line.color.is_some() || line.text_color.is_some() {
+                xml.start("Presentation", &[])?;
+                if let Some(color) = &line.color {
+                    xml.text("Colour", color)?;
+                }
+                if let Some(text_color) = &line.text_color {
+                    xml.text("TextColour", text_color)?;
+                }
+                xml.end("Presentation")?;
+            }
+            xml.end("Line")?;
+        }
+        xml.end("lines")?;
+
+        let quay_ids: BTreeSet<&str> = patterns
+            .iter()
+            .flat_map(|pattern| &pattern.stops)
+            .map(|(quay_id, _, _)| quay_id.as_str())
+            .collect();
+        xml.start("scheduledStopPoints", &[])?;
+        for quay_id in &quay_ids {
+            xml.empty(
+                "ScheduledStopPoint",
+                &[
+                    ("id", &self.id("ScheduledStopPoint", quay_id)),
+                    ("version", "1"),
+                ],
+            )?;
+        }
+        xml.end("scheduledStopPoints")?;
+        xml.start("stopAssignments", &[])?;
+        for (order, quay_id) in quay_ids.iter().enumerate() {
+            xml.start(
+                "PassengerStopAssignment",
+                &[
+                    ("id", &self.id("PassengerStopAssignment", quay_id)),
+                    ("version", "1"),
+                    ("order", &(order + 1).to_string()),
+                ],
+            )?;
+            xml.empty(
+                "ScheduledStopPointRef",
+                &[
+                    ("ref", &self.id("ScheduledStopPoint", quay_id)),
+                    ("version", "1"),
+                ],
+            )?;
+            xml.empty(
+                "QuayRef",
+                &[("ref", &self.id("Quay", quay_id)), ("version", "1")],
+            )?;
+            xml.end("PassengerStopAssignment")?;
+        }
+        xml.end("stopAssignments")?;
+
+        xml.start("journeyPatterns", &[])?;
+        for pattern in patterns {
+            xml.start(
+                "ServiceJourneyPattern",
+                &[
+                    ("id", &self.id("ServiceJourneyPattern", &pattern.id)),
+                    ("version", "1"),
+                ],
+            )?;
+            xml.start("RouteView", &[])?;
+            xml.empty(
+                "LineRef",
+                &[
+                    ("ref", &self.id("Line", &pattern.line_id)),
+                    ("version", "1"),
+                ],
+            )?;
+            xml.end("RouteView")?;
+            xml.start("pointsInSequence", &[])?;
+            for (order, (quay_id, for_boarding, for_alighting)) in pattern.stops.iter().enumerate()
+            {
+                xml.start(
+                    "StopPointInJourneyPattern",
+                    &[
+                        (
+                            "id",
+                            &self.id("StopPointInJourneyPattern", &pattern.stop_point_id(order)),
+                        ),
+                        ("version", "1"),
+                        ("order", &(order + 1).to_string()),
+                    ],
+                )?;
+                xml.empty(
+                    "ScheduledStopPointRef",
+                    &[
+                        ("ref", &self.id("ScheduledStopPoint", quay_id)),
+                        ("version", "1"),
+                    ],
+                )?;
+                if !for_alighting {
+                    xml.text("ForAlighting", "false")?;
+                }
+                if !for_boarding {
+                    xml.text("ForBoarding", "false")?;
+                }
+                xml.end("StopPointInJourneyPattern")?;
+            }
+            xml.end("pointsInSequence")?;
+            xml.end("ServiceJourneyPattern")?;
+        }
+        xml.end("journeyPatterns")?;
+        xml.end("ServiceFrame")
+    }
+
+    fn write_service_calendar_frame(
+        &self,
+        model: &TransitModel,
+        xml: &mut XmlWriter,
+    ) -> Result<()> {
+        xml.start(
+            "ServiceCalendarFrame",
+            &[
+                ("id", &self.id("ServiceCalendarFrame", "1")),
+                ("version", "1"),
+            ],
+        )?;
+        xml.start("dayTypes", &[])?;
+        for day_type in &model.day_types {
+            xml.empty(
+                "DayType",
+                &[("id", &self.id("DayType", &day_type.id)), ("version", "1")],
+            )?;
+        }
+        xml.end("dayTypes")?;
+
+        let dates: BTreeSet<NaiveDate> = model
+            .day_types
+            .iter()
+            .flat_map(|day_type| day_type.dates.iter().copied())
+            .collect();
+        xml.start("operatingDays", &[])?;
+        for date in &dates {
+            xml.start(
+                "OperatingDay",
+                &[
+                    ("id", &self.id("OperatingDay", &date.to_string())),
+                    ("version", "1"),
+                ],
+            )?;
+            xml.text("CalendarDate", &date.to_string())?;
+            xml.end("OperatingDay")?;
+        }
+        xml.end("operatingDays")?;
+
+        xml.start("dayTypeAssignments", &[])?;
+        let mut order = 0;
+        for day_type in &model.day_types {
+            for date in &day_type.dates {
+                order += 1;
+                xml.start(
+                    "DayTypeAssignment",
+                    &[
+                        (
+                            "id",
+                            &self.id("DayTypeAssignment", &format!("{}_{}", day_type.id, date)),
+                        ),
+                        ("version", "1"),
+                        ("order", &order.to_string()),
+                    ],
+                )?;
+                xml.empty(
+                    "OperatingDayRef",
+                    &[("ref", &self.id("OperatingDay", &date.to_string()))],
+                )?;
+                xml.empty(
+                    "DayTypeRef",
+                    &[("ref", &self.id("DayType", &day_type.id)), ("version", "1")],
+                )?;
+                xml.end("DayTypeAssignment")?;
+            }
+        }
+        xml.end("dayTypeAssignments")?;
+        xml.end("ServiceCalendarFrame")
+    }
+
+    fn write_timetable_frame(
+        &self,
+        model: &TransitModel,
+        patterns: &[JourneyPattern],
+        journey_patterns: &[usize],
+        xml: &mut XmlWriter,
+    ) -> Result<()> {
+        xml.start(
+            "TimetableFrame",
+            &[("id", &self.id("TimetableFrame", "1")), ("version", "1")],
+        )?;
+        xml.start("vehicleJourneys", &[])?;
+        for (journey, pattern) in model.service_journeys.iter().zip(journey_patterns) {
+            let pattern = &patterns[*pattern];
+            xml.start(
+                "ServiceJourney",
+                &[
+                    ("id", &self.id("ServiceJourney", &journey.id)),
+                    ("version", "1"),
+                ],
+            )?;
+            if let Some(headsign) = &journey.headsign {
+                xml.text("Name", headsign)?;
+            }
+            xml.start("dayTypes", &[])?;
+            xml.empty(
+                "DayTypeRef",
+                &[
+                    ("ref", &self.id("DayType", &journey.day_type_id)),
+                    ("version", "1"),
+                ],
+            )?;
+            xml.end("dayTypes")?;
+            xml.empty(
+                "ServiceJourneyPatternRef",
+                &[
+                    ("ref", &self.id("ServiceJourneyPattern", &pattern.id)),
+                    ("version", "1"),
+                ],
+            )?;
+            xml.empty(
+                "LineRef",
+                &[
+                    ("ref", &self.id("Line", &journey.line_id)),
+                    ("version", "1"),
+                ],
+            )?;
+            xml.start("passingTimes", &[])?;
+            for (order, call) in journey.calls.iter().enumerate() {
+                xml.start(
+                    "TimetabledPassingTime",
+                    &[
+                        (
+                            "id",
+                            &self.id(
+                                "TimetabledPassingTime",
+                                &format!("{}_{}", journey.id, order + 1),
+                            ),
+                        ),
+                        ("version", "1"),
+                    ],
+                )?;
+                xml.empty(
+                    "StopPointInJourneyPatternRef",
+                    &[
+                        (
+                            "ref",
+                            &self.id("StopPointInJourneyPattern", &pattern.stop_point_id(order)),
+                        ),
+                        ("version", "1"),
+                    ],
+                )?;
+                for (element, time) in [
+                    ("Arrival", call.arrival_time),
+                    ("Departure", call.departure_time),
+                ] {
+                    let Some(time) = time else {
+                        continue;
+                    };
+                    let secs = time.as_secs();
+                    xml.text(
+                        &format!("{}Time", element),
+                        &format!(
+                            "{:02}:{:02}:{:02}",
+                            secs / 3600 % 24,
+                            secs / 60 % 60,
+                            secs % 60
+                        ),
+                    )?;
+                    if secs >= 24 * 3600 {
+                        xml.text(
+                            &format!("{}DayOffset", element),
+                            &(secs / (24 * 3600)).to_string(),
+                        )?;
+                    }
+                }
+                xml.end("TimetabledPassingTime")?;
+            }
+            xml.end("passingTimes")?;
+            xml.end("ServiceJourney")?;
+        }
+        xml.end("vehicleJourneys")?;
+        xml.end("TimetableFrame")
+    }
+}
+
+impl Exporter for NetexExporter {
+    fn export(&self, model: &TransitModel, writer: &mut dyn Write) -> Result<()> {
+        let mut patterns: Vec<JourneyPattern> = vec![];
+        let mut pattern_indices: HashMap<(&str, PatternStops), usize> = HashMap::new();
+        let mut journey_patterns = vec![];
+        for journey in &model.service_journeys {
+            let stops: PatternStops = journey
+                .calls
+                .iter()
+                .map(|call| (call.quay_id.clone(), call.for_boarding, call.for_alighting))
+                .collect();
+            let index = *pattern_indices
+                .entry((journey.line_id.as_str(), stops.clone()))
+                .or_insert_with(|| {
+                    let number = patterns
+                        .iter()
+                        .filter(|pattern| pattern.line_id == journey.line_id)
+                        .count();
+                    patterns.push(JourneyPattern {
+                        id: format!("{}_{}", journey.line_id, number + 1),
+                        line_id: journey.line_id.clone(),
+                        stops,
+                    });
+                    patterns.len() - 1
+                });
+            journey_patterns.push(index);
+        }
+
+        let timestamp = self
+            .timestamp
+            .unwrap_or_else(|| Utc::now().naive_utc())
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string();
+        let mut xml = XmlWriter { writer, depth: 0 };
+        xml.line(r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        xml.start(
+            "PublicationDelivery",
+            &[
+                ("xmlns", "http://www.netex.org.uk/netex"),
+                ("xmlns:gml", "http://www.opengis.net/gml/3.2"),
+                ("version", "1.1"),
+            ],
+        )?;
+        xml.text("PublicationTimestamp", &timestamp)?;
+        xml.text("ParticipantRef", &self.participant_ref)?;
+        xml.start("dataObjects", &[])?;
+        xml.start(
+            "CompositeFrame",
+            &[("id", &self.id("CompositeFrame", "1")), ("version", "1")],
+        )?;
+        if let Some(timezone) = model.timezone {
+            xml.start("FrameDefaults", &[])?;
+            xml.start("DefaultLocale", &[])?;
+            xml.text("TimeZone", timezone.name())?;
+            xml.end("DefaultLocale")?;
+            xml.end("FrameDefaults")?;
+        }
+        xml.start("frames", &[])?;
+        self.write_resource_frame(model, &mut xml)?;
+        self.write_site_frame(model, &mut xml)?;
+        self.write_service_frame(model, &patterns, &mut xml)?;
+        self.write_service_calendar_frame(model, &mut xml)?;
+        self.write_timetable_frame(model, &patterns, &journey_patterns, &mut xml)?;
+        xml.end("frames")?;
+        xml.end("CompositeFrame")?;
+        xml.end("dataObjects")?;
+        xml.end("PublicationDelivery")
+    }
+}
+
+/// The quays of a journey pattern, along with whether riders may board and alight.
+type PatternStops = Vec<(String, bool, bool)>;
+
+/// Journeys of a line sharing the same stops.
+struct JourneyPattern {
+    id: String,
+    line_id: String,
+    stops: PatternStops,
+}
+
+impl JourneyPattern {
+    /// The id of the stop at `order`, starting from 0.
+    fn stop_point_id(&self, order: usize) -> String {
+        format!("{}_{}", self.id, order + 1)
+    }
+}
+
+/// Returns the NeTEx name of a mode of transport.
+fn netex_mode(mode: TransportMode) -> &'static str {
+    match mode {
+        TransportMode::Tram => "tram",
+        TransportMode::Metro => "metro",
+        TransportMode::Rail => "rail",
+        TransportMode::Bus => "bus",
+        TransportMode::Trolleybus => "trolleyBus",
+        TransportMode::Water => "water",
+        TransportMode::Cableway => "cableway",
+        TransportMode::Funicular => "funicular",
+        TransportMode::Unknown => "unknown",
+    }
+}
+
+/// Writes the location of a stop place or a quay, if known.
+fn write_centroid(coord: Option<Coord>, xml: &mut XmlWriter) -> Result<()> {
+    let Some(coord) = coord else {
+        return Ok(());
+    };
+    xml.start("Centroid", &[])?;
+    xml.start("Location", &[])?;
+    xml.text("Longitude", &coord.x.to_string())?;
+    xml.text("Latitude", &coord.y.to_string())?;
+    xml.end("Location")?;
+    xml.end("Centroid")
+}
+
+/// Writes an indented XML document, one element per line.
+struct XmlWriter<'a> {
+    writer: &'a mut dyn Write,
+    depth: usize,
+}
+
+impl XmlWriter<'_> {
+    /// Writes an opening tag, the following elements being its children.
+    fn start(&mut self, name: &str, attributes: &[(&str, &str)]) -> Result<()> {
+        self.line(&format!("<{}{}>", name, format_attributes(attributes)))?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Writes the closing tag of the element opened last.
+    fn end(&mut self, name: &str) -> Result<()> {
+        self.depth -= 1;
+        self.line(&format!("</{}>", name))
+    }
+
+    /// Writes an element without content.
+    fn empty(&mut self, name: &str, attributes: &[(&str, &str)]) -> Result<()> {
+        self.line(&format!("<{}{}/>", name, format_attributes(attributes)))
+    }
+
+    /// Writes an element holding text.
+    fn text(&mut self, name: &str, text: &str) -> Result<()> {
+        self.line(&format!("<{}>{}</{}>", name, escape(text), name))
+    }
+
+    /// Writes a line at the current depth.
+    fn line(&mut self, line: &str) -> Result<()> {
+        writeln!(
+            self.writer,
+            "{:indent$}{}",
+            "",
+            line,
+            indent = self.depth * 2
+        )
+        .map_err(|e| ParseError::from(ParseErrorKind::from(e)).into())
+    }
+}
+
+/// Formats attributes, each preceded by a space.
+fn format_attributes(attributes: &[(&str, &str)]) -> String {
+    attributes
+        .iter()
+        .map(|(name, value)| format!(" {}=\"{}\"", name, escape(value)))
+        .collect()
+}
+
+/// Escapes the characters of a text or an attribute value that are reserved in XML.
+pub(crate) fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
@@ -1,15 +1,29 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::{cell::OnceCell, env};
 
 use chrono::NaiveDate;
+use dashmap::mapref::multiple::RefMulti;
+use dashmap::mapref::one::Ref;
 use dashmap::DashMap;
 use oxilangtag::LanguageTag;
 
-use crate::error::{DatasetValidationError, ErrorContext, ParseError, ParseErrorKind, Result};
+use crate::edit::DirtyRecords;
+use crate::error::{
+    DatasetValidationError, Error, ErrorContext, ParseError, ParseErrorKind, Result,
+};
+use crate::interning::InternScope;
+use crate::load_filter::{load_rank, LoadFilter};
+use crate::provenance::{SourceLines, ToSourceKey};
 use crate::schemas::*;
+use crate::writer::field_value;
+use crate::{
+    DeprecatedColumn, DuplicateKey, DuplicateKeyPolicy, InvalidContact, ParseOptions, ParseReport,
+};
 
 pub static CSV_FILES: &[&str] = &[
     "agency.txt",
@@ -43,6 +57,232 @@ pub static CSV_FILES: &[&str] = &[
     "attributions.txt",
 ];
 
+/// Identifies one of the files that make up a GTFS dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GtfsFile {
+    Agency,
+    Stops,
+    Routes,
+    Trips,
+    StopTimes,
+    Calendar,
+    CalendarDates,
+    FareAttributes,
+    FareRules,
+    Timeframes,
+    FareMedia,
+    FareProducts,
+    FareLegRules,
+    FareTransfers,
+    Areas,
+    StopsAreas,
+    Networks,
+    RoutesNetworks,
+    Shapes,
+    Frequencies,
+    Transfers,
+    Pathways,
+    Levels,
+    LocationGroups,
+    LocationGroupsStops,
+    BookingRules,
+    Translations,
+    FeedInfo,
+    Attributions,
+}
+
+impl GtfsFile {
+    /// All the files of a dataset, in the same order as [`CSV_FILES`].
+    pub const ALL: &'static [GtfsFile] = &[
+        GtfsFile::Agency,
+        GtfsFile::Stops,
+        GtfsFile::Routes,
+        GtfsFile::Trips,
+        GtfsFile::StopTimes,
+        GtfsFile::Calendar,
+        GtfsFile::CalendarDates,
+        GtfsFile::FareAttributes,
+        GtfsFile::FareRules,
+        GtfsFile::Timeframes,
+        GtfsFile::FareMedia,
+        GtfsFile::FareProducts,
+        GtfsFile::FareLegRules,
+        GtfsFile::FareTransfers,
+        GtfsFile::Areas,
+        GtfsFile::StopsAreas,
+        GtfsFile::Networks,
+        GtfsFile::RoutesNetworks,
+        GtfsFile::Shapes,
+        GtfsFile::Frequencies,
+        GtfsFile::Transfers,
+        GtfsFile::Pathways,
+        GtfsFile::Levels,
+        GtfsFile::LocationGroups,
+        GtfsFile::LocationGroupsStops,
+        GtfsFile::BookingRules,
+        GtfsFile::Translations,
+        GtfsFile::FeedInfo,
+        GtfsFile::Attributions,
+    ];
+
+    /// Returns the name of the file as found in a dataset (e.g. `stop_times.txt`).
+    pub const fn file_name(&self) -> &'static str {
+        match self {
+            GtfsFile::Agency => "agency.txt",
+            GtfsFile::Stops => "stops.txt",
+            GtfsFile::Routes => "routes.txt",
+            GtfsFile::Trips => "trips.txt",
+            GtfsFile::StopTimes => "stop_times.txt",
+            GtfsFile::Calendar => "calendar.txt",
+            GtfsFile::CalendarDates => "calendar_dates.txt",
+            GtfsFile::FareAttributes => "fare_attributes.txt",
+            GtfsFile::FareRules => "fare_rules.txt",
+            GtfsFile::Timeframes => "timeframes.txt",
+            GtfsFile::FareMedia => "fare_media.txt",
+            GtfsFile::FareProducts => "fare_products.txt",
+            GtfsFile::FareLegRules => "fare_leg_rules.txt",
+            GtfsFile::FareTransfers => "fare_transfers.txt",
+            GtfsFile::Areas => "areas.txt",
+            GtfsFile::StopsAreas => "stops_areas.txt",
+            GtfsFile::Networks => "networks.txt",
+            GtfsFile::RoutesNetworks => "routes_networks.txt",
+            GtfsFile::Shapes => "shapes.txt",
+            GtfsFile::Frequencies => "frequencies.txt",
+            GtfsFile::Transfers => "transfers.txt",
+            GtfsFile::Pathways => "pathways.txt",
+            GtfsFile::Levels => "levels.txt",
+            GtfsFile::LocationGroups => "location_groups.txt",
+            GtfsFile::LocationGroupsStops => "location_groups_stops.txt",
+            GtfsFile::BookingRules => "booking_rules.txt",
+            GtfsFile::Translations => "translations.txt",
+            GtfsFile::FeedInfo => "feed_info.txt",
+            GtfsFile::Attributions => "attributions.txt",
+        }
+    }
+
+    /// Returns other names under which the file is found in datasets, such as the names used by
+    /// earlier revisions of the specification.
+    pub fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            GtfsFile::FareTransfers => &["fare_transfer_rules.txt"],
+            GtfsFile::StopsAreas => &["stop_areas.txt"],
+            _ => &[],
+        }
+    }
+
+    /// Returns the legacy names of columns of the file still found in datasets, along with the
+    /// column each stands for.
+    ///
+    /// They are read as the column they stand for when it is not also present, see
+    /// [`crate::ParseReport::deprecated_columns`]. A name is only listed once a former version
+    /// of the reference documents it; none of the files has one yet, so columns under another
+    /// name are reported as unknown.
+    pub fn column_aliases(&self) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+
+    /// Returns the [`GtfsFile`] matching the given file name, if any.
+    ///
+    /// Names are matched regardless of case, and [`GtfsFile::aliases`] are accepted.
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|file| {
+                std::iter::once(file.file_name())
+                    .chain(file.aliases().iter().copied())
+                    .any(|name| name.eq_ignore_ascii_case(file_name))
+            })
+            .copied()
+    }
+
+    /// Returns the columns identifying a record of the file in the keyed tables of [`Dataset`].
+    ///
+    /// Tables stored as lists, such as [`Dataset::agencies`], have no key columns.
+    pub fn key_columns(&self) -> &'static [&'static str] {
+        match self {
+            GtfsFile::Agency => Agency::PRIMARY_KEY,
+            GtfsFile::Stops => Stop::PRIMARY_KEY,
+            GtfsFile::Routes => Route::PRIMARY_KEY,
+            GtfsFile::Trips => Trip::PRIMARY_KEY,
+            GtfsFile::StopTimes => StopTime::PRIMARY_KEY,
+            GtfsFile::Calendar => Calendar::PRIMARY_KEY,
+            GtfsFile::CalendarDates => CalendarDate::PRIMARY_KEY,
+            GtfsFile::FareAttributes => FareAttribute::PRIMARY_KEY,
+            GtfsFile::FareRules => FareRule::PRIMARY_KEY,
+            GtfsFile::Timeframes => Timeframe::PRIMARY_KEY,
+            GtfsFile::FareMedia => FareMedia::PRIMARY_KEY,
+            GtfsFile::FareProducts => FareProduct::PRIMARY_KEY,
+            GtfsFile::FareLegRules => FareLegRule::PRIMARY_KEY,
+            GtfsFile::FareTransfers => FareTransferRule::PRIMARY_KEY,
+            GtfsFile::Areas => Area::PRIMARY_KEY,
+            GtfsFile::StopsAreas => StopArea::PRIMARY_KEY,
+            GtfsFile::Networks => Network::PRIMARY_KEY,
+            GtfsFile::RoutesNetworks => RouteNetwork::PRIMARY_KEY,
+            GtfsFile::Shapes => Shape::PRIMARY_KEY,
+            GtfsFile::Frequencies => Frequency::PRIMARY_KEY,
+            GtfsFile::Transfers => Transfer::PRIMARY_KEY,
+            GtfsFile::Pathways => Pathway::PRIMARY_KEY,
+            GtfsFile::Levels => Level::PRIMARY_KEY,
+            GtfsFile::LocationGroups => LocationGroup::PRIMARY_KEY,
+            GtfsFile::LocationGroupsStops => LocationGroupStop::PRIMARY_KEY,
+            GtfsFile::BookingRules => BookingRule::PRIMARY_KEY,
+            GtfsFile::Translations => Translation::PRIMARY_KEY,
+            GtfsFile::FeedInfo => FeedInfo::PRIMARY_KEY,
+            GtfsFile::Attributions => Attribution::PRIMARY_KEY,
+        }
+    }
+
+    /// Returns the columns of the file supported by this crate, in the order of the specification.
+    pub fn columns(&self) -> &'static [&'static str] {
+        match self {
+            GtfsFile::Agency => Agency::HEADERS,
+            GtfsFile::Stops => Stop::HEADERS,
+            GtfsFile::Routes => Route::HEADERS,
+            GtfsFile::Trips => Trip::HEADERS,
+            GtfsFile::StopTimes => StopTime::HEADERS,
+            GtfsFile::Calendar => Calendar::HEADERS,
+            GtfsFile::CalendarDates => CalendarDate::HEADERS,
+            GtfsFile::FareAttributes => FareAttribute::HEADERS,
+            GtfsFile::FareRules => FareRule::HEADERS,
+            GtfsFile::Timeframes => Timeframe::HEADERS,
+            GtfsFile::FareMedia => FareMedia::HEADERS,
+            GtfsFile::FareProducts => FareProduct::HEADERS,
+            GtfsFile::FareLegRules => FareLegRule::HEADERS,
+            GtfsFile::FareTransfers => FareTransferRule::HEADERS,
+            GtfsFile::Areas => Area::HEADERS,
+            GtfsFile::StopsAreas => StopArea::HEADERS,
+            GtfsFile::Networks => Network::HEADERS,
+            GtfsFile::RoutesNetworks => RouteNetwork::HEADERS,
+            GtfsFile::Shapes => Shape::HEADERS,
+            GtfsFile::Frequencies => Frequency::HEADERS,
+            GtfsFile::Transfers => Transfer::HEADERS,
+            GtfsFile::Pathways => Pathway::HEADERS,
+            GtfsFile::Levels => Level::HEADERS,
+            GtfsFile::LocationGroups => LocationGroup::HEADERS,
+            GtfsFile::LocationGroupsStops => LocationGroupStop::HEADERS,
+            GtfsFile::BookingRules => BookingRule::HEADERS,
+            GtfsFile::Translations => Translation::HEADERS,
+            GtfsFile::FeedInfo => FeedInfo::HEADERS,
+            GtfsFile::Attributions => Attribution::HEADERS,
+        }
+    }
+}
+
+impl std::fmt::Display for GtfsFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file_name())
+    }
+}
+
+impl serde::Serialize for GtfsFile {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.file_name())
+    }
+}
+
 pub struct Dataset {
     /// Transit agencies with service represented in this dataset.
     ///
@@ -214,6 +454,16 @@ pub struct Dataset {
     ///
     /// Primary key ([`Attribution::attribution_id`])
     pub attributions: Vec<Attribution>,
+    /// Tables that were not parsed, see [`ParseOptions`].
+    pub(crate) skipped_tables: HashSet<GtfsFile>,
+    /// Issues tolerated while parsing the dataset.
+    pub(crate) parse_report: ParseReport,
+    /// Identifiers shared by the records, see [`Dataset::memory_stats`].
+    pub(crate) interned_ids: HashSet<Arc<str>>,
+    /// Lines the records were parsed from, see [`Dataset::provenance`].
+    pub(crate) source_lines: SourceLines,
+    /// Records changed since they were last validated, see [`Dataset::revalidate_dirty`].
+    pub(crate) dirty: DirtyRecords,
 }
 
 impl Dataset {
@@ -248,10 +498,35 @@ impl Dataset {
             translations: vec![],
             feed_info: None,
             attributions: vec![],
+            skipped_tables: HashSet::new(),
+            parse_report: ParseReport::default(),
+            interned_ids: HashSet::new(),
+            source_lines: SourceLines::default(),
+            dirty: DirtyRecords::default(),
         }
     }
 
+    /// Returns `true` if the given table was loaded.
+    ///
+    /// A table is not loaded when it was skipped through [`ParseOptions`]; it is then empty,
+    /// regardless of its content in the source dataset. Note that [`Dataset::validate`] does not
+    /// account for skipped tables, records referencing them will be reported as invalid.
+    pub fn is_loaded(&self, table: GtfsFile) -> bool {
+        !self.skipped_tables.contains(&table)
+    }
+
+    /// Returns the issues tolerated while parsing the dataset, such as unknown columns.
+    ///
+    /// The report is empty for datasets that were not parsed from CSV files.
+    pub fn parse_report(&self) -> &ParseReport {
+        &self.parse_report
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "validate", skip_all))]
     pub fn validate(&self) -> Result<()> {
+        #[cfg(feature = "tracing")]
+        let stage = tracing::debug_span!("validate_records").entered();
+
         //
         // Validate individual fields.
         //
@@ -368,9 +643,20 @@ impl Dataset {
             attribution.validate()?;
         }
 
-        //
-        // Validate the dataset as a whole.
-        //
+        #[cfg(feature = "tracing")]
+        stage.exit();
+        self.validate_dataset()
+    }
+
+    /// Validates the dataset as a whole, e.g. the references between records, the second half
+    /// of [`Dataset::validate`].
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "validate_dataset", level = "debug", skip_all)
+    )]
+    pub(crate) fn validate_dataset(&self) -> Result<()> {
+        // Coordinates must be within range, and not (0, 0).
+        self.validate_coordinates(None)?;
 
         // If there is more than one agency:
         // - agency_id must be present and unique.
@@ -440,8 +726,19 @@ impl Dataset {
                 if let Some(parent_station_id) = &stop.parent_station {
                     let mut current_parent_id = parent_station_id.clone();
                     let mut parent_chain = vec![stop.clone().into()];
+                    let mut visited = HashSet::from([stop.stop_id.clone()]);
 
                     loop {
+                        if !visited.insert(current_parent_id.clone()) {
+                            return Err(DatasetValidationError::new_inconsistent_value(
+                                "parent_station".to_string(),
+                                current_parent_id.to_string(),
+                                Some("The parent station chain loops back on itself".to_string()),
+                                parent_chain,
+                            )
+                            .into());
+                        }
+
                         let parent = self.stops.get(&current_parent_id).ok_or_else(|| {
                             DatasetValidationError::new_foreign_key_not_found(
                                 "parent_station".to_string(),
@@ -537,11 +834,12 @@ impl Dataset {
 
                 // Validate continuous_pickup and continuous_drop_off
                 if route.continuous_pickup.is_some() || route.continuous_drop_off.is_some() {
-                    let stop_times = self.stop_times_get_all_from_route(&route.route_id);
-                    let has_pickup_drop_off_window = stop_times.iter().any(|stop_time| {
-                        stop_time.start_pickup_drop_off_window.is_some()
-                            || stop_time.end_pickup_drop_off_window.is_some()
-                    });
+                    let has_pickup_drop_off_window = self
+                        .iter_stop_times_for_route(&route.route_id)
+                        .any(|stop_time| {
+                            stop_time.start_pickup_drop_off_window.is_some()
+                                || stop_time.end_pickup_drop_off_window.is_some()
+                        });
                     if has_pickup_drop_off_window {
                         return Err(DatasetValidationError::new_inconsistent_value(
                             "continuous_pickup or continuous_drop_off".to_string(),
@@ -577,16 +875,41 @@ impl Dataset {
         // - service_id must exist in either calendar.txt or calendar_dates.txt.
         // - shape_id is required if the trip has a continuous pickup or drop-off behavior defined
         //   either in routes.txt or in stop_times.txt.
+        // - shape_id (if provided) must exist in shapes.txt.
         {
+            let shape_ids: HashSet<ShapeId> = self
+                .shapes
+                .iter()
+                .map(|shape| shape.key().0.clone())
+                .collect();
+            let continuous_trip_ids: HashSet<TripId> = self
+                .stop_times
+                .iter()
+                .filter(|stop_time| {
+                    stop_time.continuous_pickup.is_some() || stop_time.continuous_drop_off.is_some()
+                })
+                .map(|stop_time| stop_time.trip_id.clone())
+                .collect();
+
             for trip in self.trips.iter() {
+                // Validate shape_id reference (if specified)
+                if let Some(shape_id) = &trip.shape_id {
+                    if !shape_ids.contains(shape_id) {
+                        return Err(DatasetValidationError::new_foreign_key_not_found(
+                            "shape_id".to_string(),
+                            shape_id.to_string(),
+                            "shapes.txt".to_string(),
+                            vec![trip.clone().into()],
+                        )
+                        .into());
+                    }
+                }
+
                 if trip.shape_id.is_none() {
                     let as_continuous_pickup_or_drop_off =
-                        self.routes.iter().any(|route| {
+                        self.routes.get(&trip.route_id).is_some_and(|route| {
                             route.continuous_pickup.is_some() || route.continuous_drop_off.is_some()
-                        }) || self.stop_times.iter().any(|stop_time| {
-                            stop_time.start_pickup_drop_off_window.is_some()
-                                || stop_time.end_pickup_drop_off_window.is_some()
-                        });
+                        }) || continuous_trip_ids.contains(&trip.trip_id);
                     if as_continuous_pickup_or_drop_off {
                         return Err(DatasetValidationError::new_missing_value(
                             "shape_id".to_string(),
@@ -628,19 +951,22 @@ impl Dataset {
         // Validate stop_times:
         // - trip_id must reference a valid Trip.
         // - stop_id must reference a valid Stop (if specified).
-        // - arrival_time and departure_time must be in the correct order and format.
-        // - stop_sequence must increase along the trip.
+        // - location_group_id must reference a valid LocationGroup (if specified).
+        // - pickup_booking_rule_id and drop_off_booking_rule_id must reference a valid BookingRule (if specified).
+        // - a trip must not mix arrival/departure times and pickup/drop-off windows.
+        // - arrival_time and departure_time are required for the first and last stop times of a trip,
+        //   unless they use pickup/drop-off windows.
+        // - arrival_time must not be after departure_time, and times must not decrease along the trip.
         // - shape_dist_traveled must increase along the trip (if provided).
         {
-            let trip_stop_sequences = DashMap::new();
             let trip_shape_distances = DashMap::new();
 
-            // Sort all stop times by trip_id and arrival_time
+            // Sort all stop times by trip_id and stop_sequence
             let mut sorted_stop_times: Vec<_> = self.stop_times.iter().map(|v| v.clone()).collect();
             sorted_stop_times.sort_by(|a, b| {
                 a.trip_id
                     .cmp(&b.trip_id)
-                    .then_with(|| a.arrival_time.cmp(&b.arrival_time))
+                    .then_with(|| a.stop_sequence.cmp(&b.stop_sequence))
             });
 
             for stop_time in sorted_stop_times.iter() {
@@ -668,21 +994,39 @@ impl Dataset {
                     }
                 }
 
-                // Validate stop_sequence
-                let mut stop_sequences = trip_stop_sequences
-                    .entry(stop_time.trip_id.clone())
-                    .or_insert_with(Vec::new);
-                if !stop_sequences.is_empty()
-                    && stop_time.stop_sequence <= *stop_sequences.last().unwrap()
-                {
-                    return Err(DatasetValidationError::new_inconsistent_value(
-                        "stop_sequence".to_string(),
-                        stop_time.stop_sequence.to_string(),
-                        Some(format!("must increase along the trip. Found non-increasing sequence for trip_id: {:?}", stop_time.trip_id)),
-                        vec![stop_time.clone().into()],
-                    ).into());
+                // Validate location_group_id reference (if specified)
+                if let Some(location_group_id) = &stop_time.location_group_id {
+                    if !self.location_groups.contains_key(location_group_id) {
+                        return Err(DatasetValidationError::new_foreign_key_not_found(
+                            "location_group_id".to_string(),
+                            location_group_id.to_string(),
+                            "location_groups.txt".to_string(),
+                            vec![stop_time.clone().into()],
+                        )
+                        .into());
+                    }
+                }
+
+                // Validate pickup_booking_rule_id and drop_off_booking_rule_id references (if specified)
+                for (field_name, booking_rule_id) in [
+                    ("pickup_booking_rule_id", &stop_time.pickup_booking_rule_id),
+                    (
+                        "drop_off_booking_rule_id",
+                        &stop_time.drop_off_booking_rule_id,
+                    ),
+                ] {
+                    if let Some(booking_rule_id) = booking_rule_id {
+                        if !self.booking_rules.contains_key(booking_rule_id) {
+                            return Err(DatasetValidationError::new_foreign_key_not_found(
+                                field_name.to_string(),
+                                booking_rule_id.to_string(),
+                                "booking_rules.txt".to_string(),
+                                vec![stop_time.clone().into()],
+                            )
+                            .into());
+                        }
+                    }
                 }
-                stop_sequences.push(stop_time.stop_sequence);
 
                 // Validate shape_dist_traveled
                 if let Some(shape_dist) = stop_time.shape_dist_traveled {
@@ -702,6 +1046,10 @@ impl Dataset {
                     shape_distances.push(shape_dist);
                 }
             }
+
+            for trip_stop_times in sorted_stop_times.chunk_by(|a, b| a.trip_id == b.trip_id) {
+                Self::validate_trip_times(trip_stop_times)?;
+            }
         }
 
         // Validate calendar:
@@ -964,6 +1312,7 @@ impl Dataset {
         // - If provided, from_area_id and to_area_id must reference valid area_id values in areas.txt.
         // - If provided, from_timeframe_group_id and to_timeframe_group_id must reference valid timeframe_group_id values in timeframes.txt.
         // - fare_product_id must reference a valid fare_product_id in fare_products.txt.
+        // - The fare products of a network must share a currency.
         {
             let valid_network_ids: HashSet<_> = self
                 .routes
@@ -1064,6 +1413,34 @@ impl Dataset {
                     .into());
                 }
             }
+
+            // Validate that the fare products of a network share a currency
+            let mut network_currencies = HashMap::new();
+            for fare_leg_rule in &self.fare_leg_rules {
+                let Some(network_id) = &fare_leg_rule.network_id else {
+                    continue;
+                };
+                for fare_product in self.fare_products.iter().filter(|fare_product| {
+                    fare_product.fare_product_id == fare_leg_rule.fare_product_id
+                }) {
+                    let currency = *network_currencies
+                        .entry(network_id.clone())
+                        .or_insert(fare_product.currency);
+                    if currency != fare_product.currency {
+                        return Err(DatasetValidationError::new_inconsistent_value(
+                            "currency".to_string(),
+                            fare_product.currency.code().to_string(),
+                            Some(format!(
+                                "fare products of network {:?} must all be in {}",
+                                network_id.as_str(),
+                                currency.code()
+                            )),
+                            vec![fare_product.clone().into(), fare_leg_rule.clone().into()],
+                        )
+                        .into());
+                    }
+                }
+            }
         }
 
         // Validate fare_transfer_rules:
@@ -1232,7 +1609,36 @@ impl Dataset {
 
         // Validate frequencies.txt
         // - trip_id must reference a valid trip_id in trips.txt.
+        // - The windows of a trip must not overlap.
         {
+            let mut windows: HashMap<TripId, Vec<Frequency>> = HashMap::new();
+            for frequency in self.frequencies.iter() {
+                windows
+                    .entry(frequency.trip_id.clone())
+                    .or_default()
+                    .push(frequency.clone());
+            }
+            for frequencies in windows.values_mut() {
+                frequencies.sort_by_key(|frequency| frequency.start_time.as_secs());
+                for pair in frequencies.windows(2) {
+                    if pair[0].end_time.as_secs() > pair[1].start_time.as_secs() {
+                        let window = |frequency: &Frequency| {
+                            format!(
+                                "{}-{}",
+                                String::from(frequency.start_time),
+                                String::from(frequency.end_time)
+                            )
+                        };
+                        return Err(DatasetValidationError::new_overlapping_frequencies(
+                            pair[0].trip_id.to_string(),
+                            window(&pair[0]),
+                            window(&pair[1]),
+                            vec![pair[0].clone().into(), pair[1].clone().into()],
+                        )
+                        .into());
+                    }
+                }
+            }
             for frequency in self.frequencies.iter() {
                 // Validate trip_id reference
                 if !self.trips.contains_key(&frequency.trip_id) {
@@ -1251,6 +1657,7 @@ impl Dataset {
         // - from_stop_id and to_stop_id must reference valid stop_id values in stops.txt.
         // - from_route_id and to_route_id (if provided) must reference valid route_id values in routes.txt.
         // - from_trip_id and to_trip_id (if provided) must reference valid trip_id values in trips.txt.
+        // - The trips must belong to the routes, and serve the stops, given along with them.
         {
             for transfer in &self.transfers {
                 // Validate stop_id references
@@ -1324,6 +1731,82 @@ impl Dataset {
                         .into());
                     }
                 }
+
+                self.validate_transfer_consistency(transfer)?;
+            }
+        }
+
+        // Validate timed transfers between trips:
+        // - When both trips are given, a timed transfer (transfer_type=1) or a minimum-time transfer
+        //   (transfer_type=2) must be feasible: the from trip must arrive at from_stop_id, plus
+        //   min_transfer_time, no later than the to trip departs from to_stop_id.
+        // - Trips defined in frequencies.txt are not checked, as their times are only a template.
+        {
+            let frequency_based_trips: HashSet<TripId> = self
+                .frequencies
+                .iter()
+                .map(|frequency| frequency.key().0.clone())
+                .collect();
+            let format_secs =
+                |secs: u32| format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+
+            for transfer in &self.transfers {
+                let min_transfer_time = match transfer.transfer_type {
+                    TransferType::TimedTransferPoint => 0,
+                    TransferType::MinimumTimeTransferPoint => {
+                        transfer.min_transfer_time.unwrap_or(0)
+                    }
+                    _ => continue,
+                };
+                let (Some(from_stop_id), Some(to_stop_id), Some(from_trip_id), Some(to_trip_id)) = (
+                    &transfer.from_stop_id,
+                    &transfer.to_stop_id,
+                    &transfer.from_trip_id,
+                    &transfer.to_trip_id,
+                ) else {
+                    continue;
+                };
+                if frequency_based_trips.contains(from_trip_id)
+                    || frequency_based_trips.contains(to_trip_id)
+                {
+                    continue;
+                }
+
+                let earliest_arrival = self
+                    .iter_stop_times_for_trip(from_trip_id)
+                    .filter(|stop_time| stop_time.stop_id.as_ref() == Some(from_stop_id))
+                    .filter_map(|stop_time| stop_time.arrival_time.or(stop_time.departure_time))
+                    .map(|time| time.as_secs())
+                    .min();
+                let latest_departure = self
+                    .iter_stop_times_for_trip(to_trip_id)
+                    .filter(|stop_time| stop_time.stop_id.as_ref() == Some(to_stop_id))
+                    .filter_map(|stop_time| stop_time.departure_time.or(stop_time.arrival_time))
+                    .map(|time| time.as_secs())
+                    .max();
+
+                if let (Some(arrival), Some(departure)) = (earliest_arrival, latest_departure) {
+                    if arrival + min_transfer_time > departure {
+                        return Err(DatasetValidationError::new_invalid_combination(
+                            vec![
+                                "from_trip_id".to_string(),
+                                "to_trip_id".to_string(),
+                                "min_transfer_time".to_string(),
+                            ],
+                            Some(format!(
+                                "the transfer can never be made: {} arrives at {} at {}, {} departs from {} at {}",
+                                from_trip_id,
+                                from_stop_id,
+                                format_secs(arrival),
+                                to_trip_id,
+                                to_stop_id,
+                                format_secs(departure)
+                            )),
+                            vec![transfer.clone().into()],
+                        )
+                        .into());
+                    }
+                }
             }
         }
 
@@ -1653,11 +2136,13 @@ impl Dataset {
                             }
                         }
                         if let Some(record_sub_id) = &translation.record_sub_id {
-                            if !self.stop_times.iter().any(|stop_time| {
-                                stop_time.stop_sequence
-                                    == u32::from_str(record_sub_id.as_str())
-                                        .expect("Could not parse stop_sequence from record_sub_id")
-                            }) {
+                            let stop_sequence = u32::from_str(record_sub_id.as_str())
+                                .map_err(|_| invalid_record_sub_id(translation, "stop_sequence"))?;
+                            if !self
+                                .stop_times
+                                .iter()
+                                .any(|stop_time| stop_time.stop_sequence == stop_sequence)
+                            {
                                 return Err(DatasetValidationError::new_foreign_key_not_found(
                                     "stop_sequence".to_string(),
                                     record_sub_id.to_string(),
@@ -1808,11 +2293,13 @@ impl Dataset {
                             }
                         }
                         if let Some(record_sub_id) = &translation.record_sub_id {
-                            if !self.calendar_dates.iter().any(|calendar_date| {
-                                calendar_date.date
-                                    == NaiveDate::from_str(record_sub_id.as_str())
-                                        .expect("Could not parse NaiveDate from record_sub_id")
-                            }) {
+                            let date = NaiveDate::parse_from_str(record_sub_id.as_str(), "%Y%m%d")
+                                .map_err(|_| invalid_record_sub_id(translation, "date"))?;
+                            if !self
+                                .calendar_dates
+                                .iter()
+                                .any(|calendar_date| calendar_date.date == date)
+                            {
                                 return Err(DatasetValidationError::new_foreign_key_not_found(
                                     "date".to_string(),
                                     record_sub_id.to_string(),
@@ -1922,12 +2409,13 @@ impl Dataset {
                             }
                         }
                         if let Some(record_sub_id) = &translation.record_sub_id {
-                            if !self.frequencies.iter().any(|frequency| {
-                                frequency.start_time
-                                    == NaiveServiceTime::try_from(record_sub_id.as_str()).expect(
-                                        "Could not parse NaiveServiceTime from record_sub_id",
-                                    )
-                            }) {
+                            let start_time = NaiveServiceTime::try_from(record_sub_id.as_str())
+                                .map_err(|_| invalid_record_sub_id(translation, "start_time"))?;
+                            if !self
+                                .frequencies
+                                .iter()
+                                .any(|frequency| frequency.start_time == start_time)
+                            {
                                 return Err(DatasetValidationError::new_foreign_key_not_found(
                                     "start_time".to_string(),
                                     record_sub_id.to_string(),
@@ -2146,290 +2634,812 @@ impl Dataset {
         Ok(())
     }
 
+    /// Checks the times of the stop times of a trip, sorted by `stop_sequence`.
+    fn validate_trip_times(stop_times: &[StopTime]) -> Result<()> {
+        let uses_window = |stop_time: &StopTime| {
+            stop_time.start_pickup_drop_off_window.is_some()
+                || stop_time.end_pickup_drop_off_window.is_some()
+        };
+        // GTFS-Flex trips are either scheduled with times or with windows, not both.
+        let window_stop_time = stop_times.iter().find(|stop_time| uses_window(stop_time));
+        let timed_stop_time = stop_times.iter().find(|stop_time| {
+            stop_time.arrival_time.is_some() || stop_time.departure_time.is_some()
+        });
+        if let (Some(window_stop_time), Some(timed_stop_time)) = (window_stop_time, timed_stop_time)
+        {
+            return Err(DatasetValidationError::new_invalid_combination(
+                vec![
+                    "arrival_time".to_string(),
+                    "departure_time".to_string(),
+                    "start_pickup_drop_off_window".to_string(),
+                    "end_pickup_drop_off_window".to_string(),
+                ],
+                Some(
+                    "times and pickup/drop-off windows must not be mixed within a trip".to_string(),
+                ),
+                vec![
+                    timed_stop_time.clone().into(),
+                    window_stop_time.clone().into(),
+                ],
+            )
+            .into());
+        }
+
+        let endpoints = [stop_times.first(), stop_times.last()];
+        for stop_time in endpoints.into_iter().flatten() {
+            if uses_window(stop_time) {
+                continue;
+            }
+            let missing_field = match (stop_time.arrival_time, stop_time.departure_time) {
+                (None, _) => "arrival_time",
+                (_, None) => "departure_time",
+                _ => continue,
+            };
+            return Err(DatasetValidationError::new_missing_value(
+                missing_field.to_string(),
+                Some("is required for the first and last stop times of a trip".to_string()),
+                vec![stop_time.clone().into()],
+            )
+            .into());
+        }
+
+        let mut previous: Option<(&StopTime, NaiveServiceTime)> = None;
+        for stop_time in stop_times {
+            if let (Some(arrival_time), Some(departure_time)) =
+                (stop_time.arrival_time, stop_time.departure_time)
+            {
+                if arrival_time.as_secs() > departure_time.as_secs() {
+                    return Err(DatasetValidationError::new_inconsistent_value(
+                        "arrival_time".to_string(),
+                        String::from(arrival_time),
+                        Some("must not be after departure_time".to_string()),
+                        vec![stop_time.clone().into()],
+                    )
+                    .into());
+                }
+            }
+            if let Some(arrival_time) = stop_time.arrival_time.or(stop_time.departure_time) {
+                if let Some((previous_stop_time, departure_time)) = previous {
+                    if arrival_time.as_secs() < departure_time.as_secs() {
+                        return Err(DatasetValidationError::new_inconsistent_value(
+                            "arrival_time".to_string(),
+                            String::from(arrival_time),
+                            Some(format!(
+                                "must not be before the departure_time {} of stop_sequence {}",
+                                String::from(departure_time),
+                                previous_stop_time.stop_sequence
+                            )),
+                            vec![previous_stop_time.clone().into(), stop_time.clone().into()],
+                        )
+                        .into());
+                    }
+                }
+            }
+            if let Some(departure_time) = stop_time.departure_time.or(stop_time.arrival_time) {
+                previous = Some((stop_time, departure_time));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the trips of a transfer belong to its routes and serve its stops, or the
+    /// stops of its stations.
+    pub(crate) fn validate_transfer_consistency(&self, transfer: &Transfer) -> Result<()> {
+        let ends = [
+            (
+                "from",
+                &transfer.from_trip_id,
+                &transfer.from_route_id,
+                &transfer.from_stop_id,
+            ),
+            (
+                "to",
+                &transfer.to_trip_id,
+                &transfer.to_route_id,
+                &transfer.to_stop_id,
+            ),
+        ];
+        for (end, trip_id, route_id, stop_id) in ends {
+            let Some(trip) = trip_id.as_ref().and_then(|trip_id| self.trips.get(trip_id)) else {
+                continue;
+            };
+            if let Some(route_id) = route_id {
+                if trip.route_id != *route_id {
+                    return Err(DatasetValidationError::new_inconsistent_value(
+                        format!("{end}_route_id"),
+                        route_id.to_string(),
+                        Some(format!(
+                            "{end}_trip_id {} belongs to route {}",
+                            trip.trip_id, trip.route_id
+                        )),
+                        vec![transfer.clone().into()],
+                    )
+                    .into());
+                }
+            }
+            if let Some(stop_id) = stop_id {
+                let serves_stop = self
+                    .iter_stop_times_for_trip(&trip.trip_id)
+                    .any(|stop_time| {
+                        stop_time.stop_id.as_ref().is_some_and(|served_id| {
+                            served_id == stop_id
+                                || self.stops.get(served_id).is_some_and(|stop| {
+                                    stop.parent_station.as_ref() == Some(stop_id)
+                                })
+                        })
+                    });
+                if !serves_stop {
+                    return Err(DatasetValidationError::new_inconsistent_value(
+                        format!("{end}_stop_id"),
+                        stop_id.to_string(),
+                        Some(format!("is not served by {end}_trip_id {}", trip.trip_id)),
+                        vec![transfer.clone().into()],
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a dataset from the CSV files of the directory `dir`.
+    ///
+    /// Not available on `wasm32` targets, which have no file system: use
+    /// `Dataset::from_zip_bytes` (`zip` feature) there.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn from_csv(dir: &Path) -> Result<Self> {
-        // Get all files in the directory matching the CSV_FILES
-        let files = std::fs::read_dir(dir)
+        Self::from_csv_with_options(dir, &ParseOptions::default())
+    }
+
+    /// Same as [`Dataset::from_csv`], but only parses the tables selected by `options`.
+    ///
+    /// Files are found as described by [`GtfsFile::from_file_name`]. When several files match
+    /// the same table, only the one named exactly [`GtfsFile::file_name`] is parsed, or else the
+    /// first one in alphabetical order.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_csv_with_options(dir: &Path, options: &ParseOptions) -> Result<Self> {
+        Self::from_csv_files(Self::csv_file_names(dir)?, options, |file_name| {
+            let file = std::fs::File::open(dir.join(file_name))
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+            Ok(Box::new(file))
+        })
+    }
+
+    /// Returns the names of the files in `dir`, ignoring directories and non UTF-8 names.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn csv_file_names(dir: &Path) -> Result<Vec<String>> {
+        Ok(std::fs::read_dir(dir)
             .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?
             .filter_map(|entry| entry.ok())
             .filter(|entry| entry.path().is_file())
-            .filter(|entry| {
-                CSV_FILES
-                    .iter()
-                    .any(|file| entry.path().file_name().unwrap().to_str().unwrap() == *file)
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect())
+    }
+
+    /// Parses the files among `file_names` that match a table selected by `options`, reading
+    /// them from `open`.
+    ///
+    /// Files are parsed in the order of `file_names`, unless records are filtered.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "parse", skip_all))]
+    pub(crate) fn from_csv_files(
+        file_names: Vec<String>,
+        options: &ParseOptions,
+        mut open: impl FnMut(&str) -> Result<Box<dyn std::io::Read>>,
+    ) -> Result<Self> {
+        // Get all files matching the CSV_FILES, and that were not skipped.
+        let mut files: Vec<String> = file_names
+            .into_iter()
+            .filter(|file_name| {
+                GtfsFile::from_file_name(file_name).is_some_and(|file| options.loads(file))
             })
-            .collect::<Vec<_>>();
+            .collect();
+        // Only one file is parsed per table, preferring the exact name.
+        let mut kept_names: HashMap<GtfsFile, String> = HashMap::new();
+        for file_name in &files {
+            let Some(gtfs_file) = GtfsFile::from_file_name(file_name) else {
+                continue;
+            };
+            let rank = |name: &str| (name != gtfs_file.file_name(), name.to_string());
+            if kept_names
+                .get(&gtfs_file)
+                .is_none_or(|kept| rank(file_name) < rank(kept))
+            {
+                kept_names.insert(gtfs_file, file_name.clone());
+            }
+        }
+        files.retain(|file_name| kept_names.values().any(|kept| kept == file_name));
+        // Services and trips are parsed first, so that the records depending on them can be
+        // filtered as they are read.
+        if options.filters_records() {
+            files.sort_by_key(|file_name| GtfsFile::from_file_name(file_name).map(load_rank));
+        }
+        let mut load_filter = LoadFilter::default();
 
         // Read each file and parse it.
         let mut dataset = Self::default();
-        for file in files {
-            let file_name = file.file_name();
-            let file_name = file_name.to_str().unwrap();
-            let mut reader = csv::Reader::from_path(file.path())
-                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
-            let header = reader
-                .headers()
-                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?
-                .clone();
-            for record in reader.records() {
-                let record = record.map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
-                let position = record.position().expect("Could not get position of record");
-                let wrap_err_with_context = |f: &str| {
-                    format!(
-                        "Failed to deserialize {} at position: {:?}; Cell: {:?}",
-                        f,
-                        position,
-                        record.get(position.record() as usize).unwrap()
-                    )
-                };
-                match file_name {
-                    "agency.txt" => {
-                        let record: Agency = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.agencies.push(record);
-                    }
-                    "stops.txt" => {
-                        let record: Stop = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.stops.insert(record.stop_id.clone(), record);
-                    }
-                    "routes.txt" => {
-                        let record: Route = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.routes.insert(record.route_id.clone(), record);
-                    }
-                    "trips.txt" => {
-                        let record: Trip = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.trips.insert(record.trip_id.clone(), record);
-                    }
-                    "stop_times.txt" => {
-                        let record: StopTime = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset
-                            .stop_times
-                            .insert((record.trip_id.clone(), record.stop_sequence), record);
-                    }
-                    "calendar.txt" => {
-                        let record: Calendar = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.calendar.insert(record.service_id.clone(), record);
-                    }
-                    "calendar_dates.txt" => {
-                        let record: CalendarDate =
-                            record.deserialize(Some(&header)).map_err(|e| {
-                                ParseError::from(ParseErrorKind::from(e))
-                                    .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                            })?;
-                        dataset
-                            .calendar_dates
-                            .insert((record.service_id.clone(), record.date), record);
-                    }
-                    "fare_attributes.txt" => {
-                        let record: FareAttribute =
-                            record.deserialize(Some(&header)).map_err(|e| {
-                                ParseError::from(ParseErrorKind::from(e))
-                                    .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                            })?;
-                        dataset
-                            .fare_attributes
-                            .insert(record.fare_id.clone(), record);
-                    }
-                    "fare_rules.txt" => {
-                        let record: FareRule = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.fare_rules.push(record);
-                    }
-                    "timeframes.txt" => {
-                        let record: Timeframe = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.timeframes.push(record);
-                    }
-                    "fare_media.txt" => {
-                        let record: FareMedia = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset
-                            .fare_medias
-                            .insert(record.fare_media_id.clone(), record);
-                    }
-                    "fare_products.txt" => {
-                        let record: FareProduct =
-                            record.deserialize(Some(&header)).map_err(|e| {
-                                ParseError::from(ParseErrorKind::from(e))
-                                    .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                            })?;
-                        dataset.fare_products.insert(
-                            (record.fare_product_id.clone(), record.fare_media_id.clone()),
-                            record,
-                        );
-                    }
-                    "fare_leg_rules.txt" => {
-                        let record: FareLegRule =
-                            record.deserialize(Some(&header)).map_err(|e| {
-                                ParseError::from(ParseErrorKind::from(e))
-                                    .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                            })?;
-                        dataset.fare_leg_rules.push(record);
-                    }
-                    "fare_transfers.txt" => {
-                        let record: FareTransferRule =
-                            record.deserialize(Some(&header)).map_err(|e| {
-                                ParseError::from(ParseErrorKind::from(e))
-                                    .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                            })?;
-                        dataset.fare_transfers.push(record);
-                    }
-                    "areas.txt" => {
-                        let record: Area = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.areas.insert(record.area_id.clone(), record);
-                    }
-                    "stops_areas.txt" => {
-                        let record: StopArea = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.stops_areas.push(record);
-                    }
-                    "networks.txt" => {
-                        let record: Network = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.networks.insert(record.network_id.clone(), record);
-                    }
-                    "routes_networks.txt" => {
-                        let record: RouteNetwork =
-                            record.deserialize(Some(&header)).map_err(|e| {
-                                ParseError::from(ParseErrorKind::from(e))
-                                    .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                            })?;
-                        dataset
-                            .routes_networks
-                            .insert(record.route_id.clone(), record);
-                    }
-                    "shapes.txt" => {
-                        let record: Shape = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset
-                            .shapes
-                            .insert((record.shape_id.clone(), record.shape_pt_sequence), record);
-                    }
-                    "frequencies.txt" => {
-                        let record: Frequency = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset
-                            .frequencies
-                            .insert((record.trip_id.clone(), record.start_time), record);
-                    }
-                    "transfers.txt" => {
-                        let record: Transfer = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.transfers.push(record);
-                    }
-                    "pathways.txt" => {
-                        let record: Pathway = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.pathways.insert(record.pathway_id.clone(), record);
-                    }
-                    "levels.txt" => {
-                        let record: Level = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.levels.insert(record.level_id.clone(), record);
-                    }
-                    "location_groups.txt" => {
-                        let record: LocationGroup =
-                            record.deserialize(Some(&header)).map_err(|e| {
-                                ParseError::from(ParseErrorKind::from(e))
-                                    .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                            })?;
-                        dataset
-                            .location_groups
-                            .insert(record.location_group_id.clone(), record);
-                    }
-                    "location_groups_stops.txt" => {
-                        let record: LocationGroupStop =
-                            record.deserialize(Some(&header)).map_err(|e| {
-                                ParseError::from(ParseErrorKind::from(e))
-                                    .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                            })?;
-                        dataset.location_groups_stops.push(record);
-                    }
-                    "booking_rules.txt" => {
-                        let record: BookingRule =
-                            record.deserialize(Some(&header)).map_err(|e| {
-                                ParseError::from(ParseErrorKind::from(e))
-                                    .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                            })?;
-                        dataset
-                            .booking_rules
-                            .insert(record.booking_rule_id.clone(), record);
-                    }
-                    "translations.txt" => {
-                        let record: Translation =
-                            record.deserialize(Some(&header)).map_err(|e| {
-                                ParseError::from(ParseErrorKind::from(e))
-                                    .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                            })?;
-                        dataset.translations.push(record);
-                    }
-                    "feed_info.txt" => {
-                        let record: FeedInfo = record.deserialize(Some(&header)).map_err(|e| {
-                            ParseError::from(ParseErrorKind::from(e))
-                                .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                        })?;
-                        dataset.feed_info = Some(record);
-                    }
-                    "attributions.txt" => {
-                        let record: Attribution =
-                            record.deserialize(Some(&header)).map_err(|e| {
-                                ParseError::from(ParseErrorKind::from(e))
-                                    .with_context(ErrorContext(wrap_err_with_context(file_name)))
-                            })?;
-                        dataset.attributions.push(record);
-                    }
-                    _ => {}
+        let intern_scope = InternScope::enter(HashSet::new());
+        let _lenient_codes_scope = LenientCodesScope::enter(options.lenient_codes);
+        dataset.skipped_tables = GtfsFile::ALL
+            .iter()
+            .filter(|file| !options.loads(**file))
+            .copied()
+            .collect();
+        for file_name in &files {
+            let Some(gtfs_file) = GtfsFile::from_file_name(file_name) else {
+                continue;
+            };
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!("parse_file", file = %file_name).entered();
+            if file_name != gtfs_file.file_name() {
+                dataset
+                    .parse_report
+                    .renamed_files
+                    .insert(gtfs_file, file_name.to_string());
+            }
+            let mut reader = options.csv_backend.reader(open(file_name)?);
+            // Column names are matched regardless of surrounding whitespace and byte order marks.
+            let header: csv::StringRecord = reader
+                .read_header()?
+                .iter()
+                .map(|column| column.trim_start_matches('\u{feff}').trim())
+                .collect();
+            let header = dataset.resolve_column_aliases(gtfs_file, header);
+            let unknown_columns: Vec<String> = header
+                .iter()
+                .filter(|column| !gtfs_file.columns().contains(column))
+                .map(|column| column.to_string())
+                .collect();
+            if !unknown_columns.is_empty() {
+                if options.deny_unknown_columns {
+                    return Err(ParseError::from(ParseErrorKind::InvalidValue(format!(
+                        "unknown columns in {}: {}",
+                        file_name,
+                        unknown_columns.join(", ")
+                    )))
+                    .into());
+                }
+                dataset
+                    .parse_report
+                    .unknown_columns
+                    .insert(gtfs_file, unknown_columns);
+            }
+            let mut record = csv::StringRecord::new();
+            #[cfg(feature = "tracing")]
+            let (start, mut rows) = (std::time::Instant::now(), 0u64);
+            while reader.read_record(&mut record)? {
+                #[cfg(feature = "tracing")]
+                {
+                    rows += 1;
+                }
+                if options.filters_records()
+                    && !load_filter.keeps(&dataset, options, gtfs_file, &header, &record)
+                {
+                    continue;
                 }
+                dataset.insert_csv_record(gtfs_file, &header, &record, options)?;
             }
+            if options.filters_records() {
+                load_filter.finish_file(&dataset, options, gtfs_file);
+            }
+            #[cfg(feature = "tracing")]
+            {
+                let elapsed = start.elapsed().as_secs_f64();
+                tracing::debug!(
+                    rows,
+                    rows_per_sec = rows as f64 / elapsed.max(f64::EPSILON),
+                    "parsed {}",
+                    file_name
+                );
+            }
+        }
+        dataset.interned_ids = intern_scope.exit();
+        if !options.keep_source_lines {
+            dataset.source_lines = SourceLines::default();
         }
 
         Ok(dataset)
     }
 
+    /// Renames the columns of `header` found under one of the [`GtfsFile::column_aliases`],
+    /// reporting them in [`ParseReport::deprecated_columns`].
+    ///
+    /// A legacy column is left as is, and ignored as unknown, when the column it stands for is
+    /// also present.
+    fn resolve_column_aliases(
+        &mut self,
+        gtfs_file: GtfsFile,
+        header: csv::StringRecord,
+    ) -> csv::StringRecord {
+        let aliases = gtfs_file.column_aliases();
+        if !header
+            .iter()
+            .any(|column| aliases.iter().any(|(alias, _)| *alias == column))
+        {
+            return header;
+        }
+        let mut resolved: Vec<String> = header.iter().map(|column| column.to_string()).collect();
+        for (alias, name) in aliases {
+            let Some(index) = resolved.iter().position(|column| column == alias) else {
+                continue;
+            };
+            if resolved.iter().any(|column| column == name) {
+                continue;
+            }
+            resolved[index] = name.to_string();
+            self.parse_report.deprecated_columns.push(DeprecatedColumn {
+                file: gtfs_file,
+                column: alias.to_string(),
+                replaced_by: name.to_string(),
+            });
+        }
+        csv::StringRecord::from(resolved)
+    }
+
+    /// Parses a record of `gtfs_file` and inserts it into the dataset.
+    pub(crate) fn insert_csv_record(
+        &mut self,
+        gtfs_file: GtfsFile,
+        header: &csv::StringRecord,
+        record: &csv::StringRecord,
+        options: &ParseOptions,
+    ) -> Result<()> {
+        let lenient_record = match options.lenient_contacts {
+            true => self.drop_invalid_contacts(gtfs_file, header, record),
+            false => None,
+        };
+        let record = lenient_record.as_ref().unwrap_or(record);
+        let line = record.position().map(|position| position.line());
+        #[cfg(feature = "extensions")]
+        let extensions: std::collections::HashMap<String, String> = header
+            .iter()
+            .zip(record.iter())
+            .filter(|(column, value)| !value.is_empty() && !gtfs_file.columns().contains(column))
+            .map(|(column, value)| (column.to_string(), value.to_string()))
+            .collect();
+        match gtfs_file {
+            GtfsFile::Agency => {
+                let record: Agency = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.agencies.push(record);
+            }
+            GtfsFile::Stops => {
+                let record: Stop = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.stops,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::Routes => {
+                let record: Route = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.routes,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::Trips => {
+                let record: Trip = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.trips,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::StopTimes => {
+                let record: StopTime = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.stop_times,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::Calendar => {
+                let record: Calendar = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.calendar,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::CalendarDates => {
+                let record: CalendarDate = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.calendar_dates,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::FareAttributes => {
+                let record: FareAttribute = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.fare_attributes,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::FareRules => {
+                let record: FareRule = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.fare_rules.push(record);
+            }
+            GtfsFile::Timeframes => {
+                let record: Timeframe = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.timeframes.push(record);
+            }
+            GtfsFile::FareMedia => {
+                let record: FareMedia = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.fare_medias,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::FareProducts => {
+                let record: FareProduct = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.fare_products,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::FareLegRules => {
+                let record: FareLegRule = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.fare_leg_rules.push(record);
+            }
+            GtfsFile::FareTransfers => {
+                let record: FareTransferRule = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.fare_transfers.push(record);
+            }
+            GtfsFile::Areas => {
+                let record: Area = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.areas,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::StopsAreas => {
+                let record: StopArea = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.stops_areas.push(record);
+            }
+            GtfsFile::Networks => {
+                let record: Network = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.networks,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::RoutesNetworks => {
+                let record: RouteNetwork = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.routes_networks,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::Shapes => {
+                let record: Shape = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.shapes,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::Frequencies => {
+                let record: Frequency = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.frequencies,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::Transfers => {
+                let record: Transfer = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.transfers.push(record);
+            }
+            GtfsFile::Pathways => {
+                let record: Pathway = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.pathways,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::Levels => {
+                let record: Level = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.levels,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::LocationGroups => {
+                let record: LocationGroup = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.location_groups,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::LocationGroupsStops => {
+                let record: LocationGroupStop = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.location_groups_stops.push(record);
+            }
+            GtfsFile::BookingRules => {
+                let record: BookingRule = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.insert_keyed(
+                    gtfs_file,
+                    |dataset| &dataset.booking_rules,
+                    record.key(),
+                    record,
+                    line,
+                    options,
+                )?;
+            }
+            GtfsFile::Translations => {
+                let record: Translation = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.translations.push(record);
+            }
+            GtfsFile::FeedInfo => {
+                let record: FeedInfo = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.feed_info = Some(record);
+            }
+            GtfsFile::Attributions => {
+                let record: Attribution = parse_csv_record(header, record)?;
+                #[cfg(feature = "extensions")]
+                let record = with_extensions(record, extensions);
+                self.attributions.push(record);
+            }
+        }
+        if gtfs_file.key_columns().is_empty() {
+            let index = match gtfs_file {
+                GtfsFile::FeedInfo => 0,
+                _ => self.source_lines.listed.get(&gtfs_file).map_or(0, Vec::len),
+            };
+            if let Some(record) = self.listed_record(gtfs_file, index) {
+                self.source_lines.push_listed(&record, line);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Blanks the optional URLs, emails and phone numbers of `record` that are not valid,
+    /// reporting them in [`ParseReport::invalid_contacts`].
+    ///
+    /// Returns `None` if every value is valid, so that the record is parsed as is.
+    fn drop_invalid_contacts(
+        &mut self,
+        gtfs_file: GtfsFile,
+        header: &csv::StringRecord,
+        record: &csv::StringRecord,
+    ) -> Option<csv::StringRecord> {
+        let invalid: Vec<usize> = header
+            .iter()
+            .zip(record.iter())
+            .enumerate()
+            .filter(|(_, (column, value))| {
+                !value.is_empty() && is_valid_contact(column, value) == Some(false)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        if invalid.is_empty() {
+            return None;
+        }
+
+        let line = record.position().map(|position| position.line());
+        for index in &invalid {
+            self.parse_report.invalid_contacts.push(InvalidContact {
+                file: gtfs_file,
+                column: header[*index].to_string(),
+                value: record[*index].to_string(),
+                line,
+            });
+        }
+        let mut cleaned: csv::StringRecord = record
+            .iter()
+            .enumerate()
+            .map(|(index, value)| match invalid.contains(&index) {
+                true => "",
+                false => value,
+            })
+            .collect();
+        cleaned.set_position(record.position().cloned());
+        Some(cleaned)
+    }
+
+    /// Inserts a parsed record into a keyed table, applying [`ParseOptions::duplicate_keys`] when
+    /// a record with the same key was already parsed.
+    fn insert_keyed<K: Hash + Eq + ToSourceKey, V: Clone + Into<Schema>>(
+        &mut self,
+        gtfs_file: GtfsFile,
+        table: impl Fn(&Self) -> &DashMap<K, V>,
+        key: K,
+        record: V,
+        line: Option<u64>,
+        options: &ParseOptions,
+    ) -> Result<()> {
+        let source_key = key.source_key(gtfs_file);
+
+        let Some(previous) = table(self).get(&key).map(|previous| previous.clone()) else {
+            if let Some(line) = line {
+                self.source_lines.keyed.insert(source_key, line);
+            }
+            table(self).insert(key, record);
+            return Ok(());
+        };
+
+        let previous: Schema = previous.into();
+        let key_value = key_value(gtfs_file, &previous);
+        let previous_line = self.source_lines.keyed.get(&source_key).copied();
+        match options.duplicate_key_policy {
+            DuplicateKeyPolicy::Error => {
+                let lines = match (previous_line, line) {
+                    (Some(previous_line), Some(line)) => {
+                        format!(" in {} at lines {} and {}", gtfs_file, previous_line, line)
+                    }
+                    _ => format!(" in {}", gtfs_file),
+                };
+                return Err(DatasetValidationError::new_primary_key_not_unique(
+                    gtfs_file.key_columns().join(", "),
+                    format!("{}{}", key_value, lines),
+                    vec![previous, record.into()],
+                )
+                .into());
+            }
+            DuplicateKeyPolicy::KeepFirst => {}
+            DuplicateKeyPolicy::KeepLast => {
+                if let Some(line) = line {
+                    self.source_lines.keyed.insert(source_key, line);
+                }
+                table(self).insert(key, record);
+            }
+        }
+        self.parse_report.duplicate_keys.push(DuplicateKey {
+            file: gtfs_file,
+            key: key_value,
+            previous_line,
+            line,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the contact information of an agency.
+    pub fn agency_contact(&self, agency_id: &AgencyId) -> Option<AgencyContact> {
+        self.agencies
+            .iter()
+            .find(|agency| agency.agency_id.as_ref() == Some(agency_id))
+            .map(Agency::contact)
+    }
+
+    /// Returns the parent station of a stop, `None` if the stop is unknown, has no parent or
+    /// its parent is not in stops.txt.
     pub fn stop_get_parent_station(&self, stop_id: &StopId) -> Option<Stop> {
+        let parent_station = self.stops.get(stop_id)?.parent_station.clone()?;
         self.stops
+            .get(&parent_station)
+            .map(|stop| stop.value().clone())
+    }
+
+    /// Returns the stops whose parent station is `stop_id`, sorted by id, see
+    /// [`Dataset::children_of`].
+    pub fn stop_get_children(&self, stop_id: &StopId) -> Vec<Stop> {
+        let mut children: Vec<Stop> = self
+            .stops
             .iter()
-            .find(|stop| stop.parent_station == Some(stop_id.clone()))
-            .map(|stop| stop.clone().into())
+            .filter(|stop| stop.parent_station.as_ref() == Some(stop_id))
+            .map(|stop| stop.value().clone())
+            .collect();
+        children.sort_by(|a, b| a.stop_id.as_str().cmp(b.stop_id.as_str()));
+        children
     }
 
     pub fn stop_get_level(&self, stop_id: &StopId) -> Option<Level> {
@@ -2443,41 +3453,395 @@ impl Dataset {
             .map(|level| level.clone().into())
     }
 
+    #[deprecated(
+        note = "use `Dataset::iter_location_groups_for_stop`, which does not clone records"
+    )]
     pub fn stop_get_all_location_groups(&self, stop_id: &StopId) -> Vec<LocationGroup> {
-        let location_groups_ids: Vec<&LocationGroupId> = self
-            .location_groups_stops
-            .iter()
-            .filter(|location_group| location_group.stop_id == *stop_id)
-            .map(|location_group| location_group.location_group_id.as_wrapper())
-            .collect();
-
-        location_groups_ids
-            .iter()
-            .map(|location_group_id| self.location_groups.get(location_group_id).unwrap().clone())
+        self.iter_location_groups_for_stop(stop_id)
+            .map(|location_group| location_group.clone())
             .collect()
     }
 
+    #[deprecated(note = "use `Dataset::iter_trips_for_route`, which does not clone records")]
     pub fn trip_get_all_from_route(&self, route_id: &RouteId) -> Vec<Trip> {
-        self.trips
-            .iter()
-            .filter(|trip| trip.route_id == *route_id)
-            .map(|trip| trip.clone().into())
+        self.iter_trips_for_route(route_id)
+            .map(|trip| trip.clone())
             .collect()
     }
 
+    #[deprecated(note = "use `Dataset::iter_stop_times_for_trip`, which does not clone records")]
     pub fn stop_times_get_all_from_trip(&self, trip_id: &TripId) -> Vec<StopTime> {
-        self.stop_times
-            .iter()
-            .filter(|stop_time| stop_time.trip_id == *trip_id)
-            .map(|stop_time| stop_time.clone().into())
+        self.iter_stop_times_for_trip(trip_id)
+            .map(|stop_time| stop_time.clone())
             .collect()
     }
 
+    #[deprecated(note = "use `Dataset::iter_stop_times_for_route`, which does not clone records")]
     pub fn stop_times_get_all_from_route(&self, route_id: &RouteId) -> Vec<StopTime> {
-        let trips = self.trip_get_all_from_route(route_id);
-        trips
-            .iter()
-            .flat_map(|trip| self.stop_times_get_all_from_trip(&trip.trip_id))
+        self.iter_stop_times_for_route(route_id)
+            .map(|stop_time| stop_time.clone())
             .collect()
     }
+
+    /// Iterates over the location groups a stop belongs to.
+    ///
+    /// Location groups referenced by `location_group_stops.txt` but missing from
+    /// `location_groups.txt` are skipped. The items are guards holding a read lock on
+    /// [`Dataset::location_groups`], so the dataset must not be mutated until they are dropped.
+    pub fn iter_location_groups_for_stop<'a>(
+        &'a self,
+        stop_id: &'a StopId,
+    ) -> impl Iterator<Item = Ref<'a, LocationGroupId, LocationGroup>> + 'a {
+        self.location_groups_stops
+            .iter()
+            .filter(move |location_group_stop| location_group_stop.stop_id == *stop_id)
+            .filter_map(|location_group_stop| {
+                self.location_groups
+                    .get(&location_group_stop.location_group_id)
+            })
+    }
+
+    /// Iterates over the trips of a route, in no particular order.
+    ///
+    /// The items are guards holding a read lock on [`Dataset::trips`], so the dataset must not be
+    /// mutated until they are dropped.
+    pub fn iter_trips_for_route<'a>(
+        &'a self,
+        route_id: &'a RouteId,
+    ) -> impl Iterator<Item = RefMulti<'a, TripId, Trip>> + 'a {
+        self.trips
+            .iter()
+            .filter(move |trip| trip.route_id == *route_id)
+    }
+
+    /// Iterates over the stop times of a trip, in no particular order.
+    ///
+    /// The items are guards holding a read lock on [`Dataset::stop_times`], so the dataset must
+    /// not be mutated until they are dropped.
+    pub fn iter_stop_times_for_trip<'a>(
+        &'a self,
+        trip_id: &'a TripId,
+    ) -> impl Iterator<Item = RefMulti<'a, (TripId, u32), StopTime>> + 'a {
+        self.stop_times
+            .iter()
+            .filter(move |stop_time| stop_time.trip_id == *trip_id)
+    }
+
+    /// Iterates over the stop times of all the trips of a route, in no particular order.
+    ///
+    /// The items are guards holding a read lock on [`Dataset::stop_times`], so the dataset must
+    /// not be mutated until they are dropped.
+    pub fn iter_stop_times_for_route<'a>(
+        &'a self,
+        route_id: &'a RouteId,
+    ) -> impl Iterator<Item = RefMulti<'a, (TripId, u32), StopTime>> + 'a {
+        let trip_ids: HashSet<TripId> = self
+            .iter_trips_for_route(route_id)
+            .map(|trip| trip.trip_id.clone())
+            .collect();
+        self.stop_times
+            .iter()
+            .filter(move |stop_time| trip_ids.contains(&stop_time.trip_id))
+    }
+
+    /// Iterates over every record of the dataset as [`Schema`] values, along with the file they belong to.
+    ///
+    /// Tables are visited in the order of [`GtfsFile::ALL`]. Records are cloned as they are yielded;
+    /// the order of records within a keyed table is unspecified.
+    ///
+    /// The iterator holds read locks on the underlying maps while it walks them, so the dataset must not
+    /// be mutated until it is dropped.
+    pub fn iter_all(&self) -> impl Iterator<Item = (GtfsFile, Schema)> + '_ {
+        GtfsFile::ALL
+            .iter()
+            .flat_map(|file| self.iter_table(*file).map(|record| (*file, record)))
+    }
+
+    /// Returns the number of records of a table.
+    pub(crate) fn table_len(&self, file: GtfsFile) -> usize {
+        match file {
+            GtfsFile::Agency => self.agencies.len(),
+            GtfsFile::Stops => self.stops.len(),
+            GtfsFile::Routes => self.routes.len(),
+            GtfsFile::Trips => self.trips.len(),
+            GtfsFile::StopTimes => self.stop_times.len(),
+            GtfsFile::Calendar => self.calendar.len(),
+            GtfsFile::CalendarDates => self.calendar_dates.len(),
+            GtfsFile::FareAttributes => self.fare_attributes.len(),
+            GtfsFile::FareRules => self.fare_rules.len(),
+            GtfsFile::Timeframes => self.timeframes.len(),
+            GtfsFile::FareMedia => self.fare_medias.len(),
+            GtfsFile::FareProducts => self.fare_products.len(),
+            GtfsFile::FareLegRules => self.fare_leg_rules.len(),
+            GtfsFile::FareTransfers => self.fare_transfers.len(),
+            GtfsFile::Areas => self.areas.len(),
+            GtfsFile::StopsAreas => self.stops_areas.len(),
+            GtfsFile::Networks => self.networks.len(),
+            GtfsFile::RoutesNetworks => self.routes_networks.len(),
+            GtfsFile::Shapes => self.shapes.len(),
+            GtfsFile::Frequencies => self.frequencies.len(),
+            GtfsFile::Transfers => self.transfers.len(),
+            GtfsFile::Pathways => self.pathways.len(),
+            GtfsFile::Levels => self.levels.len(),
+            GtfsFile::LocationGroups => self.location_groups.len(),
+            GtfsFile::LocationGroupsStops => self.location_groups_stops.len(),
+            GtfsFile::BookingRules => self.booking_rules.len(),
+            GtfsFile::Translations => self.translations.len(),
+            GtfsFile::FeedInfo => usize::from(self.feed_info.is_some()),
+            GtfsFile::Attributions => self.attributions.len(),
+        }
+    }
+
+    /// Iterates over the records of a table as [`Schema`] values, cloning them as they are yielded.
+    /// Returns the record at `index` of a table stored as a list, `None` for the keyed tables.
+    pub(crate) fn listed_record(&self, file: GtfsFile, index: usize) -> Option<Schema> {
+        match file {
+            GtfsFile::Agency => self.agencies.get(index).cloned().map(Schema::from),
+            GtfsFile::FareRules => self.fare_rules.get(index).cloned().map(Schema::from),
+            GtfsFile::Timeframes => self.timeframes.get(index).cloned().map(Schema::from),
+            GtfsFile::FareLegRules => self.fare_leg_rules.get(index).cloned().map(Schema::from),
+            GtfsFile::FareTransfers => self.fare_transfers.get(index).cloned().map(Schema::from),
+            GtfsFile::StopsAreas => self.stops_areas.get(index).cloned().map(Schema::from),
+            GtfsFile::Transfers => self.transfers.get(index).cloned().map(Schema::from),
+            GtfsFile::LocationGroupsStops => self
+                .location_groups_stops
+                .get(index)
+                .cloned()
+                .map(Schema::from),
+            GtfsFile::Translations => self.translations.get(index).cloned().map(Schema::from),
+            GtfsFile::FeedInfo => self
+                .feed_info
+                .clone()
+                .filter(|_| index == 0)
+                .map(Schema::from),
+            GtfsFile::Attributions => self.attributions.get(index).cloned().map(Schema::from),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn iter_table(&self, file: GtfsFile) -> Box<dyn Iterator<Item = Schema> + '_> {
+        match file {
+            GtfsFile::Agency => Box::new(
+                self.agencies
+                    .iter()
+                    .map(|record| Schema::from(record.clone())),
+            ),
+            GtfsFile::Stops => Box::new(
+                self.stops
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::Routes => Box::new(
+                self.routes
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::Trips => Box::new(
+                self.trips
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::StopTimes => Box::new(
+                self.stop_times
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::Calendar => Box::new(
+                self.calendar
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::CalendarDates => Box::new(
+                self.calendar_dates
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::FareAttributes => Box::new(
+                self.fare_attributes
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::FareRules => Box::new(
+                self.fare_rules
+                    .iter()
+                    .map(|record| Schema::from(record.clone())),
+            ),
+            GtfsFile::Timeframes => Box::new(
+                self.timeframes
+                    .iter()
+                    .map(|record| Schema::from(record.clone())),
+            ),
+            GtfsFile::FareMedia => Box::new(
+                self.fare_medias
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::FareProducts => Box::new(
+                self.fare_products
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::FareLegRules => Box::new(
+                self.fare_leg_rules
+                    .iter()
+                    .map(|record| Schema::from(record.clone())),
+            ),
+            GtfsFile::FareTransfers => Box::new(
+                self.fare_transfers
+                    .iter()
+                    .map(|record| Schema::from(record.clone())),
+            ),
+            GtfsFile::Areas => Box::new(
+                self.areas
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::StopsAreas => Box::new(
+                self.stops_areas
+                    .iter()
+                    .map(|record| Schema::from(record.clone())),
+            ),
+            GtfsFile::Networks => Box::new(
+                self.networks
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::RoutesNetworks => Box::new(
+                self.routes_networks
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::Shapes => Box::new(
+                self.shapes
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::Frequencies => Box::new(
+                self.frequencies
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::Transfers => Box::new(
+                self.transfers
+                    .iter()
+                    .map(|record| Schema::from(record.clone())),
+            ),
+            GtfsFile::Pathways => Box::new(
+                self.pathways
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::Levels => Box::new(
+                self.levels
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::LocationGroups => Box::new(
+                self.location_groups
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::LocationGroupsStops => Box::new(
+                self.location_groups_stops
+                    .iter()
+                    .map(|record| Schema::from(record.clone())),
+            ),
+            GtfsFile::BookingRules => Box::new(
+                self.booking_rules
+                    .iter()
+                    .map(|record| Schema::from(record.value().clone())),
+            ),
+            GtfsFile::Translations => Box::new(
+                self.translations
+                    .iter()
+                    .map(|record| Schema::from(record.clone())),
+            ),
+            GtfsFile::FeedInfo => Box::new(
+                self.feed_info
+                    .iter()
+                    .map(|record| Schema::from(record.clone())),
+            ),
+            GtfsFile::Attributions => Box::new(
+                self.attributions
+                    .iter()
+                    .map(|record| Schema::from(record.clone())),
+            ),
+        }
+    }
+}
+
+/// Returns whether the value of an optional URL, email or phone number column is valid, `None`
+/// for the other columns, see [`ParseOptions::lenient_contacts`].
+fn is_valid_contact(column: &str, value: &str) -> Option<bool> {
+    match column {
+        "agency_fare_url" | "stop_url" | "route_url" | "info_url" | "booking_url"
+        | "feed_contact_url" | "attribution_url" => Some(url::Url::parse(value).is_ok()),
+        "agency_email" | "feed_contact_email" | "attribution_email" => {
+            Some(email_address::EmailAddress::from_str(value).is_ok())
+        }
+        "agency_phone" | "phone_number" | "attribution_phone" => {
+            Some(Phone::from_str(value).is_ok())
+        }
+        _ => None,
+    }
+}
+
+/// Returns the error of a translation whose record_sub_id is not a valid `field_name`.
+fn invalid_record_sub_id(translation: &Translation, field_name: &str) -> Error {
+    DatasetValidationError::new_inconsistent_value(
+        "record_sub_id".to_string(),
+        translation.record_sub_id.clone().unwrap_or_default(),
+        Some(format!("is not a valid {}", field_name)),
+        vec![translation.clone().into()],
+    )
+    .into()
+}
+
+/// Formats the values of the key columns of a record, separated by commas.
+fn key_value(gtfs_file: GtfsFile, record: &Schema) -> String {
+    let fields = match serde_json::to_value(record) {
+        Ok(serde_json::Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    };
+    gtfs_file
+        .key_columns()
+        .iter()
+        .map(|column| {
+            fields
+                .get(*column)
+                .and_then(field_value)
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Deserializes a record of `T::FILE`, read with the given header.
+fn parse_csv_record<T: GtfsTable + serde::de::DeserializeOwned>(
+    header: &csv::StringRecord,
+    record: &csv::StringRecord,
+) -> Result<T> {
+    record.deserialize(Some(header)).map_err(|e| {
+        let context = match record.position() {
+            Some(position) => format!(
+                "Failed to deserialize {} at position: {:?}",
+                T::FILE_NAME,
+                position
+            ),
+            None => format!("Failed to deserialize {}", T::FILE_NAME),
+        };
+        ParseError::from(ParseErrorKind::from(e))
+            .with_context(ErrorContext(context))
+            .into()
+    })
+}
+
+/// Sets the columns of a parsed record that are not part of the specification.
+#[cfg(feature = "extensions")]
+fn with_extensions<T: Extensions>(
+    mut record: T,
+    extensions: std::collections::HashMap<String, String>,
+) -> T {
+    *record.extensions_mut() = extensions;
+    record
 }
@@ -0,0 +1,211 @@
+//! Merging of the trips repeated on several services.
+//!
+//! The main type is:
+//! - [`TripMerge`]: Trips merged into one, running on the dates of all of them.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use chrono::NaiveDate;
+
+use crate::schemas::{CalendarServiceId, TableName, TripId};
+use crate::Dataset;
+
+/// Trips merged into one by [`Dataset::dedupe_trips`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TripMerge {
+    /// The trip kept, the first of the merged trips by [`TripId`].
+    pub trip_id: TripId,
+    /// The trips removed, sorted.
+    pub merged_trip_ids: Vec<TripId>,
+    /// The service of the kept trip, running on the dates of the services of every merged trip.
+    pub service_id: CalendarServiceId,
+}
+
+/// The stop times, by stop sequence, and the frequencies of a trip, serialized.
+type TripContent = (Vec<(u32, String)>, Vec<String>);
+
+/// Serializes a record to JSON, keys being sorted in the output.
+fn to_json<T: serde::Serialize>(record: &T) -> String {
+    // Serializing a record to a JSON value can not fail.
+    serde_json::to_value(record)
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
+
+impl Dataset {
+    /// Merges the trips that only differ by their id and service, e.g. the copies of a trip
+    /// written for each day by exporters unaware of calendars.
+    ///
+    /// Trips are merged when every field of the trip, its stop times and its frequencies are
+    /// the same. The first trip by [`TripId`] is kept, and runs on a service whose dates are
+    /// those of the services of all the merged trips: an existing service if one runs on these
+    /// dates exactly, otherwise a new one named after the merged services, e.g. `MON+TUE`,
+    /// defined by a weekly pattern and its exceptions.
+    ///
+    /// The transfers and attributions of the removed trips are moved to the kept trip, and so
+    /// are their translations unless the kept trip already has one for the same field and
+    /// language. Services no longer used by any trip are kept, see [`Dataset::prune_orphans`].
+    ///
+    /// Returns the merges, sorted by kept trip.
+    pub fn dedupe_trips(&mut self) -> Vec<TripMerge> {
+        // The content of each trip, with its id and service left out.
+        let mut contents: HashMap<TripId, TripContent> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            let mut stop_time = stop_time.value().clone();
+            let trip_id = std::mem::replace(&mut stop_time.trip_id, TripId::from(""));
+            let (stop_times, _) = contents.entry(trip_id).or_default();
+            stop_times.push((stop_time.stop_sequence, to_json(&stop_time)));
+        }
+        for frequency in self.frequencies.iter() {
+            let mut frequency = frequency.value().clone();
+            let trip_id = std::mem::replace(&mut frequency.trip_id, TripId::from(""));
+            let (_, frequencies) = contents.entry(trip_id).or_default();
+            frequencies.push(to_json(&frequency));
+        }
+        let mut groups: HashMap<String, Vec<(TripId, CalendarServiceId)>> = HashMap::new();
+        for trip in self.trips.iter() {
+            let Some((mut stop_times, mut frequencies)) = contents.remove(&trip.trip_id) else {
+                continue;
+            };
+            stop_times.sort_unstable();
+            frequencies.sort_unstable();
+            let mut record = trip.value().clone();
+            record.trip_id = TripId::from("");
+            record.service_id = CalendarServiceId::from("");
+            let signature = to_json(&(record, stop_times, frequencies));
+            groups
+                .entry(signature)
+                .or_default()
+                .push((trip.trip_id.clone(), trip.service_id.clone()));
+        }
+        let mut groups: Vec<Vec<(TripId, CalendarServiceId)>> = groups
+            .into_values()
+            .filter(|trips| trips.len() > 1)
+            .map(|mut trips| {
+                trips.sort_unstable_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+                trips
+            })
+            .collect();
+        groups.sort_unstable_by(|a, b| a[0].0.as_str().cmp(b[0].0.as_str()));
+        if groups.is_empty() {
+            return vec![];
+        }
+
+        let service_dates = self.service_dates_all();
+        let mut services: BTreeMap<BTreeSet<NaiveDate>, CalendarServiceId> = BTreeMap::new();
+        let mut service_ids: Vec<&CalendarServiceId> = service_dates.keys().collect();
+        service_ids.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+        for service_id in service_ids {
+            let dates = service_dates[service_id].clone();
+            services.entry(dates).or_insert(service_id.clone());
+        }
+
+        let mut merges = vec![];
+        for trips in groups {
+            let mut merged_services: Vec<&CalendarServiceId> =
+                trips.iter().map(|(_, service_id)| service_id).collect();
+            merged_services.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+            merged_services.dedup();
+            let dates: BTreeSet<NaiveDate> = merged_services
+                .iter()
+                .filter_map(|service_id| service_dates.get(*service_id))
+                .flatten()
+                .copied()
+                .collect();
+            let service_id = match services.get(&dates) {
+                Some(service_id) => service_id.clone(),
+                None => {
+                    let name = merged_services
+                        .iter()
+                        .map(|service_id| service_id.as_str())
+                        .collect::<Vec<_>>()
+                        .join("+");
+                    let mut service_id = CalendarServiceId::from(name.as_str());
+                    let mut suffix = 1;
+                    while services.values().any(|used| *used == service_id) {
+                        suffix += 1;
+                        service_id = CalendarServiceId::from(format!("{name}_{suffix}").as_str());
+                    }
+                    let dates_list: Vec<NaiveDate> = dates.iter().copied().collect();
+                    self.insert_weekly_pattern(&service_id, &dates_list);
+                    services.insert(dates, service_id.clone());
+                    service_id
+                }
+            };
+
+            let (kept, _) = &trips[0];
+            if let Some(mut trip) = self.trips.get_mut(kept) {
+                trip.service_id = service_id.clone();
+            }
+            let merged_trip_ids: Vec<TripId> = trips[1..]
+                .iter()
+                .map(|(trip_id, _)| trip_id.clone())
+                .collect();
+            self.remove_merged_trips(kept, &merged_trip_ids);
+            merges.push(TripMerge {
+                trip_id: kept.clone(),
+                merged_trip_ids,
+                service_id,
+            });
+        }
+        merges
+    }
+
+    /// Removes the trips merged into `kept`, moving the records referring to them to `kept`.
+    fn remove_merged_trips(&mut self, kept: &TripId, merged: &[TripId]) {
+        let merged: HashSet<&TripId> = merged.iter().collect();
+        self.trips.retain(|trip_id, _| !merged.contains(trip_id));
+        self.stop_times
+            .retain(|(trip_id, _), _| !merged.contains(trip_id));
+        self.frequencies
+            .retain(|(trip_id, _), _| !merged.contains(trip_id));
+
+        let remap = |trip_id: &mut Option<TripId>| {
+            if trip_id.as_ref().is_some_and(|id| merged.contains(id)) {
+                *trip_id = Some(kept.clone());
+            }
+        };
+        for transfer in &mut self.transfers {
+            remap(&mut transfer.from_trip_id);
+            remap(&mut transfer.to_trip_id);
+        }
+        for attribution in &mut self.attributions {
+            remap(&mut attribution.trip_id);
+        }
+
+        let is_trip_table =
+            |table_name: &TableName| matches!(table_name, TableName::Trips | TableName::StopTimes);
+        let mut translated: HashSet<(String, String, Option<String>)> = self
+            .translations
+            .iter()
+            .filter(|translation| {
+                is_trip_table(&translation.table_name)
+                    && translation.record_id.as_deref() == Some(kept.as_str())
+            })
+            .map(|translation| {
+                (
+                    translation.field_name.clone(),
+                    translation.language.to_string(),
+                    translation.record_sub_id.clone(),
+                )
+            })
+            .collect();
+        self.translations.retain_mut(|translation| {
+            let refers_to_merged = is_trip_table(&translation.table_name)
+                && translation
+                    .record_id
+                    .as_deref()
+                    .is_some_and(|record_id| merged.contains(&TripId::from(record_id)));
+            if !refers_to_merged {
+                return true;
+            }
+            let key = (
+                translation.field_name.clone(),
+                translation.language.to_string(),
+                translation.record_sub_id.clone(),
+            );
+            translation.record_id = Some(kept.to_string());
+            translated.insert(key)
+        });
+    }
+}
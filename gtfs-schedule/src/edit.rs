@@ -0,0 +1,801 @@
+//! Editing of datasets, with validation of the changed records only.
+//!
+//! The main types are:
+//! - [`DatasetEditor`]: Changes the records of a dataset, keeping track of them.
+//! - [`CascadePolicy`]: How [`Dataset::remove_route`] treats the records referring to a route.
+
+use std::collections::HashSet;
+
+use crate::error::{DatasetValidationError, Result};
+use crate::provenance::ToSourceKey;
+use crate::schemas::{
+    AreaId, BookingRuleId, CalendarServiceId, FareId, FareMediaId, LevelId, LocationGroupId,
+    NetworkId, RouteId, Schema, StopId, StopTime, TableName, Trip, TripId,
+};
+use crate::writer::csv_row;
+use crate::{Dataset, GtfsFile};
+
+/// The references between tables checked by [`Dataset::revalidate_dirty`].
+///
+/// Each entry is a column of a file, and the file and column holding the values it references.
+const FOREIGN_KEYS: [(GtfsFile, &str, GtfsFile, &str); 40] = [
+    (
+        GtfsFile::Stops,
+        "parent_station",
+        GtfsFile::Stops,
+        "stop_id",
+    ),
+    (GtfsFile::Stops, "level_id", GtfsFile::Levels, "level_id"),
+    (GtfsFile::Routes, "agency_id", GtfsFile::Agency, "agency_id"),
+    (GtfsFile::Trips, "route_id", GtfsFile::Routes, "route_id"),
+    (
+        GtfsFile::Trips,
+        "service_id",
+        GtfsFile::Calendar,
+        "service_id",
+    ),
+    (GtfsFile::Trips, "shape_id", GtfsFile::Shapes, "shape_id"),
+    (GtfsFile::StopTimes, "trip_id", GtfsFile::Trips, "trip_id"),
+    (GtfsFile::StopTimes, "stop_id", GtfsFile::Stops, "stop_id"),
+    (
+        GtfsFile::StopTimes,
+        "location_group_id",
+        GtfsFile::LocationGroups,
+        "location_group_id",
+    ),
+    (
+        GtfsFile::StopTimes,
+        "pickup_booking_rule_id",
+        GtfsFile::BookingRules,
+        "booking_rule_id",
+    ),
+    (
+        GtfsFile::StopTimes,
+        "drop_off_booking_rule_id",
+        GtfsFile::BookingRules,
+        "booking_rule_id",
+    ),
+    (
+        GtfsFile::FareAttributes,
+        "agency_id",
+        GtfsFile::Agency,
+        "agency_id",
+    ),
+    (
+        GtfsFile::FareRules,
+        "fare_id",
+        GtfsFile::FareAttributes,
+        "fare_id",
+    ),
+    (
+        GtfsFile::FareRules,
+        "route_id",
+        GtfsFile::Routes,
+        "route_id",
+    ),
+    (GtfsFile::FareRules, "origin_id", GtfsFile::Stops, "zone_id"),
+    (
+        GtfsFile::FareRules,
+        "destination_id",
+        GtfsFile::Stops,
+        "zone_id",
+    ),
+    (
+        GtfsFile::FareRules,
+        "contains_id",
+        GtfsFile::Stops,
+        "zone_id",
+    ),
+    (
+        GtfsFile::Timeframes,
+        "service_id",
+        GtfsFile::Calendar,
+        "service_id",
+    ),
+    (
+        GtfsFile::FareProducts,
+        "fare_media_id",
+        GtfsFile::FareMedia,
+        "fare_media_id",
+    ),
+    (
+        GtfsFile::FareLegRules,
+        "network_id",
+        GtfsFile::Networks,
+        "network_id",
+    ),
+    (
+        GtfsFile::FareLegRules,
+        "from_area_id",
+        GtfsFile::Areas,
+        "area_id",
+    ),
+    (
+        GtfsFile::FareLegRules,
+        "to_area_id",
+        GtfsFile::Areas,
+        "area_id",
+    ),
+    (
+        GtfsFile::FareLegRules,
+        "from_timeframe_group_id",
+        GtfsFile::Timeframes,
+        "timeframe_group_id",
+    ),
+    (
+        GtfsFile::FareLegRules,
+        "to_timeframe_group_id",
+        GtfsFile::Timeframes,
+        "timeframe_group_id",
+    ),
+    (
+        GtfsFile::FareLegRules,
+        "fare_product_id",
+        GtfsFile::FareProducts,
+        "fare_product_id",
+    ),
+    (
+        GtfsFile::FareTransfers,
+        "from_leg_group_id",
+        GtfsFile::FareLegRules,
+        "leg_group_id",
+    ),
+    (
+        GtfsFile::FareTransfers,
+        "to_leg_group_id",
+        GtfsFile::FareLegRules,
+        "leg_group_id",
+    ),
+    (
+        GtfsFile::FareTransfers,
+        "fare_product_id",
+        GtfsFile::FareProducts,
+        "fare_product_id",
+    ),
+    (GtfsFile::StopsAreas, "area_id", GtfsFile::Areas, "area_id"),
+    (GtfsFile::StopsAreas, "stop_id", GtfsFile::Stops, "stop_id"),
+    (
+        GtfsFile::RoutesNetworks,
+        "network_id",
+        GtfsFile::Networks,
+        "network_id",
+    ),
+    (
+        GtfsFile::RoutesNetworks,
+        "route_id",
+        GtfsFile::Routes,
+        "route_id",
+    ),
+    (GtfsFile::Frequencies, "trip_id", GtfsFile::Trips, "trip_id"),
+    (
+        GtfsFile::Transfers,
+        "from_stop_id",
+        GtfsFile::Stops,
+        "stop_id",
+    ),
+    (
+        GtfsFile::Transfers,
+        "to_stop_id",
+        GtfsFile::Stops,
+        "stop_id",
+    ),
+    (
+        GtfsFile::Pathways,
+        "from_stop_id",
+        GtfsFile::Stops,
+        "stop_id",
+    ),
+    (GtfsFile::Pathways, "to_stop_id", GtfsFile::Stops, "stop_id"),
+    (
+        GtfsFile::LocationGroupsStops,
+        "location_group_id",
+        GtfsFile::LocationGroups,
+        "location_group_id",
+    ),
+    (
+        GtfsFile::LocationGroupsStops,
+        "stop_id",
+        GtfsFile::Stops,
+        "stop_id",
+    ),
+    (
+        GtfsFile::BookingRules,
+        "prior_notice_service_id",
+        GtfsFile::Calendar,
+        "service_id",
+    ),
+];
+
+/// The records changed since the last validation, see [`Dataset::revalidate_dirty`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DirtyRecords {
+    /// Records that were inserted or replaced, to validate in their current version.
+    changed: Vec<Schema>,
+    /// Records that were removed or replaced, whose referencing records may now be invalid.
+    removed: Vec<Schema>,
+}
+
+/// How the records referring to a removed record are treated, see [`Dataset::remove_route`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CascadePolicy {
+    /// The record is not removed if other records refer to it.
+    #[default]
+    Reject,
+    /// The records referring to the record are removed along with it, and so on.
+    Cascade,
+}
+
+/// Changes the records of a dataset, see [`Dataset::begin_edit`].
+///
+/// Every record inserted, replaced or removed through the editor is marked as dirty, so that
+/// [`Dataset::revalidate_dirty`] can validate the changes without validating the whole dataset.
+pub struct DatasetEditor<'a> {
+    dataset: &'a mut Dataset,
+}
+
+impl DatasetEditor<'_> {
+    /// Inserts a record, returns the record with the same key it replaced in keyed tables.
+    ///
+    /// Records of tables stored as lists, such as [`Dataset::transfers`], are appended.
+    pub fn upsert(&mut self, record: impl Into<Schema>) -> Option<Schema> {
+        let record = record.into();
+        self.dataset.dirty.changed.push(record.clone());
+        let replaced = self.dataset.insert_record(record);
+        if let Some(replaced) = &replaced {
+            self.dataset.dirty.removed.push(replaced.clone());
+        }
+        replaced
+    }
+
+    /// Removes a record, by key in keyed tables and by value otherwise.
+    ///
+    /// Returns the removed record, `None` if the dataset did not hold it.
+    pub fn remove(&mut self, record: &Schema) -> Option<Schema> {
+        let removed = self.dataset.remove_record(record)?;
+        self.dataset.dirty.removed.push(removed.clone());
+        Some(removed)
+    }
+
+    /// Marks a record as dirty, for records changed directly through the tables of the dataset.
+    ///
+    /// `previous` is the record before the change, if its key or a referenced value changed.
+    pub fn touch(&mut self, record: impl Into<Schema>, previous: Option<Schema>) {
+        self.dataset.dirty.changed.push(record.into());
+        self.dataset.dirty.removed.extend(previous);
+    }
+
+    /// Returns the dataset being edited.
+    pub fn dataset(&self) -> &Dataset {
+        self.dataset
+    }
+}
+
+impl Dataset {
+    /// Starts editing the dataset, see [`DatasetEditor`].
+    pub fn begin_edit(&mut self) -> DatasetEditor<'_> {
+        DatasetEditor { dataset: self }
+    }
+
+    /// Returns `true` if records were changed through [`Dataset::begin_edit`] since the last
+    /// successful [`Dataset::revalidate_dirty`].
+    pub fn has_dirty_records(&self) -> bool {
+        !self.dirty.changed.is_empty() || !self.dirty.removed.is_empty()
+    }
+
+    /// Validates the records changed through [`Dataset::begin_edit`], and the records referencing
+    /// them.
+    ///
+    /// The changed records are validated on their own, and their references to other tables are
+    /// checked. The records referencing a removed record, or a value it held such as a
+    /// `zone_id`, are checked as well. Rules spanning whole tables, such as the ordering of the
+    /// stop times of a trip, are only checked by [`Dataset::validate`].
+    ///
+    /// The dirty records are cleared when no error is found, and kept otherwise, so that they
+    /// are checked again once the error is fixed.
+    pub fn revalidate_dirty(&mut self) -> Result<()> {
+        let dirty = std::mem::take(&mut self.dirty);
+        let result = self.validate_dirty(&dirty);
+        if result.is_err() {
+            self.dirty = dirty;
+        }
+        result
+    }
+
+    /// Validates the given dirty records, see [`Dataset::revalidate_dirty`].
+    fn validate_dirty(&mut self, dirty: &DirtyRecords) -> Result<()> {
+        for record in &dirty.changed {
+            // The record may have been replaced or removed since.
+            let Some(mut record) = self.current_record(record) else {
+                continue;
+            };
+            validate_record(&mut record)?;
+            if matches!(record, Schema::StopTime(_) | Schema::Level(_)) {
+                // Their validation normalizes some fields, as in `Dataset::validate`.
+                self.insert_record(record.clone());
+            }
+
+            self.check_foreign_keys(&record)?;
+        }
+
+        for record in &dirty.removed {
+            let file = record.file();
+            let row = csv_row(record);
+            for (source_file, column, _, target_column) in FOREIGN_KEYS
+                .iter()
+                .filter(|(.., target_file, _)| *target_file == file)
+            {
+                let Some(value) = row.get(*target_column) else {
+                    continue;
+                };
+                if self.value_exists(file, target_column, value) {
+                    continue;
+                }
+                if let Some(referencing) = self.find_referencing(*source_file, column, value) {
+                    return Err(DatasetValidationError::new_foreign_key_not_found(
+                        column.to_string(),
+                        value.clone(),
+                        file.file_name().to_string(),
+                        vec![referencing],
+                    )
+                    .into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the values referenced by a record exist, see [`FOREIGN_KEYS`].
+    fn check_foreign_keys(&self, record: &Schema) -> Result<()> {
+        let file = record.file();
+        let row = csv_row(record);
+        for (_, column, target_file, target_column) in FOREIGN_KEYS
+            .iter()
+            .filter(|(source_file, ..)| *source_file == file)
+        {
+            let Some(value) = row.get(*column) else {
+                continue;
+            };
+            if !self.value_exists(*target_file, target_column, value) {
+                return Err(DatasetValidationError::new_foreign_key_not_found(
+                    column.to_string(),
+                    value.clone(),
+                    target_file.file_name().to_string(),
+                    vec![record.clone()],
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if a record of `file` holds `value` in `column`.
+    fn value_exists(&self, file: GtfsFile, column: &str, value: &str) -> bool {
+        match (file, column) {
+            (GtfsFile::Stops, "stop_id") => self.stops.contains_key(&StopId::from(value)),
+            (GtfsFile::Stops, "zone_id") => self.stops.iter().any(|stop| {
+                stop.zone_id
+                    .as_ref()
+                    .is_some_and(|zone_id| **zone_id == *value)
+            }),
+            (GtfsFile::Routes, "route_id") => self.routes.contains_key(&RouteId::from(value)),
+            (GtfsFile::Trips, "trip_id") => self.trips.contains_key(&TripId::from(value)),
+            (GtfsFile::Calendar, "service_id") => {
+                self.calendar.contains_key(&CalendarServiceId::from(value))
+                    || self
+                        .calendar_dates
+                        .iter()
+                        .any(|calendar_date| *calendar_date.service_id == *value)
+            }
+            (GtfsFile::Shapes, "shape_id") => {
+                self.shapes.iter().any(|shape| *shape.shape_id == *value)
+            }
+            (GtfsFile::FareAttributes, "fare_id") => {
+                self.fare_attributes.contains_key(&FareId::from(value))
+            }
+            (GtfsFile::FareMedia, "fare_media_id") => {
+                self.fare_medias.contains_key(&FareMediaId::from(value))
+            }
+            (GtfsFile::FareProducts, "fare_product_id") => self
+                .fare_products
+                .iter()
+                .any(|fare_product| *fare_product.fare_product_id == *value),
+            (GtfsFile::Areas, "area_id") => self.areas.contains_key(&AreaId::from(value)),
+            (GtfsFile::Networks, "network_id") => {
+                self.networks.contains_key(&NetworkId::from(value))
+                    || self.routes.iter().any(|route| {
+                        route
+                            .network_id
+                            .as_ref()
+                            .is_some_and(|network_id| **network_id == *value)
+                    })
+            }
+            (GtfsFile::Levels, "level_id") => self.levels.contains_key(&LevelId::from(value)),
+            (GtfsFile::LocationGroups, "location_group_id") => self
+                .location_groups
+                .contains_key(&LocationGroupId::from(value)),
+            (GtfsFile::BookingRules, "booking_rule_id") => {
+                self.booking_rules.contains_key(&BookingRuleId::from(value))
+            }
+            _ => self
+                .iter_table(file)
+                .any(|record| csv_row(&record).get(column).is_some_and(|v| v == value)),
+        }
+    }
+
+    /// Returns a record of `file` holding `value` in `column`, if any.
+    fn find_referencing(&self, file: GtfsFile, column: &str, value: &str) -> Option<Schema> {
+        match (file, column) {
+            (GtfsFile::StopTimes, "trip_id") => self
+                .stop_times
+                .iter()
+                .find(|stop_time| *stop_time.trip_id == *value)
+                .map(|stop_time| stop_time.value().clone().into()),
+            (GtfsFile::StopTimes, "stop_id") => self
+                .stop_times
+                .iter()
+                .find(|stop_time| {
+                    stop_time
+                        .stop_id
+                        .as_ref()
+                        .is_some_and(|stop_id| **stop_id == *value)
+                })
+                .map(|stop_time| stop_time.value().clone().into()),
+            _ => self
+                .iter_table(file)
+                .find(|record| csv_row(record).get(column).is_some_and(|v| v == value)),
+        }
+    }
+}
+
+impl Dataset {
+    /// Removes a route, returns the removed records, starting with the route.
+    ///
+    /// The records referring to the route are its trips, its fare rules and its network in
+    /// routes_networks.txt, and the transfers, attributions and translations of the route. With
+    /// [`CascadePolicy::Cascade`], they are removed too, along with the stop times, frequencies,
+    /// transfers, attributions and translations of the removed trips. With
+    /// [`CascadePolicy::Reject`], nothing is removed if any of them exists, and the error holds
+    /// the first of them. Nothing is removed either if the route does not exist.
+    pub fn remove_route(
+        &mut self,
+        route_id: &RouteId,
+        policy: CascadePolicy,
+    ) -> Result<Vec<Schema>> {
+        let Some(route) = self.routes.get(route_id).map(|route| route.value().clone()) else {
+            return Ok(vec![]);
+        };
+        let trip_ids: HashSet<TripId> = self
+            .trips
+            .iter()
+            .filter(|trip| trip.route_id == *route_id)
+            .map(|trip| trip.trip_id.clone())
+            .collect();
+        let is_removed_trip =
+            |trip_id: &Option<TripId>| trip_id.as_ref().is_some_and(|id| trip_ids.contains(id));
+        let is_route = |id: &Option<RouteId>| id.as_ref() == Some(route_id);
+
+        let mut referencing: Vec<Schema> = vec![];
+        referencing.extend(self.trips.iter().filter_map(|trip| {
+            trip_ids
+                .contains(&trip.trip_id)
+                .then(|| trip.value().clone().into())
+        }));
+        referencing.extend(
+            self.routes_networks
+                .get(route_id)
+                .map(|route_network| route_network.value().clone().into()),
+        );
+        referencing.extend(
+            self.fare_rules
+                .iter()
+                .filter(|fare_rule| is_route(&fare_rule.route_id))
+                .map(|fare_rule| fare_rule.clone().into()),
+        );
+        referencing.extend(
+            self.transfers
+                .iter()
+                .filter(|transfer| {
+                    is_route(&transfer.from_route_id)
+                        || is_route(&transfer.to_route_id)
+                        || is_removed_trip(&transfer.from_trip_id)
+                        || is_removed_trip(&transfer.to_trip_id)
+                })
+                .map(|transfer| transfer.clone().into()),
+        );
+        referencing.extend(
+            self.attributions
+                .iter()
+                .filter(|attribution| {
+                    is_route(&attribution.route_id) || is_removed_trip(&attribution.trip_id)
+                })
+                .map(|attribution| attribution.clone().into()),
+        );
+        referencing.extend(
+            self.translations
+                .iter()
+                .filter(|translation| {
+                    let record_id = translation.record_id.as_deref();
+                    match translation.table_name {
+                        TableName::Routes => record_id == Some(route_id.as_str()),
+                        TableName::Trips | TableName::StopTimes => record_id
+                            .is_some_and(|record_id| trip_ids.contains(&TripId::from(record_id))),
+                        _ => false,
+                    }
+                })
+                .map(|translation| translation.clone().into()),
+        );
+
+        if let (CascadePolicy::Reject, Some(first)) = (policy, referencing.first()) {
+            return Err(DatasetValidationError::new_still_referenced(
+                "route_id".to_string(),
+                route_id.to_string(),
+                first.file().file_name().to_string(),
+                vec![first.clone()],
+            )
+            .into());
+        }
+
+        referencing.extend(self.stop_times.iter().filter_map(|stop_time| {
+            trip_ids
+                .contains(&stop_time.trip_id)
+                .then(|| stop_time.value().clone().into())
+        }));
+        referencing.extend(self.frequencies.iter().filter_map(|frequency| {
+            trip_ids
+                .contains(&frequency.trip_id)
+                .then(|| frequency.value().clone().into())
+        }));
+        let mut removed = vec![Schema::from(route)];
+        removed.extend(referencing);
+        for record in &removed {
+            self.remove_record(record);
+        }
+        Ok(removed)
+    }
+
+    /// Changes the id of a stop, and every reference to it.
+    ///
+    /// References are the parent stations of stops, and the stops of stop times, transfers,
+    /// pathways, areas, location groups and translations. Fails if `old` does not exist, or if
+    /// `new` already does. Renaming a stop to its own id does nothing.
+    pub fn rename_stop_id(&mut self, old: &StopId, new: &StopId) -> Result<()> {
+        if old == new && self.stops.contains_key(old) {
+            return Ok(());
+        }
+        if let Some(existing) = self.stops.get(new) {
+            return Err(DatasetValidationError::new_primary_key_not_unique(
+                "stop_id".to_string(),
+                new.to_string(),
+                vec![existing.value().clone().into()],
+            )
+            .into());
+        }
+        let Some((_, mut stop)) = self.stops.remove(old) else {
+            return Err(DatasetValidationError::new_foreign_key_not_found(
+                "stop_id".to_string(),
+                old.to_string(),
+                GtfsFile::Stops.file_name().to_string(),
+                vec![],
+            )
+            .into());
+        };
+        let file = GtfsFile::Stops;
+        if let Some(line) = self.source_lines.keyed.remove(&old.source_key(file)) {
+            self.source_lines.keyed.insert(new.source_key(file), line);
+        }
+        stop.stop_id = new.clone();
+        self.stops.insert(new.clone(), stop);
+
+        let rename = |stop_id: &mut StopId| {
+            if stop_id == old {
+                *stop_id = new.clone();
+            }
+        };
+        for mut stop in self.stops.iter_mut() {
+            stop.parent_station.iter_mut().for_each(rename);
+        }
+        for mut stop_time in self.stop_times.iter_mut() {
+            stop_time.stop_id.iter_mut().for_each(rename);
+        }
+        for transfer in &mut self.transfers {
+            transfer.from_stop_id.iter_mut().for_each(rename);
+            transfer.to_stop_id.iter_mut().for_each(rename);
+        }
+        for mut pathway in self.pathways.iter_mut() {
+            rename(&mut pathway.from_stop_id);
+            rename(&mut pathway.to_stop_id);
+        }
+        for stop_area in &mut self.stops_areas {
+            rename(&mut stop_area.stop_id);
+        }
+        for location_group_stop in &mut self.location_groups_stops {
+            rename(&mut location_group_stop.stop_id);
+        }
+        for translation in &mut self.translations {
+            if translation.table_name == TableName::Stops
+                && translation.record_id.as_deref() == Some(old.as_str())
+            {
+                translation.record_id = Some(new.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a trip with its stop times.
+    ///
+    /// The trip and stop times are validated on their own, and the records they refer to, such
+    /// as their route, service and stops, must exist. Nothing is added if the trip already
+    /// exists, if a stop time belongs to another trip, or if two stop times share a
+    /// stop_sequence. Rules spanning whole tables, such as the ordering of the times of the
+    /// trip, are only checked by [`Dataset::validate`].
+    pub fn add_trip(&mut self, trip: Trip, stop_times: Vec<StopTime>) -> Result<()> {
+        if let Some(existing) = self.trips.get(&trip.trip_id) {
+            return Err(DatasetValidationError::new_primary_key_not_unique(
+                "trip_id".to_string(),
+                trip.trip_id.to_string(),
+                vec![existing.value().clone().into()],
+            )
+            .into());
+        }
+        trip.validate()?;
+        let trip_record = Schema::from(trip.clone());
+        self.check_foreign_keys(&trip_record)?;
+
+        let mut stop_sequences = HashSet::new();
+        let mut records = vec![];
+        for mut stop_time in stop_times {
+            if stop_time.trip_id != trip.trip_id {
+                return Err(DatasetValidationError::new_inconsistent_value(
+                    "trip_id".to_string(),
+                    stop_time.trip_id.to_string(),
+                    Some(format!("differs from the added trip {}", trip.trip_id)),
+                    vec![stop_time.into()],
+                )
+                .into());
+            }
+            if !stop_sequences.insert(stop_time.stop_sequence) {
+                return Err(DatasetValidationError::new_primary_key_not_unique(
+                    "stop_sequence".to_string(),
+                    stop_time.stop_sequence.to_string(),
+                    vec![stop_time.into()],
+                )
+                .into());
+            }
+            stop_time.validate()?;
+            records.push(Schema::from(stop_time));
+        }
+
+        // The stop times refer to the trip, which must be added to check them.
+        self.insert_record(trip_record.clone());
+        if let Err(error) = records
+            .iter()
+            .try_for_each(|record| self.check_foreign_keys(record))
+        {
+            self.remove_record(&trip_record);
+            return Err(error);
+        }
+        for record in records {
+            self.insert_record(record);
+        }
+        Ok(())
+    }
+}
+
+/// Implements the insertion, removal and lookup of records of any table.
+macro_rules! record_tables {
+    (
+        keyed: { $($keyed:ident => $map:ident, |$record:ident| $key:expr;)* }
+        listed: { $($listed:ident => $list:ident;)* }
+    ) => {
+        impl Dataset {
+            /// Inserts a record, returns the record with the same key it replaced in keyed tables.
+            pub(crate) fn insert_record(&mut self, record: Schema) -> Option<Schema> {
+                match record {
+                    $(Schema::$keyed($record) => {
+                        self.$map.insert($key, $record).map(Schema::from)
+                    })*
+                    $(Schema::$listed(record) => {
+                        self.$list.push(record);
+                        None
+                    })*
+                    Schema::FeedInfo(feed_info) => {
+                        self.feed_info.replace(feed_info).map(Schema::from)
+                    }
+                }
+            }
+
+            /// Removes a record, by key in keyed tables and by value otherwise.
+            pub(crate) fn remove_record(&mut self, record: &Schema) -> Option<Schema> {
+                let file = record.file();
+                match record {
+                    $(Schema::$keyed($record) => {
+                        let key = $key;
+                        self.source_lines.keyed.remove(&key.source_key(file));
+                        self.$map.remove(&key).map(|(_, record)| Schema::from(record))
+                    })*
+                    $(Schema::$listed(record) => {
+                        let value = serde_json::to_value(record).ok()?;
+                        let index = self.$list.iter().position(|listed| {
+                            serde_json::to_value(listed).is_ok_and(|listed| listed == value)
+                        })?;
+                        self.source_lines.remove_listed(file, index);
+                        Some(Schema::from(self.$list.remove(index)))
+                    })*
+                    Schema::FeedInfo(_) => self.feed_info.take().map(Schema::from),
+                }
+            }
+
+            /// Returns the version of a record held by the dataset, looked up as in
+            /// [`Dataset::remove_record`].
+            fn current_record(&self, record: &Schema) -> Option<Schema> {
+                match record {
+                    $(Schema::$keyed($record) => self
+                        .$map
+                        .get(&$key)
+                        .map(|record| Schema::from(record.value().clone())),)*
+                    $(Schema::$listed(record) => {
+                        let value = serde_json::to_value(record).ok()?;
+                        self.$list
+                            .iter()
+                            .any(|listed| {
+                                serde_json::to_value(listed).is_ok_and(|listed| listed == value)
+                            })
+                            .then(|| Schema::from(record.clone()))
+                    })*
+                    Schema::FeedInfo(_) => self.feed_info.clone().map(Schema::from),
+                }
+            }
+        }
+
+        /// Validates a record on its own.
+        pub(crate) fn validate_record(record: &mut Schema) -> Result<()> {
+            match record {
+                $(Schema::$keyed(record) => record.validate(),)*
+                $(Schema::$listed(record) => record.validate(),)*
+                Schema::FeedInfo(record) => record.validate(),
+            }
+        }
+    };
+}
+
+record_tables! {
+    keyed: {
+        Stop => stops, |stop| stop.stop_id.clone();
+        Route => routes, |route| route.route_id.clone();
+        Trip => trips, |trip| trip.trip_id.clone();
+        StopTime => stop_times, |stop_time| (stop_time.trip_id.clone(), stop_time.stop_sequence);
+        Calendar => calendar, |calendar| calendar.service_id.clone();
+        CalendarDate => calendar_dates, |calendar_date| {
+            (calendar_date.service_id.clone(), calendar_date.date)
+        };
+        FareAttribute => fare_attributes, |fare_attribute| fare_attribute.fare_id.clone();
+        FareMedia => fare_medias, |fare_media| fare_media.fare_media_id.clone();
+        FareProduct => fare_products, |fare_product| {
+            (fare_product.fare_product_id.clone(), fare_product.fare_media_id.clone())
+        };
+        Area => areas, |area| area.area_id.clone();
+        Network => networks, |network| network.network_id.clone();
+        RouteNetwork => routes_networks, |route_network| route_network.route_id.clone();
+        Shape => shapes, |shape| (shape.shape_id.clone(), shape.shape_pt_sequence);
+        Frequency => frequencies, |frequency| (frequency.trip_id.clone(), frequency.start_time);
+        Pathway => pathways, |pathway| pathway.pathway_id.clone();
+        Level => levels, |level| level.level_id.clone();
+        LocationGroup => location_groups, |location_group| {
+            location_group.location_group_id.clone()
+        };
+        BookingRule => booking_rules, |booking_rule| booking_rule.booking_rule_id.clone();
+    }
+    listed: {
+        Agency => agencies;
+        FareRule => fare_rules;
+        Timeframe => timeframes;
+        FareLegRule => fare_leg_rules;
+        FareTransferRule => fare_transfers;
+        StopArea => stops_areas;
+        Transfer => transfers;
+        LocationGroupStop => location_groups_stops;
+        Translation => translations;
+        Attribution => attributions;
+    }
+}
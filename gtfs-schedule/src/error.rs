@@ -72,6 +72,17 @@ pub enum ParseErrorKind {
     Csv(#[from] csv::Error),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow::error::ArrowError),
+    #[cfg(feature = "arrow")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[cfg(feature = "zip")]
+    #[error("Zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
 }
 
 #[derive(Error, Debug, Diagnostic)]
@@ -91,6 +102,13 @@ pub enum SchemaValidationErrorKind {
         field_name: String,
         reason: Option<String>,
     },
+    #[error("Frequency window ends at {end_time}, which is not after its start at {start_time}")]
+    FrequencyWindowOutOfOrder {
+        start_time: String,
+        end_time: String,
+    },
+    #[error("Frequency headway_secs must be greater than 0")]
+    NonPositiveHeadway,
 }
 
 #[derive(Error, Debug, Diagnostic)]
@@ -123,6 +141,24 @@ pub enum DatasetValidationErrorKind {
     },
     #[error("Overlapping intervals found: {details}")]
     OverlappingIntervals { details: String },
+    #[error("Suspicious coordinate in {field_name}: {value}; reason: {reason}")]
+    SuspiciousCoordinate {
+        field_name: String,
+        value: String,
+        reason: String,
+    },
+    #[error("Frequency windows {first} and {second} of trip {trip_id} overlap")]
+    OverlappingFrequencies {
+        trip_id: String,
+        first: String,
+        second: String,
+    },
+    #[error("{field_name} {value} is still referenced by a record of {referencing_file_name}")]
+    StillReferenced {
+        field_name: String,
+        value: String,
+        referencing_file_name: String,
+    },
 }
 
 #[derive(Error, Debug, Diagnostic)]
@@ -190,6 +226,25 @@ impl SchemaValidationError {
             schema_instance,
         )
     }
+    pub fn new_frequency_window_out_of_order(
+        start_time: String,
+        end_time: String,
+        schema_instance: Schema,
+    ) -> Self {
+        Self::new(
+            SchemaValidationErrorKind::FrequencyWindowOutOfOrder {
+                start_time,
+                end_time,
+            },
+            schema_instance,
+        )
+    }
+    pub fn new_non_positive_headway(schema_instance: Schema) -> Self {
+        Self::new(
+            SchemaValidationErrorKind::NonPositiveHeadway,
+            schema_instance,
+        )
+    }
 }
 
 impl DatasetValidationError {
@@ -265,6 +320,51 @@ impl DatasetValidationError {
             schema_instances,
         )
     }
+    pub fn new_suspicious_coordinate(
+        field_name: String,
+        value: String,
+        reason: String,
+        schema_instances: Vec<Schema>,
+    ) -> Self {
+        Self::new(
+            DatasetValidationErrorKind::SuspiciousCoordinate {
+                field_name,
+                value,
+                reason,
+            },
+            schema_instances,
+        )
+    }
+    pub fn new_overlapping_frequencies(
+        trip_id: String,
+        first: String,
+        second: String,
+        schema_instances: Vec<Schema>,
+    ) -> Self {
+        Self::new(
+            DatasetValidationErrorKind::OverlappingFrequencies {
+                trip_id,
+                first,
+                second,
+            },
+            schema_instances,
+        )
+    }
+    pub fn new_still_referenced(
+        field_name: String,
+        value: String,
+        referencing_file_name: String,
+        schema_instances: Vec<Schema>,
+    ) -> Self {
+        Self::new(
+            DatasetValidationErrorKind::StillReferenced {
+                field_name,
+                value,
+                referencing_file_name,
+            },
+            schema_instances,
+        )
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
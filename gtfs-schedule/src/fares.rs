@@ -0,0 +1,725 @@
+//! Computation of the fares of itineraries.
+//!
+//! The main types are:
+//! - [`FareLeg`]: A part of an itinerary ridden on a single trip.
+//! - [`FareTicket`]: A fare paid for consecutive legs of an itinerary.
+//! - [`FareOption`]: A way to pay for a whole itinerary.
+//! - [`FareProductPurchase`]: A fare product bought during a journey.
+//! - [`JourneyFare`]: The cost of a whole journey.
+//!
+//! Fares are computed either with fare_attributes.txt and fare_rules.txt, see
+//! [`Dataset::fare_for_itinerary`], or with the fare_leg_rules.txt and fare_transfer_rules.txt of
+//! Fares v2, see [`Dataset::journey_fares`].
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+
+use chrono::{Datelike, Days, NaiveDate, NaiveTime};
+use iso_currency::Currency;
+use rust_decimal::Decimal;
+
+use crate::schemas::{
+    AgencyId, DurationLimitType, FareAttribute, FareId, FareLegRule, FareLegRuleId, FareMediaId,
+    FareProduct, FareProductId, FareRule, FareTransferRule, FareTransferRuleDurationLimit,
+    FareTransferType, FareTransfers, Money, NaiveServiceTime, NetworkId, RouteId, StopId,
+    TimeframeGroupId, TripId, ZoneId,
+};
+use crate::Dataset;
+
+/// A part of an itinerary ridden on a single trip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FareLeg {
+    /// The trip ridden.
+    pub trip_id: TripId,
+    /// The service day of the trip.
+    pub date: NaiveDate,
+    /// The stop where the rider boards.
+    pub from_stop_id: StopId,
+    /// The stop where the rider alights.
+    pub to_stop_id: StopId,
+    /// The time at which the rider boards.
+    pub departure_time: NaiveServiceTime,
+    /// The time at which the rider alights.
+    pub arrival_time: NaiveServiceTime,
+}
+
+/// A fare paid for consecutive legs of an itinerary, see [`FareOption`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FareTicket {
+    /// The fare paid.
+    pub fare_id: FareId,
+    /// The price of the fare.
+    pub price: Money,
+    /// The indices of the legs covered by the ticket.
+    pub legs: Range<usize>,
+}
+
+/// A way to pay for a whole itinerary, see [`Dataset::fare_for_itinerary`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FareOption {
+    /// The tickets to buy, in the order of the legs they cover.
+    pub tickets: Vec<FareTicket>,
+    /// The sum of the prices of the tickets.
+    pub total: Money,
+}
+
+/// A fare product bought during a journey, see [`JourneyFare`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FareProductPurchase {
+    /// The fare product bought.
+    pub fare_product_id: FareProductId,
+    /// The fare media the fare product is bought on, if known.
+    pub fare_media_id: Option<FareMediaId>,
+    /// The price of the fare product.
+    pub price: Money,
+    /// The index of the leg the fare product is bought for. For a transfer, this is the leg
+    /// transferred to.
+    pub leg: usize,
+    /// `true` if the fare product pays for a transfer rather than for a leg.
+    pub transfer: bool,
+}
+
+/// The cost of a whole journey, see [`Dataset::journey_fares`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JourneyFare {
+    /// The fare products to buy, in the order of the legs they are bought for.
+    pub purchases: Vec<FareProductPurchase>,
+    /// The sum of the prices of the fare products.
+    pub total: Money,
+}
+
+/// What the rules of fare_rules.txt consider about a leg.
+struct RiddenLeg {
+    route_id: RouteId,
+    agency_id: Option<AgencyId>,
+    from_zone: Option<ZoneId>,
+    to_zone: Option<ZoneId>,
+    /// The zones of every stop from the boarding stop to the alighting one.
+    zones: HashSet<ZoneId>,
+    /// Seconds at which the rider boards, counted from a fixed day so that legs ridden on
+    /// different service days compare.
+    departure: i64,
+}
+
+impl Dataset {
+    /// Computes the ways to pay for an itinerary, with fare_attributes.txt and fare_rules.txt.
+    ///
+    /// The itinerary is split into runs of consecutive legs, each paid with a single fare. A fare
+    /// covers a run when:
+    /// - it allows as many transfers as the run has, the last leg departing within its
+    ///   `transfer_duration` of the first one, service days included;
+    /// - its `agency_id`, if any, operates every route of the run;
+    /// - each leg is matched by one of its rules on its own: the route of the rule, if any, is
+    ///   the route of the leg, and its origin and destination, if any, are the zones of the
+    ///   first and last stops of the run. The rules sharing a route, origin and destination
+    ///   make up a single rule with all their contained zones, which must then be exactly the
+    ///   zones the run passes through.
+    ///
+    /// A fare without rules covers any run.
+    ///
+    /// As prices in different currencies cannot be compared, the cheapest option of each currency
+    /// is returned, sorted by currency code. No option is returned if a leg cannot be covered or
+    /// rides an unknown trip.
+    pub fn fare_for_itinerary(&self, legs: &[FareLeg]) -> Vec<FareOption> {
+        let Some(ridden) = legs
+            .iter()
+            .map(|leg| self.ridden_leg(leg))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return vec![];
+        };
+        if ridden.is_empty() {
+            return vec![];
+        }
+
+        let mut rules: HashMap<&FareId, Vec<&FareRule>> = HashMap::new();
+        for fare_rule in &self.fare_rules {
+            rules.entry(&fare_rule.fare_id).or_default().push(fare_rule);
+        }
+        let fares: Vec<FareAttribute> = self
+            .fare_attributes
+            .iter()
+            .map(|fare_attribute| fare_attribute.value().clone())
+            .collect();
+
+        let mut currencies: Vec<_> = fares
+            .iter()
+            .map(|fare| fare.currency_type)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        currencies.sort_by_key(|currency| currency.code());
+
+        let mut options = vec![];
+        for currency in currencies {
+            // The cheapest tickets covering the first legs, by number of legs covered.
+            let mut cheapest: Vec<Option<(Decimal, Vec<FareTicket>)>> =
+                vec![None; ridden.len() + 1];
+            cheapest[0] = Some((Decimal::ZERO, vec![]));
+            for end in 1..=ridden.len() {
+                for start in 0..end {
+                    let Some((total, tickets)) = cheapest[start].clone() else {
+                        continue;
+                    };
+                    for fare in fares.iter().filter(|fare| fare.currency_type == currency) {
+                        let fare_rules = rules.get(&fare.fare_id).map_or(&[][..], Vec::as_slice);
+                        if !covers(fare, fare_rules, &ridden[start..end]) {
+                            continue;
+                        }
+                        let candidate = total + fare.price;
+                        if cheapest[end]
+                            .as_ref()
+                            .is_some_and(|(best, _)| *best <= candidate)
+                        {
+                            continue;
+                        }
+                        let mut tickets = tickets.clone();
+                        tickets.push(FareTicket {
+                            fare_id: fare.fare_id.clone(),
+                            price: fare.money(),
+                            legs: start..end,
+                        });
+                        cheapest[end] = Some((candidate, tickets));
+                    }
+                }
+            }
+            if let Some((total, tickets)) = cheapest[ridden.len()].take() {
+                options.push(FareOption {
+                    tickets,
+                    total: Money::new(total, currency),
+                });
+            }
+        }
+
+        options
+    }
+
+    /// Looks up the route, agency and zones of a leg.
+    fn ridden_leg(&self, leg: &FareLeg) -> Option<RiddenLeg> {
+        let route_id = self.trips.get(&leg.trip_id)?.route_id.clone();
+        let agency_id = self.routes.get(&route_id)?.agency_id.clone().or_else(|| {
+            match self.agencies.as_slice() {
+                [agency] => agency.agency_id.clone(),
+                _ => None,
+            }
+        });
+        let zone = |stop_id: &StopId| self.zone_for_stop(stop_id);
+
+        let mut stop_times: Vec<(u32, StopId)> = self
+            .iter_stop_times_for_trip(&leg.trip_id)
+            .filter_map(|stop_time| Some((stop_time.stop_sequence, stop_time.stop_id.clone()?)))
+            .collect();
+        stop_times.sort_by_key(|(stop_sequence, _)| *stop_sequence);
+        let from = stop_times
+            .iter()
+            .position(|(_, stop_id)| *stop_id == leg.from_stop_id);
+        let to = from.and_then(|from| {
+            stop_times[from..]
+                .iter()
+                .position(|(_, stop_id)| *stop_id == leg.to_stop_id)
+                .map(|to| from + to)
+        });
+        let mut zones: HashSet<ZoneId> = match (from, to) {
+            (Some(from), Some(to)) => stop_times[from..=to]
+                .iter()
+                .filter_map(|(_, stop_id)| zone(stop_id))
+                .collect(),
+            _ => HashSet::new(),
+        };
+        let from_zone = zone(&leg.from_stop_id);
+        let to_zone = zone(&leg.to_stop_id);
+        zones.extend(from_zone.clone());
+        zones.extend(to_zone.clone());
+
+        Some(RiddenLeg {
+            route_id,
+            agency_id,
+            from_zone,
+            to_zone,
+            zones,
+            departure: i64::from(leg.date.num_days_from_ce()) * 86_400
+                + i64::from(leg.departure_time.as_secs()),
+        })
+    }
+}
+
+/// A fare product a leg can be paid with, through a rule of fare_leg_rules.txt.
+struct LegProduct {
+    leg_group_id: Option<FareLegRuleId>,
+    product: FareProduct,
+}
+
+/// The cheapest way found to pay for the legs of a journey up to a given one.
+#[derive(Clone)]
+struct JourneyState {
+    cost: Decimal,
+    /// The price paid for the last leg itself, which a transfer of type
+    /// [`FareTransferType::TransferOnly`] replaces.
+    charged: Option<Decimal>,
+    purchases: Vec<FareProductPurchase>,
+}
+
+impl Dataset {
+    /// Computes the cost of a journey, with the fare_leg_rules.txt and fare_transfer_rules.txt of
+    /// Fares v2.
+    ///
+    /// Each leg is matched against the rules of fare_leg_rules.txt by its network, the areas of
+    /// its stops, and the timeframes in effect when the rider boards and alights. An empty field
+    /// of a rule matches the legs that no rule lists a value for, or any leg when the rules have
+    /// a `rule_priority`, in which case only the matching rules of the highest priority are kept.
+    ///
+    /// Consecutive legs may then be linked by a rule of fare_transfer_rules.txt between their
+    /// leg groups, within its `transfer_count` and `duration_limit`. The cost of the transfer
+    /// follows its `fare_transfer_type`: the transfer is paid in addition to the first leg
+    /// (`A + AB`), in addition to both legs (`A + AB + B`), or instead of the first leg (`AB`).
+    ///
+    /// As prices in different currencies cannot be compared, the cheapest fare of each currency
+    /// is returned, sorted by currency code. No fare is returned if a leg matches no rule or
+    /// rides an unknown trip.
+    pub fn journey_fares(&self, legs: &[FareLeg]) -> Vec<JourneyFare> {
+        if legs.is_empty() {
+            return vec![];
+        }
+        let Some(leg_products) = legs
+            .iter()
+            .map(|leg| {
+                let products = self.leg_products(leg);
+                (!products.is_empty()).then_some(products)
+            })
+            .collect::<Option<Vec<_>>>()
+        else {
+            return vec![];
+        };
+
+        let mut currencies: Vec<Currency> = leg_products
+            .iter()
+            .flatten()
+            .map(|leg_product| leg_product.product.currency)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        currencies.sort_by_key(|currency| currency.code());
+
+        currencies
+            .into_iter()
+            .filter_map(|currency| self.cheapest_journey_fare(legs, &leg_products, currency))
+            .collect()
+    }
+
+    /// Finds the cheapest way to pay for a journey in a currency.
+    fn cheapest_journey_fare(
+        &self,
+        legs: &[FareLeg],
+        leg_products: &[Vec<LegProduct>],
+        currency: Currency,
+    ) -> Option<JourneyFare> {
+        let purchase = |product: &FareProduct, leg: usize, transfer: bool| FareProductPurchase {
+            fare_product_id: product.fare_product_id.clone(),
+            fare_media_id: product.fare_media_id.clone(),
+            price: product.money(),
+            leg,
+            transfer,
+        };
+        let listed_from: HashSet<&FareLegRuleId> = self
+            .fare_transfers
+            .iter()
+            .filter_map(|rule| rule.from_leg_group_id.as_ref())
+            .collect();
+        let listed_to: HashSet<&FareLegRuleId> = self
+            .fare_transfers
+            .iter()
+            .filter_map(|rule| rule.to_leg_group_id.as_ref())
+            .collect();
+
+        // The cheapest states reaching a leg, by product paying for the leg and number of
+        // consecutive transfers.
+        let mut states: BTreeMap<(usize, i32), JourneyState> = BTreeMap::new();
+        for (index, leg_product) in leg_products[0].iter().enumerate() {
+            if leg_product.product.currency != currency {
+                continue;
+            }
+            states.insert(
+                (index, 0),
+                JourneyState {
+                    cost: leg_product.product.amount,
+                    charged: Some(leg_product.product.amount),
+                    purchases: vec![purchase(&leg_product.product, 0, false)],
+                },
+            );
+        }
+
+        for leg in 1..legs.len() {
+            let mut next: BTreeMap<(usize, i32), JourneyState> = BTreeMap::new();
+            let mut keep = |key: (usize, i32), state: JourneyState| {
+                if next.get(&key).is_none_or(|kept| state.cost < kept.cost) {
+                    next.insert(key, state);
+                }
+            };
+
+            for (&(from_index, transfers), state) in &states {
+                let from_product = &leg_products[leg - 1][from_index];
+                for (to_index, to_product) in leg_products[leg].iter().enumerate() {
+                    if to_product.product.currency != currency {
+                        continue;
+                    }
+                    let price = to_product.product.amount;
+
+                    // Pay for the leg on its own.
+                    let mut purchases = state.purchases.clone();
+                    purchases.push(purchase(&to_product.product, leg, false));
+                    keep(
+                        (to_index, 0),
+                        JourneyState {
+                            cost: state.cost + price,
+                            charged: Some(price),
+                            purchases,
+                        },
+                    );
+
+                    // Or transfer to it.
+                    for rule in &self.fare_transfers {
+                        if !group_matches(
+                            &rule.from_leg_group_id,
+                            &from_product.leg_group_id,
+                            &listed_from,
+                        ) || !group_matches(
+                            &rule.to_leg_group_id,
+                            &to_product.leg_group_id,
+                            &listed_to,
+                        ) || !within_transfer_count(rule, transfers + 1)
+                            || !within_duration_limit(rule, &legs[leg - 1], &legs[leg])
+                        {
+                            continue;
+                        }
+                        let transfer_product = match &rule.fare_product_id {
+                            Some(fare_product_id) => {
+                                match self.cheapest_fare_product(fare_product_id, currency) {
+                                    Some(product) => Some(product),
+                                    None => continue,
+                                }
+                            }
+                            None => None,
+                        };
+
+                        let mut state = state.clone();
+                        if rule.fare_transfer_type == FareTransferType::TransferOnly {
+                            if let Some(charged) = state.charged {
+                                state.cost -= charged;
+                                state.purchases.pop();
+                            }
+                        }
+                        state.charged = None;
+                        if let Some(product) = transfer_product {
+                            state.cost += product.amount;
+                            state.purchases.push(purchase(&product, leg, true));
+                        }
+                        if rule.fare_transfer_type == FareTransferType::FromLegPlusTransferPlusToLeg
+                        {
+                            state.cost += price;
+                            state.charged = Some(price);
+                            state
+                                .purchases
+                                .push(purchase(&to_product.product, leg, false));
+                        }
+                        keep((to_index, transfers + 1), state);
+                    }
+                }
+            }
+            states = next;
+        }
+
+        let state = states.into_values().reduce(|cheapest, state| {
+            if state.cost < cheapest.cost {
+                state
+            } else {
+                cheapest
+            }
+        })?;
+        Some(JourneyFare {
+            purchases: state.purchases,
+            total: Money::new(state.cost, currency),
+        })
+    }
+
+    /// Finds the fare products a leg can be paid with, sorted by leg group and fare product.
+    fn leg_products(&self, leg: &FareLeg) -> Vec<LegProduct> {
+        let Some(route_id) = self
+            .trips
+            .get(&leg.trip_id)
+            .map(|trip| trip.route_id.clone())
+        else {
+            return vec![];
+        };
+        let networks: Vec<NetworkId> = self.network_of_route(&route_id).into_iter().collect();
+        let from_areas = self.areas_for_stop(&leg.from_stop_id);
+        let to_areas = self.areas_for_stop(&leg.to_stop_id);
+        let from_timeframes = self.timeframes_in_effect(leg.date, leg.departure_time);
+        let to_timeframes = self.timeframes_in_effect(leg.date, leg.arrival_time);
+
+        let rules = &self.fare_leg_rules;
+        let prioritized = rules.iter().any(|rule| rule.rule_priority.is_some());
+        let listed = |field: fn(&FareLegRule) -> Option<&str>| -> HashSet<&str> {
+            rules.iter().filter_map(field).collect()
+        };
+        let listed_networks = listed(|rule| rule.network_id.as_deref());
+        let listed_from_areas = listed(|rule| rule.from_area_id.as_deref());
+        let listed_to_areas = listed(|rule| rule.to_area_id.as_deref());
+        let listed_from_timeframes = listed(|rule| rule.from_timeframe_group_id.as_deref());
+        let listed_to_timeframes = listed(|rule| rule.to_timeframe_group_id.as_deref());
+
+        let matching: Vec<&FareLegRule> = rules
+            .iter()
+            .filter(|rule| {
+                field_matches(
+                    rule.network_id.as_deref(),
+                    &networks,
+                    &listed_networks,
+                    prioritized,
+                ) && field_matches(
+                    rule.from_area_id.as_deref(),
+                    &from_areas,
+                    &listed_from_areas,
+                    prioritized,
+                ) && field_matches(
+                    rule.to_area_id.as_deref(),
+                    &to_areas,
+                    &listed_to_areas,
+                    prioritized,
+                ) && field_matches(
+                    rule.from_timeframe_group_id.as_deref(),
+                    &from_timeframes,
+                    &listed_from_timeframes,
+                    prioritized,
+                ) && field_matches(
+                    rule.to_timeframe_group_id.as_deref(),
+                    &to_timeframes,
+                    &listed_to_timeframes,
+                    prioritized,
+                )
+            })
+            .collect();
+        let priority = matching
+            .iter()
+            .map(|rule| rule.rule_priority.unwrap_or(0))
+            .max();
+
+        let mut leg_products: Vec<LegProduct> = matching
+            .into_iter()
+            .filter(|rule| Some(rule.rule_priority.unwrap_or(0)) == priority)
+            .flat_map(|rule| {
+                self.fare_products
+                    .iter()
+                    .filter(|product| product.fare_product_id == rule.fare_product_id)
+                    .map(|product| LegProduct {
+                        leg_group_id: rule.leg_group_id.clone(),
+                        product: product.value().clone(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        leg_products.sort_by(|a, b| {
+            a.leg_group_id
+                .as_deref()
+                .cmp(&b.leg_group_id.as_deref())
+                .then_with(|| a.product.fare_product_id.cmp(&b.product.fare_product_id))
+                .then_with(|| {
+                    a.product
+                        .fare_media_id
+                        .as_deref()
+                        .cmp(&b.product.fare_media_id.as_deref())
+                })
+        });
+        leg_products
+    }
+
+    /// Returns the timeframe groups in effect at a time of a service day.
+    fn timeframes_in_effect(
+        &self,
+        date: NaiveDate,
+        time: NaiveServiceTime,
+    ) -> Vec<TimeframeGroupId> {
+        // Times past midnight happen on the next calendar day.
+        let secs = time.as_secs();
+        let (Some(date), Some(time)) = (
+            date.checked_add_days(Days::new(u64::from(secs / 86_400))),
+            NaiveTime::from_num_seconds_from_midnight_opt(secs % 86_400, 0),
+        ) else {
+            return vec![];
+        };
+
+        let mut timeframes: Vec<TimeframeGroupId> = vec![];
+        for timeframe in &self.timeframes {
+            if timeframe
+                .start_time
+                .is_none_or(|start_time| start_time <= time)
+                && timeframe.end_time.is_none_or(|end_time| time < end_time)
+                && self.is_service_active(&timeframe.service_id, date)
+                && !timeframes.contains(&timeframe.timeframe_group_id)
+            {
+                timeframes.push(timeframe.timeframe_group_id.clone());
+            }
+        }
+        timeframes
+    }
+
+    /// Returns the cheapest fare product with an id in a currency, among its fare media.
+    fn cheapest_fare_product(
+        &self,
+        fare_product_id: &FareProductId,
+        currency: Currency,
+    ) -> Option<FareProduct> {
+        self.fare_products
+            .iter()
+            .filter(|product| {
+                product.fare_product_id == *fare_product_id && product.currency == currency
+            })
+            .map(|product| product.value().clone())
+            .min_by(|a, b| {
+                a.amount
+                    .cmp(&b.amount)
+                    .then_with(|| a.fare_media_id.as_deref().cmp(&b.fare_media_id.as_deref()))
+            })
+    }
+}
+
+/// Returns `true` if a field of a rule of fare_leg_rules.txt matches the values of a leg.
+///
+/// An empty field matches the legs without any value listed by the rules, or any leg when the
+/// rules are prioritized.
+fn field_matches<T: AsRef<str>>(
+    rule_value: Option<&str>,
+    leg_values: &[T],
+    listed: &HashSet<&str>,
+    prioritized: bool,
+) -> bool {
+    match rule_value {
+        Some(rule_value) => leg_values.iter().any(|value| value.as_ref() == rule_value),
+        None => {
+            prioritized
+                || !leg_values
+                    .iter()
+                    .any(|value| listed.contains(value.as_ref()))
+        }
+    }
+}
+
+/// Returns `true` if a leg group of a rule of fare_transfer_rules.txt matches that of a leg.
+///
+/// An empty leg group matches the leg groups that no rule lists.
+fn group_matches(
+    rule_group: &Option<FareLegRuleId>,
+    leg_group: &Option<FareLegRuleId>,
+    listed: &HashSet<&FareLegRuleId>,
+) -> bool {
+    match (rule_group, leg_group) {
+        (Some(rule_group), Some(leg_group)) => rule_group == leg_group,
+        (None, Some(leg_group)) => !listed.contains(leg_group),
+        (_, None) => false,
+    }
+}
+
+/// Returns `true` if a transfer rule may be applied to the given number of consecutive transfers.
+fn within_transfer_count(rule: &FareTransferRule, transfers: i32) -> bool {
+    match rule.transfer_count {
+        Some(transfer_count) if transfer_count >= 0 => transfers <= transfer_count,
+        _ => true,
+    }
+}
+
+/// Returns `true` if a transfer between two legs happens within the duration limit of a rule.
+fn within_duration_limit(rule: &FareTransferRule, from: &FareLeg, to: &FareLeg) -> bool {
+    let Some(FareTransferRuleDurationLimit::WithLimit(limit)) = &rule.duration_limit else {
+        return true;
+    };
+    let instant = |leg: &FareLeg, time: NaiveServiceTime| {
+        i64::from(leg.date.num_days_from_ce()) * 86_400 + i64::from(time.as_secs())
+    };
+    let (start, end) = match rule.duration_limit_type {
+        Some(DurationLimitType::BetweenDepartureAndArrival | DurationLimitType::Unknown(_))
+        | None => (
+            instant(from, from.departure_time),
+            instant(to, to.arrival_time),
+        ),
+        Some(DurationLimitType::BetweenDepartureAndDeparture) => (
+            instant(from, from.departure_time),
+            instant(to, to.departure_time),
+        ),
+        Some(DurationLimitType::BetweenArrivalAndDeparture) => (
+            instant(from, from.arrival_time),
+            instant(to, to.departure_time),
+        ),
+        Some(DurationLimitType::BetweenArrivalAndArrival) => (
+            instant(from, from.arrival_time),
+            instant(to, to.arrival_time),
+        ),
+    };
+    end - start <= i64::try_from(limit.get()).unwrap_or(i64::MAX)
+}
+
+/// The route, origin and destination of a rule of fare_rules.txt.
+type RuleKey<'a> = (&'a Option<RouteId>, &'a Option<ZoneId>, &'a Option<ZoneId>);
+
+/// Returns `true` if a fare can pay for a run of consecutive legs.
+fn covers(fare: &FareAttribute, rules: &[&FareRule], run: &[RiddenLeg]) -> bool {
+    let (Some(first), Some(last)) = (run.first(), run.last()) else {
+        return false;
+    };
+
+    let max_transfers = match fare.transfers {
+        FareTransfers::NoTransfers => 0,
+        FareTransfers::OneTransfer => 1,
+        FareTransfers::TwoTransfers => 2,
+        FareTransfers::UnlimitedTransfers => usize::MAX,
+        FareTransfers::Unknown(transfers) => usize::from(transfers),
+    };
+    if run.len() - 1 > max_transfers {
+        return false;
+    }
+    if let Some(transfer_duration) = fare.transfer_duration {
+        let transfer_duration = i64::try_from(transfer_duration.as_secs()).unwrap_or(i64::MAX);
+        if last.departure - first.departure > transfer_duration {
+            return false;
+        }
+    }
+    if let Some(agency_id) = &fare.agency_id {
+        if run
+            .iter()
+            .any(|leg| leg.agency_id.as_ref() != Some(agency_id))
+        {
+            return false;
+        }
+    }
+    if rules.is_empty() {
+        return true;
+    }
+
+    // Rows of fare_rules.txt only differing by their contained zone make up a single rule.
+    let mut contained: HashMap<RuleKey, HashSet<&ZoneId>> = HashMap::new();
+    for rule in rules {
+        contained
+            .entry((&rule.route_id, &rule.origin_id, &rule.destination_id))
+            .or_default()
+            .extend(&rule.contains_id);
+    }
+    let zones: HashSet<&ZoneId> = run.iter().flat_map(|leg| &leg.zones).collect();
+    let matching: Vec<&Option<RouteId>> = contained
+        .iter()
+        .filter(|((_, origin_id, destination_id), contains)| {
+            origin_id
+                .as_ref()
+                .is_none_or(|origin_id| first.from_zone.as_ref() == Some(origin_id))
+                && destination_id
+                    .as_ref()
+                    .is_none_or(|destination_id| last.to_zone.as_ref() == Some(destination_id))
+                && (contains.is_empty() || **contains == zones)
+        })
+        .map(|((route_id, _, _), _)| *route_id)
+        .collect();
+    run.iter().all(|leg| {
+        matching.iter().any(|route_id| {
+            route_id
+                .as_ref()
+                .is_none_or(|route_id| leg.route_id == *route_id)
+        })
+    })
+}
@@ -0,0 +1,268 @@
+//! Collections of datasets published by different operators of a region.
+//!
+//! The main types are:
+//! - [`FeedCollection`]: Datasets keyed by feed id, queried together.
+//! - [`FeedStop`]: A stop of one of the feeds of a collection.
+//! - [`StopMatch`]: Stops of different feeds standing for the same place.
+//! - [`FeedDeparture`]: A departure from a stop of one of the feeds.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{Duration, NaiveDateTime};
+use geo::{Coord, HaversineDistance, Point};
+
+use crate::schemas::{LocationType, RouteId, StopId, TripId};
+use crate::station::{normalize_name, similarity};
+use crate::Dataset;
+
+/// Datasets keyed by feed id, e.g. those of the operators of a region.
+///
+/// Each dataset keeps its own identifiers, so records are referred to by feed id and record id,
+/// see [`FeedStop`].
+#[derive(Default)]
+pub struct FeedCollection {
+    feeds: BTreeMap<String, Dataset>,
+}
+
+/// A stop of one of the feeds of a [`FeedCollection`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FeedStop {
+    /// Identifies the feed.
+    pub feed_id: String,
+    /// Identifies the stop within the feed.
+    pub stop_id: StopId,
+}
+
+/// Stops of different feeds standing for the same place, see [`FeedCollection::match_stops`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StopMatch {
+    /// The stops, sorted by feed id then stop id.
+    pub stops: Vec<FeedStop>,
+}
+
+/// A departure from a stop of one of the feeds, see [`FeedCollection::departures_at`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedDeparture {
+    /// Identifies the feed.
+    pub feed_id: String,
+    /// The stop departed from.
+    pub stop_id: StopId,
+    /// The trip departing.
+    pub trip_id: TripId,
+    /// The route of the trip.
+    pub route_id: RouteId,
+    /// The [`crate::schemas::Trip::trip_headsign`] of the trip.
+    pub headsign: Option<String>,
+    /// When the trip leaves the stop.
+    pub departure: NaiveDateTime,
+}
+
+impl FeedStop {
+    /// Orders stops by feed id, then by stop id.
+    fn cmp(&self, other: &FeedStop) -> std::cmp::Ordering {
+        (self.feed_id.as_str(), self.stop_id.as_str())
+            .cmp(&(other.feed_id.as_str(), other.stop_id.as_str()))
+    }
+}
+
+impl FeedCollection {
+    /// Creates an empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a dataset, returning the dataset it replaces under the same feed id.
+    pub fn insert(&mut self, feed_id: impl Into<String>, dataset: Dataset) -> Option<Dataset> {
+        self.feeds.insert(feed_id.into(), dataset)
+    }
+
+    /// Removes a dataset.
+    pub fn remove(&mut self, feed_id: &str) -> Option<Dataset> {
+        self.feeds.remove(feed_id)
+    }
+
+    /// Returns the dataset of a feed.
+    pub fn get(&self, feed_id: &str) -> Option<&Dataset> {
+        self.feeds.get(feed_id)
+    }
+
+    /// Returns the feeds and their datasets, sorted by feed id.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Dataset)> {
+        self.feeds
+            .iter()
+            .map(|(feed_id, dataset)| (feed_id.as_str(), dataset))
+    }
+
+    /// Returns the number of feeds.
+    pub fn len(&self) -> usize {
+        self.feeds.len()
+    }
+
+    /// Returns `true` if the collection holds no feed.
+    pub fn is_empty(&self) -> bool {
+        self.feeds.is_empty()
+    }
+
+    /// Links the stops of different feeds that stand for the same place, e.g. a stop served by
+    /// two operators.
+    ///
+    /// Two stops match when they belong to different feeds, are of the same kind (stops and
+    /// platforms, or stations), are at most `max_distance_m` meters apart and the similarity of
+    /// their names is at least `name_similarity`, as in [`Dataset::infer_parent_stations`].
+    /// Matches are formed transitively, so they may hold several stops of a feed.
+    ///
+    /// Returns the matches sorted by their first stop.
+    pub fn match_stops(&self, max_distance_m: f64, name_similarity: f64) -> Vec<StopMatch> {
+        let mut stops: Vec<(FeedStop, bool, Coord, String)> = vec![];
+        for (feed_id, dataset) in &self.feeds {
+            for stop in dataset.stops.iter() {
+                let is_station = match stop.location_type {
+                    None | Some(LocationType::StopOrPlatform) => false,
+                    Some(LocationType::Station) => true,
+                    _ => continue,
+                };
+                let (Some(coord), Some(name)) = (stop.stop_coord.as_ref(), &stop.stop_name) else {
+                    continue;
+                };
+                let name = normalize_name(name);
+                if !name.is_empty() {
+                    let feed_stop = FeedStop {
+                        feed_id: feed_id.clone(),
+                        stop_id: stop.stop_id.clone(),
+                    };
+                    stops.push((feed_stop, is_station, **coord, name));
+                }
+            }
+        }
+        stops.sort_by(|a, b| a.2.y.total_cmp(&b.2.y).then_with(|| a.0.cmp(&b.0)));
+
+        // Union-find of the stops, sweeping them by latitude, as in
+        // `Dataset::infer_parent_stations`.
+        let max_latitude_delta = max_distance_m / 110_000.0;
+        let mut groups: Vec<usize> = (0..stops.len()).collect();
+        fn root(groups: &mut [usize], mut index: usize) -> usize {
+            while groups[index] != index {
+                groups[index] = groups[groups[index]];
+                index = groups[index];
+            }
+            index
+        }
+        for i in 0..stops.len() {
+            for j in i + 1..stops.len() {
+                let ((a, a_station, a_coord, a_name), (b, b_station, b_coord, b_name)) =
+                    (&stops[i], &stops[j]);
+                if b_coord.y - a_coord.y > max_latitude_delta {
+                    break;
+                }
+                if a.feed_id != b.feed_id
+                    && a_station == b_station
+                    && Point::from(*a_coord).haversine_distance(&Point::from(*b_coord))
+                        <= max_distance_m
+                    && similarity(a_name, b_name) >= name_similarity
+                {
+                    let (a, b) = (root(&mut groups, i), root(&mut groups, j));
+                    groups[a.max(b)] = a.min(b);
+                }
+            }
+        }
+        let mut members: HashMap<usize, Vec<FeedStop>> = HashMap::new();
+        for (index, (feed_stop, _, _, _)) in stops.iter().enumerate() {
+            let group = root(&mut groups, index);
+            members.entry(group).or_default().push(feed_stop.clone());
+        }
+
+        let mut matches: Vec<StopMatch> = members
+            .into_values()
+            .filter(|stops| stops.len() > 1)
+            .map(|mut stops| {
+                stops.sort_by(FeedStop::cmp);
+                StopMatch { stops }
+            })
+            .collect();
+        matches.sort_by(|a, b| a.stops[0].cmp(&b.stops[0]));
+        matches
+    }
+
+    /// Returns the departures from `stops` between `from` and `until` included, whatever their
+    /// feed, sorted by time then by feed and trip.
+    ///
+    /// Passing the stops of a [`StopMatch`] gives the departures of every operator serving a
+    /// place. Trips defined in frequencies.txt depart once per headway. As in
+    /// [`Dataset::service_span`], a stop time without departure time departs at its arrival
+    /// time; the stop times ending their trip are left out.
+    pub fn departures_at(
+        &self,
+        stops: &[FeedStop],
+        from: NaiveDateTime,
+        until: NaiveDateTime,
+    ) -> Vec<FeedDeparture> {
+        let mut stops_by_feed: BTreeMap<&str, HashSet<&StopId>> = BTreeMap::new();
+        for stop in stops {
+            stops_by_feed
+                .entry(stop.feed_id.as_str())
+                .or_default()
+                .insert(&stop.stop_id);
+        }
+
+        let mut departures = vec![];
+        for (feed_id, stop_ids) in stops_by_feed {
+            let Some(dataset) = self.feeds.get(feed_id) else {
+                continue;
+            };
+            let stopping_trips: HashSet<TripId> = dataset
+                .stop_times
+                .iter()
+                .filter(|stop_time| {
+                    stop_time
+                        .stop_id
+                        .as_ref()
+                        .is_some_and(|stop_id| stop_ids.contains(stop_id))
+                })
+                .map(|stop_time| stop_time.trip_id.clone())
+                .collect();
+
+            // Times of the previous service day may go past midnight.
+            let mut date = from.date() - Duration::days(1);
+            while date <= until.date() {
+                let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+                for (trip_id, runs) in
+                    dataset.active_runs(date, |trip_id| stopping_trips.contains(trip_id))
+                {
+                    let Some(trip) = dataset.trips.get(&trip_id) else {
+                        continue;
+                    };
+                    for run in runs {
+                        let end = run.iter().map(|(_, time)| *time).max();
+                        for (stop_id, time) in &run {
+                            let departure = midnight + Duration::seconds(i64::from(*time));
+                            if Some(*time) == end
+                                || !stop_ids.contains(stop_id)
+                                || departure < from
+                                || departure > until
+                            {
+                                continue;
+                            }
+                            departures.push(FeedDeparture {
+                                feed_id: feed_id.to_string(),
+                                stop_id: stop_id.clone(),
+                                trip_id: trip_id.clone(),
+                                route_id: trip.route_id.clone(),
+                                headsign: trip.trip_headsign.clone(),
+                                departure,
+                            });
+                        }
+                    }
+                }
+                date += Duration::days(1);
+            }
+        }
+
+        departures.sort_by(|a, b| {
+            a.departure
+                .cmp(&b.departure)
+                .then_with(|| a.feed_id.cmp(&b.feed_id))
+                .then_with(|| a.trip_id.as_str().cmp(b.trip_id.as_str()))
+        });
+        departures
+    }
+}
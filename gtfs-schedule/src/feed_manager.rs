@@ -0,0 +1,245 @@
+//! Periodic reloading of a dataset in the background, for services answering queries about it.
+//!
+//! The main types are:
+//! - [`FeedManager`]: Holds the current dataset, and swaps it for a fresh one once reloaded.
+//! - [`FeedManagerBuilder`]: Sets where the dataset comes from, how often it is reloaded and
+//!   who is notified.
+
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::{Dataset, FrozenDataset};
+
+/// Loads the dataset served by a [`FeedManager`], each time it is reloaded.
+///
+/// Any closure returning a dataset is a source. Fetching a remote feed is left to the HTTP
+/// client of the service, the archive being parsed with `Dataset::from_zip_bytes` (`zip`
+/// feature):
+///
+/// ```ignore
+/// let source = move || {
+///     let bytes = http_client.get("https://example.com/gtfs.zip")?.bytes()?;
+///     Dataset::from_zip_bytes(&bytes)
+/// };
+/// ```
+pub trait FeedSource: Send {
+    /// Loads a fresh copy of the dataset.
+    fn load(&mut self) -> Result<Dataset>;
+}
+
+impl<F: FnMut() -> Result<Dataset> + Send> FeedSource for F {
+    fn load(&mut self) -> Result<Dataset> {
+        self()
+    }
+}
+
+/// Called with the new dataset after each successful reload.
+type ReloadHook = Box<dyn Fn(&FrozenDataset) + Send + Sync>;
+/// Called with the error of each failed reload.
+type ErrorHook = Box<dyn Fn(&Error) + Send + Sync>;
+
+/// Sets up a [`FeedManager`], see [`FeedManager::builder`].
+pub struct FeedManagerBuilder {
+    source: Box<dyn FeedSource>,
+    interval: Duration,
+    validate: bool,
+    on_reload: Option<ReloadHook>,
+    on_error: Option<ErrorHook>,
+}
+
+impl FeedManagerBuilder {
+    /// Choose the time waited between two reloads, one hour by default.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Choose whether a reloaded dataset must pass [`Dataset::validate`] before being served,
+    /// `true` by default.
+    pub fn validate(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Calls `hook` with the new dataset after each successful reload, e.g. to warm up caches
+    /// or log the new version. The initial load is not reported.
+    pub fn on_reload(mut self, hook: impl Fn(&FrozenDataset) + Send + Sync + 'static) -> Self {
+        self.on_reload = Some(Box::new(hook));
+        self
+    }
+
+    /// Calls `hook` with the error of each failed reload, the previous dataset being served
+    /// meanwhile.
+    pub fn on_error(mut self, hook: impl Fn(&Error) + Send + Sync + 'static) -> Self {
+        self.on_error = Some(Box::new(hook));
+        self
+    }
+
+    /// Loads the dataset, then starts reloading it in the background.
+    ///
+    /// Fails if the initial load fails, as there is nothing to serve yet.
+    pub fn start(self) -> Result<FeedManager> {
+        let mut source = self.source;
+        let dataset = load(source.as_mut(), self.validate)?;
+
+        let shared = Arc::new(Shared {
+            current: RwLock::new(Arc::new(dataset)),
+            source: Mutex::new(source),
+            validate: self.validate,
+            on_reload: self.on_reload,
+            on_error: self.on_error,
+            stopped: Mutex::new(false),
+            wake_up: Condvar::new(),
+        });
+        let thread = {
+            let shared = Arc::clone(&shared);
+            let interval = self.interval;
+            std::thread::spawn(move || loop {
+                let stopped = shared.stopped.lock().unwrap_or_else(|e| e.into_inner());
+                let (stopped, _) = shared
+                    .wake_up
+                    .wait_timeout_while(stopped, interval, |stopped| !*stopped)
+                    .unwrap_or_else(|e| e.into_inner());
+                if *stopped {
+                    return;
+                }
+                drop(stopped);
+                // Failures are reported to the hook, the thread keeps going.
+                let _ = shared.reload();
+            })
+        };
+
+        Ok(FeedManager {
+            shared,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// The state shared by a [`FeedManager`] and its background thread.
+struct Shared {
+    current: RwLock<Arc<FrozenDataset>>,
+    source: Mutex<Box<dyn FeedSource>>,
+    validate: bool,
+    on_reload: Option<ReloadHook>,
+    on_error: Option<ErrorHook>,
+    stopped: Mutex<bool>,
+    wake_up: Condvar,
+}
+
+/// Loads a dataset from `source`, validating it if asked to.
+fn load(source: &mut dyn FeedSource, validate: bool) -> Result<FrozenDataset> {
+    let dataset = source.load()?;
+    if validate {
+        dataset.validate()?;
+    }
+    Ok(dataset.freeze())
+}
+
+impl Shared {
+    fn reload(&self) -> Result<()> {
+        // Holding the source also keeps two reloads from running at once.
+        let mut source = self.source.lock().unwrap_or_else(|e| e.into_inner());
+        match load(source.as_mut(), self.validate) {
+            Ok(dataset) => {
+                let dataset = Arc::new(dataset);
+                *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::clone(&dataset);
+                #[cfg(feature = "tracing")]
+                tracing::info!(
+                    stops = dataset.stops.len(),
+                    trips = dataset.trips.len(),
+                    "feed reloaded"
+                );
+                if let Some(on_reload) = &self.on_reload {
+                    on_reload(&dataset);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %e, "feed reload failed");
+                if let Some(on_error) = &self.on_error {
+                    on_error(&e);
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Holds the current dataset of a service, and reloads it periodically in the background.
+///
+/// A reloaded dataset is parsed and validated aside, then swapped in at once: queries keep
+/// being answered with the previous dataset meanwhile, and with it for good if the reload
+/// fails. The background thread stops when the manager is dropped.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use gtfs_schedule::{Dataset, FeedManager};
+///
+/// let manager = FeedManager::builder(|| Dataset::from_csv("feed".as_ref()))
+///     .interval(Duration::from_secs(15 * 60))
+///     .on_error(|e| eprintln!("keeping the previous feed: {e}"))
+///     .start()
+///     .unwrap();
+///
+/// // Each query works on a consistent dataset, even if a reload happens meanwhile.
+/// let dataset = manager.current();
+/// println!("{} trips", dataset.trips.len());
+/// ```
+pub struct FeedManager {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FeedManager {
+    /// Starts setting up a manager serving the datasets loaded by `source`.
+    pub fn builder(source: impl FeedSource + 'static) -> FeedManagerBuilder {
+        FeedManagerBuilder {
+            source: Box::new(source),
+            interval: Duration::from_secs(60 * 60),
+            validate: true,
+            on_reload: None,
+            on_error: None,
+        }
+    }
+
+    /// Returns the dataset currently served.
+    ///
+    /// The dataset is not affected by later reloads, so it should be fetched again for each
+    /// query rather than kept.
+    pub fn current(&self) -> Arc<FrozenDataset> {
+        Arc::clone(
+            &self
+                .shared
+                .current
+                .read()
+                .unwrap_or_else(|e| e.into_inner()),
+        )
+    }
+
+    /// Reloads the dataset right away, on the calling thread, without waiting for the next
+    /// periodic reload, e.g. when notified that the feed was published.
+    ///
+    /// The hooks are called as for periodic reloads, and the error is also returned.
+    pub fn reload(&self) -> Result<()> {
+        self.shared.reload()
+    }
+}
+
+impl Drop for FeedManager {
+    fn drop(&mut self) {
+        *self
+            .shared
+            .stopped
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = true;
+        self.shared.wake_up.notify_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
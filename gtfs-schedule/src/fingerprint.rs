@@ -0,0 +1,58 @@
+//! Content hash of a dataset.
+
+use std::collections::BTreeMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{ParseError, ParseErrorKind, Result};
+use crate::{Dataset, GtfsFile};
+
+impl Dataset {
+    /// Returns a SHA-256 hash of the content of the dataset, as a lowercase hexadecimal string.
+    ///
+    /// The hash is computed over the parsed records, so it does not depend on the order of the
+    /// rows or columns of the files, nor on the way values are formatted (e.g. `6:00:00` and
+    /// `06:00:00`). Two datasets with the same fingerprint describe the same content, which can
+    /// be used to detect that a newly downloaded feed did not change.
+    ///
+    /// Records are normalized by serializing them to JSON, then sorted within their table. Fails
+    /// if a record can not be serialized.
+    pub fn fingerprint(&self) -> Result<String> {
+        self.fingerprint_of(|_| true)
+    }
+
+    /// Same as [`Dataset::fingerprint`], but leaves feed_info.txt out, so that the hash only
+    /// changes with the services described by the dataset, not with its version or publisher.
+    pub fn content_fingerprint(&self) -> Result<String> {
+        self.fingerprint_of(|file| file != GtfsFile::FeedInfo)
+    }
+
+    /// Hashes the records of the files selected by `include`.
+    fn fingerprint_of(&self, include: impl Fn(GtfsFile) -> bool) -> Result<String> {
+        let mut tables: BTreeMap<GtfsFile, Vec<String>> = BTreeMap::new();
+        for (file, record) in self.iter_all().filter(|(file, _)| include(*file)) {
+            // Keys are sorted in the output of a JSON value.
+            let record = serde_json::to_value(record)
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?
+                .to_string();
+            tables.entry(file).or_default().push(record);
+        }
+
+        let mut hasher = Sha256::new();
+        for (file, mut records) in tables {
+            records.sort_unstable();
+            hasher.update(file.file_name().as_bytes());
+            hasher.update((records.len() as u64).to_le_bytes());
+            for record in records {
+                hasher.update(record.as_bytes());
+                hasher.update([b'\n']);
+            }
+        }
+
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect())
+    }
+}
@@ -0,0 +1,777 @@
+//! Geometric utilities built on top of shapes.
+//!
+//! The main types are:
+//! - [`ShapeGeometry`]: The path described by the points of a shape.
+//! - [`SnappedStop`]: The point of the shapes closest to a stop.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use geo::{
+    Coord, HaversineDistance, HaversineLength, Intersects, LineString, Point, Polygon, SimplifyIdx,
+};
+
+use crate::convert::escape;
+use crate::error::{DatasetValidationError, Result};
+use crate::schemas::{Schema, Shape, ShapeId, StopId, StopTime, TripId};
+use crate::Dataset;
+
+/// The path described by the points of a shape, ordered by [`Shape::shape_pt_sequence`].
+///
+/// Coordinates are expressed in degrees (`x` is the longitude, `y` the latitude) and distances in meters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeGeometry {
+    /// Identifies the shape.
+    pub shape_id: ShapeId,
+    /// The points of the shape.
+    pub line_string: LineString<f64>,
+    /// The [`Shape::shape_pt_sequence`] of each point of [`ShapeGeometry::line_string`].
+    pub shape_pt_sequences: Vec<u32>,
+}
+
+/// The point of the shapes closest to a stop, see [`Dataset::snap_stops_to_shapes`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnappedStop {
+    /// Identifies the stop.
+    pub stop_id: StopId,
+    /// The current coordinates of the stop.
+    pub coord: Coord,
+    /// The shape holding the closest point.
+    pub shape_id: ShapeId,
+    /// The point of the shape closest to the stop, suggested as its coordinates.
+    pub snapped_coord: Coord,
+    /// The distance, in meters, between the stop and the snapped point.
+    pub distance_m: f64,
+}
+
+impl ShapeGeometry {
+    /// Builds the geometry of a shape from its points.
+    ///
+    /// The points are sorted by [`Shape::shape_pt_sequence`]. Returns `None` if there is no point.
+    pub fn from_points(mut points: Vec<Shape>) -> Option<Self> {
+        points.sort_by_key(|point| point.shape_pt_sequence);
+        let shape_id = points.first()?.shape_id.clone();
+
+        Some(Self {
+            shape_id,
+            line_string: points.iter().map(|point| *point.shape_pt).collect(),
+            shape_pt_sequences: points.iter().map(|point| point.shape_pt_sequence).collect(),
+        })
+    }
+
+    /// Total length of the shape, in meters.
+    pub fn total_length_m(&self) -> f64 {
+        self.line_string.haversine_length()
+    }
+
+    /// Simplifies the shape using the Ramer–Douglas–Peucker algorithm.
+    ///
+    /// `tolerance` is expressed in degrees, as the algorithm works on raw coordinates.
+    pub fn simplify(&self, tolerance: f64) -> Self {
+        let indices = self.line_string.simplify_idx(&tolerance);
+
+        Self {
+            shape_id: self.shape_id.clone(),
+            line_string: indices.iter().map(|&i| self.line_string[i]).collect(),
+            shape_pt_sequences: indices
+                .iter()
+                .map(|&i| self.shape_pt_sequences[i])
+                .collect(),
+        }
+    }
+
+    /// Returns the distance along the shape, in meters, of the point of the shape closest to `stop_coord`.
+    pub fn project_stop(&self, stop_coord: Coord) -> Option<f64> {
+        let (segment, ratio) = self.locate(stop_coord, 0)?;
+        let along: f64 = self
+            .line_string
+            .lines()
+            .take(segment)
+            .map(|line| line.haversine_length())
+            .sum();
+        let segment_length = self
+            .line_string
+            .lines()
+            .nth(segment)
+            .map_or(0.0, |line| line.haversine_length());
+
+        Some(along + ratio * segment_length)
+    }
+
+    /// Returns the distance, in meters, between `coord` and the point of the shape closest to it.
+    pub fn distance_m(&self, coord: Coord) -> Option<f64> {
+        let closest = self.closest_point(coord)?;
+        Some(Point::from(coord).haversine_distance(&Point::from(closest)))
+    }
+
+    /// Returns the point of the shape closest to `coord`, which may lie between two of its points.
+    pub fn closest_point(&self, coord: Coord) -> Option<Coord> {
+        let (segment, ratio) = self.locate(coord, 0)?;
+        let points = &self.line_string.0;
+        let start = points[segment];
+        Some(match points.get(segment + 1) {
+            Some(end) => start + (*end - start) * ratio,
+            None => start,
+        })
+    }
+
+    /// Cumulative distance, in meters, from the first point of the shape to each of its points.
+    fn cumulative_distances_m(&self) -> Vec<f64> {
+        let mut total = 0.0;
+        std::iter::once(0.0)
+            .chain(self.line_string.lines().map(|line| {
+                total += line.haversine_length();
+                total
+            }))
+            .collect()
+    }
+
+    /// Finds the segment closest to `coord`, starting the search at segment `from`.
+    ///
+    /// Returns the index of the segment, and the position of the closest point on it as a ratio
+    /// of its length. A shape made of a single point is considered as a single empty segment.
+    fn locate(&self, coord: Coord, from: usize) -> Option<(usize, f64)> {
+        let points = &self.line_string.0;
+        if points.len() < 2 {
+            return points.first().map(|_| (0, 0.0));
+        }
+
+        points
+            .windows(2)
+            .enumerate()
+            .skip(from)
+            .map(|(index, segment)| {
+                let ratio = closest_ratio(segment[0], segment[1], coord);
+                let closest = segment[0] + (segment[1] - segment[0]) * ratio;
+                let distance = Point::from(coord).haversine_distance(&Point::from(closest));
+                (index, ratio, distance)
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(index, ratio, _)| (index, ratio))
+    }
+}
+
+/// Checks that a coordinate is within range and not (0, 0), see [`Dataset::validate_coordinates`].
+fn check_coordinate(coord: Coord, field_name: &str, record: Schema) -> Result<()> {
+    let (lat, lon) = (coord.y, coord.x);
+    let reason = if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        if (-90.0..=90.0).contains(&lon) && (-180.0..=180.0).contains(&lat) {
+            "out of range, the latitude and longitude look swapped"
+        } else {
+            "out of range"
+        }
+    } else if lat == 0.0 && lon == 0.0 {
+        "(0, 0) usually stands for a missing coordinate"
+    } else {
+        return Ok(());
+    };
+
+    Err(DatasetValidationError::new_suspicious_coordinate(
+        field_name.to_string(),
+        format!("{lat}, {lon}"),
+        reason.to_string(),
+        vec![record],
+    )
+    .into())
+}
+
+/// Position, as a ratio of the segment length, of the point of the segment `a`-`b` closest to `p`.
+///
+/// Longitudes are scaled by the cosine of the latitude so that the projection stays accurate
+/// away from the equator.
+fn closest_ratio(a: Coord, b: Coord, p: Coord) -> f64 {
+    let scale = ((a.y + b.y) / 2.0).to_radians().cos();
+    let (dx, dy) = ((b.x - a.x) * scale, b.y - a.y);
+    let (px, py) = ((p.x - a.x) * scale, p.y - a.y);
+    let length = dx * dx + dy * dy;
+    if length == 0.0 {
+        return 0.0;
+    }
+
+    ((px * dx + py * dy) / length).clamp(0.0, 1.0)
+}
+
+/// Discrete Fréchet distance, in meters, between the points of two shapes.
+///
+/// Unlike the Hausdorff distance, it follows the order of the points, so that a shape does not
+/// match the same path travelled the other way around.
+fn frechet_distance_m(a: &LineString<f64>, b: &LineString<f64>) -> f64 {
+    let distance = |i: usize, j: usize| Point::from(a[i]).haversine_distance(&Point::from(b[j]));
+    let mut previous: Vec<f64> = vec![];
+    for i in 0..a.0.len() {
+        let mut row: Vec<f64> = Vec::with_capacity(b.0.len());
+        for j in 0..b.0.len() {
+            let reachable = match (i, j) {
+                (0, 0) => 0.0,
+                (0, _) => row[j - 1],
+                (_, 0) => previous[0],
+                _ => previous[j].min(previous[j - 1]).min(row[j - 1]),
+            };
+            row.push(reachable.max(distance(i, j)));
+        }
+        previous = row;
+    }
+
+    previous.last().copied().unwrap_or(f64::INFINITY)
+}
+
+impl Dataset {
+    /// Returns the geometry of a shape, if it exists.
+    pub fn shape_geometry(&self, shape_id: &ShapeId) -> Option<ShapeGeometry> {
+        let points = self
+            .shapes
+            .iter()
+            .filter(|shape| shape.shape_id == *shape_id)
+            .map(|shape| shape.value().clone())
+            .collect();
+
+        ShapeGeometry::from_points(points)
+    }
+
+    /// Returns the path travelled by a trip, `None` if the trip is unknown or has no path.
+    ///
+    /// The path is the shape of the trip, or, without shape, the line linking the stops of the
+    /// trip in order. Stop times without coordinates, such as those of flexible services, are
+    /// skipped.
+    pub fn trip_geometry(&self, trip_id: &TripId) -> Option<LineString<f64>> {
+        let shape_id = self.trips.get(trip_id)?.shape_id.clone();
+        if let Some(geometry) = shape_id.and_then(|shape_id| self.shape_geometry(&shape_id)) {
+            return Some(geometry.line_string);
+        }
+
+        let mut stop_times: Vec<(u32, Coord)> = self
+            .stop_times
+            .iter()
+            .filter(|stop_time| stop_time.trip_id == *trip_id)
+            .filter_map(|stop_time| {
+                let stop = self.stops.get(stop_time.stop_id.as_ref()?)?;
+                Some((stop_time.stop_sequence, **stop.stop_coord.as_ref()?))
+            })
+            .collect();
+        if stop_times.is_empty() {
+            return None;
+        }
+        stop_times.sort_by_key(|(stop_sequence, _)| *stop_sequence);
+
+        Some(stop_times.into_iter().map(|(_, coord)| coord).collect())
+    }
+
+    /// Returns the path travelled by a trip as a WKT `LINESTRING`, see
+    /// [`Dataset::trip_geometry`].
+    pub fn trip_geometry_wkt(&self, trip_id: &TripId) -> Option<String> {
+        let points: Vec<String> = self
+            .trip_geometry(trip_id)?
+            .coords()
+            .map(|coord| format!("{} {}", coord.x, coord.y))
+            .collect();
+
+        Some(format!("LINESTRING ({})", points.join(", ")))
+    }
+
+    /// Returns the path travelled by a trip as a GPX document holding a single track named after
+    /// the trip, see [`Dataset::trip_geometry`].
+    pub fn trip_geometry_gpx(&self, trip_id: &TripId) -> Option<String> {
+        let points: String = self
+            .trip_geometry(trip_id)?
+            .coords()
+            .map(|coord| format!("      <trkpt lat=\"{}\" lon=\"{}\"/>\n", coord.y, coord.x))
+            .collect();
+
+        Some(format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<gpx version=\"1.1\" creator=\"gtfs-schedule\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+                "  <trk>\n",
+                "    <name>{}</name>\n",
+                "    <trkseg>\n",
+                "{}",
+                "    </trkseg>\n",
+                "  </trk>\n",
+                "</gpx>\n"
+            ),
+            escape(trip_id.as_str()),
+            points
+        ))
+    }
+
+    /// Returns the stops located within `polygon` or on its boundary, sorted by id.
+    ///
+    /// The polygon is expressed in degrees, as coordinates. Stops without coordinates, such as
+    /// generic nodes, are left out.
+    pub fn stops_in_polygon(&self, polygon: &Polygon<f64>) -> Vec<StopId> {
+        let mut stop_ids: Vec<StopId> = self
+            .stops
+            .iter()
+            .filter(|stop| {
+                stop.stop_coord
+                    .as_ref()
+                    .is_some_and(|coord| polygon.intersects(&Point::from(**coord)))
+            })
+            .map(|stop| stop.stop_id.clone())
+            .collect();
+        stop_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        stop_ids
+    }
+
+    /// Returns the trips running on the service day `date` whose path crosses `polygon`, or
+    /// stops within it, sorted by id.
+    ///
+    /// Paths are those of [`Dataset::trip_geometry`]. The geometry of each shape is only built
+    /// and tested once, whatever the number of trips following it.
+    pub fn trips_crossing_polygon(&self, polygon: &Polygon<f64>, date: NaiveDate) -> Vec<TripId> {
+        let crosses = |line_string: &LineString<f64>| match line_string.0.as_slice() {
+            [coord] => polygon.intersects(&Point::from(*coord)),
+            _ => polygon.intersects(line_string),
+        };
+
+        let mut active_services = HashMap::new();
+        let trips: Vec<(TripId, Option<ShapeId>)> = self
+            .trips
+            .iter()
+            .filter(|trip| {
+                *active_services
+                    .entry(trip.service_id.clone())
+                    .or_insert_with(|| self.is_service_active(&trip.service_id, date))
+            })
+            .map(|trip| (trip.trip_id.clone(), trip.shape_id.clone()))
+            .collect();
+
+        let shape_ids: HashSet<&ShapeId> = trips.iter().filter_map(|(_, id)| id.as_ref()).collect();
+        let mut points: HashMap<ShapeId, Vec<Shape>> = HashMap::new();
+        for shape in self.shapes.iter() {
+            if shape_ids.contains(&shape.shape_id) {
+                points
+                    .entry(shape.shape_id.clone())
+                    .or_default()
+                    .push(shape.value().clone());
+            }
+        }
+        let crossing_shapes: HashMap<ShapeId, bool> = points
+            .into_iter()
+            .filter_map(|(shape_id, points)| {
+                let geometry = ShapeGeometry::from_points(points)?;
+                Some((shape_id, crosses(&geometry.line_string)))
+            })
+            .collect();
+
+        // Trips without shape follow the line linking their stops.
+        let unshaped: HashSet<&TripId> = trips
+            .iter()
+            .filter(|(_, shape_id)| {
+                shape_id
+                    .as_ref()
+                    .is_none_or(|shape_id| !crossing_shapes.contains_key(shape_id))
+            })
+            .map(|(trip_id, _)| trip_id)
+            .collect();
+        let mut stop_coords: HashMap<&TripId, Vec<(u32, Coord)>> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            let Some(trip_id) = unshaped.get(&stop_time.trip_id) else {
+                continue;
+            };
+            if let Some(coord) = self.stop_time_coord(&stop_time) {
+                stop_coords
+                    .entry(*trip_id)
+                    .or_default()
+                    .push((stop_time.stop_sequence, coord));
+            }
+        }
+
+        let mut trip_ids: Vec<TripId> = trips
+            .iter()
+            .filter(|(trip_id, shape_id)| {
+                if let Some(crosses) = shape_id
+                    .as_ref()
+                    .and_then(|shape_id| crossing_shapes.get(shape_id))
+                {
+                    return *crosses;
+                }
+                stop_coords.get(trip_id).is_some_and(|coords| {
+                    let mut coords = coords.clone();
+                    coords.sort_by_key(|(stop_sequence, _)| *stop_sequence);
+                    crosses(&coords.into_iter().map(|(_, coord)| coord).collect())
+                })
+            })
+            .map(|(trip_id, _)| trip_id.clone())
+            .collect();
+        trip_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        trip_ids
+    }
+
+    /// Validates the coordinates of stops and shapes.
+    ///
+    /// Coordinates are reported as suspicious when:
+    /// - The latitude is not within [-90, 90] or the longitude within [-180, 180], the reason
+    ///   telling when they look swapped.
+    /// - They are (0, 0), which usually stands for a missing value.
+    /// - `max_spread_km` is given, and the stop is the farthest from the median coordinate of the
+    ///   stops while being more than `max_spread_km` away from it.
+    ///
+    /// [`Dataset::validate`] checks coordinates without `max_spread_km`, as the extent of a feed
+    /// ranges from a city to a continent.
+    pub fn validate_coordinates(&self, max_spread_km: Option<f64>) -> Result<()> {
+        for stop in self.stops.iter() {
+            if let Some(coord) = &stop.stop_coord {
+                check_coordinate(**coord, "stop_lat, stop_lon", stop.value().clone().into())?;
+            }
+        }
+        for shape in self.shapes.iter() {
+            check_coordinate(
+                *shape.shape_pt,
+                "shape_pt_lat, shape_pt_lon",
+                shape.value().clone().into(),
+            )?;
+        }
+
+        let Some(max_spread_km) = max_spread_km else {
+            return Ok(());
+        };
+        let coords: Vec<Coord> = self
+            .stops
+            .iter()
+            .filter_map(|stop| stop.stop_coord.as_ref().map(|coord| **coord))
+            .collect();
+        if coords.is_empty() {
+            return Ok(());
+        }
+        let median = |mut values: Vec<f64>| {
+            values.sort_by(f64::total_cmp);
+            values[values.len() / 2]
+        };
+        let center = Point::new(
+            median(coords.iter().map(|coord| coord.x).collect()),
+            median(coords.iter().map(|coord| coord.y).collect()),
+        );
+
+        let farthest = self
+            .stops
+            .iter()
+            .filter_map(|stop| {
+                let coord = **stop.stop_coord.as_ref()?;
+                let distance_km = Point::from(coord).haversine_distance(&center) / 1_000.0;
+                Some((distance_km, stop))
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((distance_km, stop)) = farthest {
+            if distance_km > max_spread_km {
+                let coord = stop
+                    .stop_coord
+                    .as_ref()
+                    .map_or(Coord::zero(), |coord| **coord);
+                return Err(DatasetValidationError::new_suspicious_coordinate(
+                    "stop_lat, stop_lon".to_string(),
+                    format!("{}, {}", coord.y, coord.x),
+                    format!(
+                        "{distance_km:.0} km away from the center of the stops, more than {max_spread_km} km"
+                    ),
+                    vec![stop.value().clone().into()],
+                )
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates that stops lie within `max_m` meters of the shapes of the trips serving them.
+    ///
+    /// A stop far from the path of its trips is usually misplaced, or the shape is wrong. The
+    /// stop farthest from one of its shapes is reported. Stops without coordinates and trips
+    /// without shape are not checked.
+    pub fn validate_stop_shape_distance(&self, max_m: f64) -> Result<()> {
+        let farthest = self
+            .stop_shape_distances()
+            .into_iter()
+            .flat_map(|(stop_id, distances)| {
+                distances
+                    .into_iter()
+                    .map(move |(shape_id, distance_m, _)| (stop_id.clone(), shape_id, distance_m))
+            })
+            .max_by(|a, b| a.2.total_cmp(&b.2));
+        let Some((stop_id, shape_id, distance_m)) = farthest else {
+            return Ok(());
+        };
+        if distance_m <= max_m {
+            return Ok(());
+        }
+        let Some(stop) = self.stops.get(&stop_id) else {
+            return Ok(());
+        };
+        let coord = stop
+            .stop_coord
+            .as_ref()
+            .map_or(Coord::zero(), |coord| **coord);
+        Err(DatasetValidationError::new_suspicious_coordinate(
+            "stop_lat, stop_lon".to_string(),
+            format!("{}, {}", coord.y, coord.x),
+            format!("{distance_m:.0} m away from shape {shape_id}, more than {max_m} m"),
+            vec![stop.value().clone().into()],
+        )
+        .into())
+    }
+
+    /// Suggests coordinates for the stops served by trips with a shape: the closest point of
+    /// those shapes.
+    ///
+    /// Stops are sorted by id. The suggestion can be compared to the current coordinates with
+    /// [`SnappedStop::distance_m`], to only move the stops that are clearly misplaced, see
+    /// [`Dataset::validate_stop_shape_distance`].
+    pub fn snap_stops_to_shapes(&self) -> Vec<SnappedStop> {
+        let mut snapped: Vec<SnappedStop> = self
+            .stop_shape_distances()
+            .into_iter()
+            .filter_map(|(stop_id, distances)| {
+                let (shape_id, distance_m, snapped_coord) =
+                    distances.into_iter().min_by(|a, b| a.1.total_cmp(&b.1))?;
+                let coord = **self.stops.get(&stop_id)?.stop_coord.as_ref()?;
+                Some(SnappedStop {
+                    stop_id,
+                    coord,
+                    shape_id,
+                    snapped_coord,
+                    distance_m,
+                })
+            })
+            .collect();
+        snapped.sort_by(|a, b| a.stop_id.as_str().cmp(b.stop_id.as_str()));
+        snapped
+    }
+
+    /// Returns, for each stop with coordinates, the shapes of the trips serving it with the
+    /// distance to their closest point, in meters, and that point.
+    fn stop_shape_distances(&self) -> HashMap<StopId, Vec<(ShapeId, f64, Coord)>> {
+        let trip_shapes: HashMap<TripId, ShapeId> = self
+            .trips
+            .iter()
+            .filter_map(|trip| Some((trip.trip_id.clone(), trip.shape_id.clone()?)))
+            .collect();
+        let mut stop_shapes: HashMap<StopId, HashSet<ShapeId>> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            let (Some(stop_id), Some(shape_id)) =
+                (&stop_time.stop_id, trip_shapes.get(&stop_time.trip_id))
+            else {
+                continue;
+            };
+            stop_shapes
+                .entry(stop_id.clone())
+                .or_default()
+                .insert(shape_id.clone());
+        }
+
+        let mut points: HashMap<ShapeId, Vec<Shape>> = HashMap::new();
+        for shape in self.shapes.iter() {
+            points
+                .entry(shape.shape_id.clone())
+                .or_default()
+                .push(shape.value().clone());
+        }
+        let geometries: HashMap<ShapeId, ShapeGeometry> = points
+            .into_iter()
+            .filter_map(|(shape_id, points)| Some((shape_id, ShapeGeometry::from_points(points)?)))
+            .collect();
+
+        stop_shapes
+            .into_iter()
+            .filter_map(|(stop_id, shape_ids)| {
+                let coord = **self.stops.get(&stop_id)?.stop_coord.as_ref()?;
+                let distances = shape_ids
+                    .into_iter()
+                    .filter_map(|shape_id| {
+                        let closest = geometries.get(&shape_id)?.closest_point(coord)?;
+                        let distance_m =
+                            Point::from(coord).haversine_distance(&Point::from(closest));
+                        Some((shape_id, distance_m, closest))
+                    })
+                    .collect();
+                Some((stop_id, distances))
+            })
+            .collect()
+    }
+
+    /// Fills the missing `shape_dist_traveled` of shapes and stop times.
+    ///
+    /// Shapes without any distance are filled with the distance, in meters, from their first point.
+    /// Shapes that provide only some of their distances are left untouched, as their unit is unknown.
+    ///
+    /// Stop times are then projected on the shape of their trip, and their distance is interpolated
+    /// from the distances of the surrounding shape points, so that it uses the same unit. The search
+    /// only moves forward along the shape, so that loops do not produce decreasing distances.
+    ///
+    /// Returns the number of values that were filled.
+    pub fn compute_shape_dist_traveled(&self) -> usize {
+        let mut points: HashMap<ShapeId, Vec<Shape>> = HashMap::new();
+        for shape in self.shapes.iter() {
+            points
+                .entry(shape.shape_id.clone())
+                .or_default()
+                .push(shape.value().clone());
+        }
+
+        let mut filled = 0;
+        let mut geometries: HashMap<ShapeId, (ShapeGeometry, Vec<f64>)> = HashMap::new();
+        for (shape_id, points) in points {
+            let Some(geometry) = ShapeGeometry::from_points(points.clone()) else {
+                continue;
+            };
+
+            let distances = if points
+                .iter()
+                .all(|point| point.shape_dist_traveled.is_none())
+            {
+                let distances = geometry.cumulative_distances_m();
+                for (sequence, distance) in geometry.shape_pt_sequences.iter().zip(&distances) {
+                    if let Some(mut shape) = self.shapes.get_mut(&(shape_id.clone(), *sequence)) {
+                        shape.shape_dist_traveled = Some(*distance as f32);
+                        filled += 1;
+                    }
+                }
+                distances
+            } else if points
+                .iter()
+                .all(|point| point.shape_dist_traveled.is_some())
+            {
+                let mut points = points;
+                points.sort_by_key(|point| point.shape_pt_sequence);
+                points
+                    .iter()
+                    .map(|point| f64::from(point.shape_dist_traveled.unwrap_or_default()))
+                    .collect()
+            } else {
+                continue;
+            };
+
+            geometries.insert(shape_id, (geometry, distances));
+        }
+
+        let mut trips: HashMap<TripId, Vec<u32>> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            let (trip_id, stop_sequence) = stop_time.key();
+            trips
+                .entry(trip_id.clone())
+                .or_default()
+                .push(*stop_sequence);
+        }
+
+        for (trip_id, mut sequences) in trips {
+            let Some(shape_id) = self
+                .trips
+                .get(&trip_id)
+                .and_then(|trip| trip.shape_id.clone())
+            else {
+                continue;
+            };
+            let Some((geometry, distances)) = geometries.get(&shape_id) else {
+                continue;
+            };
+
+            sequences.sort_unstable();
+            let mut from = 0;
+            for stop_sequence in sequences {
+                let key = (trip_id.clone(), stop_sequence);
+                let Some(stop_time) = self.stop_times.get(&key).map(|s| s.clone()) else {
+                    continue;
+                };
+                let Some(coord) = self.stop_time_coord(&stop_time) else {
+                    continue;
+                };
+                let Some((segment, ratio)) = geometry.locate(coord, from) else {
+                    continue;
+                };
+                from = segment;
+
+                if stop_time.shape_dist_traveled.is_none() {
+                    let start = distances[segment];
+                    let end = distances.get(segment + 1).copied().unwrap_or(start);
+                    if let Some(mut stop_time) = self.stop_times.get_mut(&key) {
+                        stop_time.shape_dist_traveled =
+                            Some((start + ratio * (end - start)) as f32);
+                        filled += 1;
+                    }
+                }
+            }
+        }
+
+        filled
+    }
+
+    pub(crate) fn stop_time_coord(&self, stop_time: &StopTime) -> Option<Coord> {
+        let stop_id = stop_time.stop_id.as_ref()?;
+        let stop = self.stops.get(stop_id)?;
+        stop.stop_coord.as_ref().map(|coord| **coord)
+    }
+
+    /// Merges the shapes that describe the same path, so that their trips share a single shape.
+    ///
+    /// Two shapes describe the same path when their discrete Fréchet distance is at most
+    /// `tolerance_m` meters: walking both in order, their points are never further apart than
+    /// that. Distances are measured between points, so a shape with extra points along a
+    /// segment only matches its sparser twin if `tolerance_m` covers the spacing of the points.
+    /// As the stop times of the trips refer to them, the shapes must also have the same
+    /// `shape_dist_traveled`, or none at all.
+    ///
+    /// Shapes are visited by id, and the first shape of each path is kept. The trips of the other
+    /// shapes are moved to it, and their points are removed.
+    ///
+    /// Returns the number of shapes that were removed.
+    pub fn dedupe_shapes(&mut self, tolerance_m: f64) -> usize {
+        let mut points: HashMap<ShapeId, Vec<Shape>> = HashMap::new();
+        for shape in self.shapes.iter() {
+            points
+                .entry(shape.shape_id.clone())
+                .or_default()
+                .push(shape.value().clone());
+        }
+        let mut shapes: Vec<(ShapeGeometry, Vec<Option<f32>>)> = points
+            .into_values()
+            .filter_map(|mut points| {
+                points.sort_by_key(|point| point.shape_pt_sequence);
+                let distances = points
+                    .iter()
+                    .map(|point| point.shape_dist_traveled)
+                    .collect();
+                Some((ShapeGeometry::from_points(points)?, distances))
+            })
+            .collect();
+        shapes.sort_by(|a, b| a.0.shape_id.as_str().cmp(b.0.shape_id.as_str()));
+
+        let same_distances = |a: &[Option<f32>], b: &[Option<f32>]| {
+            a == b || (a.iter().all(Option::is_none) && b.iter().all(Option::is_none))
+        };
+        let near = |a: Option<&Coord>, b: Option<&Coord>| match (a, b) {
+            (Some(a), Some(b)) => {
+                Point::from(*a).haversine_distance(&Point::from(*b)) <= tolerance_m
+            }
+            _ => false,
+        };
+        let mut kept: Vec<usize> = vec![];
+        let mut duplicates: HashMap<ShapeId, ShapeId> = HashMap::new();
+        for (index, (geometry, distances)) in shapes.iter().enumerate() {
+            let canonical =
+                kept.iter()
+                    .map(|kept| &shapes[*kept])
+                    .find(|(kept, kept_distances)| {
+                        same_distances(distances, kept_distances)
+                            && near(geometry.line_string.0.first(), kept.line_string.0.first())
+                            && near(geometry.line_string.0.last(), kept.line_string.0.last())
+                            && frechet_distance_m(&geometry.line_string, &kept.line_string)
+                                <= tolerance_m
+                    });
+            match canonical {
+                Some((canonical, _)) => {
+                    duplicates.insert(geometry.shape_id.clone(), canonical.shape_id.clone());
+                }
+                None => kept.push(index),
+            }
+        }
+
+        for mut trip in self.trips.iter_mut() {
+            if let Some(canonical) = trip.shape_id.as_ref().and_then(|id| duplicates.get(id)) {
+                trip.shape_id = Some(canonical.clone());
+            }
+        }
+        self.shapes
+            .retain(|(shape_id, _), _| !duplicates.contains_key(shape_id));
+
+        duplicates.len()
+    }
+}
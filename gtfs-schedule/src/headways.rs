@@ -0,0 +1,249 @@
+//! Analysis of the frequency of service of routes and stops.
+//!
+//! The main types are:
+//! - [`HeadwayProfile`]: The time between consecutive trips of a route on a date.
+//! - [`HeadwayStats`]: Statistics on a set of headways.
+//! - [`ServiceSpan`]: The first and last departures from a stop on a date.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+
+use crate::compiled_timetable::trip_starts;
+use crate::schemas::{DirectionId, Frequency, NaiveServiceTime, RouteId, StopId, TripId};
+use crate::Dataset;
+
+/// The time between consecutive trips of a route on a date, see [`Dataset::headways`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadwayProfile {
+    /// Identifies the route.
+    pub route_id: RouteId,
+    /// The service day analysed.
+    pub date: NaiveDate,
+    /// The headways of each direction of the route, the trips without direction first.
+    pub directions: Vec<DirectionHeadways>,
+}
+
+/// The time between consecutive trips of a route going in a direction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectionHeadways {
+    /// The [`crate::schemas::Trip::direction_id`] of the trips.
+    pub direction_id: Option<DirectionId>,
+    /// When the trips leave their first stop, sorted.
+    pub departures: Vec<NaiveServiceTime>,
+    /// Statistics on every headway of the day, `None` if there is less than two trips.
+    pub overall: Option<HeadwayStats>,
+    /// Statistics on the headways starting during each hour of the service day.
+    ///
+    /// Hours are counted from the start of the service day, and can exceed 23.
+    pub by_hour: BTreeMap<u32, HeadwayStats>,
+}
+
+/// Statistics on a set of headways.
+///
+/// The median and 90th percentile use the nearest-rank method, and are actual headways.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadwayStats {
+    /// Number of headways.
+    pub count: usize,
+    /// Average headway.
+    pub mean: Duration,
+    /// Median headway.
+    pub median: Duration,
+    /// Headway longer than or as long as 90% of the headways.
+    pub p90: Duration,
+}
+
+/// The first and last departures from a stop on a date, see [`Dataset::service_span`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServiceSpan {
+    /// The first departure of the day.
+    pub first_departure: NaiveServiceTime,
+    /// The last departure of the day.
+    pub last_departure: NaiveServiceTime,
+    /// Number of departures during the day.
+    pub departure_count: usize,
+}
+
+impl ServiceSpan {
+    /// Time between the first and last departures.
+    pub fn duration(&self) -> Duration {
+        self.last_departure
+            .duration_since(self.first_departure)
+            .unwrap_or_default()
+    }
+}
+
+impl HeadwayStats {
+    /// Computes the statistics of a set of headways, in seconds, `None` if it is empty.
+    fn from_secs(mut headways: Vec<u32>) -> Option<Self> {
+        if headways.is_empty() {
+            return None;
+        }
+        headways.sort_unstable();
+        let rank = |percentile: usize| headways[(headways.len() * percentile).div_ceil(100) - 1];
+        let total: u64 = headways.iter().map(|headway| u64::from(*headway)).sum();
+
+        Some(Self {
+            count: headways.len(),
+            mean: Duration::from_secs_f64(total as f64 / headways.len() as f64),
+            median: Duration::from_secs(u64::from(rank(50))),
+            p90: Duration::from_secs(u64::from(rank(90))),
+        })
+    }
+}
+
+impl Dataset {
+    /// Computes the time between consecutive trips of a route running on `date`, by direction.
+    ///
+    /// Trips are compared when they leave their first stop, whichever it is, and trips defined
+    /// in frequencies.txt count once per departure. A headway belongs to the hour of the
+    /// departure starting it.
+    pub fn headways(&self, route_id: &RouteId, date: NaiveDate) -> HeadwayProfile {
+        let mut directions: BTreeMap<Option<u8>, (Option<DirectionId>, Vec<u32>)> = BTreeMap::new();
+        for (trip_id, runs) in self.active_runs(date, |trip_id| {
+            self.trips
+                .get(trip_id)
+                .is_some_and(|trip| trip.route_id == *route_id)
+        }) {
+            let direction_id = self
+                .trips
+                .get(&trip_id)
+                .and_then(|trip| trip.direction_id.clone());
+            let (_, departures) = directions
+                .entry(direction_id.clone().map(|direction_id| direction_id.code()))
+                .or_insert_with(|| (direction_id, vec![]));
+            departures.extend(
+                runs.iter()
+                    .filter_map(|run| run.iter().map(|(_, time)| *time).min()),
+            );
+        }
+
+        let directions = directions
+            .into_values()
+            .map(|(direction_id, mut departures)| {
+                departures.sort_unstable();
+                let mut by_hour: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+                for pair in departures.windows(2) {
+                    by_hour
+                        .entry(pair[0] / 3600)
+                        .or_default()
+                        .push(pair[1] - pair[0]);
+                }
+
+                DirectionHeadways {
+                    direction_id,
+                    departures: departures
+                        .iter()
+                        .map(|time| NaiveServiceTime::from_secs(*time))
+                        .collect(),
+                    overall: HeadwayStats::from_secs(by_hour.values().flatten().copied().collect()),
+                    by_hour: by_hour
+                        .into_iter()
+                        .filter_map(|(hour, headways)| {
+                            Some((hour, HeadwayStats::from_secs(headways)?))
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+
+        HeadwayProfile {
+            route_id: route_id.clone(),
+            date,
+            directions,
+        }
+    }
+
+    /// Returns the first and last departures from a stop on `date`, `None` if no trip stops
+    /// there.
+    ///
+    /// Every stop time of the stop counts, whether passengers can board or not, using its arrival
+    /// time when its departure time is missing. Stop times without any time are ignored.
+    pub fn service_span(&self, stop_id: &StopId, date: NaiveDate) -> Option<ServiceSpan> {
+        let stopping_trips: HashSet<TripId> = self
+            .stop_times
+            .iter()
+            .filter(|stop_time| stop_time.stop_id.as_ref() == Some(stop_id))
+            .map(|stop_time| stop_time.trip_id.clone())
+            .collect();
+
+        let departures: Vec<u32> = self
+            .active_runs(date, |trip_id| stopping_trips.contains(trip_id))
+            .into_values()
+            .flatten()
+            .flatten()
+            .filter(|(departure_stop_id, _)| departure_stop_id == stop_id)
+            .map(|(_, time)| time)
+            .collect();
+
+        Some(ServiceSpan {
+            first_departure: NaiveServiceTime::from_secs(*departures.iter().min()?),
+            last_departure: NaiveServiceTime::from_secs(*departures.iter().max()?),
+            departure_count: departures.len(),
+        })
+    }
+
+    /// Returns the runs of the trips running on `date` selected by `filter`, by trip.
+    ///
+    /// Each run lists the stops of the trip and when it leaves them, in seconds since the start
+    /// of the service day. Trips defined in frequencies.txt run once per departure, the others
+    /// once.
+    pub(crate) fn active_runs(
+        &self,
+        date: NaiveDate,
+        filter: impl Fn(&TripId) -> bool,
+    ) -> HashMap<TripId, Vec<Vec<(StopId, u32)>>> {
+        let mut trip_departures: HashMap<TripId, Vec<(StopId, u32)>> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            let (Some(stop_id), Some(time)) = (
+                &stop_time.stop_id,
+                stop_time.departure_time.or(stop_time.arrival_time),
+            ) else {
+                continue;
+            };
+            if filter(&stop_time.trip_id) {
+                trip_departures
+                    .entry(stop_time.trip_id.clone())
+                    .or_default()
+                    .push((stop_id.clone(), time.as_secs()));
+            }
+        }
+        trip_departures.retain(|trip_id, _| {
+            self.trips
+                .get(trip_id)
+                .is_some_and(|trip| self.is_service_active(&trip.service_id, date))
+        });
+
+        let mut frequencies: HashMap<TripId, Vec<Frequency>> = HashMap::new();
+        for frequency in self.frequencies.iter() {
+            if trip_departures.contains_key(&frequency.trip_id) {
+                frequencies
+                    .entry(frequency.trip_id.clone())
+                    .or_default()
+                    .push(frequency.value().clone());
+            }
+        }
+
+        trip_departures
+            .into_iter()
+            .map(|(trip_id, departures)| {
+                let first_departure = departures.iter().map(|(_, time)| *time).min().unwrap_or(0);
+                let runs = trip_starts(
+                    frequencies.get(&trip_id).map(Vec::as_slice),
+                    first_departure,
+                )
+                .into_iter()
+                .map(|start| {
+                    departures
+                        .iter()
+                        .map(|(stop_id, time)| (stop_id.clone(), time + start - first_departure))
+                        .collect()
+                })
+                .collect();
+                (trip_id, runs)
+            })
+            .collect()
+    }
+}
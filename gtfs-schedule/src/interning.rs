@@ -0,0 +1,119 @@
+//! Interning of the identifiers of records.
+//!
+//! Identifiers such as [`crate::schemas::TripId`] wrap an `Arc<str>`. While a dataset is
+//! parsed, identifiers with the same value share a single allocation, so that the trip and stop
+//! identifiers repeated across `stop_times.txt` are only stored once.
+//!
+//! The main types are:
+//! - [`MemoryStats`]: Figures on the identifiers shared by the records of a dataset.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use serde::de::{Deserializer, Error, Visitor};
+
+use crate::{Dataset, GtfsFile};
+
+thread_local! {
+    /// Identifiers of the dataset being parsed on this thread, see [`InternScope`].
+    static INTERNED_IDS: RefCell<Option<HashSet<Arc<str>>>> = const { RefCell::new(None) };
+}
+
+/// Figures on the identifiers shared by the records of a dataset, see [`Dataset::memory_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Number of records in the dataset.
+    pub records: usize,
+    /// Number of distinct identifiers that were interned while parsing the dataset.
+    pub interned_ids: usize,
+    /// Number of bytes held by the interned identifiers.
+    pub interned_bytes: usize,
+    /// Number of references to the interned identifiers held by the records, including the keys
+    /// of the tables.
+    pub id_references: usize,
+    /// Number of bytes that would be needed by the identifiers if each reference held its own
+    /// copy, minus [`MemoryStats::interned_bytes`].
+    pub saved_bytes: usize,
+}
+
+impl Dataset {
+    /// Returns figures on the identifiers shared by the records of the dataset.
+    ///
+    /// Only the identifiers parsed by [`Dataset::from_csv`] and [`Dataset::from_json`] are
+    /// interned, identifiers built afterwards hold their own allocation.
+    pub fn memory_stats(&self) -> MemoryStats {
+        let mut stats = MemoryStats {
+            records: GtfsFile::ALL.iter().map(|file| self.table_len(*file)).sum(),
+            ..MemoryStats::default()
+        };
+        for id in &self.interned_ids {
+            // The set of interned identifiers holds a reference itself.
+            let references = Arc::strong_count(id) - 1;
+            stats.interned_ids += 1;
+            stats.interned_bytes += id.len();
+            stats.id_references += references;
+            stats.saved_bytes += references.saturating_sub(1) * id.len();
+        }
+        stats
+    }
+}
+
+/// Makes identifiers parsed on the current thread share their allocations until it is dropped.
+pub(crate) struct InternScope;
+
+impl InternScope {
+    /// Starts interning identifiers, reusing the ones already in `ids`.
+    pub(crate) fn enter(ids: HashSet<Arc<str>>) -> Self {
+        INTERNED_IDS.with(|interned| *interned.borrow_mut() = Some(ids));
+        InternScope
+    }
+
+    /// Stops interning identifiers, and returns all of them.
+    pub(crate) fn exit(self) -> HashSet<Arc<str>> {
+        INTERNED_IDS.with(|interned| interned.borrow_mut().take().unwrap_or_default())
+    }
+}
+
+impl Drop for InternScope {
+    fn drop(&mut self) {
+        INTERNED_IDS.with(|interned| *interned.borrow_mut() = None);
+    }
+}
+
+/// Returns the interned copy of `id`, or a new allocation outside of an [`InternScope`].
+pub(crate) fn intern(id: &str) -> Arc<str> {
+    INTERNED_IDS.with(|interned| match interned.borrow_mut().as_mut() {
+        Some(ids) => match ids.get(id) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = Arc::from(id);
+                ids.insert(interned.clone());
+                interned
+            }
+        },
+        None => Arc::from(id),
+    })
+}
+
+/// Deserializes an identifier, interning it.
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Arc<str>, D::Error> {
+    struct IdVisitor;
+
+    impl Visitor<'_> for IdVisitor {
+        type Value = Arc<str>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an identifier")
+        }
+
+        fn visit_str<E: Error>(self, value: &str) -> Result<Self::Value, E> {
+            Ok(intern(value))
+        }
+    }
+
+    deserializer.deserialize_str(IdVisitor)
+}
@@ -0,0 +1,150 @@
+//! Interpolation of missing stop times.
+//!
+//! GTFS allows intermediate stop times of a trip to leave `arrival_time` and `departure_time`
+//! blank, only the first and last stop times (and those marked as [`Timepoint::Exact`]) must
+//! provide them. Consumers such as routers usually need a time at every stop, which is what
+//! [`Dataset::interpolate_stop_times`] and [`Dataset::interpolate_all_stop_times`] provide.
+
+use std::collections::HashMap;
+
+use crate::schemas::{NaiveServiceTime, StopTime, Timepoint, TripId};
+use crate::Dataset;
+
+impl Dataset {
+    /// Fills the missing `arrival_time` and `departure_time` of the stop times of a trip.
+    ///
+    /// Each run of stop times without times is interpolated linearly between the surrounding
+    /// timed stop times, using [`StopTime::shape_dist_traveled`] when it is provided for the
+    /// whole run and its bounds, and [`StopTime::stop_sequence`] otherwise. Interpolated stop
+    /// times are marked as [`Timepoint::Approximate`].
+    ///
+    /// Stop times before the first or after the last timed stop time are left untouched, as are
+    /// stop times using pickup/drop off windows.
+    ///
+    /// Returns the number of stop times that were interpolated.
+    pub fn interpolate_stop_times(&self, trip_id: &TripId) -> usize {
+        let sequences: Vec<u32> = self
+            .stop_times
+            .iter()
+            .filter(|stop_time| stop_time.key().0 == *trip_id)
+            .map(|stop_time| stop_time.key().1)
+            .collect();
+
+        self.interpolate_trip_stop_times(trip_id, sequences)
+    }
+
+    /// Fills the missing `arrival_time` and `departure_time` of the stop times of every trip.
+    ///
+    /// See [`Dataset::interpolate_stop_times`].
+    ///
+    /// Returns the number of stop times that were interpolated.
+    pub fn interpolate_all_stop_times(&self) -> usize {
+        let mut trips: HashMap<TripId, Vec<u32>> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            let (trip_id, stop_sequence) = stop_time.key();
+            trips
+                .entry(trip_id.clone())
+                .or_default()
+                .push(*stop_sequence);
+        }
+
+        trips
+            .into_iter()
+            .map(|(trip_id, sequences)| self.interpolate_trip_stop_times(&trip_id, sequences))
+            .sum()
+    }
+
+    fn interpolate_trip_stop_times(&self, trip_id: &TripId, mut sequences: Vec<u32>) -> usize {
+        sequences.sort_unstable();
+
+        let stop_times: Vec<StopTime> = sequences
+            .iter()
+            .filter_map(|stop_sequence| {
+                self.stop_times
+                    .get(&(trip_id.clone(), *stop_sequence))
+                    .map(|stop_time| stop_time.clone())
+            })
+            .filter(|stop_time| {
+                stop_time.start_pickup_drop_off_window.is_none()
+                    && stop_time.end_pickup_drop_off_window.is_none()
+            })
+            .collect();
+
+        let mut interpolated = 0;
+        let mut previous: Option<usize> = None;
+        for (index, stop_time) in stop_times.iter().enumerate() {
+            if stop_time.arrival_time.is_none() && stop_time.departure_time.is_none() {
+                continue;
+            }
+
+            if let Some(previous) = previous {
+                if index > previous + 1 {
+                    for (stop_sequence, time) in interpolate_run(
+                        &stop_times[previous],
+                        stop_time,
+                        &stop_times[previous + 1..index],
+                    ) {
+                        if let Some(mut stop_time) =
+                            self.stop_times.get_mut(&(trip_id.clone(), stop_sequence))
+                        {
+                            stop_time.arrival_time = Some(time);
+                            stop_time.departure_time = Some(time);
+                            stop_time.timepoint = Some(Timepoint::Approximate);
+                            interpolated += 1;
+                        }
+                    }
+                }
+            }
+            previous = Some(index);
+        }
+
+        interpolated
+    }
+}
+
+/// Computes the times of the untimed stop times found between `start` and `end`.
+fn interpolate_run(
+    start: &StopTime,
+    end: &StopTime,
+    run: &[StopTime],
+) -> Vec<(u32, NaiveServiceTime)> {
+    // Both bounds have at least one of the two times set.
+    let start_secs = start
+        .departure_time
+        .or(start.arrival_time)
+        .unwrap()
+        .as_secs();
+    let end_secs = end.arrival_time.or(end.departure_time).unwrap().as_secs();
+    if end_secs < start_secs {
+        return vec![];
+    }
+
+    let by_distance = match (start.shape_dist_traveled, end.shape_dist_traveled) {
+        (Some(start_dist), Some(end_dist)) => {
+            end_dist > start_dist
+                && run.iter().all(|stop_time| {
+                    stop_time
+                        .shape_dist_traveled
+                        .is_some_and(|dist| dist >= start_dist && dist <= end_dist)
+                })
+        }
+        _ => false,
+    };
+
+    run.iter()
+        .map(|stop_time| {
+            let ratio = if by_distance {
+                let start_dist = f64::from(start.shape_dist_traveled.unwrap());
+                let end_dist = f64::from(end.shape_dist_traveled.unwrap());
+                let dist = f64::from(stop_time.shape_dist_traveled.unwrap());
+                (dist - start_dist) / (end_dist - start_dist)
+            } else {
+                f64::from(stop_time.stop_sequence - start.stop_sequence)
+                    / f64::from(end.stop_sequence - start.stop_sequence)
+            };
+            let secs = start_secs + (ratio * f64::from(end_secs - start_secs)).round() as u32;
+
+            (stop_time.stop_sequence, NaiveServiceTime::from_secs(secs))
+        })
+        .collect()
+}
@@ -0,0 +1,409 @@
+//! Conversion of datasets to and from JSON.
+//!
+//! A dataset is written either as a single document with [`Dataset::to_json`], or as one
+//! newline-delimited JSON (NDJSON) stream per table with [`Dataset::to_ndjson`]. Records are
+//! converted column by column, with the `JsonValue` trait, rather than through their CSV
+//! serialization.
+//!
+//! Only single documents are read back, with [`Dataset::from_json`]: there is no NDJSON reader.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use chrono::{NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use email_address::EmailAddress;
+use iso_currency::Currency;
+use oxilangtag::LanguageTag;
+use rust_decimal::Decimal;
+use serde_json::{Map, Number, Value};
+use url::Url;
+
+#[cfg(feature = "extensions")]
+use crate::schemas::Extensions;
+
+use crate::error::{ParseError, ParseErrorKind, Result};
+use crate::interning::InternScope;
+use crate::provenance::SourceLines;
+use crate::schemas::{
+    coord_type, BikesAllowed, ExactTimes, FareTransferRuleDurationLimit, GtfsCoord,
+    NaiveServiceTime, Phone, Schema, TableName, Timepoint, TransferType, WheelchairAccessible,
+};
+use crate::writer::{field_value, shortest_float};
+use crate::{Dataset, GtfsFile, ParseOptions};
+
+/// Columns holding a date, written as `YYYYMMDD` in CSV files and `YYYY-MM-DD` in JSON documents.
+pub(crate) const DATE_COLUMNS: [&str; 5] = [
+    "date",
+    "start_date",
+    "end_date",
+    "feed_start_date",
+    "feed_end_date",
+];
+
+impl Dataset {
+    /// Writes the dataset as a single JSON document.
+    ///
+    /// The document is an object with a member for each table with at least one record, named
+    /// after its file without the extension (e.g. `stop_times`). Each member is an array of
+    /// records, which are objects holding the columns with a value. Numbers and enumerations are
+    /// written as JSON numbers, dates as `YYYY-MM-DD` and service times as `HH:MM:SS`.
+    pub fn to_json<W: Write>(&self, writer: W) -> Result<()> {
+        let mut tables: BTreeMap<GtfsFile, Vec<Value>> = BTreeMap::new();
+        for (file, record) in self.iter_all() {
+            tables
+                .entry(file)
+                .or_default()
+                .push(Value::Object(json_record(&record)));
+        }
+
+        let document: Map<String, Value> = tables
+            .into_iter()
+            .map(|(file, records)| (table_name(file).to_string(), Value::Array(records)))
+            .collect();
+        serde_json::to_writer(writer, &document)
+            .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+
+        Ok(())
+    }
+
+    /// Writes the records of a table as newline-delimited JSON (NDJSON), one record per line.
+    ///
+    /// Records are written as in [`Dataset::to_json`]. Nothing is written for an empty table.
+    pub fn to_ndjson<W: Write>(&self, file: GtfsFile, mut writer: W) -> Result<()> {
+        for record in self.iter_table(file) {
+            serde_json::to_writer(&mut writer, &json_record(&record))
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+            writer
+                .write_all(b"\n")
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a dataset from a JSON document written by [`Dataset::to_json`].
+    ///
+    /// Records are parsed as if they were read from CSV files, so a document that does not
+    /// follow the layout of [`Dataset::to_json`] is rejected.
+    pub fn from_json<R: Read>(reader: R) -> Result<Self> {
+        let invalid = |reason: String| ParseError::from(ParseErrorKind::InvalidValue(reason));
+
+        let document: Value = serde_json::from_reader(reader)
+            .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+        let Value::Object(tables) = document else {
+            return Err(invalid("the document must be an object".to_string()).into());
+        };
+
+        let mut dataset = Self::default();
+        let intern_scope = InternScope::enter(HashSet::new());
+        for (table, records) in tables {
+            let file = GtfsFile::ALL
+                .iter()
+                .copied()
+                .find(|file| table_name(*file) == table)
+                .ok_or_else(|| invalid(format!("unknown table: {}", table)))?;
+            let Value::Array(records) = records else {
+                return Err(invalid(format!("{} must be an array", table)).into());
+            };
+
+            for record in records {
+                let Value::Object(fields) = record else {
+                    return Err(invalid(format!("the records of {} must be objects", table)).into());
+                };
+                let header: csv::StringRecord = fields.keys().map(String::as_str).collect();
+                let values: csv::StringRecord = fields
+                    .iter()
+                    .map(|(column, value)| csv_value(column, value))
+                    .collect();
+                dataset.insert_csv_record(file, &header, &values, &ParseOptions::default())?;
+            }
+        }
+        dataset.interned_ids = intern_scope.exit();
+        dataset.source_lines = SourceLines::default();
+
+        Ok(dataset)
+    }
+}
+
+/// Name of the member holding the records of a table in a JSON document.
+pub(crate) fn table_name(file: GtfsFile) -> &'static str {
+    file.file_name().trim_end_matches(".txt")
+}
+
+/// Converts a record to a JSON object, leaving out empty columns.
+fn json_record(record: &Schema) -> Map<String, Value> {
+    let fields = record.json_fields();
+    #[cfg(feature = "extensions")]
+    let fields = {
+        let mut fields = fields;
+        for (column, value) in record.extensions() {
+            fields
+                .entry(column.clone())
+                .or_insert_with(|| Value::String(value.clone()));
+        }
+        fields
+    };
+
+    fields
+}
+
+/// Records converted to JSON objects, implemented with [`gtfs_schedule_macros::GtfsTable`].
+pub(crate) trait JsonRecord {
+    /// Returns the columns of the record with a value, by name.
+    fn json_fields(&self) -> Map<String, Value>;
+}
+
+/// Dispatches [`JsonRecord`] to the record held by a [`Schema`].
+macro_rules! impl_schema_json_record {
+    ($($schema:ident),* $(,)?) => {
+        impl JsonRecord for Schema {
+            fn json_fields(&self) -> Map<String, Value> {
+                match self {
+                    $(Schema::$schema(record) => record.json_fields(),)*
+                }
+            }
+        }
+    };
+}
+
+impl_schema_json_record!(
+    Agency,
+    Area,
+    Attribution,
+    BookingRule,
+    Calendar,
+    CalendarDate,
+    FareAttribute,
+    FareLegRule,
+    FareMedia,
+    FareProduct,
+    FareRule,
+    FareTransferRule,
+    FeedInfo,
+    Frequency,
+    Level,
+    LocationGroup,
+    LocationGroupStop,
+    Network,
+    Pathway,
+    Route,
+    RouteNetwork,
+    Shape,
+    Stop,
+    StopArea,
+    StopTime,
+    Timeframe,
+    Transfer,
+    Translation,
+    Trip,
+);
+
+/// Values of a column converted to JSON.
+pub(crate) trait JsonValue {
+    /// Returns the value, `None` if the column is empty.
+    fn json_value(&self) -> Option<Value>;
+}
+
+/// Values of several columns converted to JSON, such as coordinates.
+pub(crate) trait JsonColumns {
+    /// Returns the value of each column, in order, nothing if they are empty.
+    fn json_columns(&self) -> Vec<Value>;
+}
+
+impl<T: JsonValue> JsonValue for Option<T> {
+    fn json_value(&self) -> Option<Value> {
+        self.as_ref().and_then(T::json_value)
+    }
+}
+
+impl JsonValue for str {
+    fn json_value(&self) -> Option<Value> {
+        (!self.is_empty()).then(|| Value::String(self.to_string()))
+    }
+}
+
+impl JsonValue for String {
+    fn json_value(&self) -> Option<Value> {
+        self.as_str().json_value()
+    }
+}
+
+/// Converts values with their `Display` implementation.
+macro_rules! display_json_value {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl JsonValue for $ty {
+                fn json_value(&self) -> Option<Value> {
+                    self.to_string().json_value()
+                }
+            }
+        )*
+    };
+}
+
+display_json_value!(Url, EmailAddress, Phone, Tz, Decimal);
+
+/// Converts integers to JSON numbers.
+macro_rules! integer_json_value {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl JsonValue for $ty {
+                fn json_value(&self) -> Option<Value> {
+                    Some((*self).into())
+                }
+            }
+        )*
+    };
+}
+
+integer_json_value!(u32, i32);
+
+/// Converts the enumerations written as integers, other than those of `coded_enum!`.
+macro_rules! repr_json_value {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl JsonValue for $ty {
+                fn json_value(&self) -> Option<Value> {
+                    Some((self.clone() as u8).into())
+                }
+            }
+        )*
+    };
+}
+
+repr_json_value!(
+    ExactTimes,
+    Timepoint,
+    TransferType,
+    WheelchairAccessible,
+    BikesAllowed
+);
+
+impl JsonValue for bool {
+    fn json_value(&self) -> Option<Value> {
+        Some(u8::from(*self).into())
+    }
+}
+
+impl JsonValue for f32 {
+    fn json_value(&self) -> Option<Value> {
+        Number::from_f64(shortest_float(f64::from(*self))).map(Value::Number)
+    }
+}
+
+impl JsonValue for f64 {
+    fn json_value(&self) -> Option<Value> {
+        Number::from_f64(*self).map(Value::Number)
+    }
+}
+
+/// Durations are written as a number of seconds.
+impl JsonValue for Duration {
+    fn json_value(&self) -> Option<Value> {
+        Some(self.as_secs().into())
+    }
+}
+
+/// Writes a duration as a number of minutes, for the columns of booking_rules.txt.
+pub(crate) fn json_minutes(duration: &Option<Duration>) -> Option<Value> {
+    duration.map(|duration| (duration.as_secs() / 60).into())
+}
+
+impl JsonValue for NaiveDate {
+    fn json_value(&self) -> Option<Value> {
+        Some(Value::String(self.format("%Y-%m-%d").to_string()))
+    }
+}
+
+impl JsonValue for NaiveTime {
+    fn json_value(&self) -> Option<Value> {
+        Some(Value::String(self.format("%H:%M:%S").to_string()))
+    }
+}
+
+impl JsonValue for NaiveServiceTime {
+    fn json_value(&self) -> Option<Value> {
+        Some(Value::String(self.to_string()))
+    }
+}
+
+impl JsonValue for LanguageTag<String> {
+    fn json_value(&self) -> Option<Value> {
+        self.as_str().json_value()
+    }
+}
+
+impl JsonValue for Currency {
+    fn json_value(&self) -> Option<Value> {
+        self.code().json_value()
+    }
+}
+
+impl JsonValue for TableName {
+    fn json_value(&self) -> Option<Value> {
+        let table_name = match self {
+            TableName::Agency => "agency",
+            TableName::Stops => "stops",
+            TableName::Routes => "routes",
+            TableName::Trips => "trips",
+            TableName::StopTimes => "stop_times",
+            TableName::Pathways => "pathways",
+            TableName::Levels => "levels",
+            TableName::FeedInfo => "feed_info",
+            TableName::Attributions => "attributions",
+            TableName::Calendar => "calendar",
+            TableName::CalendarDates => "calendar_dates",
+            TableName::FareAttributes => "fare_attributes",
+            TableName::FareRules => "fare_rules",
+            TableName::Shapes => "shapes",
+            TableName::Frequencies => "frequencies",
+            TableName::Transfers => "transfers",
+        };
+        table_name.json_value()
+    }
+}
+
+/// An unlimited duration is written as an empty column.
+impl JsonValue for FareTransferRuleDurationLimit {
+    fn json_value(&self) -> Option<Value> {
+        match self {
+            FareTransferRuleDurationLimit::WithLimit(limit) => Some(limit.get().into()),
+            FareTransferRuleDurationLimit::Unlimited => None,
+        }
+    }
+}
+
+/// Coordinates are written as the latitude, then the longitude.
+impl<const COORD_TYPE: coord_type::T> JsonColumns for GtfsCoord<COORD_TYPE> {
+    fn json_columns(&self) -> Vec<Value> {
+        [self.y, self.x]
+            .iter()
+            .filter_map(JsonValue::json_value)
+            .collect()
+    }
+}
+
+impl<T: JsonColumns> JsonColumns for Option<T> {
+    fn json_columns(&self) -> Vec<Value> {
+        self.as_ref().map(T::json_columns).unwrap_or_default()
+    }
+}
+
+/// Converts a JSON value back to the way it is written in CSV files.
+fn csv_value(column: &str, value: &Value) -> String {
+    match value {
+        Value::String(value) if DATE_COLUMNS.contains(&column) => {
+            convert_date(value, "%Y-%m-%d", "%Y%m%d")
+        }
+        value => field_value(value).unwrap_or_default(),
+    }
+}
+
+/// Converts a date between two formats, returns it unchanged if it does not match `from`.
+fn convert_date(date: &str, from: &str, to: &str) -> String {
+    NaiveDate::parse_from_str(date, from)
+        .map(|date| date.format(to).to_string())
+        .unwrap_or_else(|_| date.to_string())
+}
@@ -1,5 +1,116 @@
+mod agency_view;
+mod areas;
+mod blocks;
+mod borrowed;
+#[cfg(feature = "arrow")]
+mod columnar;
+mod compiled_timetable;
+mod conformance;
+mod continuous_stopping;
+pub mod convert;
 mod dataset;
+mod dedupe;
+mod edit;
 pub mod error;
+mod fares;
+mod feed_collection;
+#[cfg(not(target_arch = "wasm32"))]
+mod feed_manager;
+mod fingerprint;
+mod geometry;
+mod headways;
+mod interning;
+mod interpolation;
+mod json;
+mod load_filter;
+mod map_style;
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+mod mmap;
+mod networks;
+mod notices;
+mod orphans;
+mod parse_options;
+mod parse_report;
+mod patterns;
+mod planner;
+mod provenance;
+mod publisher;
+#[cfg(feature = "rdf")]
+mod rdf;
+#[cfg(feature = "realtime")]
+mod realtime;
+mod record_reader;
+mod repair;
 pub mod schemas;
+mod scrub;
+mod service;
+mod service_day;
+mod snapshot;
+mod station;
+mod station_graph;
+mod stop_hierarchy;
+mod summary;
+pub mod synth;
+mod table;
+mod table_reader;
+mod table_writer;
+mod timetable;
+mod transform;
+mod translations;
+mod versioning;
+mod warnings;
+mod write_options;
+mod writer;
+#[cfg(feature = "zip")]
+mod zip_archive;
 
+pub use agency_view::*;
+pub use areas::*;
+pub use blocks::*;
+pub use borrowed::*;
+#[cfg(feature = "arrow")]
+pub use columnar::*;
+pub use compiled_timetable::*;
+pub use conformance::*;
 pub use dataset::*;
+pub use dedupe::*;
+pub use edit::*;
+pub use fares::*;
+pub use feed_collection::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use feed_manager::*;
+pub use geometry::*;
+pub use headways::*;
+pub use interning::*;
+pub use map_style::*;
+pub use networks::*;
+pub use notices::*;
+pub use orphans::*;
+pub use parse_options::*;
+pub use parse_report::*;
+pub use patterns::*;
+pub use planner::*;
+pub use provenance::*;
+pub use publisher::*;
+#[cfg(feature = "rdf")]
+pub use rdf::*;
+#[cfg(feature = "realtime")]
+pub use realtime::*;
+pub use record_reader::*;
+pub use repair::*;
+pub use scrub::*;
+pub use service::*;
+pub use service_day::*;
+pub use snapshot::*;
+pub use station::*;
+pub use station_graph::*;
+pub use stop_hierarchy::*;
+pub use summary::*;
+pub use table::*;
+pub use table_reader::*;
+pub use table_writer::*;
+pub use timetable::*;
+pub use translations::*;
+pub use versioning::*;
+pub use warnings::*;
+pub use write_options::*;
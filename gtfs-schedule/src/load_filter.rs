@@ -0,0 +1,225 @@
+//! Filtering of the records of a dataset while it is parsed, see [`crate::ParseOptions::only_routes`],
+//! [`crate::ParseOptions::only_dates`] and [`crate::ParseOptions::only_times`].
+
+use std::collections::{HashMap, HashSet};
+
+use crate::schemas::{CalendarServiceId, NaiveServiceTime, TripId};
+use crate::{Dataset, GtfsFile, ParseOptions};
+
+/// Decides which records of a file are parsed, from their raw values.
+///
+/// Files must be parsed in [`load_rank`] order, so that the services and trips are known when
+/// the records depending on them are met.
+#[derive(Debug, Default)]
+pub(crate) struct LoadFilter {
+    /// Whether each service runs on one of the selected dates.
+    active_services: HashMap<String, bool>,
+    /// The trips that were kept, once trips.txt is parsed.
+    trip_ids: Option<HashSet<String>>,
+    /// The shapes of the trips that were kept, once trips.txt is parsed.
+    shape_ids: Option<HashSet<String>>,
+    /// Whether one of the frequencies of each trip of frequencies.txt was kept.
+    frequency_trips: HashMap<String, bool>,
+}
+
+/// The rank of a file in the parsing order: services first, then routes, then trips, then
+/// frequencies, then every other file.
+pub(crate) fn load_rank(file: GtfsFile) -> u8 {
+    match file {
+        GtfsFile::Calendar | GtfsFile::CalendarDates => 0,
+        GtfsFile::Routes => 1,
+        GtfsFile::Trips => 2,
+        GtfsFile::Frequencies => 3,
+        _ => 4,
+    }
+}
+
+/// Parses an optional time, `Err` if the value is not a valid time.
+fn parse_time(value: Option<&str>) -> Result<Option<NaiveServiceTime>, ()> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(value) => NaiveServiceTime::try_from(value).map(Some).map_err(|_| ()),
+    }
+}
+
+/// Returns `true` if the period of a frequency overlaps the selected times.
+fn is_period_in_window(
+    options: &ParseOptions,
+    start_time: Option<&str>,
+    end_time: Option<&str>,
+) -> bool {
+    let Some(times) = &options.times else {
+        return true;
+    };
+    match (parse_time(start_time), parse_time(end_time)) {
+        (Ok(Some(start_time)), Ok(Some(end_time))) => {
+            start_time <= *times.end() && end_time > *times.start()
+        }
+        _ => true,
+    }
+}
+
+impl LoadFilter {
+    /// Returns `true` if the record should be parsed.
+    pub(crate) fn keeps(
+        &mut self,
+        dataset: &Dataset,
+        options: &ParseOptions,
+        gtfs_file: GtfsFile,
+        header: &csv::StringRecord,
+        record: &csv::StringRecord,
+    ) -> bool {
+        let value = |column: &str| {
+            header
+                .iter()
+                .position(|name| name == column)
+                .and_then(|index| record.get(index))
+        };
+        match gtfs_file {
+            GtfsFile::Routes => {
+                value("route_id").is_none_or(|route_id| options.keeps_route(route_id))
+            }
+            GtfsFile::Trips => {
+                value("route_id").is_none_or(|route_id| options.keeps_route(route_id))
+                    && value("service_id")
+                        .is_none_or(|service_id| self.is_service_kept(dataset, options, service_id))
+            }
+            GtfsFile::StopTimes => value("trip_id").is_none_or(|trip_id| {
+                self.is_trip_kept(dataset, options, trip_id)
+                    && self.frequency_trips.get(trip_id).is_none_or(|kept| *kept)
+            }),
+            GtfsFile::Frequencies => {
+                let trip_id = value("trip_id");
+                if !trip_id.is_none_or(|trip_id| self.is_trip_kept(dataset, options, trip_id)) {
+                    return false;
+                }
+                let kept = is_period_in_window(options, value("start_time"), value("end_time"));
+                if let Some(trip_id) = trip_id {
+                    *self.frequency_trips.entry(trip_id.to_string()).or_default() |= kept;
+                }
+                kept
+            }
+            GtfsFile::Shapes => value("shape_id").is_none_or(|shape_id| {
+                self.kept_ids(dataset, options)
+                    .1
+                    .is_none_or(|shape_ids| shape_ids.contains(shape_id))
+            }),
+            _ => true,
+        }
+    }
+
+    /// Returns `true` if the trip was kept, or if trips.txt is skipped.
+    fn is_trip_kept(&mut self, dataset: &Dataset, options: &ParseOptions, trip_id: &str) -> bool {
+        self.kept_ids(dataset, options)
+            .0
+            .is_none_or(|trip_ids| trip_ids.contains(trip_id))
+    }
+
+    /// Drops the records of a file that can only be judged once the whole file is parsed.
+    ///
+    /// The stop times of a trip are kept or dropped together, by whether the trip runs within
+    /// the selected times from its first departure to its last arrival, so that no trip is cut
+    /// short. The trips defined by frequencies.txt follow their frequencies instead, and are
+    /// filtered as their stop times are read.
+    pub(crate) fn finish_file(
+        &self,
+        dataset: &Dataset,
+        options: &ParseOptions,
+        gtfs_file: GtfsFile,
+    ) {
+        let Some(times) = &options.times else {
+            return;
+        };
+        if gtfs_file != GtfsFile::StopTimes {
+            return;
+        }
+        // The first and last timed stop times of each trip, by stop_sequence.
+        let mut spans: HashMap<TripId, [(u32, NaiveServiceTime); 2]> = HashMap::new();
+        for stop_time in dataset.stop_times.iter() {
+            if self
+                .frequency_trips
+                .contains_key(stop_time.trip_id.as_str())
+            {
+                continue;
+            }
+            let (Some(departure), Some(arrival)) = (
+                stop_time.departure_time.or(stop_time.arrival_time),
+                stop_time.arrival_time.or(stop_time.departure_time),
+            ) else {
+                continue;
+            };
+            let stop_sequence = stop_time.stop_sequence;
+            spans
+                .entry(stop_time.trip_id.clone())
+                .and_modify(|[first, last]| {
+                    if stop_sequence < first.0 {
+                        *first = (stop_sequence, departure);
+                    }
+                    if stop_sequence > last.0 {
+                        *last = (stop_sequence, arrival);
+                    }
+                })
+                .or_insert([(stop_sequence, departure), (stop_sequence, arrival)]);
+        }
+        let dropped: HashSet<TripId> = spans
+            .into_iter()
+            .filter(|(_, [(_, departure), (_, arrival)])| {
+                departure > times.end() || arrival < times.start()
+            })
+            .map(|(trip_id, _)| trip_id)
+            .collect();
+        if !dropped.is_empty() {
+            dataset
+                .stop_times
+                .retain(|(trip_id, _), _| !dropped.contains(trip_id));
+        }
+    }
+
+    /// Returns `true` if the service runs on one of the selected dates.
+    fn is_service_kept(
+        &mut self,
+        dataset: &Dataset,
+        options: &ParseOptions,
+        service_id: &str,
+    ) -> bool {
+        let Some(dates) = &options.dates else {
+            return true;
+        };
+        *self
+            .active_services
+            .entry(service_id.to_string())
+            .or_insert_with(|| {
+                let service_id = CalendarServiceId::from(service_id);
+                dates
+                    .start()
+                    .iter_days()
+                    .take_while(|date| date <= dates.end())
+                    .any(|date| dataset.is_service_active(&service_id, date))
+            })
+    }
+
+    /// Returns the ids of the trips and shapes that were kept, `None` if trips.txt is skipped.
+    fn kept_ids(
+        &mut self,
+        dataset: &Dataset,
+        options: &ParseOptions,
+    ) -> (Option<&HashSet<String>>, Option<&HashSet<String>>) {
+        if self.trip_ids.is_none() && options.loads(GtfsFile::Trips) {
+            self.trip_ids = Some(
+                dataset
+                    .trips
+                    .iter()
+                    .map(|trip| trip.trip_id.to_string())
+                    .collect(),
+            );
+            self.shape_ids = Some(
+                dataset
+                    .trips
+                    .iter()
+                    .filter_map(|trip| trip.shape_id.as_ref().map(|shape_id| shape_id.to_string()))
+                    .collect(),
+            );
+        }
+        (self.trip_ids.as_ref(), self.shape_ids.as_ref())
+    }
+}
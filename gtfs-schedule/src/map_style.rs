@@ -0,0 +1,309 @@
+//! Export of routes as map layers ready to be added to a MapLibre or Mapbox style.
+//!
+//! The main types are:
+//! - [`RouteMapStyle`]: The GeoJSON source and the style layers drawing a route.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use geo::{Coord, LineString};
+use serde_json::{json, Value};
+
+use crate::schemas::{Route, RouteId, Shape, ShapeId, StopId, TripId};
+use crate::{Dataset, ShapeGeometry};
+
+/// Color of [`Route::route_color`] when omitted, as defined by the specification.
+const DEFAULT_ROUTE_COLOR: &str = "FFFFFF";
+/// Color of [`Route::route_text_color`] when omitted, as defined by the specification.
+const DEFAULT_ROUTE_TEXT_COLOR: &str = "000000";
+
+/// The distinct paths of the trips of a route, keyed by shape or by sequence of stops.
+type RoutePaths = BTreeMap<Vec<String>, LineString<f64>>;
+
+/// The sequence, stop and coordinates of a stop time.
+type Call = (u32, StopId, Option<Coord>);
+
+/// The GeoJSON source and the style layers drawing a route, see [`Dataset::route_map_style`].
+///
+/// The source holds a `LineString` feature per distinct path of the trips of the route and a
+/// `Point` feature per stop it serves. The layers draw the paths in the color of the route,
+/// the stops as circles, and label both with the names of the route and of the stops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteMapStyle {
+    /// Identifies the route.
+    pub route_id: RouteId,
+    /// Identifies the source in the style, referred to by [`RouteMapStyle::layers`].
+    pub source_id: String,
+    /// The GeoJSON `FeatureCollection` of the paths and stops of the route.
+    pub geojson: Value,
+    /// The style layers drawing the features of [`RouteMapStyle::geojson`].
+    pub layers: Vec<Value>,
+}
+
+impl RouteMapStyle {
+    /// Returns a style fragment holding the source and the layers, to be merged into the
+    /// `sources` and `layers` of a MapLibre or Mapbox style.
+    pub fn style_snippet(&self) -> Value {
+        json!({
+            "sources": {
+                &self.source_id: {
+                    "type": "geojson",
+                    "data": self.geojson,
+                }
+            },
+            "layers": self.layers,
+        })
+    }
+}
+
+/// Returns a color of routes.txt as a CSS color, `default` if it is missing or malformed.
+fn css_color(color: Option<&String>, default: &str) -> String {
+    let color = color
+        .map(|color| color.trim())
+        .filter(|color| color.len() == 6 && color.chars().all(|c| c.is_ascii_hexdigit()))
+        .unwrap_or(default);
+    format!("#{}", color.to_ascii_uppercase())
+}
+
+fn line_feature(route: &Route, label: &str, color: &str, line_string: &LineString<f64>) -> Value {
+    let coordinates: Vec<[f64; 2]> = line_string
+        .coords()
+        .map(|coord| [coord.x, coord.y])
+        .collect();
+    json!({
+        "type": "Feature",
+        "geometry": {"type": "LineString", "coordinates": coordinates},
+        "properties": {
+            "route_id": route.route_id.as_str(),
+            "route_short_name": route.route_short_name,
+            "route_long_name": route.route_long_name,
+            "label": label,
+            "color": color,
+        },
+    })
+}
+
+impl Dataset {
+    /// Returns the GeoJSON source and the style layers drawing a route, `None` if the route is
+    /// unknown.
+    ///
+    /// Paths are those of [`Dataset::trip_geometry`], trips sharing a shape or a sequence of
+    /// stops being drawn once. Lines take [`Route::route_color`] and labels
+    /// [`Route::route_text_color`], with the defaults of the specification when omitted; they
+    /// are labelled with [`Route::route_short_name`], or [`Route::route_long_name`] without
+    /// short name.
+    pub fn route_map_style(&self, route_id: &RouteId) -> Option<RouteMapStyle> {
+        if !self.routes.contains_key(route_id) {
+            return None;
+        }
+        self.route_map_styles_for(Some(route_id)).pop()
+    }
+
+    /// Returns the GeoJSON source and the style layers of every route, sorted by route id, see
+    /// [`Dataset::route_map_style`].
+    pub fn route_map_styles(&self) -> Vec<RouteMapStyle> {
+        self.route_map_styles_for(None)
+    }
+
+    /// Builds the map styles of a route, or of every route, going through the stop times and
+    /// the shapes once.
+    fn route_map_styles_for(&self, route_id: Option<&RouteId>) -> Vec<RouteMapStyle> {
+        let trips: HashMap<TripId, (RouteId, Option<ShapeId>)> = self
+            .trips
+            .iter()
+            .filter(|trip| route_id.is_none_or(|route_id| trip.route_id == *route_id))
+            .map(|trip| {
+                let value = (trip.route_id.clone(), trip.shape_id.clone());
+                (trip.trip_id.clone(), value)
+            })
+            .collect();
+
+        let shape_ids: HashSet<&ShapeId> = trips
+            .values()
+            .filter_map(|(_, shape_id)| shape_id.as_ref())
+            .collect();
+        let mut points: HashMap<ShapeId, Vec<Shape>> = HashMap::new();
+        for shape in self.shapes.iter() {
+            if shape_ids.contains(&shape.shape_id) {
+                points
+                    .entry(shape.shape_id.clone())
+                    .or_default()
+                    .push(shape.value().clone());
+            }
+        }
+        let shapes: HashMap<ShapeId, LineString<f64>> = points
+            .into_iter()
+            .filter_map(|(shape_id, points)| {
+                Some((shape_id, ShapeGeometry::from_points(points)?.line_string))
+            })
+            .collect();
+
+        let mut stop_times: HashMap<&TripId, Vec<Call>> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            let (Some((trip_id, _)), Some(stop_id)) = (
+                trips.get_key_value(&stop_time.trip_id),
+                stop_time.stop_id.as_ref(),
+            ) else {
+                continue;
+            };
+            stop_times.entry(trip_id).or_default().push((
+                stop_time.stop_sequence,
+                stop_id.clone(),
+                self.stop_time_coord(&stop_time),
+            ));
+        }
+
+        let mut paths: BTreeMap<&str, RoutePaths> = BTreeMap::new();
+        let mut stops: BTreeMap<&str, HashSet<StopId>> = BTreeMap::new();
+        let mut trip_ids: Vec<&TripId> = trips.keys().collect();
+        trip_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        for trip_id in trip_ids {
+            let (route_id, shape_id) = &trips[trip_id];
+            let mut calls = stop_times.remove(trip_id).unwrap_or_default();
+            calls.sort_by_key(|(stop_sequence, _, _)| *stop_sequence);
+            stops
+                .entry(route_id.as_str())
+                .or_default()
+                .extend(calls.iter().map(|(_, stop_id, _)| stop_id.clone()));
+
+            let route_paths = paths.entry(route_id.as_str()).or_default();
+            match shape_id.as_ref().and_then(|id| Some((id, shapes.get(id)?))) {
+                Some((shape_id, line_string)) => {
+                    route_paths
+                        .entry(vec![shape_id.to_string()])
+                        .or_insert_with(|| line_string.clone());
+                }
+                None => {
+                    let coords: Vec<Coord> =
+                        calls.iter().filter_map(|(_, _, coord)| *coord).collect();
+                    if !coords.is_empty() {
+                        let key = calls.iter().map(|(_, id, _)| id.to_string()).collect();
+                        route_paths
+                            .entry(key)
+                            .or_insert_with(|| LineString::from(coords));
+                    }
+                }
+            }
+        }
+
+        let mut routes: Vec<Route> = self
+            .routes
+            .iter()
+            .filter(|route| route_id.is_none_or(|route_id| route.route_id == *route_id))
+            .map(|route| route.clone())
+            .collect();
+        routes.sort_by(|a, b| a.route_id.as_str().cmp(b.route_id.as_str()));
+
+        routes
+            .into_iter()
+            .map(|route| {
+                let route_paths = paths.remove(route.route_id.as_str()).unwrap_or_default();
+                let route_stops = stops.remove(route.route_id.as_str()).unwrap_or_default();
+                self.route_map_style_of(route, route_paths.into_values(), route_stops)
+            })
+            .collect()
+    }
+
+    fn route_map_style_of(
+        &self,
+        route: Route,
+        paths: impl Iterator<Item = LineString<f64>>,
+        stops: HashSet<StopId>,
+    ) -> RouteMapStyle {
+        let color = css_color(route.route_color.as_ref(), DEFAULT_ROUTE_COLOR);
+        let text_color = css_color(route.route_text_color.as_ref(), DEFAULT_ROUTE_TEXT_COLOR);
+        let label = route
+            .route_short_name
+            .as_ref()
+            .or(route.route_long_name.as_ref())
+            .cloned()
+            .unwrap_or_default();
+
+        let mut features: Vec<Value> = paths
+            .map(|line_string| line_feature(&route, &label, &color, &line_string))
+            .collect();
+        let mut stops: Vec<StopId> = stops.into_iter().collect();
+        stops.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        for stop_id in stops {
+            let Some(stop) = self.stops.get(&stop_id) else {
+                continue;
+            };
+            let Some(coord) = stop.stop_coord.as_ref() else {
+                continue;
+            };
+            features.push(json!({
+                "type": "Feature",
+                "geometry": {"type": "Point", "coordinates": [coord.x, coord.y]},
+                "properties": {
+                    "stop_id": stop.stop_id.as_str(),
+                    "stop_name": stop.stop_name,
+                    "color": color,
+                },
+            }));
+        }
+
+        let source_id = format!("route-{}", route.route_id);
+        let layer_id = |suffix: &str| format!("{}-{}", source_id, suffix);
+        let layers = vec![
+            json!({
+                "id": layer_id("line"),
+                "type": "line",
+                "source": source_id,
+                "filter": ["==", ["geometry-type"], "LineString"],
+                "layout": {"line-join": "round", "line-cap": "round"},
+                "paint": {"line-color": color, "line-width": 4},
+            }),
+            json!({
+                "id": layer_id("stops"),
+                "type": "circle",
+                "source": source_id,
+                "filter": ["==", ["geometry-type"], "Point"],
+                "paint": {
+                    "circle-radius": 5,
+                    "circle-color": "#FFFFFF",
+                    "circle-stroke-color": color,
+                    "circle-stroke-width": 2,
+                },
+            }),
+            json!({
+                "id": layer_id("line-labels"),
+                "type": "symbol",
+                "source": source_id,
+                "filter": ["==", ["geometry-type"], "LineString"],
+                "layout": {
+                    "symbol-placement": "line",
+                    "text-field": ["get", "label"],
+                    "text-size": 12,
+                },
+                "paint": {
+                    "text-color": text_color,
+                    "text-halo-color": color,
+                    "text-halo-width": 2,
+                },
+            }),
+            json!({
+                "id": layer_id("stop-labels"),
+                "type": "symbol",
+                "source": source_id,
+                "filter": ["==", ["geometry-type"], "Point"],
+                "layout": {
+                    "text-field": ["coalesce", ["get", "stop_name"], ["get", "stop_id"]],
+                    "text-size": 11,
+                    "text-offset": [0, 1.2],
+                    "text-anchor": "top",
+                },
+                "paint": {
+                    "text-color": "#333333",
+                    "text-halo-color": "#FFFFFF",
+                    "text-halo-width": 1,
+                },
+            }),
+        ];
+
+        RouteMapStyle {
+            route_id: route.route_id,
+            source_id,
+            geojson: json!({"type": "FeatureCollection", "features": features}),
+            layers,
+        }
+    }
+}
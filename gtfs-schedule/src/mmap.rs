@@ -0,0 +1,53 @@
+//! Parsing of datasets from memory-mapped files, see [`Dataset::from_csv_mmap`].
+//!
+//! Mapped files are paged in by the operating system as they are read, instead of being copied
+//! into buffers of the process, which keeps the memory used while scanning large feeds low.
+
+use std::fs::File;
+use std::io::Cursor;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::error::{ParseError, ParseErrorKind, Result};
+use crate::{BorrowedReader, Dataset, ParseOptions};
+
+/// Maps the file at `path` in memory.
+///
+/// The file must not be modified while it is mapped, which would change the bytes being parsed.
+fn map(path: &Path) -> Result<Mmap> {
+    let io_error = |e: std::io::Error| ParseError::from(ParseErrorKind::from(e));
+    let file = File::open(path).map_err(io_error)?;
+    // SAFETY: The map is private to the reader it is handed to, and is only read.
+    Ok(unsafe { Mmap::map(&file) }.map_err(io_error)?)
+}
+
+impl Dataset {
+    /// Same as [`Dataset::from_csv`], but maps the files in memory rather than reading them.
+    ///
+    /// ```no_run
+    /// use gtfs_schedule::Dataset;
+    ///
+    /// let dataset = Dataset::from_csv_mmap("gtfs".as_ref()).unwrap();
+    /// ```
+    pub fn from_csv_mmap(dir: &Path) -> Result<Self> {
+        Self::from_csv_mmap_with_options(dir, &ParseOptions::default())
+    }
+
+    /// Same as [`Dataset::from_csv_with_options`], but maps the files in memory rather than
+    /// reading them.
+    pub fn from_csv_mmap_with_options(dir: &Path, options: &ParseOptions) -> Result<Self> {
+        Self::from_csv_files(Self::csv_file_names(dir)?, options, |file_name| {
+            Ok(Box::new(Cursor::new(map(&dir.join(file_name))?)))
+        })
+    }
+}
+
+impl BorrowedReader<Cursor<Mmap>> {
+    /// Reads records from the file at `path`, mapped in memory.
+    ///
+    /// Scanning a multi-gigabyte file this way neither copies it nor allocates for each record.
+    pub fn from_mmap(path: &Path) -> Result<Self> {
+        Self::from_reader(Cursor::new(map(path)?))
+    }
+}
@@ -0,0 +1,119 @@
+//! Resolution of the networks of routes.
+//!
+//! A feed groups routes into networks either with [`crate::schemas::Route::network_id`], or
+//! with routes_networks.txt, but never both. The functions of this module hide which one is used.
+//!
+//! The main type is:
+//! - [`NetworkForm`]: The way the routes of a dataset are grouped into networks.
+
+use crate::schemas::{Network, NetworkId, RouteId, RouteNetwork};
+use crate::Dataset;
+
+/// The way the routes of a dataset are grouped into networks, see
+/// [`Dataset::normalize_networks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkForm {
+    /// The [`crate::schemas::Route::network_id`] of each route, without networks.txt.
+    RouteField,
+    /// A record of routes_networks.txt for each route, referring to networks.txt.
+    RoutesNetworks,
+}
+
+impl Dataset {
+    /// Returns the network a route belongs to, if any.
+    ///
+    /// The network is read from routes_networks.txt when the file has records, and from
+    /// [`crate::schemas::Route::network_id`] otherwise.
+    pub fn network_of_route(&self, route_id: &RouteId) -> Option<NetworkId> {
+        if self.routes_networks.is_empty() {
+            self.routes
+                .get(route_id)
+                .and_then(|route| route.network_id.clone())
+        } else {
+            self.routes_networks
+                .get(route_id)
+                .map(|route_network| route_network.network_id.clone())
+        }
+    }
+
+    /// Returns the routes belonging to a network, sorted by id, see
+    /// [`Dataset::network_of_route`].
+    pub fn routes_in_network(&self, network_id: &NetworkId) -> Vec<RouteId> {
+        let mut route_ids: Vec<RouteId> = if self.routes_networks.is_empty() {
+            self.routes
+                .iter()
+                .filter(|route| route.network_id.as_ref() == Some(network_id))
+                .map(|route| route.route_id.clone())
+                .collect()
+        } else {
+            self.routes_networks
+                .iter()
+                .filter(|route_network| route_network.network_id == *network_id)
+                .map(|route_network| route_network.route_id.clone())
+                .collect()
+        };
+        route_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        route_ids
+    }
+
+    /// Rewrites the networks of the routes so that they are all defined in `form`, as some tools
+    /// only support one of them.
+    ///
+    /// The network of each route is kept, as given by [`Dataset::network_of_route`].
+    /// With [`NetworkForm::RoutesNetworks`], the networks missing from networks.txt are added
+    /// without name. With [`NetworkForm::RouteField`], networks.txt is emptied, as it is
+    /// forbidden alongside [`crate::schemas::Route::network_id`], so the network names are lost.
+    pub fn normalize_networks(&mut self, form: NetworkForm) {
+        let route_ids: Vec<RouteId> = self
+            .routes
+            .iter()
+            .map(|route| route.route_id.clone())
+            .collect();
+        let networks: Vec<(RouteId, Option<NetworkId>)> = route_ids
+            .into_iter()
+            .map(|route_id| {
+                let network_id = self.network_of_route(&route_id);
+                (route_id, network_id)
+            })
+            .collect();
+
+        self.routes_networks.clear();
+        for (route_id, network_id) in networks {
+            match form {
+                NetworkForm::RouteField => {
+                    if let Some(mut route) = self.routes.get_mut(&route_id) {
+                        route.network_id = network_id;
+                    }
+                }
+                NetworkForm::RoutesNetworks => {
+                    if let Some(mut route) = self.routes.get_mut(&route_id) {
+                        route.network_id = None;
+                    }
+                    let Some(network_id) = network_id else {
+                        continue;
+                    };
+                    self.networks
+                        .entry(network_id.clone())
+                        .or_insert_with(|| Network {
+                            network_id: network_id.clone(),
+                            network_name: None,
+                            #[cfg(feature = "extensions")]
+                            extensions: Default::default(),
+                        });
+                    self.routes_networks.insert(
+                        route_id.clone(),
+                        RouteNetwork {
+                            network_id,
+                            route_id,
+                            #[cfg(feature = "extensions")]
+                            extensions: Default::default(),
+                        },
+                    );
+                }
+            }
+        }
+        if form == NetworkForm::RouteField {
+            self.networks.clear();
+        }
+    }
+}
@@ -0,0 +1,439 @@
+//! Validation results in the format of the MobilityData GTFS validator.
+//!
+//! The main types are:
+//! - [`NoticeReport`]: The notices of a dataset, grouped by code.
+//! - [`NoticeSeverity`]: How serious a notice is.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::error::{
+    DatasetValidationErrorKind, Error, ParseError, ParseErrorKind, Result,
+    SchemaValidationErrorKind,
+};
+use crate::schemas::Schema;
+use crate::{Dataset, Warning, WarningKind};
+
+/// How serious a notice is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum NoticeSeverity {
+    /// Something worth knowing, that is not a problem.
+    Info,
+    /// A recommendation of the specification that is not followed.
+    Warning,
+    /// A requirement of the specification that is not met.
+    Error,
+}
+
+/// The notices sharing a code, see [`NoticeReport`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoticeGroup {
+    /// Identifies the rule, e.g. `foreign_key_violation`.
+    pub code: String,
+    /// How serious the notices are.
+    pub severity: NoticeSeverity,
+    /// Number of notices with this code.
+    pub total_notices: usize,
+    /// The context of each notice, such as `filename`, `csvRowNumber` and `fieldName`.
+    pub sample_notices: Vec<Map<String, Value>>,
+}
+
+/// The notices of a dataset, see [`Dataset::notice_report`].
+///
+/// It serializes to the `report.json` layout of the MobilityData GTFS validator, so that tools
+/// consuming that report can consume this one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NoticeReport {
+    /// Notices grouped by code, from the most to the least serious, then by code.
+    pub notices: Vec<NoticeGroup>,
+}
+
+impl NoticeReport {
+    /// Returns `true` if there is no notice of severity [`NoticeSeverity::Error`].
+    pub fn is_valid(&self) -> bool {
+        self.notices
+            .iter()
+            .all(|group| group.severity < NoticeSeverity::Error)
+    }
+
+    /// Returns the notices with the given code, if any.
+    pub fn group(&self, code: &str) -> Option<&NoticeGroup> {
+        self.notices.iter().find(|group| group.code == code)
+    }
+
+    /// Writes the report as JSON.
+    pub fn to_json<W: Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer(writer, self)
+            .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+
+        Ok(())
+    }
+
+    /// Adds a notice.
+    fn push(&mut self, code: &str, severity: NoticeSeverity, context: Map<String, Value>) {
+        match self
+            .notices
+            .iter_mut()
+            .find(|group| group.code == code && group.severity == severity)
+        {
+            Some(group) => {
+                group.total_notices += 1;
+                group.sample_notices.push(context);
+            }
+            None => self.notices.push(NoticeGroup {
+                code: code.to_string(),
+                severity,
+                total_notices: 1,
+                sample_notices: vec![context],
+            }),
+        }
+    }
+}
+
+impl Error {
+    /// Returns the code of the notice reporting this error.
+    ///
+    /// Codes are those of the MobilityData GTFS validator when it has an equivalent rule (e.g.
+    /// `foreign_key_violation`), and follow the same naming otherwise.
+    pub fn notice_code(&self) -> &'static str {
+        match self {
+            Error::ParseError(error) => match error.kind {
+                ParseErrorKind::Csv(_) => "csv_parsing_failed",
+                ParseErrorKind::Io(_) => "i_o_error",
+                ParseErrorKind::Chrono(_) => "invalid_date",
+                ParseErrorKind::ParseInt(_) => "invalid_integer",
+                ParseErrorKind::Json(_) => "malformed_json",
+                #[cfg(feature = "zip")]
+                ParseErrorKind::Zip(_) => "i_o_error",
+                _ => "field_parsing_error",
+            },
+            Error::SchemaValidationError(error) => match error.kind {
+                SchemaValidationErrorKind::MissingValue { .. } => "missing_required_field",
+                SchemaValidationErrorKind::ForbiddenValue { .. } => "forbidden_field_value",
+                SchemaValidationErrorKind::InvalidValue { .. } => "invalid_field_value",
+                SchemaValidationErrorKind::FrequencyWindowOutOfOrder { .. } => {
+                    "start_and_end_range_out_of_order"
+                }
+                SchemaValidationErrorKind::NonPositiveHeadway => "non_positive_headway",
+            },
+            Error::DatasetValidationError(error) => match error.kind {
+                DatasetValidationErrorKind::PrimaryKeyNotUnique { .. } => "duplicate_key",
+                DatasetValidationErrorKind::ForeignKeyNotFound { .. } => "foreign_key_violation",
+                DatasetValidationErrorKind::InconsistentValue { .. } => "inconsistent_field_value",
+                DatasetValidationErrorKind::InvalidCombination { .. } => {
+                    "invalid_field_combination"
+                }
+                DatasetValidationErrorKind::MissingValue { .. } => "missing_required_field",
+                DatasetValidationErrorKind::OverlappingIntervals { .. } => "timeframe_overlap",
+                DatasetValidationErrorKind::SuspiciousCoordinate { .. } => "suspicious_coordinate",
+                DatasetValidationErrorKind::OverlappingFrequencies { .. } => {
+                    "overlapping_frequency"
+                }
+                DatasetValidationErrorKind::StillReferenced { .. } => "record_still_referenced",
+            },
+        }
+    }
+}
+
+impl WarningKind {
+    /// Returns the code of the notice reporting this warning, see [`Error::notice_code`].
+    pub fn notice_code(&self) -> &'static str {
+        match self {
+            WarningKind::LowColorContrast { .. } => "route_color_contrast",
+            WarningKind::StopDescSameAsName { .. } => "same_name_and_description_for_stop",
+            WarningKind::LongRouteShortName { .. } => "route_short_name_too_long",
+            WarningKind::SameRouteShortAndLongName { .. } => "route_short_and_long_name_equal",
+            WarningKind::RouteLongNameContainsShortName { .. } => {
+                "route_long_name_contains_short_name"
+            }
+            WarningKind::DuplicateRouteName { .. } => "duplicate_route_name",
+            WarningKind::StopTooFarFromShape { .. } => "stop_too_far_from_shape",
+            WarningKind::MissingTtsStopName { .. } => "missing_tts_stop_name",
+            WarningKind::OverlappingBlockTrips { .. } => "block_trips_with_overlapping_stop_times",
+            WarningKind::MissingFeedContact => "missing_feed_contact_email_and_url",
+            WarningKind::UnevenExactTimesWindow { .. } => "uneven_exact_times_window",
+            WarningKind::PublisherUrlSameAsAgencyUrl { .. } => {
+                "feed_publisher_url_same_as_agency_url"
+            }
+        }
+    }
+}
+
+impl Dataset {
+    /// Validates the dataset, and reports the findings as notices.
+    ///
+    /// The notices are those of [`Dataset::validation_report`] and [`Dataset::warnings`], along
+    /// with the unknown columns, deprecated columns and duplicate keys of
+    /// [`Dataset::parse_report`].
+    ///
+    /// This is not the full notice set of the MobilityData validator: only the first error
+    /// found across records is reported, see [`Dataset::validation_report`], and the codes
+    /// only follow theirs where both report the same problem.
+    ///
+    /// Row numbers are only known for datasets parsed with
+    /// [`crate::ParseOptions::keep_source_lines`].
+    pub fn notice_report(&self) -> NoticeReport {
+        let mut report = NoticeReport::default();
+
+        for (file, columns) in &self.parse_report().unknown_columns {
+            for column in columns {
+                let mut context = Map::new();
+                context.insert("filename".to_string(), file.file_name().into());
+                context.insert("fieldName".to_string(), column.as_str().into());
+                report.push("unknown_column", NoticeSeverity::Info, context);
+            }
+        }
+        for deprecated in &self.parse_report().deprecated_columns {
+            let mut context = Map::new();
+            context.insert("filename".to_string(), deprecated.file.file_name().into());
+            context.insert("fieldName".to_string(), deprecated.column.as_str().into());
+            context.insert(
+                "replacementFieldName".to_string(),
+                deprecated.replaced_by.as_str().into(),
+            );
+            report.push("deprecated_column", NoticeSeverity::Warning, context);
+        }
+        for duplicate_key in &self.parse_report().duplicate_keys {
+            let mut context = Map::new();
+            context.insert(
+                "filename".to_string(),
+                duplicate_key.file.file_name().into(),
+            );
+            if let Some(line) = duplicate_key.line {
+                context.insert("csvRowNumber".to_string(), line.into());
+            }
+            if let Some(previous_line) = duplicate_key.previous_line {
+                context.insert("oldCsvRowNumber".to_string(), previous_line.into());
+            }
+            context.insert(
+                "fieldName".to_string(),
+                duplicate_key.file.key_columns().join(", ").into(),
+            );
+            context.insert("fieldValue".to_string(), duplicate_key.key.as_str().into());
+            report.push("duplicate_key", NoticeSeverity::Error, context);
+        }
+
+        for finding in self.validation_report().findings {
+            let context = self.notice_context(&finding.error);
+            let code = match (finding.error.notice_code(), finding.severity) {
+                ("missing_required_field", NoticeSeverity::Warning) => "missing_recommended_field",
+                (code, _) => code,
+            };
+            report.push(code, finding.severity, context);
+        }
+        for warning in self.warnings() {
+            let context = self.warning_context(&warning);
+            report.push(warning.kind.notice_code(), NoticeSeverity::Warning, context);
+        }
+
+        report
+            .notices
+            .sort_by(|a, b| b.severity.cmp(&a.severity).then(a.code.cmp(&b.code)));
+        report
+    }
+
+    /// Describes the records and fields involved in an error.
+    fn notice_context(&self, error: &Error) -> Map<String, Value> {
+        let mut context = Map::new();
+        let mut fields: BTreeMap<&str, Value> = BTreeMap::new();
+        let records: &[Schema] = match error {
+            Error::ParseError(error) => {
+                fields.insert("message", error.to_string().into());
+                &[]
+            }
+            Error::SchemaValidationError(error) => {
+                match &error.kind {
+                    SchemaValidationErrorKind::MissingValue { field_name, reason }
+                    | SchemaValidationErrorKind::ForbiddenValue { field_name, reason }
+                    | SchemaValidationErrorKind::InvalidValue { field_name, reason } => {
+                        fields.insert("fieldName", field_name.as_str().into());
+                        if let Some(reason) = reason {
+                            fields.insert("message", reason.as_str().into());
+                        }
+                    }
+                    SchemaValidationErrorKind::FrequencyWindowOutOfOrder {
+                        start_time,
+                        end_time,
+                    } => {
+                        fields.insert("startFieldName", "start_time".into());
+                        fields.insert("startValue", start_time.as_str().into());
+                        fields.insert("endFieldName", "end_time".into());
+                        fields.insert("endValue", end_time.as_str().into());
+                    }
+                    SchemaValidationErrorKind::NonPositiveHeadway => {
+                        fields.insert("fieldName", "headway_secs".into());
+                        fields.insert("fieldValue", 0.into());
+                    }
+                }
+                std::slice::from_ref(&error.schema_instance)
+            }
+            Error::DatasetValidationError(error) => {
+                match &error.kind {
+                    DatasetValidationErrorKind::PrimaryKeyNotUnique { field_name, value } => {
+                        fields.insert("fieldName", field_name.as_str().into());
+                        fields.insert("fieldValue", value.as_str().into());
+                    }
+                    DatasetValidationErrorKind::ForeignKeyNotFound {
+                        field_name,
+                        value,
+                        reference_file_name,
+                    } => {
+                        fields.insert("fieldName", field_name.as_str().into());
+                        fields.insert("fieldValue", value.as_str().into());
+                        fields.insert("parentFilename", reference_file_name.as_str().into());
+                    }
+                    DatasetValidationErrorKind::InconsistentValue {
+                        field_name,
+                        value,
+                        reason,
+                    } => {
+                        fields.insert("fieldName", field_name.as_str().into());
+                        fields.insert("fieldValue", value.as_str().into());
+                        if let Some(reason) = reason {
+                            fields.insert("message", reason.as_str().into());
+                        }
+                    }
+                    DatasetValidationErrorKind::InvalidCombination {
+                        fields: names,
+                        reason,
+                    } => {
+                        fields.insert("fieldName", names.join(", ").into());
+                        if let Some(reason) = reason {
+                            fields.insert("message", reason.as_str().into());
+                        }
+                    }
+                    DatasetValidationErrorKind::MissingValue { field_name, reason } => {
+                        fields.insert("fieldName", field_name.as_str().into());
+                        if let Some(reason) = reason {
+                            fields.insert("message", reason.as_str().into());
+                        }
+                    }
+                    DatasetValidationErrorKind::OverlappingIntervals { details } => {
+                        fields.insert("message", details.as_str().into());
+                    }
+                    DatasetValidationErrorKind::SuspiciousCoordinate {
+                        field_name,
+                        value,
+                        reason,
+                    } => {
+                        fields.insert("fieldName", field_name.as_str().into());
+                        fields.insert("fieldValue", value.as_str().into());
+                        fields.insert("message", reason.as_str().into());
+                    }
+                    DatasetValidationErrorKind::OverlappingFrequencies { trip_id, .. } => {
+                        fields.insert("tripId", trip_id.as_str().into());
+                        fields.insert("message", error.kind.to_string().into());
+                    }
+                    DatasetValidationErrorKind::StillReferenced {
+                        field_name,
+                        value,
+                        referencing_file_name,
+                    } => {
+                        fields.insert("fieldName", field_name.as_str().into());
+                        fields.insert("fieldValue", value.as_str().into());
+                        fields.insert("childFilename", referencing_file_name.as_str().into());
+                    }
+                }
+                error.schema_instances.as_slice()
+            }
+        };
+
+        if let Some(record) = records.first() {
+            context.insert("filename".to_string(), record.file().file_name().into());
+            if let Some(provenance) = self.provenance(record) {
+                context.insert("csvRowNumber".to_string(), provenance.line.into());
+            }
+        }
+        for (name, value) in fields {
+            context.insert(name.to_string(), value);
+        }
+        context
+    }
+
+    /// Describes the records and fields involved in a warning.
+    fn warning_context(&self, warning: &Warning) -> Map<String, Value> {
+        let mut context = Map::new();
+        if let Some(record) = warning.schema_instances.first() {
+            context.insert("filename".to_string(), record.file().file_name().into());
+            if let Some(provenance) = self.provenance(record) {
+                context.insert("csvRowNumber".to_string(), provenance.line.into());
+            }
+        }
+        match &warning.kind {
+            WarningKind::LowColorContrast {
+                route_id,
+                contrast_ratio,
+            } => {
+                context.insert("routeId".to_string(), route_id.as_str().into());
+                context.insert("contrastRatio".to_string(), (*contrast_ratio).into());
+            }
+            WarningKind::StopDescSameAsName { stop_id } => {
+                context.insert("stopId".to_string(), stop_id.as_str().into());
+            }
+            WarningKind::LongRouteShortName { route_id, length } => {
+                context.insert("routeId".to_string(), route_id.as_str().into());
+                context.insert("length".to_string(), (*length).into());
+            }
+            WarningKind::SameRouteShortAndLongName { route_id }
+            | WarningKind::RouteLongNameContainsShortName { route_id } => {
+                context.insert("routeId".to_string(), route_id.as_str().into());
+            }
+            WarningKind::DuplicateRouteName {
+                route_id,
+                other_route_id,
+            } => {
+                context.insert("routeId1".to_string(), route_id.as_str().into());
+                context.insert("routeId2".to_string(), other_route_id.as_str().into());
+            }
+            WarningKind::StopTooFarFromShape {
+                stop_id,
+                shape_id,
+                distance_m,
+            } => {
+                context.insert("stopId".to_string(), stop_id.as_str().into());
+                context.insert("shapeId".to_string(), shape_id.as_str().into());
+                context.insert("distanceMeters".to_string(), (*distance_m).into());
+            }
+            WarningKind::MissingTtsStopName { stop_id, stop_name } => {
+                context.insert("stopId".to_string(), stop_id.as_str().into());
+                context.insert("stopName".to_string(), stop_name.as_str().into());
+            }
+            WarningKind::OverlappingBlockTrips {
+                block_id,
+                trip_id,
+                other_trip_id,
+                date,
+            } => {
+                context.insert("blockId".to_string(), block_id.as_str().into());
+                context.insert("tripId1".to_string(), trip_id.as_str().into());
+                context.insert("tripId2".to_string(), other_trip_id.as_str().into());
+                context.insert(
+                    "intersection".to_string(),
+                    date.format("%Y%m%d").to_string().into(),
+                );
+            }
+            WarningKind::MissingFeedContact => {}
+            WarningKind::UnevenExactTimesWindow {
+                trip_id,
+                start_time,
+                window_secs,
+                headway_secs,
+            } => {
+                context.insert("tripId".to_string(), trip_id.as_str().into());
+                context.insert("startTime".to_string(), start_time.as_str().into());
+                context.insert("windowSecs".to_string(), (*window_secs).into());
+                context.insert("headwaySecs".to_string(), (*headway_secs).into());
+            }
+            WarningKind::PublisherUrlSameAsAgencyUrl { url, agency_name } => {
+                context.insert("feedPublisherUrl".to_string(), url.as_str().into());
+                context.insert("agencyName".to_string(), agency_name.as_str().into());
+            }
+        }
+        context
+    }
+}
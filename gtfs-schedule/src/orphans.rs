@@ -0,0 +1,267 @@
+//! Detection and removal of records that nothing refers to.
+//!
+//! The main type is:
+//! - [`Orphans`]: The records of a dataset that are not used by the rest of it.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::schemas::{CalendarServiceId, RouteId, ShapeId, StopId, TripId};
+use crate::Dataset;
+
+/// The records of a dataset that are not used by the rest of it, see [`Dataset::find_orphans`].
+///
+/// Each list is sorted by id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Orphans {
+    /// Stops not used by any stop time, pathway, transfer, area or location group, nor being in
+    /// the station of such a stop, e.g. its parent station or the entrances of that station.
+    pub stops: Vec<StopId>,
+    /// Routes without trips.
+    pub routes: Vec<RouteId>,
+    /// Trips with fewer than two stop times.
+    pub trips: Vec<TripId>,
+    /// Services of calendar.txt or calendar_dates.txt not used by any trip, timeframe or booking
+    /// rule.
+    pub services: Vec<CalendarServiceId>,
+    /// Shapes not used by any trip.
+    pub shapes: Vec<ShapeId>,
+}
+
+impl Orphans {
+    /// Returns `true` if no orphan was found.
+    pub fn is_empty(&self) -> bool {
+        self.stops.is_empty()
+            && self.routes.is_empty()
+            && self.trips.is_empty()
+            && self.services.is_empty()
+            && self.shapes.is_empty()
+    }
+
+    /// Adds the orphans of `other` to these ones.
+    fn extend(&mut self, other: Orphans) {
+        self.stops.extend(other.stops);
+        self.routes.extend(other.routes);
+        self.trips.extend(other.trips);
+        self.services.extend(other.services);
+        self.shapes.extend(other.shapes);
+    }
+
+    /// Sorts each list by id.
+    fn sort(&mut self) {
+        self.stops.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        self.routes.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        self.trips.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        self.services.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        self.shapes.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    }
+}
+
+impl Dataset {
+    /// Finds the records that are not used by the rest of the dataset.
+    ///
+    /// Removing some orphans may leave others behind, e.g. the route of a trip without stop
+    /// times; see [`Dataset::prune_orphans`].
+    pub fn find_orphans(&self) -> Orphans {
+        let mut stop_times_per_trip: HashMap<&str, usize> = HashMap::new();
+        let stop_times: Vec<_> = self.stop_times.iter().collect();
+        for stop_time in &stop_times {
+            *stop_times_per_trip
+                .entry(stop_time.trip_id.as_str())
+                .or_default() += 1;
+        }
+        let trips: Vec<TripId> = self
+            .trips
+            .iter()
+            .filter(|trip| {
+                stop_times_per_trip
+                    .get(trip.trip_id.as_str())
+                    .is_none_or(|count| *count < 2)
+            })
+            .map(|trip| trip.trip_id.clone())
+            .collect();
+
+        // Stops used by other tables, and the stations they belong to along with everything in
+        // them.
+        let mut used_stops: HashSet<StopId> = stop_times
+            .iter()
+            .filter_map(|stop_time| stop_time.stop_id.clone())
+            .chain(
+                self.pathways
+                    .iter()
+                    .flat_map(|pathway| [pathway.from_stop_id.clone(), pathway.to_stop_id.clone()]),
+            )
+            .chain(self.transfers.iter().flat_map(|transfer| {
+                [transfer.from_stop_id.clone(), transfer.to_stop_id.clone()]
+                    .into_iter()
+                    .flatten()
+            }))
+            .chain(
+                self.stops_areas
+                    .iter()
+                    .map(|stop_area| stop_area.stop_id.clone()),
+            )
+            .chain(
+                self.location_groups_stops
+                    .iter()
+                    .map(|location_group_stop| location_group_stop.stop_id.clone()),
+            )
+            .collect();
+        let hierarchy = self.stop_hierarchy();
+        let mut pending: Vec<StopId> = used_stops.iter().cloned().collect();
+        while let Some(stop_id) = pending.pop() {
+            if let Some(parent_station) = hierarchy.parent_of(&stop_id) {
+                if used_stops.insert(parent_station.clone()) {
+                    pending.push(parent_station.clone());
+                }
+            }
+        }
+        let descendants: Vec<StopId> = used_stops
+            .iter()
+            .filter(|stop_id| hierarchy.parent_of(stop_id).is_none())
+            .flat_map(|stop_id| hierarchy.descendants_of(stop_id))
+            .collect();
+        used_stops.extend(descendants);
+        let stops: Vec<StopId> = self
+            .stops
+            .iter()
+            .filter(|stop| !used_stops.contains(&stop.stop_id))
+            .map(|stop| stop.stop_id.clone())
+            .collect();
+
+        let used_routes: HashSet<RouteId> = self
+            .trips
+            .iter()
+            .map(|trip| trip.route_id.clone())
+            .collect();
+        let routes: Vec<RouteId> = self
+            .routes
+            .iter()
+            .filter(|route| !used_routes.contains(&route.route_id))
+            .map(|route| route.route_id.clone())
+            .collect();
+
+        let used_services: HashSet<CalendarServiceId> = self
+            .trips
+            .iter()
+            .map(|trip| trip.service_id.clone())
+            .chain(
+                self.timeframes
+                    .iter()
+                    .map(|timeframe| timeframe.service_id.clone()),
+            )
+            .chain(
+                self.booking_rules
+                    .iter()
+                    .filter_map(|booking_rule| booking_rule.prior_notice_service_id.clone()),
+            )
+            .collect();
+        let services: Vec<CalendarServiceId> = self
+            .calendar
+            .iter()
+            .map(|calendar| calendar.service_id.clone())
+            .chain(
+                self.calendar_dates
+                    .iter()
+                    .map(|calendar_date| calendar_date.service_id.clone()),
+            )
+            .filter(|service_id| !used_services.contains(service_id))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        let used_shapes: HashSet<ShapeId> = self
+            .trips
+            .iter()
+            .filter_map(|trip| trip.shape_id.clone())
+            .collect();
+        let shapes: Vec<ShapeId> = self
+            .shapes
+            .iter()
+            .map(|shape| shape.key().0.clone())
+            .filter(|shape_id| !used_shapes.contains(shape_id))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        let mut orphans = Orphans {
+            stops,
+            routes,
+            trips,
+            services,
+            shapes,
+        };
+        orphans.sort();
+        orphans
+    }
+
+    /// Removes the records that are not used by the rest of the dataset, until none is left.
+    ///
+    /// The records referring to removed trips and routes, such as their stop times, frequencies,
+    /// transfers and attributions, are removed along with them. Returns all the removed orphans.
+    pub fn prune_orphans(&mut self) -> Orphans {
+        let mut removed = Orphans::default();
+        loop {
+            let orphans = self.find_orphans();
+            if orphans.is_empty() {
+                break;
+            }
+
+            let trip_ids: HashSet<&TripId> = orphans.trips.iter().collect();
+            self.stop_times
+                .retain(|(trip_id, _), _| !trip_ids.contains(trip_id));
+            self.frequencies
+                .retain(|(trip_id, _), _| !trip_ids.contains(trip_id));
+            self.trips.retain(|trip_id, _| !trip_ids.contains(trip_id));
+
+            let route_ids: HashSet<&RouteId> = orphans.routes.iter().collect();
+            self.routes_networks
+                .retain(|route_id, _| !route_ids.contains(route_id));
+            self.fare_rules.retain(|fare_rule| {
+                fare_rule
+                    .route_id
+                    .as_ref()
+                    .is_none_or(|route_id| !route_ids.contains(route_id))
+            });
+            self.routes
+                .retain(|route_id, _| !route_ids.contains(route_id));
+
+            self.transfers.retain(|transfer| {
+                [&transfer.from_trip_id, &transfer.to_trip_id]
+                    .into_iter()
+                    .flatten()
+                    .all(|trip_id| !trip_ids.contains(trip_id))
+                    && [&transfer.from_route_id, &transfer.to_route_id]
+                        .into_iter()
+                        .flatten()
+                        .all(|route_id| !route_ids.contains(route_id))
+            });
+            self.attributions.retain(|attribution| {
+                attribution
+                    .trip_id
+                    .as_ref()
+                    .is_none_or(|trip_id| !trip_ids.contains(trip_id))
+                    && attribution
+                        .route_id
+                        .as_ref()
+                        .is_none_or(|route_id| !route_ids.contains(route_id))
+            });
+
+            let stop_ids: HashSet<&StopId> = orphans.stops.iter().collect();
+            self.stops.retain(|stop_id, _| !stop_ids.contains(stop_id));
+
+            let service_ids: HashSet<&CalendarServiceId> = orphans.services.iter().collect();
+            self.calendar
+                .retain(|service_id, _| !service_ids.contains(service_id));
+            self.calendar_dates
+                .retain(|(service_id, _), _| !service_ids.contains(service_id));
+
+            let shape_ids: HashSet<&ShapeId> = orphans.shapes.iter().collect();
+            self.shapes
+                .retain(|(shape_id, _), _| !shape_ids.contains(shape_id));
+
+            removed.extend(orphans);
+        }
+        removed.sort();
+        removed
+    }
+}
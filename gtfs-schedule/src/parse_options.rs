@@ -0,0 +1,175 @@
+//! Options controlling how a dataset is parsed.
+
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use chrono::NaiveDate;
+
+use crate::schemas::{NaiveServiceTime, RouteId};
+use crate::{CsvBackend, GtfsFile};
+
+/// Options used by [`crate::Dataset::from_csv_with_options`].
+///
+/// By default, every file of the dataset is parsed, unknown columns are ignored, records with an
+/// already parsed key replace the previous record, and invalid URLs, emails, phone numbers and
+/// codes fail the parsing.
+///
+/// ```
+/// use gtfs_schedule::{GtfsFile, ParseOptions};
+///
+/// let options = ParseOptions::default().skip_tables(&[GtfsFile::Shapes, GtfsFile::Translations]);
+/// assert!(!options.loads(GtfsFile::Shapes));
+/// assert!(options.loads(GtfsFile::Stops));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    skipped_tables: HashSet<GtfsFile>,
+    pub(crate) deny_unknown_columns: bool,
+    pub(crate) duplicate_key_policy: DuplicateKeyPolicy,
+    pub(crate) keep_source_lines: bool,
+    pub(crate) lenient_contacts: bool,
+    pub(crate) lenient_codes: bool,
+    pub(crate) csv_backend: CsvBackend,
+    route_ids: Option<HashSet<String>>,
+    pub(crate) dates: Option<RangeInclusive<NaiveDate>>,
+    pub(crate) times: Option<RangeInclusive<NaiveServiceTime>>,
+}
+
+/// What to do with a record whose key was already parsed in the same table, see
+/// [`GtfsFile::key_columns`].
+///
+/// Keyed tables hold a single record per key, so one of the records has to be dropped unless
+/// parsing fails. Dropped records are reported in [`crate::ParseReport::duplicate_keys`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// Fail to parse the dataset.
+    Error,
+    /// Keep the record that was parsed first.
+    KeepFirst,
+    /// Keep the record that was parsed last.
+    #[default]
+    KeepLast,
+}
+
+impl ParseOptions {
+    /// Do not parse the given tables, in addition to the ones already skipped.
+    pub fn skip_tables(mut self, tables: &[GtfsFile]) -> Self {
+        self.skipped_tables.extend(tables.iter().copied());
+        self
+    }
+
+    /// Only parse the given tables, every other table is skipped.
+    pub fn only_tables(mut self, tables: &[GtfsFile]) -> Self {
+        self.skipped_tables = GtfsFile::ALL
+            .iter()
+            .filter(|file| !tables.contains(file))
+            .copied()
+            .collect();
+        self
+    }
+
+    /// Fail to parse files with columns that are not part of [`GtfsFile::columns`], instead of
+    /// reporting them in [`crate::ParseReport::unknown_columns`].
+    pub fn deny_unknown_columns(mut self) -> Self {
+        self.deny_unknown_columns = true;
+        self
+    }
+
+    /// Choose what to do with records whose key was already parsed.
+    pub fn duplicate_keys(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.duplicate_key_policy = policy;
+        self
+    }
+
+    /// Keep the line each record was parsed from, see [`crate::Dataset::provenance`].
+    ///
+    /// Lines are also used by [`crate::Dataset::to_csv`] to write records in their original order.
+    pub fn keep_source_lines(mut self) -> Self {
+        self.keep_source_lines = true;
+        self
+    }
+
+    /// Drop the optional URLs, emails and phone numbers that are not valid, e.g. `stop_url` or
+    /// `agency_email`, reporting them in [`crate::ParseReport::invalid_contacts`] instead of
+    /// failing to parse the dataset.
+    ///
+    /// Required values, such as `agency_url`, still fail the parsing.
+    pub fn lenient_contacts(mut self) -> Self {
+        self.lenient_contacts = true;
+        self
+    }
+
+    /// Parse the codes that are not part of the specification, e.g. a `route_type` of `99`, as
+    /// the `Unknown` variant of their enumeration, such as [`crate::schemas::RouteType::Unknown`],
+    /// instead of failing to parse the dataset.
+    ///
+    /// Those codes are written back as they were read by [`crate::Dataset::to_csv`].
+    pub fn lenient_codes(mut self) -> Self {
+        self.lenient_codes = true;
+        self
+    }
+
+    /// Only parse the records of the given routes: their trips, and the stop times, frequencies
+    /// and shapes of those trips.
+    ///
+    /// Records are dropped as they are read, so the tables that are not needed are never held in
+    /// memory. Other tables, such as stops.txt, are parsed in full.
+    pub fn only_routes(mut self, route_ids: &[RouteId]) -> Self {
+        self.route_ids = Some(
+            route_ids
+                .iter()
+                .map(|route_id| route_id.to_string())
+                .collect(),
+        );
+        self
+    }
+
+    /// Only parse the trips running on at least one of `dates`, along with their stop times,
+    /// frequencies and shapes, as [`ParseOptions::only_routes`].
+    ///
+    /// Services are kept in full, as calendar.txt is not split by date.
+    pub fn only_dates(mut self, dates: RangeInclusive<NaiveDate>) -> Self {
+        self.dates = Some(dates);
+        self
+    }
+
+    /// Only parse the stop times and frequencies within `times`, measured from the start of the
+    /// service day, e.g. `00:00:00..=47:59:59` along with [`ParseOptions::only_dates`] to only
+    /// keep the next 48 hours of service.
+    ///
+    /// A frequency is kept if the period from its start time to its end time overlaps `times`.
+    /// The stop times of a trip are kept as a whole if the trip runs within `times` at some
+    /// point between its first departure and its last arrival, so that trips are never cut
+    /// short. The stop times of a trip defined by frequencies.txt are kept as long as one of
+    /// its frequencies is, since they only give the duration of the trip. The stop times of a
+    /// trip without any time are kept, and so are the trips left without stop times.
+    pub fn only_times(mut self, times: RangeInclusive<NaiveServiceTime>) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    /// Choose the backend splitting files into records.
+    pub fn csv_backend(mut self, backend: CsvBackend) -> Self {
+        self.csv_backend = backend;
+        self
+    }
+
+    /// Returns `true` if some records are dropped while parsing, see
+    /// [`ParseOptions::only_routes`], [`ParseOptions::only_dates`] and
+    /// [`ParseOptions::only_times`].
+    pub(crate) fn filters_records(&self) -> bool {
+        self.route_ids.is_some() || self.dates.is_some() || self.times.is_some()
+    }
+
+    /// Returns `true` if the records of the route are parsed.
+    pub(crate) fn keeps_route(&self, route_id: &str) -> bool {
+        self.route_ids
+            .as_ref()
+            .is_none_or(|route_ids| route_ids.contains(route_id))
+    }
+
+    /// Returns `true` if the given table will be parsed.
+    pub fn loads(&self, table: GtfsFile) -> bool {
+        !self.skipped_tables.contains(&table)
+    }
+}
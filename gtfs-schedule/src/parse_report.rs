@@ -0,0 +1,84 @@
+//! Report of the tolerated issues met while parsing a dataset.
+
+use std::collections::BTreeMap;
+
+use crate::GtfsFile;
+
+/// Issues that did not prevent a dataset from being parsed, see [`crate::Dataset::parse_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseReport {
+    /// Columns that are not part of [`GtfsFile::columns`], by file, in the order of the header.
+    ///
+    /// Their values are ignored.
+    pub unknown_columns: BTreeMap<GtfsFile, Vec<String>>,
+    /// Records whose key was already parsed, in the order they were met.
+    ///
+    /// Only one of the records was kept, see [`crate::DuplicateKeyPolicy`].
+    pub duplicate_keys: Vec<DuplicateKey>,
+    /// Optional URLs, emails and phone numbers that are not valid, in the order they were met.
+    ///
+    /// They were dropped from their records, see [`crate::ParseOptions::lenient_contacts`].
+    pub invalid_contacts: Vec<InvalidContact>,
+    /// Files that were found under another name than [`GtfsFile::file_name`], with the name
+    /// found, such as `Stops.txt` or `stop_areas.txt`.
+    pub renamed_files: BTreeMap<GtfsFile, String>,
+    /// Columns that were found under a legacy name, in the order they were met.
+    ///
+    /// Their values were read as those of the column they stand for.
+    pub deprecated_columns: Vec<DeprecatedColumn>,
+}
+
+/// A record whose key was already parsed in the same table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKey {
+    /// The file of the record.
+    pub file: GtfsFile,
+    /// The values of the [`GtfsFile::key_columns`] of the record, separated by commas.
+    pub key: String,
+    /// The line of the record that was parsed before with the same key, if known.
+    pub previous_line: Option<u64>,
+    /// The line of the record, if known.
+    pub line: Option<u64>,
+}
+
+/// An optional URL, email or phone number that is not valid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidContact {
+    /// The file of the record.
+    pub file: GtfsFile,
+    /// The column of the value, e.g. `stop_url`.
+    pub column: String,
+    /// The value, as written.
+    pub value: String,
+    /// The line of the record, if known.
+    pub line: Option<u64>,
+}
+
+/// A column found under a legacy name, see [`GtfsFile::column_aliases`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecatedColumn {
+    /// The file of the column.
+    pub file: GtfsFile,
+    /// The name of the column, as written.
+    pub column: String,
+    /// The column it stands for.
+    pub replaced_by: String,
+}
+
+impl ParseReport {
+    /// Returns `true` if nothing was reported.
+    pub fn is_empty(&self) -> bool {
+        self.unknown_columns.is_empty()
+            && self.duplicate_keys.is_empty()
+            && self.invalid_contacts.is_empty()
+            && self.renamed_files.is_empty()
+            && self.deprecated_columns.is_empty()
+    }
+
+    /// Returns the unknown columns of a file.
+    pub fn unknown_columns_of(&self, file: GtfsFile) -> &[String] {
+        self.unknown_columns
+            .get(&file)
+            .map_or(&[], |columns| columns.as_slice())
+    }
+}
@@ -0,0 +1,179 @@
+//! Detection of the stopping patterns of routes.
+//!
+//! The main types are:
+//! - [`TripPattern`]: The trips of a route serving the same stops in the same order.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::schemas::{DirectionId, RouteId, ShapeId, Stop, StopId, TripId};
+use crate::Dataset;
+
+/// The trips of a route serving the same stops in the same order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TripPattern {
+    /// Identifies the route.
+    pub route_id: RouteId,
+    /// The stops served by the trips, in order.
+    pub stop_ids: Vec<StopId>,
+    /// The most common [`crate::schemas::Trip::trip_headsign`] of the trips.
+    pub trip_headsign: Option<String>,
+    /// The most common [`crate::schemas::Trip::direction_id`] of the trips.
+    pub direction_id: Option<DirectionId>,
+    /// The most common [`crate::schemas::Trip::shape_id`] of the trips.
+    pub shape_id: Option<ShapeId>,
+    /// The trips following the pattern, sorted by [`TripId`].
+    pub trip_ids: Vec<TripId>,
+}
+
+impl TripPattern {
+    /// Number of trips following the pattern.
+    pub fn trip_count(&self) -> usize {
+        self.trip_ids.len()
+    }
+}
+
+impl Dataset {
+    /// Groups the trips of a route by the ordered list of stops they serve.
+    ///
+    /// Stop times without a [`crate::schemas::StopTime::stop_id`] (flexible services) are ignored,
+    /// and so are trips without any stop. When the trips of a pattern disagree on their headsign,
+    /// direction or shape, the most common value is used, ties being broken by [`TripId`].
+    ///
+    /// Patterns are sorted by decreasing number of trips, then by stops.
+    pub fn trip_patterns(&self, route_id: &RouteId) -> Vec<TripPattern> {
+        let trip_ids: HashSet<TripId> = self
+            .trips
+            .iter()
+            .filter(|trip| trip.route_id == *route_id)
+            .map(|trip| trip.trip_id.clone())
+            .collect();
+
+        let mut stops: HashMap<TripId, Vec<(u32, StopId)>> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            if !trip_ids.contains(&stop_time.trip_id) {
+                continue;
+            }
+            if let Some(stop_id) = &stop_time.stop_id {
+                stops
+                    .entry(stop_time.trip_id.clone())
+                    .or_default()
+                    .push((stop_time.stop_sequence, stop_id.clone()));
+            }
+        }
+
+        let mut groups: HashMap<Vec<StopId>, Vec<TripId>> = HashMap::new();
+        for (trip_id, mut stops) in stops {
+            stops.sort_unstable_by_key(|(stop_sequence, _)| *stop_sequence);
+            let stop_ids = stops.into_iter().map(|(_, stop_id)| stop_id).collect();
+            groups.entry(stop_ids).or_default().push(trip_id);
+        }
+
+        let mut patterns: Vec<TripPattern> = groups
+            .into_iter()
+            .map(|(stop_ids, mut trip_ids)| {
+                trip_ids.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+                let trips: Vec<_> = trip_ids
+                    .iter()
+                    .filter_map(|trip_id| self.trips.get(trip_id).map(|trip| trip.clone()))
+                    .collect();
+
+                TripPattern {
+                    route_id: route_id.clone(),
+                    stop_ids,
+                    trip_headsign: most_common(trips.iter().map(|trip| trip.trip_headsign.clone())),
+                    direction_id: most_common(trips.iter().map(|trip| trip.direction_id.clone())),
+                    shape_id: most_common(trips.iter().map(|trip| trip.shape_id.clone())),
+                    trip_ids,
+                }
+            })
+            .collect();
+
+        patterns.sort_by(|a, b| {
+            b.trip_count().cmp(&a.trip_count()).then_with(|| {
+                let a = a.stop_ids.iter().map(|stop_id| stop_id.as_str());
+                a.cmp(b.stop_ids.iter().map(|stop_id| stop_id.as_str()))
+            })
+        });
+        patterns
+    }
+
+    /// Returns the stops served by the trips of a route, in the order they are served and
+    /// without duplicates.
+    ///
+    /// The stops of the most common pattern, see [`Dataset::trip_patterns`], come first. The
+    /// stops of the other patterns are inserted after the stop preceding them in their pattern,
+    /// patterns running in the opposite direction being read backwards.
+    pub fn stops_served_by_route(&self, route_id: &RouteId) -> Vec<StopId> {
+        let patterns = self.trip_patterns(route_id);
+        let direction_id = patterns
+            .first()
+            .and_then(|pattern| pattern.direction_id.clone());
+
+        let mut stop_ids: Vec<StopId> = vec![];
+        for pattern in &patterns {
+            let reversed = direction_id.is_some()
+                && pattern.direction_id.is_some()
+                && pattern.direction_id != direction_id;
+            let pattern_stop_ids: Vec<&StopId> = if reversed {
+                pattern.stop_ids.iter().rev().collect()
+            } else {
+                pattern.stop_ids.iter().collect()
+            };
+            let mut position = 0;
+            for stop_id in pattern_stop_ids {
+                match stop_ids.iter().position(|served| served == stop_id) {
+                    Some(served) => position = served + 1,
+                    None => {
+                        stop_ids.insert(position, stop_id.clone());
+                        position += 1;
+                    }
+                }
+            }
+        }
+        stop_ids
+    }
+
+    /// Returns the first and last stops of a trip, by stop sequence.
+    ///
+    /// Stop times without a [`crate::schemas::StopTime::stop_id`] (flexible services) are
+    /// ignored. `None` if the trip has no stop, or if one of its endpoints is not in stops.txt.
+    pub fn trip_endpoints(&self, trip_id: &TripId) -> Option<(Stop, Stop)> {
+        let mut first: Option<(u32, StopId)> = None;
+        let mut last: Option<(u32, StopId)> = None;
+        for stop_time in self.iter_stop_times_for_trip(trip_id) {
+            let Some(stop_id) = &stop_time.stop_id else {
+                continue;
+            };
+            let stop_sequence = stop_time.stop_sequence;
+            if first
+                .as_ref()
+                .is_none_or(|(first, _)| stop_sequence < *first)
+            {
+                first = Some((stop_sequence, stop_id.clone()));
+            }
+            if last.as_ref().is_none_or(|(last, _)| stop_sequence > *last) {
+                last = Some((stop_sequence, stop_id.clone()));
+            }
+        }
+
+        let stop = |(_, stop_id): (u32, StopId)| self.stops.get(&stop_id).map(|stop| stop.clone());
+        Some((stop(first?)?, stop(last?)?))
+    }
+}
+
+/// Returns the most common of the given values, the first one in case of a tie.
+fn most_common<T: PartialEq>(values: impl Iterator<Item = Option<T>>) -> Option<T> {
+    let mut counts: Vec<(T, usize)> = vec![];
+    for value in values.flatten() {
+        match counts.iter_mut().find(|(counted, _)| *counted == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+
+    let max = counts.iter().map(|(_, count)| *count).max()?;
+    counts
+        .into_iter()
+        .find(|(_, count)| *count == max)
+        .map(|(value, _)| value)
+}
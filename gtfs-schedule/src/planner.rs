@@ -0,0 +1,290 @@
+//! Journey planning between stops, built on top of compiled timetables.
+//!
+//! The main types are:
+//! - [`PlanOptions`]: The constraints of a journey planned by [`Dataset::plan`].
+//! - [`Journey`]: A way to travel from a stop to another.
+//! - [`JourneyLeg`]: A ride in a vehicle, or a walk between two stops.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+use geo::{HaversineDistance, Point};
+
+use crate::schemas::{StopId, TransferType, TripId};
+use crate::{CompiledTimetable, Dataset};
+
+/// Walking speed used for walking transfers, in meters per second.
+const WALKING_SPEED: f64 = 1.3;
+
+/// The constraints of a journey planned by [`Dataset::plan`].
+///
+/// The default options only allow the transfers listed in transfers.txt.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PlanOptions {
+    /// Allow walking between stops at most this far apart, in meters.
+    pub max_walking_distance: Option<f64>,
+    /// Walking speed, in meters per second, 1.3 if not set.
+    pub walking_speed: Option<f64>,
+}
+
+/// A way to travel from a stop to another, see [`Dataset::plan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Journey {
+    /// When the journey starts, the time of its first leg if any.
+    pub departure: NaiveDateTime,
+    /// When the journey ends, the time of its last leg if any.
+    pub arrival: NaiveDateTime,
+    /// The legs of the journey, in order.
+    pub legs: Vec<JourneyLeg>,
+}
+
+/// A part of a [`Journey`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JourneyLeg {
+    /// A ride in the vehicle of a trip.
+    Ride {
+        /// Identifies the trip.
+        trip_id: TripId,
+        /// The stop where the vehicle is boarded.
+        from_stop_id: StopId,
+        /// The stop where the vehicle is left.
+        to_stop_id: StopId,
+        /// When the vehicle leaves [`JourneyLeg::Ride::from_stop_id`].
+        departure: NaiveDateTime,
+        /// When the vehicle reaches [`JourneyLeg::Ride::to_stop_id`].
+        arrival: NaiveDateTime,
+    },
+    /// A transfer between two stops, from transfers.txt or on foot.
+    Walk {
+        /// The stop left.
+        from_stop_id: StopId,
+        /// The stop reached.
+        to_stop_id: StopId,
+        /// When the transfer starts.
+        departure: NaiveDateTime,
+        /// When the transfer ends.
+        arrival: NaiveDateTime,
+    },
+}
+
+/// How a stop was reached while scanning connections.
+#[derive(Debug, Clone, Copy)]
+enum Reached {
+    /// The stop is the origin of the journey.
+    Origin,
+    /// By riding a trip, from the connection it was boarded at to the one leaving it.
+    Ride { enter: usize, exit: usize },
+    /// By walking from another stop.
+    Walk { from: u32, duration: u32 },
+}
+
+impl Dataset {
+    /// Plans the journey from `from_stop` reaching `to_stop` the earliest, leaving at or after
+    /// `departure`.
+    ///
+    /// This is a baseline earliest arrival Connection Scan over the trips of the day of
+    /// `departure` and the days around it, see [`Dataset::compile_timetable`]. Transfers between
+    /// two stops come from transfers.txt, ignoring the ones specific to routes or trips, and
+    /// from walking when [`PlanOptions::max_walking_distance`] is set, which compares every pair
+    /// of stops. Transfers are not chained, and changing vehicles at a stop takes no time.
+    ///
+    /// Times are local times of the feed, ignoring daylight saving time changes. Returns `None`
+    /// if a stop is unknown, or if `to_stop` can not be reached.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(from_stop = %from_stop, to_stop = %to_stop))
+    )]
+    pub fn plan(
+        &self,
+        from_stop: &StopId,
+        to_stop: &StopId,
+        departure: NaiveDateTime,
+        options: &PlanOptions,
+    ) -> Option<Journey> {
+        let date = departure.date();
+        let timetable = self.compile_timetable(date.pred_opt()?..=date.succ_opt()?);
+        let start = timetable.start_date.and_time(NaiveTime::MIN);
+        let time = |seconds: u32| start + Duration::seconds(i64::from(seconds));
+
+        let from = timetable.stop_index(from_stop)?;
+        let to = timetable.stop_index(to_stop)?;
+        let departure_time = u32::try_from((departure - start).num_seconds()).ok()?;
+        let footpaths = self.footpaths(&timetable, options);
+
+        let mut earliest = vec![u32::MAX; timetable.stops.len()];
+        let mut reached: Vec<Option<Reached>> = vec![None; timetable.stops.len()];
+        let mut boarded: Vec<Option<usize>> = vec![None; timetable.trips.len()];
+        earliest[from as usize] = departure_time;
+        reached[from as usize] = Some(Reached::Origin);
+        let walk = |stop: u32, earliest: &mut [u32], reached: &mut [Option<Reached>]| {
+            for &(to_stop, duration) in footpaths.get(&stop).into_iter().flatten() {
+                let arrival = earliest[stop as usize].saturating_add(duration);
+                if arrival < earliest[to_stop as usize] {
+                    earliest[to_stop as usize] = arrival;
+                    reached[to_stop as usize] = Some(Reached::Walk {
+                        from: stop,
+                        duration,
+                    });
+                }
+            }
+        };
+        walk(from, &mut earliest, &mut reached);
+
+        let first = timetable
+            .connections
+            .partition_point(|connection| connection.departure_time < departure_time);
+        for (index, connection) in timetable.connections.iter().enumerate().skip(first) {
+            if connection.departure_time >= earliest[to as usize] {
+                break;
+            }
+            let trip = connection.trip as usize;
+            if boarded[trip].is_none()
+                && connection.pickup
+                && earliest[connection.departure_stop as usize] <= connection.departure_time
+            {
+                boarded[trip] = Some(index);
+            }
+            let Some(enter) = boarded[trip] else {
+                continue;
+            };
+            let arrival_stop = connection.arrival_stop as usize;
+            if connection.drop_off && connection.arrival_time < earliest[arrival_stop] {
+                earliest[arrival_stop] = connection.arrival_time;
+                reached[arrival_stop] = Some(Reached::Ride { enter, exit: index });
+                walk(connection.arrival_stop, &mut earliest, &mut reached);
+            }
+        }
+
+        let mut legs = vec![];
+        let mut stop = to;
+        for _ in 0..timetable.stops.len() {
+            match reached[stop as usize]? {
+                Reached::Origin => break,
+                Reached::Ride { enter, exit } => {
+                    let enter = &timetable.connections[enter];
+                    let exit = &timetable.connections[exit];
+                    legs.push(JourneyLeg::Ride {
+                        trip_id: timetable.trips[enter.trip as usize].trip_id.clone(),
+                        from_stop_id: timetable.stops[enter.departure_stop as usize].clone(),
+                        to_stop_id: timetable.stops[exit.arrival_stop as usize].clone(),
+                        departure: time(enter.departure_time),
+                        arrival: time(exit.arrival_time),
+                    });
+                    stop = enter.departure_stop;
+                }
+                Reached::Walk { from, duration } => {
+                    let arrival = earliest[stop as usize];
+                    legs.push(JourneyLeg::Walk {
+                        from_stop_id: timetable.stops[from as usize].clone(),
+                        to_stop_id: timetable.stops[stop as usize].clone(),
+                        departure: time(arrival - duration),
+                        arrival: time(arrival),
+                    });
+                    stop = from;
+                }
+            }
+        }
+        legs.reverse();
+
+        let (departure, arrival) = match (legs.first(), legs.last()) {
+            (Some(first), Some(last)) => (first.departure(), last.arrival()),
+            _ => (departure, departure),
+        };
+        Some(Journey {
+            departure,
+            arrival,
+            legs,
+        })
+    }
+
+    /// Returns the transfers between two stops allowed by `options`, by index of the stop left.
+    ///
+    /// Each transfer is the index of the stop reached and its duration, in seconds.
+    fn footpaths(
+        &self,
+        timetable: &CompiledTimetable,
+        options: &PlanOptions,
+    ) -> HashMap<u32, Vec<(u32, u32)>> {
+        let mut durations: HashMap<(u32, u32), Option<u32>> = HashMap::new();
+
+        if let Some(max_distance) = options.max_walking_distance {
+            let speed = options.walking_speed.unwrap_or(WALKING_SPEED);
+            let points: Vec<(u32, Point)> = self
+                .stops
+                .iter()
+                .filter_map(|stop| {
+                    let coord = stop.stop_coord.as_ref()?;
+                    Some((timetable.stop_index(&stop.stop_id)?, Point::from(**coord)))
+                })
+                .collect();
+            for (from, from_point) in &points {
+                for (to, to_point) in &points {
+                    let distance = from_point.haversine_distance(to_point);
+                    if from != to && distance <= max_distance {
+                        let duration = (distance / speed).ceil() as u32;
+                        durations.insert((*from, *to), Some(duration));
+                    }
+                }
+            }
+        }
+
+        for transfer in &self.transfers {
+            if transfer.from_route_id.is_some()
+                || transfer.to_route_id.is_some()
+                || transfer.from_trip_id.is_some()
+                || transfer.to_trip_id.is_some()
+            {
+                continue;
+            }
+            let (Some(from), Some(to)) = (
+                transfer
+                    .from_stop_id
+                    .as_ref()
+                    .and_then(|stop_id| timetable.stop_index(stop_id)),
+                transfer
+                    .to_stop_id
+                    .as_ref()
+                    .and_then(|stop_id| timetable.stop_index(stop_id)),
+            ) else {
+                continue;
+            };
+            if from == to {
+                continue;
+            }
+            let duration = match transfer.transfer_type {
+                TransferType::RecommendedTransferPoint
+                | TransferType::TimedTransferPoint
+                | TransferType::MinimumTimeTransferPoint => {
+                    Some(transfer.min_transfer_time.unwrap_or(0))
+                }
+                TransferType::NoTransferPossible => None,
+                TransferType::InSeatTransfer | TransferType::NoInSeatTransfer => continue,
+            };
+            durations.insert((from, to), duration);
+        }
+
+        let mut footpaths: HashMap<u32, Vec<(u32, u32)>> = HashMap::new();
+        for ((from, to), duration) in durations {
+            if let Some(duration) = duration {
+                footpaths.entry(from).or_default().push((to, duration));
+            }
+        }
+        footpaths
+    }
+}
+
+impl JourneyLeg {
+    /// When the leg starts.
+    pub fn departure(&self) -> NaiveDateTime {
+        match self {
+            Self::Ride { departure, .. } | Self::Walk { departure, .. } => *departure,
+        }
+    }
+
+    /// When the leg ends.
+    pub fn arrival(&self) -> NaiveDateTime {
+        match self {
+            Self::Ride { arrival, .. } | Self::Walk { arrival, .. } => *arrival,
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! Location of the records of a dataset in the files they were parsed from.
+//!
+//! The main types are:
+//! - [`Provenance`]: The file and line a record was parsed from.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+
+use crate::error::Error;
+use crate::schemas::{
+    AreaId, BookingRuleId, CalendarServiceId, FareId, FareMediaId, FareProductId, GtfsTable,
+    LevelId, LocationGroupId, NaiveServiceTime, NetworkId, PathwayId, RouteId, Schema, ShapeId,
+    StopId, TripId,
+};
+use crate::{Dataset, GtfsFile};
+
+/// The file and line a record was parsed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Provenance {
+    /// The file of the record.
+    pub file: GtfsFile,
+    /// The line of the record in the file, the header being on line 1.
+    pub line: u64,
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+/// Lines of the records of a dataset, see [`crate::ParseOptions::keep_source_lines`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SourceLines {
+    /// Line of the record of each key of the keyed tables.
+    pub(crate) keyed: HashMap<SourceKey, u64>,
+    /// Line of each record of the tables stored as lists, by index.
+    pub(crate) listed: HashMap<GtfsFile, Vec<Option<u64>>>,
+    /// Indices of the records of the tables stored as lists, by hash of their values, see
+    /// [`value_hash`].
+    pub(crate) listed_indices: HashMap<(GtfsFile, u64), Vec<usize>>,
+}
+
+impl SourceLines {
+    /// Records the index of the last record parsed into a table stored as a list.
+    pub(crate) fn push_listed(&mut self, record: &Schema, line: Option<u64>) {
+        let file = record.file();
+        let lines = self.listed.entry(file).or_default();
+        // The records of feed_info.txt replace each other.
+        if file == GtfsFile::FeedInfo {
+            lines.clear();
+            self.listed_indices
+                .retain(|(listed_file, _), _| *listed_file != file);
+        }
+        let index = lines.len();
+        lines.push(line);
+        if let Some(value) = record_value(record) {
+            self.listed_indices
+                .entry((file, value_hash(file, &value)))
+                .or_default()
+                .push(index);
+        }
+    }
+
+    /// Forgets the record removed at `index` from a table stored as a list, shifting the
+    /// indices of the records after it.
+    pub(crate) fn remove_listed(&mut self, file: GtfsFile, index: usize) {
+        if let Some(lines) = self.listed.get_mut(&file) {
+            if index < lines.len() {
+                lines.remove(index);
+            }
+        }
+        for ((listed_file, _), indices) in self.listed_indices.iter_mut() {
+            if *listed_file != file {
+                continue;
+            }
+            indices.retain(|listed| *listed != index);
+            for listed in indices.iter_mut().filter(|listed| **listed > index) {
+                *listed -= 1;
+            }
+        }
+        self.listed_indices.retain(|_, indices| !indices.is_empty());
+    }
+}
+
+/// A part of the key of a record, see [`SourceKey`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum KeyPart {
+    /// An id, e.g. a stop_id.
+    Id(Arc<str>),
+    /// A stop_sequence or a shape_pt_sequence.
+    Sequence(u32),
+    /// The date of calendar_dates.txt.
+    Date(NaiveDate),
+    /// The start_time of frequencies.txt.
+    Time(NaiveServiceTime),
+    /// A missing optional value, or the missing second part of a single-column key.
+    None,
+}
+
+/// The key of a record of a keyed table, along with its file, see [`SourceLines::keyed`].
+pub(crate) type SourceKey = (GtfsFile, KeyPart, KeyPart);
+
+/// The values making up the keys of the keyed tables.
+pub(crate) trait ToKeyPart {
+    /// Returns the value as a part of a key.
+    fn key_part(&self) -> KeyPart;
+}
+
+macro_rules! id_key_parts {
+    ($($id:ty),* $(,)?) => {
+        $(impl ToKeyPart for $id {
+            fn key_part(&self) -> KeyPart {
+                KeyPart::Id(self.0.clone())
+            }
+        })*
+    };
+}
+
+id_key_parts!(
+    AreaId,
+    BookingRuleId,
+    CalendarServiceId,
+    FareId,
+    FareMediaId,
+    FareProductId,
+    LevelId,
+    LocationGroupId,
+    NetworkId,
+    PathwayId,
+    RouteId,
+    ShapeId,
+    StopId,
+    TripId,
+);
+
+impl ToKeyPart for u32 {
+    fn key_part(&self) -> KeyPart {
+        KeyPart::Sequence(*self)
+    }
+}
+
+impl ToKeyPart for NaiveDate {
+    fn key_part(&self) -> KeyPart {
+        KeyPart::Date(*self)
+    }
+}
+
+impl ToKeyPart for NaiveServiceTime {
+    fn key_part(&self) -> KeyPart {
+        KeyPart::Time(*self)
+    }
+}
+
+impl<T: ToKeyPart> ToKeyPart for Option<T> {
+    fn key_part(&self) -> KeyPart {
+        self.as_ref().map_or(KeyPart::None, ToKeyPart::key_part)
+    }
+}
+
+/// The keys of the keyed tables, see [`crate::schemas::GtfsTable::key`].
+pub(crate) trait ToSourceKey {
+    /// Returns the key along with the file of its table.
+    fn source_key(&self, file: GtfsFile) -> SourceKey;
+}
+
+impl<T: ToKeyPart> ToSourceKey for T {
+    fn source_key(&self, file: GtfsFile) -> SourceKey {
+        (file, self.key_part(), KeyPart::None)
+    }
+}
+
+impl<A: ToKeyPart, B: ToKeyPart> ToSourceKey for (A, B) {
+    fn source_key(&self, file: GtfsFile) -> SourceKey {
+        (file, self.0.key_part(), self.1.key_part())
+    }
+}
+
+impl Dataset {
+    /// Returns the file and line a record was parsed from.
+    ///
+    /// Lines are only kept when parsing with [`crate::ParseOptions::keep_source_lines`]. Records
+    /// are looked up by key in keyed tables, and by value in the tables stored as lists, such as
+    /// [`Dataset::transfers`], where identical records resolve to the first of them; see
+    /// [`Dataset::listed_provenance`] to look them up by index instead. Records added after
+    /// parsing have no provenance.
+    pub fn provenance(&self, record: &Schema) -> Option<Provenance> {
+        let file = record.file();
+        let line = match record_source_key(record) {
+            Some(source_key) => self.source_lines.keyed.get(&source_key).copied(),
+            None => self.listed_lines(record).first().copied(),
+        }?;
+
+        Some(Provenance { file, line })
+    }
+
+    /// Returns the file and line the record at `index` of a table stored as a list was parsed
+    /// from, e.g. `dataset.transfers[index]`.
+    ///
+    /// See [`Dataset::provenance`], `None` for the keyed tables.
+    pub fn listed_provenance(&self, file: GtfsFile, index: usize) -> Option<Provenance> {
+        let line = self.source_lines.listed.get(&file)?.get(index).copied()??;
+        Some(Provenance { file, line })
+    }
+
+    /// Returns the lines of the records of a table stored as a list equal to `record`, in the
+    /// order of the table.
+    fn listed_lines(&self, record: &Schema) -> Vec<u64> {
+        let file = record.file();
+        let (Some(lines), Some(value)) =
+            (self.source_lines.listed.get(&file), record_value(record))
+        else {
+            return vec![];
+        };
+        let mut indices = self
+            .source_lines
+            .listed_indices
+            .get(&(file, value_hash(file, &value)))
+            .cloned()
+            .unwrap_or_default();
+        indices.sort_unstable();
+        indices
+            .into_iter()
+            // Hashes may collide, and tables may change after parsing.
+            .filter(|index| {
+                self.listed_record(file, *index)
+                    .is_some_and(|listed| record_value(&listed).as_ref() == Some(&value))
+            })
+            .filter_map(|index| lines.get(index).copied().flatten())
+            .collect()
+    }
+
+    /// Returns the files and lines of the records involved in a validation error, sorted.
+    ///
+    /// See [`Dataset::provenance`], parse errors have no provenance.
+    pub fn error_provenance(&self, error: &Error) -> Vec<Provenance> {
+        let records = match error {
+            Error::ParseError(_) => return vec![],
+            Error::SchemaValidationError(error) => std::slice::from_ref(&error.schema_instance),
+            Error::DatasetValidationError(error) => error.schema_instances.as_slice(),
+        };
+
+        // Identical records of a table stored as a list are given their lines in turn.
+        let mut occurrences: HashMap<String, usize> = HashMap::new();
+        let mut provenances: Vec<Provenance> = records
+            .iter()
+            .filter_map(|record| {
+                if record_source_key(record).is_some() {
+                    return self.provenance(record);
+                }
+                let occurrence = occurrences.entry(record_value(record)?).or_default();
+                let line = self.listed_lines(record).get(*occurrence).copied();
+                *occurrence += 1;
+                Some(Provenance {
+                    file: record.file(),
+                    line: line?,
+                })
+            })
+            .collect();
+        provenances.sort();
+        provenances.dedup();
+        provenances
+    }
+
+    /// Returns the line of a record of a keyed table, see [`Dataset::provenance`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn keyed_source_line(&self, record: &Schema) -> Option<u64> {
+        self.source_lines
+            .keyed
+            .get(&record_source_key(record)?)
+            .copied()
+    }
+}
+
+/// Hashes the serialized value of a record of a table stored as a list, along with its file.
+fn value_hash(file: GtfsFile, value: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (file, value).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Serializes a record to compare it by value, the keys of its maps being sorted.
+fn record_value(record: &Schema) -> Option<String> {
+    serde_json::to_value(record)
+        .ok()
+        .map(|value| value.to_string())
+}
+
+/// Returns the key of a record, `None` for the records of tables stored as lists.
+///
+/// The keys must be those used by [`Dataset`] for its keyed tables.
+fn record_source_key(record: &Schema) -> Option<SourceKey> {
+    let file = record.file();
+    let source_key = match record {
+        Schema::Stop(stop) => stop.key().source_key(file),
+        Schema::Route(route) => route.key().source_key(file),
+        Schema::Trip(trip) => trip.key().source_key(file),
+        Schema::StopTime(stop_time) => stop_time.key().source_key(file),
+        Schema::Calendar(calendar) => calendar.key().source_key(file),
+        Schema::CalendarDate(calendar_date) => calendar_date.key().source_key(file),
+        Schema::FareAttribute(fare_attribute) => fare_attribute.key().source_key(file),
+        Schema::FareMedia(fare_media) => fare_media.key().source_key(file),
+        Schema::FareProduct(fare_product) => fare_product.key().source_key(file),
+        Schema::Area(area) => area.key().source_key(file),
+        Schema::Network(network) => network.key().source_key(file),
+        Schema::RouteNetwork(route_network) => route_network.key().source_key(file),
+        Schema::Shape(shape) => shape.key().source_key(file),
+        Schema::Frequency(frequency) => frequency.key().source_key(file),
+        Schema::Pathway(pathway) => pathway.key().source_key(file),
+        Schema::Level(level) => level.key().source_key(file),
+        Schema::LocationGroup(location_group) => location_group.key().source_key(file),
+        Schema::BookingRule(booking_rule) => booking_rule.key().source_key(file),
+        Schema::Agency(_)
+        | Schema::FareRule(_)
+        | Schema::Timeframe(_)
+        | Schema::FareLegRule(_)
+        | Schema::FareTransferRule(_)
+        | Schema::StopArea(_)
+        | Schema::Transfer(_)
+        | Schema::LocationGroupStop(_)
+        | Schema::Translation(_)
+        | Schema::FeedInfo(_)
+        | Schema::Attribution(_) => return None,
+    };
+
+    Some(source_key)
+}
@@ -0,0 +1,65 @@
+//! The organization publishing a dataset, as described by feed_info.txt.
+//!
+//! The main type is:
+//! - [`Publisher`]: Who publishes a dataset, and how to reach them.
+
+use email_address::EmailAddress;
+use url::Url;
+
+use crate::schemas::Agency;
+use crate::Dataset;
+
+/// Who publishes a dataset, and how to reach them, see [`Dataset::publisher`].
+#[derive(Debug, Clone)]
+pub struct Publisher<'a> {
+    /// The [`crate::schemas::FeedInfo::feed_publisher_name`].
+    pub name: &'a str,
+    /// The [`crate::schemas::FeedInfo::feed_publisher_url`].
+    pub url: &'a Url,
+    /// The [`crate::schemas::FeedInfo::feed_contact_email`], a technical contact for
+    /// consumers of the dataset.
+    pub contact_email: Option<&'a EmailAddress>,
+    /// The [`crate::schemas::FeedInfo::feed_contact_url`], a technical contact for consumers
+    /// of the dataset.
+    pub contact_url: Option<&'a Url>,
+    /// The agency publishing the dataset itself, matched by name or URL, if any.
+    pub agency: Option<&'a Agency>,
+}
+
+impl Publisher<'_> {
+    /// Whether the dataset gives a way to reach the publisher about the dataset itself, as
+    /// recommended.
+    pub fn has_contact(&self) -> bool {
+        self.contact_email.is_some() || self.contact_url.is_some()
+    }
+}
+
+/// Compares two URLs, ignoring a trailing slash.
+pub(crate) fn same_url(a: &Url, b: &Url) -> bool {
+    a.as_str().trim_end_matches('/') == b.as_str().trim_end_matches('/')
+}
+
+impl Dataset {
+    /// Returns the publisher of the dataset, `None` without feed_info.txt record.
+    ///
+    /// The publisher may be one of the agencies, in which case [`Publisher::agency`] is the
+    /// agency sharing its name, ignoring case, or its URL.
+    pub fn publisher(&self) -> Option<Publisher<'_>> {
+        let feed_info = self.feed_info.as_ref()?;
+        let agency = self.agencies.iter().find(|agency| {
+            agency
+                .agency_name
+                .trim()
+                .eq_ignore_ascii_case(feed_info.feed_publisher_name.trim())
+                || same_url(&agency.agency_url, &feed_info.feed_publisher_url)
+        });
+
+        Some(Publisher {
+            name: &feed_info.feed_publisher_name,
+            url: &feed_info.feed_publisher_url,
+            contact_email: feed_info.feed_contact_email.as_ref(),
+            contact_url: feed_info.feed_contact_url.as_ref(),
+            agency,
+        })
+    }
+}
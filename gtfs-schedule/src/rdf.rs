@@ -0,0 +1,651 @@
+//! Export of datasets as RDF graphs, using the Linked GTFS vocabulary.
+//!
+//! The main types are:
+//! - [`RdfExporter`]: Writes a dataset as Turtle or N-Triples.
+//! - [`RdfFormat`]: The syntax of the written graph.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use crate::error::{ParseError, ParseErrorKind, Result};
+use crate::schemas::{
+    AgencyId, CalendarDayService, DirectionId, ExactTimes, ExceptionType, LocationType, RouteType,
+    StopId,
+};
+use crate::Dataset;
+
+/// Prefixes of the vocabularies used, with their namespace.
+const PREFIXES: &[(&str, &str)] = &[
+    ("gtfs", "http://vocab.gtfs.org/terms#"),
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+    ("foaf", "http://xmlns.com/foaf/0.1/"),
+    ("dct", "http://purl.org/dc/terms/"),
+    ("geo", "http://www.w3.org/2003/01/geo/wgs84_pos#"),
+    ("schema", "http://schema.org/"),
+];
+
+/// The syntax of a graph written by [`RdfExporter`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RdfFormat {
+    /// Turtle, grouping the triples of each resource and abbreviating IRIs with prefixes.
+    #[default]
+    Turtle,
+    /// N-Triples, a triple per line with full IRIs, convenient to concatenate or stream.
+    NTriples,
+}
+
+/// Writes a dataset as an RDF graph using the
+/// [Linked GTFS](https://github.com/OpenTransport/linked-gtfs) vocabulary.
+///
+/// Each record of agency.txt, stops.txt, routes.txt, trips.txt, stop_times.txt, calendar.txt,
+/// calendar_dates.txt, shapes.txt, frequencies.txt, transfers.txt and feed_info.txt becomes a
+/// resource, named by an IRI built from the base IRI, the kind of the record and its key,
+/// e.g. `https://example.com/gtfs/stop/BEATTY_AIRPORT`. Foreign keys become links between
+/// resources. The other files have no counterpart in the vocabulary and are left out.
+///
+/// Resources are written sorted by key, so exporting the same dataset twice yields the same
+/// document.
+///
+/// ```
+/// use gtfs_schedule::{Dataset, RdfExporter, RdfFormat};
+///
+/// let dataset = Dataset::default();
+/// let mut document = vec![];
+/// RdfExporter::new("https://example.com/gtfs/")
+///     .format(RdfFormat::NTriples)
+///     .export(&dataset, &mut document)
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RdfExporter {
+    base_iri: String,
+    format: RdfFormat,
+}
+
+impl RdfExporter {
+    /// Names resources after `base_iri`, which should end with `/` or `#`.
+    pub fn new(base_iri: &str) -> Self {
+        Self {
+            base_iri: base_iri.to_string(),
+            format: RdfFormat::default(),
+        }
+    }
+
+    /// Choose the syntax of the graph, Turtle by default.
+    pub fn format(mut self, format: RdfFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Formats the IRI of a resource from the kind of its record and the parts of its key.
+    fn iri(&self, kind: &str, key: &[&str]) -> String {
+        let mut iri = format!("{}{}", self.base_iri, kind);
+        for part in key {
+            iri.push('/');
+            percent_encode(part, &mut iri);
+        }
+        iri
+    }
+
+    /// Writes `dataset` to `writer`.
+    pub fn export(&self, dataset: &Dataset, writer: &mut dyn Write) -> Result<()> {
+        let mut graph = GraphWriter {
+            writer,
+            format: self.format,
+        };
+        graph.start()?;
+
+        // Routes without agency belong to the single agency of the dataset.
+        let agency_iri = |agency_id: Option<&AgencyId>| match agency_id {
+            Some(agency_id) => Some(self.iri("agency", &[agency_id.as_str()])),
+            None => match dataset.agencies.as_slice() {
+                [agency] => Some(
+                    self.iri(
+                        "agency",
+                        &[agency
+                            .agency_id
+                            .as_ref()
+                            .map_or(agency.agency_name.as_str(), |id| id.as_str())],
+                    ),
+                ),
+                _ => None,
+            },
+        };
+
+        for agency in &dataset.agencies {
+            let Some(subject) = agency_iri(agency.agency_id.as_ref()) else {
+                continue;
+            };
+            let mut properties = vec![
+                ("rdf:type", Term::Name("gtfs:Agency")),
+                ("foaf:name", Term::string(&agency.agency_name)),
+                ("foaf:page", Term::Iri(agency.agency_url.to_string())),
+                ("gtfs:timeZone", Term::string(agency.agency_timezone.name())),
+            ];
+            if let Some(lang) = &agency.agency_lang {
+                properties.push(("dct:language", Term::string(lang.as_str())));
+            }
+            if let Some(phone) = &agency.agency_phone {
+                properties.push(("foaf:phone", Term::string(phone.as_str())));
+            }
+            if let Some(fare_url) = &agency.agency_fare_url {
+                properties.push(("gtfs:fareUrl", Term::Iri(fare_url.to_string())));
+            }
+            if let Some(email) = &agency.agency_email {
+                properties.push(("foaf:mbox", Term::Iri(format!("mailto:{}", email))));
+            }
+            graph.resource(&subject, &properties)?;
+        }
+
+        let mut stop_ids: Vec<StopId> = dataset.stops.iter().map(|s| s.stop_id.clone()).collect();
+        stop_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        for stop_id in stop_ids {
+            let Some(stop) = dataset.stops.get(&stop_id) else {
+                continue;
+            };
+            let class = match stop.location_type {
+                Some(LocationType::Station) => "gtfs:Station",
+                _ => "gtfs:Stop",
+            };
+            let mut properties = vec![("rdf:type", Term::Name(class))];
+            if let Some(code) = &stop.stop_code {
+                properties.push(("gtfs:code", Term::string(code)));
+            }
+            if let Some(name) = &stop.stop_name {
+                properties.push(("foaf:name", Term::string(name)));
+            }
+            if let Some(desc) = &stop.stop_desc {
+                properties.push(("dct:description", Term::string(desc)));
+            }
+            if let Some(coord) = &stop.stop_coord {
+                properties.push(("geo:lat", Term::typed(coord.y, "xsd:double")));
+                properties.push(("geo:long", Term::typed(coord.x, "xsd:double")));
+            }
+            if let Some(zone_id) = &stop.zone_id {
+                properties.push(("gtfs:zone", Term::string(zone_id.as_str())));
+            }
+            if let Some(url) = &stop.stop_url {
+                properties.push(("foaf:page", Term::Iri(url.to_string())));
+            }
+            if let Some(parent_station) = &stop.parent_station {
+                let parent = self.iri("stop", &[parent_station.as_str()]);
+                properties.push(("gtfs:parentStation", Term::Iri(parent)));
+            }
+            if let Some(timezone) = &stop.stop_timezone {
+                properties.push(("gtfs:timeZone", Term::string(timezone.name())));
+            }
+            if let Some(platform_code) = &stop.platform_code {
+                properties.push(("gtfs:platformCode", Term::string(platform_code)));
+            }
+            graph.resource(&self.iri("stop", &[stop_id.as_str()]), &properties)?;
+        }
+
+        let mut routes: Vec<_> = dataset.routes.iter().map(|r| r.value().clone()).collect();
+        routes.sort_by(|a, b| a.route_id.as_str().cmp(b.route_id.as_str()));
+        for route in routes {
+            let mut properties = vec![("rdf:type", Term::Name("gtfs:Route"))];
+            if let Some(agency) = agency_iri(route.agency_id.as_ref()) {
+                properties.push(("gtfs:agency", Term::Iri(agency)));
+            }
+            if let Some(short_name) = &route.route_short_name {
+                properties.push(("gtfs:shortName", Term::string(short_name)));
+            }
+            if let Some(long_name) = &route.route_long_name {
+                properties.push(("gtfs:longName", Term::string(long_name)));
+            }
+            if let Some(desc) = &route.route_desc {
+                properties.push(("dct:description", Term::string(desc)));
+            }
+            properties.push(("gtfs:routeType", route_type(&route.route_type)));
+            if let Some(url) = &route.route_url {
+                properties.push(("foaf:page", Term::Iri(url.to_string())));
+            }
+            if let Some(color) = &route.route_color {
+                properties.push(("gtfs:color", Term::string(color)));
+            }
+            if let Some(text_color) = &route.route_text_color {
+                properties.push(("gtfs:textColor", Term::string(text_color)));
+            }
+            graph.resource(&self.iri("route", &[route.route_id.as_str()]), &properties)?;
+        }
+
+        let mut trips: Vec<_> = dataset.trips.iter().map(|t| t.value().clone()).collect();
+        trips.sort_by(|a, b| a.trip_id.as_str().cmp(b.trip_id.as_str()));
+        for trip in trips {
+            let mut properties = vec![
+                ("rdf:type", Term::Name("gtfs:Trip")),
+                (
+                    "gtfs:route",
+                    Term::Iri(self.iri("route", &[trip.route_id.as_str()])),
+                ),
+                (
+                    "gtfs:service",
+                    Term::Iri(self.iri("service", &[trip.service_id.as_str()])),
+                ),
+            ];
+            if let Some(headsign) = &trip.trip_headsign {
+                properties.push(("gtfs:headsign", Term::string(headsign)));
+            }
+            if let Some(short_name) = &trip.trip_short_name {
+                properties.push(("gtfs:shortName", Term::string(short_name)));
+            }
+            if let Some(direction_id) = &trip.direction_id {
+                let opposite = matches!(direction_id, DirectionId::OppositeDirection);
+                properties.push(("gtfs:direction", Term::typed(opposite, "xsd:boolean")));
+            }
+            if let Some(block_id) = &trip.block_id {
+                properties.push(("gtfs:block", Term::string(block_id.as_str())));
+            }
+            if let Some(shape_id) = &trip.shape_id {
+                let shape = self.iri("shape", &[shape_id.as_str()]);
+                properties.push(("gtfs:shape", Term::Iri(shape)));
+            }
+            graph.resource(&self.iri("trip", &[trip.trip_id.as_str()]), &properties)?;
+        }
+
+        let mut stop_times: Vec<_> = dataset
+            .stop_times
+            .iter()
+            .map(|s| s.value().clone())
+            .collect();
+        stop_times.sort_by(|a, b| {
+            (a.trip_id.as_str(), a.stop_sequence).cmp(&(b.trip_id.as_str(), b.stop_sequence))
+        });
+        for stop_time in stop_times {
+            let sequence = stop_time.stop_sequence.to_string();
+            let mut properties = vec![
+                ("rdf:type", Term::Name("gtfs:StopTime")),
+                (
+                    "gtfs:trip",
+                    Term::Iri(self.iri("trip", &[stop_time.trip_id.as_str()])),
+                ),
+                (
+                    "gtfs:stopSequence",
+                    Term::typed(&sequence, "xsd:nonNegativeInteger"),
+                ),
+            ];
+            if let Some(stop_id) = &stop_time.stop_id {
+                let stop = self.iri("stop", &[stop_id.as_str()]);
+                properties.push(("gtfs:stop", Term::Iri(stop)));
+            }
+            if let Some(arrival_time) = stop_time.arrival_time {
+                properties.push(("gtfs:arrivalTime", Term::string(arrival_time.to_string())));
+            }
+            if let Some(departure_time) = stop_time.departure_time {
+                let departure_time = departure_time.to_string();
+                properties.push(("gtfs:departureTime", Term::string(departure_time)));
+            }
+            if let Some(headsign) = &stop_time.stop_headsign {
+                properties.push(("gtfs:headsign", Term::string(headsign)));
+            }
+            if let Some(pickup_type) = &stop_time.pickup_type {
+                properties.push(("gtfs:pickupType", pickup_drop_off_type(pickup_type.code())));
+            }
+            if let Some(drop_off_type) = &stop_time.drop_off_type {
+                let drop_off_type = pickup_drop_off_type(drop_off_type.code());
+                properties.push(("gtfs:dropOffType", drop_off_type));
+            }
+            if let Some(distance) = stop_time.shape_dist_traveled {
+                properties.push(("gtfs:distanceTraveled", Term::typed(distance, "xsd:double")));
+            }
+            let subject = self.iri("stop_time", &[stop_time.trip_id.as_str(), &sequence]);
+            graph.resource(&subject, &properties)?;
+        }
+
+        let mut calendars: Vec<_> = dataset.calendar.iter().map(|c| c.value().clone()).collect();
+        calendars.sort_by(|a, b| a.service_id.as_str().cmp(b.service_id.as_str()));
+        let mut calendar_dates: Vec<_> = dataset
+            .calendar_dates
+            .iter()
+            .map(|c| c.value().clone())
+            .collect();
+        calendar_dates
+            .sort_by(|a, b| (a.service_id.as_str(), a.date).cmp(&(b.service_id.as_str(), b.date)));
+        let mut service_ids: Vec<&str> = calendars
+            .iter()
+            .map(|calendar| calendar.service_id.as_str())
+            .chain(calendar_dates.iter().map(|date| date.service_id.as_str()))
+            .collect();
+        service_ids.sort_unstable();
+        service_ids.dedup();
+        for service_id in service_ids {
+            let rules = calendars
+                .iter()
+                .filter(|calendar| calendar.service_id.as_str() == service_id)
+                .map(|_| self.iri("calendar_rule", &[service_id]))
+                .chain(
+                    calendar_dates
+                        .iter()
+                        .filter(|date| date.service_id.as_str() == service_id)
+                        .map(|date| {
+                            let date = date.date.format("%Y%m%d").to_string();
+                            self.iri("calendar_date_rule", &[service_id, &date])
+                        }),
+                );
+            let properties: Vec<_> = [("rdf:type", Term::Name("gtfs:Service"))]
+                .into_iter()
+                .chain(rules.map(|rule| ("gtfs:serviceRule", Term::Iri(rule))))
+                .collect();
+            graph.resource(&self.iri("service", &[service_id]), &properties)?;
+        }
+        for calendar in &calendars {
+            let service_id = calendar.service_id.as_str();
+            let mut properties = vec![
+                ("rdf:type", Term::Name("gtfs:CalendarRule")),
+                (
+                    "gtfs:service",
+                    Term::Iri(self.iri("service", &[service_id])),
+                ),
+            ];
+            for (day, service) in [
+                ("gtfs:monday", &calendar.monday),
+                ("gtfs:tuesday", &calendar.tuesday),
+                ("gtfs:wednesday", &calendar.wednesday),
+                ("gtfs:thursday", &calendar.thursday),
+                ("gtfs:friday", &calendar.friday),
+                ("gtfs:saturday", &calendar.saturday),
+                ("gtfs:sunday", &calendar.sunday),
+            ] {
+                let available = matches!(service, CalendarDayService::Available);
+                properties.push((day, Term::typed(available, "xsd:boolean")));
+            }
+            properties.push(("schema:startDate", date(calendar.start_date)));
+            properties.push(("schema:endDate", date(calendar.end_date)));
+            graph.resource(&self.iri("calendar_rule", &[service_id]), &properties)?;
+        }
+        for calendar_date in &calendar_dates {
+            let service_id = calendar_date.service_id.as_str();
+            let added = matches!(calendar_date.exception_type, ExceptionType::Added);
+            let properties = [
+                ("rdf:type", Term::Name("gtfs:CalendarDateRule")),
+                (
+                    "gtfs:service",
+                    Term::Iri(self.iri("service", &[service_id])),
+                ),
+                ("dct:date", date(calendar_date.date)),
+                ("gtfs:dateAddition", Term::typed(added, "xsd:boolean")),
+            ];
+            let day = calendar_date.date.format("%Y%m%d").to_string();
+            let subject = self.iri("calendar_date_rule", &[service_id, &day]);
+            graph.resource(&subject, &properties)?;
+        }
+
+        let mut shape_points: Vec<_> = dataset.shapes.iter().map(|s| s.value().clone()).collect();
+        shape_points.sort_by(|a, b| {
+            (a.shape_id.as_str(), a.shape_pt_sequence)
+                .cmp(&(b.shape_id.as_str(), b.shape_pt_sequence))
+        });
+        for points in shape_points.chunk_by(|a, b| a.shape_id == b.shape_id) {
+            let shape_id = points[0].shape_id.as_str();
+            let properties: Vec<_> = [("rdf:type", Term::Name("gtfs:Shape"))]
+                .into_iter()
+                .chain(points.iter().map(|point| {
+                    let sequence = point.shape_pt_sequence.to_string();
+                    let point = self.iri("shape_point", &[shape_id, &sequence]);
+                    ("gtfs:shapePoint", Term::Iri(point))
+                }))
+                .collect();
+            graph.resource(&self.iri("shape", &[shape_id]), &properties)?;
+            for point in points {
+                let sequence = point.shape_pt_sequence.to_string();
+                let mut properties = vec![
+                    ("rdf:type", Term::Name("gtfs:ShapePoint")),
+                    ("geo:lat", Term::typed(point.shape_pt.y, "xsd:double")),
+                    ("geo:long", Term::typed(point.shape_pt.x, "xsd:double")),
+                    (
+                        "gtfs:pointSequence",
+                        Term::typed(&sequence, "xsd:nonNegativeInteger"),
+                    ),
+                ];
+                if let Some(distance) = point.shape_dist_traveled {
+                    properties.push(("gtfs:distanceTraveled", Term::typed(distance, "xsd:double")));
+                }
+                let subject = self.iri("shape_point", &[shape_id, &sequence]);
+                graph.resource(&subject, &properties)?;
+            }
+        }
+
+        let mut frequencies: Vec<_> = dataset
+            .frequencies
+            .iter()
+            .map(|f| f.value().clone())
+            .collect();
+        frequencies.sort_by(|a, b| {
+            (a.trip_id.as_str(), a.start_time).cmp(&(b.trip_id.as_str(), b.start_time))
+        });
+        for frequency in frequencies {
+            let start_time = frequency.start_time.to_string();
+            let exact = frequency.service_type() == ExactTimes::Exact;
+            let properties = [
+                ("rdf:type", Term::Name("gtfs:Frequency")),
+                (
+                    "gtfs:trip",
+                    Term::Iri(self.iri("trip", &[frequency.trip_id.as_str()])),
+                ),
+                ("gtfs:startTime", Term::string(start_time.clone())),
+                ("gtfs:endTime", Term::string(frequency.end_time.to_string())),
+                (
+                    "gtfs:headwaySeconds",
+                    Term::typed(frequency.headway_secs.as_secs(), "xsd:nonNegativeInteger"),
+                ),
+                ("gtfs:exactTimes", Term::typed(exact, "xsd:boolean")),
+            ];
+            let subject = self.iri("frequency", &[frequency.trip_id.as_str(), &start_time]);
+            graph.resource(&subject, &properties)?;
+        }
+
+        // Transfers have no key, so they are named after their position.
+        for (index, transfer) in dataset.transfers.iter().enumerate() {
+            let mut properties = vec![("rdf:type", Term::Name("gtfs:TransferRule"))];
+            if let Some(from_stop_id) = &transfer.from_stop_id {
+                let stop = self.iri("stop", &[from_stop_id.as_str()]);
+                properties.push(("gtfs:originStop", Term::Iri(stop)));
+            }
+            if let Some(to_stop_id) = &transfer.to_stop_id {
+                let stop = self.iri("stop", &[to_stop_id.as_str()]);
+                properties.push(("gtfs:destinationStop", Term::Iri(stop)));
+            }
+            let transfer_type = transfer.transfer_type.clone() as u8;
+            properties.push((
+                "gtfs:transferType",
+                Term::typed(transfer_type, "xsd:nonNegativeInteger"),
+            ));
+            if let Some(min_transfer_time) = transfer.min_transfer_time {
+                properties.push((
+                    "gtfs:minimumTransferTime",
+                    Term::typed(min_transfer_time, "xsd:nonNegativeInteger"),
+                ));
+            }
+            let subject = self.iri("transfer", &[&index.to_string()]);
+            graph.resource(&subject, &properties)?;
+        }
+
+        if let Some(feed_info) = &dataset.feed_info {
+            let mut properties = vec![
+                ("rdf:type", Term::Name("gtfs:Feed")),
+                (
+                    "dct:publisher",
+                    Term::string(&feed_info.feed_publisher_name),
+                ),
+                (
+                    "foaf:page",
+                    Term::Iri(feed_info.feed_publisher_url.to_string()),
+                ),
+                ("dct:language", Term::string(feed_info.feed_lang.as_str())),
+            ];
+            if let Some(start_date) = feed_info.feed_start_date {
+                properties.push(("schema:startDate", date(start_date)));
+            }
+            if let Some(end_date) = feed_info.feed_end_date {
+                properties.push(("schema:endDate", date(end_date)));
+            }
+            if let Some(version) = &feed_info.feed_version {
+                properties.push(("schema:version", Term::string(version)));
+            }
+            graph.resource(&self.iri("feed", &[]), &properties)?;
+        }
+
+        graph.writer.flush().map_err(io_error)
+    }
+}
+
+/// The object of a triple.
+enum Term<'a> {
+    /// A resource, by its full IRI.
+    Iri(String),
+    /// A resource of a vocabulary, by its prefixed name.
+    Name(&'static str),
+    /// A literal, with the prefixed name of its datatype, a plain string without.
+    Literal(Cow<'a, str>, Option<&'static str>),
+}
+
+impl<'a> Term<'a> {
+    fn string(value: impl Into<Cow<'a, str>>) -> Self {
+        Term::Literal(value.into(), None)
+    }
+
+    fn typed(value: impl ToString, datatype: &'static str) -> Self {
+        Term::Literal(Cow::Owned(value.to_string()), Some(datatype))
+    }
+}
+
+/// Formats a date as an `xsd:date` literal.
+fn date(date: chrono::NaiveDate) -> Term<'static> {
+    Term::typed(date.format("%Y-%m-%d"), "xsd:date")
+}
+
+/// The term of a route type: an individual of the vocabulary for the basic types, the code
+/// otherwise.
+fn route_type(route_type: &RouteType) -> Term<'static> {
+    match route_type {
+        RouteType::LightRail => Term::Name("gtfs:LightRail"),
+        RouteType::Subway => Term::Name("gtfs:Subway"),
+        RouteType::Rail => Term::Name("gtfs:Rail"),
+        RouteType::Bus => Term::Name("gtfs:Bus"),
+        RouteType::Ferry => Term::Name("gtfs:Ferry"),
+        RouteType::CableTram => Term::Name("gtfs:CableCar"),
+        RouteType::AerialLift => Term::Name("gtfs:Gondola"),
+        RouteType::Funicular => Term::Name("gtfs:Funicular"),
+        route_type => Term::typed(route_type.code(), "xsd:nonNegativeInteger"),
+    }
+}
+
+/// The term of a pickup or drop off type, given by its code.
+fn pickup_drop_off_type(code: u8) -> Term<'static> {
+    match code {
+        0 => Term::Name("gtfs:Regular"),
+        1 => Term::Name("gtfs:NotAvailable"),
+        2 => Term::Name("gtfs:MustPhone"),
+        3 => Term::Name("gtfs:MustCoordinateWithDriver"),
+        code => Term::typed(code, "xsd:nonNegativeInteger"),
+    }
+}
+
+/// Appends `text` to `iri`, percent-encoding the characters that are not unreserved.
+fn percent_encode(text: &str, iri: &mut String) {
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            iri.push(byte as char);
+        } else {
+            let _ = write!(iri, "%{:02X}", byte);
+        }
+    }
+}
+
+/// Escapes the characters of a literal that are reserved in Turtle and N-Triples.
+fn escape_literal(text: &str) -> Cow<'_, str> {
+    if !text.contains(['\\', '"', '\n', '\r', '\t']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Expands the prefixed name of a vocabulary term into a full IRI.
+fn expand(name: &str) -> String {
+    let (prefix, local) = name.split_once(':').unwrap_or(("", name));
+    let namespace = PREFIXES
+        .iter()
+        .find(|(candidate, _)| *candidate == prefix)
+        .map_or("", |(_, namespace)| *namespace);
+    format!("{}{}", namespace, local)
+}
+
+fn io_error(e: std::io::Error) -> crate::error::Error {
+    ParseError::from(ParseErrorKind::from(e)).into()
+}
+
+/// Writes the resources of a graph in a given syntax.
+struct GraphWriter<'w> {
+    writer: &'w mut dyn Write,
+    format: RdfFormat,
+}
+
+impl GraphWriter<'_> {
+    /// Writes what precedes the resources, the prefixes in Turtle.
+    fn start(&mut self) -> Result<()> {
+        if self.format == RdfFormat::Turtle {
+            for (prefix, namespace) in PREFIXES {
+                writeln!(self.writer, "@prefix {}: <{}> .", prefix, namespace).map_err(io_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the triples describing a resource.
+    fn resource(&mut self, subject: &str, properties: &[(&str, Term)]) -> Result<()> {
+        let object = |term: &Term| match (term, self.format) {
+            (Term::Iri(iri), _) => format!("<{}>", iri),
+            (Term::Name(name), RdfFormat::Turtle) => name.to_string(),
+            (Term::Name(name), RdfFormat::NTriples) => format!("<{}>", expand(name)),
+            (Term::Literal(value, None), _) => format!("\"{}\"", escape_literal(value)),
+            (Term::Literal(value, Some(datatype)), RdfFormat::Turtle) => {
+                format!("\"{}\"^^{}", escape_literal(value), datatype)
+            }
+            (Term::Literal(value, Some(datatype)), RdfFormat::NTriples) => {
+                format!("\"{}\"^^<{}>", escape_literal(value), expand(datatype))
+            }
+        };
+
+        let mut text = String::new();
+        match self.format {
+            RdfFormat::Turtle => {
+                let _ = write!(text, "\n<{}>", subject);
+                for (index, (predicate, term)) in properties.iter().enumerate() {
+                    let predicate = if *predicate == "rdf:type" {
+                        "a"
+                    } else {
+                        predicate
+                    };
+                    let separator = if index == 0 { " " } else { " ;\n    " };
+                    let _ = write!(text, "{}{} {}", separator, predicate, object(term));
+                }
+                text.push_str(" .\n");
+            }
+            RdfFormat::NTriples => {
+                for (predicate, term) in properties {
+                    let _ = writeln!(
+                        text,
+                        "<{}> <{}> {} .",
+                        subject,
+                        expand(predicate),
+                        object(term)
+                    );
+                }
+            }
+        }
+        self.writer.write_all(text.as_bytes()).map_err(io_error)
+    }
+}
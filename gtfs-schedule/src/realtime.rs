@@ -0,0 +1,377 @@
+//! Linking of GTFS Realtime messages to a dataset.
+//!
+//! GTFS Realtime feeds are Protocol Buffers messages. Rather than depending on a particular
+//! decoder, this module defines the parts of the messages needed to link them to a dataset, with
+//! the names of the specification, so that messages decoded with any library can be mapped to
+//! them.
+//!
+//! The main types are:
+//! - [`RealtimeBinding`]: Resolves the references of realtime messages against a dataset.
+//! - [`UnmatchedEntity`]: A reference of a realtime message that is not part of the dataset.
+//! - [`EffectiveStopTime`]: A stop time of a trip, with the realtime updates applied.
+
+use std::collections::HashSet;
+
+use chrono::{Duration, NaiveDate, TimeZone};
+
+use crate::schemas::{AgencyId, NaiveServiceTime, RouteId, StopId, StopTime, Trip, TripId};
+use crate::Dataset;
+
+/// Identifies a trip, see `TripDescriptor` in the GTFS Realtime specification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TripDescriptor {
+    pub trip_id: Option<String>,
+    pub route_id: Option<String>,
+    pub direction_id: Option<u32>,
+    /// Start time of the trip, as `HH:MM:SS`.
+    pub start_time: Option<String>,
+    /// Service date of the trip, as `YYYYMMDD`.
+    pub start_date: Option<String>,
+}
+
+/// The timing of an arrival or a departure, see `StopTimeEvent` in the GTFS Realtime
+/// specification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StopTimeEvent {
+    /// Delay, in seconds, relative to the schedule.
+    pub delay: Option<i32>,
+    /// Absolute time, in seconds since the Unix epoch.
+    pub time: Option<i64>,
+}
+
+/// The relation between a [`StopTimeUpdate`] and the schedule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StopTimeScheduleRelationship {
+    /// The vehicle proceeds as scheduled, with the given timings.
+    #[default]
+    Scheduled,
+    /// The stop is skipped.
+    Skipped,
+    /// There is no realtime data for the stop.
+    NoData,
+}
+
+/// Realtime update of a stop time, see `StopTimeUpdate` in the GTFS Realtime specification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StopTimeUpdate {
+    pub stop_sequence: Option<u32>,
+    pub stop_id: Option<String>,
+    pub arrival: Option<StopTimeEvent>,
+    pub departure: Option<StopTimeEvent>,
+    pub schedule_relationship: StopTimeScheduleRelationship,
+}
+
+/// Realtime progress of a trip, see `TripUpdate` in the GTFS Realtime specification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TripUpdate {
+    pub trip: TripDescriptor,
+    /// Updates of the stop times, ordered by stop sequence.
+    pub stop_time_update: Vec<StopTimeUpdate>,
+    /// Delay, in seconds, of the trip where no stop time update applies.
+    pub delay: Option<i32>,
+}
+
+/// Realtime position of a vehicle, see `VehiclePosition` in the GTFS Realtime specification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VehiclePosition {
+    pub trip: Option<TripDescriptor>,
+    pub stop_id: Option<String>,
+}
+
+/// The entities affected by an alert, see `EntitySelector` in the GTFS Realtime specification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntitySelector {
+    pub agency_id: Option<String>,
+    pub route_id: Option<String>,
+    pub trip: Option<TripDescriptor>,
+    pub stop_id: Option<String>,
+}
+
+/// A service alert, see `Alert` in the GTFS Realtime specification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Alert {
+    pub informed_entity: Vec<EntitySelector>,
+}
+
+/// A reference of a realtime message that is not part of the dataset.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum UnmatchedEntity {
+    Agency(AgencyId),
+    Route(RouteId),
+    Trip(TripId),
+    Stop(StopId),
+    /// A stop time update referencing a stop sequence, or a stop, that the trip does not serve.
+    StopTime {
+        trip_id: TripId,
+        stop_sequence: Option<u32>,
+        stop_id: Option<StopId>,
+    },
+}
+
+/// A stop time of a trip, with the realtime updates applied, see
+/// [`RealtimeBinding::effective_schedule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveStopTime {
+    pub stop_sequence: u32,
+    pub stop_id: Option<StopId>,
+    /// The arrival time of the schedule.
+    pub scheduled_arrival_time: Option<NaiveServiceTime>,
+    /// The departure time of the schedule.
+    pub scheduled_departure_time: Option<NaiveServiceTime>,
+    /// The arrival time, with the delay applied.
+    pub arrival_time: Option<NaiveServiceTime>,
+    /// The departure time, with the delay applied.
+    pub departure_time: Option<NaiveServiceTime>,
+    /// The delay, in seconds, applying to the departure from the stop, if known.
+    pub delay: Option<i32>,
+    /// Whether the stop is skipped.
+    pub skipped: bool,
+}
+
+/// Resolves the references of realtime messages against a dataset.
+pub struct RealtimeBinding<'a> {
+    dataset: &'a Dataset,
+}
+
+impl<'a> RealtimeBinding<'a> {
+    /// Links realtime messages to `dataset`.
+    pub fn new(dataset: &'a Dataset) -> Self {
+        Self { dataset }
+    }
+
+    /// Returns the trip a descriptor refers to, if it is part of the dataset.
+    ///
+    /// Trips are resolved by `trip_id` only, descriptors without one are not resolved.
+    pub fn resolve_trip(&self, descriptor: &TripDescriptor) -> Option<Trip> {
+        let trip_id = TripId::from(descriptor.trip_id.as_deref()?);
+        self.dataset
+            .trips
+            .get(&trip_id)
+            .map(|trip| trip.value().clone())
+    }
+
+    /// Lists the references of a trip update that are not part of the dataset.
+    pub fn unmatched_in_trip_update(&self, trip_update: &TripUpdate) -> Vec<UnmatchedEntity> {
+        let mut unmatched = self.unmatched_in_trip(&trip_update.trip);
+        let Some(trip_id) = self
+            .resolve_trip(&trip_update.trip)
+            .map(|trip| trip.trip_id)
+        else {
+            return unmatched;
+        };
+
+        let stop_times = self.stop_times(&trip_id);
+        for update in &trip_update.stop_time_update {
+            let stop_id = update.stop_id.as_deref().map(StopId::from);
+            if let Some(stop_id) = &stop_id {
+                if !self.dataset.stops.contains_key(stop_id) {
+                    unmatched.push(UnmatchedEntity::Stop(stop_id.clone()));
+                    continue;
+                }
+            }
+            if update_index(&stop_times, update).is_none() {
+                unmatched.push(UnmatchedEntity::StopTime {
+                    trip_id: trip_id.clone(),
+                    stop_sequence: update.stop_sequence,
+                    stop_id,
+                });
+            }
+        }
+
+        unmatched
+    }
+
+    /// Lists the references of a vehicle position that are not part of the dataset.
+    pub fn unmatched_in_vehicle_position(
+        &self,
+        position: &VehiclePosition,
+    ) -> Vec<UnmatchedEntity> {
+        let mut unmatched = position
+            .trip
+            .as_ref()
+            .map(|trip| self.unmatched_in_trip(trip))
+            .unwrap_or_default();
+        unmatched.extend(self.unmatched_stop(position.stop_id.as_deref()));
+        unmatched
+    }
+
+    /// Lists the references of an alert that are not part of the dataset, each once, in the order
+    /// of the informed entities.
+    pub fn unmatched_in_alert(&self, alert: &Alert) -> Vec<UnmatchedEntity> {
+        let mut unmatched = vec![];
+        for entity in &alert.informed_entity {
+            if let Some(agency_id) = entity.agency_id.as_deref().map(AgencyId::from) {
+                let known = self
+                    .dataset
+                    .agencies
+                    .iter()
+                    .any(|agency| agency.agency_id.as_ref() == Some(&agency_id));
+                if !known {
+                    unmatched.push(UnmatchedEntity::Agency(agency_id));
+                }
+            }
+            if let Some(route_id) = entity.route_id.as_deref().map(RouteId::from) {
+                if !self.dataset.routes.contains_key(&route_id) {
+                    unmatched.push(UnmatchedEntity::Route(route_id));
+                }
+            }
+            if let Some(trip) = &entity.trip {
+                unmatched.extend(self.unmatched_in_trip(trip));
+            }
+            unmatched.extend(self.unmatched_stop(entity.stop_id.as_deref()));
+        }
+
+        let mut seen = HashSet::new();
+        unmatched.retain(|entity| seen.insert(entity.clone()));
+        unmatched
+    }
+
+    /// Returns the stop times of the trip of a trip update, with the realtime updates applied.
+    ///
+    /// Delays propagate along the trip: a stop without update gets the departure delay of the
+    /// previous update, or the delay of the trip before the first one. An update without
+    /// departure uses its arrival delay for the departure. Absolute times are only used when the
+    /// descriptor has a `start_date`, and are read in the timezone of the first agency. Times
+    /// whose delay is unknown are the scheduled ones.
+    ///
+    /// Returns `None` if the trip is not part of the dataset.
+    pub fn effective_schedule(&self, trip_update: &TripUpdate) -> Option<Vec<EffectiveStopTime>> {
+        let trip = self.resolve_trip(&trip_update.trip)?;
+        let stop_times = self.stop_times(&trip.trip_id);
+        let service_day_start = self.service_day_start(&trip_update.trip);
+
+        let mut updates: Vec<Option<&StopTimeUpdate>> = vec![None; stop_times.len()];
+        for update in &trip_update.stop_time_update {
+            if let Some(index) = update_index(&stop_times, update) {
+                updates[index] = Some(update);
+            }
+        }
+
+        let mut delay = trip_update.delay;
+        let schedule = stop_times
+            .iter()
+            .zip(updates)
+            .map(|(stop_time, update)| {
+                let mut arrival_delay = delay;
+                let mut skipped = false;
+                if let Some(update) = update {
+                    match update.schedule_relationship {
+                        StopTimeScheduleRelationship::Scheduled => {
+                            let delay_of = |event: Option<StopTimeEvent>, scheduled| {
+                                event_delay(event?, scheduled, service_day_start)
+                            };
+                            let arrival = delay_of(update.arrival, stop_time.arrival_time);
+                            let departure = delay_of(update.departure, stop_time.departure_time);
+                            arrival_delay = arrival.or(departure).or(delay);
+                            delay = departure.or(arrival).or(delay);
+                        }
+                        StopTimeScheduleRelationship::Skipped => skipped = true,
+                        StopTimeScheduleRelationship::NoData => {
+                            arrival_delay = None;
+                            delay = None;
+                        }
+                    }
+                }
+
+                EffectiveStopTime {
+                    stop_sequence: stop_time.stop_sequence,
+                    stop_id: stop_time.stop_id.clone(),
+                    scheduled_arrival_time: stop_time.arrival_time,
+                    scheduled_departure_time: stop_time.departure_time,
+                    arrival_time: delayed(stop_time.arrival_time, arrival_delay),
+                    departure_time: delayed(stop_time.departure_time, delay),
+                    delay,
+                    skipped,
+                }
+            })
+            .collect();
+
+        Some(schedule)
+    }
+
+    /// Lists the trip and route of a descriptor that are not part of the dataset.
+    fn unmatched_in_trip(&self, descriptor: &TripDescriptor) -> Vec<UnmatchedEntity> {
+        let mut unmatched = vec![];
+        if let Some(trip_id) = descriptor.trip_id.as_deref().map(TripId::from) {
+            if !self.dataset.trips.contains_key(&trip_id) {
+                unmatched.push(UnmatchedEntity::Trip(trip_id));
+            }
+        }
+        if let Some(route_id) = descriptor.route_id.as_deref().map(RouteId::from) {
+            if !self.dataset.routes.contains_key(&route_id) {
+                unmatched.push(UnmatchedEntity::Route(route_id));
+            }
+        }
+        unmatched
+    }
+
+    /// Returns the stop, if it is not part of the dataset.
+    fn unmatched_stop(&self, stop_id: Option<&str>) -> Option<UnmatchedEntity> {
+        let stop_id = StopId::from(stop_id?);
+        (!self.dataset.stops.contains_key(&stop_id)).then_some(UnmatchedEntity::Stop(stop_id))
+    }
+
+    /// Returns the stop times of a trip, ordered by stop sequence.
+    fn stop_times(&self, trip_id: &TripId) -> Vec<StopTime> {
+        let mut stop_times: Vec<StopTime> = self
+            .dataset
+            .stop_times
+            .iter()
+            .filter(|stop_time| stop_time.trip_id == *trip_id)
+            .map(|stop_time| stop_time.value().clone())
+            .collect();
+        stop_times.sort_by_key(|stop_time| stop_time.stop_sequence);
+        stop_times
+    }
+
+    /// Returns the Unix time of the start of the service day of a trip, if it can be known.
+    ///
+    /// The service day starts 12 hours before noon, which is midnight except on the days when
+    /// daylight saving time starts or ends.
+    fn service_day_start(&self, descriptor: &TripDescriptor) -> Option<i64> {
+        let date = NaiveDate::parse_from_str(descriptor.start_date.as_deref()?, "%Y%m%d").ok()?;
+        let timezone = self.dataset.agencies.first()?.agency_timezone;
+        let noon = timezone
+            .from_local_datetime(&date.and_hms_opt(12, 0, 0)?)
+            .single()?;
+
+        Some((noon - Duration::hours(12)).timestamp())
+    }
+}
+
+/// Returns the index of the stop time an update applies to.
+///
+/// Updates are matched by stop sequence, or by stop when the stop sequence is missing.
+fn update_index(stop_times: &[StopTime], update: &StopTimeUpdate) -> Option<usize> {
+    match (update.stop_sequence, update.stop_id.as_deref()) {
+        (Some(stop_sequence), _) => stop_times
+            .iter()
+            .position(|stop_time| stop_time.stop_sequence == stop_sequence),
+        (None, Some(stop_id)) => {
+            let stop_id = StopId::from(stop_id);
+            stop_times
+                .iter()
+                .position(|stop_time| stop_time.stop_id.as_ref() == Some(&stop_id))
+        }
+        (None, None) => None,
+    }
+}
+
+/// Returns the delay of an event relative to the scheduled time.
+fn event_delay(
+    event: StopTimeEvent,
+    scheduled: Option<NaiveServiceTime>,
+    service_day_start: Option<i64>,
+) -> Option<i32> {
+    if let Some(delay) = event.delay {
+        return Some(delay);
+    }
+    let scheduled = service_day_start? + i64::from(scheduled?.as_secs());
+    i32::try_from(event.time? - scheduled).ok()
+}
+
+/// Applies a delay to a scheduled time.
+fn delayed(time: Option<NaiveServiceTime>, delay: Option<i32>) -> Option<NaiveServiceTime> {
+    let secs = i64::from(time?.as_secs()) + i64::from(delay.unwrap_or(0));
+    Some(NaiveServiceTime::from_secs(u32::try_from(secs).ok()?))
+}
@@ -0,0 +1,165 @@
+//! Backends splitting the files of a dataset into records.
+//!
+//! The main types are:
+//! - [`RecordReader`]: Reads the header and records of a file.
+//! - [`CsvBackend`]: The backend used by [`crate::Dataset::from_csv_with_options`].
+//!
+//! Backends only split files into [`csv::StringRecord`], which are then deserialized into the
+//! records of [`crate::schemas`] the same way whatever the backend.
+
+use std::io::Read;
+
+use crate::error::{ParseError, ParseErrorKind, Result};
+
+/// Reads the header and records of a file, see [`CsvBackend`].
+pub trait RecordReader {
+    /// Reads the header of the file. Must be called once, before any record is read.
+    fn read_header(&mut self) -> Result<csv::StringRecord>;
+
+    /// Reads the next record into `record`, with its position in the file. Returns `false` once
+    /// every record was read.
+    fn read_record(&mut self, record: &mut csv::StringRecord) -> Result<bool>;
+}
+
+impl<R: Read> RecordReader for csv::Reader<R> {
+    fn read_header(&mut self) -> Result<csv::StringRecord> {
+        Ok(self
+            .headers()
+            .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?
+            .clone())
+    }
+
+    fn read_record(&mut self, record: &mut csv::StringRecord) -> Result<bool> {
+        Ok(csv::Reader::read_record(self, record)
+            .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?)
+    }
+}
+
+/// The backend splitting files into records, see [`crate::ParseOptions::csv_backend`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CsvBackend {
+    /// The reader of the `csv` crate.
+    #[default]
+    Csv,
+    /// A push parser over `csv-core`, reusing its buffers from one record to the next.
+    #[cfg(feature = "csv-core")]
+    CsvCore,
+}
+
+impl CsvBackend {
+    /// Returns a reader of the records of `reader`.
+    pub fn reader<'a>(self, reader: Box<dyn Read + 'a>) -> Box<dyn RecordReader + 'a> {
+        match self {
+            CsvBackend::Csv => Box::new(csv::Reader::from_reader(reader)),
+            #[cfg(feature = "csv-core")]
+            CsvBackend::CsvCore => Box::new(CsvCoreReader::new(reader)),
+        }
+    }
+}
+
+/// Reads records with the push parser of `csv-core`, see [`CsvBackend::CsvCore`].
+///
+/// Like the reader of the `csv` crate, every record must have as many fields as the header.
+#[cfg(feature = "csv-core")]
+pub struct CsvCoreReader<R: Read> {
+    reader: std::io::BufReader<R>,
+    parser: csv_core::Reader,
+    output: Vec<u8>,
+    ends: Vec<usize>,
+    header_len: Option<usize>,
+    records: u64,
+    bytes: u64,
+}
+
+#[cfg(feature = "csv-core")]
+impl<R: Read> CsvCoreReader<R> {
+    /// Reads records from `reader`, starting with the header.
+    pub fn new(reader: R) -> Self {
+        CsvCoreReader {
+            reader: std::io::BufReader::new(reader),
+            parser: csv_core::Reader::new(),
+            output: vec![0; 1024],
+            ends: vec![0; 32],
+            header_len: None,
+            records: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Parses the next record into `record`, returns `false` at the end of the file.
+    fn parse_record(&mut self, record: &mut csv::StringRecord) -> Result<bool> {
+        use std::io::BufRead;
+
+        let io_error = |e: std::io::Error| ParseError::from(ParseErrorKind::from(e));
+        let mut position = csv::Position::new();
+        position
+            .set_byte(self.bytes)
+            .set_line(self.parser.line())
+            .set_record(self.records);
+        let (mut output_len, mut ends_len) = (0, 0);
+        loop {
+            let input = self.reader.fill_buf().map_err(io_error)?;
+            let (result, read, written, ended) = self.parser.read_record(
+                input,
+                &mut self.output[output_len..],
+                &mut self.ends[ends_len..],
+            );
+            self.reader.consume(read);
+            self.bytes += read as u64;
+            output_len += written;
+            ends_len += ended;
+            match result {
+                csv_core::ReadRecordResult::InputEmpty => {}
+                csv_core::ReadRecordResult::OutputFull => {
+                    self.output.resize(self.output.len() * 2, 0);
+                }
+                csv_core::ReadRecordResult::OutputEndsFull => {
+                    self.ends.resize(self.ends.len() * 2, 0);
+                }
+                csv_core::ReadRecordResult::Record => break,
+                csv_core::ReadRecordResult::End => return Ok(false),
+            }
+        }
+
+        let fields = std::str::from_utf8(&self.output[..output_len]).map_err(|_| {
+            ParseError::from(ParseErrorKind::InvalidValue(format!(
+                "invalid UTF-8 in record on line {}",
+                position.line()
+            )))
+        })?;
+        record.clear();
+        let mut start = 0;
+        for end in &self.ends[..ends_len] {
+            record.push_field(&fields[start..*end]);
+            start = *end;
+        }
+        if let Some(header_len) = self.header_len {
+            if record.len() != header_len {
+                return Err(ParseError::from(ParseErrorKind::InvalidValue(format!(
+                    "record on line {} has {} fields, but the header has {}",
+                    position.line(),
+                    record.len(),
+                    header_len
+                )))
+                .into());
+            }
+        }
+        record.set_position(Some(position));
+        self.records += 1;
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "csv-core")]
+impl<R: Read> RecordReader for CsvCoreReader<R> {
+    fn read_header(&mut self) -> Result<csv::StringRecord> {
+        let mut header = csv::StringRecord::new();
+        self.parse_record(&mut header)?;
+        self.header_len = Some(header.len());
+        Ok(header)
+    }
+
+    fn read_record(&mut self, record: &mut csv::StringRecord) -> Result<bool> {
+        self.parse_record(record)
+    }
+}
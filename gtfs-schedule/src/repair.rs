@@ -0,0 +1,399 @@
+//! Repair of datasets from validation findings.
+//!
+//! The main types are:
+//! - [`ValidationReport`]: The findings of the validation of a dataset.
+//! - [`Finding`]: A problem found in a dataset, with the fix that can be applied to it, if any.
+//! - [`SuggestedFix`]: A change that repairs a finding.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::edit::validate_record;
+use crate::error::{DatasetValidationError, Error, SchemaValidationError};
+use crate::schemas::{AgencyId, RouteId, Schema, ShapeId, Transfer, TripId};
+use crate::{Dataset, NoticeSeverity};
+
+/// A change that repairs a [`Finding`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SuggestedFix {
+    /// Removes the `shape_dist_traveled` of a stop time that does not increase along its trip.
+    ClearStopTimeShapeDistTraveled { trip_id: TripId, stop_sequence: u32 },
+    /// Removes the `shape_dist_traveled` of a shape point that does not increase along its shape.
+    ClearShapeDistTraveled {
+        shape_id: ShapeId,
+        shape_pt_sequence: u32,
+    },
+    /// Gives an `agency_id` to the only agency of the dataset, and to the routes without one.
+    ///
+    /// The suggested `agency_id` is the one already used by the routes, or the `agency_name`.
+    SetAgencyId { agency_id: AgencyId },
+    /// Swaps the `arrival_time` and `departure_time` of a stop time.
+    SwapArrivalDepartureTimes { trip_id: TripId, stop_sequence: u32 },
+    /// Sets the `route_short_name` of a route without any name.
+    SetRouteShortName {
+        route_id: RouteId,
+        route_short_name: String,
+    },
+    /// Sets the `from_route_id` and `to_route_id` of a transfer to the routes of its trips.
+    SetTransferRouteIds {
+        transfer: Box<Transfer>,
+        from_route_id: Option<RouteId>,
+        to_route_id: Option<RouteId>,
+    },
+    /// Removes a transfer.
+    RemoveTransfer { transfer: Box<Transfer> },
+}
+
+impl SuggestedFix {
+    /// Applies the fix to the dataset.
+    ///
+    /// Returns `false` if the records targeted by the fix no longer exist.
+    pub fn apply(&self, dataset: &mut Dataset) -> bool {
+        match self {
+            SuggestedFix::ClearStopTimeShapeDistTraveled {
+                trip_id,
+                stop_sequence,
+            } => dataset
+                .stop_times
+                .get_mut(&(trip_id.clone(), *stop_sequence))
+                .map(|mut stop_time| stop_time.shape_dist_traveled = None)
+                .is_some(),
+            SuggestedFix::ClearShapeDistTraveled {
+                shape_id,
+                shape_pt_sequence,
+            } => dataset
+                .shapes
+                .get_mut(&(shape_id.clone(), *shape_pt_sequence))
+                .map(|mut shape| shape.shape_dist_traveled = None)
+                .is_some(),
+            SuggestedFix::SetAgencyId { agency_id } => {
+                let [agency] = dataset.agencies.as_mut_slice() else {
+                    return false;
+                };
+                agency.agency_id = Some(agency_id.clone());
+                for mut route in dataset.routes.iter_mut() {
+                    if route.agency_id.is_none() {
+                        route.agency_id = Some(agency_id.clone());
+                    }
+                }
+                true
+            }
+            SuggestedFix::SwapArrivalDepartureTimes {
+                trip_id,
+                stop_sequence,
+            } => dataset
+                .stop_times
+                .get_mut(&(trip_id.clone(), *stop_sequence))
+                .map(|mut stop_time| {
+                    let stop_time = &mut *stop_time;
+                    std::mem::swap(&mut stop_time.arrival_time, &mut stop_time.departure_time)
+                })
+                .is_some(),
+            SuggestedFix::SetRouteShortName {
+                route_id,
+                route_short_name,
+            } => dataset
+                .routes
+                .get_mut(route_id)
+                .map(|mut route| route.route_short_name = Some(route_short_name.clone()))
+                .is_some(),
+            SuggestedFix::SetTransferRouteIds {
+                transfer,
+                from_route_id,
+                to_route_id,
+            } => dataset
+                .transfers
+                .iter_mut()
+                .find(|other| **other == **transfer)
+                .map(|other| {
+                    other.from_route_id = from_route_id.clone();
+                    other.to_route_id = to_route_id.clone();
+                })
+                .is_some(),
+            SuggestedFix::RemoveTransfer { transfer } => {
+                let Some(index) = dataset
+                    .transfers
+                    .iter()
+                    .position(|other| *other == **transfer)
+                else {
+                    return false;
+                };
+                dataset.transfers.remove(index);
+                true
+            }
+        }
+    }
+}
+
+/// A problem found in a dataset.
+#[derive(Debug)]
+pub struct Finding {
+    /// The problem.
+    pub error: Error,
+    /// How serious the problem is, [`NoticeSeverity::Warning`] for a recommendation of the
+    /// specification that is not followed.
+    pub severity: NoticeSeverity,
+    /// The change that repairs the problem, if it can be repaired automatically.
+    pub fix: Option<SuggestedFix>,
+}
+
+/// The findings of the validation of a dataset, see [`Dataset::validation_report`].
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    /// Returns `true` if nothing was found.
+    pub fn is_valid(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Applies the suggested fixes of all findings to the dataset.
+    ///
+    /// Returns the number of fixes that were applied. The dataset should be validated again
+    /// afterwards, as [`Dataset::validate`] stops at the first error and may have hidden others.
+    pub fn apply_fixes(&self, dataset: &mut Dataset) -> usize {
+        self.findings
+            .iter()
+            .filter_map(|finding| finding.fix.as_ref())
+            .filter(|fix| fix.apply(dataset))
+            .count()
+    }
+}
+
+impl Dataset {
+    /// Validates the dataset, and looks for problems that can be repaired automatically.
+    ///
+    /// The following findings come with a [`SuggestedFix`]:
+    /// - `shape_dist_traveled` values of stop times and shapes that do not increase.
+    /// - A missing `agency_id` when there is a single agency.
+    /// - Stop times arriving after they depart.
+    /// - Routes without `route_short_name` nor `route_long_name`.
+    /// - Transfers whose trips do not belong to their routes, which are given the routes of the
+    ///   trips, or do not serve their stops, which are removed.
+    ///
+    /// The other errors of [`Dataset::validate`] are reported without a fix: every record that
+    /// is invalid on its own, but only the first of the errors found across records, such as
+    /// missing references.
+    pub fn validation_report(&self) -> ValidationReport {
+        let mut findings = vec![];
+
+        // Look for shape_dist_traveled that do not increase along trips.
+        let mut trips: HashMap<TripId, Vec<u32>> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            let (trip_id, stop_sequence) = stop_time.key();
+            trips
+                .entry(trip_id.clone())
+                .or_default()
+                .push(*stop_sequence);
+        }
+        for (trip_id, mut stop_sequences) in trips {
+            stop_sequences.sort_unstable();
+            let mut last_distance = None;
+            for stop_sequence in stop_sequences {
+                let Some(stop_time) = self.stop_times.get(&(trip_id.clone(), stop_sequence)) else {
+                    continue;
+                };
+                let Some(distance) = stop_time.shape_dist_traveled else {
+                    continue;
+                };
+                if last_distance.is_some_and(|last_distance| distance <= last_distance) {
+                    findings.push(Finding {
+                        error: DatasetValidationError::new_inconsistent_value(
+                            "shape_dist_traveled".to_string(),
+                            distance.to_string(),
+                            Some("must increase along the trip".to_string()),
+                            vec![stop_time.clone().into()],
+                        )
+                        .into(),
+                        severity: NoticeSeverity::Error,
+                        fix: Some(SuggestedFix::ClearStopTimeShapeDistTraveled {
+                            trip_id: trip_id.clone(),
+                            stop_sequence,
+                        }),
+                    });
+                } else {
+                    last_distance = Some(distance);
+                }
+            }
+        }
+
+        // Look for shape_dist_traveled that do not increase along shapes.
+        let mut shapes: HashMap<ShapeId, Vec<u32>> = HashMap::new();
+        for shape in self.shapes.iter() {
+            let (shape_id, shape_pt_sequence) = shape.key();
+            shapes
+                .entry(shape_id.clone())
+                .or_default()
+                .push(*shape_pt_sequence);
+        }
+        for (shape_id, mut shape_pt_sequences) in shapes {
+            shape_pt_sequences.sort_unstable();
+            let mut last_distance = None;
+            for shape_pt_sequence in shape_pt_sequences {
+                let Some(shape) = self.shapes.get(&(shape_id.clone(), shape_pt_sequence)) else {
+                    continue;
+                };
+                let Some(distance) = shape.shape_dist_traveled else {
+                    continue;
+                };
+                if last_distance.is_some_and(|last_distance| distance <= last_distance) {
+                    findings.push(Finding {
+                        error: DatasetValidationError::new_inconsistent_value(
+                            "shape_dist_traveled".to_string(),
+                            distance.to_string(),
+                            Some("must increase along the shape".to_string()),
+                            vec![shape.clone().into()],
+                        )
+                        .into(),
+                        severity: NoticeSeverity::Error,
+                        fix: Some(SuggestedFix::ClearShapeDistTraveled {
+                            shape_id: shape_id.clone(),
+                            shape_pt_sequence,
+                        }),
+                    });
+                } else {
+                    last_distance = Some(distance);
+                }
+            }
+        }
+
+        // Look for a missing agency_id when there is a single agency.
+        if let [agency] = self.agencies.as_slice() {
+            if agency.agency_id.is_none() {
+                // Reuse the agency_id of the routes when they agree on one.
+                let route_agency_ids: HashSet<AgencyId> = self
+                    .routes
+                    .iter()
+                    .filter_map(|route| route.agency_id.clone())
+                    .collect();
+                let mut route_agency_ids = route_agency_ids.into_iter();
+                let agency_id = match (route_agency_ids.next(), route_agency_ids.next()) {
+                    (Some(agency_id), None) => agency_id,
+                    _ => AgencyId::from(agency.agency_name.as_str()),
+                };
+                findings.push(Finding {
+                    error: DatasetValidationError::new_missing_value(
+                        "agency_id".to_string(),
+                        Some("is recommended even when there is a single agency".to_string()),
+                        vec![agency.clone().into()],
+                    )
+                    .into(),
+                    severity: NoticeSeverity::Warning,
+                    fix: Some(SuggestedFix::SetAgencyId { agency_id }),
+                });
+            }
+        }
+
+        // Look for stop times arriving after they depart.
+        for stop_time in self.stop_times.iter() {
+            if let (Some(arrival_time), Some(departure_time)) =
+                (stop_time.arrival_time, stop_time.departure_time)
+            {
+                if arrival_time > departure_time {
+                    findings.push(Finding {
+                        error: DatasetValidationError::new_inconsistent_value(
+                            "arrival_time".to_string(),
+                            String::from(arrival_time),
+                            Some("must not be after departure_time".to_string()),
+                            vec![stop_time.clone().into()],
+                        )
+                        .into(),
+                        severity: NoticeSeverity::Error,
+                        fix: Some(SuggestedFix::SwapArrivalDepartureTimes {
+                            trip_id: stop_time.trip_id.clone(),
+                            stop_sequence: stop_time.stop_sequence,
+                        }),
+                    });
+                }
+            }
+        }
+
+        // Look for routes without any name.
+        for route in self.routes.iter() {
+            if route.route_short_name.is_none() && route.route_long_name.is_none() {
+                findings.push(Finding {
+                    error: SchemaValidationError::new_missing_value(
+                        "route_short_name or route_long_name".to_string(),
+                        Some("at least one of them must be non-empty".to_string()),
+                        Schema::from(route.clone()),
+                    )
+                    .into(),
+                    severity: NoticeSeverity::Error,
+                    fix: Some(SuggestedFix::SetRouteShortName {
+                        route_id: route.route_id.clone(),
+                        route_short_name: route.route_id.to_string(),
+                    }),
+                });
+            }
+        }
+
+        // Look for transfers inconsistent with their trips.
+        for transfer in &self.transfers {
+            let Err(error) = self.validate_transfer_consistency(transfer) else {
+                continue;
+            };
+            let route_id = |trip_id: &Option<TripId>, route_id: &Option<RouteId>| {
+                trip_id
+                    .as_ref()
+                    .and_then(|trip_id| self.trips.get(trip_id))
+                    .filter(|_| route_id.is_some())
+                    .map(|trip| trip.route_id.clone())
+                    .or_else(|| route_id.clone())
+            };
+            let mut corrected = transfer.clone();
+            corrected.from_route_id = route_id(&transfer.from_trip_id, &transfer.from_route_id);
+            corrected.to_route_id = route_id(&transfer.to_trip_id, &transfer.to_route_id);
+            let fix = match self.validate_transfer_consistency(&corrected) {
+                Ok(()) => SuggestedFix::SetTransferRouteIds {
+                    transfer: Box::new(transfer.clone()),
+                    from_route_id: corrected.from_route_id,
+                    to_route_id: corrected.to_route_id,
+                },
+                Err(_) => SuggestedFix::RemoveTransfer {
+                    transfer: Box::new(transfer.clone()),
+                },
+            };
+            findings.push(Finding {
+                error,
+                severity: NoticeSeverity::Error,
+                fix: Some(fix),
+            });
+        }
+
+        // Report the errors of every record, then the first error found across records, unless
+        // the same problem was already found on the same records.
+        let record_errors = self
+            .iter_all()
+            .filter_map(|(_, mut record)| validate_record(&mut record).err());
+        let mut reported: HashSet<String> = findings
+            .iter()
+            .map(|finding| problem_key(&finding.error))
+            .collect();
+        for error in record_errors.chain(self.validate_dataset().err()) {
+            if reported.insert(problem_key(&error)) {
+                findings.push(Finding {
+                    error,
+                    severity: NoticeSeverity::Error,
+                    fix: None,
+                });
+            }
+        }
+
+        ValidationReport { findings }
+    }
+}
+
+/// Identifies the problem an error reports by its notice code and the records involved, so that
+/// the same problem is not reported twice under differently worded reasons.
+fn problem_key(error: &Error) -> String {
+    match error {
+        Error::SchemaValidationError(inner) => {
+            format!("{} {:?}", error.notice_code(), inner.schema_instance)
+        }
+        Error::DatasetValidationError(inner) => {
+            format!("{} {:?}", error.notice_code(), inner.schema_instances)
+        }
+        Error::ParseError(inner) => inner.to_string(),
+    }
+}
@@ -3,16 +3,19 @@
 //! The main types are:
 //! - [`Agency`]: Represents a transit agency.
 //! - [`AgencyId`]: Identifies a transit brand which is often synonymous with a transit agency.
+//! - [`AgencyContact`]: The ways riders can reach a transit agency.
+
+use std::sync::Arc;
 
 use chrono_tz::Tz;
 use email_address::EmailAddress;
-use gtfs_schedule_macros::StringWrapper;
+use gtfs_schedule_macros::{GtfsTable, StringWrapper};
 use oxilangtag::LanguageTag;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use url::Url;
 
-use super::Schema;
+use super::{deserialize_optional_language_tag, Phone, Schema};
 use crate::error::{Result, SchemaValidationError};
 
 /// Identifies a transit brand which is often synonymous with a transit agency.
@@ -21,12 +24,13 @@ use crate::error::{Result, SchemaValidationError};
 ///
 /// See [agency.txt](https://gtfs.org/schedule/reference/#agencytxt) for more details.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct AgencyId(pub String);
+pub struct AgencyId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
 /// Represents a transit agency.
 ///
 /// See [agency.txt](https://gtfs.org/schedule/reference/#agencytxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable)]
+#[gtfs(file = "agency.txt")]
 #[skip_serializing_none]
 pub struct Agency {
     /// Identifies a transit brand which is often synonymous with a transit agency.
@@ -49,13 +53,14 @@ pub struct Agency {
     /// Primary language used by this transit agency. Should be provided to help
     /// GTFS consumers choose capitalization rules and other language-specific
     /// settings for the dataset.
+    #[serde(deserialize_with = "deserialize_optional_language_tag", default)]
     pub agency_lang: Option<LanguageTag<String>>,
     /// A voice telephone number for the specified agency. This field is a string
     /// value that presents the telephone number as typical for the agency's service
     /// area. It may contain punctuation marks to group the digits of the number.
     /// Dialable text (for example, TriMet's "503-238-RIDE") is permitted, but the
     /// field must not contain any other descriptive text.
-    pub agency_phone: Option<String>,
+    pub agency_phone: Option<Phone>,
     /// URL of a web page that allows a rider to purchase tickets or other fare
     /// instruments for that agency online.
     pub agency_fare_url: Option<Url>,
@@ -63,6 +68,10 @@ pub struct Agency {
     /// This email address should be a direct contact point where transit riders can
     /// reach a customer service representative at the agency.
     pub agency_email: Option<EmailAddress>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Agency {
@@ -80,6 +89,50 @@ impl Agency {
             }
         }
 
+        // Validate agency_url and agency_fare_url.
+        for (field_name, url) in [
+            ("agency_url", Some(&self.agency_url)),
+            ("agency_fare_url", self.agency_fare_url.as_ref()),
+        ] {
+            if url.is_some_and(|url| !matches!(url.scheme(), "http" | "https")) {
+                return Err(SchemaValidationError::new_invalid_value(
+                    field_name.to_string(),
+                    Some("must be a fully qualified URL using http:// or https://".to_string()),
+                    Schema::from(self.clone()),
+                )
+                .into());
+            }
+        }
+
         Ok(())
     }
+
+    /// Returns the ways riders can reach this agency.
+    pub fn contact(&self) -> AgencyContact {
+        AgencyContact {
+            agency_name: self.agency_name.clone(),
+            agency_url: self.agency_url.clone(),
+            agency_phone: self.agency_phone.clone(),
+            agency_fare_url: self.agency_fare_url.clone(),
+            agency_email: self.agency_email.clone(),
+            agency_lang: self.agency_lang.clone(),
+        }
+    }
+}
+
+/// The ways riders can reach a transit agency, as defined in [`Agency`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgencyContact {
+    /// Full name of the transit agency.
+    pub agency_name: String,
+    /// URL of the transit agency.
+    pub agency_url: Url,
+    /// A voice telephone number for the agency.
+    pub agency_phone: Option<Phone>,
+    /// URL of a web page that allows a rider to purchase tickets online.
+    pub agency_fare_url: Option<Url>,
+    /// Email address monitored by the agency's customer service department.
+    pub agency_email: Option<EmailAddress>,
+    /// Primary language used by the agency.
+    pub agency_lang: Option<LanguageTag<String>>,
 }
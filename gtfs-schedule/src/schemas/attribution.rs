@@ -4,26 +4,31 @@
 //! - [`Attribution`]: Defines the attributions applied to the dataset.
 //! - [`AttributionId`]: Identifies an attribution for the dataset or a subset of it.
 
+use std::sync::Arc;
+
 use email_address::EmailAddress;
-use gtfs_schedule_macros::StringWrapper;
-use phonenumber::PhoneNumber;
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use url::Url;
 
 use crate::error::{Result, SchemaValidationError};
 
-use super::{AgencyId, RouteId, Schema, TripId};
+use super::{
+    common::{deserialize_optional_bool_as_int, serialize_bool_as_int},
+    AgencyId, Phone, RouteId, Schema, TripId,
+};
 
 /// Identifies an attribution for the dataset or a subset of it.
 /// This is mostly useful for translations.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct AttributionId(pub String);
+pub struct AttributionId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
 /// Defines the attributions applied to the dataset.
 ///
 /// See [attributions.txt](https://gtfs.org/schedule/reference/#attributionstxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "attributions.txt")]
 #[skip_serializing_none]
 pub struct Attribution {
     /// Identifies an attribution for the dataset or a subset of it. This is mostly useful for translations.
@@ -38,31 +43,69 @@ pub struct Attribution {
     /// Functions in the same way as [`Attribution::agency_id`] except the attribution applies to a trip. Multiple attributions may apply to the same trip.
     pub trip_id: Option<TripId>,
     /// Name of the organization that the dataset is attributed to.
+    #[gtfs(required)]
     pub organization_name: String,
     /// The role of the organization is producer.
     ///
-    /// At least one of the fields [`Attribution::is_producer`], [`Attribution::is_operator`], or [`Attribution::is_authority`] should be set at `true`.
-    pub is_producer: Option<bool>,
+    /// At least one of the fields [`Attribution::is_producer`], [`Attribution::is_operator`], or [`Attribution::is_authority`] must be set at `true`.
+    /// An empty value is read as `false`.
+    #[serde(
+        default,
+        serialize_with = "serialize_bool_as_int",
+        deserialize_with = "deserialize_optional_bool_as_int"
+    )]
+    pub is_producer: bool,
     /// Functions in the same way as [`Attribution::is_producer`] except the role of the organization is operator.
-    pub is_operator: Option<bool>,
+    #[serde(
+        default,
+        serialize_with = "serialize_bool_as_int",
+        deserialize_with = "deserialize_optional_bool_as_int"
+    )]
+    pub is_operator: bool,
     /// Functions in the same way as [`Attribution::is_producer`] except the role of the organization is authority.
-    pub is_authority: Option<bool>,
+    #[serde(
+        default,
+        serialize_with = "serialize_bool_as_int",
+        deserialize_with = "deserialize_optional_bool_as_int"
+    )]
+    pub is_authority: bool,
     /// URL of the organization.
     pub attribution_url: Option<Url>,
     /// Email of the organization.
     pub attribution_email: Option<EmailAddress>,
     /// Phone number of the organization.
-    pub attribution_phone: Option<PhoneNumber>,
+    pub attribution_phone: Option<Phone>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Attribution {
     /// Validates if the Attribution is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate organization_name.
-        if self.organization_name.is_empty() {
+        self.validate_fields()?;
+
+        // Validate the roles of the organization.
+        if !self.is_producer && !self.is_operator && !self.is_authority {
             return Err(SchemaValidationError::new_missing_value(
-                "organization_name".to_string(),
-                Some("can never be empty".to_string()),
+                "is_producer, is_operator or is_authority".to_string(),
+                Some("at least one of them must be 1".to_string()),
+                Schema::from(self.clone()),
+            )
+            .into());
+        }
+
+        // Validate the scope of the attribution, the whole dataset when none is given.
+        let scopes = [
+            self.agency_id.is_some(),
+            self.route_id.is_some(),
+            self.trip_id.is_some(),
+        ];
+        if scopes.into_iter().filter(|is_set| *is_set).count() > 1 {
+            return Err(SchemaValidationError::new_forbidden_value(
+                "agency_id, route_id or trip_id".to_string(),
+                Some("only one of them may be defined".to_string()),
                 Schema::from(self.clone()),
             )
             .into());
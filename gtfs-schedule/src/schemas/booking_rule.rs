@@ -5,43 +5,48 @@
 //! - [`BookingRuleId`]: Identifies a rule.
 //! - [`BookingType`]: Indicates how far in advance booking can be made.
 
+use std::sync::Arc;
 use std::time::Duration;
 
 use chrono::NaiveTime;
-use gtfs_schedule_macros::StringWrapper;
-use phonenumber::PhoneNumber;
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
-use serde_repr::*;
 use serde_with::skip_serializing_none;
 use url::Url;
 
 use crate::error::{Result, SchemaValidationError};
 
-use super::{CalendarServiceId, Schema};
+use super::{
+    coded_enum, deserialize_optional_minutes, serialize_optional_minutes, CalendarServiceId, Phone,
+    Schema,
+};
 
 /// Identifies a rule.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct BookingRuleId(pub String);
+pub struct BookingRuleId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
-/// Indicates how far in advance booking can be made.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum BookingType {
-    /// Real time booking.
-    RealTime = 0,
-    /// Up to same-day booking with advance notice.
-    SameDayWithNotice = 1,
-    /// Up to prior day(s) booking.
-    PriorDaysWithNotice = 2,
+coded_enum! {
+    /// Indicates how far in advance booking can be made.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum BookingType {
+        /// Real time booking.
+        RealTime = 0,
+        /// Up to same-day booking with advance notice.
+        SameDayWithNotice = 1,
+        /// Up to prior day(s) booking.
+        PriorDaysWithNotice = 2,
+    }
 }
 
 /// Defines the booking rules for rider-requested services.
 ///
 /// See [booking_rules.txt](https://gtfs.org/schedule/reference/#booking_rulestxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "booking_rules.txt", primary_key = "booking_rule_id")]
 #[skip_serializing_none]
 pub struct BookingRule {
     /// Identifies a rule.
+    #[gtfs(required)]
     pub booking_rule_id: BookingRuleId,
     /// Indicates how far in advance booking can be made.
     pub booking_type: BookingType,
@@ -50,12 +55,26 @@ pub struct BookingRule {
     /// **Conditionally Required:**
     /// - Required for [`BookingType::SameDayWithNotice`].
     /// - Forbidden otherwise.
+    #[serde(
+        serialize_with = "serialize_optional_minutes",
+        deserialize_with = "deserialize_optional_minutes",
+        default
+    )]
+    #[gtfs(json_with = "crate::json::json_minutes")]
     pub prior_notice_duration_min: Option<Duration>,
     /// Maximum number of minutes before travel to make the booking request.
     ///
+    /// Must not be less than [`BookingRule::prior_notice_duration_min`].
+    ///
     /// **Conditionally Forbidden:**
     /// - Forbidden for [`BookingType::RealTime`] and [`BookingType::PriorDaysWithNotice`].
     /// - Optional for [`BookingType::SameDayWithNotice`].
+    #[serde(
+        serialize_with = "serialize_optional_minutes",
+        deserialize_with = "deserialize_optional_minutes",
+        default
+    )]
+    #[gtfs(json_with = "crate::json::json_minutes")]
     pub prior_notice_duration_max: Option<Duration>,
     /// Last day before travel to make the booking request.
     ///
@@ -113,25 +132,21 @@ pub struct BookingRule {
     /// Functions in the same way as [`BookingRule::message`] but used when riders have on-demand drop off only.
     pub drop_off_message: Option<String>,
     /// Phone number to call to make the booking request.
-    pub phone_number: Option<PhoneNumber>,
+    pub phone_number: Option<Phone>,
     /// URL providing information about the booking rule.
     pub info_url: Option<Url>,
     /// URL to an online interface or app where the booking request can be made.
     pub booking_url: Option<Url>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl BookingRule {
     /// Validates if the BookingRule is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate booking_rule_id.
-        if self.booking_rule_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "booking_rule_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
+        self.validate_fields()?;
 
         // Validate prior_notice_duration_min.
         if self.booking_type == BookingType::SameDayWithNotice
@@ -173,6 +188,20 @@ impl BookingRule {
             .into());
         }
 
+        if let (Some(min), Some(max)) = (
+            self.prior_notice_duration_min,
+            self.prior_notice_duration_max,
+        ) {
+            if max < min {
+                return Err(SchemaValidationError::new_invalid_value(
+                    "prior_notice_duration_max".to_string(),
+                    Some("must not be less than prior_notice_duration_min".to_string()),
+                    Schema::from(self.clone()),
+                )
+                .into());
+            }
+        }
+
         // Validate prior_notice_last_day.
         if self.booking_type == BookingType::PriorDaysWithNotice
             && self.prior_notice_last_day.is_none()
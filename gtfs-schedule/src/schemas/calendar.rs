@@ -4,37 +4,98 @@
 //! - [`Calendar`]: Service dates specified using a weekly schedule with start and end dates.
 //! - [`CalendarServiceId`]: Identifies a set of dates when service is available for one or more routes.
 //! - [`CalendarDayService`]: Indicates whether service is available on a given day of the week.
+//! - [`ServiceDays`]: The days of the week on which service is available.
 
-use chrono::NaiveDate;
-use gtfs_schedule_macros::StringWrapper;
+use std::ops::RangeBounds;
+use std::sync::Arc;
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
-use serde_repr::*;
 use serde_with::skip_serializing_none;
 
-use super::{deserialize_date, serialize_date, Schema};
+use super::{coded_enum, deserialize_date, serialize_date, Schema};
 use crate::error::{Result, SchemaValidationError};
 
 /// Identifies a set of dates when service is available for one or more routes.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct CalendarServiceId(pub String);
-
-/// Indicates whether service is available on a given day of the week.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum CalendarDayService {
-    /// Service is available for this day of the week during the entire date range.
-    Available = 1,
-    /// Service is not available for this day of the week during the entire date range.
-    NotAvailable = 0,
+pub struct CalendarServiceId(
+    #[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>,
+);
+
+coded_enum! {
+    /// Indicates whether service is available on a given day of the week.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum CalendarDayService {
+        /// Service is available for this day of the week during the entire date range.
+        Available = 1,
+        /// Service is not available for this day of the week during the entire date range.
+        NotAvailable = 0,
+    }
+}
+
+bitflags::bitflags! {
+    /// The days of the week on which service is available, see [`Calendar::service_days`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ServiceDays: u8 {
+        const MONDAY = 1;
+        const TUESDAY = 1 << 1;
+        const WEDNESDAY = 1 << 2;
+        const THURSDAY = 1 << 3;
+        const FRIDAY = 1 << 4;
+        const SATURDAY = 1 << 5;
+        const SUNDAY = 1 << 6;
+        /// Monday to Friday.
+        const WEEKDAYS = Self::MONDAY.bits()
+            | Self::TUESDAY.bits()
+            | Self::WEDNESDAY.bits()
+            | Self::THURSDAY.bits()
+            | Self::FRIDAY.bits();
+        /// Saturday and Sunday.
+        const WEEKEND = Self::SATURDAY.bits() | Self::SUNDAY.bits();
+    }
+}
+
+impl ServiceDays {
+    /// Returns the flag of a single day of the week.
+    pub fn from_weekday(weekday: Weekday) -> Self {
+        Self::from_bits_truncate(1 << weekday.num_days_from_monday())
+    }
+
+    /// Returns `true` if service is available on the given day of the week.
+    pub fn runs_on(&self, weekday: Weekday) -> bool {
+        self.contains(Self::from_weekday(weekday))
+    }
+
+    /// Returns `true` if service is available on some days, all from Monday to Friday.
+    pub fn weekdays_only(&self) -> bool {
+        !self.is_empty() && Self::WEEKDAYS.contains(*self)
+    }
+
+    /// Returns `true` if service is available on some days, all on Saturday or Sunday.
+    pub fn weekend_only(&self) -> bool {
+        !self.is_empty() && Self::WEEKEND.contains(*self)
+    }
+}
+
+impl From<bool> for CalendarDayService {
+    fn from(available: bool) -> Self {
+        match available {
+            true => CalendarDayService::Available,
+            false => CalendarDayService::NotAvailable,
+        }
+    }
 }
 
 /// Service dates specified using a weekly schedule with start and end dates.
 ///
 /// See [calendar.txt](https://gtfs.org/schedule/reference/#calendartxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "calendar.txt", primary_key = "service_id")]
 #[skip_serializing_none]
 pub struct Calendar {
     /// Identifies a set of dates when service is available for one or more routes.
+    #[gtfs(required)]
     pub service_id: CalendarServiceId,
     /// Indicates whether the service operates on all Mondays in the date range. Note
     /// that exceptions for particular dates may be listed in [`crate::schemas::calendar_date::CalendarDate`].
@@ -63,20 +124,16 @@ pub struct Calendar {
         deserialize_with = "deserialize_date"
     )]
     pub end_date: NaiveDate,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Calendar {
     /// Validates if the Calendar is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate service_id.
-        if self.service_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "service_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
+        self.validate_fields()?;
 
         // Validate start_date and end_date.
         if self.start_date > self.end_date {
@@ -90,4 +147,59 @@ impl Calendar {
 
         Ok(())
     }
+
+    /// Returns the days of the week on which service is available.
+    pub fn service_days(&self) -> ServiceDays {
+        [
+            &self.monday,
+            &self.tuesday,
+            &self.wednesday,
+            &self.thursday,
+            &self.friday,
+            &self.saturday,
+            &self.sunday,
+        ]
+        .into_iter()
+        .enumerate()
+        .filter(|(_, day)| **day == CalendarDayService::Available)
+        .map(|(index, _)| ServiceDays::from_bits_truncate(1 << index))
+        .collect()
+    }
+
+    /// Sets the days of the week on which service is available.
+    pub fn set_service_days(&mut self, days: ServiceDays) {
+        self.monday = days.runs_on(Weekday::Mon).into();
+        self.tuesday = days.runs_on(Weekday::Tue).into();
+        self.wednesday = days.runs_on(Weekday::Wed).into();
+        self.thursday = days.runs_on(Weekday::Thu).into();
+        self.friday = days.runs_on(Weekday::Fri).into();
+        self.saturday = days.runs_on(Weekday::Sat).into();
+        self.sunday = days.runs_on(Weekday::Sun).into();
+    }
+
+    /// Returns `true` if service is available on the given day of the week.
+    ///
+    /// The service interval and the exceptions of calendar_dates.txt are not taken into account,
+    /// see [`Calendar::is_available_on_date`] and [`crate::Dataset::is_service_active`].
+    pub fn is_available_on(&self, weekday: Weekday) -> bool {
+        self.service_days().runs_on(weekday)
+    }
+
+    /// Returns `true` if `date` is within the service interval and service is available on its day of the week.
+    pub fn is_available_on_date(&self, date: NaiveDate) -> bool {
+        self.start_date <= date && date <= self.end_date && self.is_available_on(date.weekday())
+    }
+
+    /// Returns the dates within `range` where service is available, sorted.
+    ///
+    /// Use `..` to get all the dates of the service interval. As for
+    /// [`Calendar::is_available_on_date`], the exceptions of calendar_dates.txt are not taken into
+    /// account, see [`crate::Dataset::service_dates`].
+    pub fn dates_in_range(&self, range: impl RangeBounds<NaiveDate>) -> Vec<NaiveDate> {
+        self.start_date
+            .iter_days()
+            .take_while(|date| *date <= self.end_date)
+            .filter(|date| range.contains(date) && self.is_available_on(date.weekday()))
+            .collect()
+    }
 }
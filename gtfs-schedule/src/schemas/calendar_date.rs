@@ -5,30 +5,29 @@
 //! - [`ExceptionType`]: Indicates whether service is available on the date specified in the date field.
 
 use chrono::NaiveDate;
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate};
 use serde::{Deserialize, Serialize};
-use serde_repr::*;
 use serde_with::skip_serializing_none;
 
-use super::{deserialize_date, serialize_date, Schema};
-use crate::{
-    error::{Result, SchemaValidationError},
-    schemas::calendar::CalendarServiceId,
-};
+use super::{coded_enum, deserialize_date, serialize_date};
+use crate::{error::Result, schemas::calendar::CalendarServiceId};
 
-/// Indicates whether service is available on the date specified in the date field.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum ExceptionType {
-    /// Service has been added for the specified date.
-    Added = 1,
-    /// Service has been removed for the specified date.
-    Removed = 2,
+coded_enum! {
+    /// Indicates whether service is available on the date specified in the date field.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum ExceptionType {
+        /// Service has been added for the specified date.
+        Added = 1,
+        /// Service has been removed for the specified date.
+        Removed = 2,
+    }
 }
 
 /// Exceptions for the services defined in the [`Calendar`].
 ///
 /// See [calendar_dates.txt](https://gtfs.org/schedule/reference/#calendar_datestxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "calendar_dates.txt", primary_key = "service_id, date")]
 #[skip_serializing_none]
 pub struct CalendarDate {
     /// Identifies a set of dates when a service exception occurs for one or more routes.
@@ -36,6 +35,7 @@ pub struct CalendarDate {
     /// once in [`CalendarDate`] if using [`crate::schemas::calendar::Calendar`] and [`CalendarDate`] in conjunction.
     /// If a [`CalendarDate::service_id`] value appears in both [`crate::schemas::calendar::Calendar`] and [`CalendarDate`],
     /// the information in [`CalendarDate`] modifies the service information specified in [`crate::schemas::calendar::Calendar`].
+    #[gtfs(required)]
     pub service_id: CalendarServiceId,
     /// Date when service exception occurs.
     #[serde(
@@ -45,21 +45,15 @@ pub struct CalendarDate {
     pub date: NaiveDate,
     /// Indicates whether service is available on the date specified in the [`CalendarDate::date`] field.
     pub exception_type: ExceptionType,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl CalendarDate {
     /// Validates if the CalendarDate is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate service_id.
-        if self.service_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "service_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        Ok(())
+        self.validate_fields()
     }
 }
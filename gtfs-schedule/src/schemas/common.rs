@@ -8,21 +8,28 @@
 //! - [`ContinuousDropOff`]: Indicates that the rider can alight from
 //!   the transit vehicle at any point along the vehicle's travel path as
 //!   described by [`crate::schemas::shape::Shape`], on every trip of the route.
+//! - [`Money`]: An amount in an ISO 4217 currency, such as a fare price.
+//! - [`Phone`]: A voice telephone number.
 
 use std::{
-    cmp::Ordering,
+    cell::Cell,
     fmt,
-    ops::{Add, Deref, DerefMut},
+    ops::{Add, Deref, DerefMut, Sub},
+    str::FromStr,
     time::Duration,
 };
 
-use chrono::{NaiveDate, NaiveTime, Timelike};
+use chrono::{NaiveDate, NaiveTime};
 use geo::Coord;
+use iso_currency::Currency;
+use oxilangtag::LanguageTag;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use serde::de::{self, Error as DeError, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_repr::*;
 
 use crate::error::{Error, ParseError, ParseErrorKind};
+use crate::GtfsFile;
 
 use super::{
     Agency, Area, Attribution, BookingRule, Calendar, CalendarDate, FareAttribute, FareLegRule,
@@ -65,6 +72,43 @@ pub enum Schema {
     Trip(Trip),
 }
 
+impl Schema {
+    /// Returns the file holding this kind of record.
+    pub fn file(&self) -> GtfsFile {
+        match self {
+            Schema::Agency(_) => GtfsFile::Agency,
+            Schema::Area(_) => GtfsFile::Areas,
+            Schema::Attribution(_) => GtfsFile::Attributions,
+            Schema::BookingRule(_) => GtfsFile::BookingRules,
+            Schema::Calendar(_) => GtfsFile::Calendar,
+            Schema::CalendarDate(_) => GtfsFile::CalendarDates,
+            Schema::FareAttribute(_) => GtfsFile::FareAttributes,
+            Schema::FareLegRule(_) => GtfsFile::FareLegRules,
+            Schema::FareMedia(_) => GtfsFile::FareMedia,
+            Schema::FareProduct(_) => GtfsFile::FareProducts,
+            Schema::FareRule(_) => GtfsFile::FareRules,
+            Schema::FareTransferRule(_) => GtfsFile::FareTransfers,
+            Schema::FeedInfo(_) => GtfsFile::FeedInfo,
+            Schema::Frequency(_) => GtfsFile::Frequencies,
+            Schema::Level(_) => GtfsFile::Levels,
+            Schema::LocationGroup(_) => GtfsFile::LocationGroups,
+            Schema::LocationGroupStop(_) => GtfsFile::LocationGroupsStops,
+            Schema::Network(_) => GtfsFile::Networks,
+            Schema::Pathway(_) => GtfsFile::Pathways,
+            Schema::Route(_) => GtfsFile::Routes,
+            Schema::RouteNetwork(_) => GtfsFile::RoutesNetworks,
+            Schema::Shape(_) => GtfsFile::Shapes,
+            Schema::Stop(_) => GtfsFile::Stops,
+            Schema::StopArea(_) => GtfsFile::StopsAreas,
+            Schema::StopTime(_) => GtfsFile::StopTimes,
+            Schema::Timeframe(_) => GtfsFile::Timeframes,
+            Schema::Transfer(_) => GtfsFile::Transfers,
+            Schema::Translation(_) => GtfsFile::Translations,
+            Schema::Trip(_) => GtfsFile::Trips,
+        }
+    }
+}
+
 impl From<Agency> for Schema {
     fn from(agency: Agency) -> Self {
         Schema::Agency(agency)
@@ -239,54 +283,286 @@ impl From<Trip> for Schema {
     }
 }
 
-/// Indicates the type of the location.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum LocationType {
-    /// A location where passengers board or disembark from a transit vehicle.
-    /// Is called a platform when defined within a [`LocationType::Station`].
-    StopOrPlatform = 0,
-    /// A physical structure or area that contains one or more [`LocationType::StopOrPlatform`].
-    Station = 1,
-    /// A location where passengers can enter or exit a station from the street.
-    /// If an entrance/exit belongs to multiple stations, it may be linked by
-    /// pathways to both, but the data provider must pick one of them as parent.
-    EntranceOrExit = 2,
-    /// A location within a station, not matching any other [`LocationType`],
-    /// that may be used to link together pathways defined in [`crate::schemas::pathway::Pathway`].
-    GenericNode = 3,
-    /// A specific location on a platform, where passengers can board and/or alight vehicles.
-    BoardingArea = 4,
-}
-
-/// Indicates that the rider can board the transit vehicle at any point along
-/// the vehicle's travel path as described by [`crate::schemas::shape::Shape`], on every trip of the route.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum ContinuousPickup {
-    /// Continuous stopping pickup.
-    ContinuousStopping = 0,
-    /// No continuous stopping pickup.
-    NoContinuousStopping = 1,
-    /// Must phone agency to arrange continuous stopping pickup.
-    PhoneAgencyToArrange = 2,
-    /// Must coordinate with driver to arrange continuous stopping pickup.
-    CoordinateWithDriver = 3,
-}
-
-/// Indicates that the rider can alight from the transit vehicle at any point along
-/// the vehicle's travel path as described by [`crate::schemas::shape::Shape`], on every trip of the route.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum ContinuousDropOff {
-    /// Continuous stopping drop off.
-    ContinuousStopping = 0,
-    /// No continuous stopping drop off.
-    NoContinuousStopping = 1,
-    /// Must phone agency to arrange continuous stopping drop off.
-    PhoneAgencyToArrange = 2,
-    /// Must coordinate with driver to arrange continuous stopping drop off.
-    CoordinateWithDriver = 3,
+/// Records of one of the files of a dataset, implemented with
+/// [`gtfs_schedule_macros::GtfsTable`].
+pub trait GtfsTable {
+    /// The value identifying a record, `()` for the files without primary key.
+    type Key;
+
+    /// The file holding the records.
+    const FILE: crate::GtfsFile;
+    /// The name of the file holding the records (e.g. `stop_times.txt`).
+    const FILE_NAME: &'static str = Self::FILE.file_name();
+    /// The columns of the file supported by this crate.
+    const HEADERS: &'static [&'static str];
+    /// The columns identifying a record, empty for the files without primary key.
+    const PRIMARY_KEY: &'static [&'static str];
+
+    /// Returns the value identifying the record.
+    fn key(&self) -> Self::Key;
+}
+
+/// Records holding the columns that are not part of the specification.
+///
+/// Those columns are filled when parsing a dataset, and written back by [`crate::Dataset::to_csv`].
+#[cfg(feature = "extensions")]
+pub trait Extensions {
+    /// Returns the columns of the record that are not part of the specification, by name.
+    fn extensions(&self) -> &std::collections::HashMap<String, String>;
+
+    /// Returns a mutable reference to the columns of the record that are not part of the specification.
+    fn extensions_mut(&mut self) -> &mut std::collections::HashMap<String, String>;
+}
+
+#[cfg(feature = "extensions")]
+macro_rules! impl_extensions {
+    ($($schema:ident),* $(,)?) => {
+        $(
+            impl Extensions for $schema {
+                fn extensions(&self) -> &std::collections::HashMap<String, String> {
+                    &self.extensions
+                }
+
+                fn extensions_mut(&mut self) -> &mut std::collections::HashMap<String, String> {
+                    &mut self.extensions
+                }
+            }
+        )*
+
+        impl Extensions for Schema {
+            fn extensions(&self) -> &std::collections::HashMap<String, String> {
+                match self {
+                    $(Schema::$schema(record) => &record.extensions,)*
+                }
+            }
+
+            fn extensions_mut(&mut self) -> &mut std::collections::HashMap<String, String> {
+                match self {
+                    $(Schema::$schema(record) => &mut record.extensions,)*
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "extensions")]
+impl_extensions!(
+    Agency,
+    Area,
+    Attribution,
+    BookingRule,
+    Calendar,
+    CalendarDate,
+    FareAttribute,
+    FareLegRule,
+    FareMedia,
+    FareProduct,
+    FareRule,
+    FareTransferRule,
+    FeedInfo,
+    Frequency,
+    Level,
+    LocationGroup,
+    LocationGroupStop,
+    Network,
+    Pathway,
+    Route,
+    RouteNetwork,
+    Shape,
+    Stop,
+    StopArea,
+    StopTime,
+    Timeframe,
+    Transfer,
+    Translation,
+    Trip,
+);
+
+thread_local! {
+    /// Whether codes that are not part of the specification are parsed on this thread, see
+    /// [`LenientCodesScope`].
+    static LENIENT_CODES: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Makes the enumerations parsed on the current thread accept codes that are not part of the
+/// specification until it is dropped, see [`crate::ParseOptions::lenient_codes`].
+pub(crate) struct LenientCodesScope {
+    previous: bool,
+}
+
+impl LenientCodesScope {
+    /// Accepts unknown codes if `lenient` is `true`, rejects them otherwise.
+    pub(crate) fn enter(lenient: bool) -> Self {
+        LenientCodesScope {
+            previous: LENIENT_CODES.with(|codes| codes.replace(lenient)),
+        }
+    }
+}
+
+impl Drop for LenientCodesScope {
+    fn drop(&mut self) {
+        LENIENT_CODES.with(|codes| codes.set(self.previous));
+    }
+}
+
+/// Returns `true` if unknown codes are accepted on the current thread.
+pub(crate) fn accepts_unknown_codes() -> bool {
+    LENIENT_CODES.with(Cell::get)
+}
+
+/// Declares an enumeration of the integer codes of a column, such as `location_type`.
+///
+/// Each variant is given its code, and an `Unknown(u8)` variant is added for the codes that are
+/// not part of the specification. Those are only parsed within a [`LenientCodesScope`], and are
+/// written back as they were read.
+macro_rules! coded_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident = $code:literal,
+            )+
+        }
+    ) => {
+        coded_enum! {
+            $(#[$meta])*
+            $vis enum $name: u8 {
+                $(
+                    $(#[$variant_meta])*
+                    $variant = $code,
+                )+
+            }
+        }
+    };
+    // The codes are `u8` unless another integer type is given, e.g. `enum RouteType: u16`.
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident: $repr:ty {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident = $code:literal,
+            )+
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant,
+            )+
+            /// A code that is not part of the specification, only parsed with
+            /// [`crate::ParseOptions::lenient_codes`].
+            Unknown($repr),
+        }
+
+        impl $name {
+            /// Returns the code of the value, as written in the dataset.
+            pub fn code(&self) -> $repr {
+                match self {
+                    $($name::$variant => $code,)+
+                    $name::Unknown(code) => *code,
+                }
+            }
+
+            /// Returns the value of `code`, `Unknown` if it is not part of the specification.
+            pub fn from_code(code: $repr) -> Self {
+                match code {
+                    $($code => $name::$variant,)+
+                    code => $name::Unknown(code),
+                }
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serde::Serialize::serialize(&self.code(), serializer)
+            }
+        }
+
+        impl $crate::json::JsonValue for $name {
+            fn json_value(&self) -> Option<serde_json::Value> {
+                Some(self.code().into())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                let code = <$repr as serde::Deserialize>::deserialize(deserializer)?;
+                match $name::from_code(code) {
+                    $name::Unknown(code) if !$crate::schemas::accepts_unknown_codes() => {
+                        Err(<D::Error as serde::de::Error>::custom(format!(
+                            "invalid value: {}, expected one of: {}",
+                            code,
+                            [$(stringify!($code)),+].join(", ")
+                        )))
+                    }
+                    value => Ok(value),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use coded_enum;
+
+coded_enum! {
+    /// Indicates the type of the location.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum LocationType {
+        /// A location where passengers board or disembark from a transit vehicle.
+        /// Is called a platform when defined within a [`LocationType::Station`].
+        StopOrPlatform = 0,
+        /// A physical structure or area that contains one or more [`LocationType::StopOrPlatform`].
+        Station = 1,
+        /// A location where passengers can enter or exit a station from the street.
+        /// If an entrance/exit belongs to multiple stations, it may be linked by
+        /// pathways to both, but the data provider must pick one of them as parent.
+        EntranceOrExit = 2,
+        /// A location within a station, not matching any other [`LocationType`],
+        /// that may be used to link together pathways defined in [`crate::schemas::pathway::Pathway`].
+        GenericNode = 3,
+        /// A specific location on a platform, where passengers can board and/or alight vehicles.
+        BoardingArea = 4,
+    }
+}
+
+coded_enum! {
+    /// Indicates that the rider can board the transit vehicle at any point along
+    /// the vehicle's travel path as described by [`crate::schemas::shape::Shape`], on every trip of the route.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum ContinuousPickup {
+        /// Continuous stopping pickup.
+        ContinuousStopping = 0,
+        /// No continuous stopping pickup.
+        NoContinuousStopping = 1,
+        /// Must phone agency to arrange continuous stopping pickup.
+        PhoneAgencyToArrange = 2,
+        /// Must coordinate with driver to arrange continuous stopping pickup.
+        CoordinateWithDriver = 3,
+    }
+}
+
+coded_enum! {
+    /// Indicates that the rider can alight from the transit vehicle at any point along
+    /// the vehicle's travel path as described by [`crate::schemas::shape::Shape`], on every trip of the route.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum ContinuousDropOff {
+        /// Continuous stopping drop off.
+        ContinuousStopping = 0,
+        /// No continuous stopping drop off.
+        NoContinuousStopping = 1,
+        /// Must phone agency to arrange continuous stopping drop off.
+        PhoneAgencyToArrange = 2,
+        /// Must coordinate with driver to arrange continuous stopping drop off.
+        CoordinateWithDriver = 3,
+    }
 }
 
 /// Represents a time value in the GTFS format, allowing for times beyond 24 hours.
@@ -294,142 +570,306 @@ pub enum ContinuousDropOff {
 /// In GTFS, time values can exceed 24 hours to represent service days that go beyond midnight.
 /// For example, "25:35:00" represents 1:35 AM on the next service day.
 ///
-/// The `NaiveServiceTime` struct contains a `NaiveTime` value representing the actual time,
-/// and an `overflow` flag indicating whether the time exceeds 24 hours.
-/// For example, if the `time` field is 1:35 AM and the `overflow` flag is `true`,
-/// the actual time (in the GTFS format) would be "25:35:00".
-#[derive(Debug, Clone, Copy, Hash)]
+/// The time is stored as the number of seconds elapsed since the start of the service day, so
+/// that times of the following days (e.g. "49:10:00") are supported, and ordering and arithmetic
+/// are exact. It is displayed and serialized as `HH:MM:SS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct NaiveServiceTime {
-    pub time: NaiveTime,
-    pub overflow: bool,
+    secs: u32,
+}
+
+impl NaiveServiceTime {
+    /// Builds a `NaiveServiceTime` from hours, minutes and seconds, the hours possibly being 24
+    /// or more.
+    ///
+    /// Returns `None` if the minutes or seconds are not below 60, or if the time overflows.
+    pub fn from_hms(hours: u32, minutes: u32, seconds: u32) -> Option<Self> {
+        if minutes >= 60 || seconds >= 60 {
+            return None;
+        }
+        let secs = hours
+            .checked_mul(3600)?
+            .checked_add(minutes * 60 + seconds)?;
+        Some(NaiveServiceTime { secs })
+    }
+
+    /// Builds a `NaiveServiceTime` from a number of seconds elapsed since the start of the service day.
+    pub fn from_secs(secs: u32) -> Self {
+        NaiveServiceTime { secs }
+    }
+
+    /// Returns the number of seconds elapsed since the start of the service day.
+    pub fn total_seconds(&self) -> u32 {
+        self.secs
+    }
+
+    /// Same as [`NaiveServiceTime::total_seconds`].
+    pub fn as_secs(&self) -> u32 {
+        self.secs
+    }
+
+    /// Returns the hours, minutes and seconds of the time, the hours possibly being 24 or more.
+    pub fn hms(&self) -> (u32, u32, u32) {
+        (self.secs / 3600, self.secs / 60 % 60, self.secs % 60)
+    }
+
+    /// Returns the time of day, e.g. 01:35:00 for "25:35:00".
+    pub fn time_of_day(&self) -> NaiveTime {
+        NaiveTime::from_num_seconds_from_midnight_opt(self.secs % (24 * 3600), 0)
+            .expect("a number of seconds below a day is a valid time")
+    }
+
+    /// Adds a duration, `None` if the result overflows.
+    pub fn checked_add(self, duration: Duration) -> Option<Self> {
+        let secs = u32::try_from(duration.as_secs()).ok()?;
+        Some(NaiveServiceTime {
+            secs: self.secs.checked_add(secs)?,
+        })
+    }
+
+    /// Adds a duration, stopping at the largest time if the result overflows.
+    pub fn saturating_add(self, duration: Duration) -> Self {
+        let secs = u32::try_from(duration.as_secs()).unwrap_or(u32::MAX);
+        NaiveServiceTime {
+            secs: self.secs.saturating_add(secs),
+        }
+    }
+
+    /// Subtracts a duration, `None` if the result is before the start of the service day.
+    pub fn checked_sub(self, duration: Duration) -> Option<Self> {
+        let secs = u32::try_from(duration.as_secs()).ok()?;
+        Some(NaiveServiceTime {
+            secs: self.secs.checked_sub(secs)?,
+        })
+    }
+
+    /// Returns the duration elapsed from `earlier` to `self`, `None` if `earlier` is later.
+    pub fn duration_since(self, earlier: NaiveServiceTime) -> Option<Duration> {
+        Some(Duration::from_secs(u64::from(
+            self.secs.checked_sub(earlier.secs)?,
+        )))
+    }
 }
 
 impl TryFrom<&str> for NaiveServiceTime {
     type Error = Error;
 
     fn try_from(s: &str) -> std::result::Result<Self, Self::Error> {
-        let parts: Vec<_> = s.split(':').collect();
-        let hours: u32 = parts[0]
-            .parse()
-            .map_err(ParseErrorKind::from)
-            .map_err(ParseError::from)?;
-        let minutes: u32 = parts[1]
-            .parse()
-            .map_err(ParseErrorKind::from)
-            .map_err(ParseError::from)?;
-        let seconds: u32 = parts[2]
-            .parse()
-            .map_err(ParseErrorKind::from)
-            .map_err(ParseError::from)?;
+        let invalid_time =
+            || ParseError::from(ParseErrorKind::InvalidValue(format!("Invalid time: {}", s)));
+        let mut parts = s.trim().split(':');
+        let mut next_part = || -> std::result::Result<u32, Error> {
+            let part = parts.next().ok_or_else(invalid_time)?;
+            Ok(part
+                .parse()
+                .map_err(ParseErrorKind::from)
+                .map_err(ParseError::from)?)
+        };
+        let (hours, minutes, seconds) = (next_part()?, next_part()?, next_part()?);
+        if parts.next().is_some() {
+            return Err(invalid_time().into());
+        }
 
-        let overflow = hours >= 24;
-        let time = NaiveTime::from_hms_opt(hours % 24, minutes, seconds)
-            .ok_or_else(|| ParseErrorKind::InvalidValue(format!("Invalid time: {}", s)))
-            .map_err(ParseError::from)?;
+        Ok(NaiveServiceTime::from_hms(hours, minutes, seconds).ok_or_else(invalid_time)?)
+    }
+}
 
-        Ok(NaiveServiceTime { time, overflow })
+impl fmt::Display for NaiveServiceTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (hours, minutes, seconds) = self.hms();
+        write!(f, "{:02}:{:02}:{:02}", hours, minutes, seconds)
     }
 }
 
 impl From<NaiveServiceTime> for String {
     fn from(service_time: NaiveServiceTime) -> String {
-        let overflow_time = if service_time.overflow {
-            service_time.time
-        } else {
-            NaiveTime::from_hms_opt(0, 0, 0).unwrap()
-        };
-        let (hours_overflow, minutes_overflow, seconds_overflow) = (
-            overflow_time.hour(),
-            overflow_time.minute(),
-            overflow_time.second(),
-        );
-        let (hours, minutes, seconds) = (
-            service_time.time.hour(),
-            service_time.time.minute(),
-            service_time.time.second(),
-        );
-        let hours = hours + hours_overflow;
-        let minutes = minutes + minutes_overflow;
-        let seconds = seconds + seconds_overflow;
-        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+        service_time.to_string()
     }
 }
 
-impl PartialEq for NaiveServiceTime {
-    fn eq(&self, other: &Self) -> bool {
-        self.time == other.time && self.overflow == other.overflow
+impl Add<Duration> for NaiveServiceTime {
+    type Output = NaiveServiceTime;
+
+    /// Adds a duration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result overflows, see [`NaiveServiceTime::checked_add`].
+    fn add(self, rhs: Duration) -> Self::Output {
+        self.checked_add(rhs)
+            .expect("overflow when adding a duration to a NaiveServiceTime")
     }
 }
 
-impl Eq for NaiveServiceTime {}
+impl Sub for NaiveServiceTime {
+    type Output = Duration;
 
-impl PartialOrd for NaiveServiceTime {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    /// Returns the duration elapsed from `rhs` to `self`.
+    ///
+    /// The result saturates: it is zero, not negative, if `rhs` is later. Use
+    /// [`NaiveServiceTime::duration_since`] to tell both cases apart.
+    fn sub(self, rhs: NaiveServiceTime) -> Self::Output {
+        Duration::from_secs(u64::from(self.secs.saturating_sub(rhs.secs)))
     }
 }
 
-impl Ord for NaiveServiceTime {
-    fn cmp(&self, other: &Self) -> Ordering {
-        let (self_h, self_m, self_s) = (self.time.hour(), self.time.minute(), self.time.second());
-        let (other_h, other_m, other_s) =
-            (other.time.hour(), other.time.minute(), other.time.second());
+impl<'de> Deserialize<'de> for NaiveServiceTime {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct NaiveServiceTimeVisitor;
+
+        impl Visitor<'_> for NaiveServiceTimeVisitor {
+            type Value = NaiveServiceTime;
 
-        if self_h < other_h {
-            return Ordering::Less;
-        } else if self_h > other_h {
-            return Ordering::Greater;
-        }
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a time in the HH:MM:SS format")
+            }
 
-        if self_m < other_m {
-            return Ordering::Less;
-        } else if self_m > other_m {
-            return Ordering::Greater;
+            fn visit_str<E: DeError>(self, value: &str) -> std::result::Result<Self::Value, E> {
+                NaiveServiceTime::try_from(value).map_err(E::custom)
+            }
         }
 
-        if self_s < other_s {
-            return Ordering::Less;
-        } else if self_s > other_s {
-            return Ordering::Greater;
+        // Times are parsed from the text of the cell, without copying it.
+        deserializer.deserialize_str(NaiveServiceTimeVisitor)
+    }
+}
+
+impl Serialize for NaiveServiceTime {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// An amount in an ISO 4217 currency, such as a fare price.
+///
+/// The amount is a decimal, so that prices such as `2.30` are represented exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Money {
+    /// The amount, in the major unit of the currency (e.g. dollars, not cents).
+    pub amount: Decimal,
+    /// The currency of the amount.
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: Currency) -> Self {
+        Money { amount, currency }
+    }
+
+    /// Returns `true` if the amount has no more decimal places than the currency allows,
+    /// e.g. two for `USD` and none for `JPY`.
+    ///
+    /// Currencies without a minor unit, such as gold, allow any number of decimal places.
+    pub fn has_valid_precision(&self) -> bool {
+        self.currency
+            .exponent()
+            .is_none_or(|exponent| self.amount.normalize().scale() <= u32::from(exponent))
+    }
+
+    /// Returns the amount in the minor unit of the currency (e.g. cents for `USD`, yens for
+    /// `JPY`).
+    ///
+    /// Returns `None` if the amount does not have a valid precision, or does not fit.
+    pub fn minor_units(&self) -> Option<i64> {
+        if !self.has_valid_precision() {
+            return None;
         }
+        let exponent = u32::from(self.currency.exponent().unwrap_or(0));
+        self.amount
+            .checked_mul(Decimal::from(10_i64.checked_pow(exponent)?))?
+            .to_i64()
+    }
+}
 
-        Ordering::Equal
+impl fmt::Display for Money {
+    /// Formats the amount with the decimal places of the currency, e.g. `2.50 USD` or `500 JPY`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut amount = self.amount;
+        if let Some(exponent) = self.currency.exponent() {
+            if amount.scale() < u32::from(exponent) {
+                amount.rescale(u32::from(exponent));
+            }
+        }
+        write!(f, "{} {}", amount, self.currency.code())
     }
 }
 
-impl Add<Duration> for NaiveServiceTime {
-    type Output = NaiveServiceTime;
+/// A voice telephone number, as typically presented in the service area of the dataset.
+///
+/// The number is kept as written, punctuation included, since GTFS does not require an
+/// international format: "503-238-RIDE" is as valid as "+1 503 238 7433". It must only contain
+/// dialable text, i.e. at least a digit, and otherwise letters, spaces and the usual punctuation
+/// marks. Use [`Phone::to_phone_number`] to get a normalized number.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Phone(String);
+
+impl Phone {
+    /// The number, as written.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 
-    fn add(self, rhs: Duration) -> Self::Output {
-        let (self_h, self_m, self_s) = (self.time.hour(), self.time.minute(), self.time.second());
-        let self_as_secs = self_h * 3600 + self_m * 60 + self_s;
-        let add = u32::try_from(rhs.as_secs()).unwrap() + self_as_secs;
-        let (hours, minutes, seconds) = (add / 3600, add / 60 % 60, add % 60);
-        let overflow = hours >= 24;
-        let time = NaiveTime::from_hms_opt(hours % 24, minutes, seconds)
-            .expect(format!("Could not generate a valid chrono::NaiveTime from the addition of NaiveServiceTime and Duration: {:?} + {:?}; this may be a bug in the library", self, rhs).as_str());
+    /// Parses the number with the numbering plan of `country`, which is only needed when the
+    /// number is not in the international format.
+    ///
+    /// Returns `None` if the number is not valid in that numbering plan, e.g. when it uses
+    /// letters.
+    pub fn to_phone_number(
+        &self,
+        country: Option<phonenumber::country::Id>,
+    ) -> Option<phonenumber::PhoneNumber> {
+        phonenumber::parse(country, &self.0)
+            .ok()
+            .filter(|number| number.is_valid())
+    }
+}
+
+impl FromStr for Phone {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let dialable = s.chars().any(|c| c.is_ascii_digit())
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || " +-.()/#*,;".contains(c));
+        if !dialable {
+            return Err(ParseError::from(ParseErrorKind::InvalidValue(format!(
+                "{:?} is not a dialable telephone number",
+                s
+            )))
+            .into());
+        }
+        Ok(Phone(s.trim().to_string()))
+    }
+}
 
-        NaiveServiceTime { time, overflow }
+impl fmt::Display for Phone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
     }
 }
 
-impl<'de> Deserialize<'de> for NaiveServiceTime {
+impl<'de> Deserialize<'de> for Phone {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        NaiveServiceTime::try_from(s.as_str()).map_err(serde::de::Error::custom)
+        Phone::from_str(&s).map_err(DeError::custom)
     }
 }
 
-impl Serialize for NaiveServiceTime {
+impl Serialize for Phone {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
-        S: serde::Serializer,
+        S: Serializer,
     {
-        let s: String = (*self).into();
-        serializer.serialize_str(s.as_str())
+        serializer.serialize_str(&self.0)
     }
 }
 
@@ -485,6 +925,69 @@ where
     }
 }
 
+/// Parses a BCP 47 language tag, such as `en` or `pt-BR`.
+fn parse_language_tag<E: DeError>(tag: String) -> std::result::Result<LanguageTag<String>, E> {
+    LanguageTag::parse(tag.clone()).map_err(|err| {
+        DeError::custom(format!(
+            "{:?} is not a valid BCP 47 language tag, such as \"en\" or \"pt-BR\": {}",
+            tag, err
+        ))
+    })
+}
+
+/// Custom deserialization function for language tags, reporting the invalid tag.
+pub fn deserialize_language_tag<'de, D>(
+    deserializer: D,
+) -> std::result::Result<LanguageTag<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    parse_language_tag(String::deserialize(deserializer)?)
+}
+
+/// Custom deserialization function for optional language tags, reporting the invalid tag.
+pub fn deserialize_optional_language_tag<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<LanguageTag<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(parse_language_tag)
+        .transpose()
+}
+
+/// Custom serialization function for Option<Duration> to a number of minutes
+pub fn serialize_optional_minutes<S>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match duration {
+        Some(d) => serializer.serialize_some(&(d.as_secs() / 60)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Custom deserialization function for a number of minutes to Option<Duration>
+pub fn deserialize_optional_minutes<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let opt = Option::<u64>::deserialize(deserializer)?;
+    opt.map(|minutes| {
+        minutes
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or_else(|| D::Error::custom(format!("{} minutes is too long", minutes)))
+    })
+    .transpose()
+}
+
 /// Custom deserialization function for 0/1 to bool
 pub fn deserialize_bool_as_int<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
 where
@@ -500,6 +1003,23 @@ where
     }
 }
 
+/// Custom deserialization function for an optional 0/1 to bool, a missing value being `false`
+pub fn deserialize_optional_bool_as_int<'de, D>(
+    deserializer: D,
+) -> std::result::Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<u8>::deserialize(deserializer)? {
+        None | Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        Some(other) => Err(serde::de::Error::invalid_value(
+            serde::de::Unexpected::Unsigned(other as u64),
+            &"zero, one or empty",
+        )),
+    }
+}
+
 /// Custom serialization function for bool to 0/1
 pub fn serialize_bool_as_int<S>(value: &bool, serializer: S) -> std::result::Result<S::Ok, S::Error>
 where
@@ -638,6 +1158,12 @@ fn parse_float(value: &serde_json::Value) -> Result<f64, String> {
     }
 }
 
+impl<const COORD_TYPE: coord_type::T> From<Coord> for GtfsCoord<COORD_TYPE> {
+    fn from(coord: Coord) -> Self {
+        GtfsCoord(coord)
+    }
+}
+
 // Implement Deref and DerefMut to make GtfsCoord behave like Coord
 impl<const COORD_TYPE: coord_type::T> Deref for GtfsCoord<COORD_TYPE> {
     type Target = Coord;
@@ -6,57 +6,68 @@
 //! - [`FarePaymentMethod`]: Indicates when the fare must be paid.
 //! - [`FareTransfers`]: Indicates the number of transfers permitted on this fare.
 
+use std::sync::Arc;
 use std::time::Duration;
 
-use gtfs_schedule_macros::StringWrapper;
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use iso_currency::Currency;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use serde_repr::*;
 use serde_with::skip_serializing_none;
+use serde_with::{serde_as, DurationSeconds};
 
 use crate::{
     error::{Result, SchemaValidationError},
-    schemas::{AgencyId, Schema},
+    schemas::{coded_enum, AgencyId, Money, Schema},
 };
 
 /// Identifies a fare class.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct FareId(pub String);
+pub struct FareId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
-/// Indicates when the fare must be paid.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum FarePaymentMethod {
-    /// Fare is paid on board.
-    OnBoard = 0,
-    /// Fare must be paid before boarding.
-    BeforeBoarding = 1,
+coded_enum! {
+    /// Indicates when the fare must be paid.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum FarePaymentMethod {
+        /// Fare is paid on board.
+        OnBoard = 0,
+        /// Fare must be paid before boarding.
+        BeforeBoarding = 1,
+    }
 }
 
-/// Indicates the number of transfers permitted on this fare.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum FareTransfers {
-    /// No transfers permitted on this fare.
-    NoTransfers = 0,
-    /// Riders may transfer once.
-    OneTransfer = 1,
-    /// Riders may transfer twice.
-    TwoTransfers = 2,
-    /// Unlimited transfers are permitted.
-    UnlimitedTransfers,
+coded_enum! {
+    /// Indicates the number of transfers permitted on this fare.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum FareTransfers {
+        /// No transfers permitted on this fare.
+        NoTransfers = 0,
+        /// Riders may transfer once.
+        OneTransfer = 1,
+        /// Riders may transfer twice.
+        TwoTransfers = 2,
+        /// Unlimited transfers are permitted.
+        UnlimitedTransfers = 3,
+    }
 }
 
 /// Represents fare information.
 ///
 /// See [fare_attributes.txt](https://gtfs.org/schedule/reference/#fare_attributestxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "fare_attributes.txt", primary_key = "fare_id")]
 #[skip_serializing_none]
 pub struct FareAttribute {
     /// Identifies a fare class.
+    #[gtfs(required)]
     pub fare_id: FareId,
     /// Fare price, in the unit specified by [`FareAttribute::currency_type`].
-    pub price: f32,
+    ///
+    /// Must not have more decimal places than the currency allows, see [`Money`].
+    #[gtfs(non_negative)]
+    #[serde(with = "rust_decimal::serde::str")]
+    pub price: Decimal,
     /// Currency used to pay the fare.
     pub currency_type: Currency,
     /// Indicates when the fare must be paid.
@@ -70,27 +81,32 @@ pub struct FareAttribute {
     /// - Recommended otherwise.
     pub agency_id: Option<AgencyId>,
     /// Length of time in seconds before a transfer expires.
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
     pub transfer_duration: Option<Duration>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl FareAttribute {
+    /// Returns the price along with its currency.
+    pub fn money(&self) -> Money {
+        Money::new(self.price, self.currency_type)
+    }
+
     /// Validates if the FareAttribute is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate fare_id.
-        if self.fare_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "fare_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
+        self.validate_fields()?;
 
         // Validate price.
-        if self.price < 0.0 {
+        if !self.money().has_valid_precision() {
             return Err(SchemaValidationError::new_invalid_value(
                 "price".to_string(),
-                Some("cannot be negative".to_string()),
+                Some(format!(
+                    "has more decimal places than {} allows",
+                    self.currency_type.code()
+                )),
                 Schema::from(self.clone()),
             )
             .into());
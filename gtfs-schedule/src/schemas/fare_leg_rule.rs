@@ -4,26 +4,26 @@
 //! - [`FareLegRule`]: Represents a fare leg rule.
 //! - [`FareLegRuleId`]: Identifies a group of entries in `fare_leg_rules.txt`.
 
-use gtfs_schedule_macros::StringWrapper;
+use std::sync::Arc;
+
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
 use crate::schemas::timeframe::TimeframeGroupId;
-use crate::{
-    error::{Result, SchemaValidationError},
-    schemas::fare_product::FareProductId,
-};
+use crate::{error::Result, schemas::fare_product::FareProductId};
 
-use super::{AreaId, NetworkId, Schema};
+use super::{AreaId, NetworkId};
 
 /// Identifies a group of entries in `fare_leg_rules.txt`.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct FareLegRuleId(pub String);
+pub struct FareLegRuleId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
 /// Represents a fare leg rule.
 ///
 /// See [fare_leg_rules.txt](https://gtfs.org/schedule/reference/#fare_leg_rulestxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "fare_leg_rules.txt")]
 #[skip_serializing_none]
 pub struct FareLegRule {
     /// Identifies a group of entries in `fare_leg_rules.txt`.
@@ -39,24 +39,19 @@ pub struct FareLegRule {
     /// Defines the timeframe for the fare validation event at the end of the fare leg.
     pub to_timeframe_group_id: Option<TimeframeGroupId>,
     /// The fare product required to travel the leg.
+    #[gtfs(required)]
     pub fare_product_id: FareProductId,
     /// Defines the order of priority in which matching rules are applied to legs.
     pub rule_priority: Option<u32>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl FareLegRule {
     /// Validates if the FareLegRule is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate fare_product_id.
-        if self.fare_product_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "fare_product_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        Ok(())
+        self.validate_fields()
     }
 }
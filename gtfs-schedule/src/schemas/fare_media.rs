@@ -5,35 +5,37 @@
 //! - [`FareMediaId`]: Identifies a fare media.
 //! - [`FareMediaType`]: The type of fare media.
 
-use gtfs_schedule_macros::StringWrapper;
+use std::sync::Arc;
+
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
-use serde_repr::*;
 use serde_with::skip_serializing_none;
 
-use super::Schema;
-use crate::error::{Result, SchemaValidationError};
+use super::coded_enum;
+use crate::error::Result;
 
 /// Identifies a fare media.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct FareMediaId(pub String);
+pub struct FareMediaId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
-/// The type of fare media.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum FareMediaType {
-    /// Used when there is no fare media involved in purchasing or validating,
-    /// such as paying cash to a driver or conductor with no physical ticket provided.
-    None = 0,
-    /// Physical paper ticket that allows a passenger to take either a certain number
-    /// of pre-purchased trips or unlimited trips within a fixed period of time.
-    Paper = 1,
-    /// Physical transit card that has stored tickets, passes or monetary value.
-    TransitCard = 2,
-    /// cEMV (contactless Europay, Mastercard and Visa) as an open-loop token
-    /// container for account-based ticketing.
-    PaymentCard = 3,
-    /// Mobile app that have stored virtual transit cards, tickets, passes, or monetary value.
-    MobileApp = 4,
+coded_enum! {
+    /// The type of fare media.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum FareMediaType {
+        /// Used when there is no fare media involved in purchasing or validating,
+        /// such as paying cash to a driver or conductor with no physical ticket provided.
+        None = 0,
+        /// Physical paper ticket that allows a passenger to take either a certain number
+        /// of pre-purchased trips or unlimited trips within a fixed period of time.
+        Paper = 1,
+        /// Physical transit card that has stored tickets, passes or monetary value.
+        TransitCard = 2,
+        /// cEMV (contactless Europay, Mastercard and Visa) as an open-loop token
+        /// container for account-based ticketing.
+        PaymentCard = 3,
+        /// Mobile app that have stored virtual transit cards, tickets, passes, or monetary value.
+        MobileApp = 4,
+    }
 }
 
 /// Represents a fare media.
@@ -43,10 +45,12 @@ pub enum FareMediaType {
 /// validation of a fare product.
 ///
 /// See [fare_media.txt](https://gtfs.org/schedule/reference/#fare_mediatxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "fare_media.txt", primary_key = "fare_media_id")]
 #[skip_serializing_none]
 pub struct FareMedia {
     /// Identifies a fare media.
+    #[gtfs(required)]
     pub fare_media_id: FareMediaId,
     /// Name of the fare media.
     ///
@@ -56,21 +60,15 @@ pub struct FareMedia {
     pub fare_media_name: Option<String>,
     /// The type of fare media.
     pub fare_media_type: FareMediaType,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl FareMedia {
     /// Validates if the FareMedia is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate fare_media_id.
-        if self.fare_media_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "fare_media_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        Ok(())
+        self.validate_fields()
     }
 }
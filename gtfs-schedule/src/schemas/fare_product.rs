@@ -4,12 +4,15 @@
 //! - [`FareProduct`]: Represents a fare product.
 //! - [`FareProductId`]: Identifies a fare product or set of fare products.
 
-use gtfs_schedule_macros::StringWrapper;
+use std::sync::Arc;
+
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use iso_currency::Currency;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
-use super::Schema;
+use super::{Money, Schema};
 use crate::{
     error::{Result, SchemaValidationError},
     schemas::fare_media::FareMediaId,
@@ -23,7 +26,7 @@ use crate::{
 /// Multiple records may share the same [`FareProductId`] but with different [`FareMediaId`],
 /// indicating various methods available for employing the fare product, potentially at different prices.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct FareProductId(pub String);
+pub struct FareProductId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
 /// Represents a fare product.
 ///
@@ -31,10 +34,15 @@ pub struct FareProductId(pub String);
 /// account when computing the total fare for journeys with multiple legs, such as transfer costs.
 ///
 /// See [fare_products.txt](https://gtfs.org/schedule/reference/#fare_productstxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(
+    file = "fare_products.txt",
+    primary_key = "fare_product_id, fare_media_id"
+)]
 #[skip_serializing_none]
 pub struct FareProduct {
     /// Identifies a fare product or set of fare products.
+    #[gtfs(required)]
     pub fare_product_id: FareProductId,
     /// The name of the fare product as displayed to riders.
     pub fare_product_name: Option<String>,
@@ -43,19 +51,36 @@ pub struct FareProduct {
     pub fare_media_id: Option<FareMediaId>,
     /// The cost of the fare product. May be negative to represent transfer discounts.
     /// May be zero to represent a fare product that is free.
-    pub amount: f32,
+    ///
+    /// Must not have more decimal places than the currency allows, see [`Money`].
+    #[serde(with = "rust_decimal::serde::str")]
+    pub amount: Decimal,
     /// The currency of the cost of the fare product.
     pub currency: Currency,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl FareProduct {
+    /// Returns the cost of the fare product along with its currency.
+    pub fn money(&self) -> Money {
+        Money::new(self.amount, self.currency)
+    }
+
     /// Validates if the FareProduct is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate fare_product_id.
-        if self.fare_product_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "fare_product_id".to_string(),
-                Some("can never be empty".to_string()),
+        self.validate_fields()?;
+
+        // Validate amount.
+        if !self.money().has_valid_precision() {
+            return Err(SchemaValidationError::new_invalid_value(
+                "amount".to_string(),
+                Some(format!(
+                    "has more decimal places than {} allows",
+                    self.currency.code()
+                )),
                 Schema::from(self.clone()),
             )
             .into());
@@ -8,11 +8,11 @@
 
 use std::num::NonZeroUsize;
 
+use gtfs_schedule_macros::GtfsTable;
 use serde::{Deserialize, Serialize};
-use serde_repr::*;
 use serde_with::skip_serializing_none;
 
-use super::{FareLegRuleId, FareProductId, Schema};
+use super::{coded_enum, FareLegRuleId, FareProductId, Schema};
 use crate::error::{Result, SchemaValidationError};
 
 /// Defines the duration limit of the transfer.
@@ -24,33 +24,35 @@ pub enum FareTransferRuleDurationLimit {
     Unlimited,
 }
 
-/// Defines the relative start and end of [`FareTransferRule::duration_limit`].
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum DurationLimitType {
-    /// Between the departure fare validation of the current leg and the arrival fare validation of the next leg.
-    BetweenDepartureAndArrival = 0,
-    /// Between the departure fare validation of the current leg and the departure fare validation of the next leg.
-    BetweenDepartureAndDeparture = 1,
-    /// Between the arrival fare validation of the current leg and the departure fare validation of the next leg.
-    BetweenArrivalAndDeparture = 2,
-    /// Between the arrival fare validation of the current leg and the arrival fare validation of the next leg.
-    BetweenArrivalAndArrival = 3,
+coded_enum! {
+    /// Defines the relative start and end of [`FareTransferRule::duration_limit`].
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum DurationLimitType {
+        /// Between the departure fare validation of the current leg and the arrival fare validation of the next leg.
+        BetweenDepartureAndArrival = 0,
+        /// Between the departure fare validation of the current leg and the departure fare validation of the next leg.
+        BetweenDepartureAndDeparture = 1,
+        /// Between the arrival fare validation of the current leg and the departure fare validation of the next leg.
+        BetweenArrivalAndDeparture = 2,
+        /// Between the arrival fare validation of the current leg and the arrival fare validation of the next leg.
+        BetweenArrivalAndArrival = 3,
+    }
 }
 
-/// Indicates the cost processing method of transferring between legs in a journey.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum FareTransferType {
-    /// From-leg [`crate::schemas::fare_leg_rule::FareLegRule::fare_product_id`]
-    /// plus [`FareTransferRule::fare_product_id`]; A + AB.
-    FromLegPlusTransfer = 0,
-    /// From-leg [`crate::schemas::fare_leg_rule::FareLegRule::fare_product_id`]
-    /// plus [`FareTransferRule::fare_product_id`] plus to-leg
-    /// [`crate::schemas::fare_leg_rule::FareLegRule::fare_product_id`]; A + AB + B.
-    FromLegPlusTransferPlusToLeg = 1,
-    /// [`FareTransferRule::fare_product_id`]; AB.
-    TransferOnly = 2,
+coded_enum! {
+    /// Indicates the cost processing method of transferring between legs in a journey.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum FareTransferType {
+        /// From-leg [`crate::schemas::fare_leg_rule::FareLegRule::fare_product_id`]
+        /// plus [`FareTransferRule::fare_product_id`]; A + AB.
+        FromLegPlusTransfer = 0,
+        /// From-leg [`crate::schemas::fare_leg_rule::FareLegRule::fare_product_id`]
+        /// plus [`FareTransferRule::fare_product_id`] plus to-leg
+        /// [`crate::schemas::fare_leg_rule::FareLegRule::fare_product_id`]; A + AB + B.
+        FromLegPlusTransferPlusToLeg = 1,
+        /// [`FareTransferRule::fare_product_id`]; AB.
+        TransferOnly = 2,
+    }
 }
 
 /// Represents a fare transfer rule.
@@ -58,7 +60,8 @@ pub enum FareTransferType {
 /// Fare rules for transfers between legs of travel defined in [`crate::schemas::fare_leg_rule::FareLegRule`].
 ///
 /// See [fare_transfer_rules.txt](https://gtfs.org/schedule/reference/#fare_transfer_rulestxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable)]
+#[gtfs(file = "fare_transfers.txt")]
 #[skip_serializing_none]
 pub struct FareTransferRule {
     /// Identifies a group of pre-transfer fare leg rules.
@@ -75,6 +78,10 @@ pub struct FareTransferRule {
     pub fare_transfer_type: FareTransferType,
     /// The fare product required to transfer between two fare legs. If empty, the cost of the transfer rule is 0.
     pub fare_product_id: Option<FareProductId>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl FareTransferRule {
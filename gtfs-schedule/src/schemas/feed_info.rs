@@ -5,12 +5,16 @@
 
 use chrono::NaiveDate;
 use email_address::EmailAddress;
+use gtfs_schedule_macros::GtfsTable;
 use oxilangtag::LanguageTag;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use url::Url;
 
-use super::{deserialize_optional_date, serialize_optional_date, Schema};
+use super::{
+    deserialize_language_tag, deserialize_optional_date, deserialize_optional_language_tag,
+    serialize_optional_date, Schema,
+};
 use crate::error::{Result, SchemaValidationError};
 
 /// Represents dataset metadata.
@@ -19,7 +23,8 @@ use crate::error::{Result, SchemaValidationError};
 /// that the dataset describes. In some cases, the publisher of the dataset is a different entity than any of the agencies.
 ///
 /// See [feed_info.txt](https://gtfs.org/schedule/reference/#feed_infotxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable)]
+#[gtfs(file = "feed_info.txt")]
 #[skip_serializing_none]
 pub struct FeedInfo {
     /// Full name of the organization that publishes the dataset. This may be
@@ -46,9 +51,11 @@ pub struct FeedInfo {
     /// be provided in [`crate::schemas::translation::Translation`], in German: "Genf", "Zürich" and
     /// "Biel"; in French: "Genève", "Zurich" and "Bienne"; in Italian: "Ginevra", "Zurigo" and
     /// "Bienna"; and in English: "Geneva", "Zurich" and "Biel/Bienne".
+    #[serde(deserialize_with = "deserialize_language_tag")]
     pub feed_lang: LanguageTag<String>,
     /// Defines the language that should be used when the data consumer doesn't know the
     /// language of the rider. It will often be `en` (English).
+    #[serde(deserialize_with = "deserialize_optional_language_tag", default)]
     pub default_lang: Option<LanguageTag<String>>,
     /// The dataset provides complete and reliable schedule information for service in the
     /// period from the beginning of the [`FeedInfo::feed_start_date`] day to the end of
@@ -90,6 +97,10 @@ pub struct FeedInfo {
     /// information through [`crate::schemas::agency::Agency`]. It's recommended that at least
     /// one of [`FeedInfo::feed_contact_url`] or [`FeedInfo::feed_contact_email`] are provided.
     pub feed_contact_url: Option<Url>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl FeedInfo {
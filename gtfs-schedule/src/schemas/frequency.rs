@@ -6,7 +6,9 @@
 
 use std::time::Duration;
 
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate};
 use serde::{Deserialize, Serialize};
+use serde_repr::Serialize_repr;
 use serde_with::skip_serializing_none;
 use serde_with::{serde_as, DurationSeconds};
 
@@ -15,10 +17,11 @@ use crate::error::{Result, SchemaValidationError};
 use super::{NaiveServiceTime, Schema, TripId};
 
 /// Indicates the type of service for a trip.
-#[derive(Serialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize_repr, Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
 #[repr(u8)]
 pub enum ExactTimes {
     /// Frequency-based trips.
+    #[default]
     Approximate = 0,
     /// Schedule-based trips with the exact same headway throughout the day.
     /// In this case the [`Frequency::end_time`] value must be greater than the last desired
@@ -60,10 +63,12 @@ impl<'de> Deserialize<'de> for ExactTimes {
 ///
 /// See [frequencies.txt](https://gtfs.org/schedule/reference/#frequenciestxt) for more details.
 #[serde_as]
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "frequencies.txt", primary_key = "trip_id, start_time")]
 #[skip_serializing_none]
 pub struct Frequency {
     /// Identifies a trip to which the specified headway of service applies.
+    #[gtfs(required)]
     pub trip_id: TripId,
     /// Time at which the first vehicle departs from the first stop of the trip with the specified headway.
     pub start_time: NaiveServiceTime,
@@ -74,34 +79,55 @@ pub struct Frequency {
     pub headway_secs: Duration,
     /// Indicates the type of service for a trip.
     pub exact_times: Option<ExactTimes>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Frequency {
+    /// Returns the type of service, [`ExactTimes::Approximate`] when not given.
+    pub fn service_type(&self) -> ExactTimes {
+        self.exact_times.unwrap_or_default()
+    }
+
+    /// Returns when the runs leave the first stop of the trip: every
+    /// [`Frequency::headway_secs`] from [`Frequency::start_time`], until
+    /// [`Frequency::end_time`] excluded.
+    ///
+    /// The departures of [`ExactTimes::Approximate`] service only show the headway riders can
+    /// expect, vehicles not following a fixed schedule.
+    pub fn departures(&self) -> impl Iterator<Item = NaiveServiceTime> + '_ {
+        let headway = self.headway_secs.max(Duration::from_secs(1));
+        std::iter::successors(Some(self.start_time), move |time| time.checked_add(headway))
+            .take_while(|time| *time < self.end_time)
+    }
+
     /// Validates if the Frequency is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        if self.trip_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "trip_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
+        self.validate_fields()?;
 
         // Validate start_time and end_time.
-        if self.start_time > self.end_time {
-            return Err(SchemaValidationError::new_invalid_value(
-                "start_time or end_time".to_string(),
-                Some("start_time cannot be greater than end_time".to_string()),
+        if self.start_time.as_secs() >= self.end_time.as_secs() {
+            return Err(SchemaValidationError::new_frequency_window_out_of_order(
+                String::from(self.start_time),
+                String::from(self.end_time),
                 Schema::from(self.clone()),
             )
             .into());
         }
 
+        // Validate headway_secs.
+        if self.headway_secs.as_secs() == 0 {
+            return Err(
+                SchemaValidationError::new_non_positive_headway(Schema::from(self.clone())).into(),
+            );
+        }
+
         // Validate exact_times.
-        if self.exact_times == Some(ExactTimes::Exact) {
-            let last_start_time = self.start_time + self.headway_secs;
-            if last_start_time >= self.end_time {
+        if self.service_type() == ExactTimes::Exact {
+            let last_start_time = self.start_time.checked_add(self.headway_secs);
+            if last_start_time.is_none_or(|last_start_time| last_start_time >= self.end_time) {
                 return Err(SchemaValidationError::new_invalid_value(
                     "end_time".to_string(),
                     Some("the last start_time as computed from start_time + headway_secs must be less than end_time".to_string()),
@@ -4,24 +4,27 @@
 //! - [`Level`]: Represents a level within a station.
 //! - [`LevelId`]: Identifies a level in a station.
 
-use gtfs_schedule_macros::StringWrapper;
+use std::sync::Arc;
+
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
-use super::Schema;
-use crate::error::{Result, SchemaValidationError};
+use crate::error::Result;
 
 /// Identifies a level in a station.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct LevelId(pub String);
+pub struct LevelId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
 /// Represents a level within a station.
 ///
 /// See [levels.txt](https://gtfs.org/schedule/reference/#levelstxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "levels.txt", primary_key = "level_id")]
 #[skip_serializing_none]
 pub struct Level {
     /// Identifies a level in a station.
+    #[gtfs(required)]
     pub level_id: LevelId,
     /// Numeric index of the level that indicates its relative position.
     /// Ground level should have index 0, with levels above ground indicated by positive indices
@@ -31,21 +34,15 @@ pub struct Level {
     ///
     /// Example: "Mezzanine", "Platform" or "-1".
     pub level_name: Option<String>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Level {
     /// Validates if the Level is valid in regards to the GTFS specification constraints.
     pub fn validate(&mut self) -> Result<()> {
-        // Validate level_id.
-        if self.level_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "level_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        Ok(())
+        self.validate_fields()
     }
 }
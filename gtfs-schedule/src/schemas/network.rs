@@ -4,42 +4,39 @@
 //! - [`Network`]: Defines network identifiers that apply for fare leg rules.
 //! - [`NetworkId`]: Identifies a network.
 
-use gtfs_schedule_macros::StringWrapper;
+use std::sync::Arc;
+
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
-use super::Schema;
-use crate::error::{Result, SchemaValidationError};
+use crate::error::Result;
 
 /// Identifies a network. Must be unique in [`Network`].
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct NetworkId(pub String);
+pub struct NetworkId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
 /// Defines network identifiers that apply for fare leg rules.
 ///
 /// See [networks.txt](https://gtfs.org/schedule/reference/#networkstxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "networks.txt", primary_key = "network_id")]
 #[skip_serializing_none]
 pub struct Network {
     /// Identifies a network.
+    #[gtfs(required)]
     pub network_id: NetworkId,
     /// The name of the network that apply for fare leg rules, as used by the local agency and its riders.
     pub network_name: Option<String>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Network {
     /// Validates if the Network is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate network_id.
-        if self.network_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "network_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        Ok(())
+        self.validate_fields()
     }
 }
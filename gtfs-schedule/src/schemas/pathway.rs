@@ -5,14 +5,15 @@
 //! - [`PathwayId`]: Identifies a pathway.
 //! - [`PathwayMode`]: Type of pathway between the specified (from_stop_id, to_stop_id) pair.
 
+use std::sync::Arc;
 use std::time::Duration;
 
-use gtfs_schedule_macros::StringWrapper;
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
-use serde_repr::*;
 use serde_with::{serde_as, skip_serializing_none, DurationSeconds};
 
 use super::{
+    coded_enum,
     common::{deserialize_bool_as_int, serialize_bool_as_int},
     Schema, StopId,
 };
@@ -24,42 +25,45 @@ use crate::error::{Result, SchemaValidationError};
 ///
 /// Example: When two escalators are side-by-side in opposite directions, or when a stair set and elevator go from the same place to the same place, different [`PathwayId`] may have the same [`Pathway::from_stop_id`] and [`Pathway::to_stop_id`] values.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct PathwayId(pub String);
+pub struct PathwayId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
-/// Type of pathway between the specified (from_stop_id, to_stop_id) pair.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum PathwayMode {
-    /// Walkway.
-    Walkway = 1,
-    /// Stairs.
-    Stairs = 2,
-    /// Moving sidewalk/travelator.
-    MovingSidewalk = 3,
-    /// Escalator.
-    Escalator = 4,
-    /// Elevator.
-    Elevator = 5,
-    /// Fare gate (or payment gate): A pathway that crosses into an area of
-    /// the station where proof of payment is required to cross. Fare gates may
-    /// separate paid areas of the station from unpaid ones, or separate different
-    /// payment areas within the same station from each other. This information
-    /// can be used to avoid routing passengers through stations using shortcuts
-    /// that would require passengers to make unnecessary payments, like directing
-    /// a passenger to walk through a subway platform to reach a busway.
-    FareGate = 6,
-    /// Exit gate: A pathway exiting a paid area into an unpaid area where proof of payment is not required to cross.
-    ExitGate = 7,
+coded_enum! {
+    /// Type of pathway between the specified (from_stop_id, to_stop_id) pair.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum PathwayMode {
+        /// Walkway.
+        Walkway = 1,
+        /// Stairs.
+        Stairs = 2,
+        /// Moving sidewalk/travelator.
+        MovingSidewalk = 3,
+        /// Escalator.
+        Escalator = 4,
+        /// Elevator.
+        Elevator = 5,
+        /// Fare gate (or payment gate): A pathway that crosses into an area of
+        /// the station where proof of payment is required to cross. Fare gates may
+        /// separate paid areas of the station from unpaid ones, or separate different
+        /// payment areas within the same station from each other. This information
+        /// can be used to avoid routing passengers through stations using shortcuts
+        /// that would require passengers to make unnecessary payments, like directing
+        /// a passenger to walk through a subway platform to reach a busway.
+        FareGate = 6,
+        /// Exit gate: A pathway exiting a paid area into an unpaid area where proof of payment is not required to cross.
+        ExitGate = 7,
+    }
 }
 
 /// Represents a pathway linking together locations within stations.
 ///
 /// See [pathways.txt](https://gtfs.org/schedule/reference/#pathwaystxt) for more details.
 #[serde_as]
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "pathways.txt", primary_key = "pathway_id")]
 #[skip_serializing_none]
 pub struct Pathway {
     /// Identifies a pathway.
+    #[gtfs(required)]
     pub pathway_id: PathwayId,
     /// Location at which the pathway begins.
     pub from_stop_id: StopId,
@@ -122,20 +126,16 @@ pub struct Pathway {
     pub signposted_as: Option<String>,
     /// Same as [`Pathway::signposted_as`], but when the pathway is used from the [`Pathway::to_stop_id`] to the [`Pathway::from_stop_id`].
     pub reversed_signposted_as: Option<String>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Pathway {
     /// Validates if the Pathway is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate pathway_id.
-        if self.pathway_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "pathway_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
+        self.validate_fields()?;
 
         // Validate from_stop_id and to_stop_id.
         if self.from_stop_id.is_empty() || self.to_stop_id.is_empty() {
@@ -5,52 +5,71 @@
 //! - [`RouteId`]: Identifies a route.
 //! - [`RouteType`]: Indicates the type of transportation used on a route.
 
-use gtfs_schedule_macros::StringWrapper;
+use std::sync::Arc;
+
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
-use serde_repr::*;
 use serde_with::skip_serializing_none;
+use url::Url;
 
-use super::{AgencyId, ContinuousDropOff, ContinuousPickup, NetworkId, Schema};
+use super::{coded_enum, AgencyId, ContinuousDropOff, ContinuousPickup, NetworkId, Schema};
 use crate::error::{Result, SchemaValidationError};
 
 /// Identifies a route.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct RouteId(pub String);
+pub struct RouteId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
+
+coded_enum! {
+    /// Indicates the type of transportation used on a route.
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    pub enum RouteType: u16 {
+        /// Tram, Streetcar, Light rail. Any light rail or street level system within a metropolitan area.
+        LightRail = 0,
+        /// Subway, Metro. Any underground rail system within a metropolitan area.
+        Subway = 1,
+        /// Rail. Used for intercity or long-distance travel.
+        Rail = 2,
+        /// Bus. Used for short- and long-distance bus routes.
+        Bus = 3,
+        /// Ferry. Used for short- and long-distance boat service.
+        Ferry = 4,
+        /// Cable tram. Used for street-level rail cars where the cable runs beneath the vehicle (e.g., cable car in San Francisco).
+        CableTram = 5,
+        /// Aerial lift, suspended cable car (e.g., gondola lift, aerial tramway).
+        /// Cable transport where cabins, cars, gondolas or open chairs are suspended by means of one or more cables.
+        AerialLift = 6,
+        /// Funicular. Any rail system designed for steep inclines.
+        Funicular = 7,
+        /// Trolleybus. Electric buses that draw power from overhead wires using poles.
+        Trolleybus = 11,
+        /// Monorail. Railway in which the track consists of a single rail or a beam.
+        Monorail = 12,
+    }
+}
+
+impl PartialOrd for RouteType {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-/// Indicates the type of transportation used on a route.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum RouteType {
-    /// Tram, Streetcar, Light rail. Any light rail or street level system within a metropolitan area.
-    LightRail = 0,
-    /// Subway, Metro. Any underground rail system within a metropolitan area.
-    Subway = 1,
-    /// Rail. Used for intercity or long-distance travel.
-    Rail = 2,
-    /// Bus. Used for short- and long-distance bus routes.
-    Bus = 3,
-    /// Ferry. Used for short- and long-distance boat service.
-    Ferry = 4,
-    /// Cable tram. Used for street-level rail cars where the cable runs beneath the vehicle (e.g., cable car in San Francisco).
-    CableTram = 5,
-    /// Aerial lift, suspended cable car (e.g., gondola lift, aerial tramway).
-    /// Cable transport where cabins, cars, gondolas or open chairs are suspended by means of one or more cables.
-    AerialLift = 6,
-    /// Funicular. Any rail system designed for steep inclines.
-    Funicular = 7,
-    /// Trolleybus. Electric buses that draw power from overhead wires using poles.
-    Trolleybus = 11,
-    /// Monorail. Railway in which the track consists of a single rail or a beam.
-    Monorail = 12,
+/// Route types are ordered by code, an unknown code coming after the type it stands for.
+impl Ord for RouteType {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let is_unknown = |route_type: &Self| matches!(route_type, RouteType::Unknown(_));
+        (self.code(), is_unknown(self)).cmp(&(other.code(), is_unknown(other)))
+    }
 }
 
 /// Represents a transit route.
 ///
 /// See [routes.txt](https://gtfs.org/schedule/reference/#routestxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "routes.txt", primary_key = "route_id")]
 #[skip_serializing_none]
 pub struct Route {
     /// Identifies a route.
+    #[gtfs(required)]
     pub route_id: RouteId,
     /// Agency for the specified route.
     ///
@@ -82,7 +101,7 @@ pub struct Route {
     /// Indicates the type of transportation used on a route.
     pub route_type: RouteType,
     /// URL of a web page about the particular route. Should be different from the [`crate::schemas::agency::Agency::agency_url`] value.
-    pub route_url: Option<String>,
+    pub route_url: Option<Url>,
     /// Route color designation that matches public facing material. Defaults to white (`FFFFFF`)
     /// when omitted or left empty. The color difference between [`Route::route_color`] and
     /// [`Route::route_text_color`] should provide sufficient contrast when viewed on a black and white screen.
@@ -123,20 +142,16 @@ pub struct Route {
     /// - Forbidden if [`crate::schemas::route_network::RouteNetwork`] exists.
     /// - Optional otherwise.
     pub network_id: Option<NetworkId>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Route {
     /// Validates if the Route is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate route_id.
-        if self.route_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "route_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
+        self.validate_fields()?;
 
         // Validate route_short_name and route_long_name.
         if self.route_short_name.is_none() && self.route_long_name.is_none() {
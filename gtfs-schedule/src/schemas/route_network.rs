@@ -3,48 +3,36 @@
 //! The main type is:
 //! - [`RouteNetwork`]: Assigns routes from [`crate::schemas::route::Route`] to networks.
 
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
-use super::{NetworkId, RouteId, Schema};
-use crate::error::{Result, SchemaValidationError};
+use super::{NetworkId, RouteId};
+use crate::error::Result;
 
 /// Assigns routes from [`crate::schemas::route::Route`] to networks.
 ///
 /// See [route_networks.txt](https://gtfs.org/schedule/reference/#route_networkstxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "routes_networks.txt", primary_key = "route_id")]
 #[skip_serializing_none]
 pub struct RouteNetwork {
     /// Identifies a network to which one or multiple [`RouteNetwork::route_id`]
     /// belong. A [`RouteNetwork::route_id`] can only be defined in one [`RouteNetwork::network_id`].
+    #[gtfs(required)]
     pub network_id: NetworkId,
     /// Identifies a route.
+    #[gtfs(required)]
     pub route_id: RouteId,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl RouteNetwork {
     /// Validates if the RouteNetwork is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate network_id.
-        if self.network_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "network_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        // Validate route_id.
-        if self.route_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "route_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        Ok(())
+        self.validate_fields()
     }
 }
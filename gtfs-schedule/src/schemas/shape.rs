@@ -4,7 +4,9 @@
 //! - [`Shape`]: Represents a shape.
 //! - [`ShapeId`]: Identifies a shape.
 
-use gtfs_schedule_macros::StringWrapper;
+use std::sync::Arc;
+
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
@@ -13,7 +15,7 @@ use crate::error::{Result, SchemaValidationError};
 
 /// Identifies a shape.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct ShapeId(pub String);
+pub struct ShapeId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
 /// Represents a shape.
 ///
@@ -23,13 +25,16 @@ pub struct ShapeId(pub String);
 /// small distance of the shape for that trip, i.e. close to straight line segments connecting the shape points.
 ///
 /// See [shapes.txt](https://gtfs.org/schedule/reference/#shapestxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "shapes.txt", primary_key = "shape_id, shape_pt_sequence")]
 #[skip_serializing_none]
 pub struct Shape {
     /// Identifies a shape.
+    #[gtfs(required)]
     pub shape_id: ShapeId,
     /// Geographic coordinate of the shape point.
     #[serde(flatten)]
+    #[gtfs(columns = "shape_pt_lat, shape_pt_lon")]
     pub shape_pt: GtfsCoord<{ coord_type::SHAPE }>,
     /// Sequence in which the shape points connect to form the shape. Values must increase along the trip but do not need to be consecutive.
     pub shape_pt_sequence: u32,
@@ -42,20 +47,16 @@ pub struct Shape {
     ///
     /// Example: If a bus travels a distance of 5.25 kilometers from the start of the shape to the stop, [`Shape::shape_dist_traveled`] = `5.25`.
     pub shape_dist_traveled: Option<f32>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Shape {
     /// Validates if the Shape is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate shape_id.
-        if self.shape_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "shape_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
+        self.validate_fields()?;
 
         // Validate shape_dist_traveled.
         if let Some(dist) = self.shape_dist_traveled {
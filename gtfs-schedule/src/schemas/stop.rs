@@ -3,17 +3,20 @@
 //! The main types are:
 //! - [`Stop`]: Represents a stop where vehicles pick up or drop off riders.
 //! - [`StopId`]: Identifies a location: stop/platform, station, entrance/exit, generic node or boarding area.
+//! - [`ZoneId`]: Identifies a fare zone.
 //! - [`WheelchairBoarding`]: Indicates whether wheelchair boardings are possible from the location.
 
+use std::sync::Arc;
+
 use chrono_tz::Tz;
-use gtfs_schedule_macros::StringWrapper;
+use gtfs_schedule_macros::{GtfsTable, StringWrapper};
 use serde::{Deserialize, Serialize};
-use serde_repr::*;
 use serde_with::skip_serializing_none;
+use url::Url;
 
 use crate::error::{Result, SchemaValidationError};
 
-use super::{coord_type, GtfsCoord, LevelId, LocationType, Schema};
+use super::{coded_enum, coord_type, GtfsCoord, LevelId, LocationType, Schema};
 
 /// Identifies a location: stop/platform, station, entrance/exit, generic node or boarding area.
 ///
@@ -22,32 +25,40 @@ use super::{coord_type, GtfsCoord, LevelId, LocationType, Schema};
 ///
 /// Multiple routes may use the same [`StopId`].
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct StopId(pub String);
+pub struct StopId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
+
+/// Identifies a fare zone.
+///
+/// Fare zones are not defined in a file of their own, they are the distinct [`Stop::zone_id`] values.
+#[derive(Serialize, Deserialize, Debug, StringWrapper)]
+pub struct ZoneId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
-/// Indicates whether wheelchair boardings are possible from the location.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum WheelchairBoarding {
-    /// For parentless stops: No accessibility information for the stop.
-    /// For child stops: Stop will inherit its [`WheelchairBoarding`] behavior
-    /// from the parent station, if specified in the parent.
-    /// For station entrances/exits: Station entrance will inherit its [`WheelchairBoarding`]
-    /// behavior from the parent station, if specified for the parent.
-    NoInformation = 0,
-    /// For parentless stops: Some vehicles at this stop can be boarded by a rider in a wheelchair.
-    /// For child stops: There exists some accessible path from outside the station to the specific stop/platform.
-    /// For station entrances/exits: Station entrance is wheelchair accessible.
-    SomeAccessibility = 1,
-    /// For parentless stops: Wheelchair boarding is not possible at this stop.
-    /// For child stops: There exists no accessible path from outside the station to the specific stop/platform.
-    /// For station entrances/exits: No accessible path from station entrance to stops/platforms.
-    NoAccessibility = 2,
+coded_enum! {
+    /// Indicates whether wheelchair boardings are possible from the location.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum WheelchairBoarding {
+        /// For parentless stops: No accessibility information for the stop.
+        /// For child stops: Stop will inherit its [`WheelchairBoarding`] behavior
+        /// from the parent station, if specified in the parent.
+        /// For station entrances/exits: Station entrance will inherit its [`WheelchairBoarding`]
+        /// behavior from the parent station, if specified for the parent.
+        NoInformation = 0,
+        /// For parentless stops: Some vehicles at this stop can be boarded by a rider in a wheelchair.
+        /// For child stops: There exists some accessible path from outside the station to the specific stop/platform.
+        /// For station entrances/exits: Station entrance is wheelchair accessible.
+        SomeAccessibility = 1,
+        /// For parentless stops: Wheelchair boarding is not possible at this stop.
+        /// For child stops: There exists no accessible path from outside the station to the specific stop/platform.
+        /// For station entrances/exits: No accessible path from station entrance to stops/platforms.
+        NoAccessibility = 2,
+    }
 }
 
 /// Represents a stop where vehicles pick up or drop off riders.
 ///
 /// See [stops.txt](https://gtfs.org/schedule/reference/#stopstxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable)]
+#[gtfs(file = "stops.txt", primary_key = "stop_id")]
 #[skip_serializing_none]
 pub struct Stop {
     /// Identifies a location: stop/platform, station, entrance/exit, generic node or
@@ -97,14 +108,15 @@ pub struct Stop {
     /// - Optional for locations which are generic nodes ([`LocationType::GenericNode`])
     /// or boarding areas ([`LocationType::BoardingArea`]).
     #[serde(flatten)]
+    #[gtfs(columns = "stop_lat, stop_lon")]
     pub stop_coord: Option<GtfsCoord<{ coord_type::STOP }>>,
     /// Identifies the fare zone for a stop. If this record represents a station or
     /// station entrance, the [`Stop::zone_id`] is ignored.
-    pub zone_id: Option<String>,
+    pub zone_id: Option<ZoneId>,
     /// URL of a web page about the location. This should be different from the
     /// [`crate::schemas::agency::Agency::agency_url`] and the
     /// [`crate::schemas::route::Route::route_url`] field values.
-    pub stop_url: Option<String>,
+    pub stop_url: Option<Url>,
     /// Location type.
     pub location_type: Option<LocationType>,
     /// Defines hierarchy between the different locations defined in [`Stop`].
@@ -143,6 +155,10 @@ pub struct Stop {
     /// (or the feed's language-specific equivalent) should not be included. This allows
     /// feed consumers to more easily internationalize and localize the platform identifier into other languages.
     pub platform_code: Option<String>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Stop {
@@ -3,20 +3,23 @@
 //! The main type is:
 //! - [`StopArea`]: Assigns stops from [`crate::schemas::stop::Stop`] to areas.
 
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
-use super::{AreaId, Schema, StopId};
-use crate::error::{Result, SchemaValidationError};
+use super::{AreaId, StopId};
+use crate::error::Result;
 
 /// Assigns stops from [`crate::schemas::stop::Stop`] to areas.
 ///
 /// See [stop_areas.txt](https://gtfs.org/schedule/reference/#stop_areastxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "stops_areas.txt")]
 #[skip_serializing_none]
 pub struct StopArea {
     /// Identifies an area to which one or multiple [`StopArea::stop_id`] belong.
     /// The same [`StopArea::stop_id`] may be defined in many [`AreaId`].
+    #[gtfs(required)]
     pub area_id: AreaId,
     /// Identifies a stop. If a station (i.e. a stop with
     /// [`crate::schemas::stop::Stop::location_type`]=`1`) is defined in
@@ -25,32 +28,17 @@ pub struct StopArea {
     /// that have this station defined as [`crate::schemas::stop::Stop::parent_station`])
     /// are part of the same area. This behavior can be overridden
     /// by assigning platforms to other areas.
+    #[gtfs(required)]
     pub stop_id: StopId,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl StopArea {
     /// Validates if the StopArea is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate area_id.
-        if self.area_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "area_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        // Validate stop_id.
-        if self.stop_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "stop_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        Ok(())
+        self.validate_fields()
     }
 }
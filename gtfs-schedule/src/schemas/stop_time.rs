@@ -2,48 +2,61 @@
 //!
 //! The main types are:
 //! - [`StopTime`]: Represents a stop time.
+//! - [`LocationId`]: Identifies a GeoJSON location.
 //! - [`PickupType`]: Indicates pickup method.
 //! - [`DropOffType`]: Indicates drop off method.
 //! - [`Timepoint`]: Indicates if arrival and departure times for a stop
 //!   are strictly adhered to by the vehicle or if they are approximate and/or interpolated times.
 
+use std::sync::Arc;
+
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
 use serde_repr::*;
 use serde_with::skip_serializing_none;
 
-use super::{ContinuousDropOff, ContinuousPickup, NaiveServiceTime, Schema, StopId, TripId};
+use super::{
+    coded_enum, BookingRuleId, ContinuousDropOff, ContinuousPickup, LocationGroupId,
+    NaiveServiceTime, Schema, StopId, TripId,
+};
 use crate::error::{Result, SchemaValidationError};
 
-/// Indicates pickup method.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum PickupType {
-    /// Regularly scheduled pickup.
-    RegularlyScheduled = 0,
-    /// No pickup available.
-    NoPickupAvailable = 1,
-    /// Must phone agency to arrange pickup.
-    MustPhoneAgency = 2,
-    /// Must coordinate with driver to arrange pickup.
-    MustCoordinateWithDriver = 3,
+/// Identifies a GeoJSON location, as defined in locations.geojson.
+#[derive(Serialize, Deserialize, Debug, StringWrapper)]
+pub struct LocationId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
+
+coded_enum! {
+    /// Indicates pickup method.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum PickupType {
+        /// Regularly scheduled pickup.
+        RegularlyScheduled = 0,
+        /// No pickup available.
+        NoPickupAvailable = 1,
+        /// Must phone agency to arrange pickup.
+        MustPhoneAgency = 2,
+        /// Must coordinate with driver to arrange pickup.
+        MustCoordinateWithDriver = 3,
+    }
 }
 
-/// Indicates drop off method.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum DropOffType {
-    /// Regularly scheduled drop off.
-    RegularlyScheduled = 0,
-    /// No drop off available.
-    NoDropOffAvailable = 1,
-    /// Must phone agency to arrange drop off.
-    MustPhoneAgency = 2,
-    /// Must coordinate with driver to arrange drop off.
-    MustCoordinateWithDriver = 3,
+coded_enum! {
+    /// Indicates drop off method.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum DropOffType {
+        /// Regularly scheduled drop off.
+        RegularlyScheduled = 0,
+        /// No drop off available.
+        NoDropOffAvailable = 1,
+        /// Must phone agency to arrange drop off.
+        MustPhoneAgency = 2,
+        /// Must coordinate with driver to arrange drop off.
+        MustCoordinateWithDriver = 3,
+    }
 }
 
 /// Indicates if arrival and departure times for a stop are strictly adhered to by the vehicle or if they are approximate and/or interpolated times.
-#[derive(Serialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize_repr, Debug, PartialEq, Eq, Clone)]
 #[repr(u8)]
 pub enum Timepoint {
     /// Times are considered approximate.
@@ -75,10 +88,12 @@ impl<'de> Deserialize<'de> for Timepoint {
 /// Times that a vehicle arrives at and departs from stops for each trip.
 ///
 /// See [stop_times.txt](https://gtfs.org/schedule/reference/#stop_timestxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "stop_times.txt", primary_key = "trip_id, stop_sequence")]
 #[skip_serializing_none]
 pub struct StopTime {
     /// Identifies a trip.
+    #[gtfs(required)]
     pub trip_id: TripId,
     /// Arrival time at the stop (defined by [`StopTime::stop_id`]) for a
     /// specific trip (defined by [`StopTime::trip_id`]) in the time zone specified
@@ -145,7 +160,7 @@ pub struct StopTime {
     ///
     /// **Conditionally Forbidden:**
     /// - Forbidden if [`StopTime::stop_id`] or [`StopTime::location_id`] are defined.
-    pub location_group_id: Option<String>,
+    pub location_group_id: Option<LocationGroupId>,
     /// Identifies the GeoJSON location that corresponds to serviced zone where riders
     /// may request pickup or drop off. All GeoJSON locations serviced during a trip
     /// must have a record in [`StopTime`]. Multiple trips and routes may service the same GeoJSON location.
@@ -158,7 +173,7 @@ pub struct StopTime {
     ///
     /// **Conditionally Forbidden:**
     /// - Forbidden if [`StopTime::stop_id`] or [`StopTime::location_group_id`] are defined.
-    pub location_id: Option<String>,
+    pub location_id: Option<LocationId>,
     /// Order of stops, location groups, or GeoJSON locations for a particular trip.
     /// The values must increase along the trip but do not need to be consecutive.
     ///
@@ -187,7 +202,7 @@ pub struct StopTime {
     /// - Required if [`StopTime::end_pickup_drop_off_window`] is defined.
     /// - Forbidden if [`StopTime::arrival_time`] or [`StopTime::departure_time`] is defined.
     /// - Optional otherwise.
-    pub start_pickup_drop_off_window: Option<String>,
+    pub start_pickup_drop_off_window: Option<NaiveServiceTime>,
     /// Time that on-demand service ends in a GeoJSON location, location group, or stop.
     ///
     /// **Conditionally Required:**
@@ -195,7 +210,9 @@ pub struct StopTime {
     /// - Required if [`StopTime::start_pickup_drop_off_window`] is defined.
     /// - Forbidden if [`StopTime::arrival_time`] or [`StopTime::departure_time`] is defined.
     /// - Optional otherwise.
-    pub end_pickup_drop_off_window: Option<String>,
+    ///
+    /// Must be after [`StopTime::start_pickup_drop_off_window`].
+    pub end_pickup_drop_off_window: Option<NaiveServiceTime>,
     /// Indicates pickup method.
     ///
     /// **Conditionally Forbidden:**
@@ -262,25 +279,21 @@ pub struct StopTime {
     /// Identifies the boarding booking rule at this stop time.
     ///
     /// Recommended when [`StopTime::pickup_type`]=[`PickupType::MustPhoneAgency`].
-    pub pickup_booking_rule_id: Option<String>,
+    pub pickup_booking_rule_id: Option<BookingRuleId>,
     /// Identifies the alighting booking rule at this stop time.
     ///
     /// Recommended when [`StopTime::drop_off_type`]=[`DropOffType::MustPhoneAgency`].
-    pub drop_off_booking_rule_id: Option<String>,
+    pub drop_off_booking_rule_id: Option<BookingRuleId>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl StopTime {
     /// Validates if the StopTime is valid in regards to the GTFS specification constraints.
     pub fn validate(&mut self) -> Result<()> {
-        // Validate trip_id.
-        if self.trip_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "trip_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
+        self.validate_fields()?;
 
         if self.stop_id.is_some()
             && (self.location_group_id.is_some() || self.location_id.is_some())
@@ -320,6 +333,44 @@ impl StopTime {
             .into());
         }
 
+        // Validate start_pickup_drop_off_window and end_pickup_drop_off_window.
+        match (
+            self.start_pickup_drop_off_window,
+            self.end_pickup_drop_off_window,
+        ) {
+            (Some(start), Some(end)) => {
+                if start.as_secs() >= end.as_secs() {
+                    return Err(SchemaValidationError::new_invalid_value(
+                        "end_pickup_drop_off_window".to_string(),
+                        Some("must be after start_pickup_drop_off_window".to_string()),
+                        Schema::from(self.clone()),
+                    )
+                    .into());
+                }
+            }
+            (Some(_), None) | (None, Some(_)) => {
+                return Err(SchemaValidationError::new_missing_value(
+                    "start_pickup_drop_off_window and end_pickup_drop_off_window".to_string(),
+                    Some("must be defined together".to_string()),
+                    Schema::from(self.clone()),
+                )
+                .into());
+            }
+            (None, None) => {
+                if self.location_group_id.is_some() || self.location_id.is_some() {
+                    return Err(SchemaValidationError::new_missing_value(
+                        "start_pickup_drop_off_window and end_pickup_drop_off_window".to_string(),
+                        Some(
+                            "are required when location_group_id or location_id is defined"
+                                .to_string(),
+                        ),
+                        Schema::from(self.clone()),
+                    )
+                    .into());
+                }
+            }
+        }
+
         // Validate arrival_time and departure_time based on timepoint and presence of start_pickup_drop_off_window and end_pickup_drop_off_window.
         if self.start_pickup_drop_off_window.is_some() || self.end_pickup_drop_off_window.is_some()
         {
@@ -4,8 +4,10 @@
 //! - [`Timeframe`]: Represents a timeframe for a fare.
 //! - [`TimeframeGroupId`]: Identifies a timeframe or set of timeframes.
 
+use std::sync::Arc;
+
 use chrono::NaiveTime;
-use gtfs_schedule_macros::StringWrapper;
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
@@ -14,7 +16,9 @@ use crate::error::{Result, SchemaValidationError};
 
 /// Identifies a timeframe or set of timeframes.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct TimeframeGroupId(pub String);
+pub struct TimeframeGroupId(
+    #[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>,
+);
 
 /// Represents a timeframe for a fare.
 ///
@@ -23,10 +27,12 @@ pub struct TimeframeGroupId(pub String);
 /// There must not be overlapping time intervals for the same [`Timeframe::timeframe_group_id`] and [`Timeframe::service_id`] values.
 ///
 /// See [timeframes.txt](https://gtfs.org/schedule/reference/#timeframestxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "timeframes.txt")]
 #[skip_serializing_none]
 pub struct Timeframe {
     /// Identifies a timeframe or set of timeframes.
+    #[gtfs(required)]
     pub timeframe_group_id: TimeframeGroupId,
     /// Defines the beginning of a timeframe. The interval includes the start time.
     ///
@@ -35,6 +41,10 @@ pub struct Timeframe {
     /// **Conditionally Required:**
     /// - Required if [`Timeframe::end_time`] is defined.
     /// - Forbidden otherwise.
+    #[gtfs(
+        required_if = "self.end_time.is_some()",
+        reason = "end_time is defined"
+    )]
     pub start_time: Option<NaiveTime>,
     /// Defines the end of a timeframe. The interval does not include the end time.
     ///
@@ -43,51 +53,26 @@ pub struct Timeframe {
     /// **Conditionally Required:**
     /// - Required if [`Timeframe::start_time`] is defined.
     /// - Forbidden otherwise.
+    #[gtfs(
+        required_if = "self.start_time.is_some()",
+        reason = "start_time is defined"
+    )]
     pub end_time: Option<NaiveTime>,
     /// Identifies a set of dates that a timeframe is in effect.
+    #[gtfs(required)]
     pub service_id: CalendarServiceId,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Timeframe {
     /// Validates if the Timeframe is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate timeframe_group_id.
-        if self.timeframe_group_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "timeframe_group_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        // Validate service_id.
-        if self.service_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "service_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
+        self.validate_fields()?;
 
         // Validate start_time and end_time.
-        if self.start_time.is_some() && self.end_time.is_none() {
-            return Err(SchemaValidationError::new_missing_value(
-                "end_time".to_string(),
-                Some("start_time is defined".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-        if self.end_time.is_some() && self.start_time.is_none() {
-            return Err(SchemaValidationError::new_missing_value(
-                "start_time".to_string(),
-                Some("end_time is defined".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
         if self.start_time.is_some() && self.end_time.is_some() && self.start_time > self.end_time {
             return Err(SchemaValidationError::new_invalid_value(
                 "start_time or end_time".to_string(),
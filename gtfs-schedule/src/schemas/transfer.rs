@@ -4,14 +4,16 @@
 //! - [`Transfer`]: Represents additional rules and overrides for selected transfers.
 //! - [`TransferType`]: Indicates the type of connection for the specified (from_stop_id, to_stop_id) pair.
 
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate};
 use serde::{Deserialize, Serialize};
+use serde_repr::Serialize_repr;
 use serde_with::skip_serializing_none;
 
-use super::{RouteId, Schema, StopId, TripId};
-use crate::error::{Result, SchemaValidationError};
+use super::{RouteId, StopId, TripId};
+use crate::error::Result;
 
 /// Indicates the type of connection for the specified (from_stop_id, to_stop_id) pair.
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize_repr, Debug, Clone, PartialEq, Eq)]
 #[repr(u8)]
 pub enum TransferType {
     /// Recommended transfer point between routes.
@@ -59,7 +61,8 @@ impl<'de> Deserialize<'de> for TransferType {
 /// specifies additional rules and overrides for selected transfers.
 ///
 /// See [transfers.txt](https://gtfs.org/schedule/reference/#transferstxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, GtfsTable, GtfsValidate)]
+#[gtfs(file = "transfers.txt")]
 #[skip_serializing_none]
 pub struct Transfer {
     /// Identifies a stop or station where a connection between routes begins.
@@ -67,12 +70,28 @@ pub struct Transfer {
     /// **Conditionally Required:**
     /// - Required for [`TransferType::NoTransferPossible`] and [`TransferType::InSeatTransfer`].
     /// - Forbidden for [`TransferType::NoInSeatTransfer`].
+    #[gtfs(
+        required_if = "matches!(self.transfer_type, TransferType::InSeatTransfer | TransferType::NoTransferPossible)",
+        reason = "required when transfer_type is InSeatTransfer or NoTransferPossible"
+    )]
+    #[gtfs(
+        forbidden_if = "self.transfer_type == TransferType::NoInSeatTransfer",
+        reason = "forbidden when transfer_type is NoInSeatTransfer"
+    )]
     pub from_stop_id: Option<StopId>,
     /// Identifies a stop or station where a connection between routes ends.
     ///
     /// **Conditionally Required:**
     /// - Required for [`TransferType::NoTransferPossible`] and [`TransferType::InSeatTransfer`].
     /// - Forbidden for [`TransferType::NoInSeatTransfer`].
+    #[gtfs(
+        required_if = "matches!(self.transfer_type, TransferType::InSeatTransfer | TransferType::NoTransferPossible)",
+        reason = "required when transfer_type is InSeatTransfer or NoTransferPossible"
+    )]
+    #[gtfs(
+        forbidden_if = "self.transfer_type == TransferType::NoInSeatTransfer",
+        reason = "forbidden when transfer_type is NoInSeatTransfer"
+    )]
     pub to_stop_id: Option<StopId>,
     /// Identifies a route where a connection begins.
     ///
@@ -103,6 +122,10 @@ pub struct Transfer {
     ///
     /// **Conditionally Required:**
     /// - Required if [`TransferType`] is [`TransferType::InSeatTransfer`] or [`TransferType::NoInSeatTransfer`].
+    #[gtfs(
+        required_if = "matches!(self.transfer_type, TransferType::InSeatTransfer | TransferType::NoInSeatTransfer)",
+        reason = "required when transfer_type is InSeatTransfer or NoInSeatTransfer"
+    )]
     pub from_trip_id: Option<TripId>,
     /// Identifies a trip where a connection between routes ends.
     ///
@@ -115,6 +138,10 @@ pub struct Transfer {
     ///
     /// **Conditionally Required:**
     /// - Required if [`TransferType`] is [`TransferType::InSeatTransfer`] or [`TransferType::NoInSeatTransfer`].
+    #[gtfs(
+        required_if = "matches!(self.transfer_type, TransferType::InSeatTransfer | TransferType::NoInSeatTransfer)",
+        reason = "required when transfer_type is InSeatTransfer or NoInSeatTransfer"
+    )]
     pub to_trip_id: Option<TripId>,
     /// Indicates the type of connection for the specified ([`Transfer::from_stop_id`], [`Transfer::to_stop_id`]) pair.
     pub transfer_type: TransferType,
@@ -123,67 +150,15 @@ pub struct Transfer {
     /// should be sufficient to permit a typical rider to move between the two stops,
     /// including buffer time to allow for schedule variance on each route.
     pub min_transfer_time: Option<u32>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Transfer {
     /// Validates if the Transfer is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        let from_stop_id_is_none_or_empty =
-            self.from_stop_id.is_none() || self.from_stop_id.as_ref().unwrap().is_empty();
-        let to_stop_id_is_none_or_empty =
-            self.to_stop_id.is_none() || self.to_stop_id.as_ref().unwrap().is_empty();
-
-        // Validate from_stop_id and to_stop_id.
-        match self.transfer_type {
-            TransferType::InSeatTransfer | TransferType::NoTransferPossible => {
-                if from_stop_id_is_none_or_empty || to_stop_id_is_none_or_empty {
-                    return Err(SchemaValidationError::new_missing_value(
-                        "from_stop_id or to_stop_id".to_string(),
-                        Some(
-                            "required when transfer_type is InSeatTransfer or NoTransferPossible"
-                                .to_string(),
-                        ),
-                        Schema::from(self.clone()),
-                    )
-                    .into());
-                }
-            }
-            TransferType::NoInSeatTransfer => {
-                if !from_stop_id_is_none_or_empty || !to_stop_id_is_none_or_empty {
-                    return Err(SchemaValidationError::new_forbidden_value(
-                        "from_stop_id or to_stop_id".to_string(),
-                        Some("forbidden when transfer_type is NoInSeatTransfer".to_string()),
-                        Schema::from(self.clone()),
-                    )
-                    .into());
-                }
-            }
-            _ => {}
-        }
-
-        let from_trip_id_is_none_or_empty =
-            self.from_trip_id.is_none() || self.from_trip_id.as_ref().unwrap().is_empty();
-        let to_trip_id_is_none_or_empty =
-            self.to_trip_id.is_none() || self.to_trip_id.as_ref().unwrap().is_empty();
-
-        // Validate from_trip_id and to_trip_id.
-        match self.transfer_type {
-            TransferType::InSeatTransfer | TransferType::NoInSeatTransfer => {
-                if from_trip_id_is_none_or_empty || to_trip_id_is_none_or_empty {
-                    return Err(SchemaValidationError::new_missing_value(
-                        "from_trip_id or to_trip_id".to_string(),
-                        Some(
-                            "required when transfer_type is InSeatTransfer or NoInSeatTransfer"
-                                .to_string(),
-                        ),
-                        Schema::from(self.clone()),
-                    )
-                    .into());
-                }
-            }
-            _ => {}
-        }
-
-        Ok(())
+        self.validate_fields()
     }
 }
@@ -3,15 +3,16 @@
 //! The main type is:
 //! - [`Translation`]: Represents a translation.
 
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate};
 use oxilangtag::LanguageTag;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
-use super::Schema;
+use super::{deserialize_language_tag, Schema};
 use crate::error::{Result, SchemaValidationError};
 
 /// Defines the table that contains the field to be translated.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum TableName {
     Agency,
@@ -47,7 +48,8 @@ pub enum TableName {
 /// the translation provided with ([`Translation::record_id`], [`Translation::record_sub_id`]) takes precedence.
 ///
 /// See [translations.txt](https://gtfs.org/schedule/reference/#translationstxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "translations.txt")]
 #[skip_serializing_none]
 pub struct Translation {
     /// Defines the table that contains the field to be translated.
@@ -58,6 +60,7 @@ pub struct Translation {
     /// Name of the field to be translated. Fields with type Text may be translated, fields
     /// with type URL, Email and Phone number may also be "translated" to provide resources
     /// in the correct language. Fields with other types should not be translated.
+    #[gtfs(required)]
     pub field_name: String,
     /// Language of translation.
     ///
@@ -67,8 +70,10 @@ pub struct Translation {
     ///
     /// Example: In Switzerland, a city in an officially bilingual canton is officially called
     /// "Biel/Bienne", but would simply be called "Bienne" in French and "Biel" in German.
+    #[serde(deserialize_with = "deserialize_language_tag")]
     pub language: LanguageTag<String>,
     /// Translated value.
+    #[gtfs(required)]
     pub translation: String,
     /// Defines the record that corresponds to the field to be translated. The value in
     /// [`Translation::record_id`] must be the first or only field of a table's primary key,
@@ -147,30 +152,16 @@ pub struct Translation {
     /// - Forbidden if [`Translation::record_id`] is defined.
     /// - Required if [`Translation::record_id`] is empty.
     pub field_value: Option<String>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Translation {
     /// Validates if the Translation is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate field_name.
-        if self.field_name.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "field_name".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        // Validate translation.
-        if self.translation.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "translation".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
+        self.validate_fields()?;
 
         // Validate record_id, record_sub_id, and field_value.
         if self.table_name == TableName::FeedInfo {
@@ -3,37 +3,45 @@
 //! The main types are:
 //! - [`Trip`]: Represents a trip.
 //! - [`TripId`]: Identifies a trip.
+//! - [`BlockId`]: Identifies a block.
 //! - [`DirectionId`]: Indicates the direction of travel for a trip.
 //! - [`WheelchairAccessible`]: Indicates wheelchair accessibility.
 //! - [`BikesAllowed`]: Indicates whether bikes are allowed.
 
-use gtfs_schedule_macros::StringWrapper;
+use std::sync::Arc;
+
+use gtfs_schedule_macros::{GtfsTable, GtfsValidate, StringWrapper};
 use serde::{Deserialize, Serialize};
 use serde_repr::*;
 use serde_with::skip_serializing_none;
 
-use super::{RouteId, Schema};
-use crate::error::{Result, SchemaValidationError};
+use super::{coded_enum, RouteId, ShapeId};
+use crate::error::Result;
 
 use super::CalendarServiceId;
 
 /// Identifies a trip.
 #[derive(Serialize, Deserialize, Debug, StringWrapper)]
-pub struct TripId(pub String);
+pub struct TripId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
 
-/// Indicates the direction of travel for a trip. This field should not be
-/// used in routing; it provides a way to separate trips by direction when publishing time tables.
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Clone, Debug)]
-#[repr(u8)]
-pub enum DirectionId {
-    /// Travel in one direction (e.g. outbound travel).
-    OneDirection = 0,
-    /// Travel in the opposite direction (e.g. inbound travel).
-    OppositeDirection = 1,
+/// Identifies a block, a sequence of trips made using the same vehicle.
+#[derive(Serialize, Deserialize, Debug, StringWrapper)]
+pub struct BlockId(#[serde(deserialize_with = "crate::interning::deserialize")] pub Arc<str>);
+
+coded_enum! {
+    /// Indicates the direction of travel for a trip. This field should not be
+    /// used in routing; it provides a way to separate trips by direction when publishing time tables.
+    #[derive(PartialEq, Eq, Clone, Debug)]
+    pub enum DirectionId {
+        /// Travel in one direction (e.g. outbound travel).
+        OneDirection = 0,
+        /// Travel in the opposite direction (e.g. inbound travel).
+        OppositeDirection = 1,
+    }
 }
 
 /// Indicates wheelchair accessibility.
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize_repr, Debug, Clone)]
 #[repr(u8)]
 pub enum WheelchairAccessible {
     /// No accessibility information for the trip.
@@ -64,7 +72,8 @@ impl<'de> Deserialize<'de> for WheelchairAccessible {
 }
 
 /// Indicates whether bikes are allowed.
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize_repr, Debug, Clone)]
+#[repr(u8)]
 pub enum BikesAllowed {
     /// No bike information for the trip.
     NoInformation = 0,
@@ -98,14 +107,18 @@ impl<'de> Deserialize<'de> for BikesAllowed {
 /// Trips for each route. A trip is a sequence of two or more stops that occur during a specific time period.
 ///
 /// See [trips.txt](https://gtfs.org/schedule/reference/#tripstxt) for more details.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, GtfsTable, GtfsValidate)]
+#[gtfs(file = "trips.txt", primary_key = "trip_id")]
 #[skip_serializing_none]
 pub struct Trip {
     /// Identifies a route.
+    #[gtfs(required)]
     pub route_id: RouteId,
     /// Identifies a set of dates when service is available for one or more routes.
+    #[gtfs(required)]
     pub service_id: CalendarServiceId,
     /// Identifies a trip.
+    #[gtfs(required)]
     pub trip_id: TripId,
     /// Text that appears on signage identifying the trip's destination to riders.
     /// Should be used to distinguish between different patterns of service on the same route.
@@ -128,51 +141,27 @@ pub struct Trip {
     /// service days and [`Trip::block_id`]. A [`Trip::block_id`] may have trips with
     /// different service days, making distinct blocks. See the example below. To provide
     /// in-seat transfers information, transfers of [`crate::schemas::transfer::TransferType::InSeatTransfer`] should be provided instead.
-    pub block_id: Option<String>,
+    pub block_id: Option<BlockId>,
     /// Identifies a geospatial shape describing the vehicle travel path for a trip.
     ///
     /// **Conditionally Required:**
     /// - Required if the trip has a continuous pickup or drop-off behavior defined
     ///   either in [`crate::schemas::route::Route`] or in [`crate::schemas::stop_time::StopTime`].
     /// - Optional otherwise.
-    pub shape_id: Option<String>,
+    pub shape_id: Option<ShapeId>,
     /// Indicates wheelchair accessibility.
     pub wheelchair_accessible: Option<WheelchairAccessible>,
     /// Indicates whether bikes are allowed.
     pub bikes_allowed: Option<BikesAllowed>,
+    /// Columns of the record that are not part of the specification, by name.
+    #[cfg(feature = "extensions")]
+    #[serde(skip)]
+    pub extensions: std::collections::HashMap<String, String>,
 }
 
 impl Trip {
     /// Validates if the Trip is valid in regards to the GTFS specification constraints.
     pub fn validate(&self) -> Result<()> {
-        // Validate route_id.
-        if self.route_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "route_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-        // Validate service_id.
-        if self.service_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "service_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-        // Validate trip_id.
-        if self.trip_id.is_empty() {
-            return Err(SchemaValidationError::new_missing_value(
-                "trip_id".to_string(),
-                Some("can never be empty".to_string()),
-                Schema::from(self.clone()),
-            )
-            .into());
-        }
-
-        Ok(())
+        self.validate_fields()
     }
 }
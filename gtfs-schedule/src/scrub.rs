@@ -0,0 +1,275 @@
+//! Removal of the sensitive content of a dataset, to publish it as a test fixture.
+//!
+//! The main types are:
+//! - [`ScrubOptions`]: What [`Dataset::scrub`] removes from a dataset.
+//! - [`ContactScrub`]: What to do with the contact details of a dataset.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::str::FromStr;
+
+use email_address::EmailAddress;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+#[cfg(feature = "extensions")]
+use crate::schemas::Extensions;
+
+use crate::error::Result;
+use crate::interning::InternScope;
+use crate::provenance::SourceLines;
+use crate::schemas::Phone;
+use crate::writer::{csv_row, Row};
+use crate::{Dataset, GtfsFile, ParseOptions, ParseReport};
+
+/// Options used by [`Dataset::scrub`].
+///
+/// By default, contact details and extension columns are removed, and ids are kept.
+///
+/// ```
+/// use gtfs_schedule::{ContactScrub, ScrubOptions};
+///
+/// let options = ScrubOptions::default()
+///     .contacts(ContactScrub::Hash { salt: "fixtures".to_string() })
+///     .rekey_ids();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScrubOptions {
+    contacts: ContactScrub,
+    keep_extensions: bool,
+    rekey_ids: bool,
+}
+
+/// What to do with the contact details of a dataset, see [`ScrubOptions::contacts`].
+///
+/// Contact details are the emails, phone numbers and URLs of attributions.txt, and the phone
+/// numbers and URLs of booking_rules.txt.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ContactScrub {
+    /// Leave them untouched.
+    Keep,
+    /// Remove them.
+    #[default]
+    Remove,
+    /// Replace them with values derived from a hash of the original value and `salt`.
+    ///
+    /// Equal values are replaced with equal values, and the replacements are still valid emails,
+    /// phone numbers and URLs, using the `example.com` domain and the `555` area code. Without a
+    /// secret salt, short values such as phone numbers can be recovered by hashing every
+    /// candidate.
+    Hash {
+        /// Mixed into the hash of each value.
+        salt: String,
+    },
+}
+
+impl ScrubOptions {
+    /// Choose what to do with contact details.
+    pub fn contacts(mut self, contacts: ContactScrub) -> Self {
+        self.contacts = contacts;
+        self
+    }
+
+    /// Keep the columns that are not part of the specification.
+    pub fn keep_extensions(mut self) -> Self {
+        self.keep_extensions = true;
+        self
+    }
+
+    /// Replace every id with a sequential one, e.g. `stop_1`, `stop_2`, …
+    ///
+    /// Ids are numbered in the order of their original value, within each kind of id, and every
+    /// column referencing them is updated, including the `record_id` of translations.txt.
+    pub fn rekey_ids(mut self) -> Self {
+        self.rekey_ids = true;
+        self
+    }
+}
+
+impl Dataset {
+    /// Removes the sensitive content of the dataset, as described by `options`.
+    ///
+    /// This is meant to publish test fixtures derived from production feeds. When ids are
+    /// rekeyed, the dataset is rebuilt: the parse report and source lines are discarded, and an
+    /// error is returned if a record can not be parsed back, in which case the ids are left
+    /// untouched.
+    pub fn scrub(&mut self, options: &ScrubOptions) -> Result<()> {
+        self.scrub_contacts(&options.contacts);
+        #[cfg(feature = "extensions")]
+        if !options.keep_extensions {
+            self.clear_extensions();
+        }
+        if options.rekey_ids {
+            self.rekey_ids()?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes or hashes the contact details of attributions.txt and booking_rules.txt.
+    fn scrub_contacts(&mut self, contacts: &ContactScrub) {
+        let salt = match contacts {
+            ContactScrub::Keep => return,
+            ContactScrub::Remove => None,
+            ContactScrub::Hash { salt } => Some(salt.as_str()),
+        };
+        let url = |url: &mut Option<Url>| {
+            *url = salt.and_then(|salt| {
+                let hash = hash(salt, url.as_ref()?.as_str());
+                Url::parse(&format!("https://example.com/{}", hash)).ok()
+            });
+        };
+        let phone = |phone: &mut Option<Phone>| {
+            *phone = salt.and_then(|salt| {
+                let hash = hash(salt, phone.as_ref()?.as_str());
+                let digits = u64::from_str_radix(&hash[..8], 16).ok()? % 10_000_000;
+                Phone::from_str(&format!("+1555{:07}", digits)).ok()
+            });
+        };
+
+        for attribution in &mut self.attributions {
+            url(&mut attribution.attribution_url);
+            phone(&mut attribution.attribution_phone);
+            attribution.attribution_email = salt.and_then(|salt| {
+                let hash = hash(salt, attribution.attribution_email.as_ref()?.as_str());
+                EmailAddress::from_str(&format!("{}@example.com", hash)).ok()
+            });
+        }
+        for mut booking_rule in self.booking_rules.iter_mut() {
+            url(&mut booking_rule.info_url);
+            url(&mut booking_rule.booking_url);
+            phone(&mut booking_rule.phone_number);
+        }
+    }
+
+    /// Removes the columns that are not part of the specification from every record.
+    #[cfg(feature = "extensions")]
+    fn clear_extensions(&mut self) {
+        macro_rules! clear {
+            (lists: $($list:ident),*; maps: $($map:ident),*) => {
+                $(
+                    for record in &mut self.$list {
+                        record.extensions_mut().clear();
+                    }
+                )*
+                $(
+                    for mut record in self.$map.iter_mut() {
+                        record.extensions_mut().clear();
+                    }
+                )*
+            };
+        }
+
+        clear!(
+            lists: agencies, fare_rules, timeframes, fare_leg_rules, fare_transfers, stops_areas,
+                transfers, location_groups_stops, translations, attributions;
+            maps: stops, routes, trips, stop_times, calendar, calendar_dates, fare_attributes,
+                fare_medias, fare_products, areas, networks, routes_networks, shapes, frequencies,
+                pathways, levels, location_groups, booking_rules
+        );
+        if let Some(feed_info) = self.feed_info.as_mut() {
+            feed_info.extensions_mut().clear();
+        }
+    }
+
+    /// Replaces every id with a sequential one, see [`ScrubOptions::rekey_ids`].
+    fn rekey_ids(&mut self) -> Result<()> {
+        let records: Vec<(GtfsFile, Row)> = self
+            .iter_all()
+            .map(|(file, record)| (file, csv_row(&record)))
+            .collect();
+
+        let mut ids: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+        for (_, row) in &records {
+            for (column, value) in row {
+                if let Some(kind) = id_kind(row, column) {
+                    ids.entry(kind).or_default().insert(value.clone());
+                }
+            }
+        }
+        let new_ids: BTreeMap<(&str, &str), String> = ids
+            .iter()
+            .flat_map(|(kind, values)| {
+                values.iter().enumerate().map(move |(index, value)| {
+                    ((*kind, value.as_str()), format!("{}_{}", kind, index + 1))
+                })
+            })
+            .collect();
+
+        let mut dataset = Self::default();
+        let intern_scope = InternScope::enter(HashSet::new());
+        for (file, row) in &records {
+            let header: csv::StringRecord = row.keys().map(String::as_str).collect();
+            let values: csv::StringRecord = row
+                .iter()
+                .map(|(column, value)| {
+                    id_kind(row, column)
+                        .and_then(|kind| new_ids.get(&(kind, value.as_str())))
+                        .unwrap_or(value)
+                })
+                .collect();
+            dataset.insert_csv_record(*file, &header, &values, &ParseOptions::default())?;
+        }
+        dataset.interned_ids = intern_scope.exit();
+        dataset.source_lines = SourceLines::default();
+        dataset.parse_report = ParseReport::default();
+        dataset.skipped_tables = std::mem::take(&mut self.skipped_tables);
+
+        *self = dataset;
+        Ok(())
+    }
+}
+
+/// Returns the kind of id held by a column of a record, `None` if it does not hold an id.
+///
+/// Columns referencing the same records share a kind, e.g. `parent_station` and `from_stop_id`
+/// are both stop ids. `location_id` is left out, as locations.geojson is not part of datasets.
+fn id_kind(row: &Row, column: &str) -> Option<&'static str> {
+    let kind = match column {
+        "agency_id" => "agency",
+        "stop_id" | "from_stop_id" | "to_stop_id" | "parent_station" => "stop",
+        "route_id" | "from_route_id" | "to_route_id" => "route",
+        "trip_id" | "from_trip_id" | "to_trip_id" => "trip",
+        "service_id" | "prior_notice_service_id" => "service",
+        "zone_id" | "origin_id" | "destination_id" | "contains_id" => "zone",
+        "leg_group_id" | "from_leg_group_id" | "to_leg_group_id" => "leg_group",
+        "timeframe_group_id" | "from_timeframe_group_id" | "to_timeframe_group_id" => "timeframe",
+        "area_id" | "from_area_id" | "to_area_id" => "area",
+        "booking_rule_id" | "pickup_booking_rule_id" | "drop_off_booking_rule_id" => "booking_rule",
+        "level_id" => "level",
+        "block_id" => "block",
+        "shape_id" => "shape",
+        "fare_id" => "fare",
+        "fare_media_id" => "fare_media",
+        "fare_product_id" => "fare_product",
+        "network_id" => "network",
+        "pathway_id" => "pathway",
+        "location_group_id" => "location_group",
+        "attribution_id" => "attribution",
+        "record_id" => match row.get("table_name")?.as_str() {
+            "agency" => "agency",
+            "stops" => "stop",
+            "routes" => "route",
+            "trips" | "stop_times" => "trip",
+            "pathways" => "pathway",
+            "levels" => "level",
+            "attributions" => "attribution",
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Hashes a contact detail, as a hexadecimal string.
+fn hash(salt: &str, value: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update([0]);
+    hasher.update(value.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
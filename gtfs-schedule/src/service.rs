@@ -0,0 +1,346 @@
+//! Analysis of the days of service of a dataset.
+//!
+//! The main types are:
+//! - [`HolidayCalendar`]: A source of holidays, used to label exceptional service days.
+//! - [`ExceptionalServiceDay`]: A day where a service departs from its weekly pattern.
+//! - [`CalendarForm`]: The way the days of service of a dataset are defined.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use chrono::{Datelike, NaiveDate, Weekday};
+
+use crate::error::{DatasetValidationError, Result};
+use crate::schemas::{
+    Calendar, CalendarDate, CalendarDayService, CalendarServiceId, ExceptionType,
+};
+use crate::{Dataset, GtfsFile};
+
+/// A source of holidays.
+///
+/// Datasets rarely say why a service is added or removed on a given date, a holiday calendar
+/// is used to put a name on those dates. It is implemented for maps of dates to holiday names,
+/// and can be implemented for any other provider.
+pub trait HolidayCalendar {
+    /// Returns the name of the holiday falling on `date`, if any.
+    fn holiday(&self, date: NaiveDate) -> Option<String>;
+}
+
+impl HolidayCalendar for HashMap<NaiveDate, String> {
+    fn holiday(&self, date: NaiveDate) -> Option<String> {
+        self.get(&date).cloned()
+    }
+}
+
+impl HolidayCalendar for BTreeMap<NaiveDate, String> {
+    fn holiday(&self, date: NaiveDate) -> Option<String> {
+        self.get(&date).cloned()
+    }
+}
+
+/// The way the days of service of a dataset are defined, see [`Dataset::normalize_calendars`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarForm {
+    /// A weekly pattern in calendar.txt for each service, along with the dates departing from it
+    /// in calendar_dates.txt.
+    WeeklyPatterns,
+    /// Every date of service listed in calendar_dates.txt, without calendar.txt.
+    ExplicitDates,
+}
+
+/// A day where a service departs from the weekly pattern defined in calendar.txt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExceptionalServiceDay {
+    /// The date of the exception.
+    pub date: NaiveDate,
+    /// Identifies the service.
+    pub service_id: CalendarServiceId,
+    /// Whether the service was added or removed on that date.
+    pub exception_type: ExceptionType,
+    /// The name of the holiday falling on that date, if any.
+    pub holiday: Option<String>,
+}
+
+impl Dataset {
+    /// Returns `true` if the service runs on `date`.
+    ///
+    /// An exception defined in calendar_dates.txt for that date takes precedence over the weekly
+    /// pattern defined in calendar.txt.
+    pub fn is_service_active(&self, service_id: &CalendarServiceId, date: NaiveDate) -> bool {
+        if let Some(calendar_date) = self.calendar_dates.get(&(service_id.clone(), date)) {
+            return calendar_date.exception_type == ExceptionType::Added;
+        }
+
+        self.calendar
+            .get(service_id)
+            .is_some_and(|calendar| calendar.is_available_on_date(date))
+    }
+
+    /// Lists the days where a service departs from its weekly pattern, labeled with the holiday
+    /// they fall on, if any.
+    ///
+    /// Only the exceptions of services defined in calendar.txt are listed; services solely defined
+    /// in calendar_dates.txt have no weekly pattern to depart from.
+    ///
+    /// Days are sorted by date, then by service.
+    pub fn exceptional_service_days(
+        &self,
+        holidays: &dyn HolidayCalendar,
+    ) -> Vec<ExceptionalServiceDay> {
+        let mut days: Vec<ExceptionalServiceDay> = self
+            .calendar_dates
+            .iter()
+            .filter(|calendar_date| self.calendar.contains_key(&calendar_date.service_id))
+            .map(|calendar_date| ExceptionalServiceDay {
+                date: calendar_date.date,
+                service_id: calendar_date.service_id.clone(),
+                exception_type: calendar_date.exception_type.clone(),
+                holiday: holidays.holiday(calendar_date.date),
+            })
+            .collect();
+
+        days.sort_by(|a, b| (a.date, &*a.service_id).cmp(&(b.date, &*b.service_id)));
+        days
+    }
+
+    /// Returns the dates where the service runs, sorted.
+    ///
+    /// The dates of the weekly pattern defined in calendar.txt are expanded, then the exceptions
+    /// defined in calendar_dates.txt are applied.
+    pub fn service_dates(&self, service_id: &CalendarServiceId) -> Vec<NaiveDate> {
+        let mut dates: BTreeSet<NaiveDate> = self
+            .calendar
+            .get(service_id)
+            .map(|calendar| calendar.dates_in_range(..))
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        for calendar_date in self.calendar_dates.iter() {
+            if calendar_date.service_id != *service_id {
+                continue;
+            }
+            match calendar_date.exception_type {
+                ExceptionType::Added => dates.insert(calendar_date.date),
+                ExceptionType::Removed => dates.remove(&calendar_date.date),
+                ExceptionType::Unknown(_) => false,
+            };
+        }
+
+        dates.into_iter().collect()
+    }
+
+    /// Returns the dates where each service of calendar.txt or calendar_dates.txt runs, as
+    /// [`Dataset::service_dates`] but reading calendar_dates.txt once for all services.
+    pub(crate) fn service_dates_all(&self) -> HashMap<CalendarServiceId, BTreeSet<NaiveDate>> {
+        let mut services: HashMap<CalendarServiceId, BTreeSet<NaiveDate>> = self
+            .calendar
+            .iter()
+            .map(|calendar| {
+                let dates = calendar.dates_in_range(..).into_iter().collect();
+                (calendar.service_id.clone(), dates)
+            })
+            .collect();
+        for calendar_date in self.calendar_dates.iter() {
+            let dates = services
+                .entry(calendar_date.service_id.clone())
+                .or_default();
+            match calendar_date.exception_type {
+                ExceptionType::Added => dates.insert(calendar_date.date),
+                ExceptionType::Removed => dates.remove(&calendar_date.date),
+                ExceptionType::Unknown(_) => false,
+            };
+        }
+        services
+    }
+
+    /// Returns the services defined in calendar.txt or calendar_dates.txt.
+    pub(crate) fn service_ids(&self) -> HashSet<CalendarServiceId> {
+        self.calendar
+            .iter()
+            .map(|calendar| calendar.service_id.clone())
+            .chain(
+                self.calendar_dates
+                    .iter()
+                    .map(|calendar_date| calendar_date.service_id.clone()),
+            )
+            .collect()
+    }
+
+    /// Rewrites calendar.txt and calendar_dates.txt so that all services are defined in `form`.
+    ///
+    /// The dates where each service runs are kept. With [`CalendarForm::WeeklyPatterns`], a day
+    /// of the week is part of the pattern when the service runs on most of its occurrences
+    /// between the first and last dates of service, which keeps the exceptions to a minimum.
+    /// Services that never run are left untouched.
+    pub fn normalize_calendars(&mut self, form: CalendarForm) {
+        let services: Vec<(CalendarServiceId, Vec<NaiveDate>)> = self
+            .service_dates_all()
+            .into_iter()
+            .filter(|(_, dates)| !dates.is_empty())
+            .map(|(service_id, dates)| (service_id, dates.into_iter().collect()))
+            .collect();
+
+        let rewritten: HashSet<&CalendarServiceId> =
+            services.iter().map(|(service_id, _)| service_id).collect();
+        self.calendar
+            .retain(|service_id, _| !rewritten.contains(service_id));
+        self.calendar_dates
+            .retain(|(service_id, _), _| !rewritten.contains(service_id));
+
+        for (service_id, dates) in &services {
+            match form {
+                CalendarForm::WeeklyPatterns => self.insert_weekly_pattern(service_id, dates),
+                CalendarForm::ExplicitDates => {
+                    for date in dates {
+                        self.insert_calendar_date(service_id, *date, ExceptionType::Added);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Stops the services from running on `date`, e.g. on a holiday, by writing the exceptions
+    /// of calendar_dates.txt.
+    ///
+    /// Services already not running on `date` are left untouched. Fails without changing
+    /// anything if a service is not defined in calendar.txt or calendar_dates.txt.
+    pub fn cancel_service_on(
+        &mut self,
+        date: NaiveDate,
+        service_ids: &[CalendarServiceId],
+    ) -> Result<()> {
+        for service_id in service_ids {
+            self.check_service_exists(service_id)?;
+        }
+        for service_id in service_ids {
+            self.set_service_active(service_id, date, false);
+        }
+        Ok(())
+    }
+
+    /// Runs the trips of `template_service` on `date` as well, e.g. the Sunday service on a
+    /// holiday, by writing the exception of calendar_dates.txt.
+    ///
+    /// Nothing changes if the service already runs on `date`. Fails if the service is not
+    /// defined in calendar.txt or calendar_dates.txt.
+    pub fn add_extra_service_on(
+        &mut self,
+        date: NaiveDate,
+        template_service: &CalendarServiceId,
+    ) -> Result<()> {
+        self.check_service_exists(template_service)?;
+        self.set_service_active(template_service, date, true);
+        Ok(())
+    }
+
+    /// Fails if the service is not defined in calendar.txt or calendar_dates.txt.
+    fn check_service_exists(&self, service_id: &CalendarServiceId) -> Result<()> {
+        let exists = self.calendar.contains_key(service_id)
+            || self
+                .calendar_dates
+                .iter()
+                .any(|calendar_date| calendar_date.service_id == *service_id);
+        if !exists {
+            return Err(DatasetValidationError::new_foreign_key_not_found(
+                "service_id".to_string(),
+                service_id.to_string(),
+                GtfsFile::Calendar.file_name().to_string(),
+                vec![],
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Makes the service run on `date` or not, with as few exceptions as possible.
+    ///
+    /// The exception of `date` is removed when the weekly pattern of the service already gives
+    /// the expected result. Services solely defined in calendar_dates.txt keep their exception,
+    /// so that their trips still refer to an existing service.
+    fn set_service_active(&self, service_id: &CalendarServiceId, date: NaiveDate, active: bool) {
+        if self.is_service_active(service_id, date) == active {
+            return;
+        }
+        let by_pattern = self
+            .calendar
+            .get(service_id)
+            .map(|calendar| calendar.is_available_on_date(date));
+        match (by_pattern, active) {
+            (Some(by_pattern), _) if by_pattern == active => {
+                self.calendar_dates.remove(&(service_id.clone(), date));
+            }
+            (_, true) => self.insert_calendar_date(service_id, date, ExceptionType::Added),
+            (_, false) => self.insert_calendar_date(service_id, date, ExceptionType::Removed),
+        }
+    }
+
+    /// Defines a service by a weekly pattern and its exceptions, see
+    /// [`Dataset::normalize_calendars`].
+    pub(crate) fn insert_weekly_pattern(
+        &self,
+        service_id: &CalendarServiceId,
+        dates: &[NaiveDate],
+    ) {
+        let (Some(&start_date), Some(&end_date)) = (dates.first(), dates.last()) else {
+            return;
+        };
+        let dates: HashSet<NaiveDate> = dates.iter().copied().collect();
+        let days: Vec<NaiveDate> = start_date
+            .iter_days()
+            .take_while(|date| *date <= end_date)
+            .collect();
+
+        let is_available = |weekday: Weekday| {
+            let (active, total) = days
+                .iter()
+                .filter(|date| date.weekday() == weekday)
+                .fold((0, 0), |(active, total), date| {
+                    (active + usize::from(dates.contains(date)), total + 1)
+                });
+            CalendarDayService::from(active * 2 > total)
+        };
+        let calendar = Calendar {
+            service_id: service_id.clone(),
+            monday: is_available(Weekday::Mon),
+            tuesday: is_available(Weekday::Tue),
+            wednesday: is_available(Weekday::Wed),
+            thursday: is_available(Weekday::Thu),
+            friday: is_available(Weekday::Fri),
+            saturday: is_available(Weekday::Sat),
+            sunday: is_available(Weekday::Sun),
+            start_date,
+            end_date,
+            #[cfg(feature = "extensions")]
+            extensions: HashMap::new(),
+        };
+
+        for date in days {
+            match (calendar.is_available_on_date(date), dates.contains(&date)) {
+                (false, true) => self.insert_calendar_date(service_id, date, ExceptionType::Added),
+                (true, false) => {
+                    self.insert_calendar_date(service_id, date, ExceptionType::Removed)
+                }
+                _ => {}
+            }
+        }
+        self.calendar.insert(service_id.clone(), calendar);
+    }
+
+    /// Adds an exception to calendar_dates.txt.
+    fn insert_calendar_date(
+        &self,
+        service_id: &CalendarServiceId,
+        date: NaiveDate,
+        exception_type: ExceptionType,
+    ) {
+        self.calendar_dates.insert(
+            (service_id.clone(), date),
+            CalendarDate {
+                service_id: service_id.clone(),
+                date,
+                exception_type,
+                #[cfg(feature = "extensions")]
+                extensions: HashMap::new(),
+            },
+        );
+    }
+}
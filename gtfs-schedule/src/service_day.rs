@@ -0,0 +1,111 @@
+//! Sharding of the schedule of a dataset by service day.
+//!
+//! The main types are:
+//! - [`ServiceDayShard`]: The trips running on a single service day, with their stop times.
+
+use std::ops::RangeInclusive;
+
+use chrono::NaiveDate;
+
+use crate::schemas::{Frequency, StopTime, Trip, TripId};
+use crate::Dataset;
+
+/// The trips running on a single service day, see [`Dataset::split_by_service_date`].
+///
+/// The shard owns copies of its records, so that it can be kept while the dataset is dropped or
+/// reloaded. The other tables, such as stops and routes, are not copied, as they do not depend
+/// on the day.
+#[derive(Debug, Clone)]
+pub struct ServiceDayShard {
+    /// The service day.
+    pub date: NaiveDate,
+    /// The trips running on the day, sorted by id.
+    pub trips: Vec<Trip>,
+    /// The stop times of the trips, sorted by trip id, then by stop sequence.
+    pub stop_times: Vec<StopTime>,
+    /// The frequencies of the trips, sorted by trip id, then by start time.
+    pub frequencies: Vec<Frequency>,
+}
+
+impl ServiceDayShard {
+    /// Returns a trip of the day, `None` if it does not run on that day.
+    pub fn trip(&self, trip_id: &TripId) -> Option<&Trip> {
+        self.trips
+            .binary_search_by(|trip| trip.trip_id.as_str().cmp(trip_id.as_str()))
+            .ok()
+            .map(|index| &self.trips[index])
+    }
+
+    /// Returns the stop times of a trip of the day, sorted by stop sequence.
+    pub fn stop_times_for_trip(&self, trip_id: &TripId) -> &[StopTime] {
+        let start = self
+            .stop_times
+            .partition_point(|stop_time| stop_time.trip_id.as_str() < trip_id.as_str());
+        let end = self
+            .stop_times
+            .partition_point(|stop_time| stop_time.trip_id.as_str() <= trip_id.as_str());
+        &self.stop_times[start..end]
+    }
+}
+
+impl Dataset {
+    /// Splits the trips running on `dates` by service day, see [`ServiceDayShard`].
+    ///
+    /// Shards are built lazily, one day at a time, so that only the days being used are held in
+    /// memory, e.g. today and tomorrow for a realtime system. Building a shard goes through every
+    /// stop time of the dataset.
+    pub fn split_by_service_date(
+        &self,
+        dates: RangeInclusive<NaiveDate>,
+    ) -> impl Iterator<Item = ServiceDayShard> + '_ {
+        let end = *dates.end();
+        dates
+            .start()
+            .iter_days()
+            .take_while(move |date| *date <= end)
+            .map(|date| self.service_day_shard(date))
+    }
+
+    /// Builds the shard of a single service day, see [`Dataset::split_by_service_date`].
+    pub fn service_day_shard(&self, date: NaiveDate) -> ServiceDayShard {
+        let mut trips: Vec<Trip> = self
+            .trips
+            .iter()
+            .filter(|trip| self.is_service_active(&trip.service_id, date))
+            .map(|trip| trip.value().clone())
+            .collect();
+        trips.sort_by(|a, b| a.trip_id.as_str().cmp(b.trip_id.as_str()));
+        let runs = |trip_id: &TripId| {
+            trips
+                .binary_search_by(|trip| trip.trip_id.as_str().cmp(trip_id.as_str()))
+                .is_ok()
+        };
+
+        let mut stop_times: Vec<StopTime> = self
+            .stop_times
+            .iter()
+            .filter(|stop_time| runs(&stop_time.trip_id))
+            .map(|stop_time| stop_time.value().clone())
+            .collect();
+        stop_times.sort_by(|a, b| {
+            (a.trip_id.as_str(), a.stop_sequence).cmp(&(b.trip_id.as_str(), b.stop_sequence))
+        });
+        let mut frequencies: Vec<Frequency> = self
+            .frequencies
+            .iter()
+            .filter(|frequency| runs(&frequency.trip_id))
+            .map(|frequency| frequency.value().clone())
+            .collect();
+        frequencies.sort_by(|a, b| {
+            (a.trip_id.as_str(), a.start_time.as_secs())
+                .cmp(&(b.trip_id.as_str(), b.start_time.as_secs()))
+        });
+
+        ServiceDayShard {
+            date,
+            trips,
+            stop_times,
+            frequencies,
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Copies of a dataset, to keep serving queries while the dataset is edited or reloaded.
+//!
+//! The main types are:
+//! - [`FrozenDataset`]: An immutable dataset, cheap to share between threads.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use dashmap::DashMap;
+
+use crate::schemas::{
+    Agency, Area, AreaId, Attribution, BookingRule, BookingRuleId, Calendar, CalendarDate,
+    CalendarServiceId, FareAttribute, FareId, FareLegRule, FareMedia, FareMediaId, FareProduct,
+    FareProductId, FareRule, FareTransferRule, FeedInfo, Frequency, Level, LevelId, LocationGroup,
+    LocationGroupId, LocationGroupStop, NaiveServiceTime, Network, NetworkId, Pathway, PathwayId,
+    Route, RouteId, RouteNetwork, Shape, ShapeId, Stop, StopArea, StopId, StopTime, Timeframe,
+    Transfer, Translation, Trip, TripId,
+};
+use crate::Dataset;
+
+/// An immutable dataset, see [`Dataset::freeze`].
+///
+/// Tables are plain maps, so reading them takes no lock, and nothing can change them once
+/// frozen: a service can share an `Arc<FrozenDataset>` between the threads answering queries,
+/// and swap it for a newer one when the feed is reloaded, without readers ever seeing a
+/// partially loaded feed.
+///
+/// ```no_run
+/// use std::sync::{Arc, RwLock};
+///
+/// use gtfs_schedule::{Dataset, FrozenDataset};
+///
+/// let current: RwLock<Arc<FrozenDataset>> =
+///     RwLock::new(Arc::new(Dataset::from_csv("feed".as_ref()).unwrap().freeze()));
+///
+/// // Readers only hold the lock while cloning the `Arc`.
+/// let feed = current.read().unwrap().clone();
+/// println!("{} stops", feed.stops.len());
+///
+/// // The new feed is loaded without the lock, then swapped in.
+/// let reloaded = Arc::new(Dataset::from_csv("feed".as_ref()).unwrap().freeze());
+/// *current.write().unwrap() = reloaded;
+/// ```
+///
+/// The fields mirror those of [`Dataset`].
+#[derive(Debug, Clone, Default)]
+pub struct FrozenDataset {
+    pub agencies: Vec<Agency>,
+    pub stops: HashMap<StopId, Stop>,
+    pub routes: HashMap<RouteId, Route>,
+    pub trips: HashMap<TripId, Trip>,
+    pub stop_times: HashMap<(TripId, u32), StopTime>,
+    pub calendar: HashMap<CalendarServiceId, Calendar>,
+    pub calendar_dates: HashMap<(CalendarServiceId, NaiveDate), CalendarDate>,
+    pub fare_attributes: HashMap<FareId, FareAttribute>,
+    pub fare_rules: Vec<FareRule>,
+    pub timeframes: Vec<Timeframe>,
+    pub fare_medias: HashMap<FareMediaId, FareMedia>,
+    pub fare_products: HashMap<(FareProductId, Option<FareMediaId>), FareProduct>,
+    pub fare_leg_rules: Vec<FareLegRule>,
+    pub fare_transfers: Vec<FareTransferRule>,
+    pub areas: HashMap<AreaId, Area>,
+    pub stops_areas: Vec<StopArea>,
+    pub networks: HashMap<NetworkId, Network>,
+    pub routes_networks: HashMap<RouteId, RouteNetwork>,
+    pub shapes: HashMap<(ShapeId, u32), Shape>,
+    pub frequencies: HashMap<(TripId, NaiveServiceTime), Frequency>,
+    pub transfers: Vec<Transfer>,
+    pub pathways: HashMap<PathwayId, Pathway>,
+    pub levels: HashMap<LevelId, Level>,
+    pub location_groups: HashMap<LocationGroupId, LocationGroup>,
+    pub location_groups_stops: Vec<LocationGroupStop>,
+    pub booking_rules: HashMap<BookingRuleId, BookingRule>,
+    pub translations: Vec<Translation>,
+    pub feed_info: Option<FeedInfo>,
+    pub attributions: Vec<Attribution>,
+    /// The stop sequences of each trip, in order.
+    stop_sequences: HashMap<TripId, Vec<u32>>,
+}
+
+impl FrozenDataset {
+    /// Returns the stop times of a trip, ordered by [`StopTime::stop_sequence`].
+    ///
+    /// Unlike [`Dataset::iter_stop_times_for_trip`], the stop times are indexed by trip, so this
+    /// does not go through the stop times of the other trips.
+    pub fn stop_times_for_trip<'a>(
+        &'a self,
+        trip_id: &'a TripId,
+    ) -> impl Iterator<Item = &'a StopTime> + 'a {
+        self.stop_sequences
+            .get(trip_id)
+            .into_iter()
+            .flatten()
+            .filter_map(move |stop_sequence| {
+                self.stop_times.get(&(trip_id.clone(), *stop_sequence))
+            })
+    }
+}
+
+/// Copies a table, so that changes to either copy do not show in the other.
+fn copy_table<K: Eq + Hash + Clone, V: Clone>(table: &Arc<DashMap<K, V>>) -> Arc<DashMap<K, V>> {
+    Arc::new(DashMap::clone(table))
+}
+
+/// Takes the records of a table, copying them only if the table is shared with another dataset.
+fn freeze_table<K: Eq + Hash + Clone, V: Clone>(table: Arc<DashMap<K, V>>) -> HashMap<K, V> {
+    match Arc::try_unwrap(table) {
+        Ok(table) => table.into_iter().collect(),
+        Err(table) => table
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect(),
+    }
+}
+
+impl Dataset {
+    /// Returns a copy of the dataset, to keep reading it while the original is edited.
+    ///
+    /// The tables are copied rather than shared: they can be changed through a shared
+    /// reference, e.g. with [`DashMap::insert`], so sharing them would let the edits of one
+    /// dataset show in the other. Identifiers are interned, so copying a record mostly copies
+    /// reference-counted pointers rather than text.
+    pub fn snapshot(&self) -> Dataset {
+        Dataset {
+            agencies: self.agencies.clone(),
+            stops: copy_table(&self.stops),
+            routes: copy_table(&self.routes),
+            trips: copy_table(&self.trips),
+            stop_times: copy_table(&self.stop_times),
+            calendar: copy_table(&self.calendar),
+            calendar_dates: copy_table(&self.calendar_dates),
+            fare_attributes: copy_table(&self.fare_attributes),
+            fare_rules: self.fare_rules.clone(),
+            timeframes: self.timeframes.clone(),
+            fare_medias: copy_table(&self.fare_medias),
+            fare_products: copy_table(&self.fare_products),
+            fare_leg_rules: self.fare_leg_rules.clone(),
+            fare_transfers: self.fare_transfers.clone(),
+            areas: copy_table(&self.areas),
+            stops_areas: self.stops_areas.clone(),
+            networks: copy_table(&self.networks),
+            routes_networks: copy_table(&self.routes_networks),
+            shapes: copy_table(&self.shapes),
+            frequencies: copy_table(&self.frequencies),
+            transfers: self.transfers.clone(),
+            pathways: copy_table(&self.pathways),
+            levels: copy_table(&self.levels),
+            location_groups: copy_table(&self.location_groups),
+            location_groups_stops: self.location_groups_stops.clone(),
+            booking_rules: copy_table(&self.booking_rules),
+            translations: self.translations.clone(),
+            feed_info: self.feed_info.clone(),
+            attributions: self.attributions.clone(),
+            skipped_tables: self.skipped_tables.clone(),
+            parse_report: self.parse_report.clone(),
+            interned_ids: self.interned_ids.clone(),
+            source_lines: self.source_lines.clone(),
+            dirty: self.dirty.clone(),
+        }
+    }
+
+    /// Turns the dataset into an immutable [`FrozenDataset`], faster to read and safe to share.
+    ///
+    /// Records are moved rather than copied, unless a table is shared with another dataset
+    /// through its [`Arc`]. Use [`Dataset::snapshot`] first to keep an editable dataset.
+    pub fn freeze(self) -> FrozenDataset {
+        let stop_times = freeze_table(self.stop_times);
+        let mut stop_sequences: HashMap<TripId, Vec<u32>> = HashMap::new();
+        for (trip_id, stop_sequence) in stop_times.keys() {
+            stop_sequences
+                .entry(trip_id.clone())
+                .or_default()
+                .push(*stop_sequence);
+        }
+        for sequences in stop_sequences.values_mut() {
+            sequences.sort_unstable();
+        }
+
+        FrozenDataset {
+            agencies: self.agencies,
+            stops: freeze_table(self.stops),
+            routes: freeze_table(self.routes),
+            trips: freeze_table(self.trips),
+            stop_times,
+            calendar: freeze_table(self.calendar),
+            calendar_dates: freeze_table(self.calendar_dates),
+            fare_attributes: freeze_table(self.fare_attributes),
+            fare_rules: self.fare_rules,
+            timeframes: self.timeframes,
+            fare_medias: freeze_table(self.fare_medias),
+            fare_products: freeze_table(self.fare_products),
+            fare_leg_rules: self.fare_leg_rules,
+            fare_transfers: self.fare_transfers,
+            areas: freeze_table(self.areas),
+            stops_areas: self.stops_areas,
+            networks: freeze_table(self.networks),
+            routes_networks: freeze_table(self.routes_networks),
+            shapes: freeze_table(self.shapes),
+            frequencies: freeze_table(self.frequencies),
+            transfers: self.transfers,
+            pathways: freeze_table(self.pathways),
+            levels: freeze_table(self.levels),
+            location_groups: freeze_table(self.location_groups),
+            location_groups_stops: self.location_groups_stops,
+            booking_rules: freeze_table(self.booking_rules),
+            translations: self.translations,
+            feed_info: self.feed_info,
+            attributions: self.attributions,
+            stop_sequences,
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! Stations along with their locations, levels and pathways.
+//!
+//! The main types are:
+//! - [`Station`]: A station and everything within it.
+//!
+//! Feeds that omit stations can have them inferred with [`Dataset::infer_parent_stations`].
+
+use std::collections::{HashMap, HashSet};
+
+use geo::{Coord, HaversineDistance, Point};
+
+use crate::schemas::{Level, LevelId, LocationType, Pathway, Stop, StopId};
+use crate::Dataset;
+
+/// A station and everything within it, see [`Dataset::station`].
+///
+/// The station holds copies of its records: it does not reflect the changes made to the
+/// dataset after it was built. Locations are sorted by id.
+#[derive(Debug, Clone)]
+pub struct Station {
+    stop: Stop,
+    platforms: Vec<Stop>,
+    entrances: Vec<Stop>,
+    generic_nodes: Vec<Stop>,
+    boarding_areas: Vec<Stop>,
+    levels: Vec<Level>,
+    pathways: Vec<Pathway>,
+}
+
+impl Station {
+    /// The stop of the station itself.
+    pub fn stop(&self) -> &Stop {
+        &self.stop
+    }
+
+    /// The platforms of the station, where vehicles are boarded.
+    pub fn platforms(&self) -> &[Stop] {
+        &self.platforms
+    }
+
+    /// The entrances and exits of the station.
+    pub fn entrances(&self) -> &[Stop] {
+        &self.entrances
+    }
+
+    /// The generic nodes of the station, linking its pathways.
+    pub fn generic_nodes(&self) -> &[Stop] {
+        &self.generic_nodes
+    }
+
+    /// The boarding areas of every platform of the station.
+    pub fn boarding_areas(&self) -> &[Stop] {
+        &self.boarding_areas
+    }
+
+    /// The boarding areas of a platform of the station.
+    pub fn boarding_areas_of<'a>(
+        &'a self,
+        platform_id: &'a StopId,
+    ) -> impl Iterator<Item = &'a Stop> + 'a {
+        self.boarding_areas
+            .iter()
+            .filter(move |stop| stop.parent_station.as_ref() == Some(platform_id))
+    }
+
+    /// The levels of the locations of the station, sorted by
+    /// [`crate::schemas::Level::level_index`].
+    pub fn levels(&self) -> &[Level] {
+        &self.levels
+    }
+
+    /// The pathways starting or ending in the station.
+    pub fn pathways(&self) -> &[Pathway] {
+        &self.pathways
+    }
+
+    /// Iterates over every location of the station, the station first.
+    pub fn locations(&self) -> impl Iterator<Item = &Stop> {
+        std::iter::once(&self.stop)
+            .chain(&self.platforms)
+            .chain(&self.entrances)
+            .chain(&self.generic_nodes)
+            .chain(&self.boarding_areas)
+    }
+
+    /// Whether a location belongs to the station, or is the station itself.
+    pub fn contains(&self, stop_id: &StopId) -> bool {
+        self.locations().any(|stop| stop.stop_id == *stop_id)
+    }
+
+    /// Returns the level of a location of the station.
+    pub fn level_of(&self, stop_id: &StopId) -> Option<&Level> {
+        let level_id = self
+            .locations()
+            .find(|stop| stop.stop_id == *stop_id)?
+            .level_id
+            .as_ref()?;
+        self.levels.iter().find(|level| level.level_id == *level_id)
+    }
+
+    /// Iterates over the locations of the station on a level.
+    pub fn locations_on_level<'a>(
+        &'a self,
+        level_id: &'a LevelId,
+    ) -> impl Iterator<Item = &'a Stop> + 'a {
+        self.locations()
+            .filter(move |stop| stop.level_id.as_ref() == Some(level_id))
+    }
+}
+
+impl Dataset {
+    /// Builds the station holding a location, or the station itself.
+    ///
+    /// Returns `None` if the stop is unknown, or is neither a station nor within one.
+    pub fn station(&self, stop_id: &StopId) -> Option<Station> {
+        let mut station = self.stops.get(stop_id)?.value().clone();
+        let mut visited = HashSet::new();
+        while !matches!(station.location_type, Some(LocationType::Station)) {
+            if !visited.insert(station.stop_id.clone()) {
+                return None;
+            }
+            let parent_station = station.parent_station.as_ref()?;
+            station = self.stops.get(parent_station)?.value().clone();
+        }
+
+        let mut children: Vec<Stop> = self
+            .stops
+            .iter()
+            .filter(|stop| stop.parent_station.as_ref() == Some(&station.stop_id))
+            .map(|stop| stop.value().clone())
+            .collect();
+        children.sort_by(|a, b| a.stop_id.as_str().cmp(b.stop_id.as_str()));
+        let mut platforms = vec![];
+        let mut entrances = vec![];
+        let mut generic_nodes = vec![];
+        for child in children {
+            match child.location_type {
+                Some(LocationType::EntranceOrExit) => entrances.push(child),
+                Some(LocationType::GenericNode) => generic_nodes.push(child),
+                Some(
+                    LocationType::Station | LocationType::BoardingArea | LocationType::Unknown(_),
+                ) => {}
+                None | Some(LocationType::StopOrPlatform) => platforms.push(child),
+            }
+        }
+
+        let platform_ids: HashSet<&StopId> =
+            platforms.iter().map(|platform| &platform.stop_id).collect();
+        let mut boarding_areas: Vec<Stop> = self
+            .stops
+            .iter()
+            .filter(|stop| matches!(stop.location_type, Some(LocationType::BoardingArea)))
+            .filter(|stop| {
+                stop.parent_station
+                    .as_ref()
+                    .is_some_and(|parent_station| platform_ids.contains(parent_station))
+            })
+            .map(|stop| stop.value().clone())
+            .collect();
+        boarding_areas.sort_by(|a, b| a.stop_id.as_str().cmp(b.stop_id.as_str()));
+
+        let mut station = Station {
+            stop: station,
+            platforms,
+            entrances,
+            generic_nodes,
+            boarding_areas,
+            levels: vec![],
+            pathways: vec![],
+        };
+
+        let level_ids: HashSet<LevelId> = station
+            .locations()
+            .filter_map(|stop| stop.level_id.clone())
+            .collect();
+        station.levels = level_ids
+            .iter()
+            .filter_map(|level_id| self.levels.get(level_id).map(|level| level.clone()))
+            .collect();
+        station.levels.sort_by(|a, b| {
+            a.level_index
+                .total_cmp(&b.level_index)
+                .then_with(|| a.level_id.as_str().cmp(b.level_id.as_str()))
+        });
+
+        let location_ids: HashSet<StopId> = station
+            .locations()
+            .map(|stop| stop.stop_id.clone())
+            .collect();
+        station.pathways = self
+            .pathways
+            .iter()
+            .filter(|pathway| {
+                location_ids.contains(&pathway.from_stop_id)
+                    || location_ids.contains(&pathway.to_stop_id)
+            })
+            .map(|pathway| pathway.value().clone())
+            .collect();
+        station
+            .pathways
+            .sort_by(|a, b| a.pathway_id.as_str().cmp(b.pathway_id.as_str()));
+
+        Some(station)
+    }
+
+    /// Groups nearby platforms sharing a similar name under new parent stations, for feeds that
+    /// omit them.
+    ///
+    /// Only the stops and platforms without a parent station, and with a name and coordinates,
+    /// are grouped. Two of them belong to the same station when they are at most
+    /// `max_distance_m` meters apart, and the similarity of their names is at least
+    /// `name_similarity`, from 0 (any names) to 1 (the same name, ignoring case and punctuation).
+    /// Groups are formed transitively, so a station may span more than `max_distance_m`.
+    ///
+    /// Each group of at least two platforms gets a station, located at their centroid and named
+    /// after the most common name of its platforms, the first one alphabetically on ties. Its id
+    /// is the lowest id of its platforms, followed by `_station`.
+    ///
+    /// Returns the ids of the new stations, sorted.
+    pub fn infer_parent_stations(
+        &mut self,
+        max_distance_m: f64,
+        name_similarity: f64,
+    ) -> Vec<StopId> {
+        let mut platforms: Vec<(Stop, Coord, String)> = self
+            .stops
+            .iter()
+            .filter(|stop| {
+                matches!(
+                    stop.location_type,
+                    None | Some(LocationType::StopOrPlatform)
+                ) && stop.parent_station.is_none()
+            })
+            .filter_map(|stop| {
+                let coord = **stop.stop_coord.as_ref()?;
+                let name = normalize_name(stop.stop_name.as_deref()?);
+                Some((stop.value().clone(), coord, name))
+            })
+            .filter(|(_, _, name)| !name.is_empty())
+            .collect();
+        platforms.sort_by(|a, b| {
+            a.1.y
+                .total_cmp(&b.1.y)
+                .then_with(|| a.0.stop_id.as_str().cmp(b.0.stop_id.as_str()))
+        });
+
+        // Union-find of the platforms, sweeping them by latitude. A degree of latitude is a bit
+        // more than 110 km everywhere.
+        let max_latitude_delta = max_distance_m / 110_000.0;
+        let mut groups: Vec<usize> = (0..platforms.len()).collect();
+        fn root(groups: &mut [usize], mut index: usize) -> usize {
+            while groups[index] != index {
+                groups[index] = groups[groups[index]];
+                index = groups[index];
+            }
+            index
+        }
+        for i in 0..platforms.len() {
+            for j in i + 1..platforms.len() {
+                let ((_, a, a_name), (_, b, b_name)) = (&platforms[i], &platforms[j]);
+                if b.y - a.y > max_latitude_delta {
+                    break;
+                }
+                if Point::from(*a).haversine_distance(&Point::from(*b)) <= max_distance_m
+                    && similarity(a_name, b_name) >= name_similarity
+                {
+                    let (a, b) = (root(&mut groups, i), root(&mut groups, j));
+                    groups[a.max(b)] = a.min(b);
+                }
+            }
+        }
+        let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+        for index in 0..platforms.len() {
+            let group = root(&mut groups, index);
+            members.entry(group).or_default().push(index);
+        }
+
+        let mut station_ids = vec![];
+        for indices in members.into_values().filter(|indices| indices.len() > 1) {
+            let mut group: Vec<&Stop> = indices.iter().map(|index| &platforms[*index].0).collect();
+            group.sort_by(|a, b| a.stop_id.as_str().cmp(b.stop_id.as_str()));
+
+            let mut station_id = StopId::from(format!("{}_station", group[0].stop_id));
+            let mut suffix = 2;
+            while self.stops.contains_key(&station_id) {
+                station_id = StopId::from(format!("{}_station_{}", group[0].stop_id, suffix));
+                suffix += 1;
+            }
+            let mut names: HashMap<&str, usize> = HashMap::new();
+            for name in group.iter().filter_map(|stop| stop.stop_name.as_deref()) {
+                *names.entry(name).or_default() += 1;
+            }
+            let stop_name = names
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(name, _)| name.to_string());
+            let count = indices.len() as f64;
+            let centroid = Coord {
+                x: indices
+                    .iter()
+                    .map(|index| platforms[*index].1.x)
+                    .sum::<f64>()
+                    / count,
+                y: indices
+                    .iter()
+                    .map(|index| platforms[*index].1.y)
+                    .sum::<f64>()
+                    / count,
+            };
+            let stop_timezone = group[0].stop_timezone.filter(|timezone| {
+                group
+                    .iter()
+                    .all(|stop| stop.stop_timezone == Some(*timezone))
+            });
+
+            for stop in &group {
+                if let Some(mut stop) = self.stops.get_mut(&stop.stop_id) {
+                    stop.parent_station = Some(station_id.clone());
+                }
+            }
+            self.stops.insert(
+                station_id.clone(),
+                Stop {
+                    stop_id: station_id.clone(),
+                    stop_code: None,
+                    stop_name,
+                    tts_stop_name: None,
+                    stop_desc: None,
+                    stop_coord: Some(centroid.into()),
+                    zone_id: None,
+                    stop_url: None,
+                    location_type: Some(LocationType::Station),
+                    parent_station: None,
+                    stop_timezone,
+                    wheelchair_boarding: None,
+                    level_id: None,
+                    platform_code: None,
+                    #[cfg(feature = "extensions")]
+                    extensions: HashMap::new(),
+                },
+            );
+            station_ids.push(station_id);
+        }
+        station_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        station_ids
+    }
+}
+
+/// Lowercases a name and collapses its punctuation and whitespace into single spaces.
+pub(crate) fn normalize_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Similarity of two names, from 0 (nothing in common) to 1 (the same), based on their
+/// Levenshtein distance.
+pub(crate) fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    1.0 - row[b.len()] as f64 / longest as f64
+}
@@ -0,0 +1,361 @@
+//! Navigation within stations, built on top of pathways.
+//!
+//! The main types are:
+//! - [`StationGraph`]: The locations of the stations and the pathways linking them.
+//! - [`AccessibilityProfile`]: The constraints of a rider moving through a station.
+//! - [`StationPath`]: A path between two locations of a station.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::time::Duration;
+
+use geo::{HaversineDistance, Point};
+
+use crate::schemas::{LocationType, PathwayId, PathwayMode, StopId, WheelchairBoarding};
+use crate::Dataset;
+
+/// Walking speed used when a pathway only provides its length, in meters per second.
+const WALKING_SPEED: f64 = 1.3;
+/// Time needed to climb a single stair, in seconds.
+const STAIR_TIME: f64 = 0.8;
+/// Number of stairs between two consecutive levels, as recommended by the specification.
+const STAIRS_PER_LEVEL: f64 = 15.0;
+/// Time spent waiting for an elevator, in seconds.
+const ELEVATOR_WAIT_TIME: f64 = 30.0;
+/// Time needed by an elevator to move between two consecutive levels, in seconds.
+const ELEVATOR_LEVEL_TIME: f64 = 5.0;
+/// Time assumed for a pathway nothing is known about, in seconds.
+const DEFAULT_PATHWAY_TIME: f64 = 30.0;
+
+/// The constraints of a rider moving through a station.
+///
+/// The default profile has no constraint.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AccessibilityProfile {
+    /// Do not use stairs ([`PathwayMode::Stairs`]).
+    pub avoid_stairs: bool,
+    /// Do not use escalators ([`PathwayMode::Escalator`]).
+    pub avoid_escalators: bool,
+    /// Do not go through locations where [`crate::schemas::Stop::wheelchair_boarding`] is
+    /// [`WheelchairBoarding::NoAccessibility`], directly or inherited from their parent station.
+    pub avoid_inaccessible_locations: bool,
+    /// Do not use pathways steeper than this slope ratio.
+    pub max_slope: Option<f32>,
+    /// Do not use pathways narrower than this width, in meters.
+    pub min_width: Option<f32>,
+}
+
+impl AccessibilityProfile {
+    /// The constraints of a rider in a wheelchair.
+    ///
+    /// The maximum slope is the one of hand-propelled wheelchairs in the US.
+    pub fn wheelchair() -> Self {
+        Self {
+            avoid_stairs: true,
+            avoid_escalators: true,
+            avoid_inaccessible_locations: true,
+            max_slope: Some(0.083),
+            min_width: None,
+        }
+    }
+}
+
+/// A path between two locations of a station, see [`StationGraph::shortest_path`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StationPath {
+    /// The locations visited by the path, including its origin and destination.
+    ///
+    /// When the origin or destination is a platform, the boarding area used is listed next to it.
+    pub stop_ids: Vec<StopId>,
+    /// The pathways taken, in order.
+    pub pathway_ids: Vec<PathwayId>,
+    /// The estimated time needed to follow the path.
+    pub traversal_time: Duration,
+}
+
+/// A way to move from one location to another.
+#[derive(Debug, Clone)]
+struct Edge {
+    to_stop_id: StopId,
+    pathway_id: PathwayId,
+    pathway_mode: PathwayMode,
+    max_slope: Option<f32>,
+    min_width: Option<f32>,
+    traversal_time: Duration,
+}
+
+/// A location waiting to be visited, ordered by the time needed to reach it.
+struct Visit<'a> {
+    time: Duration,
+    stop_id: &'a StopId,
+}
+
+impl PartialEq for Visit<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.time == other.time
+    }
+}
+
+impl Eq for Visit<'_> {}
+
+impl PartialOrd for Visit<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Visit<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.time.cmp(&other.time)
+    }
+}
+
+impl Edge {
+    fn is_allowed(&self, profile: &AccessibilityProfile) -> bool {
+        match self.pathway_mode {
+            PathwayMode::Stairs if profile.avoid_stairs => return false,
+            PathwayMode::Escalator if profile.avoid_escalators => return false,
+            _ => {}
+        }
+        let too_steep = matches!(
+            (self.max_slope, profile.max_slope),
+            (Some(slope), Some(max_slope)) if slope.abs() > max_slope
+        );
+        let too_narrow = matches!(
+            (self.min_width, profile.min_width),
+            (Some(width), Some(min_width)) if width < min_width
+        );
+
+        !too_steep && !too_narrow
+    }
+}
+
+/// The locations of the stations of a dataset, and the ways to move between them.
+///
+/// Locations are linked by the pathways of pathways.txt. A platform with boarding areas is reached
+/// through any of its boarding areas, as pathways then end at the boarding areas. The traversal
+/// time of a pathway is, in order of preference, its
+/// [`crate::schemas::Pathway::traversal_time`], or estimated from its length, its stair count,
+/// the [`crate::schemas::Level::level_index`] of its ends, or the distance between its ends.
+#[derive(Debug, Clone, Default)]
+pub struct StationGraph {
+    edges: HashMap<StopId, Vec<Edge>>,
+    boarding_areas: HashMap<StopId, Vec<StopId>>,
+    inaccessible_stop_ids: HashSet<StopId>,
+}
+
+impl StationGraph {
+    /// Returns the fastest path from `from_stop_id` to `to_stop_id` satisfying `profile`.
+    ///
+    /// Returns `None` if the destination can not be reached.
+    pub fn shortest_path(
+        &self,
+        from_stop_id: &StopId,
+        to_stop_id: &StopId,
+        profile: AccessibilityProfile,
+    ) -> Option<StationPath> {
+        let is_reachable = |stop_id: &StopId| {
+            !profile.avoid_inaccessible_locations || !self.inaccessible_stop_ids.contains(stop_id)
+        };
+        if !is_reachable(from_stop_id) || !is_reachable(to_stop_id) {
+            return None;
+        }
+
+        let locations = |stop_id| {
+            std::iter::once(stop_id)
+                .chain(self.boarding_areas.get(stop_id).into_iter().flatten())
+                .filter(|stop_id| is_reachable(stop_id))
+        };
+
+        let mut times: HashMap<&StopId, Duration> = HashMap::new();
+        let mut previous: HashMap<&StopId, (&StopId, &Edge)> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        for stop_id in locations(from_stop_id) {
+            times.insert(stop_id, Duration::ZERO);
+            queue.push(Reverse(Visit {
+                time: Duration::ZERO,
+                stop_id,
+            }));
+        }
+
+        let destinations: HashSet<&StopId> = locations(to_stop_id).collect();
+        let mut destination = None;
+        while let Some(Reverse(Visit { time, stop_id })) = queue.pop() {
+            if destinations.contains(stop_id) {
+                destination = Some(stop_id);
+                break;
+            }
+            if times
+                .get(stop_id)
+                .is_some_and(|best_time| *best_time < time)
+            {
+                continue;
+            }
+
+            for edge in self.edges.get(stop_id).into_iter().flatten() {
+                if !edge.is_allowed(&profile) || !is_reachable(&edge.to_stop_id) {
+                    continue;
+                }
+                let next_time = time + edge.traversal_time;
+                if times
+                    .get(&edge.to_stop_id)
+                    .is_none_or(|best_time| next_time < *best_time)
+                {
+                    times.insert(&edge.to_stop_id, next_time);
+                    previous.insert(&edge.to_stop_id, (stop_id, edge));
+                    queue.push(Reverse(Visit {
+                        time: next_time,
+                        stop_id: &edge.to_stop_id,
+                    }));
+                }
+            }
+        }
+
+        let destination = destination?;
+        let traversal_time = times[destination];
+        let mut stop_ids = vec![destination.clone()];
+        let mut pathway_ids = vec![];
+        let mut current = destination;
+        while let Some((previous_stop_id, edge)) = previous.get(current) {
+            stop_ids.push((*previous_stop_id).clone());
+            pathway_ids.push(edge.pathway_id.clone());
+            current = previous_stop_id;
+        }
+        if current != from_stop_id {
+            stop_ids.push(from_stop_id.clone());
+        }
+        stop_ids.reverse();
+        pathway_ids.reverse();
+        if destination != to_stop_id {
+            stop_ids.push(to_stop_id.clone());
+        }
+
+        Some(StationPath {
+            stop_ids,
+            pathway_ids,
+            traversal_time,
+        })
+    }
+}
+
+impl Dataset {
+    /// Builds the graph of the locations of the stations of the dataset, from pathways.txt,
+    /// levels.txt and the hierarchy of stops.txt.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn station_graph(&self) -> StationGraph {
+        let mut graph = StationGraph::default();
+
+        for pathway in self.pathways.iter() {
+            let traversal_time = pathway.traversal_time.unwrap_or_else(|| {
+                Duration::from_secs_f64(self.estimate_pathway_time(
+                    &pathway.pathway_mode,
+                    &pathway.from_stop_id,
+                    &pathway.to_stop_id,
+                    pathway.length,
+                    pathway.stair_count,
+                ))
+            });
+            let edge = Edge {
+                to_stop_id: pathway.to_stop_id.clone(),
+                pathway_id: pathway.pathway_id.clone(),
+                pathway_mode: pathway.pathway_mode.clone(),
+                max_slope: pathway.max_slope,
+                min_width: pathway.min_width,
+                traversal_time,
+            };
+            if pathway.is_bidirectional {
+                graph
+                    .edges
+                    .entry(pathway.to_stop_id.clone())
+                    .or_default()
+                    .push(Edge {
+                        to_stop_id: pathway.from_stop_id.clone(),
+                        ..edge.clone()
+                    });
+            }
+            graph
+                .edges
+                .entry(pathway.from_stop_id.clone())
+                .or_default()
+                .push(edge);
+        }
+
+        for stop in self.stops.iter() {
+            if stop.location_type == Some(LocationType::BoardingArea) {
+                if let Some(platform_id) = &stop.parent_station {
+                    graph
+                        .boarding_areas
+                        .entry(platform_id.clone())
+                        .or_default()
+                        .push(stop.stop_id.clone());
+                }
+            }
+
+            if self.stop_wheelchair_boarding(&stop.stop_id)
+                == Some(WheelchairBoarding::NoAccessibility)
+            {
+                graph.inaccessible_stop_ids.insert(stop.stop_id.clone());
+            }
+        }
+
+        graph
+    }
+
+    /// Estimates the time, in seconds, needed to go through a pathway without a traversal time.
+    fn estimate_pathway_time(
+        &self,
+        pathway_mode: &PathwayMode,
+        from_stop_id: &StopId,
+        to_stop_id: &StopId,
+        length: Option<f32>,
+        stair_count: Option<i32>,
+    ) -> f64 {
+        let level_index = |stop_id: &StopId| {
+            let level_id = self.stops.get(stop_id)?.level_id.clone()?;
+            self.levels.get(&level_id).map(|level| level.level_index)
+        };
+        let levels = match (level_index(from_stop_id), level_index(to_stop_id)) {
+            (Some(from), Some(to)) => Some(f64::from((to - from).abs())),
+            _ => None,
+        };
+        let distance = || {
+            let coord = |stop_id: &StopId| {
+                let stop = self.stops.get(stop_id)?;
+                stop.stop_coord.as_ref().map(|coord| Point::from(**coord))
+            };
+            Some(coord(from_stop_id)?.haversine_distance(&coord(to_stop_id)?))
+        };
+
+        match (pathway_mode, length, stair_count, levels) {
+            (_, Some(length), _, _) => f64::from(length) / WALKING_SPEED,
+            (PathwayMode::Stairs, _, Some(stair_count), _) => {
+                f64::from(stair_count.abs()) * STAIR_TIME
+            }
+            (PathwayMode::Stairs | PathwayMode::Escalator, _, _, Some(levels)) => {
+                levels * STAIRS_PER_LEVEL * STAIR_TIME
+            }
+            (PathwayMode::Elevator, _, _, levels) => {
+                ELEVATOR_WAIT_TIME + levels.unwrap_or(1.0) * ELEVATOR_LEVEL_TIME
+            }
+            _ => distance().map_or(DEFAULT_PATHWAY_TIME, |distance| distance / WALKING_SPEED),
+        }
+    }
+
+    /// Returns the wheelchair accessibility of a location, inherited from its parent station
+    /// when the location does not provide any information.
+    pub(crate) fn stop_wheelchair_boarding(&self, stop_id: &StopId) -> Option<WheelchairBoarding> {
+        let mut visited = HashSet::new();
+        let mut stop_id = stop_id.clone();
+        // A stop listed as the parent station of one of its ancestors would loop forever.
+        while visited.insert(stop_id.clone()) {
+            let stop = self.stops.get(&stop_id)?;
+            match &stop.wheelchair_boarding {
+                Some(WheelchairBoarding::NoInformation) | None => {
+                    stop_id = stop.parent_station.clone()?;
+                }
+                wheelchair_boarding => return wheelchair_boarding.clone(),
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,159 @@
+//! Traversal of the hierarchy of stations, platforms, entrances and boarding areas.
+//!
+//! The main type is:
+//! - [`StopHierarchy`]: The parents and children of the stops of a dataset, for repeated lookups.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::schemas::{LocationType, StopId};
+use crate::Dataset;
+
+/// The parents and children of the stops of a dataset, as given by
+/// [`crate::schemas::Stop::parent_station`], see [`Dataset::stop_hierarchy`].
+///
+/// The index is a snapshot: it does not reflect the changes made to the dataset after it was
+/// built.
+#[derive(Debug, Clone, Default)]
+pub struct StopHierarchy {
+    parents: HashMap<StopId, StopId>,
+    children: HashMap<StopId, Vec<StopId>>,
+    stations: HashSet<StopId>,
+}
+
+impl StopHierarchy {
+    /// Returns the parent station of a stop, `None` for a stop without parent.
+    pub fn parent_of(&self, stop_id: &StopId) -> Option<&StopId> {
+        self.parents.get(stop_id)
+    }
+
+    /// Returns the stops whose parent station is `stop_id`, sorted by id, see
+    /// [`Dataset::children_of`].
+    pub fn children_of(&self, stop_id: &StopId) -> &[StopId] {
+        self.children.get(stop_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the children of a stop, then their children and so on, see
+    /// [`Dataset::descendants_of`].
+    pub fn descendants_of(&self, stop_id: &StopId) -> Vec<StopId> {
+        let mut visited = HashSet::from([stop_id]);
+        let mut descendants: Vec<&StopId> = vec![];
+        let mut next = 0;
+        let mut children = self.children_of(stop_id);
+        loop {
+            for child in children {
+                // A stop listed as the parent of one of its ancestors would loop forever.
+                if visited.insert(child) {
+                    descendants.push(child);
+                }
+            }
+            let Some(stop_id) = descendants.get(next) else {
+                break;
+            };
+            children = self.children_of(stop_id);
+            next += 1;
+        }
+        descendants.into_iter().cloned().collect()
+    }
+
+    /// Returns the station at the top of the hierarchy of a stop, see
+    /// [`Dataset::root_station_of`].
+    pub fn root_station_of(&self, stop_id: &StopId) -> Option<&StopId> {
+        let mut visited = HashSet::new();
+        let mut root = None;
+        let mut current = self
+            .parents
+            .get_key_value(stop_id)
+            .map(|(stop_id, _)| stop_id)
+            .or_else(|| self.stations.get(stop_id));
+        while let Some(stop_id) = current {
+            if !visited.insert(stop_id) {
+                break;
+            }
+            if self.stations.contains(stop_id) {
+                root = Some(stop_id);
+            }
+            current = self.parents.get(stop_id);
+        }
+        root
+    }
+}
+
+impl Dataset {
+    /// Builds an index of the parents and children of the stops of the dataset, for repeated
+    /// lookups.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn stop_hierarchy(&self) -> StopHierarchy {
+        let mut hierarchy = StopHierarchy::default();
+        for stop in self.stops.iter() {
+            if stop.location_type == Some(LocationType::Station) {
+                hierarchy.stations.insert(stop.stop_id.clone());
+            }
+            if let Some(parent_station) = &stop.parent_station {
+                hierarchy
+                    .parents
+                    .insert(stop.stop_id.clone(), parent_station.clone());
+                hierarchy
+                    .children
+                    .entry(parent_station.clone())
+                    .or_default()
+                    .push(stop.stop_id.clone());
+            }
+        }
+        for children in hierarchy.children.values_mut() {
+            children.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        }
+        hierarchy
+    }
+
+    /// Returns the stops whose parent station is `stop_id`, sorted by id, e.g. the platforms
+    /// and entrances of a station, or the boarding areas of a platform.
+    ///
+    /// Use [`Dataset::stop_hierarchy`] for repeated lookups.
+    pub fn children_of(&self, stop_id: &StopId) -> Vec<StopId> {
+        let mut children: Vec<StopId> = self
+            .stops
+            .iter()
+            .filter(|stop| stop.parent_station.as_ref() == Some(stop_id))
+            .map(|stop| stop.stop_id.clone())
+            .collect();
+        children.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        children
+    }
+
+    /// Returns every stop below `stop_id` in the hierarchy, level by level: the children of
+    /// the stop sorted by id, then their own children, and so on.
+    ///
+    /// For a station, these are its platforms, entrances, generic nodes and the boarding areas
+    /// of its platforms.
+    ///
+    /// Use [`Dataset::stop_hierarchy`] for repeated lookups.
+    pub fn descendants_of(&self, stop_id: &StopId) -> Vec<StopId> {
+        self.stop_hierarchy().descendants_of(stop_id)
+    }
+
+    /// Returns the station at the top of the hierarchy of a stop, the stop itself for a
+    /// station.
+    ///
+    /// Parent stations are followed up to the last one, e.g. from a boarding area to its
+    /// platform then to the station of the platform. `None` when no station is found along the
+    /// way, e.g. for a stop outside of any station.
+    pub fn root_station_of(&self, stop_id: &StopId) -> Option<StopId> {
+        let mut visited = HashSet::new();
+        let mut root = None;
+        let mut current = Some(stop_id.clone());
+        while let Some(stop_id) = current {
+            // A stop listed as the parent of one of its ancestors would loop forever.
+            if !visited.insert(stop_id.clone()) {
+                break;
+            }
+            let Some(stop) = self.stops.get(&stop_id) else {
+                break;
+            };
+            current = stop.parent_station.clone();
+            if stop.location_type == Some(LocationType::Station) {
+                root = Some(stop_id);
+            }
+        }
+        root
+    }
+}
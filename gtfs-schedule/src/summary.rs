@@ -0,0 +1,175 @@
+//! Summary of the content of a dataset.
+//!
+//! The main types are:
+//! - [`FeedSummary`]: Key figures of a dataset, as displayed by feed registries.
+//! - [`TripDuration`]: The time a trip takes from its first stop to its last one.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use geo::{Coord, HaversineDistance, Point, Rect};
+use serde::Serialize;
+use serde_with::{serde_as, DurationSeconds};
+
+use crate::schemas::{RouteType, TripId};
+use crate::{Dataset, GtfsFile};
+
+/// Key figures of a dataset, see [`Dataset::summary`].
+///
+/// It serializes to a flat structure, maps being ordered by key, e.g. to be published as JSON
+/// next to the dataset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeedSummary {
+    /// Number of records of each table, tables without records being left out.
+    pub record_counts: BTreeMap<GtfsFile, usize>,
+    /// The first date with service, if any.
+    pub first_service_date: Option<NaiveDate>,
+    /// The last date with service, if any.
+    pub last_service_date: Option<NaiveDate>,
+    /// Number of dates with service, between the first and last ones.
+    pub service_days: usize,
+    /// Number of routes of each type, ordered by code.
+    pub routes_by_type: BTreeMap<RouteType, usize>,
+    /// Number of trips of each type of route, ordered by code.
+    pub trips_by_type: BTreeMap<RouteType, usize>,
+    /// The smallest rectangle containing all the stops with coordinates.
+    pub bounding_box: Option<Rect<f64>>,
+    /// Number of stops with coordinates per square kilometer of [`FeedSummary::bounding_box`].
+    pub stop_density: Option<f64>,
+    /// The trip taking the longest time, ties being broken by [`TripId`].
+    pub longest_trip: Option<TripDuration>,
+    /// The trip taking the shortest time, ties being broken by [`TripId`].
+    pub shortest_trip: Option<TripDuration>,
+}
+
+/// The time a trip takes from its first stop to its last one.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TripDuration {
+    /// Identifies the trip.
+    pub trip_id: TripId,
+    /// Time between the first and the last times of the stop times of the trip.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub duration: Duration,
+}
+
+impl Dataset {
+    /// Computes the key figures of the dataset.
+    ///
+    /// Dates with service are those where at least one service of calendar.txt or
+    /// calendar_dates.txt runs, whether or not trips use it. Trips without times are left out of
+    /// the longest and shortest trips, and trips defined in frequencies.txt are measured once.
+    pub fn summary(&self) -> FeedSummary {
+        let record_counts = GtfsFile::ALL
+            .iter()
+            .map(|file| (*file, self.table_len(*file)))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+
+        let service_dates = self.active_dates();
+
+        let mut routes_by_type: BTreeMap<RouteType, usize> = BTreeMap::new();
+        for route in self.routes.iter() {
+            *routes_by_type.entry(route.route_type.clone()).or_default() += 1;
+        }
+        let mut trips_by_type: BTreeMap<RouteType, usize> = BTreeMap::new();
+        for trip in self.trips.iter() {
+            if let Some(route) = self.routes.get(&trip.route_id) {
+                *trips_by_type.entry(route.route_type.clone()).or_default() += 1;
+            }
+        }
+
+        let coords: Vec<Coord> = self
+            .stops
+            .iter()
+            .filter_map(|stop| stop.stop_coord.as_ref().map(|coord| **coord))
+            .collect();
+        let bounding_box = bounding_box(&coords);
+        let stop_density = bounding_box
+            .map(area_km2)
+            .filter(|area| *area > 0.0)
+            .map(|area| coords.len() as f64 / area);
+
+        let mut durations = self.trip_durations();
+        durations.sort_by(|a, b| {
+            (a.duration, a.trip_id.as_str()).cmp(&(b.duration, b.trip_id.as_str()))
+        });
+        let shortest_trip = durations.first().cloned();
+        // Among the longest trips, pick the first one by identifier, like for the shortest.
+        let longest_trip = durations
+            .last()
+            .and_then(|last| durations.iter().find(|trip| trip.duration == last.duration))
+            .cloned();
+
+        FeedSummary {
+            record_counts,
+            first_service_date: service_dates.first().copied(),
+            last_service_date: service_dates.last().copied(),
+            service_days: service_dates.len(),
+            routes_by_type,
+            trips_by_type,
+            bounding_box,
+            stop_density,
+            longest_trip,
+            shortest_trip,
+        }
+    }
+
+    /// Lists the dates where at least one service runs, sorted.
+    fn active_dates(&self) -> Vec<NaiveDate> {
+        let dates: BTreeSet<NaiveDate> = self.service_dates_all().into_values().flatten().collect();
+        dates.into_iter().collect()
+    }
+
+    /// Measures the time each trip takes, from the earliest to the latest of its stop times.
+    fn trip_durations(&self) -> Vec<TripDuration> {
+        let mut spans: HashMap<TripId, (u32, u32)> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            let times = [stop_time.arrival_time, stop_time.departure_time];
+            for secs in times.iter().flatten().map(|time| time.as_secs()) {
+                let span = spans
+                    .entry(stop_time.trip_id.clone())
+                    .or_insert((secs, secs));
+                span.0 = span.0.min(secs);
+                span.1 = span.1.max(secs);
+            }
+        }
+
+        spans
+            .into_iter()
+            .map(|(trip_id, (start, end))| TripDuration {
+                trip_id,
+                duration: Duration::from_secs(u64::from(end - start)),
+            })
+            .collect()
+    }
+}
+
+/// Returns the smallest rectangle containing all the coordinates.
+fn bounding_box(coords: &[Coord]) -> Option<Rect<f64>> {
+    let first = coords.first()?;
+    let (min, max) = coords.iter().fold((*first, *first), |(min, max), coord| {
+        (
+            Coord {
+                x: min.x.min(coord.x),
+                y: min.y.min(coord.y),
+            },
+            Coord {
+                x: max.x.max(coord.x),
+                y: max.y.max(coord.y),
+            },
+        )
+    });
+    Some(Rect::new(min, max))
+}
+
+/// Approximates the area of a rectangle of coordinates, in square kilometers.
+fn area_km2(rect: Rect<f64>) -> f64 {
+    let center = rect.center();
+    let width =
+        Point::new(rect.min().x, center.y).haversine_distance(&Point::new(rect.max().x, center.y));
+    let height =
+        Point::new(center.x, rect.min().y).haversine_distance(&Point::new(center.x, rect.max().y));
+    width * height / 1_000_000.0
+}
@@ -0,0 +1,231 @@
+//! Generation of synthetic datasets, to test and benchmark the crate at scale without shipping
+//! large feeds.
+//!
+//! ```
+//! use gtfs_schedule::synth;
+//!
+//! let dataset = synth::generate_feed(2);
+//! assert_eq!(dataset.routes.len(), 2);
+//! assert!(dataset.validate().is_ok());
+//! ```
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use geo::Coord;
+use url::Url;
+
+use crate::schemas::*;
+use crate::Dataset;
+
+/// Number of stops served by each route, including the hub.
+pub const STOPS_PER_ROUTE: u32 = 20;
+/// Number of trips run by each route in each direction, every day.
+pub const TRIPS_PER_DIRECTION: u32 = 72;
+
+/// The stop shared by every route, halfway along each of them.
+const HUB_STOP_ID: &str = "HUB";
+/// Seconds between two departures of a route in the same direction, from 05:00.
+const HEADWAY_SECS: u32 = 15 * 60;
+/// Seconds between two consecutive stops of a trip.
+const STOP_INTERVAL_SECS: u32 = 2 * 60;
+/// Location of the hub, as longitude and latitude.
+const HUB_COORD: Coord = Coord { x: 2.35, y: 48.85 };
+/// Degrees between two consecutive stops of a route.
+const STOP_SPACING_DEG: f64 = 0.005;
+
+/// Generates a dataset of `size` routes, each running [`TRIPS_PER_DIRECTION`] trips in each
+/// direction every day of 2024, along [`STOPS_PER_ROUTE`] stops.
+///
+/// Routes are straight lines spread as spokes around a hub stop they all serve, so that
+/// journeys between two routes exist. The dataset is the same for a given `size`, and is valid.
+pub fn generate_feed(size: usize) -> Dataset {
+    let mut dataset = Dataset::default();
+    let agency_id = AgencyId::from("SYNTH");
+    let service_id = CalendarServiceId::from("DAILY");
+    let hub_stop_id = StopId::from(HUB_STOP_ID);
+
+    dataset.agencies.push(Agency {
+        agency_id: Some(agency_id.clone()),
+        agency_name: "Synthetic Transit".to_string(),
+        agency_url: Url::parse("https://example.com").unwrap(),
+        agency_timezone: Tz::UTC,
+        agency_lang: None,
+        agency_phone: None,
+        agency_fare_url: None,
+        agency_email: None,
+        #[cfg(feature = "extensions")]
+        extensions: Default::default(),
+    });
+    dataset.calendar.insert(
+        service_id.clone(),
+        Calendar {
+            service_id: service_id.clone(),
+            monday: CalendarDayService::Available,
+            tuesday: CalendarDayService::Available,
+            wednesday: CalendarDayService::Available,
+            thursday: CalendarDayService::Available,
+            friday: CalendarDayService::Available,
+            saturday: CalendarDayService::Available,
+            sunday: CalendarDayService::Available,
+            start_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+            #[cfg(feature = "extensions")]
+            extensions: Default::default(),
+        },
+    );
+    dataset
+        .stops
+        .insert(hub_stop_id.clone(), stop(hub_stop_id.clone(), HUB_COORD));
+
+    for route_index in 0..size {
+        let route_id = RouteId::from(format!("R{}", route_index).as_str());
+        let angle = std::f64::consts::TAU * route_index as f64 / size as f64;
+        let half = i64::from(STOPS_PER_ROUTE / 2);
+        let stops: Vec<(StopId, Coord)> = (0..i64::from(STOPS_PER_ROUTE))
+            .map(|index| {
+                let offset = (index - half) as f64 * STOP_SPACING_DEG;
+                let coord = Coord {
+                    x: HUB_COORD.x + angle.cos() * offset,
+                    y: HUB_COORD.y + angle.sin() * offset,
+                };
+                let stop_id = match index == half {
+                    true => hub_stop_id.clone(),
+                    false => StopId::from(format!("{}S{}", route_id, index).as_str()),
+                };
+                (stop_id, coord)
+            })
+            .collect();
+        for (stop_id, coord) in &stops {
+            if *stop_id != hub_stop_id {
+                dataset
+                    .stops
+                    .insert(stop_id.clone(), stop(stop_id.clone(), *coord));
+            }
+        }
+
+        dataset.routes.insert(
+            route_id.clone(),
+            Route {
+                route_id: route_id.clone(),
+                agency_id: Some(agency_id.clone()),
+                route_short_name: Some(route_id.to_string()),
+                route_long_name: None,
+                route_desc: None,
+                route_type: RouteType::Bus,
+                route_url: None,
+                route_color: None,
+                route_text_color: None,
+                route_sort_order: None,
+                continuous_pickup: None,
+                continuous_drop_off: None,
+                network_id: None,
+                #[cfg(feature = "extensions")]
+                extensions: Default::default(),
+            },
+        );
+
+        for direction_id in [DirectionId::OneDirection, DirectionId::OppositeDirection] {
+            let mut stops = stops.clone();
+            if direction_id == DirectionId::OppositeDirection {
+                stops.reverse();
+            }
+            let shape_id = ShapeId::from(format!("{}D{}", route_id, direction_id.code()).as_str());
+            for (sequence, (_, coord)) in (0..).zip(&stops) {
+                dataset.shapes.insert(
+                    (shape_id.clone(), sequence),
+                    Shape {
+                        shape_id: shape_id.clone(),
+                        shape_pt: (*coord).into(),
+                        shape_pt_sequence: sequence,
+                        shape_dist_traveled: None,
+                        #[cfg(feature = "extensions")]
+                        extensions: Default::default(),
+                    },
+                );
+            }
+
+            for trip_index in 0..TRIPS_PER_DIRECTION {
+                let trip_id = TripId::from(format!("{}T{}", shape_id, trip_index).as_str());
+                let start = 5 * 3600 + trip_index * HEADWAY_SECS;
+                dataset.trips.insert(
+                    trip_id.clone(),
+                    Trip {
+                        route_id: route_id.clone(),
+                        service_id: service_id.clone(),
+                        trip_id: trip_id.clone(),
+                        trip_headsign: None,
+                        trip_short_name: None,
+                        direction_id: Some(direction_id.clone()),
+                        block_id: None,
+                        shape_id: Some(shape_id.clone()),
+                        wheelchair_accessible: None,
+                        bikes_allowed: None,
+                        #[cfg(feature = "extensions")]
+                        extensions: Default::default(),
+                    },
+                );
+                for (sequence, (stop_id, _)) in (0..).zip(&stops) {
+                    let time = NaiveServiceTime::from_secs(start + sequence * STOP_INTERVAL_SECS);
+                    dataset.stop_times.insert(
+                        (trip_id.clone(), sequence),
+                        stop_time(trip_id.clone(), sequence, stop_id.clone(), time),
+                    );
+                }
+            }
+        }
+    }
+    dataset
+}
+
+/// Returns a stop at `coord`.
+fn stop(stop_id: StopId, coord: Coord) -> Stop {
+    Stop {
+        stop_name: Some(stop_id.to_string()),
+        stop_id,
+        stop_code: None,
+        tts_stop_name: None,
+        stop_desc: None,
+        stop_coord: Some(coord.into()),
+        zone_id: None,
+        stop_url: None,
+        location_type: None,
+        parent_station: None,
+        stop_timezone: None,
+        wheelchair_boarding: None,
+        level_id: None,
+        platform_code: None,
+        #[cfg(feature = "extensions")]
+        extensions: Default::default(),
+    }
+}
+
+/// Returns a stop time arriving and departing at `time`.
+fn stop_time(
+    trip_id: TripId,
+    stop_sequence: u32,
+    stop_id: StopId,
+    time: NaiveServiceTime,
+) -> StopTime {
+    StopTime {
+        trip_id,
+        arrival_time: Some(time),
+        departure_time: Some(time),
+        stop_id: Some(stop_id),
+        location_group_id: None,
+        location_id: None,
+        stop_sequence,
+        stop_headsign: None,
+        start_pickup_drop_off_window: None,
+        end_pickup_drop_off_window: None,
+        pickup_type: None,
+        drop_off_type: None,
+        continuous_pickup: None,
+        continuous_drop_off: None,
+        shape_dist_traveled: None,
+        timepoint: None,
+        pickup_booking_rule_id: None,
+        drop_off_booking_rule_id: None,
+        #[cfg(feature = "extensions")]
+        extensions: Default::default(),
+    }
+}
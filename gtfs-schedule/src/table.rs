@@ -0,0 +1,192 @@
+//! Generic access to the tables of a dataset.
+//!
+//! The main types are:
+//! - [`DatasetTable`]: Records stored in a table of [`Dataset`].
+//! - [`TableRef`]: The records of a table, whether keyed or listed.
+//! - [`RecordRef`]: A record borrowed from a [`TableRef`].
+
+use std::hash::Hash;
+use std::ops::Deref;
+
+use dashmap::mapref::multiple::RefMulti;
+use dashmap::mapref::one::Ref;
+use dashmap::DashMap;
+
+use crate::schemas::*;
+use crate::Dataset;
+
+/// Records stored in a table of [`Dataset`], see [`Dataset::table`].
+pub trait DatasetTable: GtfsTable + Sized
+where
+    Self::Key: Eq + Hash,
+{
+    /// Returns the table holding the records in `dataset`.
+    fn table(dataset: &Dataset) -> TableRef<'_, Self>;
+}
+
+/// The records of a table of a dataset, see [`Dataset::table`].
+///
+/// Keyed tables, such as [`Dataset::stops`], are read through the guards of their maps, which
+/// hold read locks until dropped.
+pub struct TableRef<'a, T: GtfsTable> {
+    storage: Storage<'a, T>,
+}
+
+enum Storage<'a, T: GtfsTable> {
+    Keyed(&'a DashMap<T::Key, T>),
+    Listed(&'a [T]),
+}
+
+/// A record of a [`TableRef`], dereferencing to the record.
+pub struct RecordRef<'a, T: GtfsTable>(Record<'a, T>)
+where
+    T::Key: Eq + Hash;
+
+enum Record<'a, T: GtfsTable>
+where
+    T::Key: Eq + Hash,
+{
+    Entry(RefMulti<'a, T::Key, T>),
+    Found(Ref<'a, T::Key, T>),
+    Listed(&'a T),
+}
+
+impl<T: GtfsTable> Deref for RecordRef<'_, T>
+where
+    T::Key: Eq + Hash,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match &self.0 {
+            Record::Entry(record) => record.value(),
+            Record::Found(record) => record.value(),
+            Record::Listed(record) => record,
+        }
+    }
+}
+
+impl<'a, T: GtfsTable> TableRef<'a, T>
+where
+    T::Key: Eq + Hash,
+{
+    /// Returns the number of records of the table.
+    pub fn len(&self) -> usize {
+        match self.storage {
+            Storage::Keyed(records) => records.len(),
+            Storage::Listed(records) => records.len(),
+        }
+    }
+
+    /// Whether the table has no records.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the records of the table, in an unspecified order for keyed tables.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = RecordRef<'a, T>> + 'a> {
+        match self.storage {
+            Storage::Keyed(records) => Box::new(
+                records
+                    .iter()
+                    .map(|record| RecordRef(Record::Entry(record))),
+            ),
+            Storage::Listed(records) => Box::new(
+                records
+                    .iter()
+                    .map(|record| RecordRef(Record::Listed(record))),
+            ),
+        }
+    }
+
+    /// Returns the record with the given key, if any.
+    ///
+    /// Every record of a table without primary key has the key `()`, so the first record is
+    /// returned, e.g. the record of feed_info.txt.
+    pub fn get(&self, key: &T::Key) -> Option<RecordRef<'a, T>> {
+        match self.storage {
+            Storage::Keyed(records) => records
+                .get(key)
+                .map(|record| RecordRef(Record::Found(record))),
+            Storage::Listed(records) => records
+                .iter()
+                .find(|record| record.key() == *key)
+                .map(|record| RecordRef(Record::Listed(record))),
+        }
+    }
+}
+
+impl Dataset {
+    /// Returns the records of the table of `T`, e.g. `dataset.table::<Stop>()`, so that generic
+    /// code can read any table without naming the field holding it.
+    pub fn table<T: DatasetTable>(&self) -> TableRef<'_, T>
+    where
+        T::Key: Eq + Hash,
+    {
+        T::table(self)
+    }
+}
+
+macro_rules! impl_dataset_table {
+    (keyed: $($keyed:ident => $keyed_field:ident),* ; listed: $($listed:ident => $listed_field:ident),* $(,)?) => {
+        $(
+            impl DatasetTable for $keyed {
+                fn table(dataset: &Dataset) -> TableRef<'_, Self> {
+                    TableRef {
+                        storage: Storage::Keyed(&dataset.$keyed_field),
+                    }
+                }
+            }
+        )*
+        $(
+            impl DatasetTable for $listed {
+                fn table(dataset: &Dataset) -> TableRef<'_, Self> {
+                    TableRef {
+                        storage: Storage::Listed(&dataset.$listed_field),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_dataset_table! {
+    keyed:
+        Stop => stops,
+        Route => routes,
+        Trip => trips,
+        StopTime => stop_times,
+        Calendar => calendar,
+        CalendarDate => calendar_dates,
+        FareAttribute => fare_attributes,
+        FareMedia => fare_medias,
+        FareProduct => fare_products,
+        Area => areas,
+        Network => networks,
+        RouteNetwork => routes_networks,
+        Shape => shapes,
+        Frequency => frequencies,
+        Pathway => pathways,
+        Level => levels,
+        LocationGroup => location_groups,
+        BookingRule => booking_rules;
+    listed:
+        Agency => agencies,
+        FareRule => fare_rules,
+        Timeframe => timeframes,
+        FareLegRule => fare_leg_rules,
+        FareTransferRule => fare_transfers,
+        StopArea => stops_areas,
+        Transfer => transfers,
+        LocationGroupStop => location_groups_stops,
+        Translation => translations,
+        Attribution => attributions,
+}
+
+impl DatasetTable for FeedInfo {
+    fn table(dataset: &Dataset) -> TableRef<'_, Self> {
+        TableRef {
+            storage: Storage::Listed(dataset.feed_info.as_slice()),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Streaming of the records of a single file, without building a dataset.
+//!
+//! The main types are:
+//! - [`TableReader`]: Parses the records of a file one at a time.
+//! - [`GtfsRecord`]: The records of a file of the specification.
+//!
+//! Each file also has an alias of [`TableReader`], e.g. [`StopTimesReader`].
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::File;
+use std::io::Read;
+use std::marker::PhantomData;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::{ErrorContext, ParseError, ParseErrorKind, Result};
+#[cfg(feature = "extensions")]
+use crate::schemas::Extensions;
+use crate::schemas::*;
+use crate::GtfsFile;
+
+/// The records of a file of the specification, see [`TableReader`] and [`crate::TableWriter`].
+#[cfg(feature = "extensions")]
+pub trait GtfsRecord: DeserializeOwned + Clone + Into<Schema> + Extensions {
+    /// The file holding the records.
+    const FILE: GtfsFile;
+}
+
+/// The records of a file of the specification, see [`TableReader`] and [`crate::TableWriter`].
+#[cfg(not(feature = "extensions"))]
+pub trait GtfsRecord: DeserializeOwned + Clone + Into<Schema> {
+    /// The file holding the records.
+    const FILE: GtfsFile;
+}
+
+/// Parses the records of a file one at a time, without building a [`crate::Dataset`].
+///
+/// This is meant for pipelines processing files too large to be loaded at once, e.g. the
+/// stop_times.txt of a national feed. Records are parsed as by [`crate::Dataset::from_csv`],
+/// but are not validated, and the ones sharing a primary key are all yielded.
+///
+/// ```no_run
+/// use gtfs_schedule::StopTimesReader;
+///
+/// let file = std::fs::File::open("stop_times.txt").unwrap();
+/// let mut trips = std::collections::HashSet::new();
+/// for stop_time in StopTimesReader::from_reader(file).unwrap() {
+///     trips.insert(stop_time.unwrap().trip_id);
+/// }
+/// ```
+pub struct TableReader<T: GtfsRecord, R: Read> {
+    reader: csv::Reader<R>,
+    header: csv::StringRecord,
+    record: csv::StringRecord,
+    record_type: PhantomData<T>,
+}
+
+impl<T: GtfsRecord, R: Read> TableReader<T, R> {
+    /// Reads records from `reader`, starting with the header.
+    ///
+    /// Column names are matched regardless of surrounding whitespace and byte order marks.
+    pub fn from_reader(reader: R) -> Result<Self> {
+        let mut reader = csv::Reader::from_reader(reader);
+        let header = reader
+            .headers()
+            .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?
+            .iter()
+            .map(|column| column.trim_start_matches('\u{feff}').trim())
+            .collect();
+
+        Ok(Self {
+            reader,
+            header,
+            record: csv::StringRecord::new(),
+            record_type: PhantomData,
+        })
+    }
+
+    /// The columns of the file, in order.
+    pub fn header(&self) -> &csv::StringRecord {
+        &self.header
+    }
+
+    /// The columns of the file that are not part of the specification.
+    pub fn unknown_columns(&self) -> Vec<&str> {
+        self.header
+            .iter()
+            .filter(|column| !T::FILE.columns().contains(column))
+            .collect()
+    }
+
+    /// Parses the record held by `self.record`.
+    fn parse_record(&self) -> Result<T> {
+        parse_record(&self.header, &self.record)
+    }
+}
+
+/// Parses a single record of a file, given the header of the file.
+///
+/// Records are parsed as by [`TableReader`], but columns are matched exactly, so `header` must
+/// not hold whitespace or byte order marks around column names. Any input, however malformed,
+/// yields an error rather than a panic, which makes this a convenient entry point to check
+/// records coming from elsewhere, or to fuzz the parser.
+///
+/// ```
+/// use gtfs_schedule::parse_record;
+/// use gtfs_schedule::schemas::Level;
+///
+/// let header = csv::StringRecord::from(vec!["level_id", "level_index"]);
+/// let level: Level = parse_record(&header, &csv::StringRecord::from(vec!["L1", "-1"])).unwrap();
+/// assert_eq!(level.level_index, -1.0);
+/// assert!(parse_record::<Level>(&header, &csv::StringRecord::from(vec!["L1", "up"])).is_err());
+/// ```
+pub fn parse_record<T: GtfsRecord>(
+    header: &csv::StringRecord,
+    record: &csv::StringRecord,
+) -> Result<T> {
+    let parsed: T = record.deserialize(Some(header)).map_err(|e| {
+        let context = match record.position() {
+            Some(position) => format!(
+                "Failed to deserialize {} at position: {:?}",
+                T::FILE.file_name(),
+                position
+            ),
+            None => format!("Failed to deserialize {}", T::FILE.file_name()),
+        };
+        ParseError::from(ParseErrorKind::from(e)).with_context(ErrorContext(context))
+    })?;
+
+    #[cfg(feature = "extensions")]
+    let parsed = {
+        let mut parsed = parsed;
+        *parsed.extensions_mut() = header
+            .iter()
+            .zip(record.iter())
+            .filter(|(column, value)| !value.is_empty() && !T::FILE.columns().contains(column))
+            .map(|(column, value)| (column.to_string(), value.to_string()))
+            .collect();
+        parsed
+    };
+    Ok(parsed)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: GtfsRecord> TableReader<T, File> {
+    /// Reads records from the file at `path`.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let file = File::open(path).map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+        Self::from_reader(file)
+    }
+}
+
+impl<T: GtfsRecord, R: Read> Iterator for TableReader<T, R> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reader.read_record(&mut self.record) {
+            Ok(true) => Some(self.parse_record()),
+            Ok(false) => None,
+            Err(e) => Some(Err(ParseError::from(ParseErrorKind::from(e)).into())),
+        }
+    }
+}
+
+macro_rules! impl_gtfs_record {
+    ($($schema:ident => $file:ident, $reader:ident;)*) => {
+        $(
+            impl GtfsRecord for $schema {
+                const FILE: GtfsFile = GtfsFile::$file;
+            }
+
+            #[doc = concat!("Reads the records of [`GtfsFile::", stringify!($file), "`], see [`TableReader`].")]
+            pub type $reader<R> = TableReader<$schema, R>;
+        )*
+    };
+}
+
+impl_gtfs_record!(
+    Agency => Agency, AgencyReader;
+    Area => Areas, AreasReader;
+    Attribution => Attributions, AttributionsReader;
+    BookingRule => BookingRules, BookingRulesReader;
+    Calendar => Calendar, CalendarReader;
+    CalendarDate => CalendarDates, CalendarDatesReader;
+    FareAttribute => FareAttributes, FareAttributesReader;
+    FareLegRule => FareLegRules, FareLegRulesReader;
+    FareMedia => FareMedia, FareMediaReader;
+    FareProduct => FareProducts, FareProductsReader;
+    FareRule => FareRules, FareRulesReader;
+    FareTransferRule => FareTransfers, FareTransfersReader;
+    FeedInfo => FeedInfo, FeedInfoReader;
+    Frequency => Frequencies, FrequenciesReader;
+    Level => Levels, LevelsReader;
+    LocationGroup => LocationGroups, LocationGroupsReader;
+    LocationGroupStop => LocationGroupsStops, LocationGroupsStopsReader;
+    Network => Networks, NetworksReader;
+    Pathway => Pathways, PathwaysReader;
+    Route => Routes, RoutesReader;
+    RouteNetwork => RoutesNetworks, RoutesNetworksReader;
+    Shape => Shapes, ShapesReader;
+    Stop => Stops, StopsReader;
+    StopArea => StopsAreas, StopsAreasReader;
+    StopTime => StopTimes, StopTimesReader;
+    Timeframe => Timeframes, TimeframesReader;
+    Transfer => Transfers, TransfersReader;
+    Translation => Translations, TranslationsReader;
+    Trip => Trips, TripsReader;
+);
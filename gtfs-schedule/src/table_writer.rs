@@ -0,0 +1,182 @@
+//! Streaming of records to a single file, without building a dataset.
+//!
+//! The main types are:
+//! - [`TableWriter`]: Writes the records of a file one at a time.
+//!
+//! Each file also has an alias of [`TableWriter`], e.g. [`StopTimesWriter`].
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::File;
+use std::io::Write;
+use std::marker::PhantomData;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use crate::error::{ParseError, ParseErrorKind, Result};
+use crate::schemas::*;
+use crate::writer::csv_row;
+use crate::GtfsRecord;
+
+/// Writes the records of a file one at a time, mirroring [`crate::TableReader`].
+///
+/// Values are formatted as by [`crate::Dataset::to_csv`]. As the records to come are unknown,
+/// the columns are chosen upfront: every column of [`crate::GtfsFile::columns`] by default, or
+/// only some of them with [`TableWriter::with_columns`]. Writing a record with a value in a
+/// column that is not written is an error, rather than losing the value.
+///
+/// ```
+/// use gtfs_schedule::{StopsReader, StopsWriter};
+///
+/// let csv = "stop_id,stop_name\nA,Alpha\n";
+/// let mut writer = StopsWriter::with_columns(Vec::new(), &["stop_id", "stop_name"]).unwrap();
+/// for stop in StopsReader::from_reader(csv.as_bytes()).unwrap() {
+///     writer.write_record(&stop.unwrap()).unwrap();
+/// }
+/// assert_eq!(writer.finish().unwrap(), csv.as_bytes());
+/// ```
+pub struct TableWriter<T: GtfsRecord, W: Write> {
+    writer: csv::Writer<W>,
+    columns: Vec<String>,
+    header_written: bool,
+    record_type: PhantomData<T>,
+}
+
+impl<T: GtfsRecord, W: Write> TableWriter<T, W> {
+    /// Writes records to `writer`, with every column of the specification.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: csv::Writer::from_writer(writer),
+            columns: T::FILE
+                .columns()
+                .iter()
+                .map(|column| column.to_string())
+                .collect(),
+            header_written: false,
+            record_type: PhantomData,
+        }
+    }
+
+    /// Writes records to `writer`, with the given columns only, in order.
+    ///
+    /// Columns that are not part of the specification are allowed, to write the extensions of
+    /// the records.
+    pub fn with_columns(writer: W, columns: &[&str]) -> Result<Self> {
+        if let Some(column) = columns
+            .iter()
+            .enumerate()
+            .find(|(index, column)| columns[..*index].contains(column))
+            .map(|(_, column)| column)
+        {
+            return Err(ParseError::from(ParseErrorKind::InvalidValue(format!(
+                "column {} of {} is listed twice",
+                column,
+                T::FILE.file_name()
+            )))
+            .into());
+        }
+
+        Ok(Self {
+            writer: csv::Writer::from_writer(writer),
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+            header_written: false,
+            record_type: PhantomData,
+        })
+    }
+
+    /// The columns written, in order.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Writes a record, preceded by the header if it is the first one.
+    pub fn write_record(&mut self, record: &T) -> Result<()> {
+        self.write_header()?;
+        let row = csv_row(&record.clone().into());
+        if let Some(column) = row.keys().find(|column| !self.columns.contains(column)) {
+            return Err(ParseError::from(ParseErrorKind::InvalidValue(format!(
+                "column {} of {} is not written, but has a value",
+                column,
+                T::FILE.file_name()
+            )))
+            .into());
+        }
+
+        self.writer
+            .write_record(
+                self.columns
+                    .iter()
+                    .map(|column| row.get(column).map_or("", String::as_str)),
+            )
+            .map_err(|e| ParseError::from(ParseErrorKind::from(e)).into())
+    }
+
+    /// Writes the header if no record was written, flushes the records, and returns the
+    /// underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.write_header()?;
+        self.writer
+            .into_inner()
+            .map_err(|e| ParseError::from(ParseErrorKind::from(e.into_error())).into())
+    }
+
+    /// Writes the header, unless it was already written.
+    fn write_header(&mut self) -> Result<()> {
+        if !self.header_written {
+            self.writer
+                .write_record(&self.columns)
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+            self.header_written = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: GtfsRecord> TableWriter<T, File> {
+    /// Writes records to a file created at `path`, with every column of the specification.
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path).map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+        Ok(Self::new(file))
+    }
+}
+
+macro_rules! writer_aliases {
+    ($($schema:ident => $file:ident, $writer:ident;)*) => {
+        $(
+            #[doc = concat!("Writes the records of [`crate::GtfsFile::", stringify!($file), "`], see [`TableWriter`].")]
+            pub type $writer<W> = TableWriter<$schema, W>;
+        )*
+    };
+}
+
+writer_aliases!(
+    Agency => Agency, AgencyWriter;
+    Area => Areas, AreasWriter;
+    Attribution => Attributions, AttributionsWriter;
+    BookingRule => BookingRules, BookingRulesWriter;
+    Calendar => Calendar, CalendarWriter;
+    CalendarDate => CalendarDates, CalendarDatesWriter;
+    FareAttribute => FareAttributes, FareAttributesWriter;
+    FareLegRule => FareLegRules, FareLegRulesWriter;
+    FareMedia => FareMedia, FareMediaWriter;
+    FareProduct => FareProducts, FareProductsWriter;
+    FareRule => FareRules, FareRulesWriter;
+    FareTransferRule => FareTransfers, FareTransfersWriter;
+    FeedInfo => FeedInfo, FeedInfoWriter;
+    Frequency => Frequencies, FrequenciesWriter;
+    Level => Levels, LevelsWriter;
+    LocationGroup => LocationGroups, LocationGroupsWriter;
+    LocationGroupStop => LocationGroupsStops, LocationGroupsStopsWriter;
+    Network => Networks, NetworksWriter;
+    Pathway => Pathways, PathwaysWriter;
+    Route => Routes, RoutesWriter;
+    RouteNetwork => RoutesNetworks, RoutesNetworksWriter;
+    Shape => Shapes, ShapesWriter;
+    Stop => Stops, StopsWriter;
+    StopArea => StopsAreas, StopsAreasWriter;
+    StopTime => StopTimes, StopTimesWriter;
+    Timeframe => Timeframes, TimeframesWriter;
+    Transfer => Transfers, TransfersWriter;
+    Translation => Translations, TranslationsWriter;
+    Trip => Trips, TripsWriter;
+);
@@ -0,0 +1,354 @@
+//! Printable timetables of routes, with a column per trip and a row per stop.
+//!
+//! The main types are:
+//! - [`Timetable`]: The times of the trips of a route going in a direction on a date.
+//! - [`TimetableOptions`]: Which stop times are shown.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use chrono::NaiveDate;
+
+use crate::compiled_timetable::trip_starts;
+use crate::error::{ParseError, ParseErrorKind, Result};
+use crate::schemas::{
+    DirectionId, Frequency, NaiveServiceTime, RouteId, StopId, Timepoint, TripId,
+};
+use crate::Dataset;
+
+/// Options used by [`Dataset::timetable_with_options`].
+///
+/// By default, every stop time with a stop is shown.
+#[derive(Debug, Clone, Default)]
+pub struct TimetableOptions {
+    timepoints_only: bool,
+}
+
+impl TimetableOptions {
+    /// Only show the stop times whose times are exact, leaving out those with
+    /// [`Timepoint::Approximate`] or without times, as printed timetables usually do.
+    pub fn timepoints_only(mut self) -> Self {
+        self.timepoints_only = true;
+        self
+    }
+}
+
+/// The times of the trips of a route going in a direction on a date, see
+/// [`Dataset::timetable`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timetable {
+    /// Identifies the route.
+    pub route_id: RouteId,
+    /// The direction of the trips, `None` for the trips of every direction.
+    pub direction_id: Option<DirectionId>,
+    /// The service day.
+    pub date: NaiveDate,
+    /// The rows of the timetable, in the order the trips serve them.
+    ///
+    /// A stop served twice by a trip, e.g. by a loop, has a row for each visit.
+    pub stops: Vec<TimetableStop>,
+    /// The columns of the timetable, sorted by the time of their first stop.
+    pub trips: Vec<TimetableTrip>,
+}
+
+/// A row of a [`Timetable`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimetableStop {
+    /// Identifies the stop.
+    pub stop_id: StopId,
+    /// The [`crate::schemas::Stop::stop_name`] of the stop.
+    pub stop_name: Option<String>,
+}
+
+/// A column of a [`Timetable`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimetableTrip {
+    /// Identifies the trip. A trip defined in frequencies.txt has a column per departure.
+    pub trip_id: TripId,
+    /// The [`crate::schemas::Trip::trip_short_name`] of the trip.
+    pub trip_short_name: Option<String>,
+    /// The [`crate::schemas::Trip::trip_headsign`] of the trip.
+    pub trip_headsign: Option<String>,
+    /// The time at each row of [`Timetable::stops`], `None` where the trip does not stop or has
+    /// no time.
+    ///
+    /// The departure time is shown, except at the last stop of the trip where the arrival
+    /// time is. Either one is used when the other is missing.
+    pub times: Vec<Option<NaiveServiceTime>>,
+}
+
+impl TimetableTrip {
+    /// The label of the column, the short name of the trip or its id.
+    fn label(&self) -> &str {
+        self.trip_short_name
+            .as_deref()
+            .unwrap_or(self.trip_id.as_str())
+    }
+}
+
+/// A stop of a trip, with the time shown in seconds.
+type Call = (StopId, Option<u32>);
+
+/// Finds the first row at or after `start` for `stop_id`.
+fn find_row(rows: &[StopId], start: usize, stop_id: &StopId) -> Option<usize> {
+    rows[start..]
+        .iter()
+        .position(|row| row == stop_id)
+        .map(|offset| start + offset)
+}
+
+/// Escapes the characters reserved in HTML text and attributes.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn io_error(e: std::io::Error) -> crate::error::Error {
+    ParseError::from(ParseErrorKind::from(e)).into()
+}
+
+impl Timetable {
+    /// Writes the timetable as CSV: a header with `stop_id`, `stop_name` and the label of each
+    /// trip, its short name or id, then a line per stop with the times as `HH:MM:SS`.
+    pub fn write_csv(&self, writer: &mut dyn Write) -> Result<()> {
+        let mut csv = csv::Writer::from_writer(writer);
+        let header = ["stop_id", "stop_name"]
+            .into_iter()
+            .chain(self.trips.iter().map(TimetableTrip::label));
+        csv.write_record(header)
+            .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+        for (row, stop) in self.stops.iter().enumerate() {
+            let times = self.trips.iter().map(|trip| {
+                trip.times[row]
+                    .map(|time| time.to_string())
+                    .unwrap_or_default()
+            });
+            let record = [
+                stop.stop_id.to_string(),
+                stop.stop_name.clone().unwrap_or_default(),
+            ]
+            .into_iter()
+            .chain(times);
+            csv.write_record(record)
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+        }
+        csv.flush().map_err(io_error)
+    }
+
+    /// Writes the timetable as an HTML `<table>`, to be embedded in a page and styled.
+    ///
+    /// Columns are headed by the label of each trip, and by its headsign when trips have
+    /// different headsigns. Times are shown as `HH:MM`, and cells without time are empty.
+    pub fn write_html(&self, writer: &mut dyn Write) -> Result<()> {
+        let mut html = String::from("<table class=\"timetable\">\n<thead>\n<tr><th>Stop</th>");
+        for trip in &self.trips {
+            html.push_str(&format!("<th>{}</th>", escape_html(trip.label())));
+        }
+        html.push_str("</tr>\n");
+        if self
+            .trips
+            .windows(2)
+            .any(|pair| pair[0].trip_headsign != pair[1].trip_headsign)
+        {
+            html.push_str("<tr><th></th>");
+            for trip in &self.trips {
+                let headsign = trip.trip_headsign.as_deref().unwrap_or_default();
+                html.push_str(&format!("<th>{}</th>", escape_html(headsign)));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</thead>\n<tbody>\n");
+        for (row, stop) in self.stops.iter().enumerate() {
+            let name = stop.stop_name.as_deref().unwrap_or(stop.stop_id.as_str());
+            html.push_str(&format!("<tr><th scope=\"row\">{}</th>", escape_html(name)));
+            for trip in &self.trips {
+                match trip.times[row] {
+                    Some(time) => {
+                        let secs = time.as_secs();
+                        html.push_str(&format!(
+                            "<td>{:02}:{:02}</td>",
+                            secs / 3600,
+                            secs % 3600 / 60
+                        ));
+                    }
+                    None => html.push_str("<td></td>"),
+                }
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</tbody>\n</table>\n");
+        writer.write_all(html.as_bytes()).map_err(io_error)
+    }
+}
+
+impl Dataset {
+    /// Builds the timetable of the trips of a route running on `date` in a direction, or in
+    /// every direction if `direction_id` is `None`, e.g. for routes whose trips have no
+    /// direction.
+    ///
+    /// Rows merge the stopping patterns of the trips, see [`Dataset::trip_patterns`]: the stops
+    /// of the most common pattern come first, and the stops of the other patterns are inserted
+    /// where they are served. Trips defined in frequencies.txt have a column per departure.
+    /// Stop times without [`crate::schemas::StopTime::stop_id`] (flexible services) are left
+    /// out.
+    pub fn timetable(
+        &self,
+        route_id: &RouteId,
+        direction_id: Option<DirectionId>,
+        date: NaiveDate,
+    ) -> Timetable {
+        self.timetable_with_options(route_id, direction_id, date, &TimetableOptions::default())
+    }
+
+    /// Same as [`Dataset::timetable`], but selects the stop times shown as set by `options`.
+    pub fn timetable_with_options(
+        &self,
+        route_id: &RouteId,
+        direction_id: Option<DirectionId>,
+        date: NaiveDate,
+        options: &TimetableOptions,
+    ) -> Timetable {
+        let trips: HashMap<TripId, _> = self
+            .trips
+            .iter()
+            .filter(|trip| {
+                trip.route_id == *route_id
+                    && (direction_id.is_none() || trip.direction_id == direction_id)
+                    && self.is_service_active(&trip.service_id, date)
+            })
+            .map(|trip| (trip.trip_id.clone(), trip.value().clone()))
+            .collect();
+
+        let mut stop_times: HashMap<TripId, Vec<_>> = HashMap::new();
+        for stop_time in self.stop_times.iter() {
+            if trips.contains_key(&stop_time.trip_id) && stop_time.stop_id.is_some() {
+                stop_times
+                    .entry(stop_time.trip_id.clone())
+                    .or_default()
+                    .push(stop_time.value().clone());
+            }
+        }
+        let mut frequencies: HashMap<TripId, Vec<Frequency>> = HashMap::new();
+        for frequency in self.frequencies.iter() {
+            if stop_times.contains_key(&frequency.trip_id) {
+                frequencies
+                    .entry(frequency.trip_id.clone())
+                    .or_default()
+                    .push(frequency.value().clone());
+            }
+        }
+
+        // The stops of each trip, with the time shown, in order.
+        let mut calls: Vec<(TripId, Vec<Call>)> = stop_times
+            .into_iter()
+            .map(|(trip_id, mut stop_times)| {
+                stop_times.sort_unstable_by_key(|stop_time| stop_time.stop_sequence);
+                let last = stop_times.len() - 1;
+                let calls = stop_times
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(index, stop_time)| {
+                        let time = if index == last {
+                            stop_time.arrival_time.or(stop_time.departure_time)
+                        } else {
+                            stop_time.departure_time.or(stop_time.arrival_time)
+                        };
+                        let timepoint =
+                            time.is_some() && stop_time.timepoint != Some(Timepoint::Approximate);
+                        if options.timepoints_only && !timepoint {
+                            return None;
+                        }
+                        Some((stop_time.stop_id?, time.map(|time| time.as_secs())))
+                    })
+                    .collect::<Vec<_>>();
+                (trip_id, calls)
+            })
+            .filter(|(_, calls)| !calls.is_empty())
+            .collect();
+        calls.sort_unstable_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+
+        // Merges the patterns into the rows, the most common first.
+        let mut patterns: HashMap<Vec<StopId>, usize> = HashMap::new();
+        for (_, calls) in &calls {
+            let stop_ids = calls.iter().map(|(stop_id, _)| stop_id.clone()).collect();
+            *patterns.entry(stop_ids).or_default() += 1;
+        }
+        let mut patterns: Vec<(Vec<StopId>, usize)> = patterns.into_iter().collect();
+        patterns.sort_unstable_by(|(a, a_count), (b, b_count)| {
+            b_count.cmp(a_count).then_with(|| {
+                let a = a.iter().map(StopId::as_str);
+                a.cmp(b.iter().map(StopId::as_str))
+            })
+        });
+        let mut rows: Vec<StopId> = vec![];
+        for (stop_ids, _) in patterns {
+            let mut next = 0;
+            for stop_id in stop_ids {
+                match find_row(&rows, next, &stop_id) {
+                    Some(row) => next = row + 1,
+                    None => {
+                        rows.insert(next, stop_id);
+                        next += 1;
+                    }
+                }
+            }
+        }
+
+        let mut columns = vec![];
+        for (trip_id, calls) in calls {
+            let Some(trip) = trips.get(&trip_id) else {
+                continue;
+            };
+            let mut row_calls = Vec::with_capacity(calls.len());
+            let mut next = 0;
+            for (stop_id, time) in calls {
+                if let Some(row) = find_row(&rows, next, &stop_id) {
+                    row_calls.push((row, time));
+                    next = row + 1;
+                }
+            }
+            let first_departure = row_calls.iter().find_map(|(_, time)| *time).unwrap_or(0);
+            for start in trip_starts(
+                frequencies.get(&trip_id).map(Vec::as_slice),
+                first_departure,
+            ) {
+                let mut times = vec![None; rows.len()];
+                for (row, time) in &row_calls {
+                    times[*row] = time.map(|time| {
+                        NaiveServiceTime::from_secs((time + start).saturating_sub(first_departure))
+                    });
+                }
+                columns.push(TimetableTrip {
+                    trip_id: trip_id.clone(),
+                    trip_short_name: trip.trip_short_name.clone(),
+                    trip_headsign: trip.trip_headsign.clone(),
+                    times,
+                });
+            }
+        }
+        // Trips without any time come last.
+        columns.sort_by_key(|trip| {
+            let first_time = trip.times.iter().find_map(|time| *time);
+            (first_time.is_none(), first_time.map(|time| time.as_secs()))
+        });
+
+        Timetable {
+            route_id: route_id.clone(),
+            direction_id,
+            date,
+            stops: rows
+                .into_iter()
+                .map(|stop_id| TimetableStop {
+                    stop_name: self
+                        .stops
+                        .get(&stop_id)
+                        .and_then(|stop| stop.stop_name.clone()),
+                    stop_id,
+                })
+                .collect(),
+            trips: columns,
+        }
+    }
+}
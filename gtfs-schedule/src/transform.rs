@@ -0,0 +1,191 @@
+//! Transformations of the schedule of a dataset.
+//!
+//! Each transformation updates every table referencing the records it changes, so that the
+//! dataset stays valid.
+
+use std::collections::HashSet;
+
+use chrono::{Duration, NaiveDate};
+
+use crate::error::{DatasetValidationError, Result};
+use crate::schemas::{CalendarServiceId, NaiveServiceTime, Schema, TripId};
+use crate::Dataset;
+
+impl Dataset {
+    /// Shifts the times of trips by `offset`, which may be negative.
+    ///
+    /// The arrival and departure times and the pickup/drop off windows of the stop times of the
+    /// trips are shifted, along with their periods in frequencies.txt. If a time would fall
+    /// before the start of the service day, an error is returned and the dataset is left
+    /// untouched. Times may be shifted past midnight, e.g. to 25:10:00, as trips can run into
+    /// the next day.
+    pub fn shift_trip_times(&mut self, trip_ids: &[TripId], offset: Duration) -> Result<()> {
+        let trip_ids: HashSet<&TripId> = trip_ids.iter().collect();
+        let offset = offset.num_seconds();
+        let out_of_range = |field_name: &str, value: String, record: Schema| {
+            DatasetValidationError::new_inconsistent_value(
+                field_name.to_string(),
+                value,
+                Some(format!(
+                    "shifting it by {offset} seconds moves it out of the service day"
+                )),
+                vec![record],
+            )
+        };
+
+        let mut stop_times = vec![];
+        for stop_time in self.stop_times.iter() {
+            if !trip_ids.contains(&stop_time.trip_id) {
+                continue;
+            }
+            let mut shifted = stop_time.value().clone();
+            for (field_name, time) in [
+                ("arrival_time", &mut shifted.arrival_time),
+                ("departure_time", &mut shifted.departure_time),
+                (
+                    "start_pickup_drop_off_window",
+                    &mut shifted.start_pickup_drop_off_window,
+                ),
+                (
+                    "end_pickup_drop_off_window",
+                    &mut shifted.end_pickup_drop_off_window,
+                ),
+            ] {
+                if let Some(value) = time {
+                    *time = Some(shift(*value, offset).ok_or_else(|| {
+                        out_of_range(
+                            field_name,
+                            String::from(*value),
+                            stop_time.value().clone().into(),
+                        )
+                    })?);
+                }
+            }
+            stop_times.push((stop_time.key().clone(), shifted));
+        }
+
+        let mut frequencies = vec![];
+        for frequency in self.frequencies.iter() {
+            if !trip_ids.contains(&frequency.trip_id) {
+                continue;
+            }
+            let mut shifted = frequency.value().clone();
+            for (field_name, time) in [
+                ("start_time", &mut shifted.start_time),
+                ("end_time", &mut shifted.end_time),
+            ] {
+                *time = shift(*time, offset).ok_or_else(|| {
+                    out_of_range(
+                        field_name,
+                        String::from(*time),
+                        frequency.value().clone().into(),
+                    )
+                })?;
+            }
+            frequencies.push((frequency.key().clone(), shifted));
+        }
+
+        for (key, stop_time) in stop_times {
+            self.stop_times.insert(key, stop_time);
+        }
+        for (key, _) in &frequencies {
+            self.frequencies.remove(key);
+        }
+        for (_, frequency) in frequencies {
+            self.frequencies
+                .insert((frequency.trip_id.clone(), frequency.start_time), frequency);
+        }
+
+        Ok(())
+    }
+
+    /// Renames a service, in calendar.txt, calendar_dates.txt and the trips and timeframes using
+    /// it.
+    ///
+    /// Returns an error if `new_id` is already used by another service, as the services would
+    /// be merged.
+    pub fn remap_service(
+        &mut self,
+        old_id: &CalendarServiceId,
+        new_id: CalendarServiceId,
+    ) -> Result<()> {
+        if *old_id == new_id {
+            return Ok(());
+        }
+        if self.service_ids().contains(&new_id) {
+            let records = self
+                .calendar
+                .get(&new_id)
+                .map(|calendar| vec![calendar.value().clone().into()])
+                .unwrap_or_default();
+            return Err(DatasetValidationError::new_primary_key_not_unique(
+                "service_id".to_string(),
+                new_id.to_string(),
+                records,
+            )
+            .into());
+        }
+
+        if let Some((_, mut calendar)) = self.calendar.remove(old_id) {
+            calendar.service_id = new_id.clone();
+            self.calendar.insert(new_id.clone(), calendar);
+        }
+        let dates: Vec<NaiveDate> = self
+            .calendar_dates
+            .iter()
+            .filter(|calendar_date| calendar_date.service_id == *old_id)
+            .map(|calendar_date| calendar_date.date)
+            .collect();
+        for date in dates {
+            if let Some((_, mut calendar_date)) =
+                self.calendar_dates.remove(&(old_id.clone(), date))
+            {
+                calendar_date.service_id = new_id.clone();
+                self.calendar_dates
+                    .insert((new_id.clone(), date), calendar_date);
+            }
+        }
+        for mut trip in self.trips.iter_mut() {
+            if trip.service_id == *old_id {
+                trip.service_id = new_id.clone();
+            }
+        }
+        for timeframe in &mut self.timeframes {
+            if timeframe.service_id == *old_id {
+                timeframe.service_id = new_id.clone();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Extends the weekly patterns of calendar.txt that end before `end_date` up to it.
+    ///
+    /// The `feed_end_date` of feed_info.txt is moved to `end_date` as well when it is earlier.
+    /// Services only defined in calendar_dates.txt are left untouched, as they have no pattern to
+    /// repeat.
+    ///
+    /// Returns the number of weekly patterns that were extended.
+    pub fn extend_calendar(&mut self, end_date: NaiveDate) -> usize {
+        let mut extended = 0;
+        for mut calendar in self.calendar.iter_mut() {
+            if calendar.end_date < end_date {
+                calendar.end_date = end_date;
+                extended += 1;
+            }
+        }
+        if let Some(feed_info) = self.feed_info.as_mut() {
+            if feed_info.feed_end_date.is_some_and(|date| date < end_date) {
+                feed_info.feed_end_date = Some(end_date);
+            }
+        }
+
+        extended
+    }
+}
+
+/// Shifts a time by `offset` seconds, `None` if it would be before the start of the service day.
+fn shift(time: NaiveServiceTime, offset: i64) -> Option<NaiveServiceTime> {
+    let secs = u32::try_from(i64::from(time.as_secs()) + offset).ok()?;
+    Some(NaiveServiceTime::from_secs(secs))
+}
@@ -0,0 +1,360 @@
+//! Coverage of the translations of a dataset.
+//!
+//! The main types are:
+//! - [`TranslatableValue`]: A value shown to riders, which translations.txt may translate.
+//! - [`TranslationCoverage`]: The values translated, or not, into a language.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use oxilangtag::LanguageTag;
+
+use crate::schemas::{TableName, Translation};
+use crate::Dataset;
+
+/// A value shown to riders, which translations.txt may translate, see
+/// [`Dataset::translation_coverage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranslatableValue {
+    /// The table of the record holding the value.
+    pub table_name: TableName,
+    /// The field holding the value, e.g. `stop_name`.
+    pub field_name: &'static str,
+    /// The id of the record, as used by [`Translation::record_id`], `None` for feed_info.txt
+    /// and for records without id.
+    pub record_id: Option<String>,
+    /// The stop sequence of a stop time, as used by [`Translation::record_sub_id`].
+    pub record_sub_id: Option<String>,
+    /// The value itself.
+    pub field_value: String,
+}
+
+/// The values translated into a language, see [`Dataset::translation_coverage`].
+#[derive(Debug, Clone)]
+pub struct TranslationCoverage {
+    /// The language of the translations.
+    pub language: LanguageTag<String>,
+    /// The number of values with a translation.
+    pub translated: usize,
+    /// The values without translation, grouped by table, then sorted by field and record.
+    pub missing: Vec<TranslatableValue>,
+}
+
+impl TranslationCoverage {
+    /// Returns the share of the values with a translation, `1.0` without any value.
+    pub fn ratio(&self) -> f64 {
+        let total = self.translated + self.missing.len();
+        if total == 0 {
+            return 1.0;
+        }
+        self.translated as f64 / total as f64
+    }
+}
+
+/// The translations of translations.txt into a language, by record and by value.
+struct TranslatedValues<'a> {
+    records: HashSet<(&'a TableName, &'a str, Option<&'a str>, Option<&'a str>)>,
+    values: HashSet<(&'a TableName, &'a str, &'a str)>,
+}
+
+impl<'a> TranslatedValues<'a> {
+    fn new(translations: &'a [Translation], language: &LanguageTag<String>) -> Self {
+        let mut translated = TranslatedValues {
+            records: HashSet::new(),
+            values: HashSet::new(),
+        };
+        for translation in translations {
+            if translation.language != *language {
+                continue;
+            }
+            let table_name = &translation.table_name;
+            let field_name = translation.field_name.as_str();
+            if let Some(field_value) = &translation.field_value {
+                translated
+                    .values
+                    .insert((table_name, field_name, field_value.as_str()));
+            } else {
+                translated.records.insert((
+                    table_name,
+                    field_name,
+                    translation.record_id.as_deref(),
+                    translation.record_sub_id.as_deref(),
+                ));
+            }
+        }
+        translated
+    }
+
+    fn contains(&self, value: &TranslatableValue) -> bool {
+        let table_name = &value.table_name;
+        // A record without id can only be translated by its value, feed_info.txt aside.
+        let by_record = value.record_id.is_some() || value.table_name == TableName::FeedInfo;
+        (by_record
+            && self.records.contains(&(
+                table_name,
+                value.field_name,
+                value.record_id.as_deref(),
+                value.record_sub_id.as_deref(),
+            )))
+            || self
+                .values
+                .contains(&(table_name, value.field_name, value.field_value.as_str()))
+    }
+}
+
+/// Returns the non-empty values of the fields of a record.
+fn record_values(
+    table_name: TableName,
+    record_id: Option<String>,
+    record_sub_id: Option<String>,
+    fields: &[(&'static str, Option<&str>)],
+) -> Vec<TranslatableValue> {
+    fields
+        .iter()
+        .filter_map(|(field_name, field_value)| {
+            let field_value = field_value.filter(|value| !value.trim().is_empty())?;
+            Some(TranslatableValue {
+                table_name: table_name.clone(),
+                field_name,
+                record_id: record_id.clone(),
+                record_sub_id: record_sub_id.clone(),
+                field_value: field_value.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Orders the values of a table by field, then record, stop sequences being compared as numbers.
+fn compare_values(a: &TranslatableValue, b: &TranslatableValue) -> Ordering {
+    let sub_id = |value: &TranslatableValue| {
+        value
+            .record_sub_id
+            .as_deref()
+            .and_then(|sub_id| sub_id.parse::<u32>().ok())
+    };
+    a.field_name
+        .cmp(b.field_name)
+        .then_with(|| a.record_id.cmp(&b.record_id))
+        .then_with(|| sub_id(a).cmp(&sub_id(b)))
+        .then_with(|| a.field_value.cmp(&b.field_value))
+}
+
+impl Dataset {
+    /// Returns the values shown to riders, such as the names of stops and the headsigns of
+    /// trips, grouped by table.
+    pub fn translatable_values(&self) -> Vec<TranslatableValue> {
+        let tables: Vec<Vec<TranslatableValue>> = vec![
+            self.agencies
+                .iter()
+                .flat_map(|agency| {
+                    record_values(
+                        TableName::Agency,
+                        agency.agency_id.as_ref().map(|id| id.to_string()),
+                        None,
+                        &[("agency_name", Some(&agency.agency_name))],
+                    )
+                })
+                .collect(),
+            self.stops
+                .iter()
+                .flat_map(|stop| {
+                    record_values(
+                        TableName::Stops,
+                        Some(stop.stop_id.to_string()),
+                        None,
+                        &[
+                            ("stop_name", stop.stop_name.as_deref()),
+                            ("tts_stop_name", stop.tts_stop_name.as_deref()),
+                            ("stop_desc", stop.stop_desc.as_deref()),
+                            ("platform_code", stop.platform_code.as_deref()),
+                        ],
+                    )
+                })
+                .collect(),
+            self.routes
+                .iter()
+                .flat_map(|route| {
+                    record_values(
+                        TableName::Routes,
+                        Some(route.route_id.to_string()),
+                        None,
+                        &[
+                            ("route_short_name", route.route_short_name.as_deref()),
+                            ("route_long_name", route.route_long_name.as_deref()),
+                            ("route_desc", route.route_desc.as_deref()),
+                        ],
+                    )
+                })
+                .collect(),
+            self.trips
+                .iter()
+                .flat_map(|trip| {
+                    record_values(
+                        TableName::Trips,
+                        Some(trip.trip_id.to_string()),
+                        None,
+                        &[
+                            ("trip_headsign", trip.trip_headsign.as_deref()),
+                            ("trip_short_name", trip.trip_short_name.as_deref()),
+                        ],
+                    )
+                })
+                .collect(),
+            self.stop_times
+                .iter()
+                .flat_map(|stop_time| {
+                    record_values(
+                        TableName::StopTimes,
+                        Some(stop_time.trip_id.to_string()),
+                        Some(stop_time.stop_sequence.to_string()),
+                        &[("stop_headsign", stop_time.stop_headsign.as_deref())],
+                    )
+                })
+                .collect(),
+            self.pathways
+                .iter()
+                .flat_map(|pathway| {
+                    record_values(
+                        TableName::Pathways,
+                        Some(pathway.pathway_id.to_string()),
+                        None,
+                        &[
+                            ("signposted_as", pathway.signposted_as.as_deref()),
+                            (
+                                "reversed_signposted_as",
+                                pathway.reversed_signposted_as.as_deref(),
+                            ),
+                        ],
+                    )
+                })
+                .collect(),
+            self.levels
+                .iter()
+                .flat_map(|level| {
+                    record_values(
+                        TableName::Levels,
+                        Some(level.level_id.to_string()),
+                        None,
+                        &[("level_name", level.level_name.as_deref())],
+                    )
+                })
+                .collect(),
+            self.feed_info
+                .iter()
+                .flat_map(|feed_info| {
+                    record_values(
+                        TableName::FeedInfo,
+                        None,
+                        None,
+                        &[("feed_publisher_name", Some(&feed_info.feed_publisher_name))],
+                    )
+                })
+                .collect(),
+            self.attributions
+                .iter()
+                .flat_map(|attribution| {
+                    record_values(
+                        TableName::Attributions,
+                        attribution.attribution_id.as_ref().map(|id| id.to_string()),
+                        None,
+                        &[("organization_name", Some(&attribution.organization_name))],
+                    )
+                })
+                .collect(),
+        ];
+
+        tables
+            .into_iter()
+            .flat_map(|mut values| {
+                values.sort_by(compare_values);
+                values
+            })
+            .collect()
+    }
+
+    /// Returns, for each language of translations.txt, the values of
+    /// [`Dataset::translatable_values`] that are translated into it and those that are not,
+    /// sorted by language.
+    ///
+    /// A value is translated by a translation of its record, or of the value itself with
+    /// [`Translation::field_value`]. The language of the dataset,
+    /// [`crate::schemas::FeedInfo::feed_lang`], needs no translation, unless it is `mul`: the
+    /// values are then in no language in particular, so every language is expected to
+    /// translate all of them.
+    pub fn translation_coverage(&self) -> Vec<TranslationCoverage> {
+        let feed_lang = self
+            .feed_info
+            .as_ref()
+            .map(|feed_info| &feed_info.feed_lang)
+            .filter(|feed_lang| !feed_lang.as_str().eq_ignore_ascii_case("mul"));
+        let mut languages: Vec<&LanguageTag<String>> = vec![];
+        for translation in &self.translations {
+            if Some(&translation.language) != feed_lang
+                && !languages.contains(&&translation.language)
+            {
+                languages.push(&translation.language);
+            }
+        }
+        languages.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        let values = self.translatable_values();
+        languages
+            .into_iter()
+            .map(|language| {
+                let translated = TranslatedValues::new(&self.translations, language);
+                let (translated, missing): (Vec<TranslatableValue>, Vec<TranslatableValue>) =
+                    values
+                        .iter()
+                        .cloned()
+                        .partition(|value| translated.contains(value));
+                TranslationCoverage {
+                    language: language.clone(),
+                    translated: translated.len(),
+                    missing,
+                }
+            })
+            .collect()
+    }
+
+    /// Translates into `language` the values of [`Dataset::translatable_values`] that are not
+    /// yet, with the translation returned by `provider` for each value, e.g. from a machine
+    /// translation service.
+    ///
+    /// Translations refer to their record, or to their value for the records without id. An
+    /// empty translation returned by `provider` is not added. Returns the number of
+    /// translations added.
+    pub fn add_missing_translations(
+        &mut self,
+        language: LanguageTag<String>,
+        provider: impl Fn(&str) -> String,
+    ) -> usize {
+        let translated = TranslatedValues::new(&self.translations, &language);
+        let missing: Vec<TranslatableValue> = self
+            .translatable_values()
+            .into_iter()
+            .filter(|value| !translated.contains(value))
+            .collect();
+
+        let mut added = 0;
+        for value in missing {
+            let translation = provider(&value.field_value);
+            if translation.trim().is_empty() {
+                continue;
+            }
+            let by_record = value.record_id.is_some() || value.table_name == TableName::FeedInfo;
+            self.translations.push(Translation {
+                table_name: value.table_name,
+                field_name: value.field_name.to_string(),
+                language: language.clone(),
+                translation,
+                record_id: value.record_id,
+                record_sub_id: value.record_sub_id,
+                field_value: (!by_record).then_some(value.field_value),
+                #[cfg(feature = "extensions")]
+                extensions: Default::default(),
+            });
+            added += 1;
+        }
+        added
+    }
+}
@@ -0,0 +1,186 @@
+//! Versioning of published datasets, through [`crate::schemas::FeedInfo::feed_version`].
+//!
+//! The main types are:
+//! - [`VersionStrategy`]: How the version of a dataset is bumped.
+//! - [`PublishedVersion`]: The version and content of a published dataset.
+//! - [`PublishStatus`]: Whether a dataset differs from its published version.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{DatasetValidationError, Error, Result};
+use crate::schemas::Schema;
+use crate::Dataset;
+
+/// How the version of a dataset is bumped, see [`Dataset::bump_version`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionStrategy {
+    /// Increments the major number of a `MAJOR.MINOR.PATCH` version, resetting the others.
+    Major,
+    /// Increments the minor number of a `MAJOR.MINOR.PATCH` version, resetting the patch number.
+    Minor,
+    /// Increments the patch number of a `MAJOR.MINOR.PATCH` version.
+    Patch,
+    /// Uses the publication date as `YYYYMMDD`, followed by `.N` for the `N`-th republication
+    /// of the same day.
+    Date(NaiveDate),
+}
+
+/// The version and content of a published dataset, see [`Dataset::published_version`].
+///
+/// It is small enough to be stored alongside the published files, and compared with the next
+/// dataset using [`Dataset::publish_status`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishedVersion {
+    /// The [`crate::schemas::FeedInfo::feed_version`] of the dataset.
+    pub feed_version: Option<String>,
+    /// The [`Dataset::content_fingerprint`] of the dataset.
+    pub fingerprint: String,
+}
+
+/// Whether a dataset differs from its published version, see [`Dataset::publish_status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishStatus {
+    /// Neither the content nor the version changed.
+    UpToDate,
+    /// The version changed, but not the content.
+    VersionOnly,
+    /// The content changed, and the version with it: the dataset is ready to be published.
+    Republish,
+    /// The content changed but the version did not, so consumers could not tell both datasets
+    /// apart: the version must be bumped before publishing.
+    StaleVersion,
+}
+
+impl PublishStatus {
+    /// Whether the content changed since the published version.
+    pub fn needs_republish(self) -> bool {
+        matches!(self, PublishStatus::Republish | PublishStatus::StaleVersion)
+    }
+}
+
+/// Parses a `MAJOR.MINOR.PATCH` version, keeping an optional `v` prefix. Missing numbers are
+/// zeros.
+fn parse_semver(version: &str) -> Option<(&str, [u64; 3])> {
+    let (prefix, numbers) = match version.strip_prefix('v') {
+        Some(numbers) => ("v", numbers),
+        None => ("", version),
+    };
+    let mut parts = [0; 3];
+    for (position, number) in numbers.split('.').enumerate() {
+        *parts.get_mut(position)? = number.parse().ok()?;
+    }
+    Some((prefix, parts))
+}
+
+/// Returns the version of a dataset published on `date`, following `current`.
+fn next_date_version(current: Option<&str>, date: NaiveDate) -> String {
+    let day = date.format("%Y%m%d").to_string();
+    let republication = match current.and_then(|current| current.strip_prefix(&day)) {
+        Some("") => Some(1),
+        Some(suffix) => suffix
+            .strip_prefix('.')
+            .and_then(|n| n.parse::<u64>().ok())
+            .map(|n| n + 1),
+        None => None,
+    };
+    match republication {
+        Some(n) => format!("{}.{}", day, n),
+        None => day,
+    }
+}
+
+/// The error returned when the version is changed without feed_info.txt record.
+fn missing_feed_info() -> Error {
+    DatasetValidationError::new_missing_value(
+        "feed_version".to_string(),
+        Some("The dataset has no feed_info.txt record".to_string()),
+        vec![],
+    )
+    .into()
+}
+
+impl Dataset {
+    /// Sets [`crate::schemas::FeedInfo::feed_version`].
+    ///
+    /// Fails if the dataset has no feed_info.txt record, as its required fields can not be
+    /// made up.
+    pub fn set_version(&mut self, version: impl Into<String>) -> Result<()> {
+        let feed_info = self.feed_info.as_mut().ok_or_else(missing_feed_info)?;
+        feed_info.feed_version = Some(version.into());
+        Ok(())
+    }
+
+    /// Returns the version following [`crate::schemas::FeedInfo::feed_version`] under `strategy`, without
+    /// changing the dataset, see [`Dataset::bump_version`].
+    pub fn next_version(&self, strategy: VersionStrategy) -> Result<String> {
+        let feed_info = self.feed_info.as_ref().ok_or_else(missing_feed_info)?;
+        let current = feed_info.feed_version.as_deref().map(str::trim);
+
+        let position = match strategy {
+            VersionStrategy::Major => 0,
+            VersionStrategy::Minor => 1,
+            VersionStrategy::Patch => 2,
+            VersionStrategy::Date(date) => return Ok(next_date_version(current, date)),
+        };
+        let (prefix, mut numbers) = match current.filter(|current| !current.is_empty()) {
+            Some(current) => parse_semver(current).ok_or_else(|| {
+                DatasetValidationError::new_inconsistent_value(
+                    "feed_version".to_string(),
+                    current.to_string(),
+                    Some("Not a MAJOR.MINOR.PATCH version".to_string()),
+                    vec![Schema::from(feed_info.clone())],
+                )
+            })?,
+            None => ("", [0; 3]),
+        };
+        numbers[position] += 1;
+        numbers[position + 1..].fill(0);
+        Ok(format!(
+            "{}{}.{}.{}",
+            prefix, numbers[0], numbers[1], numbers[2]
+        ))
+    }
+
+    /// Bumps [`crate::schemas::FeedInfo::feed_version`] under `strategy`, returning the new version.
+    ///
+    /// Without version, `MAJOR.MINOR.PATCH` strategies start from `0.0.0`. Fails if the dataset
+    /// has no feed_info.txt record, or if its version does not follow `strategy`.
+    pub fn bump_version(&mut self, strategy: VersionStrategy) -> Result<String> {
+        let version = self.next_version(strategy)?;
+        self.set_version(version.clone())?;
+        Ok(version)
+    }
+
+    /// Returns the version and content of the dataset, to be stored when publishing it.
+    ///
+    /// Fails if the content can not be fingerprinted, see [`Dataset::content_fingerprint`].
+    pub fn published_version(&self) -> Result<PublishedVersion> {
+        Ok(PublishedVersion {
+            feed_version: self
+                .feed_info
+                .as_ref()
+                .and_then(|feed_info| feed_info.feed_version.clone()),
+            fingerprint: self.content_fingerprint()?,
+        })
+    }
+
+    /// Compares the dataset with the version last published.
+    ///
+    /// Content is compared using [`Dataset::content_fingerprint`], so changing
+    /// feed_info.txt alone does not call for a republication.
+    pub fn publish_status(&self, published: &PublishedVersion) -> Result<PublishStatus> {
+        let current = self.published_version()?;
+        Ok(
+            match (
+                current.fingerprint == published.fingerprint,
+                current.feed_version == published.feed_version,
+            ) {
+                (true, true) => PublishStatus::UpToDate,
+                (true, false) => PublishStatus::VersionOnly,
+                (false, false) => PublishStatus::Republish,
+                (false, true) => PublishStatus::StaleVersion,
+            },
+        )
+    }
+}
@@ -0,0 +1,420 @@
+//! Checks of the recommendations of the specification.
+//!
+//! Unlike [`Dataset::validate`], which enforces requirements, these checks report practices the
+//! specification recommends, such as readable route colors. A dataset with warnings is valid.
+//!
+//! The main types are:
+//! - [`Warning`]: A recommendation that a dataset does not follow.
+//! - [`WarningKind`]: The recommendation that is not followed.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::publisher::same_url;
+use crate::schemas::{ExactTimes, Route, Schema, Shape, ShapeId, StopId};
+use crate::{Dataset, ShapeGeometry};
+
+/// Minimum contrast ratio between `route_color` and `route_text_color`.
+///
+/// This is the ratio required by WCAG for large text, which route names usually are.
+const MIN_COLOR_CONTRAST_RATIO: f64 = 3.0;
+
+/// Maximum number of characters of a `route_short_name`.
+const MAX_ROUTE_SHORT_NAME_LENGTH: usize = 12;
+
+/// Maximum distance, in meters, between a stop and the shape of the trips serving it.
+const MAX_STOP_DISTANCE_FROM_SHAPE_M: f64 = 1_000.0;
+
+/// Abbreviations of stop names that text-to-speech engines are likely to mispronounce.
+const AMBIGUOUS_ABBREVIATIONS: &[&str] = &[
+    "av", "ave", "blvd", "ctr", "dr", "ft", "hwy", "ln", "mt", "pkwy", "pl", "rd", "sq", "st",
+    "sta", "stn",
+];
+
+/// The recommendation that a dataset does not follow.
+#[derive(Error, Debug, Diagnostic, Clone, PartialEq)]
+#[diagnostic(severity(Warning))]
+pub enum WarningKind {
+    #[error(
+        "Route {route_id} has a color contrast ratio of {contrast_ratio:.2} between route_color and route_text_color"
+    )]
+    LowColorContrast {
+        route_id: String,
+        contrast_ratio: f64,
+    },
+    #[error("Stop {stop_id} has a stop_desc that duplicates its stop_name")]
+    StopDescSameAsName { stop_id: String },
+    #[error("Route {route_id} has a route_short_name of {length} characters")]
+    LongRouteShortName { route_id: String, length: usize },
+    #[error("Route {route_id} has the same route_short_name and route_long_name")]
+    SameRouteShortAndLongName { route_id: String },
+    #[error("Route {route_id} has a route_long_name starting with its route_short_name")]
+    RouteLongNameContainsShortName { route_id: String },
+    #[error("Route {route_id} has the same names as route {other_route_id} of the same agency")]
+    DuplicateRouteName {
+        route_id: String,
+        other_route_id: String,
+    },
+    #[error("Stop {stop_id} is {distance_m:.0} m away from shape {shape_id}")]
+    StopTooFarFromShape {
+        stop_id: String,
+        shape_id: String,
+        distance_m: f64,
+    },
+    #[error("Stop {stop_id} has no tts_stop_name, while its name {stop_name} is abbreviated")]
+    MissingTtsStopName { stop_id: String, stop_name: String },
+    #[error(
+        "Trips {trip_id} and {other_trip_id} of block {block_id} run at the same time on {date}"
+    )]
+    OverlappingBlockTrips {
+        block_id: String,
+        trip_id: String,
+        other_trip_id: String,
+        date: NaiveDate,
+    },
+    #[error("feed_info.txt has neither feed_contact_email nor feed_contact_url")]
+    MissingFeedContact,
+    #[error(
+        "Frequency of trip {trip_id} starting at {start_time} has an exact_times window of {window_secs}s that is not a multiple of its headway of {headway_secs}s"
+    )]
+    UnevenExactTimesWindow {
+        trip_id: String,
+        start_time: String,
+        window_secs: u64,
+        headway_secs: u64,
+    },
+    #[error("feed_publisher_url {url} is the agency_url of agency {agency_name}")]
+    PublisherUrlSameAsAgencyUrl { url: String, agency_name: String },
+}
+
+/// A recommendation that a dataset does not follow, see [`Dataset::warnings`].
+#[derive(Error, Debug, Diagnostic, Clone)]
+#[diagnostic(severity(Warning))]
+pub struct Warning {
+    #[source]
+    #[diagnostic_source]
+    pub kind: WarningKind,
+    pub schema_instances: Vec<Schema>,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}; row: {:?}", self.kind, self.schema_instances)
+    }
+}
+
+impl Dataset {
+    /// Checks that the dataset follows the recommendations of the specification.
+    ///
+    /// The following are reported:
+    /// - Routes whose `route_color` and `route_text_color` have a contrast ratio below 3:1,
+    ///   missing colors being white and black.
+    /// - Stops whose `stop_desc` is their `stop_name`.
+    /// - Routes whose `route_short_name` is longer than 12 characters.
+    /// - Routes whose `route_long_name` is, or starts with, their `route_short_name`, ignoring
+    ///   case.
+    /// - Routes of an agency sharing both their `route_short_name` and `route_long_name` with
+    ///   another route of the agency, reported against the route with the lowest id.
+    /// - Stops more than 1 km away from the shape of a trip serving them.
+    /// - Stops without `tts_stop_name` whose `stop_name` holds an abbreviation, such as `St`.
+    /// - Trips of a block running at the same time on a day, see [`Dataset::block_conflicts`].
+    /// - Frequencies with `exact_times` whose window is not a multiple of `headway_secs`, so
+    ///   that the last trip departs before `end_time` by less than a headway. The specification
+    ///   allows it, but it often hides a mistyped `end_time`.
+    /// - feed_info.txt without `feed_contact_email` nor `feed_contact_url`.
+    /// - A `feed_publisher_url` that is the `agency_url` of an agency. The specification allows
+    ///   it, but the publisher's own page tells consumers who to reach about the dataset, rather
+    ///   than the riders' site of the agency.
+    ///
+    /// Warnings are sorted by file, then by message.
+    pub fn warnings(&self) -> Vec<Warning> {
+        let mut warnings = vec![];
+
+        for stop in self.stops.iter() {
+            let stop_id = stop.stop_id.to_string();
+            let Some(stop_name) = stop.stop_name.as_deref() else {
+                continue;
+            };
+            if stop
+                .stop_desc
+                .as_deref()
+                .is_some_and(|stop_desc| stop_desc.trim().eq_ignore_ascii_case(stop_name.trim()))
+            {
+                warnings.push(Warning {
+                    kind: WarningKind::StopDescSameAsName {
+                        stop_id: stop_id.clone(),
+                    },
+                    schema_instances: vec![stop.value().clone().into()],
+                });
+            }
+            if stop.tts_stop_name.is_none() && is_abbreviated(stop_name) {
+                warnings.push(Warning {
+                    kind: WarningKind::MissingTtsStopName {
+                        stop_id,
+                        stop_name: stop_name.to_string(),
+                    },
+                    schema_instances: vec![stop.value().clone().into()],
+                });
+            }
+        }
+
+        for route in self.routes.iter() {
+            let route_color = route.route_color.as_deref().unwrap_or("FFFFFF");
+            let route_text_color = route.route_text_color.as_deref().unwrap_or("000000");
+            if let (Some(color), Some(text_color)) =
+                (luminance(route_color), luminance(route_text_color))
+            {
+                let contrast_ratio =
+                    (color.max(text_color) + 0.05) / (color.min(text_color) + 0.05);
+                if contrast_ratio < MIN_COLOR_CONTRAST_RATIO {
+                    warnings.push(Warning {
+                        kind: WarningKind::LowColorContrast {
+                            route_id: route.route_id.to_string(),
+                            contrast_ratio,
+                        },
+                        schema_instances: vec![route.value().clone().into()],
+                    });
+                }
+            }
+
+            let length = route
+                .route_short_name
+                .as_deref()
+                .map_or(0, |name| name.chars().count());
+            if length > MAX_ROUTE_SHORT_NAME_LENGTH {
+                warnings.push(Warning {
+                    kind: WarningKind::LongRouteShortName {
+                        route_id: route.route_id.to_string(),
+                        length,
+                    },
+                    schema_instances: vec![route.value().clone().into()],
+                });
+            }
+
+            let names = (
+                route.route_short_name.as_deref().map(normalize_route_name),
+                route.route_long_name.as_deref().map(normalize_route_name),
+            );
+            if let (Some(short_name), Some(long_name)) = names {
+                let route_id = route.route_id.to_string();
+                let kind = if short_name == long_name {
+                    Some(WarningKind::SameRouteShortAndLongName { route_id })
+                } else {
+                    // The short name must be followed by a separator, so that "1" does not
+                    // match "10th Street".
+                    long_name
+                        .strip_prefix(short_name.as_str())
+                        .and_then(|rest| rest.chars().next())
+                        .filter(|c| !c.is_alphanumeric())
+                        .map(|_| WarningKind::RouteLongNameContainsShortName { route_id })
+                };
+                if let Some(kind) = kind {
+                    warnings.push(Warning {
+                        kind,
+                        schema_instances: vec![route.value().clone().into()],
+                    });
+                }
+            }
+        }
+
+        for frequency in self.frequencies.iter() {
+            // A window ending before it starts is already a validation error.
+            let Some(window) = frequency.end_time.duration_since(frequency.start_time) else {
+                continue;
+            };
+            let window_secs = window.as_secs();
+            let headway_secs = frequency.headway_secs.as_secs();
+            if frequency.service_type() == ExactTimes::Exact
+                && headway_secs > 0
+                && window_secs % headway_secs != 0
+            {
+                warnings.push(Warning {
+                    kind: WarningKind::UnevenExactTimesWindow {
+                        trip_id: frequency.trip_id.to_string(),
+                        start_time: frequency.start_time.to_string(),
+                        window_secs,
+                        headway_secs,
+                    },
+                    schema_instances: vec![frequency.value().clone().into()],
+                });
+            }
+        }
+
+        warnings.extend(self.duplicate_route_names());
+        warnings.extend(self.stops_far_from_shapes());
+        if let Some(feed_info) = &self.feed_info {
+            if feed_info.feed_contact_email.is_none() && feed_info.feed_contact_url.is_none() {
+                warnings.push(Warning {
+                    kind: WarningKind::MissingFeedContact,
+                    schema_instances: vec![feed_info.clone().into()],
+                });
+            }
+            for agency in &self.agencies {
+                if same_url(&feed_info.feed_publisher_url, &agency.agency_url) {
+                    warnings.push(Warning {
+                        kind: WarningKind::PublisherUrlSameAsAgencyUrl {
+                            url: feed_info.feed_publisher_url.to_string(),
+                            agency_name: agency.agency_name.clone(),
+                        },
+                        schema_instances: vec![feed_info.clone().into(), agency.clone().into()],
+                    });
+                }
+            }
+        }
+        for conflict in self.block_conflicts() {
+            let trips = [&conflict.trip_id, &conflict.other_trip_id]
+                .into_iter()
+                .filter_map(|trip_id| self.trips.get(trip_id))
+                .map(|trip| trip.value().clone().into())
+                .collect();
+            warnings.push(Warning {
+                kind: WarningKind::OverlappingBlockTrips {
+                    block_id: conflict.block_id.to_string(),
+                    trip_id: conflict.trip_id.to_string(),
+                    other_trip_id: conflict.other_trip_id.to_string(),
+                    date: conflict.date,
+                },
+                schema_instances: trips,
+            });
+        }
+        warnings.sort_by_cached_key(|warning| {
+            (
+                warning.schema_instances.first().map(Schema::file),
+                warning.kind.to_string(),
+            )
+        });
+        warnings
+    }
+
+    /// Finds the routes sharing their names with another route of their agency.
+    ///
+    /// Routes without agency belong to the single agency of the dataset.
+    fn duplicate_route_names(&self) -> Vec<Warning> {
+        let mut routes_by_names: HashMap<_, Vec<Route>> = HashMap::new();
+        for route in self.routes.iter() {
+            let key = (
+                route
+                    .agency_id
+                    .as_ref()
+                    .map(|agency_id| agency_id.to_string()),
+                route.route_short_name.as_deref().map(normalize_route_name),
+                route.route_long_name.as_deref().map(normalize_route_name),
+            );
+            routes_by_names
+                .entry(key)
+                .or_default()
+                .push(route.value().clone());
+        }
+
+        let mut warnings = vec![];
+        for mut routes in routes_by_names.into_values() {
+            routes.sort_by(|a, b| a.route_id.as_str().cmp(b.route_id.as_str()));
+            let Some((first, others)) = routes.split_first() else {
+                continue;
+            };
+            for route in others {
+                warnings.push(Warning {
+                    kind: WarningKind::DuplicateRouteName {
+                        route_id: first.route_id.to_string(),
+                        other_route_id: route.route_id.to_string(),
+                    },
+                    schema_instances: vec![first.clone().into(), route.clone().into()],
+                });
+            }
+        }
+
+        warnings
+    }
+
+    /// Finds the stops too far from the shape of a trip serving them.
+    fn stops_far_from_shapes(&self) -> Vec<Warning> {
+        let mut points: HashMap<ShapeId, Vec<Shape>> = HashMap::new();
+        for shape in self.shapes.iter() {
+            points
+                .entry(shape.shape_id.clone())
+                .or_default()
+                .push(shape.value().clone());
+        }
+        let geometries: HashMap<ShapeId, ShapeGeometry> = points
+            .into_iter()
+            .filter_map(|(shape_id, points)| Some((shape_id, ShapeGeometry::from_points(points)?)))
+            .collect();
+
+        let mut checked: HashSet<(StopId, ShapeId)> = HashSet::new();
+        let mut warnings = vec![];
+        for stop_time in self.stop_times.iter() {
+            let Some(stop_id) = &stop_time.stop_id else {
+                continue;
+            };
+            let Some(shape_id) = self
+                .trips
+                .get(&stop_time.trip_id)
+                .and_then(|trip| trip.shape_id.clone())
+            else {
+                continue;
+            };
+            if !checked.insert((stop_id.clone(), shape_id.clone())) {
+                continue;
+            }
+
+            let (Some(geometry), Some(stop)) = (geometries.get(&shape_id), self.stops.get(stop_id))
+            else {
+                continue;
+            };
+            let Some(distance_m) = stop
+                .stop_coord
+                .as_ref()
+                .and_then(|coord| geometry.distance_m(**coord))
+            else {
+                continue;
+            };
+            if distance_m > MAX_STOP_DISTANCE_FROM_SHAPE_M {
+                warnings.push(Warning {
+                    kind: WarningKind::StopTooFarFromShape {
+                        stop_id: stop_id.to_string(),
+                        shape_id: shape_id.to_string(),
+                        distance_m,
+                    },
+                    schema_instances: vec![stop.value().clone().into()],
+                });
+            }
+        }
+
+        warnings
+    }
+}
+
+/// Trims and lowercases a route name, so that names differing only by case compare equal.
+fn normalize_route_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Returns `true` if a stop name holds a word that is likely an abbreviation.
+fn is_abbreviated(stop_name: &str) -> bool {
+    stop_name
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '/' || c == '-')
+        .any(|word| {
+            let word = word.trim_end_matches('.').to_ascii_lowercase();
+            AMBIGUOUS_ABBREVIATIONS.contains(&word.as_str())
+        })
+}
+
+/// Computes the relative luminance of a color written as `RRGGBB`, as defined by WCAG.
+fn luminance(color: &str) -> Option<f64> {
+    if color.len() != 6 {
+        return None;
+    }
+    let channel = |index: usize| -> Option<f64> {
+        let value = f64::from(u8::from_str_radix(color.get(index..index + 2)?, 16).ok()?) / 255.0;
+        Some(if value <= 0.039_28 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        })
+    };
+
+    Some(0.2126 * channel(0)? + 0.7152 * channel(2)? + 0.0722 * channel(4)?)
+}
@@ -0,0 +1,62 @@
+//! Options controlling how a dataset is written.
+
+use crate::VersionStrategy;
+
+/// Options used by [`crate::Dataset::to_csv_with_options`].
+///
+/// By default, records are written in the order they were parsed in, and only the columns
+/// holding at least one value are written.
+///
+/// ```
+/// use gtfs_schedule::{RowOrder, WriteOptions};
+///
+/// let options = WriteOptions::default().row_order(RowOrder::Key).all_columns();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    pub(crate) row_order: RowOrder,
+    pub(crate) all_columns: bool,
+    pub(crate) version_strategy: Option<VersionStrategy>,
+}
+
+/// The order records are written in, see [`WriteOptions::row_order`].
+///
+/// Both orders are deterministic: writing the same dataset twice yields the same files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RowOrder {
+    /// The order the records were parsed in, see [`crate::ParseOptions::keep_source_lines`].
+    ///
+    /// Records of keyed tables without a line are written last, sorted by key, and tables
+    /// stored as lists keep their order.
+    #[default]
+    Source,
+    /// Sorted by [`crate::GtfsFile::key_columns`], then by the other columns in the order of
+    /// [`crate::GtfsFile::columns`]. Numbers are compared by value, so that stop sequence `10`
+    /// comes after `9`.
+    Key,
+}
+
+impl WriteOptions {
+    /// Choose the order records are written in.
+    pub fn row_order(mut self, row_order: RowOrder) -> Self {
+        self.row_order = row_order;
+        self
+    }
+
+    /// Write every column of [`crate::GtfsFile::columns`], even those without a value, so that
+    /// the header of a file does not depend on its content.
+    pub fn all_columns(mut self) -> Self {
+        self.all_columns = true;
+        self
+    }
+
+    /// Write feed_info.txt with the version following the current one under `strategy`, see
+    /// [`crate::Dataset::next_version`].
+    ///
+    /// The dataset itself keeps its version, and writing fails if it has no feed_info.txt
+    /// record. Use [`crate::Dataset::bump_version`] instead to keep track of the new version.
+    pub fn bump_version(mut self, strategy: VersionStrategy) -> Self {
+        self.version_strategy = Some(strategy);
+        self
+    }
+}
@@ -0,0 +1,227 @@
+//! Writing of datasets to CSV files.
+
+use std::cmp::Ordering;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::BTreeSet;
+use std::collections::{BTreeMap, HashMap};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use serde_json::Value;
+
+#[cfg(feature = "extensions")]
+use crate::schemas::Extensions;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::{ParseError, ParseErrorKind, Result};
+use crate::provenance::SourceLines;
+use crate::schemas::Schema;
+use crate::{Dataset, GtfsFile};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{RowOrder, WriteOptions};
+
+/// The values of a record, by column.
+pub(crate) type Row = HashMap<String, String>;
+
+impl Dataset {
+    /// Writes the dataset as CSV files into `dir`, which must exist.
+    ///
+    /// A file is written for each table with at least one record. Only the columns holding at
+    /// least one value are written, in the order of [`GtfsFile::columns`]. With the `extensions`
+    /// feature, the columns that are not part of the specification follow, sorted by name.
+    ///
+    /// Records are written in the order they were parsed in when the dataset was parsed with
+    /// [`crate::ParseOptions::keep_source_lines`], see [`RowOrder::Source`].
+    ///
+    /// Not available on `wasm32` targets, which have no file system.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_csv(&self, dir: &Path) -> Result<()> {
+        self.to_csv_with_options(dir, &WriteOptions::default())
+    }
+
+    /// Same as [`Dataset::to_csv`], but orders records, selects columns and updates the version
+    /// as set by `options`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_csv_with_options(&self, dir: &Path, options: &WriteOptions) -> Result<()> {
+        let feed_version = options
+            .version_strategy
+            .map(|strategy| self.next_version(strategy))
+            .transpose()?;
+
+        let mut tables: BTreeMap<GtfsFile, Vec<(Option<u64>, Row)>> = BTreeMap::new();
+        for (file, mut record) in self.iter_all() {
+            if let (Schema::FeedInfo(feed_info), Some(feed_version)) = (&mut record, &feed_version)
+            {
+                feed_info.feed_version = Some(feed_version.clone());
+            }
+            let line = match options.row_order {
+                RowOrder::Source => self.keyed_source_line(&record),
+                RowOrder::Key => None,
+            };
+            tables
+                .entry(file)
+                .or_default()
+                .push((line, csv_row(&record)));
+        }
+
+        for (file, mut rows) in tables {
+            let keyed = !file.key_columns().is_empty();
+            rows.sort_by(|(line_a, row_a), (line_b, row_b)| match (line_a, line_b) {
+                (Some(line_a), Some(line_b)) => line_a.cmp(line_b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                // Tables stored as lists have no lines, and keep their order.
+                (None, None) if keyed || options.row_order == RowOrder::Key => {
+                    compare_rows(file, row_a, row_b)
+                }
+                (None, None) => Ordering::Equal,
+            });
+            let rows: Vec<Row> = rows.into_iter().map(|(_, row)| row).collect();
+            let extension_columns: BTreeSet<&String> = rows
+                .iter()
+                .flat_map(|row| row.keys())
+                .filter(|column| !file.columns().contains(&column.as_str()))
+                .collect();
+            let columns: Vec<&str> = file
+                .columns()
+                .iter()
+                .copied()
+                .filter(|column| {
+                    options.all_columns || rows.iter().any(|row| row.contains_key(*column))
+                })
+                .chain(extension_columns.into_iter().map(|column| column.as_str()))
+                .collect();
+
+            let mut writer = csv::Writer::from_path(dir.join(file.file_name()))
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+            writer
+                .write_record(&columns)
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+            for row in &rows {
+                writer
+                    .write_record(
+                        columns
+                            .iter()
+                            .map(|column| row.get(*column).map_or("", String::as_str)),
+                    )
+                    .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+            }
+            writer
+                .flush()
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Dataset {
+    /// Puts the dataset in a canonical form, so that datasets with the same content are written
+    /// identically by [`Dataset::to_csv`].
+    ///
+    /// The tables stored as lists, such as [`Dataset::transfers`], are sorted as in
+    /// [`RowOrder::Key`], and their duplicate records are removed. Source lines are discarded, so
+    /// that keyed tables are written sorted by key as well.
+    pub fn canonicalize(&mut self) {
+        self.source_lines = SourceLines::default();
+
+        canonicalize_list(&mut self.agencies, GtfsFile::Agency);
+        canonicalize_list(&mut self.fare_rules, GtfsFile::FareRules);
+        canonicalize_list(&mut self.timeframes, GtfsFile::Timeframes);
+        canonicalize_list(&mut self.fare_leg_rules, GtfsFile::FareLegRules);
+        canonicalize_list(&mut self.fare_transfers, GtfsFile::FareTransfers);
+        canonicalize_list(&mut self.stops_areas, GtfsFile::StopsAreas);
+        canonicalize_list(&mut self.transfers, GtfsFile::Transfers);
+        canonicalize_list(
+            &mut self.location_groups_stops,
+            GtfsFile::LocationGroupsStops,
+        );
+        canonicalize_list(&mut self.translations, GtfsFile::Translations);
+        canonicalize_list(&mut self.attributions, GtfsFile::Attributions);
+    }
+}
+
+/// Sorts the records of a table stored as a list, and removes its duplicate records.
+fn canonicalize_list<T: Clone + Into<Schema>>(records: &mut Vec<T>, file: GtfsFile) {
+    let mut rows: Vec<(Row, T)> = records
+        .drain(..)
+        .map(|record| (csv_row(&record.clone().into()), record))
+        .collect();
+    rows.sort_by(|(row_a, _), (row_b, _)| compare_rows(file, row_a, row_b));
+    rows.dedup_by(|(row_a, _), (row_b, _)| row_a == row_b);
+    *records = rows.into_iter().map(|(_, record)| record).collect();
+}
+
+/// Returns the values of a record by column, leaving out empty columns.
+pub(crate) fn csv_row(record: &Schema) -> Row {
+    let row: Row = match serde_json::to_value(record) {
+        Ok(Value::Object(fields)) => fields
+            .into_iter()
+            .filter_map(|(column, value)| Some((column, field_value(&value)?)))
+            .collect(),
+        _ => Row::new(),
+    };
+    #[cfg(feature = "extensions")]
+    let row = {
+        let mut row = row;
+        for (column, value) in record.extensions() {
+            row.entry(column.clone()).or_insert_with(|| value.clone());
+        }
+        row
+    };
+
+    row
+}
+
+/// Compares two records of a file, see [`RowOrder::Key`].
+fn compare_rows(file: GtfsFile, a: &Row, b: &Row) -> Ordering {
+    file.key_columns()
+        .iter()
+        .chain(file.columns())
+        .map(|column| compare_values(a.get(*column), b.get(*column)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| {
+            // Extension columns, sorted by name.
+            let a: BTreeMap<&String, &String> = a.iter().collect();
+            let b: BTreeMap<&String, &String> = b.iter().collect();
+            a.cmp(&b)
+        })
+}
+
+/// Compares two values of a column, numbers by value, empty values first.
+fn compare_values(a: Option<&String>, b: Option<&String>) -> Ordering {
+    let (Some(a), Some(b)) = (a, b) else {
+        return a.cmp(&b);
+    };
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(number_a), Ok(number_b)) => number_a.total_cmp(&number_b).then_with(|| a.cmp(b)),
+        _ => a.cmp(b),
+    }
+}
+
+/// Formats a serialized field as a CSV value, `None` if the field is empty.
+pub(crate) fn field_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(value) if value.is_empty() => None,
+        Value::String(value) => Some(value.clone()),
+        Value::Bool(value) => Some(if *value { "1" } else { "0" }.to_string()),
+        Value::Number(number) => match number.as_f64() {
+            Some(float) if number.is_f64() => Some(shortest_float(float).to_string()),
+            _ => Some(number.to_string()),
+        },
+        value => Some(value.to_string()),
+    }
+}
+
+/// Undoes the widening of `f32` fields to `f64` by `serde_json`.
+///
+/// Most decimal fields are `f32`: when the conversion to `f32` is lossless, the value is rounded
+/// to the shortest decimal representing that `f32`, so that `0.212` is not written as
+/// `0.21199999749660492`. Other values are returned as is.
+pub(crate) fn shortest_float(float: f64) -> f64 {
+    if f64::from(float as f32) != float {
+        return float;
+    }
+    (float as f32).to_string().parse().unwrap_or(float)
+}
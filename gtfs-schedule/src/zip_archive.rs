@@ -0,0 +1,49 @@
+//! Parsing of datasets from zip archives held in memory, see [`Dataset::from_zip_bytes`].
+
+use std::io::{Cursor, Read};
+
+use crate::error::{ParseError, ParseErrorKind, Result};
+use crate::{Dataset, ParseOptions};
+
+/// The most memory reserved upfront for a file of the archive, since the size recorded in the
+/// archive is not trusted.
+const MAX_PREALLOCATION: u64 = 64 * 1024 * 1024;
+
+impl Dataset {
+    /// Parses a dataset from the bytes of a zip archive, such as a feed uploaded to a web page.
+    ///
+    /// Nothing is read from the file system, so datasets can be parsed where there is none, e.g.
+    /// in a browser with `wasm32-unknown-unknown`.
+    ///
+    /// ```no_run
+    /// use gtfs_schedule::Dataset;
+    ///
+    /// let bytes = std::fs::read("gtfs.zip").unwrap();
+    /// let dataset = Dataset::from_zip_bytes(&bytes).unwrap();
+    /// ```
+    pub fn from_zip_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::from_zip_bytes_with_options(bytes, &ParseOptions::default())
+    }
+
+    /// Same as [`Dataset::from_zip_bytes`], but only parses the tables selected by `options`.
+    ///
+    /// Only the files at the root of the archive are parsed, matched as by
+    /// [`Dataset::from_csv_with_options`].
+    pub fn from_zip_bytes_with_options(bytes: &[u8], options: &ParseOptions) -> Result<Self> {
+        let zip_error = |e: zip::result::ZipError| ParseError::from(ParseErrorKind::from(e));
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(zip_error)?;
+        let file_names = archive
+            .file_names()
+            .filter(|file_name| !file_name.contains('/'))
+            .map(String::from)
+            .collect();
+        Self::from_csv_files(file_names, options, |file_name| {
+            let mut file = archive.by_name(file_name).map_err(zip_error)?;
+            let capacity = file.size().min(MAX_PREALLOCATION);
+            let mut contents = Vec::with_capacity(usize::try_from(capacity).unwrap_or(0));
+            file.read_to_end(&mut contents)
+                .map_err(|e| ParseError::from(ParseErrorKind::from(e)))?;
+            Ok(Box::new(Cursor::new(contents)))
+        })
+    }
+}
@@ -0,0 +1,3695 @@
+use chrono::{NaiveDate, Weekday};
+use gtfs_schedule::convert::{Exporter, NetexExporter, TransitModel};
+use gtfs_schedule::schemas::{
+    Agency, AgencyId, AreaId, BookingRuleId, CalendarServiceId, ContinuousDropOff,
+    ContinuousPickup, ExactTimes, ExceptionType, FareId, FareTransfers, FeedInfo, GtfsTable,
+    LocationType, Money, NaiveServiceTime, NetworkId, Phone, RouteId, RouteType, ServiceDays, Stop,
+    StopId, StopTime, Timepoint, Transfer, TransferType, TripId,
+};
+use gtfs_schedule::{
+    parse_record, synth, BlockConflict, BorrowedReader, CalendarForm, CascadePolicy,
+    ConformanceCategory, ContactScrub, Dataset, DatasetTable, DuplicateKey, DuplicateKeyPolicy,
+    FareLeg, FeedCollection, FeedManager, FrequenciesReader, GtfsFile, InvalidContact, JourneyLeg,
+    NetworkForm, NoticeSeverity, ParseOptions, PlanOptions, Provenance, PublishStatus, RowOrder,
+    ScrubOptions, StopTimeRef, StopTimesReader, StopTimesWriter, StopsReader, SuggestedFix,
+    TimetableOptions, TripMerge, VersionStrategy, WarningKind, WriteOptions,
+};
+use iso_currency::Currency;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+fn load_dataset(dataset_name: &str) -> Dataset {
+    let path = Path::new("tests/_data")
+        .join(dataset_name)
+        .canonicalize()
+        .unwrap();
+
+    Dataset::from_csv(&path).unwrap()
+}
+
+#[test]
+fn test_iter_all() {
+    let dataset = load_dataset("good_feed");
+
+    let records: Vec<_> = dataset.iter_all().collect();
+
+    let count = |file: GtfsFile| records.iter().filter(|(f, _)| *f == file).count();
+    assert_eq!(count(GtfsFile::Agency), dataset.agencies.len());
+    assert_eq!(count(GtfsFile::Stops), dataset.stops.len());
+    assert_eq!(count(GtfsFile::StopTimes), dataset.stop_times.len());
+    assert_eq!(count(GtfsFile::Transfers), dataset.transfers.len());
+    assert_eq!(count(GtfsFile::Shapes), 0);
+
+    // Tables are visited in the order of `GtfsFile::ALL`.
+    assert!(records.windows(2).all(|w| w[0].0 <= w[1].0));
+}
+
+#[test]
+fn test_interpolate_stop_times() {
+    let dataset = load_dataset("good_feed");
+    let trip_id = TripId::from("CITY1");
+
+    for stop_sequence in [5, 10] {
+        let mut stop_time = dataset
+            .stop_times
+            .get_mut(&(trip_id.clone(), stop_sequence))
+            .unwrap();
+        stop_time.arrival_time = None;
+        stop_time.departure_time = None;
+    }
+
+    assert_eq!(dataset.interpolate_stop_times(&trip_id), 2);
+
+    let stop_time = dataset.stop_times.get(&(trip_id.clone(), 5)).unwrap();
+    assert_eq!(
+        stop_time.arrival_time,
+        NaiveServiceTime::try_from("6:06:20").ok()
+    );
+    assert_eq!(stop_time.departure_time, stop_time.arrival_time);
+    assert_eq!(stop_time.timepoint, Some(Timepoint::Approximate));
+    drop(stop_time);
+
+    let stop_time = dataset.stop_times.get(&(trip_id.clone(), 10)).unwrap();
+    assert_eq!(
+        stop_time.arrival_time,
+        NaiveServiceTime::try_from("6:12:40").ok()
+    );
+    drop(stop_time);
+
+    // Every stop time now has its times.
+    assert_eq!(dataset.interpolate_all_stop_times(), 0);
+}
+
+#[test]
+fn test_parse_options() {
+    let path = Path::new("tests/_data/good_feed");
+
+    let options = ParseOptions::default().skip_tables(&[GtfsFile::StopTimes]);
+    let dataset = Dataset::from_csv_with_options(path, &options).unwrap();
+    assert!(!dataset.is_loaded(GtfsFile::StopTimes));
+    assert!(dataset.stop_times.is_empty());
+    assert!(dataset.is_loaded(GtfsFile::Stops));
+    assert!(!dataset.stops.is_empty());
+    // Loaded, but absent from the dataset.
+    assert!(dataset.is_loaded(GtfsFile::Shapes));
+
+    let options = ParseOptions::default().only_tables(&[GtfsFile::Stops]);
+    let dataset = Dataset::from_csv_with_options(path, &options).unwrap();
+    assert!(!dataset.stops.is_empty());
+    assert!(!dataset.is_loaded(GtfsFile::Routes));
+    assert!(dataset.routes.is_empty());
+}
+
+#[test]
+fn test_parse_options_filter_records() {
+    let path = Path::new("tests/_data/good_feed");
+
+    let options = ParseOptions::default().only_routes(&[RouteId::from("AB")]);
+    let dataset = Dataset::from_csv_with_options(path, &options).unwrap();
+    assert_eq!(dataset.routes.len(), 1);
+    assert_eq!(dataset.trips.len(), 2);
+    assert!(dataset
+        .stop_times
+        .iter()
+        .all(|stop_time| stop_time.trip_id.as_str().starts_with("AB")));
+    assert!(!dataset.stop_times.is_empty());
+    assert!(dataset.frequencies.is_empty());
+    assert_eq!(dataset.stops.len(), 10);
+
+    // Weekdays only, the AAMV trips run on week-ends.
+    let options = ParseOptions::default().only_dates(
+        NaiveDate::from_ymd_opt(2007, 6, 5).unwrap()..=NaiveDate::from_ymd_opt(2007, 6, 8).unwrap(),
+    );
+    let dataset = Dataset::from_csv_with_options(path, &options).unwrap();
+    assert_eq!(dataset.routes.len(), 5);
+    assert_eq!(dataset.trips.len(), 7);
+    assert!(!dataset.trips.contains_key(&TripId::from("AAMV1")));
+    assert!(!dataset.frequencies.is_empty());
+}
+
+#[test]
+fn test_exceptional_service_days() {
+    let dataset = load_dataset("good_feed");
+    let date = NaiveDate::from_ymd_opt(2007, 6, 4).unwrap();
+    let holidays = HashMap::from([(date, "Holiday".to_string())]);
+
+    let days = dataset.exceptional_service_days(&holidays);
+
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0].date, date);
+    assert_eq!(days[0].service_id.as_str(), "FULLW");
+    assert_eq!(days[0].exception_type, ExceptionType::Removed);
+    assert_eq!(days[0].holiday.as_deref(), Some("Holiday"));
+}
+
+#[test]
+fn test_agency_contact() {
+    let dataset = load_dataset("good_feed");
+
+    let contact = dataset.agency_contact(&AgencyId::from("DTA")).unwrap();
+    assert_eq!(contact.agency_name, "Autorité de passage de démonstration");
+    assert_eq!(
+        contact.agency_phone.as_ref().map(Phone::as_str),
+        Some("123 12314")
+    );
+    assert_eq!(contact.agency_url.as_str(), "http://google.com/");
+
+    assert!(dataset.agency_contact(&AgencyId::from("UNKNOWN")).is_none());
+}
+
+#[test]
+fn test_vehicles_required() {
+    let dataset = load_dataset("good_feed");
+
+    // A Monday.
+    let requirement = dataset.vehicles_required(NaiveDate::from_ymd_opt(2024, 1, 8).unwrap());
+    assert_eq!(requirement.by_route[&RouteId::from("CITY")], 6);
+    // AB1 and AB2 belong to different blocks, but never run at the same time.
+    assert_eq!(requirement.by_route[&RouteId::from("AB")], 1);
+    assert!(!requirement.by_route.contains_key(&RouteId::from("AAMV")));
+    // Between 8:00 and 9:20: 6 CITY, 1 STBA, and the block made of AB1 and BFC1.
+    assert_eq!(requirement.peak_vehicles, 8);
+}
+
+#[test]
+fn test_validation_report() {
+    let mut dataset = load_dataset("good_feed");
+    assert!(dataset.validation_report().is_valid());
+
+    dataset.agencies[0].agency_id = None;
+    dataset
+        .stop_times
+        .get_mut(&(TripId::from("STBA"), 2))
+        .unwrap()
+        .shape_dist_traveled = Some(0.1);
+    {
+        let mut stop_time = dataset
+            .stop_times
+            .get_mut(&(TripId::from("CITY1"), 5))
+            .unwrap();
+        let stop_time = &mut *stop_time;
+        std::mem::swap(&mut stop_time.arrival_time, &mut stop_time.departure_time);
+    }
+    {
+        let mut route = dataset.routes.get_mut(&RouteId::from("AB")).unwrap();
+        route.route_short_name = None;
+        route.route_long_name = None;
+    }
+
+    let report = dataset.validation_report();
+    assert_eq!(report.findings.len(), 4);
+    assert_eq!(
+        report
+            .findings
+            .iter()
+            .filter(|finding| finding.severity == NoticeSeverity::Warning)
+            .count(),
+        1
+    );
+    assert!(report.findings.iter().all(|finding| finding.fix.is_some()));
+    assert!(report.findings.iter().any(|finding| finding.fix
+        == Some(SuggestedFix::SetAgencyId {
+            agency_id: AgencyId::from("DTA")
+        })));
+
+    assert_eq!(report.apply_fixes(&mut dataset), 4);
+    assert!(dataset.validation_report().is_valid());
+    assert_eq!(
+        dataset
+            .routes
+            .get(&RouteId::from("AB"))
+            .unwrap()
+            .route_short_name
+            .as_deref(),
+        Some("AB")
+    );
+}
+
+#[test]
+fn test_fingerprint() {
+    let dataset = load_dataset("good_feed");
+    let fingerprint = dataset.fingerprint().unwrap();
+    assert_eq!(fingerprint.len(), 64);
+    assert_eq!(
+        load_dataset("good_feed").fingerprint().unwrap(),
+        fingerprint
+    );
+
+    // Same content, with rows and columns in another order and times zero-padded.
+    assert_eq!(
+        load_dataset("reformatted_feed").fingerprint().unwrap(),
+        fingerprint
+    );
+
+    dataset
+        .stop_times
+        .get_mut(&(TripId::from("STBA"), 2))
+        .unwrap()
+        .stop_headsign = Some("Beatty".to_string());
+    assert_ne!(dataset.fingerprint().unwrap(), fingerprint);
+}
+
+#[test]
+fn test_trip_patterns() {
+    let dataset = load_dataset("good_feed");
+
+    let patterns = dataset.trip_patterns(&RouteId::from("AAMV"));
+    assert_eq!(patterns.len(), 2);
+    assert!(patterns.iter().all(|pattern| pattern.trip_count() == 2));
+    let outbound = patterns
+        .iter()
+        .find(|pattern| pattern.trip_headsign.as_deref() == Some("to Amargosa Valley"))
+        .unwrap();
+    assert_eq!(
+        outbound.trip_ids,
+        vec![TripId::from("AAMV1"), TripId::from("AAMV3")]
+    );
+    assert_eq!(
+        outbound.stop_ids,
+        vec![StopId::from("BEATTY_AIRPORT"), StopId::from("AMV")]
+    );
+
+    assert!(dataset.trip_patterns(&RouteId::from("UNKNOWN")).is_empty());
+}
+
+#[test]
+fn test_parse_report() {
+    assert!(load_dataset("good_feed").parse_report().is_empty());
+
+    let dataset = load_dataset("unrecognized_columns");
+    let report = dataset.parse_report();
+    assert_eq!(
+        report.unknown_columns_of(GtfsFile::Agency),
+        ["agency_lange"]
+    );
+    assert_eq!(
+        report.unknown_columns_of(GtfsFile::StopTimes),
+        ["drop_off_time", "shapedisttraveled"]
+    );
+    assert!(report.unknown_columns_of(GtfsFile::Shapes).is_empty());
+
+    let path = Path::new("tests/_data/unrecognized_columns");
+    let options = ParseOptions::default().deny_unknown_columns();
+    assert!(Dataset::from_csv_with_options(path, &options).is_err());
+}
+
+#[test]
+fn test_duplicate_keys() {
+    let dataset = load_dataset("duplicate_keys");
+    let key = (TripId::from("AB1"), 2);
+    assert_eq!(
+        dataset.parse_report().duplicate_keys,
+        [DuplicateKey {
+            file: GtfsFile::StopTimes,
+            key: "AB1, 2".to_string(),
+            previous_line: Some(15),
+            line: Some(30),
+        }]
+    );
+    assert_eq!(
+        dataset.stop_times.get(&key).unwrap().arrival_time,
+        Some(NaiveServiceTime::try_from("08:12:00").unwrap())
+    );
+
+    let path = Path::new("tests/_data/duplicate_keys");
+    let options = ParseOptions::default().duplicate_keys(DuplicateKeyPolicy::KeepFirst);
+    let dataset = Dataset::from_csv_with_options(path, &options).unwrap();
+    assert_eq!(dataset.parse_report().duplicate_keys.len(), 1);
+    assert_eq!(
+        dataset.stop_times.get(&key).unwrap().arrival_time,
+        Some(NaiveServiceTime::try_from("08:10:00").unwrap())
+    );
+
+    let options = ParseOptions::default().duplicate_keys(DuplicateKeyPolicy::Error);
+    let error = Dataset::from_csv_with_options(path, &options)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("lines 15 and 30"));
+}
+
+#[test]
+fn test_invalid_contacts() {
+    let path = Path::new("tests/_data/invalid_contacts");
+    assert!(Dataset::from_csv(path).is_err());
+
+    let options = ParseOptions::default().lenient_contacts();
+    let dataset = Dataset::from_csv_with_options(path, &options).unwrap();
+    // Files are parsed in no particular order.
+    let invalid_contacts = &dataset.parse_report().invalid_contacts;
+    assert_eq!(invalid_contacts.len(), 2);
+    assert!(invalid_contacts.contains(&InvalidContact {
+        file: GtfsFile::Agency,
+        column: "agency_phone".to_string(),
+        value: "call us".to_string(),
+        line: Some(2),
+    }));
+    assert!(invalid_contacts.contains(&InvalidContact {
+        file: GtfsFile::Stops,
+        column: "stop_url".to_string(),
+        value: "www.furnacecreek".to_string(),
+        line: Some(2),
+    }));
+    assert!(dataset.agencies[0].agency_phone.is_none());
+    let stop = dataset.stops.get(&StopId::from("FUR_CREEK_RES")).unwrap();
+    assert!(stop.stop_url.is_none());
+    let route = dataset.routes.get(&RouteId::from("AB")).unwrap();
+    assert_eq!(
+        route.route_url.as_ref().map(|url| url.as_str()),
+        Some("https://example.com/ab")
+    );
+}
+
+#[test]
+fn test_invalid_language_tag() {
+    let path = Path::new("tests/_data/invalid_language_tag");
+    let error = Dataset::from_csv(path).err().unwrap();
+    assert!(error
+        .to_string()
+        .contains("\"en_US\" is not a valid BCP 47 language tag"));
+}
+
+#[test]
+fn test_provenance() {
+    let path = Path::new("tests/_data/good_feed");
+    let options = ParseOptions::default().keep_source_lines();
+    let dataset = Dataset::from_csv_with_options(path, &options).unwrap();
+
+    let stop_time = dataset
+        .stop_times
+        .get(&(TripId::from("AB1"), 2))
+        .unwrap()
+        .clone();
+    assert_eq!(
+        dataset.provenance(&stop_time.into()),
+        Some(Provenance {
+            file: GtfsFile::StopTimes,
+            line: 15
+        })
+    );
+    let transfer = dataset.transfers[1].clone();
+    assert_eq!(
+        dataset.provenance(&transfer.into()).unwrap().to_string(),
+        "transfers.txt:3"
+    );
+    assert!(load_dataset("good_feed")
+        .provenance(&dataset.agencies[0].clone().into())
+        .is_none());
+
+    // Records are written in their original order.
+    let dir = temp_dir("provenance");
+    dataset.to_csv(&dir).unwrap();
+    let trip_ids = |path: &Path| -> Vec<String> {
+        let mut reader = csv::Reader::from_path(path.join("stop_times.txt")).unwrap();
+        reader
+            .records()
+            .map(|record| record.unwrap()[0].to_string())
+            .collect()
+    };
+    assert_eq!(trip_ids(&dir), trip_ids(path));
+}
+
+#[test]
+fn test_provenance_of_identical_records() {
+    let dir = temp_dir("provenance_of_identical_records");
+    load_dataset("good_feed").to_csv(&dir).unwrap();
+    std::fs::write(
+        dir.join("transfers.txt"),
+        "from_stop_id,to_stop_id,transfer_type,min_transfer_time\n\
+         NADAV,NANAA,3,\n\
+         EMSI,NANAA,2,1200\n\
+         EMSI,NANAA,2,1200\n",
+    )
+    .unwrap();
+    let options = ParseOptions::default().keep_source_lines();
+    let dataset = Dataset::from_csv_with_options(&dir, &options).unwrap();
+    let line = |provenance: Option<Provenance>| provenance.unwrap().line;
+
+    // Looked up by value, identical records resolve to the first of them.
+    let transfer = dataset.transfers[2].clone();
+    assert_eq!(line(dataset.provenance(&transfer.clone().into())), 3);
+    assert_eq!(line(dataset.listed_provenance(GtfsFile::Transfers, 2)), 4);
+    assert!(dataset.listed_provenance(GtfsFile::Transfers, 3).is_none());
+    assert!(dataset.listed_provenance(GtfsFile::Stops, 0).is_none());
+
+    // The records of an error are given their lines in turn.
+    let error = gtfs_schedule::error::Error::from(
+        gtfs_schedule::error::DatasetValidationError::new_invalid_combination(
+            vec!["from_stop_id".to_string(), "to_stop_id".to_string()],
+            None,
+            vec![transfer.clone().into(), transfer.into()],
+        ),
+    );
+    let lines: Vec<u64> = dataset
+        .error_provenance(&error)
+        .iter()
+        .map(|provenance| provenance.line)
+        .collect();
+    assert_eq!(lines, [3, 4]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_notice_report() {
+    assert!(load_dataset("good_feed").notice_report().is_valid());
+
+    let path = Path::new("tests/_data/duplicate_keys");
+    let options = ParseOptions::default().keep_source_lines();
+    let dataset = Dataset::from_csv_with_options(path, &options).unwrap();
+    let report = dataset.notice_report();
+    assert!(!report.is_valid());
+
+    let group = report.group("duplicate_key").unwrap();
+    assert_eq!(group.severity, NoticeSeverity::Error);
+    assert_eq!(group.total_notices, 1);
+    assert_eq!(group.sample_notices[0]["filename"], "stop_times.txt");
+    assert_eq!(group.sample_notices[0]["csvRowNumber"], 30);
+
+    let mut json = vec![];
+    report.to_json(&mut json).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json["notices"][0]["code"], "duplicate_key");
+    assert_eq!(json["notices"][0]["severity"], "ERROR");
+    assert_eq!(json["notices"][0]["totalNotices"], 1);
+
+    // A recommendation is only a warning, and every invalid record is reported.
+    let mut dataset = load_dataset("good_feed");
+    dataset.agencies[0].agency_id = None;
+    let report = dataset.notice_report();
+    assert!(report.is_valid());
+    let group = report.group("missing_recommended_field").unwrap();
+    assert_eq!(group.severity, NoticeSeverity::Warning);
+
+    for stop_id in ["BEATTY_AIRPORT", "BEATTY_AIRPORT_STATION"] {
+        let mut stop = dataset.stops.get_mut(&StopId::from(stop_id)).unwrap();
+        stop.stop_name = None;
+    }
+    let report = dataset.notice_report();
+    assert!(!report.is_valid());
+    let group = report.group("missing_required_field").unwrap();
+    assert_eq!(group.severity, NoticeSeverity::Error);
+    assert_eq!(group.total_notices, 2);
+}
+
+#[test]
+fn test_naive_service_time() {
+    let time = NaiveServiceTime::try_from("25:35:00").unwrap();
+    assert_eq!(String::from(time), "25:35:00");
+    assert_eq!(time.time_of_day().to_string(), "01:35:00");
+    assert!(time > NaiveServiceTime::try_from("23:59:59").unwrap());
+
+    let late = NaiveServiceTime::from_hms(49, 10, 0).unwrap();
+    assert_eq!(late.to_string(), "49:10:00");
+    assert_eq!(late.total_seconds(), 49 * 3600 + 600);
+    assert_eq!(late - time, Duration::from_secs(23 * 3600 + 35 * 60));
+    assert_eq!(time - late, Duration::ZERO);
+    assert_eq!(
+        late.duration_since(time),
+        Some(Duration::from_secs(23 * 3600 + 35 * 60))
+    );
+    assert_eq!(time.duration_since(late), None);
+    assert_eq!(time.duration_since(time), Some(Duration::ZERO));
+    assert_eq!(
+        time + Duration::from_secs(25 * 60),
+        NaiveServiceTime::from_hms(26, 0, 0).unwrap()
+    );
+
+    assert!(NaiveServiceTime::from_hms(8, 60, 0).is_none());
+    assert!(NaiveServiceTime::from_secs(u32::MAX)
+        .checked_add(Duration::from_secs(1))
+        .is_none());
+    assert!(NaiveServiceTime::try_from("12:00").is_err());
+}
+
+#[test]
+fn test_service_days() {
+    let dataset = load_dataset("good_feed");
+    let service_days = |service_id: &str| {
+        dataset
+            .calendar
+            .get(&CalendarServiceId::from(service_id))
+            .unwrap()
+            .service_days()
+    };
+    assert_eq!(service_days("WE"), ServiceDays::WEEKEND);
+    assert!(service_days("WE").weekend_only());
+    assert!(service_days("WE").runs_on(Weekday::Sun));
+    assert!(!service_days("FULLW").weekdays_only());
+
+    let mut calendar = dataset
+        .calendar
+        .get(&CalendarServiceId::from("FULLW"))
+        .unwrap()
+        .clone();
+    calendar.set_service_days(ServiceDays::WEEKDAYS);
+    assert!(!calendar.is_available_on(Weekday::Sat));
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.serialize(&calendar).unwrap();
+    let written = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    assert!(written.ends_with("FULLW,1,1,1,1,1,0,0,20070101,20251231\n"));
+}
+
+#[test]
+fn test_orphans() {
+    let mut dataset = load_dataset("good_feed");
+    assert!(dataset.find_orphans().is_empty());
+
+    dataset
+        .stop_times
+        .retain(|(trip_id, _), _| !trip_id.as_str().starts_with("AB"));
+    let orphans = dataset.find_orphans();
+    assert_eq!(orphans.trips, [TripId::from("AB1"), TripId::from("AB2")]);
+    assert!(orphans.routes.is_empty());
+
+    // The route of the trips is only orphaned once they are removed.
+    let removed = dataset.prune_orphans();
+    assert_eq!(removed.trips.len(), 2);
+    assert_eq!(removed.routes, [RouteId::from("AB")]);
+    assert!(dataset.find_orphans().is_empty());
+    assert!(dataset.validate().is_ok());
+}
+
+#[test]
+fn test_orphans_station_and_booking_service() {
+    let dataset = load_dataset("good_feed");
+    let platform = dataset
+        .stops
+        .get(&StopId::from("BEATTY_AIRPORT"))
+        .unwrap()
+        .clone();
+    // An entrance of the station and a boarding area of its platform are used along with it.
+    for (stop_id, location_type, parent_station) in [
+        (
+            "BEATTY_AIRPORT_ENTRANCE",
+            LocationType::EntranceOrExit,
+            "BEATTY_AIRPORT_STATION",
+        ),
+        (
+            "BEATTY_AIRPORT_BOARDING",
+            LocationType::BoardingArea,
+            "BEATTY_AIRPORT",
+        ),
+    ] {
+        let mut stop = platform.clone();
+        stop.stop_id = StopId::from(stop_id);
+        stop.location_type = Some(location_type);
+        stop.parent_station = Some(StopId::from(parent_station));
+        dataset.stops.insert(stop.stop_id.clone(), stop);
+    }
+    assert!(dataset.find_orphans().is_empty());
+
+    let mut calendar_date = dataset
+        .calendar_dates
+        .iter()
+        .next()
+        .unwrap()
+        .value()
+        .clone();
+    calendar_date.service_id = CalendarServiceId::from("BOOKING_DAYS");
+    dataset.calendar_dates.insert(
+        (calendar_date.service_id.clone(), calendar_date.date),
+        calendar_date,
+    );
+    assert_eq!(
+        dataset.find_orphans().services,
+        [CalendarServiceId::from("BOOKING_DAYS")]
+    );
+
+    // The service is used once a booking rule counts its days of notice.
+    let header = csv::StringRecord::from(vec![
+        "booking_rule_id",
+        "booking_type",
+        "prior_notice_last_day",
+        "prior_notice_last_time",
+        "prior_notice_service_id",
+    ]);
+    let record = csv::StringRecord::from(vec!["BR", "2", "1", "17:00:00", "BOOKING_DAYS"]);
+    let booking_rule: gtfs_schedule::schemas::BookingRule = parse_record(&header, &record).unwrap();
+    dataset
+        .booking_rules
+        .insert(BookingRuleId::from("BR"), booking_rule);
+    assert!(dataset.find_orphans().is_empty());
+}
+
+#[test]
+fn test_trip_times_validation() {
+    // The first stop time of AB2 has no times.
+    let error = load_dataset("missing_endpoint_times")
+        .validate()
+        .unwrap_err();
+    assert_eq!(error.notice_code(), "missing_required_field");
+    assert!(error.to_string().contains("AB2"));
+
+    let dataset = load_dataset("good_feed");
+    let time = |time: &str| NaiveServiceTime::try_from(time).unwrap();
+    // CITY1 departs from NANAA at 06:07:00.
+    dataset
+        .stop_times
+        .get_mut(&(TripId::from("CITY1"), 10))
+        .unwrap()
+        .arrival_time = Some(time("6:06:00"));
+    let error = dataset.validate().unwrap_err();
+    assert_eq!(error.notice_code(), "inconsistent_field_value");
+    assert!(error.to_string().contains("06:07:00"));
+}
+
+#[test]
+fn test_flex_window_validation() {
+    let dataset = load_dataset("good_feed");
+    let time = |time: &str| NaiveServiceTime::try_from(time).unwrap();
+    {
+        let mut stop_time = dataset
+            .stop_times
+            .get_mut(&(TripId::from("CITY1"), 10))
+            .unwrap();
+        stop_time.arrival_time = None;
+        stop_time.departure_time = None;
+        stop_time.start_pickup_drop_off_window = Some(time("6:00:00"));
+    }
+    let error = dataset.validate().unwrap_err();
+    assert_eq!(error.notice_code(), "missing_required_field");
+    assert!(error.to_string().contains("must be defined together"));
+
+    // The other stop times of CITY1 still have times.
+    dataset
+        .stop_times
+        .get_mut(&(TripId::from("CITY1"), 10))
+        .unwrap()
+        .end_pickup_drop_off_window = Some(time("6:10:00"));
+    let error = dataset.validate().unwrap_err();
+    assert_eq!(error.notice_code(), "invalid_field_combination");
+    assert!(error.to_string().contains("CITY1"));
+}
+
+#[test]
+fn test_transfer_consistency() {
+    let mut dataset = load_dataset("good_feed");
+    let transfer = Transfer {
+        from_stop_id: Some(StopId::from("BULLFROG")),
+        to_stop_id: Some(StopId::from("BULLFROG")),
+        from_route_id: Some(RouteId::from("BFC")),
+        to_route_id: Some(RouteId::from("BFC")),
+        from_trip_id: Some(TripId::from("AB1")),
+        to_trip_id: Some(TripId::from("BFC1")),
+        transfer_type: TransferType::RecommendedTransferPoint,
+        min_transfer_time: None,
+        #[cfg(feature = "extensions")]
+        extensions: Default::default(),
+    };
+    dataset.transfers.push(transfer.clone());
+    // AB1 does not stop at FUR_CREEK_RES.
+    dataset.transfers.push(Transfer {
+        from_stop_id: Some(StopId::from("FUR_CREEK_RES")),
+        from_route_id: None,
+        to_route_id: None,
+        ..transfer.clone()
+    });
+    assert!(dataset.validate().is_err());
+
+    let report = dataset.validation_report();
+    assert_eq!(report.findings.len(), 2);
+    assert!(report.findings.iter().any(|finding| finding.fix
+        == Some(SuggestedFix::SetTransferRouteIds {
+            transfer: Box::new(transfer.clone()),
+            from_route_id: Some(RouteId::from("AB")),
+            to_route_id: Some(RouteId::from("BFC")),
+        })));
+
+    assert_eq!(report.apply_fixes(&mut dataset), 2);
+    assert!(dataset.validate().is_ok());
+    assert_eq!(dataset.transfers.len(), 3);
+}
+
+#[test]
+fn test_frequency_validation() {
+    let dataset = load_dataset("good_feed");
+    let time = |time: &str| NaiveServiceTime::try_from(time).unwrap();
+    // 08:00:00 to 09:59:59, every 10 minutes.
+    let frequency = dataset
+        .frequencies
+        .get(&(TripId::from("CITY1"), time("8:00:00")))
+        .unwrap()
+        .clone();
+
+    let mut invalid = frequency.clone();
+    invalid.headway_secs = Duration::ZERO;
+    let error = invalid.validate().unwrap_err();
+    assert_eq!(error.notice_code(), "non_positive_headway");
+
+    let mut invalid = frequency.clone();
+    invalid.end_time = invalid.start_time;
+    let error = invalid.validate().unwrap_err();
+    assert_eq!(error.notice_code(), "start_and_end_range_out_of_order");
+
+    // The last trip may depart less than a headway before end_time, e.g. 06:00:00 to
+    // 06:25:00 every 10 minutes departs at 06:00, 06:10 and 06:20.
+    let mut exact = frequency.clone();
+    exact.exact_times = Some(ExactTimes::Exact);
+    exact.start_time = time("6:00:00");
+    exact.end_time = time("6:25:00");
+    assert!(exact.validate().is_ok());
+    assert_eq!(exact.departures().count(), 3);
+    exact.end_time = time("10:00:00");
+    assert!(exact.validate().is_ok());
+
+    let mut overlapping = frequency.clone();
+    overlapping.start_time = time("9:30:00");
+    overlapping.end_time = time("10:30:00");
+    dataset
+        .frequencies
+        .insert((TripId::from("CITY1"), time("9:30:00")), overlapping);
+    let error = dataset.validate().unwrap_err();
+    assert_eq!(error.notice_code(), "overlapping_frequency");
+}
+
+#[test]
+fn test_warnings() {
+    let dataset = load_dataset("good_feed");
+    let route_id = RouteId::from("AB");
+    {
+        let mut route = dataset.routes.get_mut(&route_id).unwrap();
+        route.route_color = Some("FFFF00".to_string());
+        route.route_text_color = Some("FFFFFF".to_string());
+        route.route_short_name = Some("Airport Express".to_string());
+    }
+
+    let warnings = dataset.warnings();
+    let route_warnings: Vec<_> = warnings
+        .iter()
+        .filter(|warning| warning.kind.to_string().contains("Route AB "))
+        .collect();
+    assert!(matches!(
+        route_warnings[0].kind,
+        WarningKind::LowColorContrast { .. }
+    ));
+    assert!(matches!(
+        route_warnings[1].kind,
+        WarningKind::LongRouteShortName { length: 15, .. }
+    ));
+    assert!(warnings.iter().any(|warning| warning.kind
+        == WarningKind::MissingTtsStopName {
+            stop_id: "EMSI".to_string(),
+            stop_name: "E Main St / S Irving St (Demo)".to_string(),
+        }));
+
+    let report = dataset.notice_report();
+    assert!(report.is_valid());
+    let group = report.group("route_color_contrast").unwrap();
+    assert_eq!(group.severity, NoticeSeverity::Warning);
+    assert_eq!(group.sample_notices[0]["routeId"], "AB");
+}
+
+#[test]
+fn test_validate_coordinates() {
+    let dataset = load_dataset("good_feed");
+    assert!(dataset.validate_coordinates(Some(100.0)).is_ok());
+
+    let stop_id = StopId::from("EMSI");
+    let (lat, lon) = {
+        let stop = dataset.stops.get(&stop_id).unwrap();
+        let coord = stop.stop_coord.as_ref().unwrap();
+        (coord.y, coord.x)
+    };
+    let set_coord = |lat: f64, lon: f64| {
+        let mut stop = dataset.stops.get_mut(&stop_id).unwrap();
+        let coord = stop.stop_coord.as_mut().unwrap();
+        (coord.y, coord.x) = (lat, lon);
+    };
+
+    set_coord(lon, lat);
+    let error = dataset.validate().unwrap_err().to_string();
+    assert!(error.contains("look swapped"));
+
+    set_coord(0.0, 0.0);
+    let error = dataset.validate().unwrap_err().to_string();
+    assert!(error.contains("(0, 0)"));
+
+    set_coord(lat + 2.0, lon);
+    assert!(dataset.validate().is_ok());
+    let error = dataset.validate_coordinates(Some(100.0)).unwrap_err();
+    assert_eq!(error.notice_code(), "suspicious_coordinate");
+    assert!(error.to_string().contains("more than 100 km"));
+}
+
+#[test]
+fn test_normalize_calendars() {
+    let mut dataset = load_dataset("good_feed");
+    let fullw = CalendarServiceId::from("FULLW");
+    let we = CalendarServiceId::from("WE");
+    let dates = |dataset: &Dataset| (dataset.service_dates(&fullw), dataset.service_dates(&we));
+    let expected = dates(&dataset);
+    assert_eq!(expected.0.len(), 6939);
+    assert!(!expected
+        .0
+        .contains(&NaiveDate::from_ymd_opt(2007, 6, 4).unwrap()));
+    assert_eq!(
+        dataset.calendar.get(&we).unwrap().dates_in_range(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()
+                ..NaiveDate::from_ymd_opt(2024, 1, 8).unwrap()
+        ),
+        [
+            NaiveDate::from_ymd_opt(2024, 1, 6).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 7).unwrap()
+        ]
+    );
+
+    dataset.normalize_calendars(CalendarForm::ExplicitDates);
+    assert!(dataset.calendar.is_empty());
+    assert_eq!(dataset.calendar_dates.len(), 6939 + expected.1.len());
+    assert_eq!(dates(&dataset), expected);
+
+    dataset.normalize_calendars(CalendarForm::WeeklyPatterns);
+    assert_eq!(dates(&dataset), expected);
+    assert_eq!(dataset.calendar_dates.len(), 1);
+    assert_eq!(
+        dataset.calendar.get(&we).unwrap().start_date,
+        NaiveDate::from_ymd_opt(2007, 1, 6).unwrap()
+    );
+    assert!(dataset.validate().is_ok());
+}
+
+#[test]
+fn test_schedule_transformations() {
+    let mut dataset = load_dataset("good_feed");
+    let time = |time: &str| Some(NaiveServiceTime::try_from(time).unwrap());
+
+    let trip_ids = [TripId::from("CITY1"), TripId::from("STBA")];
+    dataset
+        .shift_trip_times(&trip_ids, chrono::Duration::minutes(-30))
+        .unwrap();
+    let stop_time = dataset.stop_times.get(&(TripId::from("CITY1"), 5)).unwrap();
+    assert_eq!(stop_time.arrival_time, time("5:35:00"));
+    assert_eq!(stop_time.departure_time, time("5:37:00"));
+    drop(stop_time);
+    assert!(dataset
+        .frequencies
+        .iter()
+        .any(|frequency| frequency.trip_id == trip_ids[1]
+            && frequency.start_time == time("5:30:00").unwrap()));
+    assert!(dataset
+        .shift_trip_times(&trip_ids, chrono::Duration::hours(-6))
+        .is_err());
+    assert_eq!(
+        dataset
+            .stop_times
+            .get(&(TripId::from("CITY1"), 5))
+            .unwrap()
+            .arrival_time,
+        time("5:35:00")
+    );
+    // Times may be shifted past midnight.
+    dataset
+        .shift_trip_times(&trip_ids, chrono::Duration::hours(3))
+        .unwrap();
+    assert!(dataset
+        .frequencies
+        .iter()
+        .any(|frequency| frequency.end_time == time("24:30:00").unwrap()));
+    dataset
+        .shift_trip_times(&trip_ids, chrono::Duration::hours(-3))
+        .unwrap();
+
+    let fullw = CalendarServiceId::from("FULLW");
+    let daily = CalendarServiceId::from("DAILY");
+    assert!(dataset
+        .remap_service(&fullw, CalendarServiceId::from("WE"))
+        .is_err());
+    dataset.remap_service(&fullw, daily.clone()).unwrap();
+    assert!(dataset.calendar.contains_key(&daily));
+    assert!(dataset
+        .calendar_dates
+        .contains_key(&(daily.clone(), NaiveDate::from_ymd_opt(2007, 6, 4).unwrap())));
+    assert!(dataset.trips.iter().all(|trip| trip.service_id != fullw));
+
+    let end_date = NaiveDate::from_ymd_opt(2026, 12, 31).unwrap();
+    assert_eq!(dataset.extend_calendar(end_date), 2);
+    assert!(dataset.is_service_active(&daily, end_date));
+    assert!(dataset.validate().is_ok());
+}
+
+#[test]
+fn test_pickup_drop_off_windows() {
+    let dataset = load_dataset("good_feed");
+    let time = |time: &str| Some(NaiveServiceTime::try_from(time).unwrap());
+    let key = (TripId::from("CITY1"), 5);
+
+    let mut stop_time = dataset.stop_times.get(&key).unwrap().clone();
+    stop_time.arrival_time = None;
+    stop_time.departure_time = None;
+    stop_time.start_pickup_drop_off_window = time("8:00:00");
+    assert!(stop_time.clone().validate().is_err());
+    stop_time.end_pickup_drop_off_window = time("7:00:00");
+    assert!(stop_time.clone().validate().is_err());
+    stop_time.end_pickup_drop_off_window = time("25:00:00");
+    assert!(stop_time.validate().is_ok());
+
+    stop_time.pickup_booking_rule_id = Some(BookingRuleId::from("UNKNOWN"));
+    dataset.stop_times.insert(key.clone(), stop_time);
+    assert!(dataset.validate().is_err());
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("gtfs-schedule-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_to_csv() {
+    let dataset = load_dataset("good_feed");
+    let dir = temp_dir("to_csv");
+
+    dataset.to_csv(&dir).unwrap();
+    let written = Dataset::from_csv(&dir).unwrap();
+    assert_eq!(
+        written.fingerprint().unwrap(),
+        dataset.fingerprint().unwrap()
+    );
+    assert!(written.validate().is_ok());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_file_name_matching() {
+    assert_eq!(
+        GtfsFile::from_file_name("stop_areas.txt"),
+        Some(GtfsFile::StopsAreas)
+    );
+    assert_eq!(
+        GtfsFile::from_file_name("Fare_Transfer_Rules.TXT"),
+        Some(GtfsFile::FareTransfers)
+    );
+    assert_eq!(GtfsFile::from_file_name("stops.csv"), None);
+
+    let dataset = load_dataset("good_feed");
+    let dir = temp_dir("file_name_matching");
+    dataset.to_csv(&dir).unwrap();
+    std::fs::rename(dir.join("stops.txt"), dir.join("Stops.txt")).unwrap();
+    // The file named as in the specification is preferred.
+    std::fs::write(dir.join("STOP_TIMES.TXT"), "trip_id,stop_sequence\n").unwrap();
+
+    let written = Dataset::from_csv(&dir).unwrap();
+    assert_eq!(
+        written.fingerprint().unwrap(),
+        dataset.fingerprint().unwrap()
+    );
+    let renamed_files = &written.parse_report().renamed_files;
+    assert_eq!(renamed_files.len(), 1);
+    assert_eq!(renamed_files[&GtfsFile::Stops], "Stops.txt");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_attribution_roles() {
+    let dir = temp_dir("attribution_roles");
+    load_dataset("good_feed").to_csv(&dir).unwrap();
+    std::fs::write(
+        dir.join("attributions.txt"),
+        "attribution_id,route_id,organization_name,is_producer,is_operator,is_authority\n\
+         PRODUCER,,Producer,1,,\n\
+         OPERATOR,AB,Operator,0,1,0\n",
+    )
+    .unwrap();
+    let mut dataset = Dataset::from_csv(&dir).unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+    assert!(dataset.validate().is_ok());
+    let attribution = dataset
+        .attributions
+        .iter()
+        .find(|attribution| attribution.route_id.is_some())
+        .unwrap();
+    assert!(!attribution.is_producer && attribution.is_operator && !attribution.is_authority);
+
+    let index = dataset
+        .attributions
+        .iter()
+        .position(|attribution| attribution.route_id.is_some())
+        .unwrap();
+    dataset.attributions[index].is_operator = false;
+    let error = dataset.validate().unwrap_err();
+    assert_eq!(error.notice_code(), "missing_required_field");
+
+    dataset.attributions[index].is_operator = true;
+    dataset.attributions[index].trip_id = Some(TripId::from("AB1"));
+    let error = dataset.validate().unwrap_err();
+    assert_eq!(error.notice_code(), "forbidden_field_value");
+}
+
+#[test]
+fn test_deterministic_csv() {
+    let read = |dir: &Path, file_name: &str| std::fs::read_to_string(dir.join(file_name)).unwrap();
+    let dir_a = temp_dir("deterministic_csv_a");
+    let dir_b = temp_dir("deterministic_csv_b");
+
+    let options = WriteOptions::default()
+        .row_order(RowOrder::Key)
+        .all_columns();
+    load_dataset("good_feed")
+        .to_csv_with_options(&dir_a, &options)
+        .unwrap();
+    load_dataset("good_feed")
+        .to_csv_with_options(&dir_b, &options)
+        .unwrap();
+    for file_name in [
+        "stops.txt",
+        "stop_times.txt",
+        "trips.txt",
+        "calendar_dates.txt",
+    ] {
+        assert_eq!(read(&dir_a, file_name), read(&dir_b, file_name));
+    }
+    let stops = read(&dir_a, "stops.txt");
+    assert!(stops.starts_with(
+        "stop_id,stop_code,stop_name,tts_stop_name,stop_desc,stop_lat,stop_lon,zone_id,"
+    ));
+    assert!(stops.lines().nth(1).unwrap().starts_with("AMV,"));
+    let stop_times = read(&dir_a, "stop_times.txt");
+    let sequences: Vec<&str> = stop_times
+        .lines()
+        .skip(1)
+        .filter(|line| line.starts_with("CITY1,"))
+        .map(|line| line.split(',').nth(6).unwrap())
+        .collect();
+    assert_eq!(sequences, ["0", "5", "10", "15", "20"]);
+
+    let mut dataset = load_dataset("good_feed");
+    let mut shuffled = load_dataset("good_feed");
+    shuffled.transfers.reverse();
+    shuffled.transfers.push(shuffled.transfers[0].clone());
+    dataset.canonicalize();
+    shuffled.canonicalize();
+    assert_eq!(shuffled.transfers.len(), 2);
+    dataset.to_csv(&dir_a).unwrap();
+    shuffled.to_csv(&dir_b).unwrap();
+    for file_name in ["transfers.txt", "stops.txt", "stop_times.txt"] {
+        assert_eq!(read(&dir_a, file_name), read(&dir_b, file_name));
+    }
+    assert!(read(&dir_a, "transfers.txt")
+        .lines()
+        .nth(1)
+        .unwrap()
+        .starts_with("EMSI,"));
+
+    std::fs::remove_dir_all(dir_a).unwrap();
+    std::fs::remove_dir_all(dir_b).unwrap();
+}
+
+#[test]
+fn test_fare_money() {
+    let dataset = load_dataset("good_feed");
+    let mut fare_attribute = dataset
+        .fare_attributes
+        .get(&FareId::from("p"))
+        .unwrap()
+        .clone();
+
+    let money = fare_attribute.money();
+    assert_eq!(money.amount, Decimal::new(125, 2));
+    assert_eq!(money.minor_units(), Some(125));
+    assert_eq!(money.to_string(), "1.25 USD");
+
+    let yens = Money::new(Decimal::new(500, 0), Currency::JPY);
+    assert_eq!(yens.minor_units(), Some(500));
+    assert_eq!(yens.to_string(), "500 JPY");
+    assert!(!Money::new(Decimal::new(5005, 1), Currency::JPY).has_valid_precision());
+    assert_eq!(
+        Money::new(Decimal::new(2, 0), Currency::USD).to_string(),
+        "2.00 USD"
+    );
+
+    fare_attribute.currency_type = Currency::JPY;
+    assert!(fare_attribute.validate().is_err());
+
+    let dir = temp_dir("fare_money");
+    dataset.to_csv(&dir).unwrap();
+    std::fs::write(dir.join("networks.txt"), "network_id\nN\n").unwrap();
+    std::fs::write(
+        dir.join("fare_products.txt"),
+        "fare_product_id,amount,currency\nSINGLE,2.50,USD\nDAY,9,EUR\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("fare_leg_rules.txt"),
+        "network_id,fare_product_id\nN,SINGLE\nN,DAY\n",
+    )
+    .unwrap();
+    let dataset = Dataset::from_csv(&dir).unwrap();
+    assert!(dataset.validate().is_err());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// Builds a leg riding a whole trip of the good_feed dataset.
+fn fare_leg(dataset: &Dataset, trip_id: &str) -> FareLeg {
+    let mut stop_times: Vec<_> = dataset
+        .iter_stop_times_for_trip(&TripId::from(trip_id))
+        .map(|stop_time| stop_time.clone())
+        .collect();
+    stop_times.sort_by_key(|stop_time| stop_time.stop_sequence);
+    let (first, last) = (&stop_times[0], &stop_times[stop_times.len() - 1]);
+    FareLeg {
+        trip_id: TripId::from(trip_id),
+        date: NaiveDate::from_ymd_opt(2007, 6, 4).unwrap(),
+        from_stop_id: first.stop_id.clone().unwrap(),
+        to_stop_id: last.stop_id.clone().unwrap(),
+        departure_time: first.departure_time.unwrap(),
+        arrival_time: last.arrival_time.unwrap(),
+    }
+}
+
+#[test]
+fn test_fare_for_itinerary() {
+    let dataset = load_dataset("good_feed");
+    let leg = |trip_id: &str| fare_leg(&dataset, trip_id);
+    let itinerary = [leg("AB1"), leg("BFC1")];
+
+    let options = dataset.fare_for_itinerary(&itinerary);
+    assert_eq!(options.len(), 1);
+    assert_eq!(options[0].tickets.len(), 2);
+    assert_eq!(options[0].total.to_string(), "2.50 USD");
+
+    dataset
+        .fare_attributes
+        .get_mut(&FareId::from("p"))
+        .unwrap()
+        .transfers = FareTransfers::OneTransfer;
+    let options = dataset.fare_for_itinerary(&itinerary);
+    assert_eq!(options[0].tickets.len(), 1);
+    assert_eq!(options[0].tickets[0].legs, 0..2);
+    assert_eq!(options[0].total.to_string(), "1.25 USD");
+
+    assert!(dataset.fare_for_itinerary(&[leg("CITY1")]).is_empty());
+}
+
+#[test]
+fn test_fare_rules_matched_per_row() {
+    let mut dataset = load_dataset("good_feed");
+    for (stop_id, zone_id) in [("BEATTY_AIRPORT", "A"), ("BULLFROG", "B")] {
+        dataset
+            .stops
+            .get_mut(&StopId::from(stop_id))
+            .unwrap()
+            .zone_id = Some(zone_id.into());
+    }
+    // AB1 rides AB from zone A to zone B, which only the rule of STBA goes from and to.
+    let mut ab = dataset.fare_rules[0].clone();
+    ab.origin_id = Some("C".into());
+    ab.destination_id = Some("D".into());
+    let mut stba = dataset.fare_rules[1].clone();
+    stba.origin_id = Some("A".into());
+    stba.destination_id = Some("B".into());
+    dataset
+        .fare_rules
+        .retain(|fare_rule| fare_rule.fare_id != FareId::from("p"));
+    dataset.fare_rules.extend([ab, stba]);
+    assert!(dataset
+        .fare_for_itinerary(&[fare_leg(&dataset, "AB1")])
+        .is_empty());
+
+    // The transfer duration counts the days between the legs.
+    let dataset = load_dataset("good_feed");
+    dataset
+        .fare_attributes
+        .get_mut(&FareId::from("p"))
+        .unwrap()
+        .transfers = FareTransfers::OneTransfer;
+    dataset
+        .fare_attributes
+        .get_mut(&FareId::from("p"))
+        .unwrap()
+        .transfer_duration = Some(Duration::from_secs(3600));
+    let mut next_day = fare_leg(&dataset, "BFC1");
+    next_day.date = next_day.date.succ_opt().unwrap();
+    let options = dataset.fare_for_itinerary(&[fare_leg(&dataset, "AB1"), next_day]);
+    assert_eq!(options[0].tickets.len(), 2);
+    let options =
+        dataset.fare_for_itinerary(&[fare_leg(&dataset, "AB1"), fare_leg(&dataset, "BFC1")]);
+    assert_eq!(options[0].tickets.len(), 1);
+}
+
+#[test]
+fn test_journey_fares() {
+    let dir = temp_dir("journey_fares");
+    load_dataset("good_feed").to_csv(&dir).unwrap();
+    for (file_name, content) in [
+        ("areas.txt", "area_id\nAIRPORT\n"),
+        ("stops_areas.txt", "area_id,stop_id\nAIRPORT,BEATTY_AIRPORT\n"),
+        (
+            "fare_products.txt",
+            "fare_product_id,amount,currency\nSINGLE,2.00,USD\nAIRPORT,5.00,USD\nTRANSFER,0.50,USD\n",
+        ),
+        (
+            "fare_leg_rules.txt",
+            "leg_group_id,from_area_id,fare_product_id\nFROM_AIRPORT,AIRPORT,AIRPORT\nLOCAL,,SINGLE\n",
+        ),
+        (
+            "fare_transfers.txt",
+            "from_leg_group_id,to_leg_group_id,fare_transfer_type,fare_product_id\n\
+             FROM_AIRPORT,LOCAL,0,TRANSFER\n",
+        ),
+    ] {
+        std::fs::write(dir.join(file_name), content).unwrap();
+    }
+    let dataset = Dataset::from_csv(&dir).unwrap();
+    let leg = |trip_id: &str| fare_leg(&dataset, trip_id);
+
+    let fares = dataset.journey_fares(&[leg("BFC1")]);
+    assert_eq!(fares.len(), 1);
+    assert_eq!(fares[0].total.to_string(), "2.00 USD");
+
+    let fares = dataset.journey_fares(&[leg("AB1"), leg("BFC1")]);
+    assert_eq!(fares[0].total.to_string(), "5.50 USD");
+    let purchases: Vec<_> = fares[0]
+        .purchases
+        .iter()
+        .map(|purchase| {
+            (
+                purchase.fare_product_id.as_str(),
+                purchase.leg,
+                purchase.transfer,
+            )
+        })
+        .collect();
+    assert_eq!(purchases, [("AIRPORT", 0, false), ("TRANSFER", 1, true)]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_area_lookups() {
+    let dir = temp_dir("area_lookups");
+    load_dataset("good_feed").to_csv(&dir).unwrap();
+    std::fs::write(dir.join("areas.txt"), "area_id\nAIRPORT\nTOWN\n").unwrap();
+    std::fs::write(
+        dir.join("stops_areas.txt"),
+        "area_id,stop_id\nAIRPORT,BEATTY_AIRPORT_STATION\nTOWN,BULLFROG\nTOWN,BEATTY_AIRPORT\n",
+    )
+    .unwrap();
+    let dataset = Dataset::from_csv(&dir).unwrap();
+    dataset
+        .stops
+        .get_mut(&StopId::from("BULLFROG"))
+        .unwrap()
+        .zone_id = Some("1".into());
+
+    let airport = StopId::from("BEATTY_AIRPORT");
+    let areas = [AreaId::from("AIRPORT"), AreaId::from("TOWN")];
+    assert_eq!(dataset.areas_for_stop(&airport), areas);
+    assert!(dataset.areas_for_stop(&StopId::from("AMV")).is_empty());
+    assert_eq!(
+        dataset.stops_in_area(&AreaId::from("AIRPORT")),
+        [airport.clone(), StopId::from("BEATTY_AIRPORT_STATION")]
+    );
+    assert_eq!(
+        dataset.zone_for_stop(&StopId::from("BULLFROG")),
+        Some("1".into())
+    );
+    assert_eq!(dataset.zone_for_stop(&airport), None);
+
+    let index = dataset.area_index();
+    assert_eq!(index.areas_for_stop(&airport), areas);
+    assert_eq!(
+        index.stops_in_area(&AreaId::from("TOWN")),
+        [airport, StopId::from("BULLFROG")]
+    );
+    assert_eq!(
+        index
+            .zone_for_stop(&StopId::from("BULLFROG"))
+            .map(|zone| zone.as_str()),
+        Some("1")
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_route_networks() {
+    let dataset = load_dataset("good_feed");
+    assert_eq!(dataset.network_of_route(&RouteId::from("AB")), None);
+    dataset
+        .routes
+        .get_mut(&RouteId::from("AB"))
+        .unwrap()
+        .network_id = Some("REGIONAL".into());
+    assert_eq!(
+        dataset.network_of_route(&RouteId::from("AB")),
+        Some(NetworkId::from("REGIONAL"))
+    );
+    assert_eq!(
+        dataset.routes_in_network(&NetworkId::from("REGIONAL")),
+        [RouteId::from("AB")]
+    );
+
+    let dir = temp_dir("route_networks");
+    load_dataset("good_feed").to_csv(&dir).unwrap();
+    std::fs::write(dir.join("networks.txt"), "network_id\nLOCAL\n").unwrap();
+    std::fs::write(
+        dir.join("routes_networks.txt"),
+        "network_id,route_id\nLOCAL,BFC\nLOCAL,AB\n",
+    )
+    .unwrap();
+    let dataset = Dataset::from_csv(&dir).unwrap();
+    assert_eq!(
+        dataset.network_of_route(&RouteId::from("BFC")),
+        Some(NetworkId::from("LOCAL"))
+    );
+    assert_eq!(dataset.network_of_route(&RouteId::from("STBA")), None);
+    assert_eq!(
+        dataset.routes_in_network(&NetworkId::from("LOCAL")),
+        [RouteId::from("AB"), RouteId::from("BFC")]
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_compile_timetable() {
+    let dataset = load_dataset("good_feed");
+    let timetable = dataset.compile_timetable(
+        NaiveDate::from_ymd_opt(2007, 6, 5).unwrap()..=NaiveDate::from_ymd_opt(2007, 6, 6).unwrap(),
+    );
+    assert!(timetable
+        .connections
+        .windows(2)
+        .all(|pair| pair[0].departure_time <= pair[1].departure_time));
+
+    let beatty_airport = timetable
+        .stop_index(&StopId::from("BEATTY_AIRPORT"))
+        .unwrap();
+    let bullfrog = timetable.stop_index(&StopId::from("BULLFROG")).unwrap();
+    let ab1: Vec<_> = timetable
+        .connections
+        .iter()
+        .filter(|connection| {
+            timetable.trips[connection.trip as usize].trip_id == TripId::from("AB1")
+        })
+        .collect();
+    assert_eq!(ab1.len(), 2);
+    assert_eq!(
+        (ab1[0].departure_stop, ab1[0].arrival_stop),
+        (beatty_airport, bullfrog)
+    );
+    assert_eq!(
+        (ab1[0].departure_time, ab1[0].arrival_time),
+        (8 * 3600, 8 * 3600 + 600)
+    );
+    assert_eq!(ab1[1].departure_time, 86400 + 8 * 3600);
+
+    // STBA runs every 30 minutes from 6:00 to 22:00, on both days.
+    let stba = timetable
+        .trips
+        .iter()
+        .filter(|trip| trip.trip_id == TripId::from("STBA"))
+        .count();
+    assert_eq!(stba, 64);
+    let route = timetable
+        .routes
+        .iter()
+        .find(|route| route.route_id == RouteId::from("STBA"))
+        .unwrap();
+    assert_eq!(route.trips.len(), 64);
+    assert_eq!(timetable.route_stops[route.stops.clone()].len(), 2);
+    assert_eq!(
+        timetable.route_stop_time(route, 1, 1).arrival_time,
+        6 * 3600 + 1800 + 1200
+    );
+    assert!(timetable.routes_by_stop[beatty_airport as usize].len() >= 2);
+
+    // With STBA arriving at its first stop 5 minutes before it departs, the run departing at
+    // midnight on the first day would arrive there before the day starts.
+    let time = |time: &str| NaiveServiceTime::try_from(time).unwrap();
+    dataset
+        .stop_times
+        .get_mut(&(TripId::from("STBA"), 0))
+        .unwrap()
+        .arrival_time = Some(time("5:55:00"));
+    let stba = TripId::from("STBA");
+    let (_, mut frequency) = dataset
+        .frequencies
+        .remove(&(stba.clone(), time("6:00:00")))
+        .unwrap();
+    frequency.start_time = time("0:00:00");
+    frequency.end_time = time("1:00:00");
+    dataset
+        .frequencies
+        .insert((stba.clone(), time("0:00:00")), frequency);
+    let timetable = dataset.compile_timetable(
+        NaiveDate::from_ymd_opt(2007, 6, 5).unwrap()..=NaiveDate::from_ymd_opt(2007, 6, 6).unwrap(),
+    );
+    let departures: Vec<u32> = timetable
+        .trips
+        .iter()
+        .filter(|trip| trip.trip_id == stba)
+        .map(|trip| trip.departure_time)
+        .collect();
+    assert_eq!(departures, [1800, 86400, 86400 + 1800]);
+}
+
+#[test]
+fn test_scrub() {
+    let dir = temp_dir("scrub");
+    load_dataset("good_feed").to_csv(&dir).unwrap();
+    std::fs::write(
+        dir.join("attributions.txt"),
+        "attribution_id,agency_id,organization_name,attribution_url,attribution_email\n\
+         DATA,DTA,Transit Data Inc.,https://data.example.org/team,jane.doe@data.example.org\n",
+    )
+    .unwrap();
+
+    let mut dataset = Dataset::from_csv(&dir).unwrap();
+    dataset.scrub(&ScrubOptions::default()).unwrap();
+    assert_eq!(dataset.attributions[0].attribution_url, None);
+    assert_eq!(dataset.attributions[0].attribution_email, None);
+
+    let options = ScrubOptions::default().contacts(ContactScrub::Hash {
+        salt: "fixtures".to_string(),
+    });
+    let mut dataset = Dataset::from_csv(&dir).unwrap();
+    dataset.scrub(&options).unwrap();
+    let mut other = Dataset::from_csv(&dir).unwrap();
+    other.scrub(&options).unwrap();
+    let attribution = &dataset.attributions[0];
+    let url = attribution.attribution_url.as_ref().unwrap();
+    assert_eq!(url.host_str(), Some("example.com"));
+    assert!(attribution
+        .attribution_email
+        .as_ref()
+        .unwrap()
+        .as_str()
+        .ends_with("@example.com"));
+    assert_eq!(other.attributions[0].attribution_url.as_ref(), Some(url));
+
+    let mut dataset = load_dataset("good_feed");
+    let trips = dataset.trips.len();
+    dataset.scrub(&ScrubOptions::default().rekey_ids()).unwrap();
+    assert!(dataset.validate().is_ok());
+    assert_eq!(dataset.trips.len(), trips);
+    assert!(dataset.stops.contains_key(&StopId::from("stop_1")));
+    assert!(!dataset.stops.contains_key(&StopId::from("BEATTY_AIRPORT")));
+    assert!(dataset
+        .trips
+        .iter()
+        .all(|trip| trip.service_id.starts_with("service_")));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_revalidate_dirty() {
+    let mut dataset = load_dataset("good_feed");
+    assert!(!dataset.has_dirty_records());
+
+    let mut stop_time = dataset
+        .stop_times
+        .get(&(TripId::from("AB1"), 2))
+        .unwrap()
+        .clone();
+    stop_time.stop_id = Some(StopId::from("AIRPORT_SHUTTLE_STOP"));
+    let mut editor = dataset.begin_edit();
+    assert!(editor.upsert(stop_time).is_some());
+    assert!(dataset.has_dirty_records());
+    let error = dataset.revalidate_dirty().unwrap_err();
+    assert_eq!(error.notice_code(), "foreign_key_violation");
+    assert!(dataset.has_dirty_records());
+
+    let mut stop = dataset
+        .stops
+        .get(&StopId::from("BULLFROG"))
+        .unwrap()
+        .clone();
+    stop.stop_id = StopId::from("AIRPORT_SHUTTLE_STOP");
+    dataset.begin_edit().upsert(stop);
+    assert!(dataset.revalidate_dirty().is_ok());
+    assert!(!dataset.has_dirty_records());
+
+    // Stop times of other trips still reference the removed stop.
+    let bullfrog = dataset
+        .stops
+        .get(&StopId::from("BULLFROG"))
+        .unwrap()
+        .clone();
+    assert!(dataset.begin_edit().remove(&bullfrog.into()).is_some());
+    let error = dataset.revalidate_dirty().unwrap_err();
+    assert_eq!(error.notice_code(), "foreign_key_violation");
+    assert!(dataset.validate().is_err());
+}
+
+#[test]
+fn test_booking_rule_durations() {
+    let dir = temp_dir("booking_rule_durations");
+    load_dataset("good_feed").to_csv(&dir).unwrap();
+    std::fs::write(
+        dir.join("booking_rules.txt"),
+        "booking_rule_id,booking_type,prior_notice_duration_min,prior_notice_duration_max\n\
+         SAME_DAY,1,30,120\n",
+    )
+    .unwrap();
+
+    let dataset = Dataset::from_csv(&dir).unwrap();
+    let mut booking_rule = dataset
+        .booking_rules
+        .get(&BookingRuleId::from("SAME_DAY"))
+        .unwrap()
+        .clone();
+    assert_eq!(
+        booking_rule.prior_notice_duration_min,
+        Some(std::time::Duration::from_secs(30 * 60))
+    );
+    assert_eq!(
+        booking_rule.prior_notice_duration_max,
+        Some(std::time::Duration::from_secs(120 * 60))
+    );
+    assert!(booking_rule.validate().is_ok());
+
+    dataset.to_csv(&dir).unwrap();
+    let written = std::fs::read_to_string(dir.join("booking_rules.txt")).unwrap();
+    assert!(written.contains("SAME_DAY,1,30,120"));
+
+    booking_rule.prior_notice_duration_max = Some(std::time::Duration::from_secs(10 * 60));
+    assert!(booking_rule.validate().is_err());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "extensions")]
+#[test]
+fn test_to_csv_extensions() {
+    use gtfs_schedule::schemas::Extensions;
+
+    let dataset = load_dataset("unrecognized_columns");
+    assert_eq!(
+        dataset.agencies[0].extensions().get("agency_lange"),
+        Some(&"en".to_string())
+    );
+
+    let dir = temp_dir("to_csv_extensions");
+    dataset.to_csv(&dir).unwrap();
+    let written = Dataset::from_csv(&dir).unwrap();
+    assert_eq!(
+        written.agencies[0].extensions().get("agency_lange"),
+        Some(&"en".to_string())
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_json() {
+    let dataset = load_dataset("good_feed");
+
+    let mut json = vec![];
+    dataset.to_json(&mut json).unwrap();
+    let document = String::from_utf8(json.clone()).unwrap();
+    assert!(document.contains(r#""start_date":"2007-01-01""#));
+    assert!(document.contains(r#""arrival_time":"06:00:00""#));
+
+    let read = Dataset::from_json(json.as_slice()).unwrap();
+    assert_eq!(read.fingerprint().unwrap(), dataset.fingerprint().unwrap());
+
+    assert!(Dataset::from_json(r#"{"unknown": []}"#.as_bytes()).is_err());
+
+    let mut ndjson = vec![];
+    dataset.to_ndjson(GtfsFile::Stops, &mut ndjson).unwrap();
+    let stops: Vec<serde_json::Value> = String::from_utf8(ndjson)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(stops.len(), dataset.stops.len());
+    let stop = stops
+        .iter()
+        .find(|stop| stop["stop_id"] == "FUR_CREEK_RES")
+        .unwrap();
+    assert_eq!(stop["stop_lat"], 36.425288);
+    assert_eq!(stop["stop_code"], "1234");
+    assert!(stop.get("stop_desc").is_none());
+
+    let mut ndjson = vec![];
+    dataset.to_ndjson(GtfsFile::Levels, &mut ndjson).unwrap();
+    assert!(ndjson.is_empty());
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_record_batches() {
+    use arrow::datatypes::DataType;
+    use arrow::record_batch::RecordBatchReader;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    let dataset = load_dataset("good_feed");
+
+    let batches: Vec<_> = dataset
+        .record_batches(GtfsFile::StopTimes, 10)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(batches.len() > 1);
+    assert!(batches.iter().all(|batch| batch.num_rows() <= 10));
+    assert_eq!(
+        batches.iter().map(|batch| batch.num_rows()).sum::<usize>(),
+        dataset.stop_times.len()
+    );
+    let schema = batches[0].schema();
+    assert_eq!(
+        schema.field_with_name("stop_sequence").unwrap().data_type(),
+        &DataType::Int64
+    );
+    assert_eq!(
+        schema.field_with_name("arrival_time").unwrap().data_type(),
+        &DataType::Utf8
+    );
+
+    let calendar = dataset.record_batches(GtfsFile::Calendar, 1024);
+    assert_eq!(
+        calendar
+            .schema()
+            .field_with_name("start_date")
+            .unwrap()
+            .data_type(),
+        &DataType::Date32
+    );
+
+    let dir = temp_dir("to_parquet");
+    dataset.to_parquet(&dir).unwrap();
+    let file = std::fs::File::open(dir.join("stop_times.parquet")).unwrap();
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+        .unwrap()
+        .build()
+        .unwrap();
+    let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+    assert_eq!(rows, dataset.stop_times.len());
+    assert!(!dir.join("shapes.parquet").exists());
+}
+
+#[test]
+fn test_memory_stats() {
+    let dataset = load_dataset("good_feed");
+    let stats = dataset.memory_stats();
+
+    assert!(stats.records > dataset.stop_times.len());
+    assert!(stats.interned_ids > 0);
+    assert!(stats.id_references > stats.interned_ids);
+    assert!(stats.saved_bytes > 0);
+
+    // Every stop time shares the identifier of its trip.
+    let trip = dataset.trips.get(&TripId::from("AB1")).unwrap();
+    let stop_time = dataset.stop_times.get(&(TripId::from("AB1"), 1)).unwrap();
+    assert!(std::ptr::eq(
+        trip.trip_id.as_str(),
+        stop_time.trip_id.as_str()
+    ));
+
+    assert_eq!(Dataset::default().memory_stats().interned_ids, 0);
+}
+
+#[test]
+fn test_iter_accessors() {
+    let dataset = load_dataset("good_feed");
+    let route_id = RouteId::from("AB");
+
+    let mut trip_ids: Vec<String> = dataset
+        .iter_trips_for_route(&route_id)
+        .map(|trip| trip.trip_id.to_string())
+        .collect();
+    trip_ids.sort();
+    assert_eq!(trip_ids, ["AB1", "AB2"]);
+
+    let trip_id = TripId::from("AB1");
+    let mut stop_sequences: Vec<u32> = dataset
+        .iter_stop_times_for_trip(&trip_id)
+        .map(|stop_time| stop_time.stop_sequence)
+        .collect();
+    stop_sequences.sort();
+    assert_eq!(stop_sequences, [1, 2]);
+
+    assert_eq!(dataset.iter_stop_times_for_route(&route_id).count(), 4);
+    assert_eq!(
+        dataset
+            .iter_stop_times_for_route(&RouteId::from("unknown"))
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn test_summary() {
+    let dataset = load_dataset("good_feed");
+    let summary = dataset.summary();
+
+    assert_eq!(summary.record_counts[&GtfsFile::Routes], 5);
+    assert!(!summary.record_counts.contains_key(&GtfsFile::Shapes));
+    assert_eq!(
+        summary.first_service_date,
+        NaiveDate::from_ymd_opt(2007, 1, 1)
+    );
+    assert_eq!(
+        summary.last_service_date,
+        NaiveDate::from_ymd_opt(2025, 12, 31)
+    );
+    // Every day but 2007-06-04, a Monday where FULLW is removed.
+    assert_eq!(summary.service_days, 6939);
+    assert_eq!(summary.routes_by_type[&RouteType::Bus], 5);
+    assert!(summary.bounding_box.is_some());
+    assert!(summary.stop_density.unwrap() > 0.0);
+
+    let longest = summary.longest_trip.clone().unwrap();
+    let shortest = summary.shortest_trip.clone().unwrap();
+    assert!(longest.duration >= shortest.duration);
+
+    let json = serde_json::to_string(&summary).unwrap();
+    assert!(json.contains(r#""routes.txt":5"#));
+    assert!(json.contains(r#""first_service_date":"2007-01-01""#));
+    assert!(json.contains(r#""routes_by_type":{"3":5}"#));
+    assert!(RouteType::Rail < RouteType::Bus);
+    assert!(RouteType::Bus < RouteType::Unknown(3));
+    assert!(RouteType::Unknown(3) < RouteType::Ferry);
+}
+
+#[cfg(feature = "realtime")]
+#[test]
+fn test_realtime_binding() {
+    use gtfs_schedule::schemas::StopId;
+    use gtfs_schedule::{
+        Alert, EntitySelector, RealtimeBinding, StopTimeEvent, StopTimeUpdate, TripDescriptor,
+        TripUpdate, UnmatchedEntity,
+    };
+
+    let dataset = load_dataset("good_feed");
+    let binding = RealtimeBinding::new(&dataset);
+    let time = |time: &str| Some(NaiveServiceTime::try_from(time).unwrap());
+
+    let trip_update = TripUpdate {
+        trip: TripDescriptor {
+            trip_id: Some("CITY1".to_string()),
+            start_date: Some("20240108".to_string()),
+            ..Default::default()
+        },
+        stop_time_update: vec![
+            StopTimeUpdate {
+                stop_sequence: Some(10),
+                arrival: Some(StopTimeEvent {
+                    delay: Some(120),
+                    time: None,
+                }),
+                ..Default::default()
+            },
+            StopTimeUpdate {
+                stop_sequence: Some(20),
+                departure: Some(StopTimeEvent {
+                    delay: None,
+                    // 06:29:00 in America/Los_Angeles.
+                    time: Some(1_704_724_140),
+                }),
+                ..Default::default()
+            },
+            StopTimeUpdate {
+                stop_sequence: Some(7),
+                ..Default::default()
+            },
+        ],
+        delay: None,
+    };
+
+    assert_eq!(
+        binding.unmatched_in_trip_update(&trip_update),
+        [UnmatchedEntity::StopTime {
+            trip_id: TripId::from("CITY1"),
+            stop_sequence: Some(7),
+            stop_id: None,
+        }]
+    );
+
+    let schedule = binding.effective_schedule(&trip_update).unwrap();
+    assert_eq!(schedule.len(), 5);
+    assert_eq!(schedule[1].departure_time, time("6:07:00"));
+    assert_eq!(schedule[2].arrival_time, time("6:14:00"));
+    assert_eq!(schedule[2].departure_time, time("6:16:00"));
+    assert_eq!(schedule[3].departure_time, time("6:23:00"));
+    assert_eq!(schedule[4].delay, Some(60));
+    assert_eq!(schedule[4].departure_time, time("6:29:00"));
+
+    let alert = Alert {
+        informed_entity: vec![
+            EntitySelector {
+                route_id: Some("CITY".to_string()),
+                stop_id: Some("NOWHERE".to_string()),
+                ..Default::default()
+            },
+            EntitySelector {
+                route_id: Some("NOROUTE".to_string()),
+                ..Default::default()
+            },
+            EntitySelector {
+                stop_id: Some("NOWHERE".to_string()),
+                ..Default::default()
+            },
+        ],
+    };
+    assert_eq!(
+        binding.unmatched_in_alert(&alert),
+        [
+            UnmatchedEntity::Stop(StopId::from("NOWHERE")),
+            UnmatchedEntity::Route(RouteId::from("NOROUTE")),
+        ]
+    );
+}
+
+#[test]
+fn test_plan() {
+    let dataset = load_dataset("good_feed");
+    let departure = NaiveDate::from_ymd_opt(2007, 6, 5)
+        .unwrap()
+        .and_hms_opt(7, 0, 0)
+        .unwrap();
+
+    let journey = dataset
+        .plan(
+            &StopId::from("BEATTY_AIRPORT"),
+            &StopId::from("FUR_CREEK_RES"),
+            departure,
+            &PlanOptions::default(),
+        )
+        .unwrap();
+    let trips: Vec<_> = journey
+        .legs
+        .iter()
+        .map(|leg| match leg {
+            JourneyLeg::Ride { trip_id, .. } => trip_id.to_string(),
+            JourneyLeg::Walk { .. } => "walk".to_string(),
+        })
+        .collect();
+    assert_eq!(trips, ["AB1", "BFC1"]);
+    assert_eq!(journey.departure, departure + chrono::Duration::hours(1));
+    assert_eq!(journey.arrival, departure + chrono::Duration::minutes(140));
+
+    // The only trip to Bullfrog of the day has left.
+    let journey = dataset
+        .plan(
+            &StopId::from("BEATTY_AIRPORT"),
+            &StopId::from("BULLFROG"),
+            departure + chrono::Duration::hours(2),
+            &PlanOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(
+        journey.departure.date(),
+        NaiveDate::from_ymd_opt(2007, 6, 6).unwrap()
+    );
+    assert_eq!(
+        dataset.plan(
+            &StopId::from("BULLFROG"),
+            &StopId::from("UNKNOWN"),
+            departure,
+            &PlanOptions::default(),
+        ),
+        None
+    );
+}
+
+#[test]
+fn test_headways() {
+    let dataset = load_dataset("good_feed");
+    let date = NaiveDate::from_ymd_opt(2007, 6, 5).unwrap();
+
+    let profile = dataset.headways(&RouteId::from("STBA"), date);
+    assert_eq!(profile.directions.len(), 1);
+    let direction = &profile.directions[0];
+    assert_eq!(direction.departures.len(), 32);
+    let overall = direction.overall.unwrap();
+    assert_eq!(overall.count, 31);
+    assert_eq!(overall.median, std::time::Duration::from_secs(1800));
+    assert_eq!(direction.by_hour[&6].count, 2);
+
+    // CITY runs every 10 minutes from 8:00 to 10:00, and every 30 minutes otherwise.
+    let profile = dataset.headways(&RouteId::from("CITY"), date);
+    assert_eq!(profile.directions.len(), 2);
+    let direction = &profile.directions[0];
+    assert_eq!(
+        direction.by_hour[&8].p90,
+        std::time::Duration::from_secs(600)
+    );
+    assert_eq!(
+        direction.by_hour[&7].p90,
+        std::time::Duration::from_secs(1800)
+    );
+    assert_eq!(
+        direction.overall.unwrap().p90,
+        std::time::Duration::from_secs(1800)
+    );
+
+    let span = dataset
+        .service_span(&StopId::from("BEATTY_AIRPORT"), date)
+        .unwrap();
+    assert_eq!(span.first_departure.as_secs(), 6 * 3600 + 1200);
+    assert_eq!(span.last_departure.as_secs(), 21 * 3600 + 1800 + 1200);
+    assert_eq!(
+        dataset.service_span(
+            &StopId::from("BEATTY_AIRPORT"),
+            NaiveDate::from_ymd_opt(2030, 1, 1).unwrap()
+        ),
+        None
+    );
+}
+
+#[test]
+fn test_conformance_report() {
+    let dataset = load_dataset("good_feed");
+    let report = dataset.conformance_report();
+    assert_eq!(report.categories.len(), 5);
+    assert_eq!(report.grade(), 'F');
+
+    let pathways = report.category(ConformanceCategory::Pathways).unwrap();
+    assert_eq!(pathways.score, None);
+    let fares = report.category(ConformanceCategory::FaresV2).unwrap();
+    assert_eq!(fares.score, Some(0.0));
+    assert_eq!(fares.findings, ["fare_leg_rules.txt has no record"]);
+
+    // CITY1 has a headsign in stop_times.txt, CITY2 has none.
+    let headsigns = report.category(ConformanceCategory::Headsigns).unwrap();
+    assert_eq!(headsigns.score, Some(1000.0 / 11.0));
+    assert_eq!(
+        headsigns.findings,
+        ["1 of 11 trips have no headsign, e.g. CITY2"]
+    );
+    assert!(serde_json::to_string(&report)
+        .unwrap()
+        .contains("\"category\":\"fares_v2\""));
+}
+
+#[test]
+fn test_netex_export() {
+    let dataset = load_dataset("good_feed");
+    let model = TransitModel::from_dataset(&dataset);
+    assert_eq!(model.operators.len(), 1);
+    assert_eq!(model.lines.len(), 5);
+    assert_eq!(model.stop_places.len(), 9);
+    let station = model
+        .stop_places
+        .iter()
+        .find(|stop_place| stop_place.id == "BEATTY_AIRPORT_STATION")
+        .unwrap();
+    assert_eq!(station.quays.len(), 1);
+    assert_eq!(station.quays[0].id, "BEATTY_AIRPORT");
+
+    // STBA runs every 30 minutes from 6:00 to 22:00.
+    let runs: Vec<_> = model
+        .service_journeys
+        .iter()
+        .filter(|journey| journey.line_id == "STBA")
+        .collect();
+    assert_eq!(runs.len(), 32);
+    let second_run = runs.iter().find(|run| run.id == "STBA_2").unwrap();
+    assert_eq!(
+        second_run.calls[0].departure_time,
+        Some(NaiveServiceTime::try_from("06:30:00").unwrap())
+    );
+    assert!(!second_run.calls[0].for_boarding);
+
+    let mut document = Vec::new();
+    NetexExporter::new("DEMO")
+        .timestamp(
+            NaiveDate::from_ymd_opt(2024, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+        )
+        .export(&model, &mut document)
+        .unwrap();
+    let document = String::from_utf8(document).unwrap();
+    assert!(document.contains("<PublicationTimestamp>2024-01-01T00:00:00</PublicationTimestamp>"));
+    assert!(document.contains(r#"<Line id="DEMO:Line:AB" version="1">"#));
+    assert!(document.contains(r#"<QuayRef ref="DEMO:Quay:BEATTY_AIRPORT" version="1"/>"#));
+    assert!(document.contains("<Name>Stagecoach Hotel &amp; Casino (Demo)</Name>"));
+    assert!(document.contains("<ForBoarding>false</ForBoarding>"));
+    // A pattern for each direction of the routes, STBA running one way only.
+    assert_eq!(document.matches("<ServiceJourneyPattern ").count(), 9);
+}
+
+#[test]
+fn test_agency_view() {
+    let dir = temp_dir("agency_view");
+    load_dataset("good_feed").to_csv(&dir).unwrap();
+    std::fs::write(
+        dir.join("agency.txt"),
+        "agency_id,agency_name,agency_url,agency_timezone\n\
+         DTA,Demo,http://google.com,America/Los_Angeles\n\
+         AMV,Amargosa,http://google.com,America/Los_Angeles\n",
+    )
+    .unwrap();
+    let routes = std::fs::read_to_string(dir.join("routes.txt")).unwrap();
+    let routes: Vec<String> = routes
+        .lines()
+        .map(|line| {
+            if line.starts_with("AAMV,") {
+                line.replacen("DTA", "AMV", 1)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    std::fs::write(dir.join("routes.txt"), routes.join("\n")).unwrap();
+    let dataset = Dataset::from_csv(&dir).unwrap();
+
+    let view = dataset.agency_view(&AgencyId::from("DTA")).unwrap();
+    assert_eq!(view.agency().unwrap().agency_name, "Demo");
+    assert_eq!(view.routes().count(), 4);
+    assert!(!view.contains_route(&RouteId::from("AAMV")));
+    assert!(view.contains_trip(&TripId::from("AB1")));
+    assert!(!view.contains_trip(&TripId::from("AAMV1")));
+    assert_eq!(view.stop_times().count(), 20);
+    // The parent station of a served stop belongs to the view.
+    assert!(view.contains_stop(&StopId::from("BEATTY_AIRPORT_STATION")));
+    assert!(!view.contains_stop(&StopId::from("AMV")));
+    let fares: Vec<_> = view
+        .fare_attributes()
+        .map(|fare| fare.fare_id.to_string())
+        .collect();
+    assert_eq!(fares, ["p"]);
+    assert_eq!(view.fare_rules().count(), 3);
+
+    assert!(dataset.agency_view(&AgencyId::from("UNKNOWN")).is_none());
+}
+
+#[test]
+fn test_table_reader() {
+    let path = Path::new("tests/_data/good_feed/stop_times.txt");
+    let stop_times: Vec<_> = StopTimesReader::from_path(path)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(stop_times.len(), 28);
+    assert_eq!(stop_times[0].trip_id, TripId::from("STBA"));
+
+    let csv = "\u{feff}stop_id, stop_name,platform_color\nA,Alpha,red\nB,Beta,\n";
+    let reader = StopsReader::from_reader(csv.as_bytes()).unwrap();
+    assert_eq!(reader.unknown_columns(), ["platform_color"]);
+    let stops: Vec<_> = reader.map(|stop| stop.unwrap().stop_id).collect();
+    assert_eq!(stops, [StopId::from("A"), StopId::from("B")]);
+
+    let csv = "trip_id,start_time,end_time,headway_secs\nT,6:00:00,7:00:00,soon\n";
+    let mut reader = FrequenciesReader::from_reader(csv.as_bytes()).unwrap();
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn test_booking_rule_minutes_overflow() {
+    let header = csv::StringRecord::from(vec![
+        "booking_rule_id",
+        "booking_type",
+        "prior_notice_duration_min",
+    ]);
+    let record = csv::StringRecord::from(vec!["BR", "1", "30"]);
+    let booking_rule: gtfs_schedule::schemas::BookingRule = parse_record(&header, &record).unwrap();
+    assert_eq!(
+        booking_rule.prior_notice_duration_min,
+        Some(Duration::from_secs(30 * 60))
+    );
+
+    let record = csv::StringRecord::from(vec!["BR", "1", "18446744073709551615"]);
+    assert!(parse_record::<gtfs_schedule::schemas::BookingRule>(&header, &record).is_err());
+}
+
+#[test]
+fn test_table_writer() {
+    let path = Path::new("tests/_data/good_feed/stop_times.txt");
+    let mut writer = StopTimesWriter::new(Vec::new());
+    for stop_time in StopTimesReader::from_path(path).unwrap() {
+        writer.write_record(&stop_time.unwrap()).unwrap();
+    }
+    let csv = writer.finish().unwrap();
+    let stop_times: Vec<_> = StopTimesReader::from_reader(csv.as_slice())
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(stop_times.len(), 28);
+    assert_eq!(
+        stop_times[1].arrival_time.unwrap().as_secs(),
+        6 * 3600 + 20 * 60
+    );
+
+    // The header is written even without records, and values are never dropped.
+    let columns = ["trip_id", "stop_sequence"];
+    let writer = StopTimesWriter::with_columns(Vec::new(), &columns).unwrap();
+    assert_eq!(writer.finish().unwrap(), b"trip_id,stop_sequence\n");
+    let mut writer = StopTimesWriter::with_columns(Vec::new(), &columns).unwrap();
+    assert!(writer.write_record(&stop_times[0]).is_err());
+    assert!(StopTimesWriter::with_columns(Vec::new(), &["trip_id", "trip_id"]).is_err());
+}
+
+#[test]
+fn test_split_by_service_date() {
+    let dataset = load_dataset("good_feed");
+    // FULLW does not run on 2007-06-04, WE runs on week-ends.
+    let shards: Vec<_> = dataset
+        .split_by_service_date(
+            NaiveDate::from_ymd_opt(2007, 6, 4).unwrap()
+                ..=NaiveDate::from_ymd_opt(2007, 6, 9).unwrap(),
+        )
+        .collect();
+    assert_eq!(shards.len(), 6);
+    assert!(shards[0].trips.is_empty());
+    assert_eq!(shards[1].trips.len(), 7);
+    assert_eq!(shards[5].trips.len(), 11);
+
+    let shard = &shards[1];
+    assert!(shard.trip(&TripId::from("AAMV1")).is_none());
+    let stop_times = shard.stop_times_for_trip(&TripId::from("CITY1"));
+    let sequences: Vec<_> = stop_times
+        .iter()
+        .map(|stop_time| stop_time.stop_sequence)
+        .collect();
+    assert_eq!(sequences, [0, 5, 10, 15, 20]);
+    assert_eq!(shard.frequencies.len(), 11);
+}
+
+#[test]
+fn test_lenient_codes() {
+    let dir = temp_dir("lenient_codes");
+    load_dataset("good_feed").to_csv(&dir).unwrap();
+    let routes = std::fs::read_to_string(dir.join("routes.txt")).unwrap();
+    let routes = routes.replacen(",3\n", ",99\n", 1);
+    std::fs::write(dir.join("routes.txt"), routes).unwrap();
+
+    assert!(Dataset::from_csv(&dir).is_err());
+    let options = ParseOptions::default().lenient_codes();
+    let dataset = Dataset::from_csv_with_options(&dir, &options).unwrap();
+    let unknown: Vec<RouteType> = dataset
+        .routes
+        .iter()
+        .map(|route| route.route_type.clone())
+        .filter(|route_type| matches!(route_type, RouteType::Unknown(_)))
+        .collect();
+    assert_eq!(unknown, [RouteType::Unknown(99)]);
+    assert_eq!(RouteType::Unknown(99).code(), 99);
+    assert_eq!(RouteType::from_code(11), RouteType::Trolleybus);
+
+    // Unknown codes are written back as they were read.
+    dataset.to_csv(&dir).unwrap();
+    let written = Dataset::from_csv_with_options(&dir, &options).unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+    assert_eq!(
+        written.fingerprint().unwrap(),
+        dataset.fingerprint().unwrap()
+    );
+}
+
+#[test]
+fn test_generate_feed() {
+    let dataset = synth::generate_feed(3);
+    assert_eq!(
+        synth::generate_feed(3).fingerprint().unwrap(),
+        dataset.fingerprint().unwrap()
+    );
+    assert!(dataset.validate().is_ok());
+    assert_eq!(dataset.routes.len(), 3);
+    // Every route serves the hub.
+    assert_eq!(dataset.stops.len(), 3 * 19 + 1);
+    assert_eq!(dataset.trips.len(), 3 * 2 * 72);
+    assert_eq!(dataset.stop_times.len(), 3 * 2 * 72 * 20);
+
+    let departure = NaiveDate::from_ymd_opt(2024, 6, 5)
+        .unwrap()
+        .and_hms_opt(8, 0, 0)
+        .unwrap();
+    let journey = dataset
+        .plan(
+            &StopId::from("R0S0"),
+            &StopId::from("R2S19"),
+            departure,
+            &PlanOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(journey.legs.len(), 2);
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn test_from_zip_bytes() {
+    use std::io::Write;
+
+    let dataset = load_dataset("good_feed");
+    let dir = temp_dir("from_zip_bytes");
+    dataset.to_csv(&dir).unwrap();
+    let mut archive = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let mut entries: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        archive
+            .start_file(name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        archive.write_all(&std::fs::read(&path).unwrap()).unwrap();
+    }
+    // Files in folders are ignored.
+    archive
+        .start_file("extra/stops.txt", zip::write::SimpleFileOptions::default())
+        .unwrap();
+    archive.write_all(b"stop_id\n").unwrap();
+    let bytes = archive.finish().unwrap().into_inner();
+    std::fs::remove_dir_all(dir).unwrap();
+
+    let parsed = Dataset::from_zip_bytes(&bytes).unwrap();
+    assert_eq!(
+        parsed.fingerprint().unwrap(),
+        dataset.fingerprint().unwrap()
+    );
+    let options = ParseOptions::default().only_tables(&[GtfsFile::Stops]);
+    let stops = Dataset::from_zip_bytes_with_options(&bytes, &options).unwrap();
+    assert_eq!(stops.stops.len(), dataset.stops.len());
+    assert!(stops.trips.is_empty());
+
+    let error = Dataset::from_zip_bytes(b"not a zip archive").err().unwrap();
+    assert_eq!(error.notice_code(), "i_o_error");
+}
+
+#[cfg(feature = "csv-core")]
+#[test]
+fn test_csv_core_backend() {
+    use gtfs_schedule::schemas::Schema;
+    use gtfs_schedule::CsvBackend;
+
+    let options = ParseOptions::default().keep_source_lines();
+    let csv_core_options = options.clone().csv_backend(CsvBackend::CsvCore);
+    for entry in std::fs::read_dir("tests/_data").unwrap() {
+        let dir = entry.unwrap().path();
+        let expected = Dataset::from_csv_with_options(&dir, &options);
+        let parsed = Dataset::from_csv_with_options(&dir, &csv_core_options);
+        match (expected, parsed) {
+            (Ok(expected), Ok(parsed)) => {
+                assert_eq!(
+                    parsed.fingerprint().unwrap(),
+                    expected.fingerprint().unwrap(),
+                    "{:?}",
+                    dir
+                );
+                for stop in expected.stops.iter() {
+                    let stop = Schema::from(stop.value().clone());
+                    assert_eq!(parsed.provenance(&stop), expected.provenance(&stop));
+                }
+            }
+            (expected, parsed) => assert_eq!(parsed.is_ok(), expected.is_ok(), "{:?}", dir),
+        }
+    }
+}
+
+#[test]
+fn test_borrowed_reader() {
+    let path = Path::new("tests/_data/good_feed/stop_times.txt");
+    let expected: Vec<_> = StopTimesReader::from_path(path)
+        .unwrap()
+        .map(|stop_time| stop_time.unwrap())
+        .collect();
+    let mut reader = BorrowedReader::from_path(path).unwrap();
+    let mut count = 0;
+    while let Some(stop_time) = reader.read::<StopTimeRef>() {
+        let stop_time = stop_time.unwrap();
+        let expected = &expected[count];
+        assert_eq!(stop_time.trip_id, expected.trip_id.as_str());
+        assert_eq!(
+            stop_time.stop_id,
+            expected.stop_id.as_ref().map(|stop_id| stop_id.as_str())
+        );
+        assert_eq!(stop_time.stop_sequence, expected.stop_sequence);
+        assert_eq!(stop_time.arrival_time, expected.arrival_time);
+        count += 1;
+    }
+    assert_eq!(count, expected.len());
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_from_csv_mmap() {
+    let path = Path::new("tests/_data/good_feed");
+    let dataset = Dataset::from_csv_mmap(path).unwrap();
+    assert_eq!(
+        dataset.fingerprint().unwrap(),
+        Dataset::from_csv(path).unwrap().fingerprint().unwrap()
+    );
+
+    let mut reader = BorrowedReader::from_mmap(&path.join("stop_times.txt")).unwrap();
+    let mut count = 0;
+    while let Some(stop_time) = reader.read::<StopTimeRef>() {
+        let stop_time = stop_time.unwrap();
+        let key = (TripId::from(stop_time.trip_id), stop_time.stop_sequence);
+        assert!(dataset.stop_times.contains_key(&key));
+        count += 1;
+    }
+    assert_eq!(count, dataset.stop_times.len());
+}
+
+/// Records the names of the spans entered while running a closure.
+#[cfg(feature = "tracing")]
+fn entered_spans(run: impl FnOnce()) -> Vec<&'static str> {
+    use std::sync::{Arc, Mutex};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+    impl Subscriber for SpanNames {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut names = self.0.lock().unwrap();
+            names.push(span.metadata().name());
+            Id::from_u64(names.len() as u64)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    let names = Arc::new(Mutex::new(Vec::new()));
+    tracing::subscriber::with_default(SpanNames(names.clone()), run);
+    let names = names.lock().unwrap().clone();
+    names
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_spans() {
+    let spans = entered_spans(|| {
+        let dataset = load_dataset("good_feed");
+        dataset.validate().unwrap();
+        dataset.compile_timetable(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()
+                ..=NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        );
+    });
+    for name in [
+        "parse",
+        "parse_file",
+        "validate",
+        "validate_records",
+        "validate_dataset",
+        "compile_timetable",
+    ] {
+        assert!(spans.contains(&name), "missing span {}", name);
+    }
+}
+
+#[test]
+fn test_hostile_inputs() {
+    // The failing cell of a record past the number of columns.
+    let dir = temp_dir("hostile_inputs");
+    std::fs::write(
+        dir.join("calendar_dates.txt"),
+        "service_id,date,exception_type\nA,20240101,1\nA,20240102,1\nA,20240103,1\nA,tomorrow,1\n",
+    )
+    .unwrap();
+    assert!(Dataset::from_csv(&dir).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Translations pointing to records by an invalid record_sub_id.
+    let header = csv::StringRecord::from(vec![
+        "table_name",
+        "field_name",
+        "language",
+        "translation",
+        "record_id",
+        "record_sub_id",
+    ]);
+    for (table_name, record_id, record_sub_id) in [
+        ("stop_times", "CITY1", "first"),
+        ("calendar_dates", "FULLW", "2007-06-04"),
+        ("frequencies", "CITY1", "6:00:00:00"),
+    ] {
+        let mut dataset = load_dataset("good_feed");
+        let record = csv::StringRecord::from(vec![
+            table_name,
+            "stop_headsign",
+            "fr",
+            "Centre",
+            record_id,
+            record_sub_id,
+        ]);
+        dataset
+            .translations
+            .push(parse_record(&header, &record).unwrap());
+        let error = dataset.validate().err().unwrap();
+        assert!(error.to_string().contains("record_sub_id"), "{}", error);
+    }
+
+    // A headway overflowing the time of the last departure.
+    let mut frequency = load_dataset("good_feed")
+        .frequencies
+        .iter()
+        .next()
+        .unwrap()
+        .clone();
+    frequency.exact_times = Some(ExactTimes::Exact);
+    frequency.headway_secs = Duration::from_secs(u64::from(u32::MAX));
+    assert_eq!(
+        frequency.validate().err().unwrap().notice_code(),
+        "invalid_field_value"
+    );
+
+    // Parent stations referring to each other.
+    let dataset = load_dataset("good_feed");
+    for (stop_id, parent_station) in [("STAGECOACH", "NADAV"), ("NADAV", "STAGECOACH")] {
+        let mut stop = dataset.stops.get_mut(&StopId::from(stop_id)).unwrap();
+        stop.location_type = None;
+        stop.parent_station = Some(StopId::from(parent_station));
+    }
+    let error = dataset.validate().err().unwrap();
+    assert!(error.to_string().contains("loops back"), "{}", error);
+}
+
+#[test]
+fn test_referential_mutations() {
+    let mut dataset = load_dataset("good_feed");
+    let route_id = RouteId::from("AB");
+    assert!(dataset
+        .remove_route(&route_id, CascadePolicy::Reject)
+        .is_err());
+    assert!(dataset.routes.contains_key(&route_id));
+    let removed = dataset
+        .remove_route(&route_id, CascadePolicy::Cascade)
+        .unwrap();
+    assert!(removed
+        .iter()
+        .any(|record| record.file() == GtfsFile::FareRules));
+    assert!(!dataset.trips.contains_key(&TripId::from("AB1")));
+    assert!(!dataset
+        .stop_times
+        .iter()
+        .any(|stop_time| stop_time.trip_id == TripId::from("AB2")));
+    dataset.validate().unwrap();
+
+    let (old, new) = (
+        StopId::from("BEATTY_AIRPORT"),
+        StopId::from("BEATTY_AIRPORT_2"),
+    );
+    assert!(dataset.rename_stop_id(&old, &StopId::from("AMV")).is_err());
+    dataset.rename_stop_id(&old, &new).unwrap();
+    assert!(dataset
+        .stop_times
+        .iter()
+        .any(|stop_time| stop_time.stop_id.as_ref() == Some(&new)));
+    dataset.validate().unwrap();
+
+    let mut trip = dataset.trips.get(&TripId::from("STBA")).unwrap().clone();
+    trip.trip_id = TripId::from("STBA2");
+    let mut stop_times: Vec<_> = dataset
+        .iter_stop_times_for_trip(&TripId::from("STBA"))
+        .map(|stop_time| {
+            let mut stop_time = stop_time.value().clone();
+            stop_time.trip_id = trip.trip_id.clone();
+            stop_time
+        })
+        .collect();
+    stop_times[0].stop_id = Some(StopId::from("NOWHERE"));
+    assert!(dataset.add_trip(trip.clone(), stop_times.clone()).is_err());
+    assert!(!dataset.trips.contains_key(&trip.trip_id));
+    stop_times[0].stop_id = Some(StopId::from("STAGECOACH"));
+    dataset.add_trip(trip.clone(), stop_times).unwrap();
+    assert_eq!(dataset.iter_stop_times_for_trip(&trip.trip_id).count(), 2);
+    dataset.validate().unwrap();
+}
+
+#[test]
+fn test_service_exceptions() {
+    let mut dataset = load_dataset("good_feed");
+    let (full_week, weekend) = (
+        CalendarServiceId::from("FULLW"),
+        CalendarServiceId::from("WE"),
+    );
+    // A Wednesday run as a Sunday.
+    let holiday = NaiveDate::from_ymd_opt(2024, 12, 25).unwrap();
+    dataset
+        .cancel_service_on(holiday, &[full_week.clone(), weekend.clone()])
+        .unwrap();
+    dataset.add_extra_service_on(holiday, &weekend).unwrap();
+    assert!(!dataset.is_service_active(&full_week, holiday));
+    assert!(dataset.is_service_active(&weekend, holiday));
+    assert_eq!(dataset.calendar_dates.len(), 3);
+
+    // Restoring the weekly pattern removes the exception rather than adding another one.
+    dataset.add_extra_service_on(holiday, &full_week).unwrap();
+    assert!(!dataset
+        .calendar_dates
+        .contains_key(&(full_week.clone(), holiday)));
+    assert!(dataset
+        .cancel_service_on(holiday, &[CalendarServiceId::from("NONE")])
+        .is_err());
+    dataset.validate().unwrap();
+}
+
+#[test]
+fn test_polygon_queries() {
+    let dataset = load_dataset("good_feed");
+    // Around the Nye County Airport.
+    let (x, y) = (-116.784582, 36.868446);
+    let polygon = geo::Polygon::new(
+        geo::LineString::from(vec![
+            (x - 0.001, y - 0.001),
+            (x + 0.001, y - 0.001),
+            (x + 0.001, y + 0.001),
+            (x - 0.001, y + 0.001),
+        ]),
+        vec![],
+    );
+    assert_eq!(
+        dataset.stops_in_polygon(&polygon),
+        vec![
+            StopId::from("BEATTY_AIRPORT"),
+            StopId::from("BEATTY_AIRPORT_STATION")
+        ]
+    );
+
+    // A Wednesday, the trips of the weekend service are left out.
+    let wednesday = NaiveDate::from_ymd_opt(2024, 6, 5).unwrap();
+    let trip_ids = dataset.trips_crossing_polygon(&polygon, wednesday);
+    for trip_id in ["AB1", "AB2", "STBA"] {
+        assert!(trip_ids.contains(&TripId::from(trip_id)), "{}", trip_id);
+    }
+    assert!(!trip_ids.contains(&TripId::from("AAMV1")));
+    let saturday = NaiveDate::from_ymd_opt(2024, 6, 8).unwrap();
+    assert!(dataset
+        .trips_crossing_polygon(&polygon, saturday)
+        .contains(&TripId::from("AAMV1")));
+}
+
+#[test]
+fn test_stop_shape_distance() {
+    let dataset = synth::generate_feed(2);
+    dataset.validate_stop_shape_distance(1.0).unwrap();
+    assert!(dataset
+        .snap_stops_to_shapes()
+        .iter()
+        .all(|snapped| snapped.distance_m < 1.0));
+
+    // Move a stop about a kilometer away from its route.
+    let stop_id = StopId::from("R0S3");
+    let original = {
+        let mut stop = dataset.stops.get_mut(&stop_id).unwrap();
+        let coord = **stop.stop_coord.as_ref().unwrap();
+        stop.stop_coord = Some(geo::Coord::from((coord.x, coord.y + 0.01)).into());
+        coord
+    };
+    let error = dataset.validate_stop_shape_distance(100.0).err().unwrap();
+    assert!(error.to_string().contains("R0D"), "{}", error);
+
+    let snapped = dataset.snap_stops_to_shapes();
+    let snapped = snapped
+        .iter()
+        .find(|snapped| snapped.stop_id == stop_id)
+        .unwrap();
+    assert!(snapped.distance_m > 1_000.0);
+    assert!((snapped.snapped_coord.x - original.x).abs() < 1e-9);
+    assert!((snapped.snapped_coord.y - original.y).abs() < 1e-9);
+}
+
+#[test]
+fn test_route_map_style() {
+    let dataset = load_dataset("good_feed");
+    assert!(dataset.route_map_style(&RouteId::from("UNKNOWN")).is_none());
+
+    let style = dataset.route_map_style(&RouteId::from("AB")).unwrap();
+    assert_eq!(style.source_id, "route-AB");
+    let features = style.geojson["features"].as_array().unwrap();
+    // AB1 and AB2 serve the same stops in opposite directions, without shape.
+    let lines: Vec<_> = features
+        .iter()
+        .filter(|feature| feature["geometry"]["type"] == "LineString")
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["properties"]["label"], "Airport ⇒ Bullfrog");
+    assert_eq!(lines[0]["properties"]["color"], "#FFFFFF");
+    assert_eq!(
+        lines[0]["geometry"]["coordinates"][0],
+        serde_json::json!([-116.784582, 36.868446])
+    );
+    let stops: Vec<_> = features
+        .iter()
+        .filter(|feature| feature["geometry"]["type"] == "Point")
+        .map(|feature| feature["properties"]["stop_id"].as_str().unwrap())
+        .collect();
+    assert_eq!(stops, ["BEATTY_AIRPORT", "BULLFROG"]);
+
+    let layer_types: Vec<_> = style.layers.iter().map(|layer| &layer["type"]).collect();
+    assert_eq!(layer_types, ["line", "circle", "symbol", "symbol"]);
+    assert!(style
+        .layers
+        .iter()
+        .all(|layer| layer["source"] == "route-AB"));
+    let snippet = style.style_snippet();
+    assert_eq!(snippet["sources"]["route-AB"]["type"], "geojson");
+    assert_eq!(snippet["sources"]["route-AB"]["data"], style.geojson);
+
+    let styles = dataset.route_map_styles();
+    assert_eq!(styles.len(), dataset.routes.len());
+    assert!(styles.contains(&style));
+}
+
+#[test]
+fn test_versioning() {
+    let mut dataset = load_dataset("good_feed");
+    assert!(dataset.set_version("1.0.0").is_err());
+
+    let header = csv::StringRecord::from(vec![
+        "feed_publisher_name",
+        "feed_publisher_url",
+        "feed_lang",
+        "feed_version",
+    ]);
+    let record = csv::StringRecord::from(vec!["DTA", "https://example.com", "en", "v1.4.2"]);
+    dataset.feed_info = Some(parse_record(&header, &record).unwrap());
+    let published = dataset.published_version().unwrap();
+    assert_eq!(published.feed_version.as_deref(), Some("v1.4.2"));
+    assert_eq!(
+        dataset.publish_status(&published).unwrap(),
+        PublishStatus::UpToDate
+    );
+
+    assert_eq!(
+        dataset.next_version(VersionStrategy::Patch).unwrap(),
+        "v1.4.3"
+    );
+    assert_eq!(
+        dataset.next_version(VersionStrategy::Major).unwrap(),
+        "v2.0.0"
+    );
+    assert_eq!(
+        dataset.bump_version(VersionStrategy::Minor).unwrap(),
+        "v1.5.0"
+    );
+    // Only the version changed.
+    assert_eq!(
+        dataset.publish_status(&published).unwrap(),
+        PublishStatus::VersionOnly
+    );
+    assert!(!dataset
+        .publish_status(&published)
+        .unwrap()
+        .needs_republish());
+
+    let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    assert_eq!(
+        dataset.bump_version(VersionStrategy::Date(date)).unwrap(),
+        "20240601"
+    );
+    assert_eq!(
+        dataset.bump_version(VersionStrategy::Date(date)).unwrap(),
+        "20240601.1"
+    );
+    assert_eq!(
+        dataset.bump_version(VersionStrategy::Date(date)).unwrap(),
+        "20240601.2"
+    );
+    dataset.set_version("spring-2024").unwrap();
+    assert!(dataset.next_version(VersionStrategy::Patch).is_err());
+
+    // Content changes call for a republication, with a new version.
+    dataset.set_version("v1.4.2").unwrap();
+    dataset.stops.remove(&StopId::from("AMV"));
+    assert_eq!(
+        dataset.publish_status(&published).unwrap(),
+        PublishStatus::StaleVersion
+    );
+    dataset.bump_version(VersionStrategy::Patch).unwrap();
+    assert_eq!(
+        dataset.publish_status(&published).unwrap(),
+        PublishStatus::Republish
+    );
+    assert!(dataset
+        .publish_status(&published)
+        .unwrap()
+        .needs_republish());
+
+    // The writer bumps the version of feed_info.txt, leaving the dataset as is.
+    let dir = temp_dir("versioning");
+    let options = WriteOptions::default().bump_version(VersionStrategy::Minor);
+    dataset.to_csv_with_options(&dir, &options).unwrap();
+    let written = Dataset::from_csv(&dir).unwrap();
+    let written_version = written.feed_info.unwrap().feed_version;
+    assert_eq!(written_version.as_deref(), Some("v1.5.0"));
+    assert_eq!(
+        dataset.published_version().unwrap().feed_version.as_deref(),
+        Some("v1.4.3")
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_snapshot_and_freeze() {
+    let dataset = load_dataset("good_feed");
+    let snapshot = dataset.snapshot();
+    assert_eq!(
+        snapshot.fingerprint().unwrap(),
+        dataset.fingerprint().unwrap()
+    );
+
+    // Edits of the original do not show in the snapshot.
+    dataset.stops.remove(&StopId::from("AMV"));
+    assert!(snapshot.stops.contains_key(&StopId::from("AMV")));
+    assert_ne!(
+        snapshot.fingerprint().unwrap(),
+        dataset.fingerprint().unwrap()
+    );
+
+    let stop_times = snapshot.stop_times.len();
+    let frozen = snapshot.freeze();
+    assert_eq!(frozen.stop_times.len(), stop_times);
+    assert!(frozen.stops.contains_key(&StopId::from("AMV")));
+    let trip_id = TripId::from("AB1");
+    let stops: Vec<_> = frozen
+        .stop_times_for_trip(&trip_id)
+        .map(|stop_time| stop_time.stop_id.clone().unwrap())
+        .collect();
+    assert_eq!(
+        stops,
+        [StopId::from("BEATTY_AIRPORT"), StopId::from("BULLFROG")]
+    );
+    assert_eq!(
+        frozen.stop_times_for_trip(&TripId::from("UNKNOWN")).count(),
+        0
+    );
+
+    // A frozen dataset can be shared between threads.
+    let frozen = std::sync::Arc::new(frozen);
+    let shared = std::sync::Arc::clone(&frozen);
+    let routes = std::thread::spawn(move || shared.routes.len())
+        .join()
+        .unwrap();
+    assert_eq!(routes, frozen.routes.len());
+}
+
+#[test]
+fn test_feed_manager() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc};
+
+    let loads = Arc::new(AtomicUsize::new(0));
+    let source = {
+        let loads = Arc::clone(&loads);
+        move || {
+            let dataset = load_dataset("good_feed");
+            match loads.fetch_add(1, Ordering::SeqCst) {
+                // The second load lost a trip.
+                1 => {
+                    dataset.trips.remove(&TripId::from("AAMV4"));
+                    dataset
+                        .stop_times
+                        .retain(|(trip_id, _), _| trip_id.as_str() != "AAMV4");
+                }
+                // The third one references a missing stop, and fails validation.
+                2 => {
+                    dataset.stops.remove(&StopId::from("AMV"));
+                }
+                _ => {}
+            }
+            Ok(dataset)
+        }
+    };
+    let (reloaded, reloads) = mpsc::channel();
+    let (failed, failures) = mpsc::channel();
+    let manager = FeedManager::builder(source)
+        .on_reload(move |dataset| reloaded.send(dataset.trips.len()).unwrap())
+        .on_error(move |_| failed.send(()).unwrap())
+        .start()
+        .unwrap();
+    let trips = manager.current().trips.len();
+
+    let before = manager.current();
+    manager.reload().unwrap();
+    assert_eq!(reloads.try_recv(), Ok(trips - 1));
+    assert_eq!(manager.current().trips.len(), trips - 1);
+    // Datasets fetched before a reload are left as they were.
+    assert_eq!(before.trips.len(), trips);
+
+    assert!(manager.reload().is_err());
+    assert_eq!(failures.try_recv(), Ok(()));
+    assert_eq!(manager.current().trips.len(), trips - 1);
+    drop(manager);
+
+    // Reloads also happen in the background.
+    let (reloaded, reloads) = mpsc::channel();
+    let manager = FeedManager::builder(|| Ok(load_dataset("good_feed")))
+        .interval(Duration::from_millis(10))
+        .on_reload(move |dataset| {
+            let _ = reloaded.send(dataset.trips.len());
+        })
+        .start()
+        .unwrap();
+    assert_eq!(reloads.recv_timeout(Duration::from_secs(10)), Ok(trips));
+    drop(manager);
+}
+
+#[test]
+fn test_feed_collection() {
+    let mut other = load_dataset("good_feed");
+    let airport = StopId::from("BEATTY_AIRPORT");
+    other
+        .rename_stop_id(&airport, &StopId::from("B_AIRPORT"))
+        .unwrap();
+    let stops = other.stops.len();
+    let mut feeds = FeedCollection::new();
+    feeds.insert("dta", load_dataset("good_feed"));
+    feeds.insert("other", other);
+    assert_eq!(feeds.len(), 2);
+
+    // Both feeds describe the same stops, under different ids for the airport.
+    let matches = feeds.match_stops(50.0, 0.8);
+    assert_eq!(matches.len(), stops);
+    let airport_match = matches
+        .iter()
+        .find(|stop_match| stop_match.stops[0].stop_id == airport)
+        .unwrap();
+    let ids: Vec<_> = airport_match
+        .stops
+        .iter()
+        .map(|stop| (stop.feed_id.as_str(), stop.stop_id.as_str()))
+        .collect();
+    assert_eq!(ids, [("dta", "BEATTY_AIRPORT"), ("other", "B_AIRPORT")]);
+    // Stops of a single feed are never matched together.
+    let mut single = FeedCollection::new();
+    single.insert("dta", load_dataset("good_feed"));
+    assert!(single.match_stops(50_000.0, 0.0).is_empty());
+
+    let date = NaiveDate::from_ymd_opt(2024, 6, 5).unwrap();
+    let departures = feeds.departures_at(
+        &airport_match.stops,
+        date.and_hms_opt(6, 0, 0).unwrap(),
+        date.and_hms_opt(13, 0, 0).unwrap(),
+    );
+    // The shuttle and AB2 end their trips at the airport.
+    let departures: Vec<_> = departures
+        .iter()
+        .map(|departure| {
+            (
+                departure.feed_id.as_str(),
+                departure.trip_id.as_str(),
+                departure.departure.time().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        departures,
+        [
+            ("dta", "AB1", "08:00:00".to_string()),
+            ("other", "AB1", "08:00:00".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_route_name_warnings() {
+    let route_warnings = |dataset: &Dataset| -> Vec<WarningKind> {
+        dataset
+            .warnings()
+            .into_iter()
+            .map(|warning| warning.kind)
+            .filter(|kind| kind.to_string().starts_with("Route "))
+            .collect()
+    };
+
+    let dataset = load_dataset("repeated_route_name");
+    assert_eq!(
+        route_warnings(&dataset),
+        [WarningKind::DuplicateRouteName {
+            route_id: "STBA".to_string(),
+            other_route_id: "STBB".to_string(),
+        }]
+    );
+    let report = dataset.notice_report();
+    let group = report.group("duplicate_route_name").unwrap();
+    assert_eq!(group.sample_notices[0]["routeId2"], "STBB");
+
+    let dataset = load_dataset("same_short_long_name");
+    assert_eq!(
+        route_warnings(&dataset),
+        [WarningKind::SameRouteShortAndLongName {
+            route_id: "CITY".to_string(),
+        }]
+    );
+
+    let dataset = load_dataset("good_feed");
+    {
+        let mut route = dataset.routes.get_mut(&RouteId::from("AB")).unwrap();
+        route.route_short_name = Some("AB".to_string());
+        route.route_long_name = Some("ab - Airport ⇒ Bullfrog".to_string());
+    }
+    {
+        let mut route = dataset.routes.get_mut(&RouteId::from("CITY")).unwrap();
+        route.route_short_name = Some("C".to_string());
+    }
+    assert_eq!(
+        route_warnings(&dataset),
+        [WarningKind::RouteLongNameContainsShortName {
+            route_id: "AB".to_string(),
+        }]
+    );
+}
+
+#[cfg(feature = "rdf")]
+#[test]
+fn test_rdf_export() {
+    use gtfs_schedule::{RdfExporter, RdfFormat};
+
+    let dataset = load_dataset("good_feed");
+    let export = |format: RdfFormat| {
+        let mut document = vec![];
+        RdfExporter::new("https://example.com/gtfs/")
+            .format(format)
+            .export(&dataset, &mut document)
+            .unwrap();
+        String::from_utf8(document).unwrap()
+    };
+
+    let turtle = export(RdfFormat::Turtle);
+    assert!(turtle.starts_with("@prefix gtfs: <http://vocab.gtfs.org/terms#> .\n"));
+    assert!(turtle.contains(
+        "<https://example.com/gtfs/route/AB> a gtfs:Route ;\n    \
+         gtfs:agency <https://example.com/gtfs/agency/DTA> ;\n    \
+         gtfs:longName \"Airport ⇒ Bullfrog\" ;\n    \
+         gtfs:routeType gtfs:Bus ."
+    ));
+    assert!(turtle.contains(
+        "<https://example.com/gtfs/stop/BEATTY_AIRPORT> a gtfs:Stop ;\n    \
+         gtfs:code \"1235\""
+    ));
+    assert!(turtle
+        .contains("gtfs:parentStation <https://example.com/gtfs/stop/BEATTY_AIRPORT_STATION>"));
+    assert!(turtle.contains(
+        "<https://example.com/gtfs/stop_time/STBA/0> a gtfs:StopTime ;\n    \
+         gtfs:trip <https://example.com/gtfs/trip/STBA> ;"
+    ));
+    assert!(turtle.contains("gtfs:departureTime \"06:00:00\""));
+    assert!(turtle.contains("gtfs:pickupType gtfs:NotAvailable"));
+    assert!(turtle
+        .contains("gtfs:serviceRule <https://example.com/gtfs/calendar_date_rule/FULLW/20070604>"));
+    assert!(turtle.contains("gtfs:dateAddition \"false\"^^xsd:boolean"));
+    assert!(turtle.contains("gtfs:headwaySeconds \"1800\"^^xsd:nonNegativeInteger"));
+
+    // The same triples, one per line and without prefixes.
+    let n_triples = export(RdfFormat::NTriples);
+    assert!(n_triples.contains(
+        "<https://example.com/gtfs/trip/AB1> \
+         <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> \
+         <http://vocab.gtfs.org/terms#Trip> .\n"
+    ));
+    assert!(n_triples.contains(
+        "<https://example.com/gtfs/stop/BEATTY_AIRPORT> \
+         <http://www.w3.org/2003/01/geo/wgs84_pos#lat> \
+         \"36.868446\"^^<http://www.w3.org/2001/XMLSchema#double> .\n"
+    ));
+    assert_eq!(
+        n_triples.lines().count(),
+        turtle.matches(" ;\n").count() + turtle.matches(" .\n").count() - 7
+    );
+    assert!(n_triples.lines().all(|line| line.ends_with(" .")));
+    assert_eq!(export(RdfFormat::NTriples), n_triples);
+}
+
+#[test]
+fn test_timetable() {
+    let dataset = load_dataset("good_feed");
+    let saturday = NaiveDate::from_ymd_opt(2024, 1, 6).unwrap();
+    let time = |time: &str| Some(NaiveServiceTime::try_from(time).unwrap());
+
+    // A stop served by a single trip gets its own row, before the stops of the other pattern.
+    let mut stop_time = dataset
+        .stop_times
+        .get(&(TripId::from("AAMV3"), 1))
+        .unwrap()
+        .clone();
+    stop_time.stop_sequence = 0;
+    stop_time.stop_id = Some(StopId::from("STAGECOACH"));
+    stop_time.arrival_time = time("12:40:00");
+    stop_time.departure_time = time("12:40:00");
+    stop_time.timepoint = Some(Timepoint::Approximate);
+    dataset
+        .stop_times
+        .insert((TripId::from("AAMV3"), 0), stop_time);
+
+    let route_id = RouteId::from("AAMV");
+    let direction = Some(gtfs_schedule::schemas::DirectionId::OneDirection);
+    let timetable = dataset.timetable(&route_id, direction.clone(), saturday);
+    let stop_ids: Vec<_> = timetable.stops.iter().map(|s| s.stop_id.as_str()).collect();
+    assert_eq!(stop_ids, ["STAGECOACH", "BEATTY_AIRPORT", "AMV"]);
+    let trip_ids: Vec<_> = timetable.trips.iter().map(|t| t.trip_id.as_str()).collect();
+    assert_eq!(trip_ids, ["AAMV1", "AAMV3"]);
+    assert_eq!(
+        timetable.trips[0].times,
+        [None, time("8:00:00"), time("9:00:00")]
+    );
+    assert_eq!(
+        timetable.trips[1].times,
+        [time("12:40:00"), time("13:00:00"), time("14:00:00")]
+    );
+
+    let mut csv = vec![];
+    timetable.write_csv(&mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "stop_id,stop_name,AAMV1,AAMV3\n\
+         STAGECOACH,Stagecoach Hotel & Casino (Demo),,12:40:00\n\
+         BEATTY_AIRPORT,Nye County Airport (Demo),08:00:00,13:00:00\n\
+         AMV,Amargosa Valley (Demo),09:00:00,14:00:00\n"
+    );
+    let mut html = vec![];
+    timetable.write_html(&mut html).unwrap();
+    let html = String::from_utf8(html).unwrap();
+    assert!(html.contains(
+        "<tr><th scope=\"row\">Stagecoach Hotel &amp; Casino (Demo)</th><td></td><td>12:40</td></tr>"
+    ));
+
+    let timepoints = dataset.timetable_with_options(
+        &route_id,
+        direction,
+        saturday,
+        &TimetableOptions::default().timepoints_only(),
+    );
+    assert_eq!(timepoints.stops.len(), 2);
+    assert_eq!(
+        timepoints.trips[1].times,
+        [time("13:00:00"), time("14:00:00")]
+    );
+
+    // Trips defined in frequencies.txt have a column per departure.
+    let shuttle = dataset.timetable(&RouteId::from("STBA"), None, saturday);
+    assert_eq!(shuttle.trips.len(), 32);
+    assert_eq!(shuttle.trips[1].times, [time("6:30:00"), time("6:50:00")]);
+    assert_eq!(shuttle.trips[31].times[0], time("21:30:00"));
+    assert!(dataset
+        .timetable(
+            &route_id,
+            None,
+            NaiveDate::from_ymd_opt(2024, 1, 8).unwrap()
+        )
+        .trips
+        .is_empty());
+}
+
+#[test]
+fn test_dedupe_trips() {
+    use gtfs_schedule::schemas::CalendarDayService;
+
+    let mut dataset = load_dataset("good_feed");
+    assert!(dataset.dedupe_trips().is_empty());
+
+    // A copy of a weekend trip running on weekdays, as written by a per-day exporter.
+    let mut weekdays = dataset
+        .calendar
+        .get(&CalendarServiceId::from("WE"))
+        .unwrap()
+        .clone();
+    weekdays.service_id = CalendarServiceId::from("WD");
+    for (day, available) in [
+        (&mut weekdays.monday, true),
+        (&mut weekdays.tuesday, true),
+        (&mut weekdays.wednesday, true),
+        (&mut weekdays.thursday, true),
+        (&mut weekdays.friday, true),
+        (&mut weekdays.saturday, false),
+        (&mut weekdays.sunday, false),
+    ] {
+        *day = CalendarDayService::from(available);
+    }
+    dataset
+        .calendar
+        .insert(CalendarServiceId::from("WD"), weekdays);
+    let copy_id = TripId::from("AAMV1_WD");
+    let mut trip = dataset.trips.get(&TripId::from("AAMV1")).unwrap().clone();
+    trip.trip_id = copy_id.clone();
+    trip.service_id = CalendarServiceId::from("WD");
+    dataset.trips.insert(copy_id.clone(), trip);
+    let stop_times: Vec<_> = dataset
+        .stop_times
+        .iter()
+        .filter(|stop_time| stop_time.trip_id.as_str() == "AAMV1")
+        .map(|stop_time| stop_time.value().clone())
+        .collect();
+    for mut stop_time in stop_times {
+        stop_time.trip_id = copy_id.clone();
+        dataset
+            .stop_times
+            .insert((copy_id.clone(), stop_time.stop_sequence), stop_time);
+    }
+
+    let merges = dataset.dedupe_trips();
+    assert_eq!(
+        merges,
+        [TripMerge {
+            trip_id: TripId::from("AAMV1"),
+            merged_trip_ids: vec![copy_id.clone()],
+            service_id: CalendarServiceId::from("WD+WE"),
+        }]
+    );
+    assert!(!dataset.trips.contains_key(&copy_id));
+    assert!(dataset
+        .stop_times
+        .iter()
+        .all(|stop_time| stop_time.trip_id != copy_id));
+    let trip = dataset.trips.get(&TripId::from("AAMV1")).unwrap().clone();
+    assert_eq!(trip.service_id, CalendarServiceId::from("WD+WE"));
+    let service_id = CalendarServiceId::from("WD+WE");
+    let monday = NaiveDate::from_ymd_opt(2024, 1, 8).unwrap();
+    let saturday = NaiveDate::from_ymd_opt(2024, 1, 6).unwrap();
+    assert!(dataset.is_service_active(&service_id, monday));
+    assert!(dataset.is_service_active(&service_id, saturday));
+    // The other weekend trips keep their service.
+    let trip = dataset.trips.get(&TripId::from("AAMV3")).unwrap().clone();
+    assert_eq!(trip.service_id, CalendarServiceId::from("WE"));
+    dataset.validate().unwrap();
+    assert!(dataset.dedupe_trips().is_empty());
+}
+
+#[test]
+fn test_block_conflicts() {
+    let dataset = load_dataset("good_feed");
+    assert!(dataset.block_conflicts().is_empty());
+
+    // BFC1 now leaves Bullfrog before AB1, run by the same vehicle, gets there.
+    let time = |time: &str| Some(NaiveServiceTime::try_from(time).unwrap());
+    {
+        let mut stop_time = dataset
+            .stop_times
+            .get_mut(&(TripId::from("BFC1"), 1))
+            .unwrap();
+        stop_time.arrival_time = time("8:10:00");
+        stop_time.departure_time = time("8:10:00");
+    }
+    let conflicts = dataset.block_conflicts();
+    assert_eq!(
+        conflicts,
+        [BlockConflict {
+            block_id: "1".into(),
+            trip_id: TripId::from("AB1"),
+            other_trip_id: TripId::from("BFC1"),
+            date: NaiveDate::from_ymd_opt(2007, 1, 1).unwrap(),
+            overlap_start: NaiveServiceTime::try_from("8:10:00").unwrap(),
+            overlap_end: NaiveServiceTime::try_from("8:15:00").unwrap(),
+        }]
+    );
+
+    let warning = dataset
+        .warnings()
+        .into_iter()
+        .find(|warning| matches!(warning.kind, WarningKind::OverlappingBlockTrips { .. }))
+        .unwrap();
+    assert_eq!(
+        warning.kind.to_string(),
+        "Trips AB1 and BFC1 of block 1 run at the same time on 2007-01-01"
+    );
+    assert_eq!(warning.schema_instances.len(), 2);
+    let report = dataset.notice_report();
+    let group = report
+        .group("block_trips_with_overlapping_stop_times")
+        .unwrap();
+    assert_eq!(group.sample_notices[0]["tripId2"], "BFC1");
+    assert_eq!(group.sample_notices[0]["intersection"], "20070101");
+}
+
+#[test]
+fn test_vehicle_rotations() {
+    let dataset = load_dataset("good_feed");
+    let monday = NaiveDate::from_ymd_opt(2024, 1, 8).unwrap();
+    let time = |time: &str| NaiveServiceTime::try_from(time).unwrap();
+
+    // AB2 now starts away from where BFC2 ends, so the vehicle has to drive there.
+    dataset
+        .stop_times
+        .get_mut(&(TripId::from("AB2"), 1))
+        .unwrap()
+        .stop_id = Some(StopId::from("STAGECOACH"));
+
+    let rotations = dataset.vehicle_rotations(monday);
+    assert_eq!(rotations.len(), 2);
+    let rotation = &rotations[0];
+    assert_eq!(rotation.block_id.as_str(), "1");
+    let trip_ids: Vec<_> = rotation.trips.iter().map(|t| t.trip_id.as_str()).collect();
+    assert_eq!(trip_ids, ["AB1", "BFC1"]);
+    assert_eq!(
+        (rotation.pull_out, rotation.pull_in),
+        (time("8:00:00"), time("9:20:00"))
+    );
+    assert_eq!(rotation.gaps.len(), 1);
+    assert!(!rotation.gaps[0].is_deadhead());
+    assert_eq!(rotation.gaps[0].duration(), Duration::from_secs(5 * 60));
+    assert_eq!(rotation.revenue_duration(), Duration::from_secs(75 * 60));
+    assert_eq!(rotation.deadhead_duration(), Duration::ZERO);
+
+    let rotation = &rotations[1];
+    let trip_ids: Vec<_> = rotation.trips.iter().map(|t| t.trip_id.as_str()).collect();
+    assert_eq!(trip_ids, ["BFC2", "AB2"]);
+    let gap = &rotation.gaps[0];
+    assert_eq!(gap.from_stop_id, Some(StopId::from("BULLFROG")));
+    assert_eq!(gap.to_stop_id, Some(StopId::from("STAGECOACH")));
+    assert_eq!(rotation.deadhead_duration(), Duration::from_secs(5 * 60));
+    assert_eq!(rotation.duration(), Duration::from_secs(75 * 60));
+
+    // Blocks are only run on the days of their trips.
+    assert!(dataset
+        .vehicle_rotations(NaiveDate::from_ymd_opt(2030, 1, 7).unwrap())
+        .is_empty());
+}
+
+#[test]
+fn test_publisher() {
+    let mut dataset = load_dataset("good_feed");
+    assert!(dataset.publisher().is_none());
+
+    let header = csv::StringRecord::from(vec![
+        "feed_publisher_name",
+        "feed_publisher_url",
+        "feed_lang",
+    ]);
+    let record = csv::StringRecord::from(vec!["Transit Data Co", "http://google.com/", "en"]);
+    dataset.feed_info = Some(parse_record(&header, &record).unwrap());
+    let publisher = dataset.publisher().unwrap();
+    assert_eq!(publisher.name, "Transit Data Co");
+    assert!(!publisher.has_contact());
+    // The publisher shares its URL with the agency.
+    assert_eq!(
+        publisher.agency.unwrap().agency_id,
+        Some(AgencyId::from("DTA"))
+    );
+
+    let kinds: Vec<_> = dataset
+        .warnings()
+        .into_iter()
+        .map(|warning| warning.kind)
+        .filter(|kind| {
+            matches!(
+                kind,
+                WarningKind::MissingFeedContact | WarningKind::PublisherUrlSameAsAgencyUrl { .. }
+            )
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            WarningKind::MissingFeedContact,
+            WarningKind::PublisherUrlSameAsAgencyUrl {
+                url: "http://google.com/".to_string(),
+                agency_name: "Autorité de passage de démonstration".to_string(),
+            },
+        ]
+    );
+    let report = dataset.notice_report();
+    assert!(report.group("missing_feed_contact_email_and_url").is_some());
+
+    let header = csv::StringRecord::from(vec![
+        "feed_publisher_name",
+        "feed_publisher_url",
+        "feed_lang",
+        "feed_contact_email",
+    ]);
+    let record = csv::StringRecord::from(vec![
+        "Transit Data Co",
+        "https://data.example.com",
+        "en",
+        "gtfs@example.com",
+    ]);
+    dataset.feed_info = Some(parse_record(&header, &record).unwrap());
+    let publisher = dataset.publisher().unwrap();
+    assert!(publisher.has_contact());
+    assert!(publisher.agency.is_none());
+    assert!(dataset.warnings().iter().all(|warning| !matches!(
+        warning.kind,
+        WarningKind::MissingFeedContact | WarningKind::PublisherUrlSameAsAgencyUrl { .. }
+    )));
+}
+
+#[test]
+fn test_transfer_field_validation() {
+    let dataset = load_dataset("good_feed");
+    let transfer = dataset.transfers[0].clone();
+    assert!(transfer.validate().is_ok());
+
+    let mut in_seat = transfer.clone();
+    in_seat.transfer_type = TransferType::InSeatTransfer;
+    let error = in_seat.validate().unwrap_err();
+    assert_eq!(error.notice_code(), "missing_required_field");
+    assert!(error.to_string().contains("from_trip_id"));
+    in_seat.from_trip_id = Some(TripId::from("AB1"));
+    in_seat.to_trip_id = Some(TripId::from("BFC1"));
+    assert!(in_seat.validate().is_ok());
+
+    let mut not_in_seat = in_seat.clone();
+    not_in_seat.transfer_type = TransferType::NoInSeatTransfer;
+    let error = not_in_seat.validate().unwrap_err();
+    assert_eq!(error.notice_code(), "forbidden_field_value");
+    assert!(error.to_string().contains("from_stop_id"));
+    not_in_seat.from_stop_id = None;
+    not_in_seat.to_stop_id = None;
+    assert!(not_in_seat.validate().is_ok());
+}
+
+#[test]
+fn test_gtfs_table() {
+    assert_eq!(StopTime::FILE, GtfsFile::StopTimes);
+    assert_eq!(StopTime::FILE_NAME, "stop_times.txt");
+    assert_eq!(StopTime::PRIMARY_KEY, ["trip_id", "stop_sequence"]);
+    assert_eq!(Stop::HEADERS, GtfsFile::Stops.columns());
+    assert!(Stop::HEADERS.contains(&"stop_lat"));
+    assert!(Transfer::PRIMARY_KEY.is_empty());
+
+    let dataset = load_dataset("good_feed");
+    for stop_time in dataset.stop_times.iter() {
+        assert_eq!(&stop_time.value().key(), stop_time.key());
+    }
+}
+
+#[test]
+fn test_dataset_table() {
+    fn count<T: DatasetTable>(dataset: &Dataset) -> usize
+    where
+        T::Key: Eq + std::hash::Hash,
+    {
+        dataset.table::<T>().iter().count()
+    }
+
+    let dataset = load_dataset("good_feed");
+    let stops = dataset.table::<Stop>();
+    assert_eq!(stops.len(), dataset.stops.len());
+    assert_eq!(count::<Stop>(&dataset), dataset.stops.len());
+    let stop = stops.get(&StopId::from("STAGECOACH")).unwrap();
+    assert_eq!(stop.stop_id, StopId::from("STAGECOACH"));
+    assert!(stops.get(&StopId::from("UNKNOWN")).is_none());
+    drop(stop);
+
+    assert_eq!(count::<Agency>(&dataset), 1);
+    assert_eq!(
+        dataset.table::<Agency>().get(&()).unwrap().agency_name,
+        dataset.agencies[0].agency_name
+    );
+    assert!(dataset.table::<FeedInfo>().is_empty());
+    assert!(dataset.table::<FeedInfo>().get(&()).is_none());
+}
+
+#[test]
+fn test_deprecated_columns() {
+    let dataset = load_dataset("good_feed");
+    let dir = temp_dir("deprecated_columns");
+    dataset.to_csv(&dir).unwrap();
+    // Misspelled names without a documented source are not read as the columns they resemble.
+    std::fs::write(
+        dir.join("transfers.txt"),
+        "from_stop_id,to_stop_id,transfer_type,min_transfert_time\nEMSI,NANAA,2,1200\n",
+    )
+    .unwrap();
+
+    let legacy = Dataset::from_csv(&dir).unwrap();
+    assert_eq!(legacy.transfers[0].min_transfer_time, None);
+    assert!(legacy.parse_report().deprecated_columns.is_empty());
+    assert_eq!(
+        legacy
+            .parse_report()
+            .unknown_columns_of(GtfsFile::Transfers),
+        ["min_transfert_time"]
+    );
+    assert!(legacy
+        .notice_report()
+        .notices
+        .iter()
+        .all(|notice| notice.code != "deprecated_column"));
+    assert!(GtfsFile::ALL
+        .iter()
+        .all(|file| file.column_aliases().is_empty()));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_frequency_departures() {
+    let dataset = load_dataset("good_feed");
+    let time = |time: &str| NaiveServiceTime::try_from(time).unwrap();
+    // 08:00:00 to 09:59:59, every 10 minutes.
+    let mut frequency = dataset
+        .frequencies
+        .get(&(TripId::from("CITY1"), time("8:00:00")))
+        .unwrap()
+        .clone();
+    assert_eq!(frequency.exact_times, None);
+    assert_eq!(frequency.service_type(), ExactTimes::Approximate);
+
+    let departures: Vec<NaiveServiceTime> = frequency.departures().collect();
+    assert_eq!(departures.len(), 12);
+    assert_eq!(departures[0], time("8:00:00"));
+    assert_eq!(departures[11], time("9:50:00"));
+
+    frequency.exact_times = Some(ExactTimes::Exact);
+    frequency.end_time = time("10:00:00");
+    assert_eq!(frequency.service_type(), ExactTimes::Exact);
+    assert_eq!(frequency.departures().count(), 12);
+}
+
+#[test]
+fn test_stop_hierarchy() {
+    let dataset = load_dataset("good_feed");
+    let station = StopId::from("BEATTY_AIRPORT_STATION");
+    let platform = StopId::from("BEATTY_AIRPORT");
+    let boarding_area = StopId::from("NADAV");
+    dataset
+        .stops
+        .get_mut(&boarding_area)
+        .unwrap()
+        .parent_station = Some(platform.clone());
+
+    let parent = dataset.stop_get_parent_station(&platform).unwrap();
+    assert_eq!(parent.stop_id, station);
+    assert!(dataset.stop_get_parent_station(&station).is_none());
+    let children = dataset.stop_get_children(&station);
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].stop_id, platform);
+
+    assert_eq!(dataset.children_of(&platform), vec![boarding_area.clone()]);
+    assert_eq!(
+        dataset.descendants_of(&station),
+        vec![platform.clone(), boarding_area.clone()]
+    );
+    assert_eq!(
+        dataset.root_station_of(&boarding_area),
+        Some(station.clone())
+    );
+    assert_eq!(dataset.root_station_of(&station), Some(station.clone()));
+    assert_eq!(dataset.root_station_of(&StopId::from("EMSI")), None);
+
+    // A loop in the hierarchy does not prevent the traversal from ending.
+    dataset.stops.get_mut(&station).unwrap().parent_station = Some(boarding_area.clone());
+    let hierarchy = dataset.stop_hierarchy();
+    assert_eq!(hierarchy.descendants_of(&station).len(), 2);
+    assert_eq!(hierarchy.root_station_of(&boarding_area), Some(&station));
+}
+
+#[test]
+fn test_parse_options_only_times() {
+    let path = Path::new("tests/_data/good_feed");
+    let time = |time: &str| NaiveServiceTime::try_from(time).unwrap();
+    let options = ParseOptions::default().only_times(time("11:00:00")..=time("12:00:00"));
+    let dataset = Dataset::from_csv_with_options(path, &options).unwrap();
+
+    // Trips are kept, even when none of their stop times are.
+    assert_eq!(dataset.trips.len(), 11);
+    // STBA all day, and CITY1 and CITY2 from 10:00:00 to 15:59:59.
+    assert_eq!(dataset.frequencies.len(), 3);
+    // The stop times of the trips defined by frequencies are kept in full.
+    assert_eq!(
+        dataset
+            .iter_stop_times_for_trip(&TripId::from("CITY1"))
+            .count(),
+        5
+    );
+    assert_eq!(
+        dataset
+            .iter_stop_times_for_trip(&TripId::from("BFC2"))
+            .count(),
+        2
+    );
+    // AAMV2 runs from 10:00:00 to 11:00:00, across the start of the window: it is kept whole
+    // rather than cut down to its arrival at BEATTY_AIRPORT.
+    assert_eq!(
+        dataset
+            .iter_stop_times_for_trip(&TripId::from("AAMV2"))
+            .count(),
+        2
+    );
+    // AB2 only departs at 12:05:00.
+    assert_eq!(
+        dataset
+            .iter_stop_times_for_trip(&TripId::from("AB2"))
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn test_effective_continuous_stopping() {
+    let dataset = load_dataset("good_feed");
+    let trip_id = TripId::from("CITY1");
+    assert_eq!(
+        dataset.effective_continuous_pickup(&trip_id, 0),
+        Some(ContinuousPickup::NoContinuousStopping)
+    );
+    assert_eq!(dataset.effective_continuous_pickup(&trip_id, 1), None);
+
+    dataset
+        .routes
+        .get_mut(&RouteId::from("CITY"))
+        .unwrap()
+        .continuous_pickup = Some(ContinuousPickup::ContinuousStopping);
+    dataset
+        .stop_times
+        .get_mut(&(trip_id.clone(), 10))
+        .unwrap()
+        .continuous_pickup = Some(ContinuousPickup::PhoneAgencyToArrange);
+
+    // An empty value inherits the value of the route, a stop time value overrides it.
+    assert_eq!(
+        dataset.effective_continuous_pickup(&trip_id, 5),
+        Some(ContinuousPickup::ContinuousStopping)
+    );
+    assert_eq!(
+        dataset.effective_continuous_pickup(&trip_id, 10),
+        Some(ContinuousPickup::PhoneAgencyToArrange)
+    );
+    assert_eq!(
+        dataset.effective_continuous_drop_off(&trip_id, 10),
+        Some(ContinuousDropOff::NoContinuousStopping)
+    );
+}
+
+#[test]
+fn test_normalize_networks() {
+    let mut dataset = load_dataset("good_feed");
+    let route_id = RouteId::from("AB");
+    let network_id = NetworkId::from("REGIONAL");
+    dataset.routes.get_mut(&route_id).unwrap().network_id = Some(network_id.clone());
+
+    dataset.normalize_networks(NetworkForm::RoutesNetworks);
+    assert_eq!(dataset.routes.get(&route_id).unwrap().network_id, None);
+    assert_eq!(dataset.routes_networks.len(), 1);
+    assert!(dataset.networks.contains_key(&network_id));
+    assert_eq!(dataset.routes_in_network(&network_id), [route_id.clone()]);
+
+    dataset.normalize_networks(NetworkForm::RouteField);
+    assert_eq!(
+        dataset.routes.get(&route_id).unwrap().network_id,
+        Some(network_id.clone())
+    );
+    assert!(dataset.routes_networks.is_empty());
+    assert!(dataset.networks.is_empty());
+    assert_eq!(dataset.network_of_route(&RouteId::from("BFC")), None);
+}
+
+#[test]
+fn test_translation_coverage() {
+    let mut dataset =
+        Dataset::from_csv(Path::new("tests/_data/upgrade_translations/new-feed")).unwrap();
+
+    // feed_lang is `en`, so only the translations into `es` are expected.
+    let coverage = dataset.translation_coverage();
+    assert_eq!(coverage.len(), 1);
+    assert_eq!(coverage[0].language.as_str(), "es");
+    // The headsign of trip3 is translated by its value.
+    assert_eq!(coverage[0].translated, 4);
+    assert_eq!(coverage[0].missing.len(), 1);
+    assert_eq!(coverage[0].missing[0].field_name, "feed_publisher_name");
+
+    let language = oxilangtag::LanguageTag::parse("es".to_string()).unwrap();
+    let added = dataset.add_missing_translations(language, |value| format!("{value} (es)"));
+    assert_eq!(added, 1);
+    assert_eq!(
+        dataset.translations.last().unwrap().translation,
+        "Narnia (es)"
+    );
+    let coverage = dataset.translation_coverage();
+    assert!(coverage[0].missing.is_empty());
+    assert_eq!(coverage[0].ratio(), 1.0);
+}
+
+#[test]
+fn test_route_stops_and_trip_endpoints() {
+    let dataset = load_dataset("good_feed");
+    // CITY1 and CITY2 serve the same stops in opposite directions.
+    let stop_ids = dataset.stops_served_by_route(&RouteId::from("CITY"));
+    let stop_ids: Vec<&str> = stop_ids.iter().map(|stop_id| stop_id.as_str()).collect();
+    assert_eq!(stop_ids, ["EMSI", "DADAN", "NADAV", "NANAA", "STAGECOACH"]);
+    assert!(dataset
+        .stops_served_by_route(&RouteId::from("UNKNOWN"))
+        .is_empty());
+
+    let (first, last) = dataset.trip_endpoints(&TripId::from("CITY1")).unwrap();
+    assert_eq!(first.stop_id.as_str(), "STAGECOACH");
+    assert_eq!(last.stop_id.as_str(), "EMSI");
+    assert!(dataset.trip_endpoints(&TripId::from("UNKNOWN")).is_none());
+}
+
+#[test]
+fn test_uneven_exact_times_window_warning() {
+    let dataset = load_dataset("good_feed");
+    let time = |time: &str| NaiveServiceTime::try_from(time).unwrap();
+    // 08:00:00 to 09:59:59, every 10 minutes.
+    dataset
+        .frequencies
+        .get_mut(&(TripId::from("CITY1"), time("8:00:00")))
+        .unwrap()
+        .exact_times = Some(ExactTimes::Exact);
+
+    let warnings = dataset.warnings();
+    let warning = warnings
+        .iter()
+        .find(|warning| matches!(warning.kind, WarningKind::UnevenExactTimesWindow { .. }))
+        .unwrap();
+    assert_eq!(
+        warning.kind,
+        WarningKind::UnevenExactTimesWindow {
+            trip_id: "CITY1".to_string(),
+            start_time: "08:00:00".to_string(),
+            window_secs: 7199,
+            headway_secs: 600,
+        }
+    );
+}
+
+#[test]
+fn test_lenient_extended_route_type() {
+    let dir = temp_dir("extended_route_type");
+    load_dataset("good_feed").to_csv(&dir).unwrap();
+    let routes = std::fs::read_to_string(dir.join("routes.txt")).unwrap();
+    // 700 is the Bus Service of the extended route types.
+    let routes = routes.replacen(",3\n", ",700\n", 1);
+    std::fs::write(dir.join("routes.txt"), routes).unwrap();
+
+    assert!(Dataset::from_csv(&dir).is_err());
+    let options = ParseOptions::default().lenient_codes();
+    let dataset = Dataset::from_csv_with_options(&dir, &options).unwrap();
+    assert!(dataset
+        .routes
+        .iter()
+        .any(|route| route.route_type == RouteType::Unknown(700)));
+
+    dataset.to_csv(&dir).unwrap();
+    let written = std::fs::read_to_string(dir.join("routes.txt")).unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+    assert!(written.contains(",700"));
+}
+
+#[test]
+fn test_remove_referenced_route_and_rename_stop_to_itself() {
+    let mut dataset = load_dataset("good_feed");
+    let error = dataset
+        .remove_route(&RouteId::from("AB"), CascadePolicy::Reject)
+        .unwrap_err();
+    assert_eq!(error.notice_code(), "record_still_referenced");
+    assert!(error.to_string().contains("trips.txt"));
+
+    let stop_id = StopId::from("BEATTY_AIRPORT");
+    dataset.rename_stop_id(&stop_id, &stop_id).unwrap();
+    assert!(dataset.stops.contains_key(&stop_id));
+    assert!(dataset
+        .rename_stop_id(&StopId::from("NOWHERE"), &StopId::from("NOWHERE"))
+        .is_err());
+}
@@ -0,0 +1,159 @@
+use geo::{coord, Coord};
+use gtfs_schedule::schemas::{Shape, ShapeId, TripId};
+use gtfs_schedule::{Dataset, ShapeGeometry};
+use std::path::Path;
+
+fn shape_point(shape_id: &str, sequence: u32, coord: Coord) -> Shape {
+    Shape {
+        shape_id: ShapeId::from(shape_id),
+        shape_pt: coord.into(),
+        shape_pt_sequence: sequence,
+        shape_dist_traveled: None,
+        #[cfg(feature = "extensions")]
+        extensions: Default::default(),
+    }
+}
+
+#[test]
+fn test_shape_geometry() {
+    let geometry = ShapeGeometry::from_points(vec![
+        shape_point("S", 3, coord! { x: 0.0, y: 1.0 }),
+        shape_point("S", 1, coord! { x: 0.0, y: 0.0 }),
+        shape_point("S", 2, coord! { x: 0.0, y: 0.5 }),
+    ])
+    .unwrap();
+
+    // One degree of latitude is roughly 111.2km.
+    assert!((geometry.total_length_m() - 111_195.0).abs() < 1.0);
+
+    let along = geometry.project_stop(coord! { x: 0.001, y: 0.25 }).unwrap();
+    assert!((along - 111_195.0 / 4.0).abs() < 1.0);
+
+    let simplified = geometry.simplify(1e-6);
+    assert_eq!(simplified.shape_pt_sequences, vec![1, 3]);
+    assert!((simplified.total_length_m() - geometry.total_length_m()).abs() < 1e-6);
+}
+
+#[test]
+fn test_compute_shape_dist_traveled() {
+    let dataset = Dataset::from_csv(Path::new("tests/_data/good_feed")).unwrap();
+
+    // A shape going through every stop of the trip CITY1.
+    let points = [
+        coord! { x: -116.751677, y: 36.915682 },
+        coord! { x: -116.761472, y: 36.914944 },
+        coord! { x: -116.76821, y: 36.914893 },
+        coord! { x: -116.768242, y: 36.909489 },
+        coord! { x: -116.76218, y: 36.905697 },
+    ];
+    for (sequence, coord) in points.into_iter().enumerate() {
+        let point = shape_point("CITY_SHAPE", sequence as u32, coord);
+        dataset
+            .shapes
+            .insert((point.shape_id.clone(), point.shape_pt_sequence), point);
+    }
+    dataset
+        .trips
+        .get_mut(&TripId::from("CITY1"))
+        .unwrap()
+        .shape_id = Some(ShapeId::from("CITY_SHAPE"));
+
+    assert_eq!(dataset.compute_shape_dist_traveled(), 10);
+
+    let shape_end = dataset
+        .shapes
+        .get(&(ShapeId::from("CITY_SHAPE"), 4))
+        .unwrap()
+        .shape_dist_traveled
+        .unwrap();
+    let distances: Vec<f32> = [0, 5, 10, 15, 20]
+        .iter()
+        .map(|sequence| {
+            dataset
+                .stop_times
+                .get(&(TripId::from("CITY1"), *sequence))
+                .unwrap()
+                .shape_dist_traveled
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(distances[0], 0.0);
+    assert!(distances.windows(2).all(|w| w[0] < w[1]));
+    assert!((distances[4] - shape_end).abs() < 1e-3);
+}
+
+#[test]
+fn test_dedupe_shapes() {
+    let mut dataset = Dataset::from_csv(Path::new("tests/_data/good_feed")).unwrap();
+
+    // The same path with a one meter offset, and the path travelled the other way around.
+    let points = [
+        coord! { x: -116.751677, y: 36.915682 },
+        coord! { x: -116.761472, y: 36.914944 },
+        coord! { x: -116.76821, y: 36.914893 },
+    ];
+    let shapes = [
+        ("CITY_A", "CITY1", points.to_vec()),
+        (
+            "CITY_B",
+            "CITY2",
+            points
+                .iter()
+                .map(|point| coord! { x: point.x, y: point.y + 0.00001 })
+                .collect(),
+        ),
+        ("CITY_C", "AB1", points.iter().rev().copied().collect()),
+    ];
+    for (shape_id, trip_id, points) in shapes {
+        for (sequence, coord) in points.into_iter().enumerate() {
+            let point = shape_point(shape_id, sequence as u32, coord);
+            dataset
+                .shapes
+                .insert((point.shape_id.clone(), point.shape_pt_sequence), point);
+        }
+        dataset
+            .trips
+            .get_mut(&TripId::from(trip_id))
+            .unwrap()
+            .shape_id = Some(ShapeId::from(shape_id));
+    }
+
+    assert_eq!(dataset.dedupe_shapes(0.0), 0);
+    assert_eq!(dataset.dedupe_shapes(5.0), 1);
+    let shape_of = |trip_id: &str| {
+        dataset
+            .trips
+            .get(&TripId::from(trip_id))
+            .unwrap()
+            .shape_id
+            .clone()
+    };
+    assert_eq!(shape_of("CITY2"), Some(ShapeId::from("CITY_A")));
+    assert_eq!(shape_of("AB1"), Some(ShapeId::from("CITY_C")));
+    assert!(dataset.shape_geometry(&ShapeId::from("CITY_B")).is_none());
+    assert_eq!(dataset.shapes.len(), 6);
+}
+
+#[test]
+fn test_trip_geometry() {
+    let dataset = Dataset::from_csv(Path::new("tests/_data/good_feed")).unwrap();
+    let trip_id = TripId::from("AB1");
+
+    // Without shape, the path links the stops of the trip.
+    let geometry = dataset.trip_geometry(&trip_id).unwrap();
+    assert_eq!(
+        geometry.0,
+        [
+            coord! { x: -116.784582, y: 36.868446 },
+            coord! { x: -116.81797, y: 36.88108 },
+        ]
+    );
+    assert_eq!(
+        dataset.trip_geometry_wkt(&trip_id).unwrap(),
+        "LINESTRING (-116.784582 36.868446, -116.81797 36.88108)"
+    );
+    let gpx = dataset.trip_geometry_gpx(&trip_id).unwrap();
+    assert!(gpx.contains("<name>AB1</name>"));
+    assert!(gpx.contains(r#"<trkpt lat="36.88108" lon="-116.81797"/>"#));
+    assert!(dataset.trip_geometry(&TripId::from("UNKNOWN")).is_none());
+}
@@ -0,0 +1,161 @@
+use gtfs_schedule::schemas::{LevelId, PathwayId, StopId};
+use gtfs_schedule::{AccessibilityProfile, Dataset};
+use std::path::Path;
+
+#[test]
+fn test_shortest_path() {
+    let dataset = Dataset::from_csv(Path::new("tests/_data/au-sydney-entrances")).unwrap();
+    let graph = dataset.station_graph();
+    let platform = StopId::from("LR_TavHill_P1_M");
+
+    let path = graph
+        .shortest_path(
+            &platform,
+            &StopId::from("LR_TavHill_EntranceStairsN"),
+            AccessibilityProfile::default(),
+        )
+        .unwrap();
+    assert_eq!(
+        path.pathway_ids,
+        vec![PathwayId::from("LR_TavHill_StairsN")]
+    );
+    assert_eq!(path.traversal_time.as_secs(), 32);
+
+    // The stairs can not be avoided to reach this entrance, which is not accessible anyway.
+    assert!(graph
+        .shortest_path(
+            &platform,
+            &StopId::from("LR_TavHill_EntranceStairsN"),
+            AccessibilityProfile::wheelchair(),
+        )
+        .is_none());
+
+    let path = graph
+        .shortest_path(
+            &platform,
+            &StopId::from("LR_TavHill_EntranceLiftN"),
+            AccessibilityProfile::wheelchair(),
+        )
+        .unwrap();
+    assert_eq!(
+        path.pathway_ids,
+        vec![
+            PathwayId::from("LR_TavHill_Walk_P1_M_S"),
+            PathwayId::from("LR_TavHill_Walk_P1_Bridge"),
+            PathwayId::from("LR_TavHill_LiftN"),
+        ]
+    );
+    assert_eq!(path.stop_ids.first(), Some(&platform));
+    assert_eq!(path.stop_ids.len(), 4);
+
+    // Boarding areas are linked to their platform.
+    let path = graph
+        .shortest_path(
+            &StopId::from("LR_TavHill_P2"),
+            &StopId::from("LR_TavHill_P1"),
+            AccessibilityProfile::wheelchair(),
+        )
+        .unwrap();
+    assert_eq!(
+        path.pathway_ids,
+        vec![PathwayId::from("LR_TavHill_Walk_P1_P2")]
+    );
+}
+
+#[test]
+fn test_station() {
+    let dataset = Dataset::from_csv(Path::new("tests/_data/au-sydney-entrances")).unwrap();
+    // Any location of the station leads to it.
+    let station = dataset.station(&StopId::from("LR_TavHill_P1_M")).unwrap();
+    assert_eq!(station.stop().stop_id, StopId::from("LR_TavHill"));
+
+    let ids = |stops: &[gtfs_schedule::schemas::Stop]| {
+        stops
+            .iter()
+            .map(|stop| stop.stop_id.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(station.platforms()), ["LR_TavHill_P1", "LR_TavHill_P2"]);
+    assert_eq!(station.entrances().len(), 4);
+    assert_eq!(
+        ids(station.generic_nodes()),
+        ["LR_TavHill_BridgeN", "LR_TavHill_BridgeS"]
+    );
+    assert_eq!(station.boarding_areas().len(), 5);
+    assert_eq!(
+        station
+            .boarding_areas_of(&StopId::from("LR_TavHill_P2"))
+            .count(),
+        2
+    );
+    assert_eq!(station.pathways().len(), 10);
+
+    let levels: Vec<_> = station
+        .levels()
+        .iter()
+        .map(|level| level.level_index)
+        .collect();
+    assert_eq!(levels, [0.0, 1.0]);
+    assert_eq!(
+        station
+            .level_of(&StopId::from("LR_TavHill_EntranceLiftN"))
+            .unwrap()
+            .level_name
+            .as_deref(),
+        Some("G")
+    );
+    assert_eq!(station.locations_on_level(&LevelId::from("0")).count(), 4);
+
+    assert!(dataset.station(&StopId::from("UNKNOWN")).is_none());
+}
+
+#[test]
+fn test_infer_parent_stations() {
+    let mut dataset = Dataset::from_csv(Path::new("tests/_data/good_feed")).unwrap();
+    let mut platform = dataset
+        .stops
+        .get(&StopId::from("FUR_CREEK_RES"))
+        .unwrap()
+        .clone();
+    platform.stop_id = StopId::from("FUR_CREEK_RES_2");
+    platform.stop_name = Some("Furnace Creek Resort (Demo) 2".to_string());
+    if let Some(coord) = platform.stop_coord.as_mut() {
+        coord.y += 0.0002;
+    }
+    dataset.stops.insert(platform.stop_id.clone(), platform);
+
+    assert!(dataset.infer_parent_stations(100.0, 1.0).is_empty());
+    let station_id = StopId::from("FUR_CREEK_RES_station");
+    assert_eq!(
+        dataset.infer_parent_stations(100.0, 0.8),
+        [station_id.clone()]
+    );
+
+    let station = dataset.station(&StopId::from("FUR_CREEK_RES_2")).unwrap();
+    assert_eq!(station.stop().stop_id, station_id);
+    assert_eq!(
+        station.stop().stop_name.as_deref(),
+        Some("Furnace Creek Resort (Demo)")
+    );
+    assert_eq!(station.platforms().len(), 2);
+    assert!(dataset
+        .stops
+        .get(&StopId::from("BULLFROG"))
+        .unwrap()
+        .parent_station
+        .is_none());
+}
+
+#[test]
+fn test_parent_station_cycle() {
+    // HALL and CONCOURSE are each other's parent station, without accessibility information.
+    let dataset = Dataset::from_csv(Path::new("tests/_data/parent_station_cycle")).unwrap();
+    let graph = dataset.station_graph();
+    assert!(graph
+        .shortest_path(
+            &StopId::from("PLATFORM"),
+            &StopId::from("HALL"),
+            AccessibilityProfile::wheelchair(),
+        )
+        .is_none());
+}